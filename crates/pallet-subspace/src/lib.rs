@@ -270,6 +270,10 @@ mod pallet {
         #[pallet::constant]
         type MinSectorLifetime: Get<HistorySize>;
 
+        /// Erasure coding rate `(source, total)` of records during archiving process.
+        #[pallet::constant]
+        type ErasureCodingRate: Get<(u32, u32)>;
+
         /// Number of votes expected per block.
         ///
         /// This impacts solution range for votes in consensus.
@@ -1262,7 +1266,7 @@ impl<T: Config> Pallet<T> {
     }
 
     pub fn chain_constants() -> ChainConstants {
-        ChainConstants::V0 {
+        ChainConstants::V1 {
             confirmation_depth_k: T::ConfirmationDepthK::get()
                 .try_into()
                 .unwrap_or_else(|_| panic!("Block number always fits in BlockNumber; qed")),
@@ -1282,6 +1286,7 @@ impl<T: Config> Pallet<T> {
                 T::RecentHistoryFraction::get().1,
             ),
             min_sector_lifetime: T::MinSectorLifetime::get(),
+            erasure_coding_rate: T::ErasureCodingRate::get(),
         }
     }
 }
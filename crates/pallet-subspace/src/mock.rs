@@ -172,6 +172,7 @@ parameter_types! {
         HistorySize::new(NonZeroU64::new(10).unwrap()),
     );
     pub const MinSectorLifetime: HistorySize = HistorySize::new(NonZeroU64::new(4).unwrap());
+    pub const ErasureCodingRate: (u32, u32) = (1, 2);
     pub const RecordSize: u32 = 3840;
     pub const ExpectedVotesPerBlock: u32 = 9;
     pub const ReplicationFactor: u16 = 1;
@@ -194,6 +195,7 @@ impl Config for Test {
     type RecentSegments = RecentSegments;
     type RecentHistoryFraction = RecentHistoryFraction;
     type MinSectorLifetime = MinSectorLifetime;
+    type ErasureCodingRate = ErasureCodingRate;
     type ExpectedVotesPerBlock = ExpectedVotesPerBlock;
     type MaxPiecesInSector = ConstU16<{ MAX_PIECES_IN_SECTOR }>;
     type ShouldAdjustSolutionRange = ShouldAdjustSolutionRange;
@@ -401,7 +403,7 @@ pub fn create_segment_header(segment_index: SegmentIndex) -> SegmentHeader {
 }
 
 pub fn create_archived_segment(kzg: Kzg) -> NewArchivedSegment {
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     let mut block = vec![0u8; RecordedHistorySegment::SIZE];
     rand::thread_rng().fill(block.as_mut_slice());
@@ -437,6 +439,7 @@ pub fn create_signed_vote(
             HistorySize::from(NonZeroU64::new(10).unwrap()),
         ),
         min_sector_lifetime: HistorySize::from(NonZeroU64::new(4).unwrap()),
+        erasure_coding_rate: RecordedHistorySegment::ERASURE_CODING_RATE,
     };
     let pieces_in_sector = farmer_protocol_info.max_pieces_in_sector;
     let sector_size = sector_size(pieces_in_sector);
@@ -29,22 +29,26 @@ use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
 use sc_client_api::{AuxStore, BlockBackend};
 use sc_consensus_subspace::archiver::{recreate_genesis_segment, SegmentHeadersStore};
+use sc_consensus_subspace::farmer_equivocation::FarmerEquivocationTracker;
 use sc_consensus_subspace::notification::SubspaceNotificationStream;
 use sc_consensus_subspace::{
     ArchivedSegmentNotification, NewSlotNotification, RewardSigningNotification, SubspaceSyncOracle,
 };
+#[cfg(feature = "pot")]
+use sc_proof_of_time::source::PotSlotInfoNotificationStream;
 use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 use sc_utils::mpsc::TracingUnboundedSender;
 use sp_api::{ApiError, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_consensus::SyncOracle;
+use sp_consensus_slots::Slot;
 use sp_consensus_subspace::{FarmerPublicKey, FarmerSignature, SubspaceApi as SubspaceRuntimeApi};
 use sp_core::crypto::ByteArray;
 use sp_core::H256;
 use sp_objects::ObjectsApi;
 use sp_runtime::traits::Block as BlockT;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -52,18 +56,29 @@ use std::sync::{Arc, Weak};
 use std::time::Duration;
 use subspace_archiving::archiver::NewArchivedSegment;
 use subspace_core_primitives::crypto::kzg::Kzg;
-use subspace_core_primitives::{PieceIndex, SegmentHeader, SegmentIndex, SlotNumber, Solution};
+use subspace_core_primitives::objects::GlobalObject;
+use subspace_core_primitives::{
+    Blake2b256Hash, Piece, PieceIndex, SegmentHeader, SegmentIndex, SlotNumber, Solution,
+};
 use subspace_farmer_components::FarmerProtocolInfo;
 use subspace_networking::libp2p::Multiaddr;
+#[cfg(feature = "pot")]
+use subspace_rpc_primitives::PotParametersChangeInfo;
 use subspace_rpc_primitives::{
-    FarmerAppInfo, NodeSyncStatus, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
-    SolutionResponse, MAX_SEGMENT_HEADERS_PER_REQUEST,
+    FarmerAppInfo, NodeSyncStatus, ObjectHash, ObjectMapping, PotParametersInfo, PotSlotInfo,
+    RewardSignatureResponse, RewardSigningInfo, SegmentObjectMappings, SlotInfo, SolutionResponse,
+    MAX_OBJECT_MAPPINGS_SEGMENTS_PER_REQUEST, MAX_SEGMENT_HEADERS_PER_REQUEST,
 };
 use tracing::{debug, error, warn};
 
+#[cfg(all(test, feature = "pot"))]
+mod tests;
+
 const SOLUTION_TIMEOUT: Duration = Duration::from_secs(2);
 const REWARD_SIGNING_TIMEOUT: Duration = Duration::from_millis(500);
 const NODE_SYNC_STATUS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+#[cfg(feature = "pot")]
+const POT_SLOT_INFO_QUEUE_SIZE_WARNING: usize = 100;
 
 /// Provides rpc methods for interacting with Subspace.
 #[rpc(client, server)]
@@ -127,6 +142,77 @@ pub trait SubspaceRpcApi {
 
     #[method(name = "subspace_lastSegmentHeaders")]
     async fn last_segment_headers(&self, limit: u64) -> RpcResult<Vec<Option<SegmentHeader>>>;
+
+    /// Location of the object with specified hash in archived history, requires object mappings
+    /// index to be enabled on the node
+    #[method(name = "subspace_objectMapping")]
+    async fn object_mapping(&self, hash: ObjectHash) -> RpcResult<Option<GlobalObject>>;
+
+    /// Object mappings of segments in the inclusive range, only indexed segments are returned,
+    /// requires object mappings index to be enabled on the node
+    #[method(name = "subspace_segmentObjectMappings")]
+    async fn segment_object_mappings(
+        &self,
+        first_segment_index: SegmentIndex,
+        last_segment_index: SegmentIndex,
+    ) -> RpcResult<Vec<SegmentObjectMappings>>;
+
+    /// Object mappings of newly archived segments subscription
+    #[subscription(
+        name = "subspace_subscribeObjectMappings" => "subspace_object_mappings",
+        unsubscribe = "subspace_unsubscribeObjectMappings",
+        item = SegmentObjectMappings,
+    )]
+    fn subscribe_object_mappings(&self);
+
+    /// Proof of time slots produced or received by the node subscription, allows external tools
+    /// to verify proof of time chain independently
+    #[subscription(
+        name = "subspace_subscribePotSlotInfo" => "subspace_pot_slot_info",
+        unsubscribe = "subspace_unsubscribePotSlotInfo",
+        item = PotSlotInfo,
+    )]
+    fn subscribe_pot_slot_info(&self);
+
+    /// Current proof of time parameters along with scheduled entropy injection at the best block
+    #[method(name = "subspace_potParameters")]
+    fn pot_parameters(&self) -> RpcResult<PotParametersInfo>;
+}
+
+/// Provider of object mappings of archived history, typically backed by persistent index.
+pub trait ObjectMappingsProvider: Send + Sync {
+    /// Location of the object with specified hash in archived history.
+    fn object_mapping(
+        &self,
+        hash: &Blake2b256Hash,
+    ) -> Result<Option<GlobalObject>, sp_blockchain::Error>;
+
+    /// Object mappings of the segment, `None` if segment was not indexed (yet).
+    fn segment_object_mappings(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<Option<Vec<(Blake2b256Hash, GlobalObject)>>, sp_blockchain::Error>;
+}
+
+/// Provider of pieces of archived history, typically backed by persistent store.
+pub trait ArchivalPiecesProvider: Send + Sync {
+    /// Piece with specified index, `None` if the piece is not stored.
+    fn piece(&self, piece_index: PieceIndex) -> Result<Option<Piece>, sp_blockchain::Error>;
+}
+
+fn to_segment_object_mappings(
+    segment_index: SegmentIndex,
+    objects: impl Iterator<Item = (Blake2b256Hash, GlobalObject)>,
+) -> SegmentObjectMappings {
+    SegmentObjectMappings {
+        segment_index,
+        objects: objects
+            .map(|(hash, global_object)| ObjectMapping {
+                hash: ObjectHash(hash),
+                global_object,
+            })
+            .collect(),
+    }
 }
 
 #[derive(Default)]
@@ -184,10 +270,20 @@ where
     pub segment_headers_store: SegmentHeadersStore<AS>,
     /// Subspace sync oracle
     pub sync_oracle: SubspaceSyncOracle<SO>,
+    /// Tracker of equivocation by the farmers connected to this node
+    pub farmer_equivocation_tracker: FarmerEquivocationTracker,
     /// Signifies whether a potentially unsafe RPC should be denied
     pub deny_unsafe: DenyUnsafe,
     /// Kzg instance
     pub kzg: Kzg,
+    /// Object mappings provider, `None` if object mappings index is disabled
+    pub object_mappings_provider: Option<Arc<dyn ObjectMappingsProvider>>,
+    /// Provider of pieces of archived history that are no longer cached, `None` if archival
+    /// pieces store is disabled
+    pub archival_pieces_provider: Option<Arc<dyn ArchivalPiecesProvider>>,
+    /// Proof of time slot info notification stream
+    #[cfg(feature = "pot")]
+    pub pot_slot_info_notification_stream: PotSlotInfoNotificationStream,
 }
 
 /// Implements the [`SubspaceRpcApiServer`] trait for interacting with Subspace.
@@ -211,7 +307,12 @@ where
         Arc<Mutex<ArchivedSegmentHeaderAcknowledgementSenders>>,
     next_subscription_id: AtomicU64,
     sync_oracle: SubspaceSyncOracle<SO>,
+    farmer_equivocation_tracker: FarmerEquivocationTracker,
     kzg: Kzg,
+    object_mappings_provider: Option<Arc<dyn ObjectMappingsProvider>>,
+    archival_pieces_provider: Option<Arc<dyn ArchivalPiecesProvider>>,
+    #[cfg(feature = "pot")]
+    pot_slot_info_notification_stream: PotSlotInfoNotificationStream,
     deny_unsafe: DenyUnsafe,
     _block: PhantomData<Block>,
 }
@@ -259,7 +360,12 @@ where
             archived_segment_acknowledgement_senders: Arc::default(),
             next_subscription_id: AtomicU64::default(),
             sync_oracle: config.sync_oracle,
+            farmer_equivocation_tracker: config.farmer_equivocation_tracker,
             kzg: config.kzg,
+            object_mappings_provider: config.object_mappings_provider,
+            archival_pieces_provider: config.archival_pieces_provider,
+            #[cfg(feature = "pot")]
+            pot_slot_info_notification_stream: config.pot_slot_info_notification_stream,
             deny_unsafe: config.deny_unsafe,
             _block: PhantomData,
         })
@@ -286,7 +392,10 @@ where
             archived_segment_acknowledgement_senders: Arc::default(),
             next_subscription_id: AtomicU64::default(),
             sync_oracle: config.sync_oracle,
+            farmer_equivocation_tracker: config.farmer_equivocation_tracker,
             kzg: config.kzg,
+            object_mappings_provider: config.object_mappings_provider,
+            archival_pieces_provider: config.archival_pieces_provider,
             deny_unsafe: config.deny_unsafe,
             _block: PhantomData,
         })
@@ -330,6 +439,7 @@ where
                 recent_segments: chain_constants.recent_segments(),
                 recent_history_fraction: chain_constants.recent_history_fraction(),
                 min_sector_lifetime: chain_constants.min_sector_lifetime(),
+                erasure_coding_rate: chain_constants.erasure_coding_rate(),
             };
 
             FarmerAppInfo {
@@ -345,7 +455,7 @@ where
         })
     }
 
-    fn submit_solution_response(&self, solution_response: SolutionResponse) -> RpcResult<()> {
+    fn submit_solution_response(&self, mut solution_response: SolutionResponse) -> RpcResult<()> {
         self.deny_unsafe.check_if_safe()?;
 
         let solution_response_senders = self.solution_response_senders.clone();
@@ -353,10 +463,34 @@ where
         // TODO: This doesn't track what client sent a solution, allowing some clients to send
         //  multiple (https://github.com/paritytech/jsonrpsee/issues/452)
 
-        let mut solution_response_senders = solution_response_senders.lock();
-
         let slot = solution_response.slot_number;
 
+        // Farmer that already submitted solutions for this slot is most likely farming the same
+        // plot more than once, refuse its solutions to avoid equivocation
+        let mut noted_public_keys = HashSet::new();
+        let mut equivocating_public_keys = HashSet::new();
+        let mut equivocation_errors = Vec::new();
+        for solution in &solution_response.solutions {
+            if !noted_public_keys.insert(solution.public_key) {
+                continue;
+            }
+            let Ok(public_key) = FarmerPublicKey::from_slice(solution.public_key.as_ref()) else {
+                continue;
+            };
+            if let Err(error) = self
+                .farmer_equivocation_tracker
+                .note_solutions(Slot::from(slot), &public_key)
+            {
+                equivocating_public_keys.insert(solution.public_key);
+                equivocation_errors.push(error.to_string());
+            }
+        }
+        solution_response
+            .solutions
+            .retain(|solution| !equivocating_public_keys.contains(&solution.public_key));
+
+        let mut solution_response_senders = solution_response_senders.lock();
+
         let success = solution_response_senders
             .peek_mut(&slot)
             .and_then(|senders| senders.pop())
@@ -370,6 +504,10 @@ where
             );
         }
 
+        if !equivocation_errors.is_empty() {
+            return Err(JsonRpseeError::Custom(equivocation_errors.join("; ")));
+        }
+
         Ok(())
     }
 
@@ -760,57 +898,22 @@ where
     fn piece(&self, requested_piece_index: PieceIndex) -> RpcResult<Option<Vec<u8>>> {
         self.deny_unsafe.check_if_safe()?;
 
-        let archived_segment = {
-            let mut cached_archived_segment = self.cached_archived_segment.lock();
-
-            match cached_archived_segment
-                .as_ref()
-                .and_then(CachedArchivedSegment::get)
-            {
-                Some(archived_segment) => archived_segment,
-                None => {
-                    if requested_piece_index > SegmentIndex::ZERO.last_piece_index() {
-                        return Ok(None);
-                    }
-
-                    debug!(%requested_piece_index, "Re-creating genesis segment on demand");
-
-                    // Try to re-create genesis segment on demand
-                    match recreate_genesis_segment(&*self.client, self.kzg.clone()) {
-                        Ok(Some(archived_segment)) => {
-                            let archived_segment = Arc::new(archived_segment);
-                            cached_archived_segment.replace(CachedArchivedSegment::Genesis(
-                                Arc::clone(&archived_segment),
-                            ));
-                            archived_segment
-                        }
-                        Ok(None) => {
-                            return Ok(None);
-                        }
-                        Err(error) => {
-                            error!(%error, "Failed to re-create genesis segment");
+        if let Some(piece) = self.recent_piece(requested_piece_index)? {
+            return Ok(Some(piece));
+        }
 
-                            return Err(JsonRpseeError::Custom(
-                                "Failed to re-create genesis segment".to_string(),
-                            ));
-                        }
-                    }
-                }
-            }
+        let Some(archival_pieces_provider) = &self.archival_pieces_provider else {
+            return Ok(None);
         };
 
-        let indices = archived_segment
-            .segment_header
-            .segment_index()
-            .segment_piece_indexes();
-        let pieces = &archived_segment.pieces;
-        for (piece_index, piece) in indices.into_iter().zip(pieces.iter()) {
-            if requested_piece_index == piece_index {
-                return Ok(Some(piece.to_vec()));
-            }
-        }
+        archival_pieces_provider
+            .piece(requested_piece_index)
+            .map(|maybe_piece| maybe_piece.map(|piece| piece.to_vec()))
+            .map_err(|error| {
+                error!(%error, %requested_piece_index, "Failed to get piece from archival store");
 
-        Ok(None)
+                JsonRpseeError::Custom("Internal error during `piece` call".to_string())
+            })
     }
 
     async fn segment_headers(
@@ -859,12 +962,241 @@ where
             }
         };
 
-        let last_segment_headers = (SegmentIndex::ZERO..=last_segment_index)
-            .rev()
-            .take(limit as usize)
-            .map(|segment_index| self.segment_headers_store.get_segment_header(segment_index))
+        let segment_headers_count = limit.min(u64::from(last_segment_index) + 1);
+        let first_segment_index =
+            SegmentIndex::from(u64::from(last_segment_index) + 1 - segment_headers_count);
+        let mut last_segment_headers = self
+            .segment_headers_store
+            .segment_headers(first_segment_index..=last_segment_index)
+            .into_iter()
+            .map(Some)
             .collect::<Vec<_>>();
+        // Segment headers that are not stored yet are missing at the end
+        last_segment_headers.resize(segment_headers_count as usize, None);
+        last_segment_headers.reverse();
 
         Ok(last_segment_headers)
     }
+
+    async fn object_mapping(&self, hash: ObjectHash) -> RpcResult<Option<GlobalObject>> {
+        let object_mappings_provider = self.object_mappings_provider()?;
+
+        object_mappings_provider
+            .object_mapping(&hash.0)
+            .map_err(|error| {
+                error!(%error, "Failed to get object mapping");
+
+                JsonRpseeError::Custom("Internal error".to_string())
+            })
+    }
+
+    async fn segment_object_mappings(
+        &self,
+        first_segment_index: SegmentIndex,
+        last_segment_index: SegmentIndex,
+    ) -> RpcResult<Vec<SegmentObjectMappings>> {
+        let object_mappings_provider = self.object_mappings_provider()?;
+
+        if first_segment_index > last_segment_index {
+            return Err(JsonRpseeError::Custom(format!(
+                "First segment index {first_segment_index} is greater than last segment index \
+                {last_segment_index}"
+            )));
+        }
+        if u64::from(last_segment_index - first_segment_index)
+            >= MAX_OBJECT_MAPPINGS_SEGMENTS_PER_REQUEST
+        {
+            return Err(JsonRpseeError::Custom(format!(
+                "Segment index range exceeds the limit {MAX_OBJECT_MAPPINGS_SEGMENTS_PER_REQUEST}"
+            )));
+        }
+
+        let mut segment_object_mappings = Vec::new();
+        for segment_index in first_segment_index..=last_segment_index {
+            let maybe_objects = object_mappings_provider
+                .segment_object_mappings(segment_index)
+                .map_err(|error| {
+                    error!(%error, %segment_index, "Failed to get segment object mappings");
+
+                    JsonRpseeError::Custom("Internal error".to_string())
+                })?;
+
+            if let Some(objects) = maybe_objects {
+                segment_object_mappings.push(to_segment_object_mappings(
+                    segment_index,
+                    objects.into_iter(),
+                ));
+            }
+        }
+
+        Ok(segment_object_mappings)
+    }
+
+    fn subscribe_object_mappings(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+        let stream = self.archived_segment_notification_stream.subscribe().map(
+            |archived_segment_notification| {
+                // Acknowledgement sender is dropped right away, object mappings are not needed for
+                // farming
+                let ArchivedSegmentNotification {
+                    archived_segment, ..
+                } = archived_segment_notification;
+
+                to_segment_object_mappings(
+                    archived_segment.segment_header.segment_index(),
+                    archived_segment.global_object_mapping(),
+                )
+            },
+        );
+
+        let fut = async move {
+            sink.pipe_from_stream(stream).await;
+        };
+
+        self.subscription_executor.spawn(
+            "subspace-object-mappings-subscription",
+            Some("rpc"),
+            fut.boxed(),
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "pot")]
+    fn subscribe_pot_slot_info(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+        let stream = self
+            .pot_slot_info_notification_stream
+            .subscribe(POT_SLOT_INFO_QUEUE_SIZE_WARNING)
+            .map(|pot_slot_info| PotSlotInfo {
+                slot_number: SlotNumber::from(pot_slot_info.slot),
+                seed: pot_slot_info.seed,
+                slot_iterations: pot_slot_info.slot_iterations,
+                checkpoints: pot_slot_info.checkpoints.to_vec(),
+            });
+
+        let fut = async move {
+            sink.pipe_from_stream(stream).await;
+        };
+
+        self.subscription_executor.spawn(
+            "subspace-pot-slot-info-subscription",
+            Some("rpc"),
+            fut.boxed(),
+        );
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "pot"))]
+    fn subscribe_pot_slot_info(&self, _sink: SubscriptionSink) -> SubscriptionResult {
+        // Proof of time is not enabled
+        Err(SubscriptionEmptyError)
+    }
+
+    #[cfg(feature = "pot")]
+    fn pot_parameters(&self) -> RpcResult<PotParametersInfo> {
+        let best_hash = self.client.info().best_hash;
+
+        let pot_parameters = self
+            .client
+            .runtime_api()
+            .pot_parameters(best_hash)
+            .map_err(|error| {
+                error!(%error, ?best_hash, "Failed to get proof of time parameters");
+
+                JsonRpseeError::Custom("Internal error".to_string())
+            })?;
+
+        Ok(PotParametersInfo {
+            slot_iterations: pot_parameters.slot_iterations(),
+            next_change: pot_parameters
+                .next_parameters_change()
+                .map(|parameters_change| PotParametersChangeInfo {
+                    slot_number: SlotNumber::from(parameters_change.slot),
+                    slot_iterations: parameters_change.slot_iterations,
+                    entropy: parameters_change.entropy,
+                }),
+        })
+    }
+
+    #[cfg(not(feature = "pot"))]
+    fn pot_parameters(&self) -> RpcResult<PotParametersInfo> {
+        Err(JsonRpseeError::Custom(
+            "Proof of time is not enabled".to_string(),
+        ))
+    }
+}
+
+impl<Block, Client, SO, AS> SubspaceRpc<Block, Client, SO, AS>
+where
+    Block: BlockT,
+    SO: SyncOracle + Send + Sync + Clone + 'static,
+{
+    fn object_mappings_provider(&self) -> RpcResult<&dyn ObjectMappingsProvider> {
+        self.object_mappings_provider
+            .as_deref()
+            .ok_or_else(|| JsonRpseeError::Custom("Object mappings index is disabled".to_string()))
+    }
+}
+
+impl<Block, Client, SO, AS> SubspaceRpc<Block, Client, SO, AS>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+    Client::Api: SubspaceRuntimeApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+    SO: SyncOracle + Send + Sync + Clone + 'static,
+{
+    /// Piece of the most recently archived segment or re-created genesis segment
+    fn recent_piece(&self, requested_piece_index: PieceIndex) -> RpcResult<Option<Vec<u8>>> {
+        let archived_segment = {
+            let mut cached_archived_segment = self.cached_archived_segment.lock();
+
+            match cached_archived_segment
+                .as_ref()
+                .and_then(CachedArchivedSegment::get)
+            {
+                Some(archived_segment) => archived_segment,
+                None => {
+                    if requested_piece_index > SegmentIndex::ZERO.last_piece_index() {
+                        return Ok(None);
+                    }
+
+                    debug!(%requested_piece_index, "Re-creating genesis segment on demand");
+
+                    // Try to re-create genesis segment on demand
+                    match recreate_genesis_segment(&*self.client, self.kzg.clone()) {
+                        Ok(Some(archived_segment)) => {
+                            let archived_segment = Arc::new(archived_segment);
+                            cached_archived_segment.replace(CachedArchivedSegment::Genesis(
+                                Arc::clone(&archived_segment),
+                            ));
+                            archived_segment
+                        }
+                        Ok(None) => {
+                            return Ok(None);
+                        }
+                        Err(error) => {
+                            error!(%error, "Failed to re-create genesis segment");
+
+                            return Err(JsonRpseeError::Custom(
+                                "Failed to re-create genesis segment".to_string(),
+                            ));
+                        }
+                    }
+                }
+            }
+        };
+
+        let indices = archived_segment
+            .segment_header
+            .segment_index()
+            .segment_piece_indexes();
+        let pieces = &archived_segment.pieces;
+        for (piece_index, piece) in indices.into_iter().zip(pieces.iter()) {
+            if requested_piece_index == piece_index {
+                return Ok(Some(piece.to_vec()));
+            }
+        }
+
+        Ok(None)
+    }
 }
@@ -0,0 +1,479 @@
+use crate::{ObjectMappingsProvider, SubspaceRpc, SubspaceRpcApiServer, SubspaceRpcConfig};
+use jsonrpsee::core::EmptyServerParams as EmptyParams;
+use parking_lot::Mutex;
+use sc_client_api::{AuxStore, BlockBackend};
+use sc_consensus_subspace::archiver::SegmentHeadersStore;
+use sc_consensus_subspace::farmer_equivocation::FarmerEquivocationTracker;
+use sc_consensus_subspace::notification::channel;
+use sc_consensus_subspace::SubspaceSyncOracle;
+use sc_proof_of_time::source::{PotSlotInfo, PotSlotInfoNotificationStream};
+use sc_rpc::DenyUnsafe;
+use sp_blockchain::{BlockStatus, HeaderBackend, Info};
+use sp_consensus::NoNetwork;
+use sp_consensus_slots::{Slot, SlotDuration};
+use sp_consensus_subspace::{
+    ChainConstants, EquivocationProof, FarmerPublicKey, PotParameters, PotParametersChange,
+    SignedVote, SolutionRanges, SubspaceApi,
+};
+use sp_core::testing::TaskExecutor;
+use sp_core::H256;
+use sp_objects::ObjectsApi;
+use sp_runtime::generic::{self, SignedBlock};
+use sp_runtime::traits::BlakeTwo256;
+use sp_runtime::{Justifications, OpaqueExtrinsic};
+use std::collections::HashMap;
+use std::num::{NonZeroU32, NonZeroU64};
+use std::sync::Arc;
+use std::time::Duration;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::objects::{BlockObjectMapping, GlobalObject};
+use subspace_core_primitives::{
+    Blake2b256Hash, HistorySize, PieceIndex, PotCheckpoints, PotOutput, PotSeed, SegmentCommitment,
+    SegmentHeader, SegmentIndex,
+};
+use subspace_rpc_primitives::{
+    ObjectHash, ObjectMapping, PotParametersChangeInfo, PotParametersInfo,
+    PotSlotInfo as RpcPotSlotInfo, SegmentObjectMappings, MAX_OBJECT_MAPPINGS_SEGMENTS_PER_REQUEST,
+};
+
+type Header = generic::Header<u32, BlakeTwo256>;
+type Block = generic::Block<Header, OpaqueExtrinsic>;
+
+const BEST_HASH: H256 = H256::repeat_byte(1);
+
+/// Client with a chain that only has the best block, only runtime API calls that the tests rely on
+/// are implemented.
+#[derive(Clone)]
+struct TestClient {
+    pot_parameters: PotParameters,
+}
+
+sp_api::mock_impl_runtime_apis! {
+    impl SubspaceApi<Block, FarmerPublicKey> for TestClient {
+        fn slot_duration() -> SlotDuration {
+            SlotDuration::from_millis(1000)
+        }
+
+        fn pot_parameters(&self) -> PotParameters {
+            self.pot_parameters.clone()
+        }
+
+        fn solution_ranges() -> SolutionRanges {
+            unreachable!()
+        }
+
+        fn submit_report_equivocation_extrinsic(
+            _equivocation_proof: EquivocationProof<Header>,
+        ) -> Option<()> {
+            unreachable!()
+        }
+
+        fn submit_vote_extrinsic(_signed_vote: SignedVote<u32, H256, FarmerPublicKey>) {
+            unreachable!()
+        }
+
+        fn is_in_block_list(_farmer_public_key: &FarmerPublicKey) -> bool {
+            unreachable!()
+        }
+
+        fn history_size() -> HistorySize {
+            unreachable!()
+        }
+
+        fn max_pieces_in_sector() -> u16 {
+            unreachable!()
+        }
+
+        fn segment_commitment(_segment_index: SegmentIndex) -> Option<SegmentCommitment> {
+            unreachable!()
+        }
+
+        fn extract_segment_headers(_ext: &OpaqueExtrinsic) -> Option<Vec<SegmentHeader>> {
+            unreachable!()
+        }
+
+        fn is_inherent(_ext: &OpaqueExtrinsic) -> bool {
+            unreachable!()
+        }
+
+        fn root_plot_public_key() -> Option<FarmerPublicKey> {
+            unreachable!()
+        }
+
+        fn should_adjust_solution_range() -> bool {
+            unreachable!()
+        }
+
+        fn chain_constants() -> ChainConstants {
+            ChainConstants::V0 {
+                confirmation_depth_k: 100,
+                block_authoring_delay: Slot::from(4),
+                era_duration: 2016,
+                slot_probability: (1, 6),
+                recent_segments: HistorySize::new(NonZeroU64::new(5).unwrap()),
+                recent_history_fraction: (
+                    HistorySize::new(NonZeroU64::new(1).unwrap()),
+                    HistorySize::new(NonZeroU64::new(10).unwrap()),
+                ),
+                min_sector_lifetime: HistorySize::new(NonZeroU64::new(4).unwrap()),
+            }
+        }
+    }
+
+    impl ObjectsApi<Block> for TestClient {
+        fn validated_object_call_hashes() -> Vec<H256> {
+            unreachable!()
+        }
+
+        fn extract_block_object_mapping(
+            _block: Block,
+            _validated_object_calls: Vec<H256>,
+        ) -> BlockObjectMapping {
+            unreachable!()
+        }
+    }
+}
+
+impl HeaderBackend<Block> for TestClient {
+    fn header(&self, _hash: H256) -> sp_blockchain::Result<Option<Header>> {
+        Ok(None)
+    }
+
+    fn info(&self) -> Info<Block> {
+        Info {
+            best_hash: BEST_HASH,
+            best_number: 1,
+            genesis_hash: H256::zero(),
+            finalized_hash: H256::zero(),
+            finalized_number: 0,
+            finalized_state: None,
+            number_leaves: 1,
+            block_gap: None,
+        }
+    }
+
+    fn status(&self, _hash: H256) -> sp_blockchain::Result<BlockStatus> {
+        Ok(BlockStatus::Unknown)
+    }
+
+    fn number(&self, _hash: H256) -> sp_blockchain::Result<Option<u32>> {
+        Ok(None)
+    }
+
+    fn hash(&self, _number: u32) -> sp_blockchain::Result<Option<H256>> {
+        Ok(None)
+    }
+}
+
+impl BlockBackend<Block> for TestClient {
+    fn block_body(&self, _hash: H256) -> sp_blockchain::Result<Option<Vec<OpaqueExtrinsic>>> {
+        Ok(None)
+    }
+
+    fn block_indexed_body(&self, _hash: H256) -> sp_blockchain::Result<Option<Vec<Vec<u8>>>> {
+        Ok(None)
+    }
+
+    fn block(&self, _hash: H256) -> sp_blockchain::Result<Option<SignedBlock<Block>>> {
+        Ok(None)
+    }
+
+    fn block_status(&self, _hash: H256) -> sp_blockchain::Result<sp_consensus::BlockStatus> {
+        Ok(sp_consensus::BlockStatus::Unknown)
+    }
+
+    fn justifications(&self, _hash: H256) -> sp_blockchain::Result<Option<Justifications>> {
+        Ok(None)
+    }
+
+    fn block_hash(&self, _number: u32) -> sp_blockchain::Result<Option<H256>> {
+        Ok(None)
+    }
+
+    fn indexed_transaction(&self, _hash: H256) -> sp_blockchain::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    fn requires_full_sync(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Default)]
+struct TestAuxStore(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+impl AuxStore for TestAuxStore {
+    fn insert_aux<
+        'a,
+        'b: 'a,
+        'c: 'a,
+        I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+        D: IntoIterator<Item = &'a &'b [u8]>,
+    >(
+        &self,
+        insert: I,
+        delete: D,
+    ) -> sp_blockchain::Result<()> {
+        let mut storage = self.0.lock();
+        for (key, value) in insert {
+            storage.insert(key.to_vec(), value.to_vec());
+        }
+        for key in delete {
+            storage.remove(*key);
+        }
+        Ok(())
+    }
+
+    fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+        Ok(self.0.lock().get(key).cloned())
+    }
+}
+
+/// Object mappings index with specified objects in each indexed segment
+#[derive(Default)]
+struct TestObjectMappingsProvider(HashMap<SegmentIndex, Vec<(Blake2b256Hash, GlobalObject)>>);
+
+impl ObjectMappingsProvider for TestObjectMappingsProvider {
+    fn object_mapping(
+        &self,
+        hash: &Blake2b256Hash,
+    ) -> Result<Option<GlobalObject>, sp_blockchain::Error> {
+        Ok(self
+            .0
+            .values()
+            .flatten()
+            .find_map(|(object_hash, global_object)| {
+                (object_hash == hash).then_some(*global_object)
+            }))
+    }
+
+    fn segment_object_mappings(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<Option<Vec<(Blake2b256Hash, GlobalObject)>>, sp_blockchain::Error> {
+        Ok(self.0.get(&segment_index).cloned())
+    }
+}
+
+fn pot_parameters() -> PotParameters {
+    PotParameters::V0 {
+        slot_iterations: NonZeroU32::new(100_000).unwrap(),
+        next_change: Some(PotParametersChange {
+            slot: Slot::from(50),
+            slot_iterations: NonZeroU32::new(200_000).unwrap(),
+            entropy: [2; 32],
+        }),
+    }
+}
+
+fn new_rpc(
+    pot_slot_info_notification_stream: PotSlotInfoNotificationStream,
+    object_mappings_provider: Option<Arc<dyn ObjectMappingsProvider>>,
+) -> SubspaceRpc<Block, TestClient, NoNetwork, TestAuxStore> {
+    SubspaceRpc::new(SubspaceRpcConfig {
+        client: Arc::new(TestClient {
+            pot_parameters: pot_parameters(),
+        }),
+        subscription_executor: Arc::new(TaskExecutor::new()),
+        new_slot_notification_stream: channel("test_new_slot_notification_stream").1,
+        reward_signing_notification_stream: channel("test_reward_signing_notification_stream").1,
+        archived_segment_notification_stream: channel("test_archived_segment_notification_stream")
+            .1,
+        dsn_bootstrap_nodes: Vec::new(),
+        segment_headers_store: SegmentHeadersStore::new(Arc::new(TestAuxStore::default())).unwrap(),
+        sync_oracle: SubspaceSyncOracle::new(false, NoNetwork),
+        farmer_equivocation_tracker: FarmerEquivocationTracker::new(None).unwrap(),
+        deny_unsafe: DenyUnsafe::No,
+        kzg: Kzg::new(embedded_kzg_settings()),
+        object_mappings_provider,
+        archival_pieces_provider: None,
+        pot_slot_info_notification_stream,
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn pot_slot_info_subscription() {
+    let (pot_slot_info_sender, pot_slot_info_notification_stream) =
+        PotSlotInfoNotificationStream::channel();
+    let rpc = new_rpc(pot_slot_info_notification_stream, None).into_rpc();
+
+    let mut subscription = rpc
+        .subscribe("subspace_subscribePotSlotInfo", EmptyParams::new())
+        .await
+        .unwrap();
+
+    let mut checkpoints = PotCheckpoints::default();
+    for (index, checkpoint) in checkpoints.iter_mut().enumerate() {
+        *checkpoint = PotOutput::from([index as u8; PotOutput::SIZE]);
+    }
+    let pot_slot_info = PotSlotInfo {
+        slot: Slot::from(10),
+        seed: PotSeed::from([1; PotSeed::SIZE]),
+        slot_iterations: NonZeroU32::new(100_000).unwrap(),
+        checkpoints,
+    };
+    pot_slot_info_sender
+        .notify(|| Ok::<_, ()>(pot_slot_info))
+        .unwrap();
+
+    let (received, _subscription_id) = tokio::time::timeout(
+        Duration::from_secs(5),
+        subscription.next::<RpcPotSlotInfo>(),
+    )
+    .await
+    .unwrap()
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        received,
+        RpcPotSlotInfo {
+            slot_number: 10,
+            seed: pot_slot_info.seed,
+            slot_iterations: pot_slot_info.slot_iterations,
+            checkpoints: checkpoints.to_vec(),
+        }
+    );
+    assert_eq!(received.checkpoints.last(), Some(&checkpoints.output()));
+}
+
+#[tokio::test]
+async fn pot_parameters_info() {
+    let (_pot_slot_info_sender, pot_slot_info_notification_stream) =
+        PotSlotInfoNotificationStream::channel();
+    let rpc = new_rpc(pot_slot_info_notification_stream, None).into_rpc();
+
+    let pot_parameters_info = rpc
+        .call::<_, PotParametersInfo>("subspace_potParameters", EmptyParams::new())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        pot_parameters_info,
+        PotParametersInfo {
+            slot_iterations: NonZeroU32::new(100_000).unwrap(),
+            next_change: Some(PotParametersChangeInfo {
+                slot_number: 50,
+                slot_iterations: NonZeroU32::new(200_000).unwrap(),
+                entropy: [2; 32],
+            }),
+        }
+    );
+}
+
+fn global_object(piece_index: u64, offset: u32) -> GlobalObject {
+    GlobalObject::V0 {
+        piece_index: PieceIndex::from(piece_index),
+        offset,
+    }
+}
+
+#[tokio::test]
+async fn object_mappings() {
+    let (_pot_slot_info_sender, pot_slot_info_notification_stream) =
+        PotSlotInfoNotificationStream::channel();
+    let object_mappings_provider = TestObjectMappingsProvider(HashMap::from([
+        (
+            SegmentIndex::ZERO,
+            vec![
+                ([1; 32], global_object(0, 10)),
+                ([2; 32], global_object(3, 20)),
+            ],
+        ),
+        (
+            SegmentIndex::from(2),
+            vec![([3; 32], global_object(512, 0))],
+        ),
+    ]));
+    let rpc = new_rpc(
+        pot_slot_info_notification_stream,
+        Some(Arc::new(object_mappings_provider)),
+    )
+    .into_rpc();
+
+    let object = rpc
+        .call::<_, Option<GlobalObject>>("subspace_objectMapping", [ObjectHash([2; 32])])
+        .await
+        .unwrap();
+    assert_eq!(object, Some(global_object(3, 20)));
+
+    let object = rpc
+        .call::<_, Option<GlobalObject>>("subspace_objectMapping", [ObjectHash([4; 32])])
+        .await
+        .unwrap();
+    assert_eq!(object, None);
+
+    // Only indexed segments are returned
+    let segment_object_mappings = rpc
+        .call::<_, Vec<SegmentObjectMappings>>(
+            "subspace_segmentObjectMappings",
+            (SegmentIndex::ZERO, SegmentIndex::from(3)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        segment_object_mappings,
+        vec![
+            SegmentObjectMappings {
+                segment_index: SegmentIndex::ZERO,
+                objects: vec![
+                    ObjectMapping {
+                        hash: ObjectHash([1; 32]),
+                        global_object: global_object(0, 10),
+                    },
+                    ObjectMapping {
+                        hash: ObjectHash([2; 32]),
+                        global_object: global_object(3, 20),
+                    },
+                ],
+            },
+            SegmentObjectMappings {
+                segment_index: SegmentIndex::from(2),
+                objects: vec![ObjectMapping {
+                    hash: ObjectHash([3; 32]),
+                    global_object: global_object(512, 0),
+                }],
+            },
+        ]
+    );
+
+    // Inverted range
+    assert!(rpc
+        .call::<_, Vec<SegmentObjectMappings>>(
+            "subspace_segmentObjectMappings",
+            (SegmentIndex::from(2), SegmentIndex::ONE),
+        )
+        .await
+        .is_err());
+
+    // Range exceeding the limit
+    assert!(rpc
+        .call::<_, Vec<SegmentObjectMappings>>(
+            "subspace_segmentObjectMappings",
+            (
+                SegmentIndex::ZERO,
+                SegmentIndex::from(MAX_OBJECT_MAPPINGS_SEGMENTS_PER_REQUEST),
+            ),
+        )
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn object_mappings_disabled() {
+    let (_pot_slot_info_sender, pot_slot_info_notification_stream) =
+        PotSlotInfoNotificationStream::channel();
+    let rpc = new_rpc(pot_slot_info_notification_stream, None).into_rpc();
+
+    assert!(rpc
+        .call::<_, Option<GlobalObject>>("subspace_objectMapping", [ObjectHash([1; 32])])
+        .await
+        .is_err());
+    assert!(rpc
+        .call::<_, Vec<SegmentObjectMappings>>(
+            "subspace_segmentObjectMappings",
+            (SegmentIndex::ZERO, SegmentIndex::ZERO),
+        )
+        .await
+        .is_err());
+}
@@ -19,12 +19,20 @@
 //! Contains implementation of archiving process in Subspace blockchain that convers blockchain
 //! history (blocks) into archived history (pieces).
 
+#[cfg(test)]
+mod tests;
+
+use crate::aux_schema::{
+    load_archiver_checkpoint, write_archiver_checkpoint, ArchiverCheckpointData,
+};
 use crate::{
     get_chain_constants, ArchivedSegmentNotification, BlockImportingNotification, SubspaceLink,
     SubspaceNotificationSender, SubspaceSyncOracle,
 };
 use codec::{Decode, Encode};
-use futures::StreamExt;
+use futures::channel::{mpsc, oneshot};
+use futures::future::Either;
+use futures::{executor, future, SinkExt, StreamExt};
 use log::{debug, error, info, warn};
 use parking_lot::Mutex;
 use rand::prelude::*;
@@ -32,7 +40,7 @@ use rand_chacha::ChaCha8Rng;
 use sc_client_api::{AuxStore, Backend as BackendT, BlockBackend, Finalizer, LockImportRun};
 use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_INFO};
 use sc_utils::mpsc::tracing_unbounded;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiError, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_consensus::SyncOracle;
 use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
@@ -40,10 +48,12 @@ use sp_objects::ObjectsApi;
 use sp_runtime::traits::{Block as BlockT, CheckedSub, Header, NumberFor, One, Zero};
 use std::error::Error;
 use std::future::Future;
-use std::slice;
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::ops::{Bound, RangeBounds};
 use std::sync::Arc;
-use subspace_archiving::archiver::{Archiver, NewArchivedSegment};
+use std::{io, thread};
+use subspace_archiving::archiver::{
+    Archiver, ArchiverInstantiationError, NewArchivedSegment, PendingArchivedSegment,
+};
 use subspace_core_primitives::crypto::kzg::Kzg;
 use subspace_core_primitives::objects::BlockObjectMapping;
 use subspace_core_primitives::{BlockNumber, RecordedHistorySegment, SegmentHeader, SegmentIndex};
@@ -51,12 +61,15 @@ use subspace_core_primitives::{BlockNumber, RecordedHistorySegment, SegmentHeade
 #[derive(Debug)]
 struct SegmentHeadersStoreInner<AS> {
     aux_store: Arc<AS>,
-    next_key_index: AtomicU16,
     /// In-memory cache of segment headers
     cache: Mutex<Vec<SegmentHeader>>,
 }
 
-/// Persistent storage of segment headers
+/// Persistent storage of segment headers.
+///
+/// Segment headers are stored in pages of [`SEGMENT_HEADERS_PER_PAGE`] consecutive segment headers
+/// each, such that the page containing any segment header is known upfront and only the last page
+/// is re-written when new segment headers are added.
 #[derive(Debug)]
 pub struct SegmentHeadersStore<AS> {
     inner: Arc<SegmentHeadersStoreInner<AS>>,
@@ -70,32 +83,60 @@ impl<AS> Clone for SegmentHeadersStore<AS> {
     }
 }
 
+/// Number of segment headers stored under a single key in [`SegmentHeadersStore`]
+pub const SEGMENT_HEADERS_PER_PAGE: u64 = 256;
+
 impl<AS> SegmentHeadersStore<AS>
 where
     AS: AuxStore,
 {
-    const KEY_PREFIX: &[u8] = b"segment-headers";
+    const PAGE_KEY_PREFIX: &[u8] = b"segment-headers-page";
+    /// Prefix of keys used before compaction into pages, each key contained one batch of
+    /// segment headers added at once
+    const LEGACY_KEY_PREFIX: &[u8] = b"segment-headers";
     const INITIAL_CACHE_CAPACITY: usize = 1_000;
 
-    /// Create new instance
+    /// Create new instance, migrating segment headers stored in legacy format if necessary
     pub fn new(aux_store: Arc<AS>) -> Result<Self, sp_blockchain::Error> {
         let mut cache = Vec::with_capacity(Self::INITIAL_CACHE_CAPACITY);
-        let mut next_key_index = 0;
 
         debug!(
             target: "subspace",
             "Started loading segment headers into cache"
         );
+        let mut page_index = 0;
         while let Some(segment_headers) =
-            aux_store
-                .get_aux(&Self::key(next_key_index))?
-                .map(|segment_header| {
-                    Vec::<SegmentHeader>::decode(&mut segment_header.as_slice())
-                        .expect("Always correct segment header unless DB is corrupted; qed")
-                })
+            Self::load(aux_store.as_ref(), &Self::page_key(page_index))?
         {
             cache.extend(segment_headers);
-            next_key_index += 1;
+            page_index += 1;
+        }
+
+        let mut legacy_keys = Vec::new();
+        while let Some(segment_headers) = Self::load(
+            aux_store.as_ref(),
+            &Self::legacy_key(legacy_keys.len() as u16),
+        )? {
+            cache.extend(segment_headers);
+            legacy_keys.push(Self::legacy_key(legacy_keys.len() as u16));
+        }
+        if !legacy_keys.is_empty() {
+            info!(
+                target: "subspace",
+                "Compacting {} segment headers stored under {} keys",
+                cache.len(),
+                legacy_keys.len()
+            );
+
+            // Pages are written and legacy keys are removed atomically
+            let pages = Self::encode_pages(&cache, 0);
+            let insert_data = pages
+                .iter()
+                .map(|(key, value)| (key.as_slice(), value.as_slice()))
+                .collect::<Vec<_>>();
+            let delete_keys = legacy_keys.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+            aux_store.insert_aux(&insert_data, &delete_keys)?;
         }
         debug!(
             target: "subspace",
@@ -105,7 +146,6 @@ where
         Ok(Self {
             inner: Arc::new(SegmentHeadersStoreInner {
                 aux_store,
-                next_key_index: AtomicU16::new(next_key_index),
                 cache: Mutex::new(cache),
             }),
         })
@@ -122,7 +162,14 @@ where
         &self,
         segment_headers: &[SegmentHeader],
     ) -> Result<(), sp_blockchain::Error> {
-        let mut maybe_last_segment_index = self.max_segment_index();
+        // Lock is held for the whole duration of addition to prevent concurrent writes of the
+        // same page
+        let mut cache = self.inner.cache.lock();
+
+        let mut maybe_last_segment_index = cache
+            .len()
+            .checked_sub(1)
+            .map(|segment_index| SegmentIndex::from(segment_index as u64));
         let mut segment_headers_to_store = Vec::with_capacity(segment_headers.len());
         for segment_header in segment_headers {
             let segment_index = segment_header.segment_index();
@@ -141,7 +188,7 @@ where
                         return Err(sp_blockchain::Error::Application(error.into()));
                     }
 
-                    segment_headers_to_store.push(segment_header);
+                    segment_headers_to_store.push(*segment_header);
                     maybe_last_segment_index.replace(segment_index);
                 }
                 None => {
@@ -152,7 +199,7 @@ where
                         return Err(sp_blockchain::Error::Application(error.into()));
                     }
 
-                    segment_headers_to_store.push(segment_header);
+                    segment_headers_to_store.push(*segment_header);
                     maybe_last_segment_index.replace(segment_index);
                 }
             }
@@ -162,17 +209,22 @@ where
             return Ok(());
         }
 
-        // TODO: Do compaction when we have too many keys: combine multiple segment headers into a
-        //  single entry for faster retrievals and more compact storage
         {
-            let key_index = self.inner.next_key_index.fetch_add(1, Ordering::SeqCst);
-            let key = Self::key(key_index);
-            let value = segment_headers_to_store.encode();
-            let insert_data = vec![(key.as_slice(), value.as_slice())];
+            // Re-write the last partially filled page together with new pages
+            let first_page_index = cache.len() as u64 / SEGMENT_HEADERS_PER_PAGE;
+            let first_page_offset = (first_page_index * SEGMENT_HEADERS_PER_PAGE) as usize;
+            let mut segment_headers = cache[first_page_offset..].to_vec();
+            segment_headers.extend_from_slice(&segment_headers_to_store);
+
+            let pages = Self::encode_pages(&segment_headers, first_page_index);
+            let insert_data = pages
+                .iter()
+                .map(|(key, value)| (key.as_slice(), value.as_slice()))
+                .collect::<Vec<_>>();
 
             self.inner.aux_store.insert_aux(&insert_data, &[])?;
         }
-        self.inner.cache.lock().extend(segment_headers_to_store);
+        cache.extend(segment_headers_to_store);
 
         Ok(())
     }
@@ -186,8 +238,59 @@ where
             .copied()
     }
 
-    fn key(key_index: u16) -> Vec<u8> {
-        (Self::KEY_PREFIX, key_index.to_le_bytes()).encode()
+    /// Get segment headers in the range, segment headers that are not stored yet are not included,
+    /// so the result might be shorter than the range or empty
+    pub fn segment_headers<R>(&self, range: R) -> Vec<SegmentHeader>
+    where
+        R: RangeBounds<SegmentIndex>,
+    {
+        let cache = self.inner.cache.lock();
+
+        let start = match range.start_bound() {
+            Bound::Included(&segment_index) => u64::from(segment_index),
+            Bound::Excluded(&segment_index) => u64::from(segment_index).saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&segment_index) => u64::from(segment_index).saturating_add(1),
+            Bound::Excluded(&segment_index) => u64::from(segment_index),
+            Bound::Unbounded => u64::MAX,
+        };
+        let start = (start as usize).min(cache.len());
+        let end = (end as usize).clamp(start, cache.len());
+
+        cache[start..end].to_vec()
+    }
+
+    /// Encode consecutive segment headers into pages, first segment header must be the first one
+    /// in the page with `first_page_index`
+    fn encode_pages(
+        segment_headers: &[SegmentHeader],
+        first_page_index: u64,
+    ) -> Vec<(Vec<u8>, Vec<u8>)> {
+        segment_headers
+            .chunks(SEGMENT_HEADERS_PER_PAGE as usize)
+            .zip(first_page_index..)
+            .map(|(page, page_index)| (Self::page_key(page_index), page.encode()))
+            .collect()
+    }
+
+    fn load(
+        aux_store: &AS,
+        key: &[u8],
+    ) -> Result<Option<Vec<SegmentHeader>>, sp_blockchain::Error> {
+        Ok(aux_store.get_aux(key)?.map(|segment_headers| {
+            Vec::<SegmentHeader>::decode(&mut segment_headers.as_slice())
+                .expect("Always correct segment header unless DB is corrupted; qed")
+        }))
+    }
+
+    fn page_key(page_index: u64) -> Vec<u8> {
+        (Self::PAGE_KEY_PREFIX, page_index).encode()
+    }
+
+    fn legacy_key(key_index: u16) -> Vec<u8> {
+        (Self::LEGACY_KEY_PREFIX, key_index.to_le_bytes()).encode()
     }
 }
 
@@ -201,6 +304,61 @@ where
 /// https://github.com/paritytech/substrate/discussions/14359
 pub(crate) const FINALIZATION_DEPTH_IN_SEGMENTS: usize = 5;
 
+/// Object mappings of the block.
+///
+/// Genesis block has no extrinsics and no parent that runtime API could be called at, so it has no
+/// object mappings.
+fn block_object_mappings<Block, Client>(
+    client: &Client,
+    block: &Block,
+) -> Result<BlockObjectMapping, ApiError>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block>,
+    Client::Api: ObjectsApi<Block>,
+{
+    if block.header().number().is_zero() {
+        return Ok(BlockObjectMapping::default());
+    }
+
+    let calls = client
+        .runtime_api()
+        .validated_object_call_hashes(block.hash())?;
+
+    client.runtime_api().extract_block_object_mapping(
+        *block.header().parent_hash(),
+        block.clone(),
+        calls,
+    )
+}
+
+/// Object mappings of the block for (re-)archiving of blocks that were imported earlier.
+///
+/// State at the parent of such block may have been pruned already, in which case object mappings
+/// can't be extracted and the block is archived without them, which only affects object mappings
+/// index and not the archived history itself.
+fn block_object_mappings_or_default<Block, Client>(
+    client: &Client,
+    block: &Block,
+) -> BlockObjectMapping
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block>,
+    Client::Api: ObjectsApi<Block>,
+{
+    block_object_mappings(client, block).unwrap_or_else(|error| {
+        warn!(
+            target: "subspace",
+            "Failed to retrieve object mappings of block {} ({}), archiving it without object \
+            mappings: {error}",
+            block.header().number(),
+            block.hash(),
+        );
+
+        BlockObjectMapping::default()
+    })
+}
+
 fn find_last_archived_block<Block, Client>(
     client: &Client,
     best_block_hash: Block::Hash,
@@ -264,19 +422,7 @@ where
         block_to_check = *block.header().parent_hash();
     };
 
-    let last_archived_block_hash = block_to_check;
-
-    let block_object_mappings = client
-        .runtime_api()
-        .validated_object_call_hashes(last_archived_block_hash)
-        .and_then(|calls| {
-            client.runtime_api().extract_block_object_mapping(
-                *last_archived_block.header().parent_hash(),
-                last_archived_block.clone(),
-                calls,
-            )
-        })
-        .unwrap_or_default();
+    let block_object_mappings = block_object_mappings_or_default(client, &last_archived_block);
 
     Some((
         last_segment_header,
@@ -344,29 +490,24 @@ pub fn recreate_genesis_segment<Block, Client>(
 where
     Block: BlockT,
     Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block>,
-    Client::Api: ObjectsApi<Block>,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
 {
-    let genesis_hash = client.info().genesis_hash;
-    let Some(block) = client.block(genesis_hash)? else {
+    let client_info = client.info();
+    let Some(block) = client.block(client_info.genesis_hash)? else {
         return Ok(None);
     };
     let block = block.block;
-
-    let block_object_mappings = client
+    // Genesis state is likely pruned already, hence chain constants are taken from the best block
+    let erasure_coding_rate = client
         .runtime_api()
-        .validated_object_call_hashes(genesis_hash)
-        .and_then(|calls| {
-            client.runtime_api().extract_block_object_mapping(
-                *block.header().parent_hash(),
-                block.clone(),
-                calls,
-            )
-        })
-        .unwrap_or_default();
+        .chain_constants(client_info.best_hash)?
+        .erasure_coding_rate();
+
+    let block_object_mappings = block_object_mappings_or_default(client, &block);
 
     let encoded_block = encode_genesis_block(&block);
 
-    let new_archived_segment = Archiver::new(kzg)?
+    let new_archived_segment = Archiver::new(kzg, erasure_coding_rate)?
         .add_block(encoded_block, block_object_mappings, false)
         .into_iter()
         .next()
@@ -375,6 +516,110 @@ where
     Ok(Some(new_archived_segment))
 }
 
+/// Re-create archived history from blocks stored in the database, from genesis up to the best
+/// block at archiving depth, `on_archived_segment` is called for every archived segment.
+///
+/// Returns number of archived segments, fails if any of the blocks was already pruned.
+pub fn recreate_archived_segments<Block, Client, F>(
+    client: &Client,
+    kzg: Kzg,
+    on_archived_segment: F,
+) -> Result<u64, Box<dyn Error>>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block> + AuxStore,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+    F: FnMut(NewArchivedSegment) -> Result<(), Box<dyn Error>>,
+{
+    let chain_constants = get_chain_constants(client)?;
+    let confirmation_depth_k = chain_constants.confirmation_depth_k();
+    let best_block_number = TryInto::<BlockNumber>::try_into(client.info().best_number)
+        .map_err(|_| "Best block number can't be converted into BlockNumber")?;
+    let Some(blocks_to_archive_to) = best_block_number.checked_sub(confirmation_depth_k) else {
+        return Ok(0);
+    };
+
+    ArchivedSegmentsRecreator::new(kzg, chain_constants.erasure_coding_rate())?.archive_blocks(
+        client,
+        blocks_to_archive_to,
+        on_archived_segment,
+    )
+}
+
+/// Re-creates archived history from blocks stored in the database incrementally, starting with
+/// genesis block, which allows to continue re-creation as more blocks are imported.
+#[derive(Debug)]
+pub struct ArchivedSegmentsRecreator {
+    archiver: Archiver,
+    next_block_number: BlockNumber,
+}
+
+impl ArchivedSegmentsRecreator {
+    /// Create new instance that will start with genesis block, `erasure_coding_rate` is taken from
+    /// chain constants.
+    pub fn new(
+        kzg: Kzg,
+        erasure_coding_rate: (usize, usize),
+    ) -> Result<Self, ArchiverInstantiationError> {
+        Ok(Self {
+            archiver: Archiver::new(kzg, erasure_coding_rate)?,
+            next_block_number: 0,
+        })
+    }
+
+    /// Archive blocks that were not archived yet up to and including `block_number`,
+    /// `on_archived_segment` is called for every archived segment.
+    ///
+    /// Returns number of archived segments, fails if any of the blocks was already pruned.
+    pub fn archive_blocks<Block, Client, F>(
+        &mut self,
+        client: &Client,
+        block_number: BlockNumber,
+        mut on_archived_segment: F,
+    ) -> Result<u64, Box<dyn Error>>
+    where
+        Block: BlockT,
+        Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block>,
+        Client::Api: ObjectsApi<Block>,
+        F: FnMut(NewArchivedSegment) -> Result<(), Box<dyn Error>>,
+    {
+        let mut archived_segments = 0;
+
+        while self.next_block_number <= block_number {
+            let block_number_to_archive = self.next_block_number;
+            let block_hash = client
+                .hash(block_number_to_archive.into())?
+                .ok_or_else(|| format!("Block {block_number_to_archive} not found"))?;
+            let block = client
+                .block(block_hash)?
+                .ok_or_else(|| {
+                    format!("Block {block_number_to_archive} ({block_hash}) was pruned")
+                })?
+                .block;
+
+            let block_object_mappings = block_object_mappings_or_default(client, &block);
+
+            let encoded_block = if block_number_to_archive == 0 {
+                encode_genesis_block(&block)
+            } else {
+                block.encode()
+            };
+
+            self.next_block_number += 1;
+
+            for archived_segment in
+                self.archiver
+                    .add_block(encoded_block, block_object_mappings, false)
+            {
+                on_archived_segment(archived_segment)?;
+                archived_segments += 1;
+            }
+        }
+
+        Ok(archived_segments)
+    }
+}
+
 struct InitializedArchiver<Block>
 where
     Block: BlockT,
@@ -413,29 +658,216 @@ where
     encoded_block
 }
 
+/// Persist archiver checkpoint in aux storage, such that archiving can be resumed from this point
+/// on restart without re-processing already archived blocks.
+fn store_archiver_checkpoint<Block, Client>(
+    client: &Client,
+    archiver: &Archiver,
+    best_archived_block: (Block::Hash, NumberFor<Block>),
+) -> Result<(), sp_blockchain::Error>
+where
+    Block: BlockT,
+    Client: AuxStore,
+{
+    store_archiver_checkpoint_data(
+        client,
+        &ArchiverCheckpointData {
+            best_archived_block,
+            checkpoint: archiver.checkpoint(),
+        },
+    )
+}
+
+/// Persist previously created archiver checkpoint in aux storage.
+fn store_archiver_checkpoint_data<Hash, Number, Client>(
+    client: &Client,
+    archiver_checkpoint: &ArchiverCheckpointData<Hash, Number>,
+) -> Result<(), sp_blockchain::Error>
+where
+    Hash: Encode,
+    Number: Encode,
+    Client: AuxStore,
+{
+    write_archiver_checkpoint(archiver_checkpoint, |values| {
+        client.insert_aux(
+            &values
+                .iter()
+                .map(|(key, value)| (key.as_slice(), *value))
+                .collect::<Vec<_>>(),
+            &[],
+        )
+    })
+}
+
+/// Try to resume archiver from checkpoint stored in aux storage, returns `None` if there is no
+/// checkpoint or it doesn't correspond to the current state of the chain.
+fn resume_archiver_from_checkpoint<Block, Client, AS>(
+    best_block_number: NumberFor<Block>,
+    confirmation_depth_k: BlockNumber,
+    erasure_coding_rate: (usize, usize),
+    segment_headers_store: &SegmentHeadersStore<AS>,
+    subspace_link: &SubspaceLink<Block>,
+    client: &Client,
+) -> Option<(Archiver, (Block::Hash, NumberFor<Block>))>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block> + AuxStore,
+    AS: AuxStore,
+{
+    let ArchiverCheckpointData {
+        best_archived_block: (best_archived_block_hash, best_archived_block_number),
+        checkpoint,
+    } = match load_archiver_checkpoint::<_, Block::Hash, NumberFor<Block>>(client) {
+        Ok(Some(archiver_checkpoint)) => archiver_checkpoint,
+        Ok(None) => {
+            return None;
+        }
+        Err(error) => {
+            warn!(
+                target: "subspace",
+                "Failed to load archiver checkpoint, ignoring: {error}"
+            );
+            return None;
+        }
+    };
+
+    if best_archived_block_number > best_block_number
+        || client.hash(best_archived_block_number).ok().flatten() != Some(best_archived_block_hash)
+    {
+        info!(
+            target: "subspace",
+            "Archiver checkpoint at block {} ({}) is not on the canonical chain, ignoring",
+            best_archived_block_number,
+            best_archived_block_hash,
+        );
+        return None;
+    }
+
+    let expected_segment_index = segment_headers_store
+        .max_segment_index()
+        .map(|segment_index| segment_index + SegmentIndex::ONE)
+        .unwrap_or(SegmentIndex::ZERO);
+    if checkpoint.segment_index() != expected_segment_index {
+        info!(
+            target: "subspace",
+            "Archiver checkpoint expects segment {}, but next segment is {}, ignoring",
+            checkpoint.segment_index(),
+            expected_segment_index,
+        );
+        return None;
+    }
+
+    let archiver = match Archiver::from_checkpoint(
+        subspace_link.kzg().clone(),
+        erasure_coding_rate,
+        checkpoint,
+    ) {
+        Ok(archiver) => archiver,
+        Err(error) => {
+            warn!(
+                target: "subspace",
+                "Failed to resume archiver from checkpoint, ignoring: {error}"
+            );
+            return None;
+        }
+    };
+
+    // Segment headers of recently archived segments are expected to be included in blocks that
+    // were not imported yet
+    if let Some(max_segment_index) = segment_headers_store.max_segment_index() {
+        let mut segment_headers = subspace_link.segment_headers.lock();
+
+        for segment_index in (SegmentIndex::ZERO..=max_segment_index).rev() {
+            let Some(segment_header) = segment_headers_store.get_segment_header(segment_index)
+            else {
+                break;
+            };
+            let block_number_to_include = NumberFor::<Block>::from(
+                segment_header.last_archived_block().number + confirmation_depth_k,
+            ) + One::one();
+            if block_number_to_include <= best_block_number {
+                break;
+            }
+
+            // Segment headers are iterated in reverse order, hence insertion at the beginning
+            match segment_headers.get_mut(&block_number_to_include) {
+                Some(expected_segment_headers) => {
+                    expected_segment_headers.insert(0, segment_header);
+                }
+                None => {
+                    segment_headers.put(block_number_to_include, vec![segment_header]);
+                }
+            }
+        }
+    }
+
+    info!(
+        target: "subspace",
+        "Resumed archiver from checkpoint at block {}",
+        best_archived_block_number,
+    );
+
+    Some((
+        archiver,
+        (best_archived_block_hash, best_archived_block_number),
+    ))
+}
+
 fn initialize_archiver<Block, Client, AS>(
     best_block_hash: Block::Hash,
     best_block_number: NumberFor<Block>,
     segment_headers_store: &SegmentHeadersStore<AS>,
     subspace_link: &SubspaceLink<Block>,
     client: &Client,
-) -> InitializedArchiver<Block>
+) -> Result<InitializedArchiver<Block>, sp_blockchain::Error>
 where
     Block: BlockT,
     Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block> + AuxStore,
     Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
     AS: AuxStore,
 {
-    let confirmation_depth_k = get_chain_constants(client)
-        .expect("Must always be able to get chain constants")
-        .confirmation_depth_k();
+    let chain_constants =
+        get_chain_constants(client).expect("Must always be able to get chain constants");
+    let confirmation_depth_k = chain_constants.confirmation_depth_k();
+    let erasure_coding_rate = chain_constants.erasure_coding_rate();
 
-    let maybe_last_archived_block = find_last_archived_block(client, best_block_hash);
-    let have_last_segment_header = maybe_last_archived_block.is_some();
+    let maybe_resumed_archiver = resume_archiver_from_checkpoint(
+        best_block_number,
+        confirmation_depth_k,
+        erasure_coding_rate,
+        segment_headers_store,
+        subspace_link,
+        client,
+    );
+    // There is no need to search for the last archived block when resuming from checkpoint
+    let maybe_last_archived_block = if maybe_resumed_archiver.is_none() {
+        find_last_archived_block(client, best_block_hash)
+    } else {
+        None
+    };
+    let have_last_segment_header =
+        maybe_resumed_archiver.is_some() || maybe_last_archived_block.is_some();
     let mut best_archived_block = None;
+    // Last block added to the archiver resumed from checkpoint, it might be still buffered and not
+    // yet included in any segment
+    let mut checkpoint_best_archived_block_number = None;
 
     let mut archiver =
-        if let Some((last_segment_header, last_archived_block, block_object_mappings)) =
+        if let Some((archiver, (best_archived_block_hash, best_archived_block_number))) =
+            maybe_resumed_archiver
+        {
+            best_archived_block.replace((best_archived_block_hash, best_archived_block_number));
+            checkpoint_best_archived_block_number.replace(
+                TryInto::<BlockNumber>::try_into(best_archived_block_number).unwrap_or_else(|_| {
+                    panic!(
+                        "Best archived block number {best_archived_block_number} can't be \
+                        converted into BlockNumber",
+                    );
+                }),
+            );
+
+            archiver
+        } else if let Some((last_segment_header, last_archived_block, block_object_mappings)) =
             maybe_last_archived_block
         {
             // Continuing from existing initial state
@@ -461,6 +893,7 @@ where
 
             Archiver::with_initial_state(
                 subspace_link.kzg().clone(),
+                erasure_coding_rate,
                 last_segment_header,
                 &last_archived_block_encoded,
                 block_object_mappings,
@@ -469,15 +902,16 @@ where
         } else {
             info!(target: "subspace", "Starting archiving from genesis");
 
-            Archiver::new(subspace_link.kzg().clone()).expect("Incorrect parameters for archiver")
+            Archiver::new(subspace_link.kzg().clone(), erasure_coding_rate)
+                .expect("Incorrect parameters for archiver")
         };
 
     let mut older_archived_segments = Vec::new();
 
     // Process blocks since last fully archived block (or genesis) up to the current head minus K
     {
-        let blocks_to_archive_from = archiver
-            .last_archived_block_number()
+        let blocks_to_archive_from = checkpoint_best_archived_block_number
+            .or_else(|| archiver.last_archived_block_number())
             .map(|n| n + 1)
             .unwrap_or_default();
         let blocks_to_archive_to =
@@ -497,7 +931,11 @@ where
                     }
                 });
 
-        if let Some(blocks_to_archive_to) = blocks_to_archive_to {
+        // Blocks might have been archived up to the current head minus K already when resuming
+        // from checkpoint
+        if let Some(blocks_to_archive_to) = blocks_to_archive_to
+            .filter(|blocks_to_archive_to| *blocks_to_archive_to >= blocks_to_archive_from)
+        {
             info!(
                 target: "subspace",
                 "Archiving already produced blocks {}..={}",
@@ -522,17 +960,7 @@ where
                     .block;
                 let block_number_to_archive = *block.header().number();
 
-                let block_object_mappings = client
-                    .runtime_api()
-                    .validated_object_call_hashes(block_hash_to_archive)
-                    .and_then(|calls| {
-                        client.runtime_api().extract_block_object_mapping(
-                            *block.header().parent_hash(),
-                            block.clone(),
-                            calls,
-                        )
-                    })
-                    .unwrap_or_default();
+                let block_object_mappings = block_object_mappings_or_default(client, &block);
 
                 let encoded_block = if block_number_to_archive.is_zero() {
                     encode_genesis_block(&block)
@@ -578,13 +1006,13 @@ where
         }
     }
 
-    InitializedArchiver {
+    Ok(InitializedArchiver {
         confirmation_depth_k,
         archiver,
         older_archived_segments,
         best_archived_block: best_archived_block
             .expect("Must always set if there is no logical error; qed"),
-    }
+    })
 }
 
 fn finalize_block<Block, Backend, Client>(
@@ -637,7 +1065,7 @@ pub fn create_subspace_archiver<Block, Backend, Client, AS, SO>(
     client: Arc<Client>,
     sync_oracle: SubspaceSyncOracle<SO>,
     telemetry: Option<TelemetryHandle>,
-) -> impl Future<Output = ()> + Send + 'static
+) -> Result<impl Future<Output = ()> + Send + 'static, sp_blockchain::Error>
 where
     Block: BlockT,
     Backend: BackendT<Block>,
@@ -669,7 +1097,20 @@ where
         &segment_headers_store,
         subspace_link,
         client.as_ref(),
-    );
+    )?;
+
+    if !older_archived_segments.is_empty() {
+        if let Err(error) = store_archiver_checkpoint::<Block, _>(
+            client.as_ref(),
+            &archiver,
+            (best_archived_block_hash, best_archived_block_number),
+        ) {
+            warn!(
+                target: "subspace",
+                "Failed to store archiver checkpoint: {error}"
+            );
+        }
+    }
 
     let mut block_importing_notification_stream = subspace_link
         .block_importing_notification_stream
@@ -677,156 +1118,349 @@ where
     let archived_segment_notification_sender =
         subspace_link.archived_segment_notification_sender.clone();
     let segment_headers = Arc::clone(&subspace_link.segment_headers);
+    let archived_segments_finisher = ArchivedSegmentsFinisher::new()
+        .map_err(|error| sp_blockchain::Error::Application(error.into()))?;
+    // Bounded to limit the number of segments whose pieces are yet to be created
+    let (mut pending_archived_segments_sender, pending_archived_segments_receiver) =
+        mpsc::channel(0);
+
+    let archived_segment_notifications = send_archived_segment_notifications::<Block, _, _>(
+        Arc::clone(&client),
+        segment_headers_store,
+        archived_segments_finisher,
+        older_archived_segments,
+        pending_archived_segments_receiver,
+        archived_segment_notification_sender,
+    );
 
-    async move {
-        // Farmers may have not received all previous segments, send them now.
-        for archived_segment in older_archived_segments {
-            send_archived_segment_notification(
-                &archived_segment_notification_sender,
-                archived_segment,
-            )
-            .await;
-        }
+    let archiving = {
+        let client = Arc::clone(&client);
+
+        async move {
+            while let Some(BlockImportingNotification {
+                block_number,
+                // Just to be very explicit that block import shouldn't continue until segment
+                // headers are produced and, if there are any, persisted after their pieces are
+                // created in the background and delivered to subscribers
+                acknowledgement_sender,
+                ..
+            }) = block_importing_notification_stream.next().await
+            {
+                let block_number_to_archive =
+                    match block_number.checked_sub(&confirmation_depth_k.into()) {
+                        Some(block_number_to_archive) => block_number_to_archive,
+                        None => {
+                            continue;
+                        }
+                    };
+
+                if best_archived_block_number >= block_number_to_archive {
+                    // This block was already archived, skip
+                    continue;
+                }
 
-        while let Some(BlockImportingNotification {
-            block_number,
-            // Just to be very explicit that block import shouldn't continue until archiving
-            // is over
-            acknowledgement_sender: _acknowledgement_sender,
-            ..
-        }) = block_importing_notification_stream.next().await
-        {
-            let block_number_to_archive =
-                match block_number.checked_sub(&confirmation_depth_k.into()) {
-                    Some(block_number_to_archive) => block_number_to_archive,
-                    None => {
-                        continue;
-                    }
-                };
+                best_archived_block_number = block_number_to_archive;
 
-            if best_archived_block_number >= block_number_to_archive {
-                // This block was already archived, skip
-                continue;
-            }
+                let block = client
+                    .block(
+                        client
+                            .hash(block_number_to_archive)
+                            .expect("Older block by number must always exist")
+                            .expect("Older block by number must always exist"),
+                    )
+                    .expect("Older block by number must always exist")
+                    .expect("Older block by number must always exist")
+                    .block;
 
-            best_archived_block_number = block_number_to_archive;
+                let parent_block_hash = *block.header().parent_hash();
+                let block_hash_to_archive = block.hash();
 
-            let block = client
-                .block(
-                    client
-                        .hash(block_number_to_archive)
-                        .expect("Older block by number must always exist")
-                        .expect("Older block by number must always exist"),
-                )
-                .expect("Older block by number must always exist")
-                .expect("Older block by number must always exist")
-                .block;
+                debug!(
+                    target: "subspace",
+                    "Archiving block {:?} ({})",
+                    block_number_to_archive,
+                    block_hash_to_archive
+                );
+
+                if parent_block_hash != best_archived_block_hash {
+                    error!(
+                        target: "subspace",
+                        "Attempt to switch to a different fork beyond archiving depth, \
+                        can't do it: parent block hash {}, best archived block hash {}",
+                        parent_block_hash,
+                        best_archived_block_hash
+                    );
+                    return None;
+                }
 
-            let parent_block_hash = *block.header().parent_hash();
-            let block_hash_to_archive = block.hash();
+                best_archived_block_hash = block_hash_to_archive;
 
-            debug!(
-                target: "subspace",
-                "Archiving block {:?} ({})",
-                block_number_to_archive,
-                block_hash_to_archive
-            );
+                let block_object_mappings = match block_object_mappings(client.as_ref(), &block) {
+                    Ok(block_object_mappings) => block_object_mappings,
+                    Err(error) => {
+                        error!(
+                            target: "subspace",
+                            "Failed to retrieve block object mappings: {error}"
+                        );
+                        return None;
+                    }
+                };
 
-            if parent_block_hash != best_archived_block_hash {
-                error!(
+                let encoded_block = block.encode();
+                debug!(
                     target: "subspace",
-                    "Attempt to switch to a different fork beyond archiving depth, \
-                    can't do it: parent block hash {}, best archived block hash {}",
-                    parent_block_hash,
-                    best_archived_block_hash
+                    "Encoded block {} has size of {:.2} kiB",
+                    block_number_to_archive,
+                    encoded_block.len() as f32 / 1024.0
                 );
-                return;
+
+                // Only segment headers are produced here, CPU-intensive creation of pieces doesn't
+                // block import of the next block
+                let pending_archived_segments = archiver.add_block_pending(
+                    encoded_block,
+                    block_object_mappings,
+                    !sync_oracle.is_major_syncing(),
+                );
+
+                if !pending_archived_segments.is_empty() {
+                    let new_segment_headers = pending_archived_segments
+                        .iter()
+                        .map(|pending_archived_segment| *pending_archived_segment.segment_header())
+                        .collect::<Vec<_>>();
+
+                    // Segment boundary is a natural checkpoint, buffer is the smallest at this point,
+                    // checkpoint is stored once pieces of archived segments are sent to subscribers
+                    let archiver_checkpoint = ArchiverCheckpointData {
+                        best_archived_block: (best_archived_block_hash, best_archived_block_number),
+                        checkpoint: archiver.checkpoint(),
+                    };
+                    if pending_archived_segments_sender
+                        .send(PendingArchivedSegments {
+                            pending_archived_segments,
+                            archiver_checkpoint,
+                            acknowledgement_sender,
+                        })
+                        .await
+                        .is_err()
+                    {
+                        error!(
+                            target: "subspace",
+                            "Archived segments processing has stopped unexpectedly"
+                        );
+                        return None;
+                    }
+
+                    let maybe_block_number_to_finalize = {
+                        let mut segment_headers = segment_headers.lock();
+                        segment_headers.put(block_number + One::one(), new_segment_headers);
+
+                        // Skip last `FINALIZATION_DEPTH_IN_SEGMENTS` archived segments
+                        segment_headers
+                            .iter()
+                            .flat_map(|(_k, v)| v.iter().rev())
+                            .nth(FINALIZATION_DEPTH_IN_SEGMENTS)
+                            .map(|segment_header| segment_header.last_archived_block().number)
+                    };
+
+                    if let Some(block_number_to_finalize) = maybe_block_number_to_finalize {
+                        let block_hash_to_finalize = client
+                            .hash(block_number_to_finalize.into())
+                            .expect("Block about to be finalized must always exist")
+                            .expect("Block about to be finalized must always exist");
+                        finalize_block(
+                            client.as_ref(),
+                            telemetry.clone(),
+                            block_hash_to_finalize,
+                            block_number_to_finalize.into(),
+                        );
+                    }
+                }
             }
 
-            best_archived_block_hash = block_hash_to_archive;
+            Some((
+                archiver,
+                (best_archived_block_hash, best_archived_block_number),
+            ))
+        }
+    };
 
-            let block_object_mappings = match client
-                .runtime_api()
-                .validated_object_call_hashes(block_hash_to_archive)
-                .and_then(|calls| {
-                    client.runtime_api().extract_block_object_mapping(
-                        parent_block_hash,
-                        block.clone(),
-                        calls,
-                    )
-                }) {
-                Ok(block_object_mappings) => block_object_mappings,
-                Err(error) => {
+    Ok(async move {
+        let archiving = Box::pin(archiving);
+        let archived_segment_notifications = Box::pin(archived_segment_notifications);
+
+        let maybe_archiver = match future::select(archiving, archived_segment_notifications).await {
+            Either::Left((maybe_archiver, archived_segment_notifications)) => {
+                // Archived segment notifications finish once archiving is over (and sender of
+                // pending archived segments is dropped) and all pending archived segments are
+                // processed
+                if let Err(error) = archived_segment_notifications.await {
                     error!(
                         target: "subspace",
-                        "Failed to retrieve block object mappings: {error}"
+                        "Failed to process archived segments: {error}"
                     );
                     return;
                 }
-            };
-
-            let encoded_block = block.encode();
-            debug!(
-                target: "subspace",
-                "Encoded block {} has size of {:.2} kiB",
-                block_number_to_archive,
-                encoded_block.len() as f32 / 1024.0
-            );
-
-            let mut new_segment_headers = Vec::new();
-            for archived_segment in archiver.add_block(
-                encoded_block,
-                block_object_mappings,
-                !sync_oracle.is_major_syncing(),
-            ) {
-                let segment_header = archived_segment.segment_header;
 
-                if let Err(error) =
-                    segment_headers_store.add_segment_headers(slice::from_ref(&segment_header))
-                {
+                maybe_archiver
+            }
+            Either::Right((result, _archiving)) => {
+                // Archiving can't continue without pieces of archived segments being created and
+                // segment headers being persisted
+                if let Err(error) = result {
                     error!(
                         target: "subspace",
-                        "Failed to store segment headers: {error}"
+                        "Failed to process archived segments: {error}"
+                    );
+                } else {
+                    error!(
+                        target: "subspace",
+                        "Archived segments processing has stopped unexpectedly"
                     );
-                    return;
                 }
+                return;
+            }
+        };
 
-                send_archived_segment_notification(
-                    &archived_segment_notification_sender,
-                    archived_segment,
-                )
-                .await;
+        let Some((archiver, best_archived_block)) = maybe_archiver else {
+            return;
+        };
 
-                new_segment_headers.push(segment_header);
-            }
+        // Block import notifications stream ended, node is shutting down
+        if let Err(error) =
+            store_archiver_checkpoint::<Block, _>(client.as_ref(), &archiver, best_archived_block)
+        {
+            warn!(
+                target: "subspace",
+                "Failed to store archiver checkpoint on shutdown: {error}"
+            );
+        }
+    })
+}
 
-            if !new_segment_headers.is_empty() {
-                let maybe_block_number_to_finalize = {
-                    let mut segment_headers = segment_headers.lock();
-                    segment_headers.put(block_number + One::one(), new_segment_headers);
+/// Pending archived segments along with archiver checkpoint corresponding to the state right
+/// after these segments were produced
+struct PendingArchivedSegments<Block>
+where
+    Block: BlockT,
+{
+    pending_archived_segments: Vec<PendingArchivedSegment>,
+    archiver_checkpoint: ArchiverCheckpointData<Block::Hash, NumberFor<Block>>,
+    /// Import of the block that triggered creation of these segments is paused until segment
+    /// headers are persisted
+    acknowledgement_sender: mpsc::Sender<()>,
+}
 
-                    // Skip last `FINALIZATION_DEPTH_IN_SEGMENTS` archived segments
-                    segment_headers
-                        .iter()
-                        .flat_map(|(_k, v)| v.iter().rev())
-                        .nth(FINALIZATION_DEPTH_IN_SEGMENTS)
-                        .map(|segment_header| segment_header.last_archived_block().number)
-                };
+/// Create pieces of pending archived segments and send them to subscribers in order, persisting
+/// segment headers and storing archiver checkpoint afterwards.
+///
+/// Segment headers are persisted only after pieces were delivered, such that if node crashes in
+/// between, archived segments are re-created from the previous checkpoint and delivered again
+/// after restart.
+async fn send_archived_segment_notifications<Block, Client, AS>(
+    client: Arc<Client>,
+    segment_headers_store: SegmentHeadersStore<AS>,
+    mut archived_segments_finisher: ArchivedSegmentsFinisher,
+    older_archived_segments: Vec<NewArchivedSegment>,
+    mut pending_archived_segments_receiver: mpsc::Receiver<PendingArchivedSegments<Block>>,
+    archived_segment_notification_sender: SubspaceNotificationSender<ArchivedSegmentNotification>,
+) -> Result<(), sp_blockchain::Error>
+where
+    Block: BlockT,
+    Client: AuxStore,
+    AS: AuxStore,
+{
+    // Farmers may have not received all previous segments, send them now.
+    for archived_segment in older_archived_segments {
+        send_archived_segment_notification(&archived_segment_notification_sender, archived_segment)
+            .await;
+    }
 
-                if let Some(block_number_to_finalize) = maybe_block_number_to_finalize {
-                    let block_hash_to_finalize = client
-                        .hash(block_number_to_finalize.into())
-                        .expect("Block about to be finalized must always exist")
-                        .expect("Block about to be finalized must always exist");
-                    finalize_block(
-                        client.as_ref(),
-                        telemetry.clone(),
-                        block_hash_to_finalize,
-                        block_number_to_finalize.into(),
-                    );
-                }
-            }
+    while let Some(PendingArchivedSegments {
+        pending_archived_segments,
+        archiver_checkpoint,
+        acknowledgement_sender,
+    }) = pending_archived_segments_receiver.next().await
+    {
+        let mut new_segment_headers = Vec::with_capacity(pending_archived_segments.len());
+
+        for pending_archived_segment in pending_archived_segments {
+            let archived_segment = archived_segments_finisher
+                .finish(pending_archived_segment)
+                .await?;
+            new_segment_headers.push(archived_segment.segment_header);
+
+            send_archived_segment_notification(
+                &archived_segment_notification_sender,
+                archived_segment,
+            )
+            .await;
+        }
+
+        segment_headers_store.add_segment_headers(&new_segment_headers)?;
+
+        if let Err(error) = store_archiver_checkpoint_data(client.as_ref(), &archiver_checkpoint) {
+            warn!(
+                target: "subspace",
+                "Failed to store archiver checkpoint: {error}"
+            );
         }
+
+        // Block import can continue now that segment headers are persisted
+        drop(acknowledgement_sender);
+    }
+
+    Ok(())
+}
+
+/// Creates pieces of pending archived segments on a dedicated thread, such that erasure coding and
+/// witness creation don't block async executor.
+///
+/// Segments are processed one at a time since pieces need to be delivered in order anyway and
+/// creation of pieces of each segment is parallelized internally already.
+struct ArchivedSegmentsFinisher {
+    pending_archived_segments_sender:
+        mpsc::Sender<(PendingArchivedSegment, oneshot::Sender<NewArchivedSegment>)>,
+}
+
+impl ArchivedSegmentsFinisher {
+    fn new() -> io::Result<Self> {
+        let (pending_archived_segments_sender, pending_archived_segments_receiver) =
+            mpsc::channel(0);
+
+        thread::Builder::new()
+            .name("subspace-archiver".to_string())
+            .spawn(move || {
+                for (pending_archived_segment, archived_segment_sender) in
+                    executor::block_on_stream(pending_archived_segments_receiver)
+                {
+                    // Doesn't matter if receiver is gone
+                    let _ = archived_segment_sender.send(pending_archived_segment.finish());
+                }
+            })?;
+
+        Ok(Self {
+            pending_archived_segments_sender,
+        })
+    }
+
+    async fn finish(
+        &mut self,
+        pending_archived_segment: PendingArchivedSegment,
+    ) -> Result<NewArchivedSegment, sp_blockchain::Error> {
+        let (archived_segment_sender, archived_segment_receiver) = oneshot::channel();
+
+        self.pending_archived_segments_sender
+            .send((pending_archived_segment, archived_segment_sender))
+            .await
+            .map_err(|_error| {
+                sp_blockchain::Error::Application(Box::from("Archiver thread has stopped"))
+            })?;
+
+        archived_segment_receiver.await.map_err(|_canceled| {
+            sp_blockchain::Error::Application(Box::from(
+                "Archiver thread has stopped before creating pieces of archived segment",
+            ))
+        })
     }
 }
 
@@ -0,0 +1,218 @@
+use crate::archiver::{SegmentHeadersStore, SEGMENT_HEADERS_PER_PAGE};
+use codec::Encode;
+use parking_lot::Mutex;
+use sc_client_api::AuxStore;
+use std::collections::HashMap;
+use std::sync::Arc;
+use subspace_core_primitives::{
+    ArchivedBlockProgress, Blake2b256Hash, LastArchivedBlock, SegmentCommitment, SegmentHeader,
+    SegmentIndex,
+};
+
+#[derive(Default)]
+struct TestAuxStore(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+impl AuxStore for TestAuxStore {
+    fn insert_aux<
+        'a,
+        'b: 'a,
+        'c: 'a,
+        I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+        D: IntoIterator<Item = &'a &'b [u8]>,
+    >(
+        &self,
+        insert: I,
+        delete: D,
+    ) -> sp_blockchain::Result<()> {
+        let mut storage = self.0.lock();
+        for (key, value) in insert {
+            storage.insert(key.to_vec(), value.to_vec());
+        }
+        for key in delete {
+            storage.remove(*key);
+        }
+        Ok(())
+    }
+
+    fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+        Ok(self.0.lock().get(key).cloned())
+    }
+}
+
+type TestSegmentHeadersStore = SegmentHeadersStore<TestAuxStore>;
+
+fn segment_header(segment_index: u64) -> SegmentHeader {
+    SegmentHeader::V0 {
+        segment_index: SegmentIndex::from(segment_index),
+        segment_commitment: SegmentCommitment::default(),
+        prev_segment_header_hash: Blake2b256Hash::default(),
+        last_archived_block: LastArchivedBlock {
+            number: segment_index as u32,
+            archived_progress: ArchivedBlockProgress::Complete,
+        },
+    }
+}
+
+fn segment_headers(segment_indexes: impl Iterator<Item = u64>) -> Vec<SegmentHeader> {
+    segment_indexes.map(segment_header).collect()
+}
+
+fn stored_pages(aux_store: &TestAuxStore) -> usize {
+    (0..)
+        .take_while(|&page_index| {
+            aux_store
+                .get_aux(&TestSegmentHeadersStore::page_key(page_index))
+                .unwrap()
+                .is_some()
+        })
+        .count()
+}
+
+#[test]
+fn add_and_get_segment_headers() {
+    let aux_store = Arc::new(TestAuxStore::default());
+    let store = TestSegmentHeadersStore::new(Arc::clone(&aux_store)).unwrap();
+
+    assert_eq!(store.max_segment_index(), None);
+    assert_eq!(store.get_segment_header(SegmentIndex::ZERO), None);
+
+    // First segment header must have index zero
+    assert!(store.add_segment_headers(&segment_headers(1..2)).is_err());
+
+    // Add segment headers in batches that cross page boundaries
+    let segments_count = SEGMENT_HEADERS_PER_PAGE * 2 + 10;
+    let batch_size = SEGMENT_HEADERS_PER_PAGE / 3;
+    for batch_start in (0..segments_count).step_by(batch_size as usize) {
+        let batch_end = (batch_start + batch_size).min(segments_count);
+        store
+            .add_segment_headers(&segment_headers(batch_start..batch_end))
+            .unwrap();
+    }
+
+    assert_eq!(
+        store.max_segment_index(),
+        Some(SegmentIndex::from(segments_count - 1))
+    );
+    assert_eq!(stored_pages(&aux_store), 3);
+
+    // Already stored segment headers are skipped
+    store
+        .add_segment_headers(&segment_headers(segments_count - 5..segments_count + 1))
+        .unwrap();
+    assert_eq!(
+        store.max_segment_index(),
+        Some(SegmentIndex::from(segments_count))
+    );
+
+    // Gaps are not allowed
+    assert!(store
+        .add_segment_headers(&segment_headers(segments_count + 2..segments_count + 3))
+        .is_err());
+    assert_eq!(
+        store.max_segment_index(),
+        Some(SegmentIndex::from(segments_count))
+    );
+
+    // Segment headers survive re-opening of the store
+    let store = TestSegmentHeadersStore::new(Arc::clone(&aux_store)).unwrap();
+    assert_eq!(
+        store.max_segment_index(),
+        Some(SegmentIndex::from(segments_count))
+    );
+    for segment_index in 0..=segments_count {
+        assert_eq!(
+            store.get_segment_header(SegmentIndex::from(segment_index)),
+            Some(segment_header(segment_index))
+        );
+    }
+    assert_eq!(
+        store.get_segment_header(SegmentIndex::from(segments_count + 1)),
+        None
+    );
+}
+
+#[test]
+fn segment_headers_range() {
+    let store = TestSegmentHeadersStore::new(Arc::new(TestAuxStore::default())).unwrap();
+    store.add_segment_headers(&segment_headers(0..10)).unwrap();
+
+    let range = |start: u64, end: u64| SegmentIndex::from(start)..SegmentIndex::from(end);
+
+    assert_eq!(store.segment_headers(..), segment_headers(0..10));
+    assert_eq!(
+        store.segment_headers(SegmentIndex::from(3)..=SegmentIndex::from(5)),
+        segment_headers(3..6)
+    );
+    assert_eq!(store.segment_headers(range(3, 5)), segment_headers(3..5));
+    assert_eq!(
+        store.segment_headers(..SegmentIndex::from(2)),
+        segment_headers(0..2)
+    );
+    assert_eq!(
+        store.segment_headers(SegmentIndex::from(8)..),
+        segment_headers(8..10)
+    );
+    // Segment headers that are not stored yet are not included
+    assert_eq!(store.segment_headers(range(8, 20)), segment_headers(8..10));
+    assert_eq!(store.segment_headers(range(15, 20)), Vec::new());
+    assert_eq!(store.segment_headers(range(5, 5)), Vec::new());
+    assert_eq!(store.segment_headers(range(5, 3)), Vec::new());
+    assert_eq!(
+        store.segment_headers(SegmentIndex::from(9)..=SegmentIndex::from(u64::MAX)),
+        segment_headers(9..10)
+    );
+}
+
+#[test]
+fn legacy_segment_headers_migration() {
+    let aux_store = Arc::new(TestAuxStore::default());
+
+    // Legacy format stored one batch of segment headers per key
+    let segments_count = SEGMENT_HEADERS_PER_PAGE + 20;
+    let legacy_batches = [
+        0..1,
+        1..SEGMENT_HEADERS_PER_PAGE - 1,
+        SEGMENT_HEADERS_PER_PAGE - 1..SEGMENT_HEADERS_PER_PAGE + 5,
+        SEGMENT_HEADERS_PER_PAGE + 5..segments_count,
+    ];
+    let legacy_keys = (0..legacy_batches.len() as u16)
+        .map(TestSegmentHeadersStore::legacy_key)
+        .collect::<Vec<_>>();
+    {
+        let legacy_data = legacy_keys
+            .iter()
+            .zip(legacy_batches)
+            .map(|(key, batch)| (key.clone(), segment_headers(batch).encode()))
+            .collect::<Vec<_>>();
+        let insert_data = legacy_data
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+            .collect::<Vec<_>>();
+        aux_store.insert_aux(&insert_data, &[]).unwrap();
+    }
+
+    let store = TestSegmentHeadersStore::new(Arc::clone(&aux_store)).unwrap();
+    assert_eq!(
+        store.segment_headers(..),
+        segment_headers(0..segments_count)
+    );
+
+    // Legacy keys are replaced with pages
+    for legacy_key in &legacy_keys {
+        assert_eq!(aux_store.get_aux(legacy_key).unwrap(), None);
+    }
+    assert_eq!(stored_pages(&aux_store), 2);
+
+    // New segment headers are appended to migrated ones
+    store
+        .add_segment_headers(&segment_headers(segments_count..segments_count + 1))
+        .unwrap();
+
+    // Re-opening migrated store loads pages only
+    let store = TestSegmentHeadersStore::new(Arc::clone(&aux_store)).unwrap();
+    assert_eq!(
+        store.segment_headers(..),
+        segment_headers(0..segments_count + 1)
+    );
+    assert_eq!(stored_pages(&aux_store), 2);
+}
@@ -15,12 +15,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-//! Schema for Subspace block weight in the aux-db.
+//! Schema for Subspace block weight, chain constants and archiver checkpoint in the aux-db.
 
 use codec::{Decode, Encode};
 use sc_client_api::backend::AuxStore;
 use sp_blockchain::{Error as ClientError, Result as ClientResult};
 use sp_consensus_subspace::ChainConstants;
+use subspace_archiving::archiver::ArchiverCheckpoint;
 use subspace_core_primitives::BlockWeight;
 
 fn load_decode<B, T>(backend: &B, key: &[u8]) -> ClientResult<Option<T>>
@@ -86,3 +87,43 @@ where
 {
     load_decode(backend, chain_constants_key().as_slice())
 }
+
+/// Archiver checkpoint along with the best archived block it corresponds to.
+#[derive(Debug, Encode, Decode)]
+pub(crate) struct ArchiverCheckpointData<Hash, Number> {
+    /// Hash and number of the last block that was added to the archiver
+    pub(crate) best_archived_block: (Hash, Number),
+    /// Internal state of the archiver
+    pub(crate) checkpoint: ArchiverCheckpoint,
+}
+
+/// The aux storage key used to store the archiver checkpoint.
+fn archiver_checkpoint_key() -> Vec<u8> {
+    b"archiver_checkpoint".encode()
+}
+
+/// Write archiver checkpoint to aux storage.
+pub(crate) fn write_archiver_checkpoint<Hash, Number, F, R>(
+    archiver_checkpoint: &ArchiverCheckpointData<Hash, Number>,
+    write_aux: F,
+) -> R
+where
+    Hash: Encode,
+    Number: Encode,
+    F: FnOnce(&[(Vec<u8>, &[u8])]) -> R,
+{
+    let key = archiver_checkpoint_key();
+    archiver_checkpoint.using_encoded(|s| write_aux(&[(key, s)]))
+}
+
+/// Load archiver checkpoint.
+pub(crate) fn load_archiver_checkpoint<Backend, Hash, Number>(
+    backend: &Backend,
+) -> ClientResult<Option<ArchiverCheckpointData<Hash, Number>>>
+where
+    Backend: AuxStore,
+    Hash: Decode,
+    Number: Decode,
+{
+    load_decode(backend, archiver_checkpoint_key().as_slice())
+}
@@ -0,0 +1,201 @@
+// Copyright (C) 2023 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Equivocation detection for farmers connected to this node.
+//!
+//! A misconfigured farmer that farms the same plot more than once submits solutions with the same
+//! public key multiple times for the same slot. Blocks produced with such solutions are an
+//! equivocation that is reported and punished on chain, so duplicates are refused by this node
+//! before they get a chance to be signed.
+
+use log::warn;
+use parking_lot::Mutex;
+use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use sp_consensus_slots::Slot;
+use sp_consensus_subspace::FarmerPublicKey;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// Number of most recent slots to track farmers for.
+const TRACKED_SLOTS: u64 = 128;
+
+/// Errors returned when farmer equivocation is detected.
+#[derive(Debug, thiserror::Error)]
+pub enum FarmerEquivocationError {
+    /// Farmer already submitted solutions for this slot
+    #[error(
+        "Farmer {public_key} already submitted solutions for slot {slot}, the same plot is \
+        likely farmed more than once"
+    )]
+    DuplicateSolutions {
+        /// Slot of the solutions
+        slot: Slot,
+        /// Farmer public key
+        public_key: FarmerPublicKey,
+    },
+    /// Block at this slot was already claimed by the farmer
+    #[error("Block at slot {slot} was already claimed by farmer {public_key}")]
+    DuplicateBlockClaim {
+        /// Slot of the block
+        slot: Slot,
+        /// Farmer public key
+        public_key: FarmerPublicKey,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Farmers that submitted solutions, by slot
+    solutions: BTreeMap<Slot, HashSet<FarmerPublicKey>>,
+    /// Farmers that claimed blocks produced by this node, by slot
+    block_claims: BTreeMap<Slot, HashSet<FarmerPublicKey>>,
+}
+
+/// Tracks `(public_key, slot)` of the solutions submitted by the farmers connected to this node
+/// and of the blocks produced by this node, detecting duplicates.
+#[derive(Clone)]
+pub struct FarmerEquivocationTracker {
+    inner: Arc<Mutex<Inner>>,
+    equivocations: Option<Counter<U64>>,
+}
+
+impl FarmerEquivocationTracker {
+    /// Create new instance, registering metrics in provided registry (if any).
+    pub fn new(registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+        let equivocations = registry
+            .map(|registry| {
+                register(
+                    Counter::new(
+                        "subspace_farmer_equivocations",
+                        "Number of solutions and block claims refused due to farmer equivocation",
+                    )?,
+                    registry,
+                )
+            })
+            .transpose()?;
+
+        Ok(Self {
+            inner: Arc::default(),
+            equivocations,
+        })
+    }
+
+    /// Note solutions submitted by the farmer for the slot, returns an error if the farmer already
+    /// submitted solutions for the same slot before.
+    ///
+    /// NOTE: Needs to be called once per batch of solutions, single farmer may submit multiple
+    /// solutions at once.
+    pub fn note_solutions(
+        &self,
+        slot: Slot,
+        public_key: &FarmerPublicKey,
+    ) -> Result<(), FarmerEquivocationError> {
+        if note(&mut self.inner.lock().solutions, slot, public_key) {
+            return Ok(());
+        }
+
+        self.on_equivocation(FarmerEquivocationError::DuplicateSolutions {
+            slot,
+            public_key: public_key.clone(),
+        })
+    }
+
+    /// Note block claimed by the farmer for the slot, returns an error if the block at the same
+    /// slot was already claimed by the farmer before.
+    pub fn note_block_claim(
+        &self,
+        slot: Slot,
+        public_key: &FarmerPublicKey,
+    ) -> Result<(), FarmerEquivocationError> {
+        if note(&mut self.inner.lock().block_claims, slot, public_key) {
+            return Ok(());
+        }
+
+        self.on_equivocation(FarmerEquivocationError::DuplicateBlockClaim {
+            slot,
+            public_key: public_key.clone(),
+        })
+    }
+
+    fn on_equivocation(
+        &self,
+        error: FarmerEquivocationError,
+    ) -> Result<(), FarmerEquivocationError> {
+        warn!(
+            target: "subspace",
+            "Farmer equivocation detected, check farmer configuration: {error}"
+        );
+        if let Some(equivocations) = &self.equivocations {
+            equivocations.inc();
+        }
+
+        Err(error)
+    }
+}
+
+/// Returns `false` if farmer was already tracked for the slot.
+fn note(
+    tracked: &mut BTreeMap<Slot, HashSet<FarmerPublicKey>>,
+    slot: Slot,
+    public_key: &FarmerPublicKey,
+) -> bool {
+    let inserted = tracked.entry(slot).or_default().insert(public_key.clone());
+
+    // Forget about slots that are too old
+    if let Some(&newest_slot) = tracked.keys().next_back() {
+        let oldest_slot = u64::from(newest_slot).saturating_sub(TRACKED_SLOTS);
+        tracked.retain(|&tracked_slot, _public_keys| u64::from(tracked_slot) > oldest_slot);
+    }
+
+    inserted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FarmerEquivocationError, FarmerEquivocationTracker, TRACKED_SLOTS};
+    use sp_consensus_slots::Slot;
+    use sp_consensus_subspace::FarmerPublicKey;
+    use sp_core::crypto::ByteArray;
+
+    #[test]
+    fn duplicates_are_refused() {
+        let tracker = FarmerEquivocationTracker::new(None).unwrap();
+        let alice = FarmerPublicKey::from_slice(&[1; 32]).unwrap();
+        let bob = FarmerPublicKey::from_slice(&[2; 32]).unwrap();
+        let slot = Slot::from(1_000);
+
+        assert!(tracker.note_solutions(slot, &alice).is_ok());
+        assert!(tracker.note_solutions(slot, &bob).is_ok());
+        assert!(tracker.note_solutions(slot + Slot::from(1), &alice).is_ok());
+        assert!(matches!(
+            tracker.note_solutions(slot, &alice),
+            Err(FarmerEquivocationError::DuplicateSolutions { .. })
+        ));
+
+        // Block claims are tracked separately from solutions
+        assert!(tracker.note_block_claim(slot, &alice).is_ok());
+        assert!(matches!(
+            tracker.note_block_claim(slot, &alice),
+            Err(FarmerEquivocationError::DuplicateBlockClaim { .. })
+        ));
+
+        // Old slots are forgotten
+        assert!(tracker
+            .note_solutions(slot + Slot::from(TRACKED_SLOTS + 1), &alice)
+            .is_ok());
+        assert!(tracker.note_solutions(slot, &alice).is_ok());
+    }
+}
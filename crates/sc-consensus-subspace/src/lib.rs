@@ -22,6 +22,7 @@
 
 pub mod archiver;
 pub mod aux_schema;
+pub mod farmer_equivocation;
 pub mod import_queue;
 pub mod notification;
 mod slot_worker;
@@ -29,6 +30,7 @@ mod slot_worker;
 mod tests;
 
 use crate::archiver::{SegmentHeadersStore, FINALIZATION_DEPTH_IN_SEGMENTS};
+use crate::farmer_equivocation::FarmerEquivocationTracker;
 use crate::import_queue::VerificationError;
 use crate::notification::{SubspaceNotificationSender, SubspaceNotificationStream};
 use crate::slot_worker::SubspaceSlotWorker;
@@ -405,6 +407,9 @@ where
     /// Will be used when sending equivocation reports and votes.
     pub offchain_tx_pool_factory: OffchainTransactionPoolFactory<Block>,
 
+    /// Tracker of equivocation by the farmers connected to this node, shared with RPC
+    pub farmer_equivocation_tracker: FarmerEquivocationTracker,
+
     /// Proof of time verifier
     #[cfg(feature = "pot")]
     pub pot_verifier: PotVerifier,
@@ -432,6 +437,7 @@ pub fn start_subspace<PosTable, Block, Client, SC, E, I, SO, CIDP, BS, L, AS, Er
         max_block_proposal_slot_portion,
         telemetry,
         offchain_tx_pool_factory,
+        farmer_equivocation_tracker,
         #[cfg(feature = "pot")]
         pot_verifier,
         #[cfg(feature = "pot")]
@@ -481,6 +487,7 @@ where
         chain_constants: get_chain_constants(client.as_ref())
             .map_err(|error| sp_consensus::Error::Other(error.into()))?,
         segment_headers_store,
+        farmer_equivocation_tracker,
         #[cfg(feature = "pot")]
         pending_solutions: Default::default(),
         #[cfg(feature = "pot")]
@@ -83,7 +83,7 @@ impl<T: Clone + Send + Sync + fmt::Debug + 'static> SubspaceNotificationStream<T
 }
 
 /// Creates a new pair of receiver and sender of notifications.
-pub(crate) fn channel<T>(
+pub fn channel<T>(
     stream_name: &'static str,
 ) -> (SubspaceNotificationSender<T>, SubspaceNotificationStream<T>)
 where
@@ -16,6 +16,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::archiver::SegmentHeadersStore;
+use crate::farmer_equivocation::FarmerEquivocationTracker;
 use crate::{
     BlockImportingNotification, NewSlotInfo, NewSlotNotification, RewardSigningNotification,
     SubspaceLink,
@@ -132,6 +133,7 @@ where
     pub(crate) offchain_tx_pool_factory: OffchainTransactionPoolFactory<Block>,
     pub(super) chain_constants: ChainConstants,
     pub(super) segment_headers_store: SegmentHeadersStore<AS>,
+    pub(super) farmer_equivocation_tracker: FarmerEquivocationTracker,
     /// Solution receivers for challenges that were sent to farmers and expected to be received
     /// eventually
     #[cfg(feature = "pot")]
@@ -435,8 +437,9 @@ where
                 }
             }
 
-            // TODO: We need also need to check for equivocation of farmers connected to *this node*
-            //  during block import, currently farmers connected to this node are considered trusted
+            // NOTE: Equivocation of farmers connected to *this node* is checked when solutions are
+            //  submitted and when block is claimed below, otherwise farmers connected to this node
+            //  are considered trusted
             if runtime_api
                 .is_in_block_list(parent_hash, &solution.public_key)
                 .ok()?
@@ -525,6 +528,16 @@ where
                     // If solution is of high enough quality and block pre-digest wasn't produced yet,
                     // block reward is claimed
                     if maybe_pre_digest.is_none() && solution_distance <= solution_range / 2 {
+                        if self
+                            .farmer_equivocation_tracker
+                            .note_block_claim(slot, &solution.public_key)
+                            .is_err()
+                        {
+                            // Warning was already logged by the tracker, producing another block
+                            // at this slot would be an equivocation
+                            continue;
+                        }
+
                         info!(target: "subspace", "🚜 Claimed block at slot {slot}");
                         maybe_pre_digest.replace(PreDigest::V0 {
                             slot,
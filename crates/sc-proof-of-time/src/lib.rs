@@ -80,7 +80,10 @@ pub async fn start_slot_worker<Block, Client, SC, Worker, SO, CIDP>(
 
     let mut maybe_last_claimed_slot = None;
 
-    while let Some(PotSlotInfo { slot, checkpoints }) = slot_info_stream.next().await {
+    while let Some(PotSlotInfo {
+        slot, checkpoints, ..
+    }) = slot_info_stream.next().await
+    {
         worker.0.on_proof(slot, checkpoints);
 
         if sync_oracle.is_major_syncing() {
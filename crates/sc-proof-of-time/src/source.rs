@@ -1,7 +1,9 @@
+pub mod external_timekeeper;
 pub mod gossip;
 mod state;
 mod timekeeper;
 
+use crate::source::external_timekeeper::{ExternalTimekeeperClient, ExternalTimekeeperConfig};
 use crate::source::gossip::{GossipProof, PotGossipWorker, ToGossipMessage};
 use crate::source::state::{NextSlotInput, PotState};
 use crate::source::timekeeper::{run_timekeeper, TimekeeperProof};
@@ -12,6 +14,7 @@ use futures::{select, StreamExt};
 use sc_client_api::BlockchainEvents;
 use sc_network::PeerId;
 use sc_network_gossip::{Network as GossipNetwork, Syncing as GossipSyncing};
+use sc_utils::notification::{NotificationSender, NotificationStream, TracingKeyStr};
 use sp_api::{ApiError, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_consensus::SyncOracle;
@@ -31,24 +34,29 @@ use sp_runtime::traits::Header as HeaderT;
 #[cfg(feature = "pot")]
 use sp_runtime::traits::Zero;
 use std::marker::PhantomData;
-#[cfg(not(feature = "pot"))]
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::thread;
-use subspace_core_primitives::PotCheckpoints;
+use subspace_core_primitives::{PotCheckpoints, PotSeed};
 #[cfg(feature = "pot")]
 use tracing::warn;
 use tracing::{debug, error};
 
 const LOCAL_PROOFS_CHANNEL_CAPACITY: usize = 10;
+const EXTERNAL_TIMEKEEPER_PROOFS_CHANNEL_CAPACITY: usize = 10;
 const SLOTS_CHANNEL_CAPACITY: usize = 10;
 const GOSSIP_OUTGOING_CHANNEL_CAPACITY: usize = 10;
 const GOSSIP_INCOMING_CHANNEL_CAPACITY: usize = 10;
 
 /// Proof of time slot information
+#[derive(Debug, Copy, Clone)]
 pub struct PotSlotInfo {
     /// Slot number
     pub slot: Slot,
+    /// Proof of time seed
+    pub seed: PotSeed,
+    /// Iterations per slot
+    pub slot_iterations: NonZeroU32,
     /// Proof of time checkpoints
     pub checkpoints: PotCheckpoints,
 }
@@ -57,20 +65,37 @@ pub struct PotSlotInfo {
 #[derive(Debug, Deref, DerefMut)]
 pub struct PotSlotInfoStream(mpsc::Receiver<PotSlotInfo>);
 
+/// Tracing key for proof of time slot info notifications
+#[derive(Clone)]
+pub struct PotSlotInfoTracingKey;
+
+impl TracingKeyStr for PotSlotInfoTracingKey {
+    const TRACING_KEY: &'static str = "mpsc_pot_slot_info_notification_stream";
+}
+
+/// Notification stream with proof of time slots, unlike [`PotSlotInfoStream`] it can have many
+/// subscribers and is meant for observers like RPC
+pub type PotSlotInfoNotificationStream = NotificationStream<PotSlotInfo, PotSlotInfoTracingKey>;
+
 /// Worker producing proofs of time.
 ///
 /// Depending on configuration may produce proofs of time locally, send/receive via gossip and keep
 /// up to day with blockchain reorgs.
-#[derive(Debug)]
 #[must_use = "Proof of time source doesn't do anything unless run() method is called"]
 pub struct PotSourceWorker<Block, Client> {
     client: Arc<Client>,
     #[cfg(feature = "pot")]
     chain_constants: ChainConstants,
     timekeeper_proofs_receiver: mpsc::Receiver<TimekeeperProof>,
+    external_timekeeper_proofs_receiver: mpsc::Receiver<TimekeeperProof>,
+    // Only used for notifications about block imports, but needs to be kept around regardless
+    #[cfg_attr(not(feature = "pot"), allow(dead_code))]
+    external_timekeeper_client: Option<ExternalTimekeeperClient>,
     to_gossip_sender: mpsc::Sender<ToGossipMessage>,
     from_gossip_receiver: mpsc::Receiver<(PeerId, GossipProof)>,
     slot_sender: mpsc::Sender<PotSlotInfo>,
+    slot_info_notification_sender: NotificationSender<PotSlotInfo>,
+    slot_info_notification_stream: PotSlotInfoNotificationStream,
     state: Arc<PotState>,
     _block: PhantomData<Block>,
 }
@@ -83,6 +108,7 @@ where
 {
     pub fn new<Network, GossipSync, SO>(
         is_timekeeper: bool,
+        external_timekeeper: Option<ExternalTimekeeperConfig>,
         client: Arc<Client>,
         pot_verifier: PotVerifier,
         network: Network,
@@ -175,6 +201,18 @@ where
                 .expect("Thread creation must not panic");
         }
 
+        let (external_timekeeper_proofs_sender, external_timekeeper_proofs_receiver) =
+            mpsc::channel(EXTERNAL_TIMEKEEPER_PROOFS_CHANNEL_CAPACITY);
+        let external_timekeeper_client = external_timekeeper.map(|config| {
+            ExternalTimekeeperClient::start(
+                config,
+                Arc::clone(&state),
+                pot_verifier.clone(),
+                external_timekeeper_proofs_sender,
+            )
+            .expect("Thread creation must not panic")
+        });
+
         let (to_gossip_sender, to_gossip_receiver) =
             mpsc::channel(GOSSIP_OUTGOING_CHANNEL_CAPACITY);
         let (from_gossip_sender, from_gossip_receiver) =
@@ -189,14 +227,21 @@ where
             sync_oracle,
         );
 
+        let (slot_info_notification_sender, slot_info_notification_stream) =
+            PotSlotInfoNotificationStream::channel();
+
         let source_worker = Self {
             client,
             #[cfg(feature = "pot")]
             chain_constants,
             timekeeper_proofs_receiver,
+            external_timekeeper_proofs_receiver,
+            external_timekeeper_client,
             to_gossip_sender,
             from_gossip_receiver,
             slot_sender,
+            slot_info_notification_sender,
+            slot_info_notification_stream,
             state,
             _block: PhantomData,
         };
@@ -206,6 +251,11 @@ where
         Ok((source_worker, gossip_worker, pot_slot_info_stream))
     }
 
+    /// Notification stream with proof of time slots produced or received by this worker
+    pub fn slot_info_notification_stream(&self) -> PotSlotInfoNotificationStream {
+        self.slot_info_notification_stream.clone()
+    }
+
     /// Run proof of time source
     pub async fn run(mut self) {
         let mut import_notification_stream = self.client.import_notification_stream();
@@ -216,6 +266,10 @@ where
                 timekeeper_proof = self.timekeeper_proofs_receiver.select_next_some() => {
                     self.handle_timekeeper_proof(timekeeper_proof);
                 }
+                // Proofs received from external timekeeper, already verified
+                external_timekeeper_proof = self.external_timekeeper_proofs_receiver.select_next_some() => {
+                    self.handle_external_timekeeper_proof(external_timekeeper_proof);
+                }
                 // List of blocks that the client has finalized.
                 maybe_gossip_proof = self.from_gossip_receiver.next() => {
                     if let Some((sender, gossip_proof)) = maybe_gossip_proof {
@@ -272,9 +326,36 @@ where
             );
         }
 
-        // We don't care if block production is too slow or block production is not enabled on this
-        // node at all
-        let _ = self.slot_sender.try_send(PotSlotInfo { slot, checkpoints });
+        self.send_slot_info(PotSlotInfo {
+            slot,
+            seed,
+            slot_iterations,
+            checkpoints,
+        });
+    }
+
+    fn handle_external_timekeeper_proof(&mut self, proof: TimekeeperProof) {
+        let expected_next_slot_input = NextSlotInput {
+            slot: proof.slot,
+            slot_iterations: proof.slot_iterations,
+            seed: proof.seed,
+        };
+
+        // Proof might have been received via gossip already or might be on a different PoT chain
+        if self
+            .state
+            .try_extend(
+                expected_next_slot_input,
+                proof.slot,
+                proof.checkpoints.output(),
+                #[cfg(feature = "pot")]
+                None,
+            )
+            .is_ok()
+        {
+            // From this point on it is handled the same way as local timekeeper proof
+            self.handle_timekeeper_proof(proof);
+        }
     }
 
     // TODO: Follow both verified and unverified checkpoints to start secondary timekeeper ASAP in
@@ -293,10 +374,10 @@ where
             #[cfg(feature = "pot")]
             None,
         ) {
-            // We don't care if block production is too slow or block production is not enabled on
-            // this node at all
-            let _ = self.slot_sender.try_send(PotSlotInfo {
+            self.send_slot_info(PotSlotInfo {
                 slot: proof.slot,
+                seed: proof.seed,
+                slot_iterations: proof.slot_iterations,
                 checkpoints: proof.checkpoints,
             });
 
@@ -314,6 +395,16 @@ where
         }
     }
 
+    fn send_slot_info(&mut self, slot_info: PotSlotInfo) {
+        // We don't care if block production is too slow or block production is not enabled on this
+        // node at all
+        let _ = self.slot_sender.try_send(slot_info);
+
+        let _ = self
+            .slot_info_notification_sender
+            .notify(|| Ok::<_, ()>(slot_info));
+    }
+
     #[cfg(not(feature = "pot"))]
     fn handle_block_import_notification(
         &mut self,
@@ -354,6 +445,14 @@ where
             .pot_info()
             .future_proof_of_time();
 
+        if let Some(external_timekeeper_client) = &self.external_timekeeper_client {
+            external_timekeeper_client.block_import(
+                best_slot,
+                best_proof,
+                subspace_digest_items.pot_parameters_change,
+            );
+        }
+
         // This will do one of 3 things depending on circumstances:
         // * if block import is ahead of timekeeper and gossip, it will update next slot input
         // * if block import is on a different PoT chain, it will update next slot input to the
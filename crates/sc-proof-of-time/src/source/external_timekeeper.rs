@@ -0,0 +1,819 @@
+//! External timekeeper.
+//!
+//! Proving proof of time requires a fast dedicated CPU core. Instead of running timekeeper in every
+//! node, a standalone process can prove on one machine and stream proofs to multiple nodes over a
+//! simple local TCP protocol.
+//!
+//! Protocol consists of length-prefixed SCALE-encoded messages. Upon connection node and timekeeper
+//! exchange protocol version and random nonces and prove the knowledge of a pre-shared secret to
+//! each other by sending keyed hash of the nonce received from the other side. After that node
+//! sends its current proof of time state and subsequent block imports, while timekeeper sends
+//! proofs. Wire format doesn't depend on enabled features of the crate.
+//!
+//! Proofs received from external timekeeper are verified by nodes just like proofs received via
+//! gossip. Gossip keeps working independently, so nodes fall back to it if timekeeper disappears.
+
+use crate::source::gossip::GossipProof;
+use crate::source::state::{NextSlotInput, PotState};
+use crate::source::timekeeper::{run_timekeeper, TimekeeperProof};
+use crate::verifier::PotVerifier;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::{SinkExt, StreamExt};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use parking_lot::Mutex;
+use sp_consensus_slots::Slot;
+#[cfg(feature = "pot")]
+use sp_consensus_subspace::PotParametersChange;
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc};
+use std::time::Duration;
+use std::{fmt, fs, io, thread};
+use subspace_core_primitives::{Blake3Hash, PotOutput, PotSeed};
+use tracing::{debug, error, info, warn};
+
+#[cfg(test)]
+mod tests;
+
+/// Version of the protocol, must match on both sides
+const PROTOCOL_VERSION: u8 = 1;
+/// Size of the secret shared between external timekeeper and nodes
+const SECRET_SIZE: usize = 32;
+/// Size of nonce used during authentication
+const NONCE_SIZE: usize = 32;
+/// Max size of encoded message, messages are small, so this is just a sanity limit
+const MAX_MESSAGE_SIZE: u32 = 4 * 1024;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Timeout for writing a message, such that connection with unresponsive side is not stuck forever
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Max number of nodes connected to external timekeeper at the same time, each connection uses two
+/// threads
+const MAX_NODE_CONNECTIONS: usize = 32;
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+const PROOFS_CHANNEL_CAPACITY: usize = 10;
+const TO_TIMEKEEPER_CHANNEL_CAPACITY: usize = 10;
+const VERIFIER_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1000).expect("Not zero; qed");
+
+/// Secret shared between external timekeeper and nodes, used for mutual authentication
+#[derive(Clone)]
+pub struct ExternalTimekeeperSecret([u8; SECRET_SIZE]);
+
+impl fmt::Debug for ExternalTimekeeperSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExternalTimekeeperSecret(..)")
+    }
+}
+
+impl FromStr for ExternalTimekeeperSecret {
+    type Err = hex::FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut secret = [0; SECRET_SIZE];
+        hex::decode_to_slice(s.trim(), &mut secret)?;
+
+        Ok(Self(secret))
+    }
+}
+
+impl ExternalTimekeeperSecret {
+    /// Generate new random secret
+    pub fn generate() -> Self {
+        Self(rand::random())
+    }
+
+    /// Read secret in hex format from a file
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Secret in hex format
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    fn authentication_proof(
+        &self,
+        role: Role,
+        nonce: &[u8; NONCE_SIZE],
+        genesis_seed: &PotSeed,
+    ) -> blake3::Hash {
+        let mut hasher = blake3::Hasher::new_keyed(&self.0);
+        hasher.update(&[PROTOCOL_VERSION, role as u8]);
+        hasher.update(nonce);
+        hasher.update(genesis_seed.as_slice());
+        hasher.finalize()
+    }
+}
+
+/// External timekeeper configuration of the node
+#[derive(Debug, Clone)]
+pub struct ExternalTimekeeperConfig {
+    /// Address of external timekeeper
+    pub address: SocketAddr,
+    /// Secret shared with external timekeeper
+    pub secret: ExternalTimekeeperSecret,
+}
+
+/// Side of the connection that proves knowledge of the secret, included in authentication proof
+/// such that proof of one side can't be reflected back as a proof of the other side
+#[derive(Debug, Copy, Clone)]
+#[repr(u8)]
+enum Role {
+    Node = 0,
+    Timekeeper = 1,
+}
+
+#[derive(Debug, Encode, Decode)]
+struct Hello {
+    version: u8,
+    genesis_seed: PotSeed,
+    nonce: [u8; NONCE_SIZE],
+}
+
+impl Hello {
+    fn new(genesis_seed: PotSeed, nonce: [u8; NONCE_SIZE]) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            genesis_seed,
+            nonce,
+        }
+    }
+
+    fn check_version(&self) -> io::Result<()> {
+        if self.version != PROTOCOL_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Protocol version {} doesn't match expected {PROTOCOL_VERSION}",
+                    self.version
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Encode, Decode)]
+struct Authentication {
+    proof: [u8; blake3::OUT_LEN],
+}
+
+/// Change of proof of time parameters, mirrors `PotParametersChange` such that wire format is the
+/// same regardless of `pot` feature
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode)]
+struct ParametersChange {
+    slot: Slot,
+    slot_iterations: NonZeroU32,
+    entropy: Blake3Hash,
+}
+
+#[cfg(feature = "pot")]
+impl From<PotParametersChange> for ParametersChange {
+    fn from(parameters_change: PotParametersChange) -> Self {
+        let PotParametersChange {
+            slot,
+            slot_iterations,
+            entropy,
+        } = parameters_change;
+
+        Self {
+            slot,
+            slot_iterations,
+            entropy,
+        }
+    }
+}
+
+#[cfg(feature = "pot")]
+impl From<ParametersChange> for PotParametersChange {
+    fn from(parameters_change: ParametersChange) -> Self {
+        let ParametersChange {
+            slot,
+            slot_iterations,
+            entropy,
+        } = parameters_change;
+
+        Self {
+            slot,
+            slot_iterations,
+            entropy,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Encode, Decode)]
+enum ToTimekeeperMessage {
+    /// Proof of time state of the node, used by timekeeper to start proving
+    State {
+        next_slot_input: NextSlotInput,
+        parameters_change: Option<ParametersChange>,
+    },
+    /// Block was imported by the node, used by timekeeper to follow the chain
+    BlockImport {
+        best_slot: Slot,
+        best_output: PotOutput,
+        parameters_change: Option<ParametersChange>,
+    },
+}
+
+#[derive(Debug, Encode, Decode)]
+enum FromTimekeeperMessage {
+    /// New proof of time
+    Proof(GossipProof),
+}
+
+fn write_message<W, T>(stream: &mut W, message: &T) -> io::Result<()>
+where
+    W: Write,
+    T: Encode,
+{
+    let encoded_message = message.encode();
+    stream.write_all(&(encoded_message.len() as u32).to_le_bytes())?;
+    stream.write_all(&encoded_message)?;
+    stream.flush()
+}
+
+fn read_message<R, T>(stream: &mut R) -> io::Result<T>
+where
+    R: Read,
+    T: Decode,
+{
+    let mut message_size = [0; 4];
+    stream.read_exact(&mut message_size)?;
+    let message_size = u32::from_le_bytes(message_size);
+    if message_size > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Message size {message_size} exceeds limit {MAX_MESSAGE_SIZE}"),
+        ));
+    }
+
+    let mut encoded_message = vec![0; message_size as usize];
+    stream.read_exact(&mut encoded_message)?;
+
+    T::decode_all(&mut encoded_message.as_slice())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+fn verify_authentication(
+    stream: &mut TcpStream,
+    secret: &ExternalTimekeeperSecret,
+    role: Role,
+    nonce: &[u8; NONCE_SIZE],
+    genesis_seed: &PotSeed,
+) -> io::Result<()> {
+    let Authentication { proof } = read_message(stream)?;
+
+    // Comparison of hashes is constant-time
+    if blake3::Hash::from(proof) != secret.authentication_proof(role, nonce, genesis_seed) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Authentication failed, secret doesn't match",
+        ));
+    }
+
+    Ok(())
+}
+
+fn send_authentication(
+    stream: &mut TcpStream,
+    secret: &ExternalTimekeeperSecret,
+    role: Role,
+    nonce: &[u8; NONCE_SIZE],
+    genesis_seed: &PotSeed,
+) -> io::Result<()> {
+    write_message(
+        stream,
+        &Authentication {
+            proof: *secret
+                .authentication_proof(role, nonce, genesis_seed)
+                .as_bytes(),
+        },
+    )
+}
+
+/// Run external timekeeper that accepts connections from nodes on `listen_address`.
+///
+/// Proving starts once the first node provides its proof of time state, after which block imports
+/// of all connected nodes are followed to stay on the correct chain. All nodes must belong to the
+/// same chain as the first one.
+///
+/// Blocks until listener fails.
+pub fn run_external_timekeeper(
+    listen_address: SocketAddr,
+    secret: ExternalTimekeeperSecret,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(listen_address)?;
+    info!(address = %listener.local_addr()?, "External timekeeper is listening");
+
+    serve(listener, secret)
+}
+
+fn serve(listener: TcpListener, secret: ExternalTimekeeperSecret) -> io::Result<()> {
+    let timekeeper = Arc::new(ExternalTimekeeper {
+        secret,
+        inner: Mutex::default(),
+        proofs_senders: Arc::default(),
+    });
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    for maybe_stream in listener.incoming() {
+        let stream = match maybe_stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                warn!(%error, "Failed to accept connection");
+                continue;
+            }
+        };
+        let Some(connection_slot) = ConnectionSlot::try_acquire(&connections) else {
+            debug!(
+                peer_address = ?stream.peer_addr().ok(),
+                "Too many node connections, dropping new connection"
+            );
+            continue;
+        };
+        let timekeeper = Arc::clone(&timekeeper);
+
+        thread::Builder::new()
+            .name("timekeeper-node".to_string())
+            .spawn(move || {
+                let _connection_slot = connection_slot;
+                let peer_address = stream.peer_addr().ok();
+                if let Err(error) = timekeeper.handle_connection(stream) {
+                    debug!(%error, ?peer_address, "Node connection closed");
+                }
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Slot for node connection, released on drop
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl ConnectionSlot {
+    fn try_acquire(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |connections| {
+                (connections < MAX_NODE_CONNECTIONS).then_some(connections + 1)
+            })
+            .ok()
+            .map(|_| Self(Arc::clone(connections)))
+    }
+}
+
+#[derive(Default)]
+struct ExternalTimekeeperInner {
+    genesis_seed: Option<PotSeed>,
+    state: Option<Arc<PotState>>,
+}
+
+struct ExternalTimekeeper {
+    secret: ExternalTimekeeperSecret,
+    inner: Mutex<ExternalTimekeeperInner>,
+    proofs_senders: Arc<Mutex<Vec<std_mpsc::SyncSender<GossipProof>>>>,
+}
+
+impl ExternalTimekeeper {
+    fn handle_connection(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+        let hello = read_message::<_, Hello>(&mut stream)?;
+        hello.check_version()?;
+        let Hello {
+            genesis_seed,
+            nonce: node_nonce,
+            ..
+        } = hello;
+        if let Some(expected_genesis_seed) = self.inner.lock().genesis_seed
+            && expected_genesis_seed != genesis_seed
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Node genesis seed {genesis_seed} doesn't match expected \
+                    {expected_genesis_seed}"
+                ),
+            ));
+        }
+
+        let nonce = rand::random();
+        write_message(&mut stream, &Hello::new(genesis_seed, nonce))?;
+        verify_authentication(&mut stream, &self.secret, Role::Node, &nonce, &genesis_seed)?;
+        send_authentication(
+            &mut stream,
+            &self.secret,
+            Role::Timekeeper,
+            &node_nonce,
+            &genesis_seed,
+        )?;
+
+        stream.set_read_timeout(None)?;
+
+        {
+            let mut inner = self.inner.lock();
+            // Another node might have been authenticated concurrently
+            if *inner.genesis_seed.get_or_insert(genesis_seed) != genesis_seed {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Node genesis seed {genesis_seed} doesn't match expected"),
+                ));
+            }
+        }
+
+        info!(peer_address = ?stream.peer_addr(), "Node connected");
+
+        let (proofs_sender, proofs_receiver) = std_mpsc::sync_channel(PROOFS_CHANNEL_CAPACITY);
+        self.proofs_senders.lock().push(proofs_sender);
+
+        let mut writer = stream.try_clone()?;
+        thread::Builder::new()
+            .name("timekeeper-node-writer".to_string())
+            .spawn(move || {
+                for proof in proofs_receiver {
+                    if let Err(error) =
+                        write_message(&mut writer, &FromTimekeeperMessage::Proof(proof))
+                    {
+                        debug!(%error, "Failed to send proof to node");
+                        break;
+                    }
+                }
+                // Unblock reader in case it is still waiting for messages
+                let _ = writer.shutdown(Shutdown::Both);
+            })?;
+
+        let result = loop {
+            match read_message(&mut stream) {
+                Ok(message) => {
+                    if let Err(error) = self.handle_node_message(genesis_seed, message) {
+                        break Err(error);
+                    }
+                }
+                Err(error) => {
+                    break Err(error);
+                }
+            }
+        };
+
+        // Make writer exit too
+        let _ = stream.shutdown(Shutdown::Both);
+
+        result
+    }
+
+    fn handle_node_message(
+        &self,
+        genesis_seed: PotSeed,
+        message: ToTimekeeperMessage,
+    ) -> io::Result<()> {
+        match message {
+            ToTimekeeperMessage::State {
+                next_slot_input,
+                parameters_change,
+            } => {
+                let mut inner = self.inner.lock();
+                if inner.state.is_some() {
+                    debug!(
+                        ?next_slot_input,
+                        "Proving is already in progress, ignoring node state"
+                    );
+                    return Ok(());
+                }
+
+                info!(?next_slot_input, "Starting proving");
+
+                #[cfg(not(feature = "pot"))]
+                warn_parameters_change_ignored(parameters_change);
+                let pot_verifier = PotVerifier::new(genesis_seed, VERIFIER_CACHE_SIZE);
+                let state = Arc::new(PotState::new(
+                    next_slot_input,
+                    #[cfg(feature = "pot")]
+                    parameters_change.map(PotParametersChange::from),
+                    pot_verifier.clone(),
+                ));
+                inner.state.replace(Arc::clone(&state));
+                drop(inner);
+
+                self.start_proving(state, pot_verifier)?;
+            }
+            ToTimekeeperMessage::BlockImport {
+                best_slot,
+                best_output,
+                parameters_change,
+            } => {
+                #[cfg(not(feature = "pot"))]
+                warn_parameters_change_ignored(parameters_change);
+                let maybe_state = self.inner.lock().state.clone();
+                if let Some(state) = maybe_state
+                    && let Some(next_slot_input) = state.update(
+                        best_slot,
+                        best_output,
+                        #[cfg(feature = "pot")]
+                        Some(parameters_change.map(PotParametersChange::from)),
+                    )
+                {
+                    warn!(?next_slot_input, "Proof of time chain reorg happened");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn start_proving(&self, state: Arc<PotState>, pot_verifier: PotVerifier) -> io::Result<()> {
+        let (timekeeper_proofs_sender, mut timekeeper_proofs_receiver) =
+            mpsc::channel(PROOFS_CHANNEL_CAPACITY);
+
+        thread::Builder::new()
+            .name("timekeeper".to_string())
+            .spawn(move || {
+                if let Err(error) = run_timekeeper(state, pot_verifier, timekeeper_proofs_sender) {
+                    error!(%error, "Timekeeper exited with an error");
+                }
+            })?;
+
+        let proofs_senders = Arc::clone(&self.proofs_senders);
+        thread::Builder::new()
+            .name("timekeeper-broadcast".to_string())
+            .spawn(move || {
+                while let Some(proof) = block_on(timekeeper_proofs_receiver.next()) {
+                    let TimekeeperProof {
+                        slot,
+                        seed,
+                        slot_iterations,
+                        checkpoints,
+                    } = proof;
+                    let proof = GossipProof {
+                        slot,
+                        seed,
+                        slot_iterations,
+                        checkpoints,
+                    };
+
+                    proofs_senders.lock().retain(|proofs_sender| {
+                        match proofs_sender.try_send(proof) {
+                            Ok(()) => true,
+                            Err(std_mpsc::TrySendError::Full(_proof)) => {
+                                debug!(%slot, "Node is not able to keep-up with proofs");
+                                true
+                            }
+                            Err(std_mpsc::TrySendError::Disconnected(_proof)) => false,
+                        }
+                    });
+                }
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Chains without `pot` feature don't have parameters changes, but node might be built with the
+/// feature enabled, in which case proofs will not pass verification on the node after the change
+#[cfg(not(feature = "pot"))]
+fn warn_parameters_change_ignored(parameters_change: Option<ParametersChange>) {
+    if let Some(parameters_change) = parameters_change {
+        warn!(
+            ?parameters_change,
+            "Parameters change ignored, external timekeeper is built without `pot` feature"
+        );
+    }
+}
+
+/// Client of external timekeeper running on the node
+#[derive(Debug)]
+pub(super) struct ExternalTimekeeperClient {
+    #[cfg_attr(not(feature = "pot"), allow(dead_code))]
+    to_timekeeper_sender: std_mpsc::SyncSender<ToTimekeeperMessage>,
+}
+
+impl ExternalTimekeeperClient {
+    /// Start client in background threads, verified proofs received from external timekeeper are
+    /// sent to `proofs_sender`.
+    ///
+    /// Client reconnects to timekeeper automatically whenever connection is lost.
+    pub(super) fn start(
+        config: ExternalTimekeeperConfig,
+        state: Arc<PotState>,
+        pot_verifier: PotVerifier,
+        proofs_sender: mpsc::Sender<TimekeeperProof>,
+    ) -> io::Result<Self> {
+        let (to_timekeeper_sender, to_timekeeper_receiver) =
+            std_mpsc::sync_channel(TO_TIMEKEEPER_CHANNEL_CAPACITY);
+        let current_stream = Arc::new(Mutex::new(None::<Arc<TcpStream>>));
+
+        thread::Builder::new()
+            .name("external-timekeeper-writer".to_string())
+            .spawn({
+                let current_stream = Arc::clone(&current_stream);
+
+                move || {
+                    // Messages are dropped while there is no connection, the latest state is sent
+                    // upon reconnection anyway
+                    for message in to_timekeeper_receiver {
+                        // Lock is not held during write, such that reconnection is not blocked
+                        let Some(stream) = current_stream.lock().clone() else {
+                            continue;
+                        };
+                        if let Err(error) = write_message(&mut &*stream, &message) {
+                            debug!(%error, "Failed to send message to external timekeeper");
+                            let _ = stream.shutdown(Shutdown::Both);
+                            let mut current_stream = current_stream.lock();
+                            if current_stream
+                                .as_ref()
+                                .is_some_and(|current| Arc::ptr_eq(current, &stream))
+                            {
+                                current_stream.take();
+                            }
+                        }
+                    }
+                }
+            })?;
+
+        thread::Builder::new()
+            .name("external-timekeeper".to_string())
+            .spawn(move || {
+                run_client(config, state, pot_verifier, current_stream, proofs_sender);
+            })?;
+
+        Ok(Self {
+            to_timekeeper_sender,
+        })
+    }
+
+    /// Notify external timekeeper about block import
+    #[cfg(feature = "pot")]
+    pub(super) fn block_import(
+        &self,
+        best_slot: Slot,
+        best_output: PotOutput,
+        parameters_change: Option<PotParametersChange>,
+    ) {
+        if self
+            .to_timekeeper_sender
+            .try_send(ToTimekeeperMessage::BlockImport {
+                best_slot,
+                best_output,
+                parameters_change: parameters_change.map(ParametersChange::from),
+            })
+            .is_err()
+        {
+            debug!(%best_slot, "External timekeeper is not able to keep-up with block import");
+        }
+    }
+}
+
+fn run_client(
+    config: ExternalTimekeeperConfig,
+    state: Arc<PotState>,
+    pot_verifier: PotVerifier,
+    current_stream: Arc<Mutex<Option<Arc<TcpStream>>>>,
+    mut proofs_sender: mpsc::Sender<TimekeeperProof>,
+) {
+    let ExternalTimekeeperConfig { address, secret } = config;
+    let genesis_seed = pot_verifier.genesis_seed();
+
+    loop {
+        let mut stream = match connect(address, &secret, genesis_seed) {
+            Ok(stream) => stream,
+            Err(error) => {
+                warn!(
+                    %error,
+                    %address,
+                    "Failed to connect to external timekeeper, relying on gossip"
+                );
+                thread::sleep(RECONNECT_INTERVAL);
+                continue;
+            }
+        };
+
+        info!(%address, "Connected to external timekeeper");
+
+        {
+            let message = ToTimekeeperMessage::State {
+                next_slot_input: state.next_slot_input(Ordering::Acquire),
+                #[cfg(feature = "pot")]
+                parameters_change: state
+                    .parameters_change(Ordering::Acquire)
+                    .map(ParametersChange::from),
+                #[cfg(not(feature = "pot"))]
+                parameters_change: None,
+            };
+            // State is written before stream is shared with writer thread, such that messages are
+            // not interleaved
+            let result = write_message(&mut stream, &message)
+                .and_then(|()| stream.try_clone())
+                .map(|writer| current_stream.lock().replace(Arc::new(writer)));
+            if let Err(error) = result {
+                warn!(
+                    %error,
+                    %address,
+                    "Failed to send state to external timekeeper, relying on gossip"
+                );
+                thread::sleep(RECONNECT_INTERVAL);
+                continue;
+            }
+        }
+
+        let error = loop {
+            let FromTimekeeperMessage::Proof(proof) = match read_message(&mut stream) {
+                Ok(message) => message,
+                Err(error) => {
+                    break error;
+                }
+            };
+
+            // Proofs from external timekeeper are not trusted more than proofs from gossip
+            if !block_on(pot_verifier.verify_checkpoints(
+                proof.seed,
+                proof.slot_iterations,
+                &proof.checkpoints,
+            )) {
+                break io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid proof for slot {}", proof.slot),
+                );
+            }
+
+            let proof = TimekeeperProof {
+                slot: proof.slot,
+                seed: proof.seed,
+                slot_iterations: proof.slot_iterations,
+                checkpoints: proof.checkpoints,
+            };
+            if let Err(error) = block_on(proofs_sender.send(proof)) {
+                debug!(%error, "Couldn't send external timekeeper proof, channel is closed");
+                let _ = stream.shutdown(Shutdown::Both);
+                return;
+            }
+        };
+
+        let _ = stream.shutdown(Shutdown::Both);
+        current_stream.lock().take();
+
+        warn!(
+            %error,
+            %address,
+            "Connection to external timekeeper lost, relying on gossip"
+        );
+        thread::sleep(RECONNECT_INTERVAL);
+    }
+}
+
+fn connect(
+    address: SocketAddr,
+    secret: &ExternalTimekeeperSecret,
+    genesis_seed: PotSeed,
+) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect_timeout(&address, HANDSHAKE_TIMEOUT)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+    let nonce = rand::random();
+    write_message(&mut stream, &Hello::new(genesis_seed, nonce))?;
+    let hello = read_message::<_, Hello>(&mut stream)?;
+    hello.check_version()?;
+    let Hello {
+        genesis_seed: timekeeper_genesis_seed,
+        nonce: timekeeper_nonce,
+        ..
+    } = hello;
+    if timekeeper_genesis_seed != genesis_seed {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Timekeeper genesis seed {timekeeper_genesis_seed} doesn't match expected \
+                {genesis_seed}"
+            ),
+        ));
+    }
+
+    send_authentication(
+        &mut stream,
+        secret,
+        Role::Node,
+        &timekeeper_nonce,
+        &genesis_seed,
+    )?;
+    verify_authentication(&mut stream, secret, Role::Timekeeper, &nonce, &genesis_seed)?;
+
+    stream.set_read_timeout(None)?;
+
+    Ok(stream)
+}
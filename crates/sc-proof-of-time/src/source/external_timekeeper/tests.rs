@@ -0,0 +1,257 @@
+use crate::source::external_timekeeper::{
+    connect, read_message, send_authentication, serve, verify_authentication, write_message,
+    Authentication, ExternalTimekeeperClient, ExternalTimekeeperConfig, ExternalTimekeeperSecret,
+    FromTimekeeperMessage, Hello, ParametersChange, Role, ToTimekeeperMessage, NONCE_SIZE,
+    PROTOCOL_VERSION,
+};
+use crate::source::gossip::GossipProof;
+use crate::source::state::{NextSlotInput, PotState};
+use crate::source::timekeeper::TimekeeperProof;
+use crate::verifier::PotVerifier;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use parity_scale_codec::{DecodeAll, Encode};
+use sp_consensus_slots::Slot;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::sync::{mpsc as std_mpsc, Arc};
+use std::time::{Duration, Instant};
+use std::{io, thread};
+use subspace_core_primitives::{PotCheckpoints, PotOutput, PotSeed};
+
+const SEED: [u8; 16] = [
+    0xd6, 0x66, 0xcc, 0xd8, 0xd5, 0x93, 0xc2, 0x3d, 0xa8, 0xdb, 0x6b, 0x5b, 0x14, 0x13, 0xb1, 0x3a,
+];
+const SLOT_ITERATIONS: NonZeroU32 = NonZeroU32::new(512).expect("Not zero; qed");
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn start_timekeeper(secret: ExternalTimekeeperSecret) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener, secret));
+    address
+}
+
+fn start_client(
+    address: SocketAddr,
+    secret: ExternalTimekeeperSecret,
+) -> (ExternalTimekeeperClient, mpsc::Receiver<TimekeeperProof>) {
+    let genesis_seed = PotSeed::from(SEED);
+    let pot_verifier = PotVerifier::new(genesis_seed, NonZeroUsize::new(1000).unwrap());
+    let state = Arc::new(PotState::new(
+        NextSlotInput {
+            slot: Slot::from(1),
+            slot_iterations: SLOT_ITERATIONS,
+            seed: genesis_seed,
+        },
+        #[cfg(feature = "pot")]
+        None,
+        pot_verifier.clone(),
+    ));
+    let (proofs_sender, proofs_receiver) = mpsc::channel(10);
+    let client = ExternalTimekeeperClient::start(
+        ExternalTimekeeperConfig { address, secret },
+        state,
+        pot_verifier,
+        proofs_sender,
+    )
+    .unwrap();
+
+    (client, proofs_receiver)
+}
+
+fn receive_proof(proofs_receiver: &mut mpsc::Receiver<TimekeeperProof>) -> TimekeeperProof {
+    let deadline = Instant::now() + RECEIVE_TIMEOUT;
+    while Instant::now() < deadline {
+        match proofs_receiver.try_next() {
+            Ok(Some(proof)) => {
+                return proof;
+            }
+            Ok(None) => {
+                panic!("Proofs channel is closed");
+            }
+            Err(_) => {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    panic!("Didn't receive proof in time");
+}
+
+#[test]
+fn handshake() {
+    let genesis_seed = PotSeed::from(SEED);
+    let secret = ExternalTimekeeperSecret::generate();
+    let address = start_timekeeper(secret.clone());
+
+    assert!(connect(address, &secret, genesis_seed).is_ok());
+
+    // Timekeeper rejects node that doesn't know the secret
+    assert!(connect(address, &ExternalTimekeeperSecret::generate(), genesis_seed).is_err());
+
+    // Timekeeper rejects node with different protocol version
+    {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write_message(
+            &mut stream,
+            &Hello {
+                version: PROTOCOL_VERSION + 1,
+                genesis_seed,
+                nonce: rand::random(),
+            },
+        )
+        .unwrap();
+        assert!(read_message::<_, Hello>(&mut stream).is_err());
+    }
+
+    // Timekeeper is bound to genesis seed of the first authenticated node
+    assert!(connect(address, &secret, PotSeed::from([1; PotSeed::SIZE])).is_err());
+}
+
+#[test]
+fn reflection_is_rejected() {
+    let genesis_seed = PotSeed::from(SEED);
+    let secret = ExternalTimekeeperSecret::generate();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    // Malicious timekeeper doesn't know the secret and tries to reflect node's own nonce and proof
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let hello = read_message::<_, Hello>(&mut stream).unwrap();
+        write_message(&mut stream, &Hello::new(hello.genesis_seed, hello.nonce)).unwrap();
+        let authentication = read_message::<_, Authentication>(&mut stream).unwrap();
+        write_message(&mut stream, &authentication).unwrap();
+    });
+
+    let error = connect(address, &secret, genesis_seed).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+}
+
+#[test]
+fn proofs_streaming() {
+    let genesis_seed = PotSeed::from(SEED);
+    let secret = ExternalTimekeeperSecret::generate();
+    let address = start_timekeeper(secret.clone());
+    let (_client, mut proofs_receiver) = start_client(address, secret);
+
+    let proof = receive_proof(&mut proofs_receiver);
+    assert_eq!(proof.slot, Slot::from(1));
+    assert_eq!(proof.seed, genesis_seed);
+    assert_eq!(proof.slot_iterations, SLOT_ITERATIONS);
+    let verifier = PotVerifier::new(genesis_seed, NonZeroUsize::new(1000).unwrap());
+    assert!(block_on(verifier.verify_checkpoints(
+        proof.seed,
+        proof.slot_iterations,
+        &proof.checkpoints
+    )));
+
+    // Proofs follow each other
+    let next_proof = receive_proof(&mut proofs_receiver);
+    assert_eq!(next_proof.slot, Slot::from(2));
+    assert_eq!(next_proof.seed, proof.checkpoints.output().seed());
+    assert!(block_on(verifier.verify_checkpoints(
+        next_proof.seed,
+        next_proof.slot_iterations,
+        &next_proof.checkpoints
+    )));
+}
+
+#[test]
+fn invalid_proof_is_rejected() {
+    let secret = ExternalTimekeeperSecret::generate();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (disconnected_sender, disconnected_receiver) = std_mpsc::channel();
+
+    // Timekeeper knows the secret, but sends invalid proof
+    thread::spawn({
+        let secret = secret.clone();
+
+        move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let Hello {
+                genesis_seed,
+                nonce: node_nonce,
+                ..
+            } = read_message(&mut stream).unwrap();
+            let nonce = [1; NONCE_SIZE];
+            write_message(&mut stream, &Hello::new(genesis_seed, nonce)).unwrap();
+            verify_authentication(&mut stream, &secret, Role::Node, &nonce, &genesis_seed).unwrap();
+            send_authentication(
+                &mut stream,
+                &secret,
+                Role::Timekeeper,
+                &node_nonce,
+                &genesis_seed,
+            )
+            .unwrap();
+            read_message::<_, ToTimekeeperMessage>(&mut stream).unwrap();
+
+            write_message(
+                &mut stream,
+                &FromTimekeeperMessage::Proof(GossipProof {
+                    slot: Slot::from(1),
+                    seed: genesis_seed,
+                    slot_iterations: SLOT_ITERATIONS,
+                    checkpoints: PotCheckpoints::default(),
+                }),
+            )
+            .unwrap();
+
+            // Node is expected to drop connection
+            let result = read_message::<_, ToTimekeeperMessage>(&mut stream);
+            disconnected_sender.send(result.is_err()).unwrap();
+        }
+    });
+
+    let (_client, mut proofs_receiver) = start_client(address, secret);
+
+    assert!(disconnected_receiver.recv_timeout(RECEIVE_TIMEOUT).unwrap());
+    assert!(proofs_receiver.try_next().is_err());
+}
+
+#[test]
+fn wire_format_is_feature_independent() {
+    let message = ToTimekeeperMessage::State {
+        next_slot_input: NextSlotInput {
+            slot: Slot::from(10),
+            slot_iterations: SLOT_ITERATIONS,
+            seed: PotSeed::from([4; PotSeed::SIZE]),
+        },
+        parameters_change: None,
+    };
+    let mut expected = vec![0];
+    expected.extend_from_slice(&10u64.to_le_bytes());
+    expected.extend_from_slice(&512u32.to_le_bytes());
+    expected.extend_from_slice(&[4; PotSeed::SIZE]);
+    expected.push(0);
+    assert_eq!(message.encode(), expected);
+    assert_eq!(
+        ToTimekeeperMessage::decode_all(&mut expected.as_slice()).unwrap(),
+        message
+    );
+
+    let message = ToTimekeeperMessage::BlockImport {
+        best_slot: Slot::from(9),
+        best_output: PotOutput::from([2; PotOutput::SIZE]),
+        parameters_change: Some(ParametersChange {
+            slot: Slot::from(20),
+            slot_iterations: SLOT_ITERATIONS,
+            entropy: [3; 32],
+        }),
+    };
+    let mut expected = vec![1];
+    expected.extend_from_slice(&9u64.to_le_bytes());
+    expected.extend_from_slice(&[2; PotOutput::SIZE]);
+    expected.push(1);
+    expected.extend_from_slice(&20u64.to_le_bytes());
+    expected.extend_from_slice(&512u32.to_le_bytes());
+    expected.extend_from_slice(&[3; 32]);
+    assert_eq!(message.encode(), expected);
+    assert_eq!(
+        ToTimekeeperMessage::decode_all(&mut expected.as_slice()).unwrap(),
+        message
+    );
+}
@@ -1,5 +1,6 @@
 use crate::verifier::PotVerifier;
 use atomic::Atomic;
+use parity_scale_codec::{Decode, Encode};
 use sp_consensus_slots::Slot;
 #[cfg(feature = "pot")]
 use sp_consensus_subspace::PotParametersChange;
@@ -7,7 +8,7 @@ use std::num::NonZeroU32;
 use std::sync::atomic::Ordering;
 use subspace_core_primitives::{PotOutput, PotSeed};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Encode, Decode)]
 pub(super) struct NextSlotInput {
     pub(super) slot: Slot,
     pub(super) slot_iterations: NonZeroU32,
@@ -112,6 +113,11 @@ impl PotState {
         self.inner_state.load(ordering).next_slot_input
     }
 
+    #[cfg(feature = "pot")]
+    pub(super) fn parameters_change(&self, ordering: Ordering) -> Option<PotParametersChange> {
+        self.inner_state.load(ordering).parameters_change
+    }
+
     /// Extend state if it matches provided expected next slot input.
     ///
     /// Returns `Ok(new_next_slot_input)` if state was extended successfully and
@@ -155,7 +161,6 @@ impl PotState {
     /// Update state, overriding PoT chain if it doesn't match provided values.
     ///
     /// Returns `Some(next_slot_input)` if reorg happened.
-    #[cfg(feature = "pot")]
     pub(super) fn update(
         &self,
         best_slot: Slot,
@@ -7,13 +7,51 @@ use async_lock::Mutex as AsyncMutex;
 use futures::channel::oneshot;
 use lru::LruCache;
 use parking_lot::Mutex;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use sp_consensus_slots::Slot;
 #[cfg(feature = "pot")]
 use sp_consensus_subspace::PotParametersChange;
 use std::num::{NonZeroU32, NonZeroUsize};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
 use subspace_core_primitives::{PotCheckpoints, PotOutput, PotSeed};
-use tracing::trace;
+use tracing::{trace, warn};
+
+/// Number of concurrent checkpoints verifications starting with which checkpoints are verified in
+/// parallel, this happens when many headers are pending (during sync for instance)
+const PARALLEL_VERIFICATION_THRESHOLD: usize = 4;
+
+/// Thread pool for parallel verification of checkpoints shared by all verifiers, `None` if it failed
+/// to be created.
+///
+/// Checkpoints are split into intervals that are verified concurrently, so there is no use in having
+/// more threads than checkpoints.
+fn parallel_verification_thread_pool() -> Option<&'static ThreadPool> {
+    static THREAD_POOL: OnceLock<Option<ThreadPool>> = OnceLock::new();
+
+    THREAD_POOL
+        .get_or_init(|| {
+            let num_threads = thread::available_parallelism()
+                .map(NonZeroUsize::get)
+                .unwrap_or(1)
+                .min(usize::from(PotCheckpoints::NUM_CHECKPOINTS.get()));
+
+            ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .thread_name(|thread_index| format!("pot-verifier-{thread_index}"))
+                .build()
+                .map_err(|error| {
+                    warn!(
+                        %error,
+                        "Failed to create thread pool for parallel proof of time verification, \
+                        checkpoints will be verified sequentially"
+                    );
+                })
+                .ok()
+        })
+        .as_ref()
+}
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 struct CacheKey {
@@ -31,6 +69,8 @@ struct CacheValue {
 pub struct PotVerifier {
     genesis_seed: PotSeed,
     cache: Arc<Mutex<LruCache<CacheKey, CacheValue>>>,
+    /// Number of checkpoints verifications in progress
+    pending_verifications: Arc<AtomicUsize>,
 }
 
 impl PotVerifier {
@@ -38,6 +78,7 @@ impl PotVerifier {
         Self {
             genesis_seed,
             cache: Arc::new(Mutex::new(LruCache::new(cache_size))),
+            pending_verifications: Arc::default(),
         }
     }
 
@@ -155,7 +196,7 @@ impl PotVerifier {
         loop {
             // TODO: This "proxy" is a workaround for https://github.com/rust-lang/rust/issues/57478
             let (result_sender, result_receiver) = oneshot::channel();
-            std::thread::spawn({
+            thread::spawn({
                 let verifier = self.clone();
 
                 move || {
@@ -296,29 +337,46 @@ impl PotVerifier {
         slot_iterations: NonZeroU32,
         checkpoints: &PotCheckpoints,
     ) -> bool {
+        // Thread always runs to completion, so counter is decreased even if caller is gone
+        self.pending_verifications.fetch_add(1, Ordering::Relaxed);
+
         // TODO: This "proxy" is a workaround for https://github.com/rust-lang/rust/issues/57478
         let (result_sender, result_receiver) = oneshot::channel();
-        std::thread::spawn({
+        thread::spawn({
             let verifier = self.clone();
             let checkpoints = *checkpoints;
 
             move || {
-                futures::executor::block_on({
-                    async move {
-                        // Result doesn't matter here
-                        let _ = result_sender.send(
-                            verifier
-                                .verify_checkpoints_internal(seed, slot_iterations, &checkpoints)
-                                .await,
-                        );
-                    }
-                });
+                let result = futures::executor::block_on(verifier.verify_checkpoints_internal(
+                    seed,
+                    slot_iterations,
+                    &checkpoints,
+                ));
+
+                // Decrease counter before sending result, such that verification is no longer
+                // pending by the time caller receives result
+                verifier
+                    .pending_verifications
+                    .fetch_sub(1, Ordering::Relaxed);
+
+                // Result doesn't matter here
+                let _ = result_sender.send(result);
             }
         });
 
         result_receiver.await.unwrap_or_default()
     }
 
+    /// Verifying checkpoints in parallel finishes sooner, but only worth it when there are many
+    /// verifications to go through, returns thread pool to use in that case.
+    fn maybe_parallel_verification_thread_pool(&self) -> Option<&'static ThreadPool> {
+        if self.pending_verifications.load(Ordering::Relaxed) >= PARALLEL_VERIFICATION_THRESHOLD {
+            parallel_verification_thread_pool()
+        } else {
+            None
+        }
+    }
+
     // TODO: False-positive, lock is not actually held over await point, remove suppression once
     //  fixed upstream
     #[allow(clippy::await_holding_lock)]
@@ -362,10 +420,22 @@ impl PotVerifier {
             let (result_sender, result_receiver) = oneshot::channel();
 
             let checkpoints = *checkpoints;
+            let maybe_thread_pool = self.maybe_parallel_verification_thread_pool();
             rayon::spawn(move || {
-                let result =
-                    subspace_proof_of_time::verify(seed, slot_iterations, checkpoints.as_slice())
-                        .unwrap_or_default();
+                let result = match maybe_thread_pool {
+                    Some(thread_pool) => subspace_proof_of_time::verify_parallel(
+                        seed,
+                        slot_iterations,
+                        checkpoints.as_slice(),
+                        thread_pool,
+                    ),
+                    None => subspace_proof_of_time::verify(
+                        seed,
+                        slot_iterations,
+                        checkpoints.as_slice(),
+                    ),
+                }
+                .unwrap_or_default();
 
                 if let Err(_error) = result_sender.send(result) {
                     trace!("Verification result receiver is gone before result was sent");
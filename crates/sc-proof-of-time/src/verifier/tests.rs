@@ -1,14 +1,17 @@
-use crate::verifier::PotVerifier;
+use crate::verifier::{PotVerifier, PARALLEL_VERIFICATION_THRESHOLD};
 use futures::executor::block_on;
+use futures::future::join_all;
 use sp_consensus_slots::Slot;
 #[cfg(feature = "pot")]
 use sp_consensus_subspace::PotParametersChange;
 #[cfg(feature = "pot")]
 use std::mem;
 use std::num::{NonZeroU32, NonZeroUsize};
+use std::ptr;
+use std::sync::atomic::Ordering;
 #[cfg(feature = "pot")]
 use subspace_core_primitives::Blake3Hash;
-use subspace_core_primitives::PotSeed;
+use subspace_core_primitives::{PotCheckpoints, PotSeed};
 
 const SEED: [u8; 16] = [
     0xd6, 0x66, 0xcc, 0xd8, 0xd5, 0x93, 0xc2, 0x3d, 0xa8, 0xdb, 0x6b, 0x5b, 0x14, 0x13, 0xb1, 0x3a,
@@ -141,6 +144,85 @@ fn test_basic() {
     ));
 }
 
+#[test]
+fn parallel_checkpoints_verification() {
+    let genesis_seed = PotSeed::from(SEED);
+    let slot_iterations = NonZeroU32::new(512).unwrap();
+    let verifier = PotVerifier::new(genesis_seed, NonZeroUsize::new(1000).unwrap());
+
+    // Checkpoints of consecutive slots, each of them needs to be verified separately
+    let mut seed = genesis_seed;
+    let seeds_and_checkpoints = (0..PARALLEL_VERIFICATION_THRESHOLD * 2)
+        .map(|_| {
+            let checkpoints = subspace_proof_of_time::prove(seed, slot_iterations).unwrap();
+            let seed_and_checkpoints = (seed, checkpoints);
+            seed = checkpoints.output().seed();
+            seed_and_checkpoints
+        })
+        .collect::<Vec<_>>();
+
+    // Few pending verifications are done sequentially
+    assert!(verifier.maybe_parallel_verification_thread_pool().is_none());
+
+    // Simulate verifications in progress, such that checkpoints are verified in parallel
+    verifier
+        .pending_verifications
+        .store(PARALLEL_VERIFICATION_THRESHOLD, Ordering::Relaxed);
+    let thread_pool = verifier.maybe_parallel_verification_thread_pool().unwrap();
+    // Thread pool is shared by all verifiers and bounded by number of checkpoints
+    {
+        let other_verifier = PotVerifier::new(genesis_seed, NonZeroUsize::new(1000).unwrap());
+        other_verifier
+            .pending_verifications
+            .store(PARALLEL_VERIFICATION_THRESHOLD, Ordering::Relaxed);
+        assert!(ptr::eq(
+            thread_pool,
+            other_verifier
+                .maybe_parallel_verification_thread_pool()
+                .unwrap()
+        ));
+        assert!(
+            thread_pool.current_num_threads() <= usize::from(PotCheckpoints::NUM_CHECKPOINTS.get())
+        );
+    }
+
+    let (seed_1, checkpoints_1) = seeds_and_checkpoints[0];
+    let mut invalid_checkpoints_1 = checkpoints_1;
+    invalid_checkpoints_1.swap(2, 3);
+
+    // Invalid checkpoints
+    assert!(!block_on(verifier.verify_checkpoints(
+        seed_1,
+        slot_iterations,
+        &invalid_checkpoints_1
+    )));
+    // Verification is no longer pending once result is returned
+    assert_eq!(
+        verifier.pending_verifications.load(Ordering::Relaxed),
+        PARALLEL_VERIFICATION_THRESHOLD
+    );
+    // Failed verification is not cached, valid checkpoints are verified in parallel again
+    assert!(block_on(verifier.verify_checkpoints(
+        seed_1,
+        slot_iterations,
+        &checkpoints_1
+    )));
+    assert_eq!(
+        verifier.pending_verifications.load(Ordering::Relaxed),
+        PARALLEL_VERIFICATION_THRESHOLD
+    );
+
+    // Many concurrent verifications
+    verifier.pending_verifications.store(0, Ordering::Relaxed);
+    let results = block_on(join_all(seeds_and_checkpoints.iter().skip(1).map(
+        |(seed, checkpoints)| verifier.verify_checkpoints(*seed, slot_iterations, checkpoints),
+    )));
+    assert!(results.into_iter().all(|valid| valid));
+    // All verifications are done
+    assert_eq!(verifier.pending_verifications.load(Ordering::Relaxed), 0);
+    assert!(verifier.maybe_parallel_verification_thread_pool().is_none());
+}
+
 #[cfg(feature = "pot")]
 #[test]
 fn parameters_change() {
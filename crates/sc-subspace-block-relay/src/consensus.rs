@@ -1,4 +1,5 @@
 //! Relay implementation for consensus blocks.
 
+mod peer_stats;
 pub mod relay;
 mod types;
@@ -0,0 +1,196 @@
+//! Per peer stats of the consensus block relay.
+//!
+//! The compact relay is only useful when the peer is reliable and the
+//! transactions are mostly found in the local pool. The stats are used to
+//! fall back to full block download for the peers where this is not the case.
+
+use crate::consensus::relay::ConsensusRelayConfig;
+use crate::consensus::types::DownloadMode;
+
+/// The compact relay response carries some data on top of the block (block
+/// hash, protocol response), which is tolerated per download. Otherwise the
+/// blocks with few or no extrinsics, where the relay has nothing to save,
+/// would always trigger the fallback.
+const COST_MARGIN_BYTES_PER_DOWNLOAD: u64 = 1024;
+/// Tolerated excess of the compact relay bytes over the full blocks, in
+/// percent of the full blocks size.
+const COST_MARGIN_PERCENT: u64 = 10;
+
+/// Reason to fall back to the full block download for the peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FallbackReason {
+    /// Compact relay failed too many times in a row
+    Failures,
+    /// Compact relay received more bytes than the full blocks would take
+    Cost,
+}
+
+/// Tracks how the compact relay performs for the peer.
+#[derive(Debug, Default)]
+pub(crate) struct PeerRelayStats {
+    /// Number of compact relay failures in a row
+    consecutive_failures: u32,
+    /// Number of compact downloads in the current sample
+    sampled_downloads: u32,
+    /// Bytes received by the compact relay in the current sample
+    compact_bytes: u64,
+    /// Size of the same blocks, if they were downloaded in full
+    full_bytes: u64,
+    /// Number of downloads to be done in full, before the compact relay
+    /// is tried again
+    full_downloads_left: u32,
+}
+
+impl PeerRelayStats {
+    /// Returns how the next block should be downloaded from the peer.
+    pub(crate) fn download_mode(&self) -> DownloadMode {
+        if self.full_downloads_left > 0 {
+            DownloadMode::Full
+        } else {
+            DownloadMode::Compact
+        }
+    }
+
+    /// Updates the stats on the full download done due to the fallback.
+    pub(crate) fn on_full_download(&mut self) {
+        self.full_downloads_left = self.full_downloads_left.saturating_sub(1);
+    }
+
+    /// Updates the stats on successful compact download. `compact_bytes` is
+    /// the number of bytes received by the relay, `full_bytes` is the size
+    /// of the full download response for the same blocks.
+    pub(crate) fn on_compact_download(
+        &mut self,
+        compact_bytes: u64,
+        full_bytes: u64,
+        config: &ConsensusRelayConfig,
+    ) -> Option<FallbackReason> {
+        self.consecutive_failures = 0;
+        self.sampled_downloads += 1;
+        self.compact_bytes = self.compact_bytes.saturating_add(compact_bytes);
+        self.full_bytes = self.full_bytes.saturating_add(full_bytes);
+        if self.sampled_downloads < config.cost_sample_size.get() {
+            return None;
+        }
+
+        let margin = self.full_bytes * COST_MARGIN_PERCENT / 100
+            + u64::from(self.sampled_downloads) * COST_MARGIN_BYTES_PER_DOWNLOAD;
+        let too_costly = self.compact_bytes > self.full_bytes.saturating_add(margin);
+        self.sampled_downloads = 0;
+        self.compact_bytes = 0;
+        self.full_bytes = 0;
+        if too_costly {
+            self.full_downloads_left = config.fallback_downloads.get();
+            Some(FallbackReason::Cost)
+        } else {
+            None
+        }
+    }
+
+    /// Updates the stats on failed compact download.
+    pub(crate) fn on_compact_download_fail(
+        &mut self,
+        config: &ConsensusRelayConfig,
+    ) -> Option<FallbackReason> {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures < config.max_consecutive_failures.get() {
+            return None;
+        }
+
+        *self = Self {
+            full_downloads_left: config.fallback_downloads.get(),
+            ..Self::default()
+        };
+        Some(FallbackReason::Failures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FallbackReason, PeerRelayStats};
+    use crate::consensus::relay::ConsensusRelayConfig;
+    use crate::consensus::types::DownloadMode;
+    use std::num::NonZeroU32;
+
+    fn config() -> ConsensusRelayConfig {
+        ConsensusRelayConfig {
+            max_consecutive_failures: NonZeroU32::new(3).unwrap(),
+            cost_sample_size: NonZeroU32::new(4).unwrap(),
+            fallback_downloads: NonZeroU32::new(2).unwrap(),
+            ..ConsensusRelayConfig::default()
+        }
+    }
+
+    fn drain_fallback(stats: &mut PeerRelayStats, config: &ConsensusRelayConfig) {
+        for _ in 0..config.fallback_downloads.get() {
+            assert_eq!(stats.download_mode(), DownloadMode::Full);
+            stats.on_full_download();
+        }
+        assert_eq!(stats.download_mode(), DownloadMode::Compact);
+    }
+
+    #[test]
+    fn fallback_on_failures() {
+        let config = config();
+        let mut stats = PeerRelayStats::default();
+
+        // Success in between resets the failure count
+        assert_eq!(stats.on_compact_download_fail(&config), None);
+        assert_eq!(stats.on_compact_download_fail(&config), None);
+        assert_eq!(stats.on_compact_download(10, 100, &config), None);
+        assert_eq!(stats.on_compact_download_fail(&config), None);
+        assert_eq!(stats.on_compact_download_fail(&config), None);
+        assert_eq!(stats.download_mode(), DownloadMode::Compact);
+
+        assert_eq!(
+            stats.on_compact_download_fail(&config),
+            Some(FallbackReason::Failures)
+        );
+        drain_fallback(&mut stats, &config);
+
+        // The compact relay is retried from scratch after the fallback
+        assert_eq!(stats.on_compact_download_fail(&config), None);
+    }
+
+    #[test]
+    fn fallback_on_cost() {
+        let config = config();
+        let mut stats = PeerRelayStats::default();
+
+        // Cheaper than the full blocks on the whole, despite a costly block
+        for _ in 0..3 {
+            assert_eq!(stats.on_compact_download(1_000, 10_000, &config), None);
+        }
+        assert_eq!(stats.on_compact_download(15_000, 10_000, &config), None);
+        assert_eq!(stats.download_mode(), DownloadMode::Compact);
+
+        // Slightly more expensive is within the margin
+        for _ in 0..4 {
+            assert_eq!(stats.on_compact_download(11_000, 10_000, &config), None);
+        }
+        assert_eq!(stats.download_mode(), DownloadMode::Compact);
+
+        for _ in 0..3 {
+            assert_eq!(stats.on_compact_download(15_000, 10_000, &config), None);
+        }
+        assert_eq!(
+            stats.on_compact_download(15_000, 10_000, &config),
+            Some(FallbackReason::Cost)
+        );
+        drain_fallback(&mut stats, &config);
+    }
+
+    #[test]
+    fn no_fallback_on_empty_blocks() {
+        let config = config();
+        let mut stats = PeerRelayStats::default();
+
+        // Blocks without extrinsics: the compact response is the full block
+        // plus the relay overhead, there is nothing to save, but nothing to
+        // lose either
+        for _ in 0..config.cost_sample_size.get() * 10 {
+            assert_eq!(stats.on_compact_download(400, 300, &config), None);
+            assert_eq!(stats.download_mode(), DownloadMode::Compact);
+        }
+    }
+}
@@ -1,8 +1,10 @@
 //! Consensus block relay implementation.
 
+use crate::consensus::peer_stats::PeerRelayStats;
 use crate::consensus::types::{
-    BlockHash, ConsensusClientMetrics, ConsensusServerMetrics, Extrinsic, FullDownloadRequest,
-    FullDownloadResponse, InitialRequest, InitialResponse, PartialBlock, ServerMessage,
+    BlockHash, ConsensusClientMetrics, ConsensusServerMetrics, DownloadMode, Extrinsic,
+    FullDownloadRequest, FullDownloadResponse, InitialRequest, InitialResponse, PartialBlock,
+    ServerMessage,
 };
 use crate::protocol::compact_block::{CompactBlockClient, CompactBlockServer};
 use crate::protocol::{
@@ -15,6 +17,8 @@ use async_trait::async_trait;
 use codec::{Compact, CompactLen, Decode, Encode};
 use futures::channel::oneshot;
 use futures::stream::StreamExt;
+use lru::LruCache;
+use parking_lot::Mutex;
 use sc_client_api::{BlockBackend, HeaderBackend};
 use sc_network::request_responses::{IncomingRequest, OutgoingResponse, ProtocolConfig};
 use sc_network::types::ProtocolName;
@@ -38,9 +42,6 @@ use tracing::{debug, info, trace, warn};
 
 const SYNC_PROTOCOL: &str = "/subspace/consensus-block-relay/1";
 
-// TODO: size these properly, or move to config
-const NUM_PEER_HINT: NonZeroUsize = NonZeroUsize::new(100).expect("Not zero; qed");
-
 /// These are the same limits used by substrate block handler.
 /// Maximum response size (bytes).
 const MAX_RESPONSE_SIZE: NonZeroUsize = NonZeroUsize::new(8 * 1024 * 1024).expect("Not zero; qed");
@@ -51,6 +52,58 @@ const MAX_RESPONSE_BLOCKS: NonZeroU32 = NonZeroU32::new(128).expect("Not zero; q
 /// return the full extrinsic along with the tx hash.
 const TX_SIZE_THRESHOLD: NonZeroUsize = NonZeroUsize::new(32).expect("Not zero; qed");
 
+/// Consensus block relay configuration.
+#[derive(Debug, Clone)]
+pub struct ConsensusRelayConfig {
+    /// Maximum number of the incoming requests queued for the server,
+    /// the requests beyond this are dropped.
+    pub max_in_flight_requests: NonZeroUsize,
+    /// Maximum size of the request (bytes).
+    pub max_request_size: u64,
+    /// Maximum size of the response (bytes).
+    pub max_response_size: u64,
+    /// Timeout of the individual request/response round-trip.
+    pub request_timeout: Duration,
+    /// Number of peers to track the relay stats for.
+    pub num_peer_hint: NonZeroUsize,
+    /// Number of compact relay failures in a row, after which the peer
+    /// falls back to the full block download.
+    pub max_consecutive_failures: NonZeroU32,
+    /// Number of compact downloads, after which the bytes received are
+    /// compared with the size of the full blocks. The peer falls back to
+    /// the full block download if compact relay costs noticeably more.
+    pub cost_sample_size: NonZeroU32,
+    /// Number of blocks downloaded in full from the peer after the
+    /// fallback, before the compact relay is tried again.
+    pub fallback_downloads: NonZeroU32,
+}
+
+impl Default for ConsensusRelayConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight_requests: NonZeroUsize::new(100).expect("Not zero; qed"),
+            max_request_size: 1024 * 1024,
+            max_response_size: 16 * 1024 * 1024,
+            request_timeout: Duration::from_secs(20),
+            num_peer_hint: NonZeroUsize::new(100).expect("Not zero; qed"),
+            max_consecutive_failures: NonZeroU32::new(3).expect("Not zero; qed"),
+            cost_sample_size: NonZeroU32::new(16).expect("Not zero; qed"),
+            fallback_downloads: NonZeroU32::new(64).expect("Not zero; qed"),
+        }
+    }
+}
+
+/// Stats of the extrinsics resolution for a compact block.
+#[derive(Default)]
+struct ResolveStats {
+    /// Extrinsics resolved from the local transaction pool
+    locally_resolved: u64,
+    /// Extrinsics fetched from the peer
+    fetched: u64,
+    /// Size of the fetched extrinsics (bytes)
+    local_miss: usize,
+}
+
 /// The client side of the consensus block relay
 struct ConsensusRelayClient<Block, Pool, ProtoClient>
 where
@@ -63,6 +116,8 @@ where
     protocol: Arc<ProtoClient>,
     backend: Arc<ConsensusClientBackend<Pool>>,
     metrics: ConsensusClientMetrics,
+    config: ConsensusRelayConfig,
+    peer_stats: Mutex<LruCache<PeerId, PeerRelayStats>>,
     _phantom_data: std::marker::PhantomData<(Block, Pool)>,
 }
 
@@ -79,23 +134,43 @@ where
         protocol: Arc<ProtoClient>,
         backend: Arc<ConsensusClientBackend<Pool>>,
         metrics: ConsensusClientMetrics,
+        config: ConsensusRelayConfig,
     ) -> Self {
+        let peer_stats = Mutex::new(LruCache::new(config.num_peer_hint));
         Self {
             network,
             protocol_name,
             protocol,
             backend,
             metrics,
+            config,
+            peer_stats,
             _phantom_data: Default::default(),
         }
     }
 
+    /// Runs the closure on the relay stats of the peer.
+    fn with_peer_stats<R>(&self, who: PeerId, f: impl FnOnce(&mut PeerRelayStats) -> R) -> R {
+        let mut peer_stats = self.peer_stats.lock();
+        if let Some(stats) = peer_stats.get_mut(&who) {
+            return f(stats);
+        }
+
+        let mut stats = PeerRelayStats::default();
+        let ret = f(&mut stats);
+        if let Some((evicted, _)) = peer_stats.push(who, stats) {
+            self.metrics.remove_peer(&evicted);
+        }
+        ret
+    }
+
     /// Downloads the requested block from the peer using the relay protocol.
+    /// Returns the block along with the number of bytes received from the peer.
     async fn download(
         &self,
         who: PeerId,
         request: BlockRequest<Block>,
-    ) -> Result<Vec<BlockData<Block>>, RelayError> {
+    ) -> Result<(Vec<BlockData<Block>>, u64), RelayError> {
         let start_ts = Instant::now();
         let network_peer_handle = self
             .network
@@ -117,30 +192,42 @@ where
             .await?;
 
         // Resolve the protocol response to get the extrinsics
-        let (body, local_miss) = if let Some(protocol_response) = initial_response.protocol_response
-        {
-            let (body, local_miss) = self
-                .resolve_extrinsics::<ServerMessage<Block, ProtoClient::Request>>(
-                    protocol_response,
-                    &network_peer_handle,
-                )
-                .await?;
-            (Some(body), local_miss)
-        } else {
-            (None, 0)
-        };
+        let (body, resolve_stats) =
+            if let Some(protocol_response) = initial_response.protocol_response {
+                let (body, resolve_stats) = self
+                    .resolve_extrinsics::<ServerMessage<Block, ProtoClient::Request>>(
+                        protocol_response,
+                        &network_peer_handle,
+                    )
+                    .await?;
+                (Some(body), resolve_stats)
+            } else {
+                (None, ResolveStats::default())
+            };
 
         // Assemble the final response
         let downloaded = vec![initial_response.partial_block.block_data(body)];
+        let transfer_stats = network_peer_handle.transfer_stats();
+        let duration = start_ts.elapsed();
         debug!(
             target: LOG_TARGET,
             block_hash = ?initial_response.block_hash,
             download_bytes = %downloaded.encoded_size(),
-            %local_miss,
-            duration = ?start_ts.elapsed(),
+            local_miss = %resolve_stats.local_miss,
+            round_trips = %transfer_stats.round_trips,
+            bytes_sent = %transfer_stats.bytes_sent,
+            bytes_received = %transfer_stats.bytes_received,
+            ?duration,
             "block_download",
         );
-        Ok(downloaded)
+        self.metrics
+            .on_peer_download(&who, DownloadMode::Compact, &transfer_stats, duration);
+        self.metrics.on_peer_protocol_units(
+            &who,
+            resolve_stats.locally_resolved,
+            resolve_stats.fetched,
+        );
+        Ok((downloaded, transfer_stats.bytes_received))
     }
 
     /// Downloads the requested blocks from the peer, without using the relay protocol.
@@ -160,15 +247,21 @@ where
             .request::<_, FullDownloadResponse<Block>>(server_request)
             .await?;
         let downloaded = full_response.0;
+        let transfer_stats = network_peer_handle.transfer_stats();
+        let duration = start_ts.elapsed();
+        // Track the peer, so that its metrics are removed when it is evicted
+        self.with_peer_stats(who, |_stats| ());
 
         debug!(
             target: LOG_TARGET,
             ?request,
             download_blocks =  %downloaded.len(),
             download_bytes = %downloaded.encoded_size(),
-            duration = ?start_ts.elapsed(),
+            ?duration,
             "full_download",
         );
+        self.metrics
+            .on_peer_download(&who, DownloadMode::Full, &transfer_stats, duration);
         Ok(downloaded)
     }
 
@@ -177,7 +270,7 @@ where
         &self,
         protocol_response: ProtoClient::Response,
         network_peer_handle: &NetworkPeerHandle,
-    ) -> Result<(Vec<Extrinsic<Block>>, usize), RelayError>
+    ) -> Result<(Vec<Extrinsic<Block>>, ResolveStats), RelayError>
     where
         Request: From<ProtoClient::Request> + Encode + Send + Sync,
     {
@@ -189,12 +282,14 @@ where
                 self.backend.as_ref(),
             )
             .await?;
-        let mut local_miss = 0;
+        let mut resolve_stats = ResolveStats::default();
         let extrinsics = resolved
             .into_iter()
             .map(|entry| {
                 let encoded = entry.protocol_unit.encode();
-                if !entry.locally_resolved {
+                if entry.locally_resolved {
+                    resolve_stats.locally_resolved += 1;
+                } else {
                     trace!(
                         target: LOG_TARGET,
                         ?block_hash,
@@ -203,12 +298,58 @@ where
                         "resolve_extrinsics: local miss"
                     );
                     self.metrics.tx_pool_miss.inc();
-                    local_miss += encoded.len();
+                    resolve_stats.fetched += 1;
+                    resolve_stats.local_miss += encoded.len();
                 }
                 entry.protocol_unit
             })
             .collect();
-        Ok((extrinsics, local_miss))
+        Ok((extrinsics, resolve_stats))
+    }
+
+    /// Downloads the single requested block from the peer, using the relay
+    /// protocol unless the peer fell back to the full block download.
+    async fn relay_block(
+        &self,
+        who: PeerId,
+        request: BlockRequest<Block>,
+    ) -> Result<Vec<BlockData<Block>>, RelayError> {
+        if self.with_peer_stats(who, |stats| stats.download_mode()) == DownloadMode::Full {
+            let mut request = request;
+            request.max = Some(1);
+            let downloaded = self.full_download(who, request).await?;
+            self.with_peer_stats(who, |stats| stats.on_full_download());
+            return Ok(downloaded);
+        }
+
+        let (ret, fallback) = match self.download(who, request).await {
+            Ok((downloaded, received_bytes)) => {
+                // Same as the size of the full download response, which is just the list of
+                // the blocks. Bytes sent are not compared, requests are small in both cases
+                let full_bytes = downloaded.encoded_size() as u64;
+                let fallback = self.with_peer_stats(who, |stats| {
+                    stats.on_compact_download(received_bytes, full_bytes, &self.config)
+                });
+                (Ok(downloaded), fallback)
+            }
+            Err(error) => {
+                let fallback =
+                    self.with_peer_stats(who, |stats| stats.on_compact_download_fail(&self.config));
+                (Err(error), fallback)
+            }
+        };
+
+        if let Some(reason) = fallback {
+            info!(
+                target: LOG_TARGET,
+                peer = ?who,
+                ?reason,
+                fallback_downloads = %self.config.fallback_downloads,
+                "Compact relay is not effective for the peer, falling back to full block download",
+            );
+            self.metrics.full_download_fallbacks.inc();
+        }
+        ret
     }
 }
 
@@ -229,7 +370,7 @@ where
         let ret = if full_download {
             self.full_download(who, request.clone()).await
         } else {
-            self.download(who, request.clone()).await
+            self.relay_block(who, request.clone()).await
         };
         match ret {
             Ok(blocks) => {
@@ -671,6 +812,7 @@ pub fn build_consensus_relay<Block, Client, Pool>(
     network: Arc<NetworkWrapper>,
     client: Arc<Client>,
     pool: Arc<Pool>,
+    config: ConsensusRelayConfig,
     registry: Option<&Registry>,
 ) -> Result<BlockRelayParams<Block>, BlockRelayConfigurationError>
 where
@@ -679,7 +821,7 @@ where
     Client::Api: SubspaceApi<Block, FarmerPublicKey>,
     Pool: TransactionPool<Block = Block> + 'static,
 {
-    let (tx, request_receiver) = async_channel::bounded(NUM_PEER_HINT.get());
+    let (tx, request_receiver) = async_channel::bounded(config.max_in_flight_requests.get());
 
     let backend = Arc::new(ConsensusClientBackend {
         transaction_pool: pool.clone(),
@@ -692,6 +834,7 @@ where
         Arc::new(CompactBlockClient::new()),
         backend,
         metrics,
+        config.clone(),
     );
 
     let backend = Arc::new(ConsensusServerBackend {
@@ -711,9 +854,9 @@ where
     let mut protocol_config = ProtocolConfig {
         name: SYNC_PROTOCOL.into(),
         fallback_names: Vec::new(),
-        max_request_size: 1024 * 1024,
-        max_response_size: 16 * 1024 * 1024,
-        request_timeout: Duration::from_secs(20),
+        max_request_size: config.max_request_size,
+        max_response_size: config.max_response_size,
+        request_timeout: config.request_timeout,
         inbound_queue: None,
     };
     protocol_config.inbound_queue = Some(tx);
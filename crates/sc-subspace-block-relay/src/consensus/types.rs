@@ -1,12 +1,14 @@
 //! Consensus related types.
 
 use crate::types::RelayError;
-use crate::utils::{RelayCounter, RelayCounterVec};
+use crate::utils::{RelayCounter, RelayCounterVec, RelayHistogramVec, TransferStats};
 use codec::{Decode, Encode};
+use sc_network::PeerId;
 use sc_network_common::sync::message::{BlockAttributes, BlockData, BlockRequest};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 use sp_runtime::Justifications;
+use std::time::Duration;
 use substrate_prometheus_endpoint::{PrometheusError, Registry};
 
 pub(crate) type BlockHash<Block> = <Block as BlockT>::Hash;
@@ -20,6 +22,23 @@ const DOWNLOAD_LABEL: &str = "client_download";
 const DOWNLOAD_BLOCKS: &str = "blocks";
 const DOWNLOAD_BYTES: &str = "bytes";
 
+const PEER_LABEL: &str = "peer";
+
+const MODE_LABEL: &str = "mode";
+const MODE_COMPACT: &str = "compact";
+const MODE_FULL: &str = "full";
+
+const RESOLUTION_LABEL: &str = "resolution";
+const RESOLUTION_LOCAL: &str = "local";
+const RESOLUTION_FETCHED: &str = "fetched";
+
+const DIRECTION_LABEL: &str = "direction";
+const DIRECTION_SENT: &str = "sent";
+const DIRECTION_RECEIVED: &str = "received";
+
+/// Latency histogram buckets (seconds).
+const LATENCY_BUCKETS: [f64; 10] = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
 /// Initial request for a single block.
 #[derive(Encode, Decode)]
 pub(crate) struct InitialRequest<Block: BlockT, ProtocolRequest> {
@@ -114,11 +133,34 @@ impl<Block: BlockT> PartialBlock<Block> {
     }
 }
 
+/// How the block was downloaded from the peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DownloadMode {
+    /// Compact relay protocol
+    Compact,
+    /// Full blocks, without the relay protocol
+    Full,
+}
+
+impl DownloadMode {
+    fn as_label(&self) -> &'static str {
+        match self {
+            Self::Compact => MODE_COMPACT,
+            Self::Full => MODE_FULL,
+        }
+    }
+}
+
 /// Client side metrics.
 pub(crate) struct ConsensusClientMetrics {
     pub(crate) requests: RelayCounterVec,
     pub(crate) downloads: RelayCounterVec,
     pub(crate) tx_pool_miss: RelayCounter,
+    pub(crate) full_download_fallbacks: RelayCounter,
+    pub(crate) peer_protocol_units: RelayCounterVec,
+    pub(crate) peer_bytes: RelayCounterVec,
+    pub(crate) peer_round_trips: RelayCounterVec,
+    pub(crate) peer_latency: RelayHistogramVec,
 }
 
 impl ConsensusClientMetrics {
@@ -141,9 +183,96 @@ impl ConsensusClientMetrics {
                 "Number of extrinsics not found in the tx pool",
                 registry,
             )?,
+            full_download_fallbacks: RelayCounter::new(
+                "relay_client_full_download_fallbacks",
+                "Number of times a peer was switched from compact relay to full block download",
+                registry,
+            )?,
+            peer_protocol_units: RelayCounterVec::new(
+                "relay_client_peer_protocol_units",
+                "Protocol units of the compact blocks(by peer, locally resolved/fetched)",
+                &[PEER_LABEL, RESOLUTION_LABEL],
+                registry,
+            )?,
+            peer_bytes: RelayCounterVec::new(
+                "relay_client_peer_bytes",
+                "Bytes sent/received by the relay client(by peer, download mode)",
+                &[PEER_LABEL, MODE_LABEL, DIRECTION_LABEL],
+                registry,
+            )?,
+            peer_round_trips: RelayCounterVec::new(
+                "relay_client_peer_round_trips",
+                "Request/response round-trips of the relay client(by peer, download mode)",
+                &[PEER_LABEL, MODE_LABEL],
+                registry,
+            )?,
+            peer_latency: RelayHistogramVec::new(
+                "relay_client_peer_latency_seconds",
+                "Block download latency of the relay client(by peer, download mode)",
+                &[PEER_LABEL, MODE_LABEL],
+                LATENCY_BUCKETS.to_vec(),
+                registry,
+            )?,
         })
     }
 
+    /// Updates the per peer metrics on successful download from the peer.
+    pub(crate) fn on_peer_download(
+        &self,
+        peer: &PeerId,
+        mode: DownloadMode,
+        transfer_stats: &TransferStats,
+        latency: Duration,
+    ) {
+        let peer = peer.to_string();
+        let peer = peer.as_str();
+        let mode = mode.as_label();
+        self.peer_bytes
+            .inc_by_label_values(&[peer, mode, DIRECTION_SENT], transfer_stats.bytes_sent);
+        self.peer_bytes.inc_by_label_values(
+            &[peer, mode, DIRECTION_RECEIVED],
+            transfer_stats.bytes_received,
+        );
+        self.peer_round_trips
+            .inc_by_label_values(&[peer, mode], transfer_stats.round_trips);
+        self.peer_latency
+            .observe(&[peer, mode], latency.as_secs_f64());
+    }
+
+    /// Updates the per peer metrics on the resolution of the compact block
+    /// protocol units.
+    pub(crate) fn on_peer_protocol_units(
+        &self,
+        peer: &PeerId,
+        locally_resolved: u64,
+        fetched: u64,
+    ) {
+        let peer = peer.to_string();
+        let peer = peer.as_str();
+        self.peer_protocol_units
+            .inc_by_label_values(&[peer, RESOLUTION_LOCAL], locally_resolved);
+        self.peer_protocol_units
+            .inc_by_label_values(&[peer, RESOLUTION_FETCHED], fetched);
+    }
+
+    /// Removes the per peer metrics when the peer is no longer tracked.
+    pub(crate) fn remove_peer(&self, peer: &PeerId) {
+        let peer = peer.to_string();
+        let peer = peer.as_str();
+        for resolution in [RESOLUTION_LOCAL, RESOLUTION_FETCHED] {
+            self.peer_protocol_units
+                .remove_label_values(&[peer, resolution]);
+        }
+        for mode in [MODE_COMPACT, MODE_FULL] {
+            for direction in [DIRECTION_SENT, DIRECTION_RECEIVED] {
+                self.peer_bytes
+                    .remove_label_values(&[peer, mode, direction]);
+            }
+            self.peer_round_trips.remove_label_values(&[peer, mode]);
+            self.peer_latency.remove_label_values(&[peer, mode]);
+        }
+    }
+
     /// Updates the metrics on successful download completion.
     pub(crate) fn on_download<Block: BlockT>(&self, blocks: &[BlockData<Block>]) {
         self.requests.inc(STATUS_LABEL, STATUS_SUCCESS);
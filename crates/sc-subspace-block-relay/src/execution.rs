@@ -0,0 +1,6 @@
+//! Relay implementation for domain bundles.
+
+pub mod relay;
+#[cfg(test)]
+mod tests;
+mod types;
@@ -0,0 +1,489 @@
+//! Domain bundle relay implementation.
+//!
+//! The bundles are gossiped in the compact form: the bundle header along
+//! with the hashes of the bundle extrinsics. The receivers resolve the
+//! extrinsics from their transaction pool, and fetch the missing ones
+//! from the peer that sent the compact bundle (reconcile phase).
+
+use crate::consensus::relay::BlockRelayConfigurationError;
+use crate::execution::types::{
+    BundleHash, ExecutionClientMetrics, ExecutionServerMetrics, Extrinsic, ExtrinsicHash,
+    ServerMessage,
+};
+use crate::protocol::compact_block::{
+    CompactBlockClient, CompactBlockRequest, CompactBlockResponse, CompactBlockServer,
+};
+use crate::protocol::{
+    ClientBackend, ProtocolClient, ProtocolServer, ProtocolUnitInfo, ServerBackend,
+};
+use crate::types::RelayError;
+use crate::utils::{
+    decode_request, send_response, server_protocol_config, NetworkWrapper, ServerProtocolLimits,
+};
+use crate::LOG_TARGET;
+use codec::{Decode, Encode};
+use futures::stream::StreamExt;
+use lru::LruCache;
+use parking_lot::Mutex;
+use sc_network::request_responses::{IncomingRequest, ProtocolConfig};
+use sc_network::types::ProtocolName;
+use sc_network::PeerId;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
+use sp_runtime::traits::{Block as BlockT, Hash as HashT, Header as HeaderT};
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use substrate_prometheus_endpoint::Registry;
+use tracing::{debug, info, trace};
+
+const SYNC_PROTOCOL: &str = "/subspace/execution-bundle-relay/1";
+
+/// Limits of the protocol. The requests are made by the domain subnet
+/// peers (25 inbound + 25 outbound) to fetch the extrinsics of the gossiped
+/// bundles missing in their transaction pool, a single request per bundle.
+const SERVER_LIMITS: ServerProtocolLimits = ServerProtocolLimits {
+    // A couple of bundles being resolved by each of the subnet peers
+    inbound_queue_size: NonZeroUsize::new(128).expect("Not zero; qed"),
+    // Hashes of the missing extrinsics of the bundle
+    max_request_size: 1024 * 1024,
+    // The bundle extrinsics, the bundle has to fit into the consensus block
+    // (5 MiB at most)
+    max_response_size: 8 * 1024 * 1024,
+    request_timeout: Duration::from_secs(20),
+};
+
+/// Number of recently relayed bundles kept around to serve the
+/// reconcile requests from the peers.
+const RECENT_BUNDLES: NonZeroUsize = NonZeroUsize::new(256).expect("Not zero; qed");
+
+/// If the encoded size of the extrinsic is less than the threshold,
+/// return the full extrinsic along with the tx hash.
+const TX_SIZE_THRESHOLD: NonZeroUsize = NonZeroUsize::new(32).expect("Not zero; qed");
+
+type CompactBundleResponse<Block> =
+    CompactBlockResponse<BundleHash<Block>, ExtrinsicHash<Block>, Extrinsic<Block>>;
+type CompactBundleRequest<Block> = CompactBlockRequest<BundleHash<Block>, ExtrinsicHash<Block>>;
+
+/// Compact form of a domain bundle that is gossiped in place of the full
+/// bundle: the bundle header along with the hashes of the extrinsics.
+#[derive(Encode, Decode)]
+pub struct CompactBundle<Block: BlockT, BundleHeader> {
+    /// The bundle header
+    header: BundleHeader,
+
+    /// The protocol specific part, lists the bundle extrinsics
+    protocol_response: CompactBundleResponse<Block>,
+}
+
+impl<Block: BlockT, BundleHeader> CompactBundle<Block, BundleHeader> {
+    /// Returns the bundle header.
+    pub fn header(&self) -> &BundleHeader {
+        &self.header
+    }
+}
+
+impl<Block: BlockT, BundleHeader: fmt::Debug> fmt::Debug for CompactBundle<Block, BundleHeader> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompactBundle")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned when a compact bundle could not be resolved.
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct ExecutionRelayError(#[from] RelayError);
+
+/// Transaction pool operations used by the execution relay.
+pub(crate) trait ExecutionTxPool<Block: BlockT>: Send + Sync {
+    /// Returns the hash of the extrinsic.
+    fn hash_of(&self, extrinsic: &Extrinsic<Block>) -> ExtrinsicHash<Block>;
+
+    /// Returns the ready transaction with the given hash.
+    fn ready_transaction(&self, tx_hash: &ExtrinsicHash<Block>) -> Option<Extrinsic<Block>>;
+}
+
+impl<Block, Pool> ExecutionTxPool<Block> for Pool
+where
+    Block: BlockT,
+    Pool: TransactionPool<Block = Block, Hash = ExtrinsicHash<Block>> + 'static,
+{
+    fn hash_of(&self, extrinsic: &Extrinsic<Block>) -> ExtrinsicHash<Block> {
+        TransactionPool::hash_of(self, extrinsic)
+    }
+
+    fn ready_transaction(&self, tx_hash: &ExtrinsicHash<Block>) -> Option<Extrinsic<Block>> {
+        TransactionPool::ready_transaction(self, tx_hash)
+            .map(|in_pool_tx| in_pool_tx.data().clone())
+    }
+}
+
+/// The client side of the execution relay, used to compact the bundles
+/// before gossiping them and to resolve the received compact bundles.
+pub struct ExecutionRelay<Block: BlockT, BundleHeader> {
+    network: Arc<NetworkWrapper>,
+    protocol_name: ProtocolName,
+    protocol_client:
+        Arc<CompactBlockClient<BundleHash<Block>, ExtrinsicHash<Block>, Extrinsic<Block>>>,
+    protocol_server:
+        Arc<CompactBlockServer<BundleHash<Block>, ExtrinsicHash<Block>, Extrinsic<Block>>>,
+    client_backend: Arc<ExecutionClientBackend<Block>>,
+    server_backend: Arc<ExecutionServerBackend<Block>>,
+    metrics: Arc<ExecutionClientMetrics>,
+    _phantom_data: std::marker::PhantomData<BundleHeader>,
+}
+
+impl<Block: BlockT, BundleHeader> Clone for ExecutionRelay<Block, BundleHeader> {
+    fn clone(&self) -> Self {
+        Self {
+            network: self.network.clone(),
+            protocol_name: self.protocol_name.clone(),
+            protocol_client: self.protocol_client.clone(),
+            protocol_server: self.protocol_server.clone(),
+            client_backend: self.client_backend.clone(),
+            server_backend: self.server_backend.clone(),
+            metrics: self.metrics.clone(),
+            _phantom_data: Default::default(),
+        }
+    }
+}
+
+impl<Block, BundleHeader> ExecutionRelay<Block, BundleHeader>
+where
+    Block: BlockT,
+    BundleHeader: Encode + Send + Sync,
+{
+    /// Builds the compact bundle to be gossiped. The bundle is also remembered,
+    /// so that the reconcile requests from the peers can be served.
+    pub fn compact_bundle(
+        &self,
+        header: BundleHeader,
+        extrinsics: Vec<Extrinsic<Block>>,
+    ) -> CompactBundle<Block, BundleHeader> {
+        let bundle_hash = compute_bundle_hash::<Block, _>(&header);
+        self.server_backend.add_bundle(bundle_hash, extrinsics);
+        let protocol_response = self
+            .protocol_server
+            .build_initial_response(
+                &bundle_hash,
+                CompactBlockRequest::Initial,
+                self.server_backend.as_ref(),
+            )
+            .expect("Bundle was just added to the recent bundles; qed");
+        CompactBundle {
+            header,
+            protocol_response,
+        }
+    }
+
+    /// Resolves the compact bundle received from the peer, returns the bundle
+    /// header and the extrinsics.
+    pub async fn resolve_bundle(
+        &self,
+        who: PeerId,
+        compact_bundle: CompactBundle<Block, BundleHeader>,
+    ) -> Result<(BundleHeader, Vec<Extrinsic<Block>>), ExecutionRelayError> {
+        match self.resolve(who, compact_bundle).await {
+            Ok(ret) => Ok(ret),
+            Err(error) => {
+                debug!(
+                    target: LOG_TARGET,
+                    peer = ?who,
+                    ?error,
+                    "resolve_bundle failed"
+                );
+                self.metrics.on_resolve_fail(&error);
+                Err(error.into())
+            }
+        }
+    }
+
+    async fn resolve(
+        &self,
+        who: PeerId,
+        compact_bundle: CompactBundle<Block, BundleHeader>,
+    ) -> Result<(BundleHeader, Vec<Extrinsic<Block>>), RelayError> {
+        let start_ts = Instant::now();
+        let compact_bytes = compact_bundle.encoded_size();
+        let CompactBundle {
+            header,
+            protocol_response,
+        } = compact_bundle;
+        let network_peer_handle = self
+            .network
+            .network_peer_handle(self.protocol_name.clone(), who)?;
+
+        let (bundle_hash, resolved) = self
+            .protocol_client
+            .resolve_initial_response::<ServerMessage<CompactBundleRequest<Block>>>(
+                protocol_response,
+                &network_peer_handle,
+                self.client_backend.as_ref(),
+            )
+            .await?;
+        if bundle_hash != compute_bundle_hash::<Block, _>(&header) {
+            return Err(RelayError::BundleHashMismatch(format!("{bundle_hash:?}")));
+        }
+
+        let mut local_miss = 0;
+        let extrinsics: Vec<_> = resolved
+            .into_iter()
+            .map(|entry| {
+                if !entry.locally_resolved {
+                    let tx_size = entry.protocol_unit.encoded_size();
+                    trace!(
+                        target: LOG_TARGET,
+                        ?bundle_hash,
+                        tx_hash = ?entry.protocol_unit_id,
+                        %tx_size,
+                        "resolve_bundle: local miss"
+                    );
+                    self.metrics.tx_pool_miss.inc();
+                    local_miss += tx_size;
+                }
+                entry.protocol_unit
+            })
+            .collect();
+
+        let download_bytes = compact_bytes + local_miss;
+        let full_bytes = header.encoded_size() + extrinsics.encoded_size();
+        self.metrics.on_resolve(download_bytes, full_bytes);
+        debug!(
+            target: LOG_TARGET,
+            ?bundle_hash,
+            %download_bytes,
+            %full_bytes,
+            %local_miss,
+            duration = ?start_ts.elapsed(),
+            "bundle_download",
+        );
+        Ok((header, extrinsics))
+    }
+}
+
+/// The server side of the execution relay, serves the reconcile requests
+/// for the recently relayed bundles.
+pub struct ExecutionRelayServer<Block: BlockT> {
+    protocol: CompactBlockServer<BundleHash<Block>, ExtrinsicHash<Block>, Extrinsic<Block>>,
+    request_receiver: async_channel::Receiver<IncomingRequest>,
+    backend: Arc<ExecutionServerBackend<Block>>,
+    metrics: ExecutionServerMetrics,
+}
+
+impl<Block: BlockT> ExecutionRelayServer<Block> {
+    /// Runs the server, processing the incoming requests.
+    pub async fn run(mut self) {
+        info!(
+            target: LOG_TARGET,
+            "relay::execution bundle server: starting"
+        );
+        while let Some(request) = self.request_receiver.next().await {
+            self.on_request(request);
+        }
+    }
+
+    /// Handles the received request from the client side
+    fn on_request(&self, request: IncomingRequest) {
+        // Drop the request in case of errors and let the client time out.
+        let IncomingRequest {
+            peer,
+            payload,
+            pending_response,
+        } = request;
+        let Some(server_msg) =
+            decode_request::<ServerMessage<CompactBundleRequest<Block>>>(peer, &payload)
+        else {
+            return;
+        };
+
+        let ret = match server_msg {
+            ServerMessage::ProtocolRequest(req) => self
+                .protocol
+                .on_request(req, self.backend.as_ref())
+                .map(|response| response.encode()),
+        };
+
+        match ret {
+            Ok(response) => {
+                self.metrics.on_request();
+                send_response(peer, response, pending_response);
+                trace!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    "server: request processed from"
+                );
+            }
+            Err(error) => {
+                self.metrics.on_failed_request(&error);
+                debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?error,
+                    "Server error"
+                );
+            }
+        }
+    }
+}
+
+/// The client backend.
+struct ExecutionClientBackend<Block: BlockT> {
+    transaction_pool: Arc<dyn ExecutionTxPool<Block>>,
+}
+
+impl<Block: BlockT> ClientBackend<ExtrinsicHash<Block>, Extrinsic<Block>>
+    for ExecutionClientBackend<Block>
+{
+    fn protocol_unit(&self, tx_hash: &ExtrinsicHash<Block>) -> Option<Extrinsic<Block>> {
+        // Look up the transaction pool.
+        self.transaction_pool.ready_transaction(tx_hash)
+    }
+}
+
+/// The server backend.
+struct ExecutionServerBackend<Block: BlockT> {
+    recent_bundles:
+        Mutex<LruCache<BundleHash<Block>, Vec<(ExtrinsicHash<Block>, Extrinsic<Block>)>>>,
+    transaction_pool: Arc<dyn ExecutionTxPool<Block>>,
+}
+
+impl<Block: BlockT> ExecutionServerBackend<Block> {
+    /// Remembers the bundle extrinsics to serve the reconcile requests.
+    fn add_bundle(&self, bundle_hash: BundleHash<Block>, extrinsics: Vec<Extrinsic<Block>>) {
+        let extrinsics = extrinsics
+            .into_iter()
+            .map(|extrinsic| (self.transaction_pool.hash_of(&extrinsic), extrinsic))
+            .collect();
+        self.recent_bundles.lock().put(bundle_hash, extrinsics);
+    }
+}
+
+impl<Block: BlockT> ServerBackend<BundleHash<Block>, ExtrinsicHash<Block>, Extrinsic<Block>>
+    for ExecutionServerBackend<Block>
+{
+    fn download_unit_members(
+        &self,
+        bundle_hash: &BundleHash<Block>,
+    ) -> Result<Vec<ProtocolUnitInfo<ExtrinsicHash<Block>, Extrinsic<Block>>>, RelayError> {
+        let mut recent_bundles = self.recent_bundles.lock();
+        let extrinsics = recent_bundles
+            .get(bundle_hash)
+            .ok_or_else(|| RelayError::BundleNotFound(format!("{bundle_hash:?}")))?;
+        Ok(extrinsics
+            .iter()
+            .map(|(tx_hash, extrinsic)| {
+                let send_tx = extrinsic.encoded_size() <= TX_SIZE_THRESHOLD.get();
+                ProtocolUnitInfo {
+                    id: *tx_hash,
+                    unit: if send_tx {
+                        Some(extrinsic.clone())
+                    } else {
+                        None
+                    },
+                }
+            })
+            .collect())
+    }
+
+    fn protocol_unit(
+        &self,
+        bundle_hash: &BundleHash<Block>,
+        tx_hash: &ExtrinsicHash<Block>,
+    ) -> Option<Extrinsic<Block>> {
+        // Look up the bundle extrinsics.
+        let maybe_extrinsic = self
+            .recent_bundles
+            .lock()
+            .get(bundle_hash)
+            .and_then(|extrinsics| {
+                extrinsics
+                    .iter()
+                    .find(|(hash, _)| hash == tx_hash)
+                    .map(|(_, extrinsic)| extrinsic.clone())
+            });
+        if maybe_extrinsic.is_some() {
+            return maybe_extrinsic;
+        }
+
+        // Next look up the transaction pool.
+        self.transaction_pool.ready_transaction(tx_hash)
+    }
+}
+
+/// Returns the hash the bundle is identified with by the relay.
+fn compute_bundle_hash<Block, BundleHeader>(header: &BundleHeader) -> BundleHash<Block>
+where
+    Block: BlockT,
+    BundleHeader: Encode,
+{
+    <<Block::Header as HeaderT>::Hashing as HashT>::hash_of(header)
+}
+
+/// The execution relay components.
+pub struct ExecutionRelayParams<Block: BlockT, BundleHeader> {
+    /// Handle to compact/resolve the bundles.
+    pub relay: ExecutionRelay<Block, BundleHeader>,
+    /// The server task, to be spawned by the caller.
+    pub server: ExecutionRelayServer<Block>,
+    /// The request/response protocol config, to be registered with the network.
+    pub request_response_config: ProtocolConfig,
+}
+
+/// Sets up the relay components.
+pub fn build_execution_relay<Block, Pool, BundleHeader>(
+    network: Arc<NetworkWrapper>,
+    pool: Arc<Pool>,
+    registry: Option<&Registry>,
+) -> Result<ExecutionRelayParams<Block, BundleHeader>, BlockRelayConfigurationError>
+where
+    Block: BlockT,
+    Pool: TransactionPool<Block = Block, Hash = ExtrinsicHash<Block>> + 'static,
+{
+    build_relay(network, pool, registry)
+}
+
+pub(crate) fn build_relay<Block, BundleHeader>(
+    network: Arc<NetworkWrapper>,
+    transaction_pool: Arc<dyn ExecutionTxPool<Block>>,
+    registry: Option<&Registry>,
+) -> Result<ExecutionRelayParams<Block, BundleHeader>, BlockRelayConfigurationError>
+where
+    Block: BlockT,
+{
+    let (request_response_config, request_receiver) =
+        server_protocol_config(SYNC_PROTOCOL, &SERVER_LIMITS);
+
+    let server_backend = Arc::new(ExecutionServerBackend {
+        recent_bundles: Mutex::new(LruCache::new(RECENT_BUNDLES)),
+        transaction_pool: transaction_pool.clone(),
+    });
+    let metrics = ExecutionClientMetrics::new(registry)
+        .map_err(BlockRelayConfigurationError::PrometheusError)?;
+    let relay = ExecutionRelay {
+        network,
+        protocol_name: SYNC_PROTOCOL.into(),
+        protocol_client: Arc::new(CompactBlockClient::new()),
+        protocol_server: Arc::new(CompactBlockServer::new()),
+        client_backend: Arc::new(ExecutionClientBackend { transaction_pool }),
+        server_backend: server_backend.clone(),
+        metrics: Arc::new(metrics),
+        _phantom_data: Default::default(),
+    };
+
+    let metrics = ExecutionServerMetrics::new(registry)
+        .map_err(BlockRelayConfigurationError::PrometheusError)?;
+    let server = ExecutionRelayServer {
+        protocol: CompactBlockServer::new(),
+        request_receiver,
+        backend: server_backend,
+        metrics,
+    };
+
+    Ok(ExecutionRelayParams {
+        relay,
+        server,
+        request_response_config,
+    })
+}
@@ -0,0 +1,121 @@
+use crate::execution::relay::{build_relay, CompactBundle, ExecutionRelay, ExecutionTxPool};
+use crate::test_utils::{connect, extrinsics, Extrinsic, Routes, TestBlock, TxHash};
+use crate::utils::NetworkWrapper;
+use codec::{Decode, Encode};
+use futures::executor::block_on;
+use sc_network::PeerId;
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+type BundleHeader = Vec<u8>;
+
+struct TestPool(HashMap<TxHash, Extrinsic>);
+
+impl ExecutionTxPool<TestBlock> for TestPool {
+    fn hash_of(&self, extrinsic: &Extrinsic) -> TxHash {
+        BlakeTwo256::hash_of(extrinsic)
+    }
+
+    fn ready_transaction(&self, tx_hash: &TxHash) -> Option<Extrinsic> {
+        self.0.get(tx_hash).cloned()
+    }
+}
+
+struct TestNode {
+    peer_id: PeerId,
+    relay: ExecutionRelay<TestBlock, BundleHeader>,
+    transferred_bytes: Arc<AtomicUsize>,
+}
+
+impl TestNode {
+    /// Starts the node with the given extrinsics in the transaction pool.
+    fn start(routes: &Routes, pool_extrinsics: &[Extrinsic]) -> Self {
+        let pool = TestPool(
+            pool_extrinsics
+                .iter()
+                .map(|extrinsic| (BlakeTwo256::hash_of(extrinsic), extrinsic.clone()))
+                .collect(),
+        );
+        let network = Arc::new(NetworkWrapper::default());
+        let params =
+            build_relay::<TestBlock, BundleHeader>(network.clone(), Arc::new(pool), None).unwrap();
+
+        let inbound_queue = params
+            .request_response_config
+            .inbound_queue
+            .clone()
+            .unwrap();
+        let (peer_id, transferred_bytes) = connect(routes, &network, inbound_queue);
+        let server = params.server;
+        thread::spawn(move || block_on(server.run()));
+
+        Self {
+            peer_id,
+            relay: params.relay,
+            transferred_bytes,
+        }
+    }
+
+    /// Receives the gossiped compact bundle from the peer and resolves it,
+    /// returns the resolved bundle and the number of bytes received/sent.
+    fn receive(&self, from: PeerId, gossip: &[u8]) -> (BundleHeader, Vec<Extrinsic>, usize) {
+        let transferred_before = self.transferred_bytes.load(Ordering::SeqCst);
+        let compact_bundle = CompactBundle::decode(&mut &gossip[..]).unwrap();
+        let (header, extrinsics) = block_on(self.relay.resolve_bundle(from, compact_bundle))
+            .expect("Compact bundle must be resolved");
+        let transferred = self.transferred_bytes.load(Ordering::SeqCst) - transferred_before;
+        (header, extrinsics, gossip.len() + transferred)
+    }
+}
+
+#[test]
+fn compact_bundle_relay() {
+    let routes = Routes::default();
+    let bundle_extrinsics = extrinsics(0..200, 256);
+    let header: BundleHeader = vec![0x55; 128];
+    let full_bytes = (&header, &bundle_extrinsics).encoded_size();
+
+    let alice = TestNode::start(&routes, &bundle_extrinsics);
+    // Bob has most of the extrinsics in the pool
+    let bob = TestNode::start(&routes, &bundle_extrinsics[..180]);
+    // Charlie has none of them
+    let charlie = TestNode::start(&routes, &[]);
+
+    // Alice produces the bundle, Bob receives it from Alice
+    let gossip = alice
+        .relay
+        .compact_bundle(header.clone(), bundle_extrinsics.clone())
+        .encode();
+    let (bob_header, bob_extrinsics, bob_bytes) = bob.receive(alice.peer_id, &gossip);
+    assert_eq!(bob_header, header);
+    assert_eq!(bob_extrinsics, bundle_extrinsics);
+    assert!(
+        bob_bytes * 2 < full_bytes,
+        "Bob: {bob_bytes} bytes relayed, full bundle is {full_bytes} bytes"
+    );
+
+    // Bob relays the bundle further, Charlie has to fetch all the extrinsics
+    // from Bob
+    let gossip = bob
+        .relay
+        .compact_bundle(bob_header, bob_extrinsics)
+        .encode();
+    let (charlie_header, charlie_extrinsics, charlie_bytes) = charlie.receive(bob.peer_id, &gossip);
+    assert_eq!(charlie_header, header);
+    assert_eq!(charlie_extrinsics, bundle_extrinsics);
+    assert!(
+        charlie_bytes < full_bytes * 2,
+        "Charlie: {charlie_bytes} bytes relayed, full bundle is {full_bytes} bytes"
+    );
+
+    // Alice doesn't know the bundle (and the extrinsics) Charlie produced
+    let gossip = charlie
+        .relay
+        .compact_bundle(vec![0x66; 128], extrinsics(0..1, 1024))
+        .encode();
+    let compact_bundle = CompactBundle::decode(&mut gossip.as_slice()).unwrap();
+    assert!(block_on(bob.relay.resolve_bundle(alice.peer_id, compact_bundle)).is_err());
+}
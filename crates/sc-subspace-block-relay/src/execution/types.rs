@@ -0,0 +1,111 @@
+//! Execution related types.
+
+use crate::types::RelayError;
+use crate::utils::{RelayCounter, RelayCounterVec};
+use codec::{Decode, Encode};
+use sp_runtime::traits::Block as BlockT;
+use substrate_prometheus_endpoint::{PrometheusError, Registry};
+
+pub(crate) type BundleHash<Block> = <Block as BlockT>::Hash;
+pub(crate) type ExtrinsicHash<Block> = <Block as BlockT>::Hash;
+pub(crate) type Extrinsic<Block> = <Block as BlockT>::Extrinsic;
+
+const STATUS_LABEL: &str = "status";
+const STATUS_SUCCESS: &str = "success";
+
+const DOWNLOAD_LABEL: &str = "client_download";
+const DOWNLOAD_BUNDLES: &str = "bundles";
+const DOWNLOAD_BYTES: &str = "bytes";
+const DOWNLOAD_SAVED_BYTES: &str = "saved_bytes";
+
+/// The message to the server. The initial response (the compact bundle)
+/// is pushed to the peers by gossip, so the server only handles the
+/// reconcile phase of the protocol.
+#[derive(Encode, Decode)]
+pub(crate) enum ServerMessage<ProtocolRequest> {
+    /// Message to be handled by the protocol
+    ProtocolRequest(ProtocolRequest),
+}
+
+impl<ProtocolRequest> From<ProtocolRequest> for ServerMessage<ProtocolRequest> {
+    fn from(inner: ProtocolRequest) -> ServerMessage<ProtocolRequest> {
+        ServerMessage::ProtocolRequest(inner)
+    }
+}
+
+/// Client side metrics.
+pub(crate) struct ExecutionClientMetrics {
+    pub(crate) requests: RelayCounterVec,
+    pub(crate) downloads: RelayCounterVec,
+    pub(crate) tx_pool_miss: RelayCounter,
+}
+
+impl ExecutionClientMetrics {
+    pub(crate) fn new(registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            requests: RelayCounterVec::new(
+                "relay_execution_client_requests",
+                "Execution relay client request metrics(by completion status)",
+                &[STATUS_LABEL],
+                registry,
+            )?,
+            downloads: RelayCounterVec::new(
+                "relay_execution_client_downloads",
+                "Execution relay client download metrics",
+                &[DOWNLOAD_LABEL],
+                registry,
+            )?,
+            tx_pool_miss: RelayCounter::new(
+                "relay_execution_client_tx_pool_miss",
+                "Number of bundle extrinsics not found in the tx pool",
+                registry,
+            )?,
+        })
+    }
+
+    /// Updates the metrics on successful bundle resolution.
+    pub(crate) fn on_resolve(&self, download_bytes: usize, full_bytes: usize) {
+        self.requests.inc(STATUS_LABEL, STATUS_SUCCESS);
+        self.downloads.inc_by(DOWNLOAD_LABEL, DOWNLOAD_BUNDLES, 1);
+        if let Ok(bytes) = u64::try_from(download_bytes) {
+            self.downloads.inc_by(DOWNLOAD_LABEL, DOWNLOAD_BYTES, bytes);
+        }
+        if let Ok(bytes) = u64::try_from(full_bytes.saturating_sub(download_bytes)) {
+            self.downloads
+                .inc_by(DOWNLOAD_LABEL, DOWNLOAD_SAVED_BYTES, bytes);
+        }
+    }
+
+    /// Updates the metrics on failed bundle resolution.
+    pub(crate) fn on_resolve_fail(&self, err: &RelayError) {
+        self.requests.inc(STATUS_LABEL, err.as_ref());
+    }
+}
+
+/// Server side metrics.
+pub(crate) struct ExecutionServerMetrics {
+    pub(crate) requests: RelayCounterVec,
+}
+
+impl ExecutionServerMetrics {
+    pub(crate) fn new(registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            requests: RelayCounterVec::new(
+                "relay_execution_server_status",
+                "Execution relay server request metrics(by status)",
+                &[STATUS_LABEL],
+                registry,
+            )?,
+        })
+    }
+
+    /// Updates the metrics on a successful request.
+    pub(crate) fn on_request(&self) {
+        self.requests.inc(STATUS_LABEL, STATUS_SUCCESS);
+    }
+
+    /// Updates the metrics on a failed request.
+    pub(crate) fn on_failed_request(&self, err: &RelayError) {
+        self.requests.inc(STATUS_LABEL, err.as_ref());
+    }
+}
@@ -3,11 +3,25 @@
 #![feature(const_option)]
 
 mod consensus;
+mod execution;
 mod protocol;
+#[cfg(test)]
+mod test_utils;
+mod transaction_pool;
 mod types;
 mod utils;
 
-pub use crate::consensus::relay::{build_consensus_relay, BlockRelayConfigurationError};
+pub use crate::consensus::relay::{
+    build_consensus_relay, BlockRelayConfigurationError, ConsensusRelayConfig,
+};
+pub use crate::execution::relay::{
+    build_execution_relay, CompactBundle, ExecutionRelay, ExecutionRelayError,
+    ExecutionRelayParams, ExecutionRelayServer,
+};
+pub use crate::transaction_pool::relay::{
+    build_transaction_pool_relay, TransactionPoolRelay, TransactionPoolRelayError,
+    TransactionPoolRelayParams, TransactionPoolRelayServer,
+};
 pub use crate::utils::NetworkWrapper;
 
 pub(crate) const LOG_TARGET: &str = "block_relay";
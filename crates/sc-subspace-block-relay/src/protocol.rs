@@ -24,10 +24,17 @@
 //!    fields are directly filled by the caller. The protocol backend
 //!    helps fetch blocks/transactions from the substrate backend
 //! 2. Execution
-//!    TODO
-//! 3. Other possible use cases (e.g) reconcile/sync the transaction pool
-//!    between two nodes. In this case, DownloadUnit = transaction pool,
-//!    ProtocolUnit = transaction
+//!    DownloadUnit = Bundle, ProtocolUnit = extrinsics
+//!    The compact bundle (bundle header + the initial response) is gossiped
+//!    to the peers instead of being requested, the receivers then resolve the
+//!    extrinsics from the domain transaction pool and fetch the misses from the
+//!    sender during the reconcile phase
+//! 3. Transaction pool
+//!    DownloadUnit = transaction pool snapshot, ProtocolUnit = transaction
+//!    The server returns the short Ids of the transactions in the snapshot
+//!    of its transaction pool, the client reconciles them with the local
+//!    transaction pool and fetches only the missing transactions during the
+//!    reconcile phase
 //!
 //! The download has two phases:
 //! -  Initial request/response
@@ -0,0 +1,114 @@
+//! In-process network shared by the relay tests.
+
+use crate::utils::NetworkWrapper;
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use parking_lot::Mutex;
+use sc_network::request_responses::{IfDisconnected, IncomingRequest};
+use sc_network::types::ProtocolName;
+use sc_network::{NetworkRequest, PeerId, RequestFailure};
+use sp_runtime::testing::{Block, ExtrinsicWrapper};
+use sp_runtime::traits::Block as BlockT;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+pub(crate) type Extrinsic = ExtrinsicWrapper<Vec<u8>>;
+pub(crate) type TestBlock = Block<Extrinsic>;
+pub(crate) type TxHash = <TestBlock as BlockT>::Hash;
+/// Relay servers of the nodes in the process, by peer Id.
+pub(crate) type Routes = Arc<Mutex<HashMap<PeerId, async_channel::Sender<IncomingRequest>>>>;
+
+/// Delivers the requests to the relay servers of the other nodes in the
+/// process, counting the bytes sent/received.
+struct TestNetwork {
+    local_peer: PeerId,
+    routes: Routes,
+    transferred_bytes: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl NetworkRequest for TestNetwork {
+    async fn request(
+        &self,
+        target: PeerId,
+        protocol: ProtocolName,
+        request: Vec<u8>,
+        connect: IfDisconnected,
+    ) -> Result<Vec<u8>, RequestFailure> {
+        let (tx, rx) = oneshot::channel();
+        self.start_request(target, protocol, request, tx, connect);
+        rx.await.map_err(|_canceled| RequestFailure::Refused)?
+    }
+
+    fn start_request(
+        &self,
+        target: PeerId,
+        _protocol: ProtocolName,
+        request: Vec<u8>,
+        tx: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
+        _connect: IfDisconnected,
+    ) {
+        let Some(server) = self.routes.lock().get(&target).cloned() else {
+            let _ = tx.send(Err(RequestFailure::NotConnected));
+            return;
+        };
+
+        self.transferred_bytes
+            .fetch_add(request.len(), Ordering::SeqCst);
+        let (response_tx, response_rx) = oneshot::channel();
+        server
+            .try_send(IncomingRequest {
+                peer: self.local_peer,
+                payload: request,
+                pending_response: response_tx,
+            })
+            .expect("Server queue is not full in tests; qed");
+
+        let transferred_bytes = self.transferred_bytes.clone();
+        thread::spawn(move || {
+            let result = block_on(response_rx)
+                .map_err(|_canceled| RequestFailure::Refused)
+                .and_then(|response| response.result.map_err(|()| RequestFailure::Refused));
+            if let Ok(response) = &result {
+                transferred_bytes.fetch_add(response.len(), Ordering::SeqCst);
+            }
+            let _ = tx.send(result);
+        });
+    }
+}
+
+/// Adds the node with the given relay server inbound queue to the routes and
+/// sets up its network, returns the peer Id of the node and the counter of
+/// the bytes received/sent by the node.
+pub(crate) fn connect(
+    routes: &Routes,
+    network: &NetworkWrapper,
+    inbound_queue: async_channel::Sender<IncomingRequest>,
+) -> (PeerId, Arc<AtomicUsize>) {
+    let peer_id = PeerId::random();
+    routes.lock().insert(peer_id, inbound_queue);
+
+    let transferred_bytes = Arc::new(AtomicUsize::new(0));
+    network.set(Arc::new(TestNetwork {
+        local_peer: peer_id,
+        routes: routes.clone(),
+        transferred_bytes: transferred_bytes.clone(),
+    }));
+
+    (peer_id, transferred_bytes)
+}
+
+/// Extrinsics of the given size with the index in the range as the prefix.
+pub(crate) fn extrinsics(range: Range<u32>, size: usize) -> Vec<Extrinsic> {
+    range
+        .map(|index| {
+            let mut data = index.to_le_bytes().to_vec();
+            data.resize(size, 0xab);
+            ExtrinsicWrapper::from(data)
+        })
+        .collect()
+}
@@ -0,0 +1,6 @@
+//! Relay implementation for the transaction pool sync between the peers.
+
+pub mod relay;
+#[cfg(test)]
+mod tests;
+mod types;
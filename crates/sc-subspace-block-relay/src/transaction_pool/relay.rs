@@ -0,0 +1,575 @@
+//! Transaction pool relay implementation.
+//!
+//! Used to sync the transaction pool from the peers (e.g) after the node
+//! restarts, instead of waiting for the pending transactions to be gossiped
+//! again. The server takes a snapshot of its ready transactions and returns
+//! the short Ids of the transactions in the snapshot. The client reconciles
+//! the list with its own transaction pool and only fetches the missing
+//! transactions (reconcile phase). The fetched transactions are submitted
+//! to the local transaction pool as external transactions, so they go
+//! through the same validation (including the pre-validation) as the
+//! transactions received by gossip.
+
+use crate::consensus::relay::BlockRelayConfigurationError;
+use crate::protocol::compact_block::{
+    CompactBlockClient, CompactBlockRequest, CompactBlockResponse, CompactBlockServer,
+};
+use crate::protocol::{
+    ClientBackend, ProtocolClient, ProtocolServer, ProtocolUnitInfo, ServerBackend,
+};
+use crate::transaction_pool::types::{
+    Extrinsic, ServerMessage, ShortTxId, SnapshotId, TransactionPoolClientMetrics,
+    TransactionPoolServerMetrics, TxHash,
+};
+use crate::types::RelayError;
+use crate::utils::{
+    decode_request, send_response, server_protocol_config, NetworkWrapper, ServerProtocolLimits,
+};
+use crate::LOG_TARGET;
+use async_trait::async_trait;
+use codec::Encode;
+use futures::stream::StreamExt;
+use lru::LruCache;
+use parking_lot::Mutex;
+use sc_client_api::HeaderBackend;
+use sc_network::request_responses::{IncomingRequest, ProtocolConfig};
+use sc_network::types::ProtocolName;
+use sc_network::PeerId;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool, TransactionSource};
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::Block as BlockT;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use substrate_prometheus_endpoint::Registry;
+use tracing::{debug, info, trace};
+
+const SYNC_PROTOCOL: &str = "/subspace/tx-pool-relay/1";
+
+/// Limits of the protocol. The transaction pool is synced by the peers
+/// after the restart, from a few peers each (initial and reconcile request
+/// per sync), so the requests are rare.
+const SERVER_LIMITS: ServerProtocolLimits = ServerProtocolLimits {
+    inbound_queue_size: NonZeroUsize::new(32).expect("Not zero; qed"),
+    // Short Ids (8 bytes each) of the missing transactions of the snapshot
+    max_request_size: 128 * 1024,
+    // The transactions of the snapshot, along with the encoding overhead
+    max_response_size: MAX_SNAPSHOT_SIZE.get() as u64 + 1024 * 1024,
+    request_timeout: Duration::from_secs(20),
+};
+
+/// Number of recent snapshots kept around to serve the reconcile
+/// requests from the peers. At most one snapshot is taken per
+/// [`SNAPSHOT_REUSE_INTERVAL`], this covers the requests made within
+/// a minute or so.
+const RECENT_SNAPSHOTS: NonZeroUsize = NonZeroUsize::new(32).expect("Not zero; qed");
+
+/// The snapshot is shared by the peers requesting it within the interval,
+/// so that the ready transactions are not copied for every request (e.g.
+/// when many peers restart at once).
+pub(super) const SNAPSHOT_REUSE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maximum transactions in the snapshot.
+const MAX_SNAPSHOT_TRANSACTIONS: NonZeroUsize = NonZeroUsize::new(8192).expect("Not zero; qed");
+
+/// Maximum total size of the transactions in the snapshot (bytes), so that
+/// the response to the reconcile request stays within the response limit.
+const MAX_SNAPSHOT_SIZE: NonZeroUsize = NonZeroUsize::new(8 * 1024 * 1024).expect("Not zero; qed");
+
+/// If the encoded size of the extrinsic is less than the threshold,
+/// return the full extrinsic along with the short Id.
+const TX_SIZE_THRESHOLD: NonZeroUsize = NonZeroUsize::new(32).expect("Not zero; qed");
+
+type CompactPoolRequest = CompactBlockRequest<SnapshotId, ShortTxId>;
+type CompactPoolResponse<Block> = CompactBlockResponse<SnapshotId, ShortTxId, Extrinsic<Block>>;
+
+/// Error returned when the transaction pool could not be synced from the peer.
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct TransactionPoolRelayError(#[from] RelayError);
+
+/// Transaction pool operations used by the transaction pool relay.
+#[async_trait]
+pub(crate) trait RelayTransactionPool<Block: BlockT>: Send + Sync {
+    /// Returns the ready transactions.
+    fn ready_transactions(&self) -> Vec<(TxHash<Block>, Extrinsic<Block>)>;
+
+    /// Returns the future transactions.
+    fn future_transactions(&self) -> Vec<(TxHash<Block>, Extrinsic<Block>)>;
+
+    /// Submits the transactions received from the peer, returns the
+    /// import result for each of the transactions.
+    async fn import_transactions(
+        &self,
+        extrinsics: Vec<Extrinsic<Block>>,
+    ) -> Vec<Result<TxHash<Block>, String>>;
+}
+
+/// The transaction pool along with the client, used to submit the
+/// transactions at the best block.
+struct PoolWithClient<Client, Pool> {
+    client: Arc<Client>,
+    pool: Arc<Pool>,
+}
+
+#[async_trait]
+impl<Block, Client, Pool> RelayTransactionPool<Block> for PoolWithClient<Client, Pool>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block> + 'static,
+    Pool: TransactionPool<Block = Block, Hash = TxHash<Block>> + 'static,
+{
+    fn ready_transactions(&self) -> Vec<(TxHash<Block>, Extrinsic<Block>)> {
+        self.pool
+            .ready()
+            .map(|in_pool_tx| (*in_pool_tx.hash(), in_pool_tx.data().clone()))
+            .collect()
+    }
+
+    fn future_transactions(&self) -> Vec<(TxHash<Block>, Extrinsic<Block>)> {
+        self.pool
+            .futures()
+            .into_iter()
+            .map(|in_pool_tx| (*in_pool_tx.hash(), in_pool_tx.data().clone()))
+            .collect()
+    }
+
+    async fn import_transactions(
+        &self,
+        extrinsics: Vec<Extrinsic<Block>>,
+    ) -> Vec<Result<TxHash<Block>, String>> {
+        let num_extrinsics = extrinsics.len();
+        let at = BlockId::Hash(self.client.info().best_hash);
+        // Submitted as external transactions, the same way as the transactions
+        // received from the transaction gossip.
+        match self
+            .pool
+            .submit_at(&at, TransactionSource::External, extrinsics)
+            .await
+        {
+            Ok(results) => results
+                .into_iter()
+                .map(|result| result.map_err(|err| err.to_string()))
+                .collect(),
+            Err(err) => vec![Err(err.to_string()); num_extrinsics],
+        }
+    }
+}
+
+/// The client side of the transaction pool relay, used to sync the
+/// transaction pool from the peers.
+pub struct TransactionPoolRelay<Block: BlockT> {
+    network: Arc<NetworkWrapper>,
+    protocol_name: ProtocolName,
+    protocol_client: Arc<CompactBlockClient<SnapshotId, ShortTxId, Extrinsic<Block>>>,
+    transaction_pool: Arc<dyn RelayTransactionPool<Block>>,
+    metrics: Arc<TransactionPoolClientMetrics>,
+}
+
+impl<Block: BlockT> Clone for TransactionPoolRelay<Block> {
+    fn clone(&self) -> Self {
+        Self {
+            network: self.network.clone(),
+            protocol_name: self.protocol_name.clone(),
+            protocol_client: self.protocol_client.clone(),
+            transaction_pool: self.transaction_pool.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<Block: BlockT> TransactionPoolRelay<Block> {
+    /// Syncs the transaction pool from the peer: fetches the transactions
+    /// missing in the local transaction pool and submits them to the pool.
+    /// Returns the number of the imported transactions.
+    pub async fn sync_from_peer(&self, who: PeerId) -> Result<usize, TransactionPoolRelayError> {
+        match self.sync(who).await {
+            Ok(imported) => Ok(imported),
+            Err(error) => {
+                debug!(
+                    target: LOG_TARGET,
+                    peer = ?who,
+                    ?error,
+                    "sync_from_peer failed"
+                );
+                self.metrics.on_sync_fail(&error);
+                Err(error.into())
+            }
+        }
+    }
+
+    async fn sync(&self, who: PeerId) -> Result<usize, RelayError> {
+        let start_ts = Instant::now();
+        let network_peer_handle = self
+            .network
+            .network_peer_handle(self.protocol_name.clone(), who)?;
+
+        // The future transactions are included as well, no point in
+        // fetching them again.
+        let backend = TransactionPoolClientBackend::<Block> {
+            local_transactions: self
+                .transaction_pool
+                .ready_transactions()
+                .into_iter()
+                .chain(self.transaction_pool.future_transactions())
+                .map(|(tx_hash, extrinsic)| (short_tx_id::<Block>(&tx_hash), extrinsic))
+                .collect(),
+        };
+
+        // Request the snapshot of the peer transaction pool
+        let initial_request =
+            ServerMessage::InitialRequest(self.protocol_client.build_initial_request(&backend));
+        let initial_response: CompactPoolResponse<Block> =
+            network_peer_handle.request(initial_request).await?;
+        let initial_bytes = initial_response.encoded_size();
+
+        // Resolve the snapshot against the local transaction pool, and
+        // fetch the misses from the peer
+        let (snapshot_id, resolved) = self
+            .protocol_client
+            .resolve_initial_response::<ServerMessage<CompactPoolRequest>>(
+                initial_response,
+                &network_peer_handle,
+                &backend,
+            )
+            .await?;
+
+        let mut local_miss = 0;
+        let snapshot_size = resolved.len();
+        let missing: Vec<_> = resolved
+            .into_iter()
+            .filter_map(|entry| {
+                if !entry.locally_resolved {
+                    let tx_size = entry.protocol_unit.encoded_size();
+                    trace!(
+                        target: LOG_TARGET,
+                        %snapshot_id,
+                        short_tx_id = %entry.protocol_unit_id,
+                        %tx_size,
+                        "sync_from_peer: local miss"
+                    );
+                    self.metrics.tx_pool_miss.inc();
+                    local_miss += tx_size;
+                }
+
+                // Small transactions are returned along with the initial
+                // response, these need to be imported as well
+                (!backend
+                    .local_transactions
+                    .contains_key(&entry.protocol_unit_id))
+                .then_some(entry.protocol_unit)
+            })
+            .collect();
+
+        let downloaded = missing.len();
+        let mut imported = 0;
+        let mut rejected = 0;
+        for result in self.transaction_pool.import_transactions(missing).await {
+            match result {
+                Ok(tx_hash) => {
+                    trace!(
+                        target: LOG_TARGET,
+                        ?tx_hash,
+                        "sync_from_peer: imported"
+                    );
+                    imported += 1;
+                }
+                Err(error) => {
+                    trace!(
+                        target: LOG_TARGET,
+                        %error,
+                        "sync_from_peer: rejected"
+                    );
+                    rejected += 1;
+                }
+            }
+        }
+
+        let download_bytes = initial_bytes + local_miss;
+        self.metrics
+            .on_sync(download_bytes, downloaded, imported, rejected);
+        debug!(
+            target: LOG_TARGET,
+            peer = ?who,
+            %snapshot_id,
+            %snapshot_size,
+            %downloaded,
+            %imported,
+            %rejected,
+            %download_bytes,
+            duration = ?start_ts.elapsed(),
+            "tx_pool_sync",
+        );
+        Ok(imported)
+    }
+}
+
+/// The server side of the transaction pool relay, serves the snapshots
+/// of the local transaction pool to the peers.
+pub struct TransactionPoolRelayServer<Block: BlockT> {
+    protocol: CompactBlockServer<SnapshotId, ShortTxId, Extrinsic<Block>>,
+    request_receiver: async_channel::Receiver<IncomingRequest>,
+    backend: Arc<TransactionPoolServerBackend<Block>>,
+    metrics: TransactionPoolServerMetrics,
+}
+
+impl<Block: BlockT> TransactionPoolRelayServer<Block> {
+    /// Runs the server, processing the incoming requests.
+    pub async fn run(mut self) {
+        info!(
+            target: LOG_TARGET,
+            "relay::transaction pool server: starting"
+        );
+        while let Some(request) = self.request_receiver.next().await {
+            self.on_request(request);
+        }
+    }
+
+    /// Handles the received request from the client side
+    fn on_request(&self, request: IncomingRequest) {
+        // Drop the request in case of errors and let the client time out.
+        let IncomingRequest {
+            peer,
+            payload,
+            pending_response,
+        } = request;
+        let Some(server_msg) = decode_request::<ServerMessage<CompactPoolRequest>>(peer, &payload)
+        else {
+            return;
+        };
+
+        let ret = match server_msg {
+            ServerMessage::InitialRequest(req) => self.on_initial_request(req),
+            ServerMessage::ProtocolRequest(req) => self
+                .protocol
+                .on_request(req, self.backend.as_ref())
+                .map(|response| response.encode()),
+        };
+
+        match ret {
+            Ok(response) => {
+                self.metrics.on_request();
+                send_response(peer, response, pending_response);
+                trace!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    "server: request processed from"
+                );
+            }
+            Err(error) => {
+                self.metrics.on_failed_request(&error);
+                debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?error,
+                    "Server error"
+                );
+            }
+        }
+    }
+
+    /// Takes the snapshot of the transaction pool and builds the
+    /// initial response
+    fn on_initial_request(&self, request: CompactPoolRequest) -> Result<Vec<u8>, RelayError> {
+        let snapshot_id = self.backend.take_snapshot();
+        self.protocol
+            .build_initial_response(&snapshot_id, request, self.backend.as_ref())
+            .map(|response| response.encode())
+    }
+}
+
+/// The client backend, built from the local transaction pool for
+/// each sync.
+struct TransactionPoolClientBackend<Block: BlockT> {
+    local_transactions: HashMap<ShortTxId, Extrinsic<Block>>,
+}
+
+impl<Block: BlockT> ClientBackend<ShortTxId, Extrinsic<Block>>
+    for TransactionPoolClientBackend<Block>
+{
+    fn protocol_unit(&self, short_tx_id: &ShortTxId) -> Option<Extrinsic<Block>> {
+        self.local_transactions.get(short_tx_id).cloned()
+    }
+}
+
+/// Snapshot of the ready transactions, in the order returned by the
+/// transaction pool.
+struct Snapshot<Block: BlockT> {
+    transactions: Vec<(ShortTxId, Extrinsic<Block>)>,
+    index: HashMap<ShortTxId, usize>,
+}
+
+/// The server backend.
+struct TransactionPoolServerBackend<Block: BlockT> {
+    next_snapshot_id: AtomicU64,
+    /// The most recent snapshot and the time it was taken at.
+    latest_snapshot: Mutex<Option<(SnapshotId, Instant)>>,
+    recent_snapshots: Mutex<LruCache<SnapshotId, Snapshot<Block>>>,
+    transaction_pool: Arc<dyn RelayTransactionPool<Block>>,
+}
+
+impl<Block: BlockT> TransactionPoolServerBackend<Block> {
+    /// Takes the snapshot of the ready transactions, to serve the
+    /// reconcile requests against it. The snapshot taken less than
+    /// [`SNAPSHOT_REUSE_INTERVAL`] ago is returned instead, if any.
+    fn take_snapshot(&self) -> SnapshotId {
+        let mut latest_snapshot = self.latest_snapshot.lock();
+        if let Some((snapshot_id, taken_at)) = *latest_snapshot {
+            if taken_at.elapsed() < SNAPSHOT_REUSE_INTERVAL
+                && self.recent_snapshots.lock().contains(&snapshot_id)
+            {
+                return snapshot_id;
+            }
+        }
+
+        let mut snapshot_size = 0;
+        let transactions: Vec<_> = self
+            .transaction_pool
+            .ready_transactions()
+            .into_iter()
+            .take(MAX_SNAPSHOT_TRANSACTIONS.get())
+            .take_while(|(_tx_hash, extrinsic)| {
+                snapshot_size += extrinsic.encoded_size();
+                snapshot_size <= MAX_SNAPSHOT_SIZE.get()
+            })
+            .map(|(tx_hash, extrinsic)| (short_tx_id::<Block>(&tx_hash), extrinsic))
+            .collect();
+        let index = transactions
+            .iter()
+            .enumerate()
+            .map(|(position, (short_tx_id, _extrinsic))| (*short_tx_id, position))
+            .collect();
+        let snapshot = Snapshot {
+            transactions,
+            index,
+        };
+        let snapshot_id = self.next_snapshot_id.fetch_add(1, Ordering::Relaxed);
+        self.recent_snapshots.lock().put(snapshot_id, snapshot);
+        *latest_snapshot = Some((snapshot_id, Instant::now()));
+        snapshot_id
+    }
+}
+
+impl<Block: BlockT> ServerBackend<SnapshotId, ShortTxId, Extrinsic<Block>>
+    for TransactionPoolServerBackend<Block>
+{
+    fn download_unit_members(
+        &self,
+        snapshot_id: &SnapshotId,
+    ) -> Result<Vec<ProtocolUnitInfo<ShortTxId, Extrinsic<Block>>>, RelayError> {
+        let mut recent_snapshots = self.recent_snapshots.lock();
+        let snapshot = recent_snapshots
+            .get(snapshot_id)
+            .ok_or_else(|| RelayError::SnapshotNotFound(format!("{snapshot_id}")))?;
+        Ok(snapshot
+            .transactions
+            .iter()
+            .map(|(short_tx_id, extrinsic)| {
+                let send_tx = extrinsic.encoded_size() <= TX_SIZE_THRESHOLD.get();
+                ProtocolUnitInfo {
+                    id: *short_tx_id,
+                    unit: if send_tx {
+                        Some(extrinsic.clone())
+                    } else {
+                        None
+                    },
+                }
+            })
+            .collect())
+    }
+
+    fn protocol_unit(
+        &self,
+        snapshot_id: &SnapshotId,
+        short_tx_id: &ShortTxId,
+    ) -> Option<Extrinsic<Block>> {
+        self.recent_snapshots
+            .lock()
+            .get(snapshot_id)
+            .and_then(|snapshot| {
+                snapshot
+                    .index
+                    .get(short_tx_id)
+                    .map(|position| snapshot.transactions[*position].1.clone())
+            })
+    }
+}
+
+/// Returns the short Id of the transaction: the leading bytes of the
+/// transaction hash. In the unlikely case of a collision with a local
+/// transaction, the peer transaction is just not fetched.
+fn short_tx_id<Block: BlockT>(tx_hash: &TxHash<Block>) -> ShortTxId {
+    let mut short_tx_id = [0u8; 8];
+    let len = tx_hash.as_ref().len().min(short_tx_id.len());
+    short_tx_id[..len].copy_from_slice(&tx_hash.as_ref()[..len]);
+    ShortTxId::from_le_bytes(short_tx_id)
+}
+
+/// The transaction pool relay components.
+pub struct TransactionPoolRelayParams<Block: BlockT> {
+    /// Handle to sync the transaction pool from the peers.
+    pub relay: TransactionPoolRelay<Block>,
+    /// The server task, to be spawned by the caller.
+    pub server: TransactionPoolRelayServer<Block>,
+    /// The request/response protocol config, to be registered with the network.
+    pub request_response_config: ProtocolConfig,
+}
+
+/// Sets up the relay components.
+pub fn build_transaction_pool_relay<Block, Client, Pool>(
+    network: Arc<NetworkWrapper>,
+    client: Arc<Client>,
+    pool: Arc<Pool>,
+    registry: Option<&Registry>,
+) -> Result<TransactionPoolRelayParams<Block>, BlockRelayConfigurationError>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block> + 'static,
+    Pool: TransactionPool<Block = Block, Hash = TxHash<Block>> + 'static,
+{
+    build_relay(network, Arc::new(PoolWithClient { client, pool }), registry)
+}
+
+pub(crate) fn build_relay<Block>(
+    network: Arc<NetworkWrapper>,
+    transaction_pool: Arc<dyn RelayTransactionPool<Block>>,
+    registry: Option<&Registry>,
+) -> Result<TransactionPoolRelayParams<Block>, BlockRelayConfigurationError>
+where
+    Block: BlockT,
+{
+    let (request_response_config, request_receiver) =
+        server_protocol_config(SYNC_PROTOCOL, &SERVER_LIMITS);
+
+    let metrics = TransactionPoolClientMetrics::new(registry)
+        .map_err(BlockRelayConfigurationError::PrometheusError)?;
+    let relay = TransactionPoolRelay {
+        network,
+        protocol_name: SYNC_PROTOCOL.into(),
+        protocol_client: Arc::new(CompactBlockClient::new()),
+        transaction_pool: transaction_pool.clone(),
+        metrics: Arc::new(metrics),
+    };
+
+    let metrics = TransactionPoolServerMetrics::new(registry)
+        .map_err(BlockRelayConfigurationError::PrometheusError)?;
+    let server = TransactionPoolRelayServer {
+        protocol: CompactBlockServer::new(),
+        request_receiver,
+        backend: Arc::new(TransactionPoolServerBackend {
+            next_snapshot_id: AtomicU64::new(0),
+            latest_snapshot: Mutex::new(None),
+            recent_snapshots: Mutex::new(LruCache::new(RECENT_SNAPSHOTS)),
+            transaction_pool,
+        }),
+        metrics,
+    };
+
+    Ok(TransactionPoolRelayParams {
+        relay,
+        server,
+        request_response_config,
+    })
+}
@@ -0,0 +1,162 @@
+use crate::test_utils::{connect, extrinsics, Extrinsic, Routes, TestBlock, TxHash};
+use crate::transaction_pool::relay::{
+    build_relay, RelayTransactionPool, TransactionPoolRelay, SNAPSHOT_REUSE_INTERVAL,
+};
+use crate::utils::NetworkWrapper;
+use async_trait::async_trait;
+use codec::Encode;
+use futures::executor::block_on;
+use parking_lot::Mutex;
+use sc_network::PeerId;
+use sp_runtime::testing::ExtrinsicWrapper;
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// Marks the extrinsics rejected by the pre-validation.
+const INVALID_MARKER: u8 = 0xff;
+
+#[derive(Default)]
+struct TestPool(Mutex<Vec<(TxHash, Extrinsic)>>);
+
+impl TestPool {
+    fn contains(&self, extrinsic: &Extrinsic) -> bool {
+        let tx_hash = BlakeTwo256::hash_of(extrinsic);
+        self.0.lock().iter().any(|(hash, _)| *hash == tx_hash)
+    }
+}
+
+#[async_trait]
+impl RelayTransactionPool<TestBlock> for TestPool {
+    fn ready_transactions(&self) -> Vec<(TxHash, Extrinsic)> {
+        self.0.lock().clone()
+    }
+
+    fn future_transactions(&self) -> Vec<(TxHash, Extrinsic)> {
+        Vec::new()
+    }
+
+    async fn import_transactions(&self, extrinsics: Vec<Extrinsic>) -> Vec<Result<TxHash, String>> {
+        extrinsics
+            .into_iter()
+            .map(|extrinsic| {
+                if extrinsic.first() == Some(&INVALID_MARKER) {
+                    return Err("Pre-validation failed".to_string());
+                }
+                let tx_hash = BlakeTwo256::hash_of(&extrinsic);
+                self.0.lock().push((tx_hash, extrinsic));
+                Ok(tx_hash)
+            })
+            .collect()
+    }
+}
+
+struct TestNode {
+    peer_id: PeerId,
+    pool: Arc<TestPool>,
+    relay: TransactionPoolRelay<TestBlock>,
+    transferred_bytes: Arc<AtomicUsize>,
+}
+
+impl TestNode {
+    /// Starts the node with the given extrinsics in the transaction pool.
+    fn start(routes: &Routes, pool_extrinsics: &[Extrinsic]) -> Self {
+        let pool = Arc::new(TestPool::default());
+        pool.0.lock().extend(
+            pool_extrinsics
+                .iter()
+                .map(|extrinsic| (BlakeTwo256::hash_of(extrinsic), extrinsic.clone())),
+        );
+        let network = Arc::new(NetworkWrapper::default());
+        let params = build_relay::<TestBlock>(network.clone(), pool.clone(), None).unwrap();
+
+        let inbound_queue = params
+            .request_response_config
+            .inbound_queue
+            .clone()
+            .unwrap();
+        let (peer_id, transferred_bytes) = connect(routes, &network, inbound_queue);
+        let server = params.server;
+        thread::spawn(move || block_on(server.run()));
+
+        Self {
+            peer_id,
+            pool,
+            relay: params.relay,
+            transferred_bytes,
+        }
+    }
+
+    /// Syncs the transaction pool from the peer, returns the number of
+    /// imported transactions and the number of bytes received/sent.
+    fn sync_from(&self, peer_id: PeerId) -> (usize, usize) {
+        let transferred_before = self.transferred_bytes.load(Ordering::SeqCst);
+        let imported =
+            block_on(self.relay.sync_from_peer(peer_id)).expect("Transaction pool must be synced");
+        let transferred = self.transferred_bytes.load(Ordering::SeqCst) - transferred_before;
+        (imported, transferred)
+    }
+}
+
+#[test]
+fn transaction_pool_relay() {
+    let routes = Routes::default();
+    let pool_extrinsics = extrinsics(0..200, 256);
+    let full_bytes = pool_extrinsics.encoded_size();
+
+    let alice = TestNode::start(&routes, &pool_extrinsics);
+    // Bob restarted and has only some of the transactions, along with a
+    // few transactions Alice doesn't have
+    let mut bob_extrinsics = pool_extrinsics[..180].to_vec();
+    bob_extrinsics.extend(extrinsics(1000..1010, 256));
+    let bob = TestNode::start(&routes, &bob_extrinsics);
+    // Charlie has an empty transaction pool
+    let charlie = TestNode::start(&routes, &[]);
+
+    // Bob only fetches the transactions missing in the pool
+    let (imported, bob_bytes) = bob.sync_from(alice.peer_id);
+    assert_eq!(imported, 20);
+    assert!(pool_extrinsics.iter().all(|xt| bob.pool.contains(xt)));
+    assert!(
+        bob_bytes * 2 < full_bytes,
+        "Bob: {bob_bytes} bytes relayed, full pool is {full_bytes} bytes"
+    );
+
+    // Syncing again is a no-op
+    let (imported, _) = bob.sync_from(alice.peer_id);
+    assert_eq!(imported, 0);
+
+    // Charlie fetches all the transactions from Bob
+    let (imported, charlie_bytes) = charlie.sync_from(bob.peer_id);
+    assert_eq!(imported, 210);
+    assert!(bob_extrinsics.iter().all(|xt| charlie.pool.contains(xt)));
+    assert!(
+        charlie_bytes < full_bytes * 2,
+        "Charlie: {charlie_bytes} bytes relayed, full pool is {full_bytes} bytes"
+    );
+
+    // Alice shares the snapshot taken for Bob within the reuse interval, so
+    // the transactions added afterwards are not visible to Charlie yet
+    let valid = extrinsics(2000..2001, 256).remove(0);
+    let mut invalid = vec![INVALID_MARKER; 256];
+    invalid[1] = 0x01;
+    let invalid = ExtrinsicWrapper::from(invalid);
+    alice.pool.0.lock().extend([
+        (BlakeTwo256::hash_of(&valid), valid.clone()),
+        (BlakeTwo256::hash_of(&invalid), invalid.clone()),
+    ]);
+    let (imported, _) = charlie.sync_from(alice.peer_id);
+    assert_eq!(imported, 0);
+    assert!(!charlie.pool.contains(&valid));
+
+    // The transactions rejected by the pre-validation are not imported
+    thread::sleep(SNAPSHOT_REUSE_INTERVAL);
+    let (imported, _) = charlie.sync_from(alice.peer_id);
+    assert_eq!(imported, 1);
+    assert!(charlie.pool.contains(&valid));
+    assert!(!charlie.pool.contains(&invalid));
+
+    // Unknown peer
+    assert!(block_on(charlie.relay.sync_from_peer(PeerId::random())).is_err());
+}
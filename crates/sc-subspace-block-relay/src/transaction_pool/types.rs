@@ -0,0 +1,143 @@
+//! Transaction pool sync related types.
+
+use crate::types::RelayError;
+use crate::utils::{RelayCounter, RelayCounterVec};
+use codec::{Decode, Encode};
+use sp_runtime::traits::Block as BlockT;
+use substrate_prometheus_endpoint::{PrometheusError, Registry};
+
+pub(crate) type TxHash<Block> = <Block as BlockT>::Hash;
+pub(crate) type Extrinsic<Block> = <Block as BlockT>::Extrinsic;
+
+/// Identifies the snapshot of the transaction pool taken by the server.
+pub(crate) type SnapshotId = u64;
+
+/// Short transaction Id used during the reconciliation, derived from
+/// the transaction hash.
+pub(crate) type ShortTxId = u64;
+
+const STATUS_LABEL: &str = "status";
+const STATUS_SUCCESS: &str = "success";
+
+const DOWNLOAD_LABEL: &str = "client_download";
+const DOWNLOAD_SNAPSHOTS: &str = "snapshots";
+const DOWNLOAD_BYTES: &str = "bytes";
+const DOWNLOAD_TRANSACTIONS: &str = "transactions";
+
+const IMPORT_LABEL: &str = "import";
+const IMPORT_SUCCESS: &str = "imported";
+const IMPORT_REJECTED: &str = "rejected";
+
+/// The message to the server
+#[allow(clippy::enum_variant_names)]
+#[derive(Encode, Decode)]
+pub(crate) enum ServerMessage<ProtocolRequest> {
+    /// Initial message, the server takes the snapshot of the
+    /// transaction pool and returns the compact response
+    InitialRequest(ProtocolRequest),
+
+    /// Message to be handled by the protocol
+    ProtocolRequest(ProtocolRequest),
+}
+
+impl<ProtocolRequest> From<ProtocolRequest> for ServerMessage<ProtocolRequest> {
+    fn from(inner: ProtocolRequest) -> ServerMessage<ProtocolRequest> {
+        ServerMessage::ProtocolRequest(inner)
+    }
+}
+
+/// Client side metrics.
+pub(crate) struct TransactionPoolClientMetrics {
+    pub(crate) requests: RelayCounterVec,
+    pub(crate) downloads: RelayCounterVec,
+    pub(crate) imports: RelayCounterVec,
+    pub(crate) tx_pool_miss: RelayCounter,
+}
+
+impl TransactionPoolClientMetrics {
+    pub(crate) fn new(registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            requests: RelayCounterVec::new(
+                "relay_tx_pool_client_requests",
+                "Transaction pool relay client request metrics(by completion status)",
+                &[STATUS_LABEL],
+                registry,
+            )?,
+            downloads: RelayCounterVec::new(
+                "relay_tx_pool_client_downloads",
+                "Transaction pool relay client download metrics",
+                &[DOWNLOAD_LABEL],
+                registry,
+            )?,
+            imports: RelayCounterVec::new(
+                "relay_tx_pool_client_imports",
+                "Transaction pool relay client import metrics(by import result)",
+                &[IMPORT_LABEL],
+                registry,
+            )?,
+            tx_pool_miss: RelayCounter::new(
+                "relay_tx_pool_client_tx_pool_miss",
+                "Number of peer transactions not found in the local tx pool",
+                registry,
+            )?,
+        })
+    }
+
+    /// Updates the metrics on successful sync with the peer.
+    pub(crate) fn on_sync(
+        &self,
+        download_bytes: usize,
+        downloaded: usize,
+        imported: usize,
+        rejected: usize,
+    ) {
+        self.requests.inc(STATUS_LABEL, STATUS_SUCCESS);
+        self.downloads.inc_by(DOWNLOAD_LABEL, DOWNLOAD_SNAPSHOTS, 1);
+        if let Ok(bytes) = u64::try_from(download_bytes) {
+            self.downloads.inc_by(DOWNLOAD_LABEL, DOWNLOAD_BYTES, bytes);
+        }
+        if let Ok(downloaded) = u64::try_from(downloaded) {
+            self.downloads
+                .inc_by(DOWNLOAD_LABEL, DOWNLOAD_TRANSACTIONS, downloaded);
+        }
+        if let Ok(imported) = u64::try_from(imported) {
+            self.imports.inc_by(IMPORT_LABEL, IMPORT_SUCCESS, imported);
+        }
+        if let Ok(rejected) = u64::try_from(rejected) {
+            self.imports.inc_by(IMPORT_LABEL, IMPORT_REJECTED, rejected);
+        }
+    }
+
+    /// Updates the metrics on failed sync with the peer.
+    pub(crate) fn on_sync_fail(&self, err: &RelayError) {
+        self.requests.inc(STATUS_LABEL, err.as_ref());
+    }
+}
+
+/// Server side metrics.
+pub(crate) struct TransactionPoolServerMetrics {
+    pub(crate) requests: RelayCounterVec,
+}
+
+impl TransactionPoolServerMetrics {
+    pub(crate) fn new(registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            requests: RelayCounterVec::new(
+                "relay_tx_pool_server_status",
+                "Transaction pool relay server request metrics(by status)",
+                &[STATUS_LABEL],
+                registry,
+            )?,
+        })
+    }
+
+    /// Updates the metrics on a successful request.
+    pub(crate) fn on_request(&self) {
+        self.requests.inc(STATUS_LABEL, STATUS_SUCCESS);
+    }
+
+    /// Updates the metrics on a failed request.
+    pub(crate) fn on_failed_request(&self, err: &RelayError) {
+        self.requests.inc(STATUS_LABEL, err.as_ref());
+    }
+}
@@ -41,6 +41,15 @@ pub(crate) enum RelayError {
     #[error("Block extrinsics not found: {0}")]
     BlockExtrinsicsNotFound(String),
 
+    #[error("Bundle not found: {0}")]
+    BundleNotFound(String),
+
+    #[error("Bundle hash mismatch: {0}")]
+    BundleHashMismatch(String),
+
+    #[error("Transaction pool snapshot not found: {0}")]
+    SnapshotNotFound(String),
+
     #[error("Unexpected number of resolved entries: {expected}, {actual}")]
     ResolveMismatch { expected: usize, actual: usize },
 
@@ -1,17 +1,25 @@
 //! Common utils.
 
 use crate::types::RequestResponseErr;
+use crate::LOG_TARGET;
 use codec::{Decode, Encode};
 use futures::channel::oneshot;
 use parking_lot::Mutex;
-use sc_network::request_responses::IfDisconnected;
+use sc_network::request_responses::{
+    IfDisconnected, IncomingRequest, OutgoingResponse, ProtocolConfig,
+};
 use sc_network::types::ProtocolName;
 use sc_network::{NetworkRequest, PeerId};
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use substrate_prometheus_endpoint::{
-    register, Counter, CounterVec, Opts, PrometheusError, Registry, U64,
+    register, Counter, CounterVec, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry,
+    U64,
 };
+use tracing::warn;
 
 type NetworkRequestService = Arc<dyn NetworkRequest + Send + Sync + 'static>;
 
@@ -48,6 +56,94 @@ impl NetworkWrapper {
     }
 }
 
+/// Limits of the request/response protocol served by the relay server.
+pub(crate) struct ServerProtocolLimits {
+    /// Number of the inbound requests queued for the server, the requests
+    /// are dropped by the network when the queue is full.
+    pub(crate) inbound_queue_size: NonZeroUsize,
+    /// Maximum size of the request (bytes).
+    pub(crate) max_request_size: u64,
+    /// Maximum size of the response (bytes).
+    pub(crate) max_response_size: u64,
+    /// Timeout of the individual request/response round-trip.
+    pub(crate) request_timeout: Duration,
+}
+
+/// Builds the request/response protocol config of the relay server, returns
+/// the config to be registered with the network and the receiver of the
+/// inbound requests.
+pub(crate) fn server_protocol_config(
+    protocol_name: &'static str,
+    limits: &ServerProtocolLimits,
+) -> (ProtocolConfig, async_channel::Receiver<IncomingRequest>) {
+    let (tx, request_receiver) = async_channel::bounded(limits.inbound_queue_size.get());
+    let protocol_config = ProtocolConfig {
+        name: protocol_name.into(),
+        fallback_names: Vec::new(),
+        max_request_size: limits.max_request_size,
+        max_response_size: limits.max_response_size,
+        request_timeout: limits.request_timeout,
+        inbound_queue: Some(tx),
+    };
+
+    (protocol_config, request_receiver)
+}
+
+/// Decodes the request received by the relay server, the request is dropped
+/// (and the client times out) if it can't be decoded.
+pub(crate) fn decode_request<Request: Decode>(peer: PeerId, payload: &[u8]) -> Option<Request> {
+    match Request::decode(&mut &payload[..]) {
+        Ok(request) => Some(request),
+        Err(err) => {
+            warn!(
+                target: LOG_TARGET,
+                ?peer,
+                ?err,
+                "Decode failed"
+            );
+            None
+        }
+    }
+}
+
+/// Sends the response of the relay server back to the client.
+pub(crate) fn send_response(
+    peer: PeerId,
+    response: Vec<u8>,
+    sender: oneshot::Sender<OutgoingResponse>,
+) {
+    let response = OutgoingResponse {
+        result: Ok(response),
+        reputation_changes: Vec::new(),
+        sent_feedback: None,
+    };
+    if sender.send(response).is_err() {
+        warn!(
+            target: LOG_TARGET,
+            ?peer,
+            "Failed to send response"
+        );
+    }
+}
+
+/// Stats of the requests made through the network peer handle.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct TransferStats {
+    /// Number of request/response round-trips
+    pub(crate) round_trips: u64,
+    /// Bytes sent in the requests
+    pub(crate) bytes_sent: u64,
+    /// Bytes received in the responses
+    pub(crate) bytes_received: u64,
+}
+
+#[derive(Default)]
+struct TransferCounters {
+    round_trips: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
 /// Network handle that allows making requests to specific peer and protocol.
 /// `Request` is the format of the request message sent on the wire.
 #[derive(Clone)]
@@ -55,6 +151,7 @@ pub(crate) struct NetworkPeerHandle {
     protocol_name: ProtocolName,
     who: PeerId,
     network: NetworkRequestService,
+    transfer_counters: Arc<TransferCounters>,
 }
 
 impl NetworkPeerHandle {
@@ -63,6 +160,20 @@ impl NetworkPeerHandle {
             protocol_name,
             who,
             network,
+            transfer_counters: Arc::default(),
+        }
+    }
+
+    /// Returns the stats of the requests made so far through this handle
+    /// (shared by the clones).
+    pub(crate) fn transfer_stats(&self) -> TransferStats {
+        TransferStats {
+            round_trips: self.transfer_counters.round_trips.load(Ordering::Relaxed),
+            bytes_sent: self.transfer_counters.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self
+                .transfer_counters
+                .bytes_received
+                .load(Ordering::Relaxed),
         }
     }
 
@@ -75,11 +186,19 @@ impl NetworkPeerHandle {
         Request: Encode,
         Response: Decode,
     {
+        let request = request.encode();
+        self.transfer_counters
+            .round_trips
+            .fetch_add(1, Ordering::Relaxed);
+        self.transfer_counters
+            .bytes_sent
+            .fetch_add(request.len() as u64, Ordering::Relaxed);
+
         let (tx, rx) = oneshot::channel();
         self.network.start_request(
             self.who,
             self.protocol_name.clone(),
-            request.encode(),
+            request,
             tx,
             IfDisconnected::ImmediateError,
         );
@@ -90,6 +209,9 @@ impl NetworkPeerHandle {
             .map_err(RequestResponseErr::RequestFailure)?;
 
         let response_len = response_bytes.len();
+        self.transfer_counters
+            .bytes_received
+            .fetch_add(response_len as u64, Ordering::Relaxed);
         Response::decode(&mut response_bytes.as_ref())
             .map_err(|err| RequestResponseErr::DecodeFailed { response_len, err })
     }
@@ -160,4 +282,58 @@ impl RelayCounterVec {
             counter.with(&labels).inc_by(v)
         }
     }
+
+    /// Increments the counter with the specified label values by the value.
+    pub(crate) fn inc_by_label_values(&self, label_values: &[&str], v: u64) {
+        if let Some(counter) = self.0.as_ref() {
+            counter.with_label_values(label_values).inc_by(v)
+        }
+    }
+
+    /// Removes the counter with the specified label values.
+    pub(crate) fn remove_label_values(&self, label_values: &[&str]) {
+        if let Some(counter) = self.0.as_ref() {
+            // Fails only if the counter was never incremented
+            let _ = counter.remove_label_values(label_values);
+        }
+    }
+}
+
+/// Convenience wrapper around prometheus histogram vec, which can be optional.
+pub(crate) struct RelayHistogramVec(Option<HistogramVec>);
+
+impl RelayHistogramVec {
+    /// Creates the histogram vec.
+    pub(crate) fn new(
+        name: &str,
+        help: &str,
+        labels: &[&str],
+        buckets: Vec<f64>,
+        registry: Option<&Registry>,
+    ) -> Result<Self, PrometheusError> {
+        let histogram_vec = if let Some(registry) = registry {
+            Some(register(
+                HistogramVec::new(HistogramOpts::new(name, help).buckets(buckets), labels)?,
+                registry,
+            )?)
+        } else {
+            None
+        };
+        Ok(Self(histogram_vec))
+    }
+
+    /// Adds the observation to the histogram with the specified label values.
+    pub(crate) fn observe(&self, label_values: &[&str], v: f64) {
+        if let Some(histogram) = self.0.as_ref() {
+            histogram.with_label_values(label_values).observe(v)
+        }
+    }
+
+    /// Removes the histogram with the specified label values.
+    pub(crate) fn remove_label_values(&self, label_values: &[&str]) {
+        if let Some(histogram) = self.0.as_ref() {
+            // Fails only if nothing was ever observed
+            let _ = histogram.remove_label_values(label_values);
+        }
+    }
 }
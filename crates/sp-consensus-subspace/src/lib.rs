@@ -52,9 +52,9 @@ use subspace_core_primitives::Randomness;
 #[cfg(feature = "pot")]
 use subspace_core_primitives::{Blake3Hash, PotOutput};
 use subspace_core_primitives::{
-    BlockNumber, HistorySize, PotCheckpoints, PublicKey, RewardSignature, SegmentCommitment,
-    SegmentHeader, SegmentIndex, SlotNumber, Solution, SolutionRange, PUBLIC_KEY_LENGTH,
-    REWARD_SIGNATURE_LENGTH,
+    BlockNumber, HistorySize, PotCheckpoints, PublicKey, RecordedHistorySegment, RewardSignature,
+    SegmentCommitment, SegmentHeader, SegmentIndex, SlotNumber, Solution, SolutionRange,
+    PUBLIC_KEY_LENGTH, REWARD_SIGNATURE_LENGTH,
 };
 #[cfg(feature = "std")]
 use subspace_proof_of_space::chia::ChiaTable;
@@ -220,13 +220,13 @@ where
 {
     /// Solution contained within.
     pub fn solution(&self) -> &Solution<FarmerPublicKey, RewardAddress> {
-        let Self::V0 { solution, .. } = self;
+        let (Self::V0 { solution, .. } | Self::V1 { solution, .. }) = self;
         solution
     }
 
     /// Slot at which vote was created.
     pub fn slot(&self) -> &Slot {
-        let Self::V0 { slot, .. } = self;
+        let (Self::V0 { slot, .. } | Self::V1 { slot, .. }) = self;
         slot
     }
 
@@ -396,77 +396,144 @@ pub enum ChainConstants {
         /// Minimum lifetime of a plotted sector, measured in archived segment.
         min_sector_lifetime: HistorySize,
     },
+    /// V1 of the chain constants, adds erasure coding rate of archived history.
+    #[codec(index = 1)]
+    V1 {
+        /// Depth `K` after which a block enters the recorded history.
+        confirmation_depth_k: BlockNumber,
+        /// Number of blocks between global randomness updates.
+        #[cfg(not(feature = "pot"))]
+        global_randomness_interval: BlockNumber,
+        /// Number of slots between slot arrival and when corresponding block can be produced.
+        #[cfg(feature = "pot")]
+        block_authoring_delay: Slot,
+        /// Era duration in blocks.
+        era_duration: BlockNumber,
+        /// Slot probability.
+        slot_probability: (u64, u64),
+        /// Number of latest archived segments that are considered "recent history".
+        recent_segments: HistorySize,
+        /// Fraction of pieces from the "recent history" (`recent_segments`) in each sector.
+        recent_history_fraction: (HistorySize, HistorySize),
+        /// Minimum lifetime of a plotted sector, measured in archived segment.
+        min_sector_lifetime: HistorySize,
+        /// Erasure coding rate `(source, total)` of records during archiving process.
+        erasure_coding_rate: (u32, u32),
+    },
 }
 
 impl ChainConstants {
     /// Depth `K` after which a block enters the recorded history.
     pub fn confirmation_depth_k(&self) -> BlockNumber {
-        let Self::V0 {
+        let (Self::V0 {
+            confirmation_depth_k,
+            ..
+        }
+        | Self::V1 {
             confirmation_depth_k,
             ..
-        } = self;
+        }) = self;
         *confirmation_depth_k
     }
 
     /// Number of blocks between global randomness updates.
     #[cfg(not(feature = "pot"))]
     pub fn global_randomness_interval(&self) -> BlockNumber {
-        let Self::V0 {
+        let (Self::V0 {
             global_randomness_interval,
             ..
-        } = self;
+        }
+        | Self::V1 {
+            global_randomness_interval,
+            ..
+        }) = self;
         *global_randomness_interval
     }
 
     /// Era duration in blocks.
     pub fn era_duration(&self) -> BlockNumber {
-        let Self::V0 { era_duration, .. } = self;
+        let (Self::V0 { era_duration, .. } | Self::V1 { era_duration, .. }) = self;
         *era_duration
     }
 
     /// Number of slots between slot arrival and when corresponding block can be produced.
     #[cfg(feature = "pot")]
     pub fn block_authoring_delay(&self) -> Slot {
-        let Self::V0 {
+        let (Self::V0 {
+            block_authoring_delay,
+            ..
+        }
+        | Self::V1 {
             block_authoring_delay,
             ..
-        } = self;
+        }) = self;
         *block_authoring_delay
     }
 
     /// Slot probability.
     pub fn slot_probability(&self) -> (u64, u64) {
-        let Self::V0 {
+        let (Self::V0 {
             slot_probability, ..
-        } = self;
+        }
+        | Self::V1 {
+            slot_probability, ..
+        }) = self;
         *slot_probability
     }
 
     /// Number of latest archived segments that are considered "recent history".
     pub fn recent_segments(&self) -> HistorySize {
-        let Self::V0 {
+        let (Self::V0 {
             recent_segments, ..
-        } = self;
+        }
+        | Self::V1 {
+            recent_segments, ..
+        }) = self;
         *recent_segments
     }
 
     /// Fraction of pieces from the "recent history" (`recent_segments`) in each sector.
     pub fn recent_history_fraction(&self) -> (HistorySize, HistorySize) {
-        let Self::V0 {
+        let (Self::V0 {
+            recent_history_fraction,
+            ..
+        }
+        | Self::V1 {
             recent_history_fraction,
             ..
-        } = self;
+        }) = self;
         *recent_history_fraction
     }
 
     /// Minimum lifetime of a plotted sector, measured in archived segment.
     pub fn min_sector_lifetime(&self) -> HistorySize {
-        let Self::V0 {
+        let (Self::V0 {
+            min_sector_lifetime,
+            ..
+        }
+        | Self::V1 {
             min_sector_lifetime,
             ..
-        } = self;
+        }) = self;
         *min_sector_lifetime
     }
+
+    /// Erasure coding rate `(source, total)` of records during archiving process.
+    ///
+    /// [`ChainConstants::V0`] doesn't specify it explicitly, for which
+    /// [`RecordedHistorySegment::ERASURE_CODING_RATE`] is used.
+    pub fn erasure_coding_rate(&self) -> (usize, usize) {
+        match self {
+            Self::V0 { .. } => RecordedHistorySegment::ERASURE_CODING_RATE,
+            Self::V1 {
+                erasure_coding_rate,
+                ..
+            } => (
+                erasure_coding_rate.0 as usize,
+                erasure_coding_rate.1 as usize,
+            ),
+        }
+    }
 }
 
 /// Wrapped solution for the purposes of runtime interface.
@@ -725,16 +792,19 @@ impl PotParameters {
     /// Number of iterations for proof of time per slot, corresponds to slot that directly follows
     /// parent block's slot and can change before slot for which block is produced
     pub fn slot_iterations(&self) -> NonZeroU32 {
-        let Self::V0 {
+        let (Self::V0 {
+            slot_iterations, ..
+        }
+        | Self::V1 {
             slot_iterations, ..
-        } = self;
+        }) = self;
 
         *slot_iterations
     }
 
     /// Get next proof of time parameters change if any
     pub fn next_parameters_change(&self) -> Option<PotParametersChange> {
-        let Self::V0 { next_change, .. } = self;
+        let (Self::V0 { next_change, .. } | Self::V1 { next_change, .. }) = self;
 
         *next_change
     }
@@ -78,7 +78,7 @@ fn archived_segment(kzg: Kzg) -> NewArchivedSegment {
     let mut block = vec![0u8; RecordedHistorySegment::SIZE];
     rng.fill(block.as_mut_slice());
 
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     archiver
         .add_block(block, Default::default(), true)
@@ -111,6 +111,7 @@ impl FarmerParameters {
                 HistorySize::from(NonZeroU64::new(10).unwrap()),
             ),
             min_sector_lifetime: HistorySize::from(NonZeroU64::new(4).unwrap()),
+            erasure_coding_rate: RecordedHistorySegment::ERASURE_CODING_RATE,
         };
 
         Self {
@@ -3,6 +3,7 @@ use rand::{thread_rng, Rng};
 use subspace_archiving::archiver::Archiver;
 use subspace_core_primitives::crypto::kzg;
 use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::RecordedHistorySegment;
 
 const AMOUNT_OF_DATA: usize = 5 * 1024 * 1024;
 const SMALL_BLOCK_SIZE: usize = 500;
@@ -11,7 +12,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     let mut input = vec![0u8; AMOUNT_OF_DATA];
     thread_rng().fill(input.as_mut_slice());
     let kzg = Kzg::new(kzg::embedded_kzg_settings());
-    let archiver = Archiver::new(kzg).unwrap();
+    let archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     c.bench_function("segment-archiving-large-block", |b| {
         b.iter(|| {
@@ -23,6 +24,52 @@ fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
+    {
+        let mut segment_input = vec![0u8; RecordedHistorySegment::SIZE];
+        thread_rng().fill(segment_input.as_mut_slice());
+        let single_thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+
+        let mut group = c.benchmark_group("segment-archiving-whole-segment");
+        group.sample_size(10);
+
+        group.bench_function("parallel", |b| {
+            b.iter(|| {
+                archiver.clone().add_block(
+                    black_box(segment_input.clone()),
+                    black_box(Default::default()),
+                    black_box(false),
+                );
+            })
+        });
+
+        group.bench_function("single-threaded", |b| {
+            b.iter(|| {
+                single_thread_pool.install(|| {
+                    archiver.clone().add_block(
+                        black_box(segment_input.clone()),
+                        black_box(Default::default()),
+                        black_box(false),
+                    );
+                })
+            })
+        });
+
+        group.bench_function("segment-headers-only", |b| {
+            b.iter(|| {
+                archiver.clone().add_block_pending(
+                    black_box(segment_input.clone()),
+                    black_box(Default::default()),
+                    black_box(false),
+                );
+            })
+        });
+
+        group.finish();
+    }
+
     c.bench_function("segment-archiving-small-blocks/incremental", |b| {
         b.iter(|| {
             let mut archiver = archiver.clone();
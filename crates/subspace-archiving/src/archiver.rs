@@ -17,28 +17,31 @@ mod incremental_record_commitments;
 
 extern crate alloc;
 
+use crate::archived_history_erasure_coding_params;
 use crate::archiver::incremental_record_commitments::{
     update_record_commitments, IncrementalRecordCommitmentsState,
 };
 use alloc::collections::VecDeque;
-use alloc::string::String;
-use alloc::vec;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use alloc::{format, vec};
 use core::cmp::Ordering;
-use core::num::NonZeroUsize;
+use core::iter;
+use core::ops::Range;
 use parity_scale_codec::{Compact, CompactLen, Decode, Encode, Input, Output};
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use subspace_core_primitives::crypto::kzg::{Kzg, Witness};
+use subspace_core_primitives::crypto::kzg::{Commitment, Kzg, Polynomial, Witness};
 use subspace_core_primitives::crypto::{blake2b_256_254_hash_to_scalar, Scalar};
 use subspace_core_primitives::objects::{
-    BlockObject, BlockObjectMapping, PieceObject, PieceObjectMapping,
+    BlockObject, BlockObjectMapping, GlobalObject, PieceObject, PieceObjectMapping,
 };
 use subspace_core_primitives::{
     ArchivedBlockProgress, ArchivedHistorySegment, Blake2b256Hash, BlockNumber, LastArchivedBlock,
-    PieceArray, RawRecord, RecordedHistorySegment, SegmentCommitment, SegmentHeader, SegmentIndex,
+    PieceArray, PieceIndex, RawRecord, Record, RecordChunksProof, RecordedHistorySegment,
+    SegmentCommitment, SegmentHeader, SegmentIndex,
 };
-use subspace_erasure_coding::ErasureCoding;
+use subspace_erasure_coding::{ErasureCoding, ErasureCodingError};
 
 const INITIAL_LAST_ARCHIVED_BLOCK: LastArchivedBlock = LastArchivedBlock {
     number: 0,
@@ -183,6 +186,143 @@ pub struct NewArchivedSegment {
     pub object_mapping: Vec<PieceObjectMapping>,
 }
 
+impl NewArchivedSegment {
+    /// Object mappings of this segment with piece indexes in the global history, along with
+    /// object hashes.
+    pub fn global_object_mapping(
+        &self,
+    ) -> impl Iterator<Item = (Blake2b256Hash, GlobalObject)> + '_ {
+        let first_piece_index = self.segment_header.segment_index().first_piece_index();
+
+        self.object_mapping.iter().enumerate().flat_map(
+            move |(source_position, piece_object_mapping)| {
+                // Source pieces are interleaved with parity pieces
+                let piece_index = first_piece_index + PieceIndex::from(source_position as u64 * 2);
+
+                piece_object_mapping
+                    .objects
+                    .iter()
+                    .map(move |piece_object| {
+                        (
+                            piece_object.hash(),
+                            GlobalObject::V0 {
+                                piece_index,
+                                offset: piece_object.offset(),
+                            },
+                        )
+                    })
+            },
+        )
+    }
+}
+
+/// Segment item pending in archiver's buffer along with its object mapping, which is not a part of
+/// [`SegmentItem`] encoding
+#[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
+struct BufferedSegmentItem {
+    segment_item: SegmentItem,
+    object_mapping: BlockObjectMapping,
+}
+
+impl From<SegmentItem> for BufferedSegmentItem {
+    fn from(segment_item: SegmentItem) -> Self {
+        match segment_item {
+            SegmentItem::Block {
+                bytes,
+                object_mapping,
+            } => Self {
+                segment_item: SegmentItem::Block {
+                    bytes,
+                    object_mapping: BlockObjectMapping::default(),
+                },
+                object_mapping,
+            },
+            SegmentItem::BlockStart {
+                bytes,
+                object_mapping,
+            } => Self {
+                segment_item: SegmentItem::BlockStart {
+                    bytes,
+                    object_mapping: BlockObjectMapping::default(),
+                },
+                object_mapping,
+            },
+            SegmentItem::BlockContinuation {
+                bytes,
+                object_mapping,
+            } => Self {
+                segment_item: SegmentItem::BlockContinuation {
+                    bytes,
+                    object_mapping: BlockObjectMapping::default(),
+                },
+                object_mapping,
+            },
+            segment_item @ (SegmentItem::Padding | SegmentItem::ParentSegmentHeader(_)) => Self {
+                segment_item,
+                object_mapping: BlockObjectMapping::default(),
+            },
+        }
+    }
+}
+
+impl From<BufferedSegmentItem> for SegmentItem {
+    fn from(buffered_segment_item: BufferedSegmentItem) -> Self {
+        let BufferedSegmentItem {
+            segment_item,
+            object_mapping,
+        } = buffered_segment_item;
+
+        match segment_item {
+            SegmentItem::Block { bytes, .. } => SegmentItem::Block {
+                bytes,
+                object_mapping,
+            },
+            SegmentItem::BlockStart { bytes, .. } => SegmentItem::BlockStart {
+                bytes,
+                object_mapping,
+            },
+            SegmentItem::BlockContinuation { bytes, .. } => SegmentItem::BlockContinuation {
+                bytes,
+                object_mapping,
+            },
+            segment_item @ (SegmentItem::Padding | SegmentItem::ParentSegmentHeader(_)) => {
+                segment_item
+            }
+        }
+    }
+}
+
+/// Internal state of the [`Archiver`] that can be persisted and used to resume archiving with
+/// [`Archiver::from_checkpoint()`] without re-processing already archived blocks.
+///
+/// Archiver resumed from a checkpoint produces exactly the same output as archiver that was never
+/// interrupted.
+#[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
+pub struct ArchiverCheckpoint {
+    /// Items pending to be included into the next segment
+    buffer: Vec<BufferedSegmentItem>,
+    /// Record commitments that were already created incrementally for the next segment
+    record_commitments: Vec<Commitment>,
+    /// Index of the next segment
+    segment_index: SegmentIndex,
+    /// Hash of the segment header of the previous segment
+    prev_segment_header_hash: Blake2b256Hash,
+    /// Last archived block
+    last_archived_block: LastArchivedBlock,
+}
+
+impl ArchiverCheckpoint {
+    /// Index of the segment archiver will produce next
+    pub fn segment_index(&self) -> SegmentIndex {
+        self.segment_index
+    }
+
+    /// Last (possibly partially) archived block
+    pub fn last_archived_block(&self) -> LastArchivedBlock {
+        self.last_archived_block
+    }
+}
+
 /// Archiver instantiation error
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
@@ -192,7 +332,13 @@ pub enum ArchiverInstantiationError {
         feature = "thiserror",
         error("Failed to initialize erasure coding: {0}")
     )]
-    FailedToInitializeErasureCoding(String),
+    FailedToInitializeErasureCoding(ErasureCodingError),
+    /// Erasure coding rate is not supported by archived history segment layout
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Erasure coding rate {0:?} is not supported by archived history segment layout")
+    )]
+    UnsupportedErasureCodingRate((usize, usize)),
     /// Invalid last archived block, its size is the same as encoded block
     #[cfg_attr(
         feature = "thiserror",
@@ -210,6 +356,9 @@ pub enum ArchiverInstantiationError {
         /// Already archived portion of the block
         archived_block_bytes: u32,
     },
+    /// Invalid archiver checkpoint
+    #[cfg_attr(feature = "thiserror", error("Invalid archiver checkpoint: {0}"))]
+    InvalidCheckpoint(String),
 }
 
 /// Block archiver for Subspace blockchain.
@@ -243,20 +392,24 @@ pub struct Archiver {
 }
 
 impl Archiver {
-    // TODO: Make erasure coding an explicit argument
-    /// Create a new instance with specified record size and recorded history segment size.
+    /// Create a new instance with specified erasure coding rate `(source, total)` (normally taken
+    /// from chain constants).
     ///
     /// Note: this is the only way to instantiate object archiver, while block archiver can be
     /// instantiated with `BlockArchiver::with_initial_state()` in case of restarts.
-    pub fn new(kzg: Kzg) -> Result<Self, ArchiverInstantiationError> {
+    pub fn new(
+        kzg: Kzg,
+        erasure_coding_rate: (usize, usize),
+    ) -> Result<Self, ArchiverInstantiationError> {
         // TODO: Check if KZG can process number configured number of elements and update proof
         //  message in `.expect()`
 
-        let erasure_coding = ErasureCoding::new(
-            NonZeroUsize::new(ArchivedHistorySegment::NUM_PIECES.ilog2() as usize)
-                .expect("Archived history segment contains at very least one piece; qed"),
-        )
-        .map_err(ArchiverInstantiationError::FailedToInitializeErasureCoding)?;
+        let erasure_coding_params = archived_history_erasure_coding_params(erasure_coding_rate)
+            .ok_or(ArchiverInstantiationError::UnsupportedErasureCodingRate(
+                erasure_coding_rate,
+            ))?;
+        let erasure_coding = ErasureCoding::with_params(erasure_coding_params)
+            .map_err(ArchiverInstantiationError::FailedToInitializeErasureCoding)?;
 
         Ok(Self {
             buffer: VecDeque::default(),
@@ -276,11 +429,12 @@ impl Archiver {
     /// `block` corresponds to `last_archived_block` and will be processed accordingly to its state.
     pub fn with_initial_state(
         kzg: Kzg,
+        erasure_coding_rate: (usize, usize),
         segment_header: SegmentHeader,
         encoded_block: &[u8],
         mut object_mapping: BlockObjectMapping,
     ) -> Result<Self, ArchiverInstantiationError> {
-        let mut archiver = Self::new(kzg)?;
+        let mut archiver = Self::new(kzg, erasure_coding_rate)?;
 
         archiver.segment_index = segment_header.segment_index() + SegmentIndex::ONE;
         archiver.prev_segment_header_hash = segment_header.hash();
@@ -332,6 +486,69 @@ impl Archiver {
         Ok(archiver)
     }
 
+    /// Create a new instance of the archiver from checkpoint previously created with
+    /// [`Self::checkpoint()`].
+    pub fn from_checkpoint(
+        kzg: Kzg,
+        erasure_coding_rate: (usize, usize),
+        checkpoint: ArchiverCheckpoint,
+    ) -> Result<Self, ArchiverInstantiationError> {
+        let ArchiverCheckpoint {
+            buffer,
+            record_commitments,
+            segment_index,
+            prev_segment_header_hash,
+            last_archived_block,
+        } = checkpoint;
+
+        if record_commitments.len() > RecordedHistorySegment::NUM_RAW_RECORDS {
+            return Err(ArchiverInstantiationError::InvalidCheckpoint(format!(
+                "{} record commitments, while segment only contains {} records",
+                record_commitments.len(),
+                RecordedHistorySegment::NUM_RAW_RECORDS
+            )));
+        }
+        if buffer.iter().any(|buffered_segment_item| {
+            matches!(
+                buffered_segment_item.segment_item,
+                SegmentItem::Padding | SegmentItem::BlockStart { .. }
+            )
+        }) {
+            return Err(ArchiverInstantiationError::InvalidCheckpoint(
+                "buffer never contains padding or block start".to_string(),
+            ));
+        }
+
+        let mut archiver = Self::new(kzg, erasure_coding_rate)?;
+
+        archiver.buffer = buffer.into_iter().map(SegmentItem::from).collect();
+        archiver
+            .incremental_record_commitments
+            .extend(record_commitments);
+        archiver.segment_index = segment_index;
+        archiver.prev_segment_header_hash = prev_segment_header_hash;
+        archiver.last_archived_block = last_archived_block;
+
+        Ok(archiver)
+    }
+
+    /// Create checkpoint of the internal state that can be used to resume archiving with
+    /// [`Self::from_checkpoint()`].
+    pub fn checkpoint(&self) -> ArchiverCheckpoint {
+        ArchiverCheckpoint {
+            buffer: self
+                .buffer
+                .iter()
+                .cloned()
+                .map(BufferedSegmentItem::from)
+                .collect(),
+            record_commitments: self.incremental_record_commitments.to_vec(),
+            segment_index: self.segment_index,
+            prev_segment_header_hash: self.prev_segment_header_hash,
+            last_archived_block: self.last_archived_block,
+        }
+    }
+
     /// Get last archived block if there was any
     pub fn last_archived_block_number(&self) -> Option<BlockNumber> {
         if self.last_archived_block != INITIAL_LAST_ARCHIVED_BLOCK {
@@ -351,19 +568,34 @@ impl Archiver {
         object_mapping: BlockObjectMapping,
         incremental: bool,
     ) -> Vec<NewArchivedSegment> {
+        self.add_block_pending(bytes, object_mapping, incremental)
+            .into_iter()
+            .map(PendingArchivedSegment::finish)
+            .collect()
+    }
+
+    /// Same as [`Self::add_block()`], but only segment headers are produced right away, while
+    /// CPU-intensive creation of pieces is deferred until [`PendingArchivedSegment::finish()`] is
+    /// called.
+    pub fn add_block_pending(
+        &mut self,
+        bytes: Vec<u8>,
+        object_mapping: BlockObjectMapping,
+        incremental: bool,
+    ) -> Vec<PendingArchivedSegment> {
         // Append new block to the buffer
         self.buffer.push_back(SegmentItem::Block {
             bytes,
             object_mapping,
         });
 
-        let mut archived_segments = Vec::new();
+        let mut pending_archived_segments = Vec::new();
 
         while let Some(segment) = self.produce_segment(incremental) {
-            archived_segments.push(self.produce_archived_segment(segment));
+            pending_archived_segments.push(self.produce_pending_archived_segment(segment));
         }
 
-        archived_segments
+        pending_archived_segments
     }
 
     /// Try to slice buffer contents into segments if there is enough data, producing one segment at
@@ -607,8 +839,9 @@ impl Archiver {
         Some(segment)
     }
 
-    // Take segment as an input, apply necessary transformations and produce archived segment
-    fn produce_archived_segment(&mut self, segment: Segment) -> NewArchivedSegment {
+    // Take segment as an input, apply necessary transformations and produce segment header, pieces
+    // are created later
+    fn produce_pending_archived_segment(&mut self, segment: Segment) -> PendingArchivedSegment {
         // Create mappings
         let object_mapping = {
             let mut corrected_object_mapping =
@@ -665,106 +898,21 @@ impl Archiver {
             corrected_object_mapping
         };
 
-        let mut pieces = {
-            // Serialize segment into concatenation of raw records
-            let mut raw_record_shards = Vec::<u8>::with_capacity(RecordedHistorySegment::SIZE);
-            segment.encode_to(&mut raw_record_shards);
-            // Segment might require some padding (see [`Self::produce_segment`] for details)
-            raw_record_shards.resize(raw_record_shards.capacity(), 0);
-
-            // Segment is quite big and no longer necessary
-            drop(segment);
-
-            let mut pieces = ArchivedHistorySegment::default();
-
-            // Scratch buffer to avoid re-allocation
-            let mut tmp_source_shards_scalars =
-                Vec::<Scalar>::with_capacity(RecordedHistorySegment::NUM_RAW_RECORDS);
-            // Iterate over the chunks of `Scalar::SAFE_BYTES` bytes of all records
-            for record_offset in 0..RawRecord::SIZE / Scalar::SAFE_BYTES {
-                // Collect chunks of each record at the same offset
-                raw_record_shards
-                    .array_chunks::<{ RawRecord::SIZE }>()
-                    .map(|record_bytes| {
-                        record_bytes
-                            .array_chunks::<{ Scalar::SAFE_BYTES }>()
-                            .nth(record_offset)
-                            .expect("Statically known to exist in a record; qed")
-                    })
-                    .map(Scalar::from)
-                    .collect_into(&mut tmp_source_shards_scalars);
-
-                // Extend to obtain corresponding parity shards
-                let parity_shards = self
-                    .erasure_coding
-                    .extend(&tmp_source_shards_scalars)
-                    .expect(
-                        "Erasure coding instance is deliberately configured to support this \
-                        input; qed",
-                    );
-
-                let interleaved_input_chunks = tmp_source_shards_scalars
-                    .drain(..)
-                    .zip(parity_shards)
-                    .flat_map(|(a, b)| [a, b]);
-                let output_chunks = pieces.iter_mut().map(|piece| {
-                    piece
-                        .record_mut()
-                        .iter_mut()
-                        .nth(record_offset)
-                        .expect("Statically known to exist in a record; qed")
-                });
-
-                interleaved_input_chunks
-                    .zip(output_chunks)
-                    .for_each(|(input, output)| output.copy_from_slice(&input.to_bytes()));
-            }
+        // Serialize segment into concatenation of raw records
+        let mut raw_record_shards = Vec::<u8>::with_capacity(RecordedHistorySegment::SIZE);
+        segment.encode_to(&mut raw_record_shards);
+        // Segment might require some padding (see [`Self::produce_segment`] for details)
+        raw_record_shards.resize(raw_record_shards.capacity(), 0);
 
-            pieces
-        };
+        // Segment is quite big and no longer necessary
+        drop(segment);
 
+        // Add commitments for records that were not created incrementally
         let existing_commitments = self.incremental_record_commitments.len();
-        // Add commitments for pieces that were not created incrementally
-        {
-            #[cfg(not(feature = "parallel"))]
-            let source_pieces = pieces.source();
-            #[cfg(feature = "parallel")]
-            let source_pieces = pieces.par_source();
-
-            let iter = source_pieces
-                .skip(existing_commitments)
-                .map(|piece| {
-                    piece.record().iter().map(|scalar_bytes| {
-                        Scalar::try_from(scalar_bytes).expect(
-                            "Source pieces were just created and are guaranteed to contain \
-                            valid scalars; qed",
-                        )
-                    })
-                })
-                .map(|record_chunks| {
-                    let number_of_chunks = record_chunks.len();
-                    let mut scalars = Vec::with_capacity(number_of_chunks.next_power_of_two());
-
-                    record_chunks.collect_into(&mut scalars);
-
-                    // Number of scalars for KZG must be a power of two elements
-                    scalars.resize(scalars.capacity(), Scalar::default());
-
-                    let polynomial = self.kzg.poly(&scalars).expect(
-                        "KZG instance must be configured to support this many scalars; qed",
-                    );
-                    self.kzg
-                        .commit(&polynomial)
-                        .expect("KZG instance must be configured to support this many scalars; qed")
-                });
-
-            #[cfg(not(feature = "parallel"))]
-            iter.collect_into(&mut *self.incremental_record_commitments);
-            // TODO: `collect_into_vec()`, unfortunately, truncates input, which is not what we want
-            //  can be unified when https://github.com/rayon-rs/rayon/issues/1039 is resolved
-            #[cfg(feature = "parallel")]
-            self.incremental_record_commitments.par_extend(iter);
-        }
+        let new_record_commitments =
+            commit_raw_records(&self.kzg, &raw_record_shards, existing_commitments);
+        self.incremental_record_commitments
+            .extend(new_record_commitments);
         // Collect hashes to commitments from all records
         let record_commitments = self
             .erasure_coding
@@ -789,29 +937,6 @@ impl Archiver {
             .commit(&polynomial)
             .expect("Internally produced values must never fail; qed");
 
-        // Create witness for every record and write it to corresponding piece.
-        pieces
-            .iter_mut()
-            .zip(record_commitments)
-            .enumerate()
-            .for_each(|(position, (piece, commitment))| {
-                let commitment_bytes = commitment.to_bytes();
-                let (_record, commitment, witness) = piece.split_mut();
-                commitment.copy_from_slice(&commitment_bytes);
-                // TODO: Consider batch witness creation for improved performance
-                witness.copy_from_slice(
-                    &self
-                        .kzg
-                        .create_witness(
-                            &polynomial,
-                            ArchivedHistorySegment::NUM_PIECES,
-                            position as u32,
-                        )
-                        .expect("Position is statically known to be valid; qed")
-                        .to_bytes(),
-                );
-            });
-
         // Now produce segment header
         let segment_header = SegmentHeader::V0 {
             segment_index: self.segment_index,
@@ -829,6 +954,75 @@ impl Archiver {
         self.buffer
             .push_front(SegmentItem::ParentSegmentHeader(segment_header));
 
+        PendingArchivedSegment {
+            segment_header,
+            raw_record_shards,
+            record_commitments,
+            polynomial,
+            object_mapping,
+            erasure_coding: self.erasure_coding.clone(),
+            kzg: self.kzg.clone(),
+        }
+    }
+}
+
+/// Archived segment whose segment header is already known, but pieces are not created yet.
+///
+/// Segment header only requires record commitments, which are mostly created incrementally, while
+/// erasure coding and witness creation are much more expensive and can be done later with
+/// [`PendingArchivedSegment::finish()`], potentially on a different thread.
+#[derive(Debug, Clone)]
+pub struct PendingArchivedSegment {
+    segment_header: SegmentHeader,
+    /// Concatenation of source records
+    raw_record_shards: Vec<u8>,
+    /// Commitments to all records, source records are interleaved with parity records
+    record_commitments: Vec<Commitment>,
+    /// Polynomial over hashes of record commitments
+    polynomial: Polynomial,
+    object_mapping: Vec<PieceObjectMapping>,
+    erasure_coding: ErasureCoding,
+    kzg: Kzg,
+}
+
+impl PendingArchivedSegment {
+    /// Segment header of this segment
+    pub fn segment_header(&self) -> &SegmentHeader {
+        &self.segment_header
+    }
+
+    /// Create pieces of this segment, output is identical to [`Archiver::add_block()`]
+    pub fn finish(self) -> NewArchivedSegment {
+        let Self {
+            segment_header,
+            raw_record_shards,
+            record_commitments,
+            polynomial,
+            object_mapping,
+            erasure_coding,
+            kzg,
+        } = self;
+
+        // Erasure coding and witness creation are independent from each other, so they are done
+        // concurrently
+        let erasure_code = || erasure_code_raw_records(&erasure_coding, &raw_record_shards);
+        let create_witnesses = || create_record_witnesses(&kzg, &polynomial);
+        #[cfg(not(feature = "parallel"))]
+        let (mut pieces, record_witnesses) = (erasure_code(), create_witnesses());
+        #[cfg(feature = "parallel")]
+        let (mut pieces, record_witnesses) = rayon::join(erasure_code, create_witnesses);
+
+        // Write commitment and witness of every record to corresponding piece
+        pieces
+            .iter_mut()
+            .zip(record_commitments)
+            .zip(record_witnesses)
+            .for_each(|((piece, record_commitment), record_witness)| {
+                let (_record, commitment, witness) = piece.split_mut();
+                commitment.copy_from_slice(&record_commitment.to_bytes());
+                witness.copy_from_slice(&record_witness.to_bytes());
+            });
+
         NewArchivedSegment {
             segment_header,
             pieces,
@@ -837,6 +1031,125 @@ impl Archiver {
     }
 }
 
+/// Number of record chunks erasure coded before results are written into pieces, bounds memory
+/// usage of parallel erasure coding
+const ERASURE_CODING_BATCH_SIZE: usize = 1024;
+
+/// Erasure code raw records of the segment into pieces (source pieces are interleaved with parity
+/// pieces), record commitments and witnesses are left empty.
+fn erasure_code_raw_records(
+    erasure_coding: &ErasureCoding,
+    raw_record_shards: &[u8],
+) -> ArchivedHistorySegment {
+    let mut pieces = ArchivedHistorySegment::default();
+
+    let record_chunks_count = RawRecord::SIZE / Scalar::SAFE_BYTES;
+    for batch_start in (0..record_chunks_count).step_by(ERASURE_CODING_BATCH_SIZE) {
+        let batch = batch_start..record_chunks_count.min(batch_start + ERASURE_CODING_BATCH_SIZE);
+
+        #[cfg(not(feature = "parallel"))]
+        let record_offsets = batch.clone();
+        #[cfg(feature = "parallel")]
+        let record_offsets = batch.clone().into_par_iter();
+
+        // Interleaved source and parity chunks of all records at each offset within the batch
+        let interleaved_chunks = record_offsets
+            .map(|record_offset| {
+                // Collect chunks of each record at the same offset
+                let source_shards_scalars = raw_record_shards
+                    .array_chunks::<{ RawRecord::SIZE }>()
+                    .map(|record_bytes| {
+                        let chunk_bytes = &record_bytes[record_offset * Scalar::SAFE_BYTES..]
+                            [..Scalar::SAFE_BYTES];
+
+                        Scalar::from(
+                            <&[u8; Scalar::SAFE_BYTES]>::try_from(chunk_bytes)
+                                .expect("Statically known to have correct size; qed"),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                // Extend to obtain corresponding parity shards
+                let parity_shards = erasure_coding.extend(&source_shards_scalars).expect(
+                    "Erasure coding instance is deliberately configured to support this input; \
+                    qed",
+                );
+
+                source_shards_scalars
+                    .into_iter()
+                    .zip(parity_shards.chunks_exact(erasure_coding.expansion_factor() - 1))
+                    .flat_map(|(source, parity)| iter::once(source).chain(parity.iter().copied()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        #[cfg(not(feature = "parallel"))]
+        let iter = pieces.iter_mut().enumerate();
+        #[cfg(feature = "parallel")]
+        let iter = pieces.par_iter_mut().enumerate();
+
+        iter.for_each(|(position, piece)| {
+            piece
+                .record_mut()
+                .iter_mut()
+                .skip(batch.start)
+                .zip(&interleaved_chunks)
+                .for_each(|(output, chunks)| output.copy_from_slice(&chunks[position].to_bytes()));
+        });
+    }
+
+    pieces
+}
+
+/// Create commitments to raw records of the segment, skipping first `skip` records that already
+/// have commitments created incrementally.
+fn commit_raw_records(kzg: &Kzg, raw_record_shards: &[u8], skip: usize) -> Vec<Commitment> {
+    #[cfg(not(feature = "parallel"))]
+    let raw_records_bytes = raw_record_shards.chunks_exact(RawRecord::SIZE);
+    #[cfg(feature = "parallel")]
+    let raw_records_bytes = raw_record_shards.par_chunks_exact(RawRecord::SIZE);
+
+    raw_records_bytes
+        .skip(skip)
+        .map(|raw_record_bytes| {
+            raw_record_bytes
+                .array_chunks::<{ Scalar::SAFE_BYTES }>()
+                .map(Scalar::from)
+        })
+        .map(|record_chunks| {
+            let number_of_chunks = record_chunks.len();
+            let mut scalars = Vec::with_capacity(number_of_chunks.next_power_of_two());
+
+            record_chunks.collect_into(&mut scalars);
+
+            // Number of scalars for KZG must be a power of two elements
+            scalars.resize(scalars.capacity(), Scalar::default());
+
+            let polynomial = kzg
+                .poly(&scalars)
+                .expect("KZG instance must be configured to support this many scalars; qed");
+            kzg.commit(&polynomial)
+                .expect("KZG instance must be configured to support this many scalars; qed")
+        })
+        .collect()
+}
+
+/// Create witness for every record of the segment against segment commitment `polynomial`
+fn create_record_witnesses(kzg: &Kzg, polynomial: &Polynomial) -> Vec<Witness> {
+    #[cfg(not(feature = "parallel"))]
+    let positions = 0..ArchivedHistorySegment::NUM_PIECES as u32;
+    #[cfg(feature = "parallel")]
+    let positions = (0..ArchivedHistorySegment::NUM_PIECES as u32).into_par_iter();
+
+    positions
+        .map(|position| {
+            // TODO: Consider batch witness creation for improved performance
+            kzg.create_witness(polynomial, ArchivedHistorySegment::NUM_PIECES, position)
+                .expect("Position is statically known to be valid; qed")
+        })
+        .collect()
+}
+
 /// Validate witness embedded within a piece produced by archiver
 pub fn is_piece_valid(
     kzg: &Kzg,
@@ -911,3 +1224,128 @@ pub fn is_record_commitment_hash_valid(
         witness,
     )
 }
+
+/// Range of record chunks that contain specified range of raw record bytes
+pub fn raw_record_chunks_range(raw_record_range: Range<u32>) -> Range<u32> {
+    let safe_bytes = Scalar::SAFE_BYTES as u32;
+
+    raw_record_range.start / safe_bytes..raw_record_range.end.div_ceil(safe_bytes)
+}
+
+/// Record chunks proof creation error
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
+pub enum RecordChunksProofError {
+    /// Invalid range of chunks, must be non-empty and within the record
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Invalid range of chunks {start}..{end}, must be non-empty and within the record")
+    )]
+    InvalidChunksRange {
+        /// Start of the range
+        start: u32,
+        /// End of the range
+        end: u32,
+    },
+    /// Record chunk is not a valid scalar
+    #[cfg_attr(feature = "thiserror", error("Record chunk {0} is not a valid scalar"))]
+    InvalidRecordChunk(u32),
+    /// KZG error
+    #[cfg_attr(feature = "thiserror", error("KZG error: {0}"))]
+    Kzg(String),
+}
+
+/// Create proof of inclusion of `chunks` range of piece's record in archived history.
+///
+/// NOTE: Piece is assumed to be valid, see [`is_piece_valid()`].
+pub fn create_record_chunks_proof(
+    kzg: &Kzg,
+    piece: &PieceArray,
+    chunks: Range<u32>,
+) -> Result<RecordChunksProof, RecordChunksProofError> {
+    if chunks.is_empty() || chunks.end as usize > Record::NUM_CHUNKS {
+        return Err(RecordChunksProofError::InvalidChunksRange {
+            start: chunks.start,
+            end: chunks.end,
+        });
+    }
+
+    let (record, record_commitment, record_witness) = piece.split();
+
+    let scalars = record
+        .iter()
+        .enumerate()
+        .map(|(offset, record_chunk)| {
+            Scalar::try_from(record_chunk)
+                .map_err(|_error| RecordChunksProofError::InvalidRecordChunk(offset as u32))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let polynomial = kzg.poly(&scalars).map_err(RecordChunksProofError::Kzg)?;
+
+    let chunk_witnesses = chunks
+        .clone()
+        .map(|offset| {
+            kzg.create_witness(&polynomial, Record::NUM_CHUNKS, offset)
+                .map_err(RecordChunksProofError::Kzg)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(RecordChunksProof {
+        record_commitment: *record_commitment,
+        record_witness: *record_witness,
+        first_chunk_offset: chunks.start,
+        chunks: scalars[chunks.start as usize..chunks.end as usize].to_vec(),
+        chunk_witnesses,
+    })
+}
+
+/// Validate proof of inclusion of record chunks of the piece at `position` in segment with
+/// `segment_commitment`
+pub fn is_record_chunks_proof_valid(
+    kzg: &Kzg,
+    proof: &RecordChunksProof,
+    segment_commitment: &SegmentCommitment,
+    position: u32,
+) -> bool {
+    let num_chunks = proof.chunks.len();
+    if num_chunks == 0
+        || num_chunks != proof.chunk_witnesses.len()
+        || proof.first_chunk_offset as usize + num_chunks > Record::NUM_CHUNKS
+    {
+        return false;
+    }
+
+    let (Ok(record_commitment), Ok(record_witness)) = (
+        Commitment::try_from_bytes(&proof.record_commitment),
+        Witness::try_from_bytes(&proof.record_witness),
+    ) else {
+        return false;
+    };
+
+    let record_commitment_hash = blake2b_256_254_hash_to_scalar(proof.record_commitment.as_ref());
+    if !is_record_commitment_hash_valid(
+        kzg,
+        &record_commitment_hash,
+        segment_commitment,
+        &record_witness,
+        position,
+    ) {
+        return false;
+    }
+
+    proof
+        .chunks
+        .iter()
+        .zip(&proof.chunk_witnesses)
+        .zip(proof.first_chunk_offset..)
+        .all(|((chunk, chunk_witness), offset)| {
+            kzg.verify(
+                &record_commitment,
+                Record::NUM_CHUNKS,
+                offset,
+                chunk,
+                chunk_witness,
+            )
+        })
+}
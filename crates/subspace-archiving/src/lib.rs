@@ -20,3 +20,37 @@
 pub mod archiver;
 pub mod piece_reconstructor;
 pub mod reconstructor;
+pub mod streaming_reconstructor;
+
+use core::num::NonZeroUsize;
+use subspace_core_primitives::{ArchivedHistorySegment, RecordedHistorySegment};
+use subspace_erasure_coding::ErasureCodingParams;
+
+/// Erasure coding parameters of archived history for erasure coding rate `(source, total)`, as
+/// specified in chain constants (see [`RecordedHistorySegment::ERASURE_CODING_RATE`] for default).
+///
+/// Returns `None` if erasure coding of [`RecordedHistorySegment::NUM_RAW_RECORDS`] source records
+/// with such rate doesn't result in [`ArchivedHistorySegment::NUM_PIECES`] pieces.
+pub fn archived_history_erasure_coding_params(
+    erasure_coding_rate: (usize, usize),
+) -> Option<ErasureCodingParams> {
+    let (source, total) = erasure_coding_rate;
+    if source == 0 || total % source != 0 {
+        return None;
+    }
+    let expansion_factor = total / source;
+
+    if RecordedHistorySegment::NUM_RAW_RECORDS.checked_mul(expansion_factor)?
+        != ArchivedHistorySegment::NUM_PIECES
+    {
+        return None;
+    }
+
+    Some(ErasureCodingParams {
+        scale: NonZeroUsize::new(
+            (RecordedHistorySegment::NUM_RAW_RECORDS * expansion_factor.next_power_of_two()).ilog2()
+                as usize,
+        )?,
+        expansion_factor: NonZeroUsize::new(expansion_factor)?,
+    })
+}
@@ -1,14 +1,14 @@
 extern crate alloc;
 
+use crate::archived_history_erasure_coding_params;
 use alloc::string::String;
 use alloc::vec::Vec;
-use core::num::NonZeroUsize;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use subspace_core_primitives::crypto::kzg::{Commitment, Kzg, Polynomial};
 use subspace_core_primitives::crypto::{blake2b_256_254_hash_to_scalar, Scalar};
 use subspace_core_primitives::{ArchivedHistorySegment, Piece, RawRecord};
-use subspace_erasure_coding::ErasureCoding;
+use subspace_erasure_coding::{ErasureCoding, ErasureCodingError};
 
 /// Reconstructor-related instantiation error.
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -19,19 +19,31 @@ pub enum ReconstructorInstantiationError {
         feature = "thiserror",
         error("Failed to initialize erasure coding: {0}")
     )]
-    FailedToInitializeErasureCoding(String),
+    FailedToInitializeErasureCoding(ErasureCodingError),
+    /// Erasure coding rate is not supported by archived history segment layout
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Erasure coding rate {0:?} is not supported by archived history segment layout")
+    )]
+    UnsupportedErasureCodingRate((usize, usize)),
 }
 
 /// Reconstructor-related instantiation error
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
 pub enum ReconstructorError {
-    /// Segment size is not bigger than record size
+    /// Error during data shards reconstruction
     #[cfg_attr(
         feature = "thiserror",
         error("Error during data shards reconstruction: {0}")
     )]
-    DataShardsReconstruction(String),
+    DataShardsReconstruction(ErasureCodingError),
+    /// Chunk of input piece record is not a valid scalar
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Chunk of input piece record is not a valid scalar: {0}")
+    )]
+    InvalidInputPieceChunk(String),
 
     /// Commitment of input piece is invalid.
     #[cfg_attr(feature = "thiserror", error("Commitment of input piece is invalid."))]
@@ -52,16 +64,21 @@ pub struct PiecesReconstructor {
 }
 
 impl PiecesReconstructor {
-    // TODO: Make erasure coding an explicit argument
-    pub fn new(kzg: Kzg) -> Result<Self, ReconstructorInstantiationError> {
+    /// Create a new instance with specified erasure coding rate `(source, total)` (normally taken
+    /// from chain constants).
+    pub fn new(
+        kzg: Kzg,
+        erasure_coding_rate: (usize, usize),
+    ) -> Result<Self, ReconstructorInstantiationError> {
         // TODO: Check if KZG can process number configured number of elements and update proof
         //  message in `.expect()`
 
-        let erasure_coding = ErasureCoding::new(
-            NonZeroUsize::new(ArchivedHistorySegment::NUM_PIECES.ilog2() as usize)
-                .expect("Archived history segment contains at very least one piece; qed"),
-        )
-        .map_err(ReconstructorInstantiationError::FailedToInitializeErasureCoding)?;
+        let erasure_coding_params = archived_history_erasure_coding_params(erasure_coding_rate)
+            .ok_or(
+                ReconstructorInstantiationError::UnsupportedErasureCodingRate(erasure_coding_rate),
+            )?;
+        let erasure_coding = ErasureCoding::with_params(erasure_coding_params)
+            .map_err(ReconstructorInstantiationError::FailedToInitializeErasureCoding)?;
 
         Ok(Self {
             erasure_coding,
@@ -95,7 +112,7 @@ impl PiecesReconstructor {
                     })
                     .map(Scalar::try_from)
                     .transpose()
-                    .map_err(ReconstructorError::DataShardsReconstruction)?;
+                    .map_err(ReconstructorError::InvalidInputPieceChunk)?;
 
                 tmp_shards_scalars.push(maybe_scalar);
             }
@@ -120,12 +137,15 @@ impl PiecesReconstructor {
 
         let source_record_commitments = {
             #[cfg(not(feature = "parallel"))]
-            let iter = reconstructed_pieces.iter_mut().zip(input_pieces).step_by(2);
+            let iter = reconstructed_pieces
+                .iter_mut()
+                .zip(input_pieces)
+                .step_by(self.erasure_coding.expansion_factor());
             #[cfg(feature = "parallel")]
             let iter = reconstructed_pieces
                 .par_iter_mut()
                 .zip_eq(input_pieces)
-                .step_by(2);
+                .step_by(self.erasure_coding.expansion_factor());
 
             iter.map(|(piece, maybe_input_piece)| {
                 if let Some(input_piece) = maybe_input_piece {
@@ -139,7 +159,7 @@ impl PiecesReconstructor {
                         for record_chunk in piece.record().iter() {
                             scalars.push(
                                 Scalar::try_from(record_chunk)
-                                    .map_err(ReconstructorError::DataShardsReconstruction)?,
+                                    .map_err(ReconstructorError::InvalidInputPieceChunk)?,
                             );
                         }
 
@@ -1,17 +1,17 @@
 extern crate alloc;
 
+use crate::archived_history_erasure_coding_params;
 use crate::archiver::{Segment, SegmentItem};
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::mem;
-use core::num::NonZeroUsize;
 use parity_scale_codec::Decode;
 use subspace_core_primitives::crypto::Scalar;
 use subspace_core_primitives::{
     ArchivedBlockProgress, ArchivedHistorySegment, BlockNumber, LastArchivedBlock, Piece,
     RawRecord, RecordedHistorySegment, SegmentHeader, SegmentIndex,
 };
-use subspace_erasure_coding::ErasureCoding;
+use subspace_erasure_coding::{ErasureCoding, ErasureCodingError};
 
 /// Reconstructor-related instantiation error.
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -22,7 +22,13 @@ pub enum ReconstructorInstantiationError {
         feature = "thiserror",
         error("Failed to initialize erasure coding: {0}")
     )]
-    FailedToInitializeErasureCoding(String),
+    FailedToInitializeErasureCoding(ErasureCodingError),
+    /// Erasure coding rate is not supported by archived history segment layout
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Erasure coding rate {0:?} is not supported by archived history segment layout")
+    )]
+    UnsupportedErasureCodingRate((usize, usize)),
 }
 
 /// Reconstructor-related instantiation error
@@ -34,7 +40,13 @@ pub enum ReconstructorError {
         feature = "thiserror",
         error("Error during data shards reconstruction: {0}")
     )]
-    DataShardsReconstruction(String),
+    DataShardsReconstruction(ErasureCodingError),
+    /// Chunk of input piece record is not a valid scalar
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Chunk of input piece record is not a valid scalar: {0}")
+    )]
+    InvalidInputPieceChunk(String),
     /// Segment size is not bigger than record size
     #[cfg_attr(feature = "thiserror", error("Error during segment decoding: {0}"))]
     SegmentDecoding(parity_scale_codec::Error),
@@ -71,16 +83,20 @@ pub struct Reconstructor {
 }
 
 impl Reconstructor {
-    // TODO: Make erasure coding an explicit argument
-    pub fn new() -> Result<Self, ReconstructorInstantiationError> {
+    /// Create a new instance with specified erasure coding rate `(source, total)` (normally taken
+    /// from chain constants).
+    pub fn new(
+        erasure_coding_rate: (usize, usize),
+    ) -> Result<Self, ReconstructorInstantiationError> {
         // TODO: Check if KZG can process number configured number of elements and update proof
         //  message in `.expect()`
 
-        let erasure_coding = ErasureCoding::new(
-            NonZeroUsize::new(ArchivedHistorySegment::NUM_PIECES.ilog2() as usize)
-                .expect("Archived history segment contains at very least one piece; qed"),
-        )
-        .map_err(ReconstructorInstantiationError::FailedToInitializeErasureCoding)?;
+        let erasure_coding_params = archived_history_erasure_coding_params(erasure_coding_rate)
+            .ok_or(
+                ReconstructorInstantiationError::UnsupportedErasureCodingRate(erasure_coding_rate),
+            )?;
+        let erasure_coding = ErasureCoding::with_params(erasure_coding_params)
+            .map_err(ReconstructorInstantiationError::FailedToInitializeErasureCoding)?;
 
         Ok(Self {
             erasure_coding,
@@ -105,7 +121,7 @@ impl Reconstructor {
         if !segment_pieces
             .iter()
             // Take each source shards here
-            .step_by(2)
+            .step_by(self.erasure_coding.expansion_factor())
             .zip(segment_data.iter_mut())
             .all(|(maybe_piece, raw_record)| {
                 if let Some(piece) = maybe_piece {
@@ -141,7 +157,7 @@ impl Reconstructor {
                         })
                         .map(Scalar::try_from)
                         .transpose()
-                        .map_err(ReconstructorError::DataShardsReconstruction)?;
+                        .map_err(ReconstructorError::InvalidInputPieceChunk)?;
 
                     tmp_shards_scalars.push(maybe_scalar);
                 }
@@ -151,7 +167,7 @@ impl Reconstructor {
                     .map_err(ReconstructorError::DataShardsReconstruction)?
                     .into_iter()
                     // Take each source shards here
-                    .step_by(2)
+                    .step_by(self.erasure_coding.expansion_factor())
                     .zip(segment_data.iter_mut().map(|raw_record| {
                         raw_record
                             .iter_mut()
@@ -0,0 +1,270 @@
+extern crate alloc;
+
+use crate::archiver::SegmentItem;
+use crate::reconstructor::ReconstructedContents;
+use alloc::vec::Vec;
+use parity_scale_codec::{Compact, CompactLen, Decode};
+use subspace_core_primitives::crypto::Scalar;
+use subspace_core_primitives::{
+    ArchivedBlockProgress, ArchivedHistorySegment, BlockNumber, LastArchivedBlock, PieceArray,
+    PieceIndex, SegmentIndex,
+};
+
+/// Streaming reconstructor-related error
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
+pub enum StreamingReconstructorError {
+    /// Error during segment decoding
+    #[cfg_attr(feature = "thiserror", error("Error during segment decoding: {0}"))]
+    SegmentDecoding(parity_scale_codec::Error),
+    /// Incorrect piece order, source pieces must be added one after another starting with the
+    /// first piece of a segment
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Incorrect piece order, expected index {expected_piece_index}, actual {actual_piece_index}")
+    )]
+    IncorrectPieceOrder {
+        expected_piece_index: PieceIndex,
+        actual_piece_index: PieceIndex,
+    },
+    /// Parent segment header doesn't correspond to the segment it is contained in
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Segment {segment_index} contains unexpected parent segment header {parent_segment_index}")
+    )]
+    UnexpectedParentSegmentHeader {
+        segment_index: SegmentIndex,
+        parent_segment_index: SegmentIndex,
+    },
+}
+
+/// Streaming reconstructor retrieves blocks from source pieces of archived history as they
+/// arrive.
+///
+/// Unlike [`Reconstructor`](crate::reconstructor::Reconstructor), which needs pieces of the whole
+/// segment at once, streaming reconstructor takes source pieces one by one in order and yields
+/// blocks as soon as they are fully decoded, only buffering bytes of items that are not yet
+/// complete. Blocks that span multiple segments are carried over between segments.
+///
+/// It is possible to start with the first source piece of any segment, in which case block numbers
+/// are derived from the parent segment header stored at the beginning of the segment, and the
+/// block that was started in one of the previous segments is skipped.
+///
+/// NOTE: Missing source pieces need to be reconstructed before they are added, see
+/// [`PiecesReconstructor`](crate::piece_reconstructor::PiecesReconstructor).
+#[derive(Debug, Clone, Default)]
+pub struct StreamingReconstructor {
+    /// Index of the next expected source piece, `None` before the first piece was added
+    next_piece_index: Option<PieceIndex>,
+    /// Bytes of the current segment that were not decoded yet
+    segment_bytes: Vec<u8>,
+    /// Number of the next block to be yielded
+    next_block_number: BlockNumber,
+    /// Partially reconstructed block waiting for more data
+    partial_block: Option<Vec<u8>>,
+}
+
+impl StreamingReconstructor {
+    /// Create new instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of the next expected source piece, `None` before the first piece was added
+    pub fn next_piece_index(&self) -> Option<PieceIndex> {
+        self.next_piece_index
+    }
+
+    /// Add next source piece of archived history, returns blocks that were fully reconstructed
+    /// with its help along with parent segment header if it was encountered in this piece.
+    ///
+    /// The first piece must be the first source piece of a segment, each next piece must be the
+    /// next source piece of the same segment or the first source piece of the next segment.
+    ///
+    /// After decoding error reconstructor is in undefined state and should not be used anymore.
+    pub fn add_piece(
+        &mut self,
+        piece_index: PieceIndex,
+        piece: &PieceArray,
+    ) -> Result<ReconstructedContents, StreamingReconstructorError> {
+        // Source pieces are interleaved with parity pieces and there is an even number of pieces
+        // in a segment, so the first source piece of the next segment follows the same pattern
+        let expected_piece_index = self
+            .next_piece_index
+            .unwrap_or_else(|| piece_index.segment_index().first_piece_index());
+        if piece_index != expected_piece_index {
+            return Err(StreamingReconstructorError::IncorrectPieceOrder {
+                expected_piece_index,
+                actual_piece_index: piece_index,
+            });
+        }
+        self.next_piece_index
+            .replace(piece_index + PieceIndex::from(2));
+
+        let first_in_segment = piece_index.position() == 0;
+        let last_in_segment =
+            piece_index.position() as usize == ArchivedHistorySegment::NUM_PIECES - 2;
+
+        self.segment_bytes.extend(
+            piece
+                .record()
+                .iter()
+                .flat_map(|bytes| &bytes[..Scalar::SAFE_BYTES]),
+        );
+
+        let mut offset = 0;
+        if first_in_segment {
+            // Segment version
+            if self.segment_bytes[0] != 0 {
+                return Err(StreamingReconstructorError::SegmentDecoding(
+                    "Could not decode `Segment`, variant doesn't exist".into(),
+                ));
+            }
+            offset += 1;
+        }
+
+        let mut reconstructed_contents = ReconstructedContents::default();
+
+        while offset < self.segment_bytes.len() {
+            let bytes = &self.segment_bytes[offset..];
+
+            if !last_in_segment {
+                match Self::encoded_item_len(bytes) {
+                    Some(encoded_item_len) => {
+                        if bytes.len() < encoded_item_len {
+                            // Wait for the rest of the item
+                            break;
+                        }
+                    }
+                    None => {
+                        // Not enough bytes to even know the size of the item yet
+                        break;
+                    }
+                }
+            }
+
+            let mut input = bytes;
+            let segment_item = match SegmentItem::decode(&mut input) {
+                Ok(segment_item) => segment_item,
+                Err(error) => {
+                    if last_in_segment {
+                        return Err(StreamingReconstructorError::SegmentDecoding(
+                            error.chain("Could not decode `Segment::V0::items`"),
+                        ));
+                    }
+
+                    // Wait for more bytes
+                    break;
+                }
+            };
+            offset += bytes.len() - input.len();
+
+            self.process_segment_item(
+                piece_index.segment_index(),
+                segment_item,
+                &mut reconstructed_contents,
+            )?;
+        }
+
+        if last_in_segment {
+            self.segment_bytes.clear();
+        } else {
+            self.segment_bytes.drain(..offset);
+        }
+
+        Ok(reconstructed_contents)
+    }
+
+    /// Size of encoded segment item whose encoding starts at the beginning of `bytes`, `None` if
+    /// there are not enough bytes to find it out.
+    ///
+    /// For items of unknown size `bytes.len()` is returned, such that decoding is attempted.
+    fn encoded_item_len(bytes: &[u8]) -> Option<usize> {
+        match bytes.first()? {
+            // `Block`, `BlockStart` and `BlockContinuation` are followed by compact length of bytes
+            1..=3 => {
+                let Compact(bytes_len) = Compact::<u32>::decode(&mut &bytes[1..]).ok()?;
+
+                Some(1 + Compact::compact_len(&bytes_len) + bytes_len as usize)
+            }
+            _ => Some(bytes.len()),
+        }
+    }
+
+    fn process_segment_item(
+        &mut self,
+        segment_index: SegmentIndex,
+        segment_item: SegmentItem,
+        reconstructed_contents: &mut ReconstructedContents,
+    ) -> Result<(), StreamingReconstructorError> {
+        match segment_item {
+            SegmentItem::Padding => {
+                // Doesn't contain anything
+            }
+            SegmentItem::Block { bytes, .. } => {
+                self.finish_partial_block(reconstructed_contents);
+
+                reconstructed_contents
+                    .blocks
+                    .push((self.next_block_number, bytes));
+                self.next_block_number += 1;
+            }
+            SegmentItem::BlockStart { bytes, .. } => {
+                self.finish_partial_block(reconstructed_contents);
+
+                self.partial_block.replace(bytes);
+            }
+            SegmentItem::BlockContinuation { bytes, .. } => {
+                // Without partial block this is continuation of the block whose beginning was
+                // archived before reconstruction started, it can't be recovered
+                if let Some(partial_block) = &mut self.partial_block {
+                    partial_block.extend_from_slice(&bytes);
+                }
+            }
+            SegmentItem::ParentSegmentHeader(segment_header) => {
+                if segment_header.segment_index() + SegmentIndex::ONE != segment_index {
+                    return Err(StreamingReconstructorError::UnexpectedParentSegmentHeader {
+                        segment_index,
+                        parent_segment_index: segment_header.segment_index(),
+                    });
+                }
+
+                let LastArchivedBlock {
+                    number,
+                    archived_progress,
+                } = segment_header.last_archived_block();
+
+                match archived_progress {
+                    ArchivedBlockProgress::Complete => {
+                        self.finish_partial_block(reconstructed_contents);
+
+                        self.next_block_number = number + 1;
+                    }
+                    ArchivedBlockProgress::Partial(_bytes) => {
+                        self.next_block_number = number;
+
+                        if self.partial_block.is_none() {
+                            // Will not be able to recover full block, bump right away
+                            self.next_block_number += 1;
+                        }
+                    }
+                }
+
+                reconstructed_contents
+                    .segment_header
+                    .replace(segment_header);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish_partial_block(&mut self, reconstructed_contents: &mut ReconstructedContents) {
+        if let Some(partial_block) = self.partial_block.take() {
+            reconstructed_contents
+                .blocks
+                .push((self.next_block_number, partial_block));
+            self.next_block_number += 1;
+        }
+    }
+}
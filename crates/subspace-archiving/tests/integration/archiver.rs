@@ -1,18 +1,23 @@
 use parity_scale_codec::{Compact, CompactLen, Decode, Encode};
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::assert_matches::assert_matches;
 use std::io::Write;
 use std::iter;
 use subspace_archiving::archiver;
-use subspace_archiving::archiver::{Archiver, ArchiverInstantiationError, SegmentItem};
+use subspace_archiving::archiver::{
+    Archiver, ArchiverCheckpoint, ArchiverInstantiationError, RecordChunksProofError, SegmentItem,
+};
+use subspace_archiving::reconstructor::Reconstructor;
 use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Commitment, Kzg};
 use subspace_core_primitives::crypto::Scalar;
 use subspace_core_primitives::objects::{BlockObject, BlockObjectMapping, PieceObject};
 use subspace_core_primitives::{
-    ArchivedBlockProgress, ArchivedHistorySegment, Blake2b256Hash, LastArchivedBlock, PieceArray,
-    Record, RecordedHistorySegment, SegmentHeader, SegmentIndex, BLAKE2B_256_HASH_SIZE,
+    ArchivedBlockProgress, ArchivedHistorySegment, Blake2b256Hash, LastArchivedBlock, Piece,
+    PieceArray, RawRecord, Record, RecordedHistorySegment, SegmentHeader, SegmentIndex,
+    BLAKE2B_256_HASH_SIZE,
 };
 
 fn extract_data<O: Into<u64>>(data: &[u8], offset: O) -> &[u8] {
@@ -64,7 +69,8 @@ fn compare_block_objects_to_piece_objects<'a>(
 #[test]
 fn archiver() {
     let kzg = Kzg::new(embedded_kzg_settings());
-    let mut archiver = Archiver::new(kzg.clone()).unwrap();
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     let (block_0, block_0_object_mapping) = {
         let mut block = vec![0u8; RecordedHistorySegment::SIZE / 2];
@@ -183,6 +189,27 @@ fn archiver() {
 
         compare_block_objects_to_piece_objects(block_objects, piece_objects);
     }
+    {
+        let block_objects = iter::repeat(block_0.as_ref())
+            .zip(&block_0_object_mapping.objects)
+            .chain(iter::repeat(block_1.as_ref()).zip(block_1_object_mapping.objects.iter()));
+        let global_objects = first_archived_segment.global_object_mapping();
+
+        for ((block, block_object), (hash, global_object)) in block_objects.zip(global_objects) {
+            assert_eq!(block_object.hash(), hash);
+            assert_eq!(
+                global_object.piece_index().segment_index(),
+                SegmentIndex::ZERO
+            );
+
+            let piece =
+                &first_archived_segment.pieces[global_object.piece_index().position() as usize];
+            assert_eq!(
+                extract_data_from_source_record(piece.record(), global_object.offset()),
+                extract_data(block, block_object.offset())
+            );
+        }
+    }
 
     #[cfg(not(feature = "parallel"))]
     let iter = first_archived_segment.pieces.iter().enumerate();
@@ -220,6 +247,7 @@ fn archiver() {
     {
         let mut archiver_with_initial_state = Archiver::with_initial_state(
             kzg.clone(),
+            RecordedHistorySegment::ERASURE_CODING_RATE,
             first_archived_segment.segment_header,
             &block_1,
             block_1_object_mapping.clone(),
@@ -346,6 +374,7 @@ fn archiver() {
     {
         let mut archiver_with_initial_state = Archiver::with_initial_state(
             kzg.clone(),
+            RecordedHistorySegment::ERASURE_CODING_RATE,
             last_segment_header,
             &block_2,
             BlockObjectMapping::default(),
@@ -394,6 +423,7 @@ fn invalid_usage() {
     {
         let result = Archiver::with_initial_state(
             kzg.clone(),
+            RecordedHistorySegment::ERASURE_CODING_RATE,
             SegmentHeader::V0 {
                 segment_index: SegmentIndex::ZERO,
                 segment_commitment: Commitment::default(),
@@ -419,7 +449,8 @@ fn invalid_usage() {
 
     {
         let result = Archiver::with_initial_state(
-            kzg,
+            kzg.clone(),
+            RecordedHistorySegment::ERASURE_CODING_RATE,
             SegmentHeader::V0 {
                 segment_index: SegmentIndex::ZERO,
                 segment_commitment: Commitment::default(),
@@ -447,6 +478,17 @@ fn invalid_usage() {
             assert_eq!(archived_block_bytes, 10);
         }
     }
+
+    // Erasure coding rates that don't match archived history segment layout
+    for erasure_coding_rate in [(0, 2), (2, 3), (1, 1), (1, 4)] {
+        assert_matches!(
+            Archiver::new(kzg.clone(), erasure_coding_rate),
+            Err(ArchiverInstantiationError::UnsupportedErasureCodingRate(rate))
+                if rate == erasure_coding_rate
+        );
+    }
+    // Equivalent rates are fine
+    assert!(Archiver::new(kzg, (2, 4)).is_ok());
 }
 
 // Please check commits where this tests are introduced for the edge cases they are testing (filling
@@ -471,7 +513,7 @@ fn one_byte_smaller_segment() {
         // We leave two bytes at the end intentionally
         - 2;
     assert_eq!(
-        Archiver::new(kzg.clone())
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE)
             .unwrap()
             .add_block(vec![0u8; block_size], BlockObjectMapping::default(), true)
             .len(),
@@ -479,20 +521,22 @@ fn one_byte_smaller_segment() {
     );
     // Cutting just one byte more is not sufficient to produce a segment, this is a protection
     // against code regressions
-    assert!(Archiver::new(kzg)
-        .unwrap()
-        .add_block(
-            vec![0u8; block_size - 1],
-            BlockObjectMapping::default(),
-            true
-        )
-        .is_empty());
+    assert!(
+        Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE)
+            .unwrap()
+            .add_block(
+                vec![0u8; block_size - 1],
+                BlockObjectMapping::default(),
+                true
+            )
+            .is_empty()
+    );
 }
 
 #[test]
 fn spill_over_edge_case() {
     let kzg = Kzg::new(embedded_kzg_settings());
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     // Carefully compute the block size such that there is just 2 bytes left to fill the segment,
     // but this should already produce archived segment since just enum variant and smallest compact
@@ -549,7 +593,7 @@ fn spill_over_edge_case() {
 #[test]
 fn object_on_the_edge_of_segment() {
     let kzg = Kzg::new(embedded_kzg_settings());
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
     let first_block = vec![0u8; RecordedHistorySegment::SIZE];
     let archived_segments =
         archiver.add_block(first_block.clone(), BlockObjectMapping::default(), true);
@@ -664,3 +708,282 @@ fn object_on_the_edge_of_segment() {
         mapped_bytes
     );
 }
+
+#[test]
+fn resume_from_checkpoint() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+
+    let blocks = [
+        RecordedHistorySegment::SIZE / 3,
+        RawRecord::SIZE * 5,
+        RecordedHistorySegment::SIZE / 2,
+        RecordedHistorySegment::SIZE * 2,
+        RawRecord::SIZE * 3,
+        RecordedHistorySegment::SIZE,
+    ]
+    .map(|block_size| {
+        let mut block = vec![0u8; block_size];
+        thread_rng().fill(block.as_mut_slice());
+
+        let object_offset = block_size / 2;
+        block[object_offset..]
+            .as_mut()
+            .write_all(&Compact(100_u64).encode())
+            .unwrap();
+        let object_mapping = BlockObjectMapping {
+            objects: vec![BlockObject::V0 {
+                hash: Blake2b256Hash::default(),
+                offset: object_offset as u32,
+            }],
+        };
+
+        (block, object_mapping)
+    });
+
+    // Alternate incremental and non-incremental archiving to cover both empty and non-empty
+    // incremental record commitments in checkpoints
+    let incremental = |index: usize| index % 2 == 0;
+
+    let mut checkpoints = Vec::with_capacity(blocks.len());
+    let mut archived_segments = Vec::with_capacity(blocks.len());
+    for (index, (block, object_mapping)) in blocks.iter().enumerate() {
+        checkpoints.push(archiver.checkpoint());
+        archived_segments.push(archiver.add_block(
+            block.clone(),
+            object_mapping.clone(),
+            incremental(index),
+        ));
+    }
+    assert!(archived_segments.iter().flatten().count() >= 3);
+
+    for (checkpoint_index, checkpoint) in checkpoints.into_iter().enumerate() {
+        // Checkpoint must survive round-trip through encoding
+        let checkpoint = ArchiverCheckpoint::decode(&mut checkpoint.encode().as_slice()).unwrap();
+        let mut resumed_archiver = Archiver::from_checkpoint(
+            kzg.clone(),
+            RecordedHistorySegment::ERASURE_CODING_RATE,
+            checkpoint,
+        )
+        .unwrap();
+
+        // Resumed archiver must produce byte-identical output to uninterrupted archiver
+        for (index, (block, object_mapping)) in blocks.iter().enumerate().skip(checkpoint_index) {
+            assert_eq!(
+                resumed_archiver.add_block(
+                    block.clone(),
+                    object_mapping.clone(),
+                    incremental(index)
+                ),
+                archived_segments[index],
+                "Checkpoint before block {checkpoint_index}, block {index}"
+            );
+        }
+        assert_eq!(resumed_archiver.checkpoint(), archiver.checkpoint());
+    }
+}
+
+#[test]
+fn record_chunks_proof() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+
+    let mut block = vec![0u8; RecordedHistorySegment::SIZE];
+    thread_rng().fill(block.as_mut_slice());
+    let archived_segment = archiver
+        .add_block(block, BlockObjectMapping::default(), true)
+        .into_iter()
+        .next()
+        .unwrap();
+    let segment_commitment = archived_segment.segment_header.segment_commitment();
+
+    // Second source piece
+    let position = 2;
+    let piece = &archived_segment.pieces[position as usize];
+
+    let raw_bytes_range = 100..200;
+    let chunks = archiver::raw_record_chunks_range(raw_bytes_range.clone());
+    assert_eq!(chunks, 3..7);
+
+    let proof = archiver::create_record_chunks_proof(&kzg, piece, chunks.clone()).unwrap();
+    assert_eq!(proof.chunks.len(), chunks.len());
+    assert!(archiver::is_record_chunks_proof_valid(
+        &kzg,
+        &proof,
+        &segment_commitment,
+        position
+    ));
+
+    // Proof must cover requested raw record bytes
+    let chunks_start_byte = chunks.start as usize * Scalar::SAFE_BYTES;
+    assert_eq!(
+        proof
+            .raw_bytes()
+            .skip(raw_bytes_range.start as usize - chunks_start_byte)
+            .take(raw_bytes_range.len())
+            .collect::<Vec<_>>(),
+        record_to_raw_record_bytes(piece.record())
+            .skip(raw_bytes_range.start as usize)
+            .take(raw_bytes_range.len())
+            .collect::<Vec<_>>()
+    );
+
+    // Wrong position
+    assert!(!archiver::is_record_chunks_proof_valid(
+        &kzg,
+        &proof,
+        &segment_commitment,
+        position + 2
+    ));
+
+    // Tampered chunk
+    {
+        let mut proof = proof.clone();
+        proof.chunks[1] = Scalar::from(&[1; Scalar::SAFE_BYTES]);
+        assert!(!archiver::is_record_chunks_proof_valid(
+            &kzg,
+            &proof,
+            &segment_commitment,
+            position
+        ));
+    }
+
+    // Shifted chunks
+    {
+        let mut proof = proof.clone();
+        proof.first_chunk_offset += 1;
+        assert!(!archiver::is_record_chunks_proof_valid(
+            &kzg,
+            &proof,
+            &segment_commitment,
+            position
+        ));
+    }
+
+    // Missing witness
+    {
+        let mut proof = proof;
+        proof.chunk_witnesses.pop();
+        assert!(!archiver::is_record_chunks_proof_valid(
+            &kzg,
+            &proof,
+            &segment_commitment,
+            position
+        ));
+    }
+
+    assert_eq!(
+        archiver::create_record_chunks_proof(&kzg, piece, 5..5),
+        Err(RecordChunksProofError::InvalidChunksRange { start: 5, end: 5 })
+    );
+    let end = Record::NUM_CHUNKS as u32 + 1;
+    assert_eq!(
+        archiver::create_record_chunks_proof(&kzg, piece, 0..end),
+        Err(RecordChunksProofError::InvalidChunksRange { start: 0, end })
+    );
+}
+
+#[test]
+fn pending_archived_segments() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+    // Fixed seed such that output is reproducible and doesn't depend on luck
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let blocks = [
+        RecordedHistorySegment::SIZE / 2,
+        RecordedHistorySegment::SIZE,
+        RawRecord::SIZE * 3,
+        RecordedHistorySegment::SIZE * 2,
+    ]
+    .into_iter()
+    .map(|block_size| {
+        let mut block = vec![0u8; block_size];
+        rng.fill(block.as_mut_slice());
+        block
+    })
+    .collect::<Vec<_>>();
+
+    let mut archived_segments = Vec::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let incremental = index % 2 == 0;
+
+        for pending_archived_segment in
+            archiver.add_block_pending(block.clone(), BlockObjectMapping::default(), incremental)
+        {
+            // Segment header is known before pieces are created
+            let segment_header = *pending_archived_segment.segment_header();
+            let archived_segment = pending_archived_segment.finish();
+            assert_eq!(archived_segment.segment_header, segment_header);
+
+            archived_segments.push(archived_segment);
+        }
+    }
+
+    assert_eq!(archived_segments.len(), 3);
+
+    // Output is checked against independent implementations rather than regular archiving, since
+    // both share the same code path
+    let mut reconstructor =
+        Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+    let mut reconstructed_blocks = Vec::new();
+    let mut prev_segment_header_hash = Blake2b256Hash::default();
+    for (segment_index, archived_segment) in archived_segments.iter().enumerate() {
+        let segment_header = archived_segment.segment_header;
+        assert_eq!(
+            segment_header.segment_index(),
+            SegmentIndex::from(segment_index as u64)
+        );
+        assert_eq!(
+            segment_header.prev_segment_header_hash(),
+            prev_segment_header_hash
+        );
+        prev_segment_header_hash = segment_header.hash();
+
+        // Records match their commitments and commitments match segment commitment
+        for (position, piece) in archived_segment.pieces.iter().enumerate() {
+            assert!(
+                archiver::is_piece_valid(
+                    &kzg,
+                    piece,
+                    &segment_header.segment_commitment(),
+                    position as u32,
+                ),
+                "Piece at position {position} of segment {segment_index} is valid"
+            );
+        }
+
+        // Parity records are erasure coded source records, such that blocks can be recovered from
+        // parity records alone
+        let contents = reconstructor
+            .add_segment(
+                &iter::repeat(None)
+                    .zip(archived_segment.pieces.parity().map(Piece::from).map(Some))
+                    .flat_map(|(a, b)| [a, b])
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap();
+        reconstructed_blocks.extend(contents.blocks);
+    }
+
+    // Last block is only partially archived
+    assert_eq!(
+        reconstructed_blocks,
+        blocks
+            .into_iter()
+            .take(3)
+            .enumerate()
+            .map(|(block_number, block)| (block_number as u32, block))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        archived_segments[2]
+            .segment_header
+            .last_archived_block()
+            .number,
+        3
+    );
+}
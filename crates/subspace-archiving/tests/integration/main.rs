@@ -3,3 +3,4 @@
 mod archiver;
 mod piece_reconstruction;
 mod reconstructor;
+mod streaming_reconstructor;
@@ -21,7 +21,8 @@ fn get_random_block() -> Vec<u8> {
 #[test]
 fn segment_reconstruction_works() {
     let kzg = Kzg::new(embedded_kzg_settings());
-    let mut archiver = Archiver::new(kzg.clone()).unwrap();
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     let block = get_random_block();
 
@@ -42,7 +43,8 @@ fn segment_reconstruction_works() {
             piece.take();
         });
 
-    let reconstructor = PiecesReconstructor::new(kzg).unwrap();
+    let reconstructor =
+        PiecesReconstructor::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     let flat_pieces = reconstructor.reconstruct_segment(&maybe_pieces).unwrap();
 
@@ -62,7 +64,8 @@ fn segment_reconstruction_works() {
 #[test]
 fn piece_reconstruction_works() {
     let kzg = Kzg::new(embedded_kzg_settings());
-    let mut archiver = Archiver::new(kzg.clone()).unwrap();
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
     // Block that fits into the segment fully
     let block = get_random_block();
 
@@ -83,7 +86,8 @@ fn piece_reconstruction_works() {
         .map(|(piece_position, piece)| (piece_position, piece.take().unwrap()))
         .collect::<Vec<_>>();
 
-    let reconstructor = PiecesReconstructor::new(kzg).unwrap();
+    let reconstructor =
+        PiecesReconstructor::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     #[cfg(not(feature = "parallel"))]
     let iter = missing_pieces.iter();
@@ -108,7 +112,8 @@ fn piece_reconstruction_works() {
 fn segment_reconstruction_fails() {
     let kzg = Kzg::new(embedded_kzg_settings());
 
-    let reconstructor = PiecesReconstructor::new(kzg.clone()).unwrap();
+    let reconstructor =
+        PiecesReconstructor::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     let pieces = vec![None];
     let result = reconstructor.reconstruct_segment(&pieces);
@@ -122,7 +127,7 @@ fn segment_reconstruction_fails() {
         ));
     }
 
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
     // Block that fits into the segment fully
     let block = get_random_block();
 
@@ -145,7 +150,8 @@ fn segment_reconstruction_fails() {
 fn piece_reconstruction_fails() {
     let kzg = Kzg::new(embedded_kzg_settings());
 
-    let reconstructor = PiecesReconstructor::new(kzg.clone()).unwrap();
+    let reconstructor =
+        PiecesReconstructor::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     let pieces = vec![None];
     let result = reconstructor.reconstruct_piece(&pieces, 0);
@@ -159,7 +165,7 @@ fn piece_reconstruction_fails() {
         ));
     }
 
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
     // Block that fits into the segment fully
     let block = get_random_block();
 
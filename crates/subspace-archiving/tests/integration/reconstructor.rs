@@ -17,7 +17,7 @@ fn pieces_to_option_of_pieces(pieces: &FlatPieces) -> Vec<Option<Piece>> {
 #[test]
 fn basic() {
     let kzg = Kzg::new(embedded_kzg_settings());
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
     // Block that fits into the segment fully
     let block_0 = {
         let mut block = vec![0u8; RecordedHistorySegment::SIZE / 2];
@@ -59,7 +59,8 @@ fn basic() {
 
     assert_eq!(archived_segments.len(), 5);
 
-    let mut reconstructor = Reconstructor::new().unwrap();
+    let mut reconstructor =
+        Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
     {
         let contents = reconstructor
@@ -91,7 +92,8 @@ fn basic() {
             }
         );
 
-        let mut partial_reconstructor = Reconstructor::new().unwrap();
+        let mut partial_reconstructor =
+            Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
         let contents = partial_reconstructor
             .add_segment(&pieces_to_option_of_pieces(&archived_segments[1].pieces))
             .unwrap();
@@ -132,7 +134,8 @@ fn basic() {
             }
         );
 
-        let mut partial_reconstructor = Reconstructor::new().unwrap();
+        let mut partial_reconstructor =
+            Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
         let contents = partial_reconstructor
             .add_segment(&pieces_to_option_of_pieces(&archived_segments[2].pieces))
             .unwrap();
@@ -175,7 +178,8 @@ fn basic() {
     }
 
     {
-        let mut partial_reconstructor = Reconstructor::new().unwrap();
+        let mut partial_reconstructor =
+            Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
         let contents = partial_reconstructor
             .add_segment(&pieces_to_option_of_pieces(&archived_segments[3].pieces))
             .unwrap();
@@ -218,7 +222,8 @@ fn basic() {
     }
 
     {
-        let mut partial_reconstructor = Reconstructor::new().unwrap();
+        let mut partial_reconstructor =
+            Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
         let contents = partial_reconstructor
             .add_segment(&pieces_to_option_of_pieces(&archived_segments[4].pieces))
             .unwrap();
@@ -243,7 +248,7 @@ fn basic() {
 #[test]
 fn partial_data() {
     let kzg = Kzg::new(embedded_kzg_settings());
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
     // Block that fits into the segment fully
     let block_0 = {
         let mut block = vec![0u8; RecordedHistorySegment::SIZE / 2];
@@ -268,7 +273,7 @@ fn partial_data() {
 
     {
         // Take just source shards
-        let contents = Reconstructor::new()
+        let contents = Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE)
             .unwrap()
             .add_segment(
                 &pieces
@@ -286,7 +291,7 @@ fn partial_data() {
 
     {
         // Take just parity shards
-        let contents = Reconstructor::new()
+        let contents = Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE)
             .unwrap()
             .add_segment(
                 &iter::repeat(None)
@@ -314,7 +319,10 @@ fn partial_data() {
             .for_each(|piece| {
                 piece.take();
             });
-        let contents = Reconstructor::new().unwrap().add_segment(&pieces).unwrap();
+        let contents = Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE)
+            .unwrap()
+            .add_segment(&pieces)
+            .unwrap();
 
         assert_eq!(contents.blocks, vec![(0, block_0)]);
     }
@@ -324,7 +332,7 @@ fn partial_data() {
 fn invalid_usage() {
     let kzg = Kzg::new(embedded_kzg_settings());
 
-    let mut archiver = Archiver::new(kzg).unwrap();
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
     // Block that overflows into the next segments
     let block_0 = {
         let mut block = vec![0u8; RecordedHistorySegment::SIZE * 4];
@@ -338,38 +346,43 @@ fn invalid_usage() {
 
     {
         // Not enough shards with contents
-        let result = Reconstructor::new().unwrap().add_segment(
-            &archived_segments[0]
-                .pieces
-                .iter()
-                .take(RecordedHistorySegment::NUM_RAW_RECORDS - 1)
-                .map(Piece::from)
-                .map(Some)
-                .chain(iter::repeat(None))
-                .take(ArchivedHistorySegment::NUM_PIECES)
-                .collect::<Vec<_>>(),
-        );
+        let result = Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE)
+            .unwrap()
+            .add_segment(
+                &archived_segments[0]
+                    .pieces
+                    .iter()
+                    .take(RecordedHistorySegment::NUM_RAW_RECORDS - 1)
+                    .map(Piece::from)
+                    .map(Some)
+                    .chain(iter::repeat(None))
+                    .take(ArchivedHistorySegment::NUM_PIECES)
+                    .collect::<Vec<_>>(),
+            );
 
         assert_matches!(result, Err(ReconstructorError::DataShardsReconstruction(_)));
     }
 
     {
         // Garbage data
-        let result = Reconstructor::new().unwrap().add_segment(
-            &iter::repeat_with(|| {
-                let mut piece = Piece::default();
-                thread_rng().fill(piece.as_mut());
-                Some(piece)
-            })
-            .take(ArchivedHistorySegment::NUM_PIECES)
-            .collect::<Vec<_>>(),
-        );
+        let result = Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE)
+            .unwrap()
+            .add_segment(
+                &iter::repeat_with(|| {
+                    let mut piece = Piece::default();
+                    thread_rng().fill(piece.as_mut());
+                    Some(piece)
+                })
+                .take(ArchivedHistorySegment::NUM_PIECES)
+                .collect::<Vec<_>>(),
+            );
 
         assert_matches!(result, Err(ReconstructorError::SegmentDecoding(_)));
     }
 
     {
-        let mut reconstructor = Reconstructor::new().unwrap();
+        let mut reconstructor =
+            Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
 
         reconstructor
             .add_segment(&pieces_to_option_of_pieces(&archived_segments[0].pieces))
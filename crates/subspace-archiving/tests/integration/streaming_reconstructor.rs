@@ -0,0 +1,166 @@
+use rand::{thread_rng, Rng};
+use std::assert_matches::assert_matches;
+use subspace_archiving::archiver::{Archiver, NewArchivedSegment};
+use subspace_archiving::streaming_reconstructor::{
+    StreamingReconstructor, StreamingReconstructorError,
+};
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::objects::BlockObjectMapping;
+use subspace_core_primitives::{
+    ArchivedHistorySegment, BlockNumber, PieceIndex, RecordedHistorySegment, SegmentIndex,
+};
+
+/// Add source pieces of archived segments to reconstructor, returns reconstructed blocks along
+/// with the index of the piece that completed them
+fn add_source_pieces(
+    reconstructor: &mut StreamingReconstructor,
+    archived_segments: &[NewArchivedSegment],
+) -> Vec<(PieceIndex, BlockNumber, Vec<u8>)> {
+    let mut blocks = Vec::new();
+
+    for archived_segment in archived_segments {
+        let first_piece_index = archived_segment
+            .segment_header
+            .segment_index()
+            .first_piece_index();
+
+        for (position, piece) in archived_segment.pieces.iter().enumerate().step_by(2) {
+            let piece_index = first_piece_index + PieceIndex::from(position as u64);
+            let contents = reconstructor.add_piece(piece_index, piece).unwrap();
+
+            if position == 0 && archived_segment.segment_header.segment_index() > SegmentIndex::ZERO
+            {
+                assert_eq!(
+                    contents.segment_header.unwrap().segment_index() + SegmentIndex::ONE,
+                    archived_segment.segment_header.segment_index()
+                );
+            } else {
+                assert_eq!(contents.segment_header, None);
+            }
+
+            blocks.extend(
+                contents
+                    .blocks
+                    .into_iter()
+                    .map(|(block_number, block)| (piece_index, block_number, block)),
+            );
+        }
+    }
+
+    blocks
+}
+
+#[test]
+fn basic() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+    let blocks = [
+        // Block that fits into the segment fully
+        RecordedHistorySegment::SIZE / 2,
+        // Block that overflows into the next segment
+        RecordedHistorySegment::SIZE,
+        // Block that also fits into the segment fully
+        RecordedHistorySegment::SIZE / 4,
+        // Block that occupies multiple segments
+        RecordedHistorySegment::SIZE * 3,
+        // Extra block
+        RecordedHistorySegment::SIZE,
+    ]
+    .map(|block_size| {
+        let mut block = vec![0u8; block_size];
+        thread_rng().fill(block.as_mut_slice());
+        block
+    });
+    let archived_segments = blocks
+        .iter()
+        .flat_map(|block| archiver.add_block(block.clone(), BlockObjectMapping::default(), true))
+        .collect::<Vec<_>>();
+
+    assert_eq!(archived_segments.len(), 5);
+
+    {
+        let mut reconstructor = StreamingReconstructor::new();
+        let reconstructed_blocks = add_source_pieces(&mut reconstructor, &archived_segments);
+
+        assert_eq!(
+            reconstructed_blocks
+                .iter()
+                .map(|(_piece_index, block_number, block)| (*block_number, block))
+                .collect::<Vec<_>>(),
+            blocks
+                .iter()
+                .take(4)
+                .enumerate()
+                .map(|(block_number, block)| (block_number as BlockNumber, block))
+                .collect::<Vec<_>>()
+        );
+
+        // First block is yielded as soon as its pieces are available, before the segment is
+        // complete
+        let (piece_index, _block_number, _block) = &reconstructed_blocks[0];
+        assert_eq!(piece_index.segment_index(), SegmentIndex::ZERO);
+        assert!((piece_index.position() as usize) < ArchivedHistorySegment::NUM_PIECES / 2 + 2);
+
+        assert_eq!(
+            reconstructor.next_piece_index(),
+            Some(SegmentIndex::from(5).first_piece_index())
+        );
+    }
+
+    // Start mid-history, block that started in the first segment can't be reconstructed
+    {
+        let mut reconstructor = StreamingReconstructor::new();
+        let reconstructed_blocks = add_source_pieces(&mut reconstructor, &archived_segments[1..]);
+
+        assert_eq!(
+            reconstructed_blocks
+                .iter()
+                .map(|(_piece_index, block_number, block)| (*block_number, block))
+                .collect::<Vec<_>>(),
+            vec![(2, &blocks[2]), (3, &blocks[3])]
+        );
+    }
+}
+
+#[test]
+fn invalid_usage() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+    let mut block = vec![0u8; RecordedHistorySegment::SIZE];
+    thread_rng().fill(block.as_mut_slice());
+    let archived_segments = archiver.add_block(block, BlockObjectMapping::default(), true);
+    let pieces = &archived_segments[0].pieces;
+
+    let mut reconstructor = StreamingReconstructor::new();
+
+    // Must start with the first piece of a segment
+    assert_matches!(
+        reconstructor.add_piece(PieceIndex::from(2), &pieces[2]),
+        Err(StreamingReconstructorError::IncorrectPieceOrder {
+            expected_piece_index,
+            actual_piece_index,
+        }) if expected_piece_index == PieceIndex::ZERO && actual_piece_index == PieceIndex::from(2)
+    );
+
+    reconstructor
+        .add_piece(PieceIndex::ZERO, &pieces[0])
+        .unwrap();
+
+    // Parity pieces are not accepted
+    assert_matches!(
+        reconstructor.add_piece(PieceIndex::ONE, &pieces[1]),
+        Err(StreamingReconstructorError::IncorrectPieceOrder {
+            expected_piece_index,
+            actual_piece_index,
+        }) if expected_piece_index == PieceIndex::from(2) && actual_piece_index == PieceIndex::ONE
+    );
+
+    // Pieces can't be skipped
+    assert_matches!(
+        reconstructor.add_piece(PieceIndex::from(4), &pieces[4]),
+        Err(StreamingReconstructorError::IncorrectPieceOrder {
+            expected_piece_index,
+            actual_piece_index,
+        }) if expected_piece_index == PieceIndex::from(2) && actual_piece_index == PieceIndex::from(4)
+    );
+}
@@ -60,8 +60,8 @@ use derive_more::{Add, AsMut, AsRef, Deref, DerefMut, Display, Div, From, Into,
 use num_traits::{WrappingAdd, WrappingSub};
 use parity_scale_codec::{Decode, Encode, MaxEncodedLen};
 pub use pieces::{
-    FlatPieces, Piece, PieceArray, PieceIndex, PieceOffset, RawRecord, Record, RecordCommitment,
-    RecordWitness, SBucket,
+    FlatPieces, Piece, PieceArray, PieceIndex, PieceOffset, RawRecord, Record, RecordChunksProof,
+    RecordCommitment, RecordWitness, SBucket,
 };
 use scale_info::TypeInfo;
 pub use segments::{ArchivedHistorySegment, HistorySize, RecordedHistorySegment, SegmentIndex};
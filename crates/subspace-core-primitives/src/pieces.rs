@@ -3,6 +3,7 @@ mod serde;
 #[cfg(test)]
 mod tests;
 
+use crate::crypto::kzg::Witness;
 use crate::crypto::Scalar;
 use crate::segments::{ArchivedHistorySegment, SegmentIndex};
 use crate::RecordedHistorySegment;
@@ -926,3 +927,33 @@ impl AsMut<[u8]> for FlatPieces {
         pieces.flatten_mut()
     }
 }
+
+/// Consecutive chunks of a record along with proofs of their inclusion in archived history.
+///
+/// Allows verifying a small portion of a piece against segment commitment without retrieving the
+/// whole piece.
+#[derive(Debug, Clone, Eq, PartialEq, Encode, Decode, TypeInfo)]
+pub struct RecordChunksProof {
+    /// Record commitment that can be used to verify that piece was included in blockchain history
+    pub record_commitment: RecordCommitment,
+    /// Witness for above record commitment
+    pub record_witness: RecordWitness,
+    /// Offset of the first chunk within the record
+    pub first_chunk_offset: u32,
+    /// Chunks of the record starting at above offset
+    pub chunks: Vec<Scalar>,
+    /// Witnesses for above chunks
+    pub chunk_witnesses: Vec<Witness>,
+}
+
+impl RecordChunksProof {
+    /// Bytes of raw record contained in chunks of this proof, raw record offset of the first byte
+    /// is `first_chunk_offset * Scalar::SAFE_BYTES`.
+    pub fn raw_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        // Chunks of source records are created from [`Scalar::SAFE_BYTES`] bytes of raw record,
+        // the rest is zero padding that needs to be skipped
+        self.chunks
+            .iter()
+            .flat_map(|chunk| chunk.to_bytes().into_iter().take(Scalar::SAFE_BYTES))
+    }
+}
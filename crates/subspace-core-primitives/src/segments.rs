@@ -201,7 +201,11 @@ impl AsMut<[u8]> for RecordedHistorySegment {
 impl RecordedHistorySegment {
     /// Number of raw records in one segment of recorded history.
     pub const NUM_RAW_RECORDS: usize = 128;
-    /// Erasure coding rate for records during archiving process.
+    /// Default erasure coding rate for records during archiving process, the rate actually used is
+    /// specified in chain constants.
+    ///
+    /// NOTE: Only rates that expand [`Self::NUM_RAW_RECORDS`] source records into
+    /// [`ArchivedHistorySegment::NUM_PIECES`] pieces are supported by archiving.
     pub const ERASURE_CODING_RATE: (usize, usize) = (1, 2);
     /// Size of recorded history segment in bytes.
     ///
@@ -9,19 +9,107 @@ use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use blst_rust::types::fft_settings::FsFFTSettings;
+use blst_rust::types::fr::FsFr;
 use blst_rust::types::g1::FsG1;
 use blst_rust::types::poly::FsPoly;
 use core::num::NonZeroUsize;
-use kzg::{FFTSettings, PolyRecover, DAS, FFTG1, G1};
+use kzg::{FFTFr, FFTSettings, Fr, PolyRecover, DAS, FFTG1, G1};
 use subspace_core_primitives::crypto::kzg::{Commitment, Polynomial};
 use subspace_core_primitives::crypto::Scalar;
 
+/// Erasure coding-related error
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
+pub enum ErasureCodingError {
+    /// Expansion factor must be at least 2
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Expansion factor must be at least 2, {0} provided")
+    )]
+    InvalidExpansionFactor(usize),
+    /// Expansion factor is too large for the scale
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Expansion factor {expansion_factor} is too large for scale {scale}")
+    )]
+    ExpansionFactorTooLarge {
+        /// Expansion factor
+        expansion_factor: usize,
+        /// Scale
+        scale: usize,
+    },
+    /// Number of source shards must be a power of two
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Number of source shards must be a power of two, {0} provided")
+    )]
+    InvalidNumberOfSourceShards(usize),
+    /// Number of shards must be a multiple of expansion factor
+    #[cfg_attr(
+        feature = "thiserror",
+        error(
+            "Number of shards {shards} is not a multiple of expansion factor {expansion_factor}"
+        )
+    )]
+    InvalidNumberOfShards {
+        /// Number of shards
+        shards: usize,
+        /// Expansion factor
+        expansion_factor: usize,
+    },
+    /// Too many shards for this erasure coding instance
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Number of shards {shards} exceeds max number of shards {max_shards}")
+    )]
+    TooManyShards {
+        /// Number of shards
+        shards: usize,
+        /// Max number of shards
+        max_shards: usize,
+    },
+    /// Not enough shards to recover data
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Not enough shards, {available} available, at least {required} required")
+    )]
+    NotEnoughShards {
+        /// Number of available shards
+        available: usize,
+        /// Number of required shards
+        required: usize,
+    },
+    /// Error from underlying KZG library
+    #[cfg_attr(feature = "thiserror", error("KZG error: {0}"))]
+    Kzg(String),
+}
+
+/// Erasure coding parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ErasureCodingParams {
+    /// Size of the evaluation domain is `2^scale`, limits the number of supported shards
+    pub scale: NonZeroUsize,
+    /// Total number of shards (source and parity) per source shard, at least 2
+    pub expansion_factor: NonZeroUsize,
+}
+
 /// Erasure coding abstraction.
 ///
 /// Supports creation of parity records and recovery of missing data.
+///
+/// Each source shard is followed by `expansion_factor - 1` parity shards:
+/// source, parity, ..., parity, source, parity, ..., parity, ...
+///
+/// Source shards are evaluations of a polynomial at a subgroup of the evaluation domain, parity
+/// shards are evaluations at the points following each of them. For expansion factors that are not
+/// powers of two some of the evaluation points are not used.
 #[derive(Debug, Clone)]
 pub struct ErasureCoding {
     fft_settings: Arc<FsFFTSettings>,
+    expansion_factor: usize,
+    /// Distance between evaluation points of consecutive source shards, the smallest power of two
+    /// that is not smaller than expansion factor
+    stride: usize,
 }
 
 impl ErasureCoding {
@@ -29,66 +117,193 @@ impl ErasureCoding {
     ///
     /// Number of shards supported is `2^scale`, half of shards are source data and the other half
     /// are parity.
-    pub fn new(scale: NonZeroUsize) -> Result<Self, String> {
-        let fft_settings = Arc::new(FsFFTSettings::new(scale.get())?);
+    pub fn new(scale: NonZeroUsize) -> Result<Self, ErasureCodingError> {
+        Self::with_params(ErasureCodingParams {
+            scale,
+            expansion_factor: NonZeroUsize::new(2).expect("Not zero; qed"),
+        })
+    }
+
+    /// Create new erasure coding instance with custom parameters.
+    pub fn with_params(params: ErasureCodingParams) -> Result<Self, ErasureCodingError> {
+        let ErasureCodingParams {
+            scale,
+            expansion_factor,
+        } = params;
+        let expansion_factor = expansion_factor.get();
+
+        if expansion_factor < 2 {
+            return Err(ErasureCodingError::InvalidExpansionFactor(expansion_factor));
+        }
+
+        let stride = expansion_factor.next_power_of_two();
+        if stride.ilog2() as usize > scale.get() {
+            return Err(ErasureCodingError::ExpansionFactorTooLarge {
+                expansion_factor,
+                scale: scale.get(),
+            });
+        }
+
+        let fft_settings =
+            Arc::new(FsFFTSettings::new(scale.get()).map_err(ErasureCodingError::Kzg)?);
 
-        Ok(Self { fft_settings })
+        Ok(Self {
+            fft_settings,
+            expansion_factor,
+            stride,
+        })
     }
 
     /// Max number of shards supported (both source and parity together)
     pub fn max_shards(&self) -> usize {
-        self.fft_settings.max_width
+        self.fft_settings.max_width / self.stride * self.expansion_factor
+    }
+
+    /// Total number of shards (source and parity) per source shard
+    pub fn expansion_factor(&self) -> usize {
+        self.expansion_factor
     }
 
     /// Extend sources using erasure coding.
     ///
-    /// Returns parity data.
-    pub fn extend(&self, source: &[Scalar]) -> Result<Vec<Scalar>, String> {
-        // TODO: das_fft_extension modifies buffer internally, it needs to change to use
-        //  pre-allocated buffer instead of allocating a new one
-        self.fft_settings
-            .das_fft_extension(Scalar::slice_to_repr(source))
-            .map(Scalar::vec_from_repr)
+    /// Returns parity data, `expansion_factor - 1` parity shards for each source shard one after
+    /// another.
+    pub fn extend(&self, source: &[Scalar]) -> Result<Vec<Scalar>, ErasureCodingError> {
+        self.check_source_shards(source.len())?;
+
+        if self.expansion_factor == 2 {
+            // TODO: das_fft_extension modifies buffer internally, it needs to change to use
+            //  pre-allocated buffer instead of allocating a new one
+            return self
+                .fft_settings
+                .das_fft_extension(Scalar::slice_to_repr(source))
+                .map(Scalar::vec_from_repr)
+                .map_err(ErasureCodingError::Kzg);
+        }
+
+        let evaluations = self.evaluate(Scalar::slice_to_repr(source))?;
+
+        Ok(Scalar::vec_from_repr(
+            evaluations
+                .chunks_exact(self.stride)
+                .flat_map(|chunk| &chunk[1..self.expansion_factor])
+                .copied()
+                .collect(),
+        ))
     }
 
-    /// Recovery of missing shards from given shards (at least 1/2 should be `Some`).
+    /// Extend multiple sources using erasure coding, the same as calling
+    /// [`ErasureCoding::extend()`] for each of them.
+    pub fn extend_batch(
+        &self,
+        sources: &[&[Scalar]],
+    ) -> Result<Vec<Vec<Scalar>>, ErasureCodingError> {
+        sources.iter().map(|source| self.extend(source)).collect()
+    }
+
+    /// Recovery of missing shards from given shards (at least `1/expansion_factor` should be
+    /// `Some`).
     ///
-    /// Both in input and output source shards are interleaved with parity shards:
-    /// source, parity, source, parity, ...
-    pub fn recover(&self, shards: &[Option<Scalar>]) -> Result<Vec<Scalar>, String> {
-        let poly = FsPoly::recover_poly_from_samples(
-            Scalar::slice_option_to_repr(shards),
-            &self.fft_settings,
-        )?;
+    /// Both in input and output source shards are interleaved with parity shards, see
+    /// [`ErasureCoding`] for details.
+    pub fn recover(&self, shards: &[Option<Scalar>]) -> Result<Vec<Scalar>, ErasureCodingError> {
+        self.check_shards(shards)?;
+
+        if self.expansion_factor == 2 {
+            let poly = FsPoly::recover_poly_from_samples(
+                Scalar::slice_option_to_repr(shards),
+                &self.fft_settings,
+            )
+            .map_err(ErasureCodingError::Kzg)?;
+
+            return Ok(Scalar::vec_from_repr(poly.coeffs));
+        }
 
-        Ok(Scalar::vec_from_repr(poly.coeffs))
+        let recovery = Recovery::new(self, shards)?;
+        let source = recovery.recover_source(Scalar::slice_option_to_repr(shards));
+
+        self.shards_from_source(&source)
     }
 
-    /// Recovery of missing shards from given shards (at least 1/2 should be `Some`) in form of
-    /// normalized polynomial (allows to not do inverse FFT afterwards if polynomial is desired).
+    /// Recovery of missing shards of multiple sets of shards, the same as calling
+    /// [`ErasureCoding::recover()`] for each of them.
     ///
-    /// Both in input and output source shards are interleaved with parity shards:
-    /// source, parity, source, parity, ...
-    pub fn recover_poly(&self, shards: &[Option<Scalar>]) -> Result<Polynomial, String> {
-        let mut poly = Polynomial::from(FsPoly::recover_poly_coeffs_from_samples(
-            Scalar::slice_option_to_repr(shards),
-            &self.fft_settings,
-        )?);
+    /// Recovery is more efficient when the same shards are missing in consecutive sets of shards.
+    pub fn recover_batch(
+        &self,
+        shards: &[&[Option<Scalar>]],
+    ) -> Result<Vec<Vec<Scalar>>, ErasureCodingError> {
+        if self.expansion_factor == 2 {
+            return shards.iter().map(|shards| self.recover(shards)).collect();
+        }
+
+        let mut maybe_recovery = None::<Recovery>;
+        shards
+            .iter()
+            .map(|shards| {
+                self.check_shards(shards)?;
+
+                let recovery = match maybe_recovery.take() {
+                    Some(recovery) if recovery.is_applicable(shards) => recovery,
+                    _ => Recovery::new(self, shards)?,
+                };
+                let source = recovery.recover_source(Scalar::slice_option_to_repr(shards));
+                maybe_recovery.replace(recovery);
+
+                self.shards_from_source(&source)
+            })
+            .collect()
+    }
+
+    /// Recovery of missing shards from given shards (at least `1/expansion_factor` should be
+    /// `Some`) in form of normalized polynomial (allows to not do inverse FFT afterwards if
+    /// polynomial is desired).
+    ///
+    /// Both in input and output source shards are interleaved with parity shards, see
+    /// [`ErasureCoding`] for details.
+    pub fn recover_poly(
+        &self,
+        shards: &[Option<Scalar>],
+    ) -> Result<Polynomial, ErasureCodingError> {
+        self.check_shards(shards)?;
+
+        let mut poly = if self.expansion_factor == 2 {
+            Polynomial::from(
+                FsPoly::recover_poly_coeffs_from_samples(
+                    Scalar::slice_option_to_repr(shards),
+                    &self.fft_settings,
+                )
+                .map_err(ErasureCodingError::Kzg)?,
+            )
+        } else {
+            let recovery = Recovery::new(self, shards)?;
+            let source = recovery.recover_source(Scalar::slice_option_to_repr(shards));
+            let coeffs = self
+                .fft_settings
+                .fft_fr(&source, true)
+                .map_err(ErasureCodingError::Kzg)?;
+
+            Polynomial::from(FsPoly { coeffs })
+        };
 
         poly.normalize();
 
         Ok(poly)
     }
 
-    /// Recovery of source shards from given shards (at least 1/2 should be `Some`).
+    /// Recovery of source shards from given shards (at least `1/expansion_factor` should be
+    /// `Some`).
     ///
     /// The same as [`ErasureCoding::recover()`], but returns only source shards in form of an
     /// iterator.
     pub fn recover_source(
         &self,
         shards: &[Option<Scalar>],
-    ) -> Result<impl ExactSizeIterator<Item = Scalar>, String> {
-        Ok(self.recover(shards)?.into_iter().step_by(2))
+    ) -> Result<impl ExactSizeIterator<Item = Scalar>, ErasureCodingError> {
+        Ok(self
+            .recover(shards)?
+            .into_iter()
+            .step_by(self.expansion_factor))
     }
 
     /// Extend commitments using erasure coding.
@@ -97,18 +312,250 @@ impl ErasureCoding {
     pub fn extend_commitments(
         &self,
         commitments: &[Commitment],
-    ) -> Result<Vec<Commitment>, String> {
+    ) -> Result<Vec<Commitment>, ErasureCodingError> {
+        self.check_source_shards(commitments.len())?;
+
         // Inverse FFT to interpolate polynomial over source commitments
         let mut coeffs = self
             .fft_settings
-            .fft_g1(Commitment::slice_to_repr(commitments), true)?;
+            .fft_g1(Commitment::slice_to_repr(commitments), true)
+            .map_err(ErasureCodingError::Kzg)?;
 
-        // Double the size
-        coeffs.resize(coeffs.len() * 2, FsG1::identity());
+        // Extend to the size of evaluation domain
+        coeffs.resize(coeffs.len() * self.stride, FsG1::identity());
 
         // FFT to get extended commitments
-        self.fft_settings
+        let extended_commitments = self
+            .fft_settings
             .fft_g1(&coeffs, false)
-            .map(Commitment::vec_from_repr)
+            .map_err(ErasureCodingError::Kzg)?;
+
+        Ok(Commitment::vec_from_repr(
+            extended_commitments
+                .chunks_exact(self.stride)
+                .flat_map(|chunk| &chunk[..self.expansion_factor])
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    fn check_source_shards(&self, source_shards: usize) -> Result<(), ErasureCodingError> {
+        if !source_shards.is_power_of_two() {
+            return Err(ErasureCodingError::InvalidNumberOfSourceShards(
+                source_shards,
+            ));
+        }
+
+        let shards = source_shards * self.expansion_factor;
+        if shards > self.max_shards() {
+            return Err(ErasureCodingError::TooManyShards {
+                shards,
+                max_shards: self.max_shards(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_shards(&self, shards: &[Option<Scalar>]) -> Result<(), ErasureCodingError> {
+        if shards.len() % self.expansion_factor != 0 {
+            return Err(ErasureCodingError::InvalidNumberOfShards {
+                shards: shards.len(),
+                expansion_factor: self.expansion_factor,
+            });
+        }
+
+        let num_source_shards = shards.len() / self.expansion_factor;
+        self.check_source_shards(num_source_shards)?;
+
+        let available = shards.iter().filter(|shard| shard.is_some()).count();
+        if available < num_source_shards {
+            return Err(ErasureCodingError::NotEnoughShards {
+                available,
+                required: num_source_shards,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Evaluate polynomial that corresponds to source shards at all points of the evaluation
+    /// domain
+    fn evaluate(&self, source: &[FsFr]) -> Result<Vec<FsFr>, ErasureCodingError> {
+        // Inverse FFT to interpolate polynomial over evaluation points of source shards
+        let mut coeffs = self
+            .fft_settings
+            .fft_fr(source, true)
+            .map_err(ErasureCodingError::Kzg)?;
+
+        coeffs.resize(source.len() * self.stride, FsFr::zero());
+
+        self.fft_settings
+            .fft_fr(&coeffs, false)
+            .map_err(ErasureCodingError::Kzg)
+    }
+
+    /// Source and parity shards interleaved that correspond to source shards
+    fn shards_from_source(&self, source: &[FsFr]) -> Result<Vec<Scalar>, ErasureCodingError> {
+        let evaluations = self.evaluate(source)?;
+
+        Ok(Scalar::vec_from_repr(
+            evaluations
+                .chunks_exact(self.stride)
+                .flat_map(|chunk| &chunk[..self.expansion_factor])
+                .copied()
+                .collect(),
+        ))
+    }
+
+    /// Evaluation point of the shard at `position`
+    fn evaluation_point(&self, num_shards: usize, position: usize) -> FsFr {
+        let num_evaluation_points = num_shards / self.expansion_factor * self.stride;
+        let domain_index =
+            position / self.expansion_factor * self.stride + position % self.expansion_factor;
+
+        self.fft_settings.get_expanded_roots_of_unity_at(
+            domain_index * (self.fft_settings.max_width / num_evaluation_points),
+        )
+    }
+}
+
+/// Recovery of source shards with the help of Lagrange interpolation over any `num_source_shards`
+/// available shards, used for expansion factors other than 2.
+///
+/// Interpolation coefficients only depend on which shards are available, so the same instance can
+/// be used for multiple sets of shards with the same shards missing.
+struct Recovery {
+    expansion_factor: usize,
+    /// Positions of shards used for interpolation
+    positions: Vec<usize>,
+    /// Whether each shard was available
+    available: Vec<bool>,
+    /// Interpolation coefficients (one per used shard) for each missing source shard
+    missing_source: Vec<(usize, Vec<FsFr>)>,
+}
+
+impl Recovery {
+    fn new(
+        erasure_coding: &ErasureCoding,
+        shards: &[Option<Scalar>],
+    ) -> Result<Self, ErasureCodingError> {
+        let num_shards = shards.len();
+        let num_source_shards = num_shards / erasure_coding.expansion_factor;
+        let available = shards.iter().map(Option::is_some).collect::<Vec<_>>();
+
+        // Prefer source shards since they don't need to be recovered
+        let mut positions = (0..num_shards)
+            .step_by(erasure_coding.expansion_factor)
+            .chain(
+                (0..num_shards).filter(|position| position % erasure_coding.expansion_factor != 0),
+            )
+            .filter(|&position| available[position])
+            .take(num_source_shards)
+            .collect::<Vec<_>>();
+        if positions.len() < num_source_shards {
+            return Err(ErasureCodingError::NotEnoughShards {
+                available: available.iter().filter(|&&available| available).count(),
+                required: num_source_shards,
+            });
+        }
+        positions.sort_unstable();
+
+        let points = positions
+            .iter()
+            .map(|&position| erasure_coding.evaluation_point(num_shards, position))
+            .collect::<Vec<_>>();
+
+        // Barycentric weights `1 / Π(x_j - x_l)` for `l != j`
+        let mut weights = points
+            .iter()
+            .enumerate()
+            .map(|(j, x_j)| {
+                points
+                    .iter()
+                    .enumerate()
+                    .filter(|&(l, _)| l != j)
+                    .fold(FsFr::one(), |acc, (_, x_l)| acc.mul(&x_j.sub(x_l)))
+            })
+            .collect::<Vec<_>>();
+        batch_inverse(&mut weights);
+
+        let missing_source = (0..num_shards)
+            .step_by(erasure_coding.expansion_factor)
+            .filter(|&position| !available[position])
+            .map(|position| {
+                let x = erasure_coding.evaluation_point(num_shards, position);
+
+                // `p(x) = L(x) * Σ(y_j * w_j / (x - x_j))` where `L(x) = Π(x - x_j)`
+                let mut differences = points.iter().map(|x_j| x.sub(x_j)).collect::<Vec<_>>();
+                let l = differences
+                    .iter()
+                    .fold(FsFr::one(), |acc, difference| acc.mul(difference));
+                batch_inverse(&mut differences);
+
+                let coefficients = differences
+                    .iter()
+                    .zip(&weights)
+                    .map(|(inverse_difference, weight)| l.mul(weight).mul(inverse_difference))
+                    .collect();
+
+                (position / erasure_coding.expansion_factor, coefficients)
+            })
+            .collect();
+
+        Ok(Self {
+            expansion_factor: erasure_coding.expansion_factor,
+            positions,
+            available,
+            missing_source,
+        })
+    }
+
+    /// Whether the same shards are available
+    fn is_applicable(&self, shards: &[Option<Scalar>]) -> bool {
+        self.available.len() == shards.len()
+            && self
+                .available
+                .iter()
+                .zip(shards)
+                .all(|(available, shard)| *available == shard.is_some())
+    }
+
+    /// Recover source shards, must only be called with shards for which recovery is applicable
+    fn recover_source(&self, shards: &[Option<FsFr>]) -> Vec<FsFr> {
+        let mut source = shards
+            .iter()
+            .step_by(self.expansion_factor)
+            .map(|shard| shard.unwrap_or_else(FsFr::zero))
+            .collect::<Vec<_>>();
+
+        for (source_index, coefficients) in &self.missing_source {
+            source[*source_index] = self.positions.iter().zip(coefficients).fold(
+                FsFr::zero(),
+                |acc, (&position, coefficient)| {
+                    let y = shards[position].expect("Only available shards are used; qed");
+                    acc.add(&y.mul(coefficient))
+                },
+            );
+        }
+
+        source
+    }
+}
+
+/// Invert all values in place using Montgomery's trick, values must not be zero
+fn batch_inverse(values: &mut [FsFr]) {
+    let mut prefix_products = Vec::with_capacity(values.len());
+    let mut product = FsFr::one();
+    for value in values.iter() {
+        prefix_products.push(product);
+        product = product.mul(value);
+    }
+
+    let mut inverse = product.inverse();
+    for (value, prefix_product) in values.iter_mut().zip(prefix_products).rev() {
+        let next_inverse = inverse.mul(value);
+        *value = inverse.mul(&prefix_product);
+        inverse = next_inverse;
     }
 }
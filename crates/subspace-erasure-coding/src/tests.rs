@@ -1,6 +1,7 @@
-use crate::ErasureCoding;
+use crate::{ErasureCoding, ErasureCodingError, ErasureCodingParams};
 use blst_rust::types::g1::FsG1;
 use kzg::G1;
+use rand::prelude::*;
 use std::iter;
 use std::num::NonZeroUsize;
 use subspace_core_primitives::crypto::kzg::Commitment;
@@ -135,3 +136,231 @@ fn not_enough_partial() {
         .replace(Scalar::default());
     assert!(ec.recover(&partial_shards).is_ok());
 }
+
+fn interleave(source: &[Scalar], parity: &[Scalar], expansion_factor: usize) -> Vec<Scalar> {
+    source
+        .iter()
+        .zip(parity.chunks_exact(expansion_factor - 1))
+        .flat_map(|(source, parity)| iter::once(source).chain(parity))
+        .copied()
+        .collect()
+}
+
+#[test]
+fn invalid_params() {
+    let scale = NonZeroUsize::new(4).unwrap();
+
+    assert_eq!(
+        ErasureCoding::with_params(ErasureCodingParams {
+            scale,
+            expansion_factor: NonZeroUsize::new(1).unwrap(),
+        })
+        .unwrap_err(),
+        ErasureCodingError::InvalidExpansionFactor(1)
+    );
+    assert_eq!(
+        ErasureCoding::with_params(ErasureCodingParams {
+            scale,
+            expansion_factor: NonZeroUsize::new(17).unwrap(),
+        })
+        .unwrap_err(),
+        ErasureCodingError::ExpansionFactorTooLarge {
+            expansion_factor: 17,
+            scale: 4
+        }
+    );
+
+    let ec = ErasureCoding::with_params(ErasureCodingParams {
+        scale,
+        expansion_factor: NonZeroUsize::new(3).unwrap(),
+    })
+    .unwrap();
+    // 4 evaluation points per source shard, one of which is not used
+    assert_eq!(ec.max_shards(), 12);
+    assert_eq!(
+        ec.recover(&[None; 8]).unwrap_err(),
+        ErasureCodingError::InvalidNumberOfShards {
+            shards: 8,
+            expansion_factor: 3
+        }
+    );
+    assert_eq!(
+        ec.extend(&[Scalar::default(); 8]).unwrap_err(),
+        ErasureCodingError::TooManyShards {
+            shards: 24,
+            max_shards: 12
+        }
+    );
+}
+
+#[test]
+fn recover_from_any_source_shards_number_of_shards() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for expansion_factor in 2..=7 {
+        for num_source_shards in [2, 8, 32] {
+            let scale = (num_source_shards * usize::next_power_of_two(expansion_factor)).ilog2()
+                as usize
+                + rng.gen_range(0..2);
+            let ec = ErasureCoding::with_params(ErasureCodingParams {
+                scale: NonZeroUsize::new(scale).unwrap(),
+                expansion_factor: NonZeroUsize::new(expansion_factor).unwrap(),
+            })
+            .unwrap();
+
+            let source_shards = (0..num_source_shards)
+                .map(|_| Scalar::from(rng.gen::<[u8; Scalar::SAFE_BYTES]>()))
+                .collect::<Vec<_>>();
+            let parity_shards = ec.extend(&source_shards).unwrap();
+            assert_eq!(
+                parity_shards.len(),
+                num_source_shards * (expansion_factor - 1)
+            );
+            let shards = interleave(&source_shards, &parity_shards, expansion_factor);
+
+            for _ in 0..5 {
+                let mut positions = (0..shards.len()).collect::<Vec<_>>();
+                positions.shuffle(&mut rng);
+                positions.truncate(num_source_shards);
+
+                let mut partial_shards = vec![None; shards.len()];
+                for &position in &positions {
+                    partial_shards[position].replace(shards[position]);
+                }
+
+                assert_eq!(ec.recover(&partial_shards).unwrap(), shards);
+                assert_eq!(
+                    ec.recover_source(&partial_shards)
+                        .unwrap()
+                        .collect::<Vec<_>>(),
+                    source_shards
+                );
+
+                // One shard less is not sufficient
+                partial_shards[positions[0]].take();
+                assert_eq!(
+                    ec.recover(&partial_shards).unwrap_err(),
+                    ErasureCodingError::NotEnoughShards {
+                        available: num_source_shards - 1,
+                        required: num_source_shards
+                    }
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn recover_poly_matches_recover() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let expansion_factor = 3;
+    let num_source_shards = 16;
+    let ec = ErasureCoding::with_params(ErasureCodingParams {
+        scale: NonZeroUsize::new(6).unwrap(),
+        expansion_factor: NonZeroUsize::new(expansion_factor).unwrap(),
+    })
+    .unwrap();
+
+    let source_shards = (0..num_source_shards)
+        .map(|_| Scalar::from(rng.gen::<[u8; Scalar::SAFE_BYTES]>()))
+        .collect::<Vec<_>>();
+    let parity_shards = ec.extend(&source_shards).unwrap();
+    let shards = interleave(&source_shards, &parity_shards, expansion_factor);
+
+    // Only parity shards
+    let partial_shards = shards
+        .iter()
+        .enumerate()
+        .map(|(position, shard)| (position % expansion_factor != 0).then_some(*shard))
+        .collect::<Vec<_>>();
+
+    let recovered_poly = ec.recover_poly(&partial_shards).unwrap();
+    let full_poly = ec
+        .recover_poly(&shards.iter().copied().map(Some).collect::<Vec<_>>())
+        .unwrap();
+
+    // Polynomial doesn't implement `PartialEq`
+    assert_eq!(format!("{recovered_poly:?}"), format!("{full_poly:?}"));
+}
+
+#[test]
+fn batch() {
+    let mut rng = StdRng::seed_from_u64(2);
+
+    for expansion_factor in [2, 3] {
+        let num_source_shards = 8;
+        let ec = ErasureCoding::with_params(ErasureCodingParams {
+            scale: NonZeroUsize::new(5).unwrap(),
+            expansion_factor: NonZeroUsize::new(expansion_factor).unwrap(),
+        })
+        .unwrap();
+
+        let sources = (0..4)
+            .map(|_| {
+                (0..num_source_shards)
+                    .map(|_| Scalar::from(rng.gen::<[u8; Scalar::SAFE_BYTES]>()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let parities = ec
+            .extend_batch(&sources.iter().map(Vec::as_slice).collect::<Vec<_>>())
+            .unwrap();
+        for (source, parity) in sources.iter().zip(&parities) {
+            assert_eq!(&ec.extend(source).unwrap(), parity);
+        }
+
+        // The same shards are missing in the first two sets and different in the rest
+        let partial_shards = sources
+            .iter()
+            .zip(&parities)
+            .enumerate()
+            .map(|(index, (source, parity))| {
+                let skip = index.saturating_sub(1);
+                interleave(source, parity, expansion_factor)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(position, shard)| {
+                        ((position + skip) % expansion_factor == 0).then_some(shard)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let recovered = ec
+            .recover_batch(&partial_shards.iter().map(Vec::as_slice).collect::<Vec<_>>())
+            .unwrap();
+        for ((source, parity), recovered) in sources.iter().zip(&parities).zip(recovered) {
+            assert_eq!(recovered, interleave(source, parity, expansion_factor));
+        }
+    }
+}
+
+#[test]
+fn commitments_with_expansion_factor() {
+    let expansion_factor = 3;
+    let num_source_shards = 8;
+    let ec = ErasureCoding::with_params(ErasureCodingParams {
+        scale: NonZeroUsize::new(5).unwrap(),
+        expansion_factor: NonZeroUsize::new(expansion_factor).unwrap(),
+    })
+    .unwrap();
+
+    let source_commitments = (0..num_source_shards)
+        .map(|_| Commitment::from(FsG1::rand()))
+        .collect::<Vec<_>>();
+
+    let extended_commitments = ec.extend_commitments(&source_commitments).unwrap();
+
+    assert_eq!(
+        source_commitments.len() * expansion_factor,
+        extended_commitments.len()
+    );
+    assert_eq!(
+        source_commitments,
+        extended_commitments
+            .iter()
+            .step_by(expansion_factor)
+            .copied()
+            .collect::<Vec<_>>()
+    );
+}
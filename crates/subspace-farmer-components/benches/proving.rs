@@ -51,7 +51,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     let mut rng = StdRng::seed_from_u64(42);
     rng.fill(AsMut::<[u8]>::as_mut(input.as_mut()));
     let kzg = Kzg::new(kzg::embedded_kzg_settings());
-    let mut archiver = Archiver::new(kzg.clone()).unwrap();
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
     let erasure_coding = ErasureCoding::new(
         NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
     )
@@ -77,6 +78,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             HistorySize::from(NonZeroU64::new(10).unwrap()),
         ),
         min_sector_lifetime: HistorySize::from(NonZeroU64::new(4).unwrap()),
+        erasure_coding_rate: RecordedHistorySegment::ERASURE_CODING_RATE,
     };
     let solution_range = SolutionRange::MAX;
     let reward_address = PublicKey::default();
@@ -24,7 +24,7 @@ mod segment_reconstruction;
 
 use serde::{Deserialize, Serialize};
 use static_assertions::const_assert;
-use subspace_core_primitives::HistorySize;
+use subspace_core_primitives::{HistorySize, RecordedHistorySegment};
 
 // Refuse to compile on non-64-bit platforms, offsets may fail on those when converting from u64 to
 // usize depending on chain parameters
@@ -44,4 +44,12 @@ pub struct FarmerProtocolInfo {
     pub recent_history_fraction: (HistorySize, HistorySize),
     /// Minimum lifetime of a plotted sector, measured in archived segment
     pub min_sector_lifetime: HistorySize,
+    /// Erasure coding rate `(source, total)` of records during archiving process
+    #[serde(default = "default_erasure_coding_rate")]
+    pub erasure_coding_rate: (usize, usize),
+}
+
+/// Nodes that don't report erasure coding rate use the default one
+fn default_erasure_coding_rate() -> (usize, usize) {
+    RecordedHistorySegment::ERASURE_CODING_RATE
 }
@@ -229,6 +229,7 @@ where
                 piece_getter,
                 piece_getter_retry_policy,
                 kzg,
+                farmer_protocol_info.erasure_coding_rate,
                 &mut incremental_piece_indices,
             )
             .await
@@ -425,6 +426,7 @@ async fn download_sector<PG: PieceGetter>(
     piece_getter: &PG,
     piece_getter_retry_policy: PieceGetterRetryPolicy,
     kzg: &Kzg,
+    erasure_coding_rate: (usize, usize),
     piece_indexes: &mut [Option<PieceIndex>],
 ) -> Result<(), PlottingError> {
     // TODO: Make configurable, likely allowing user to specify RAM usage expectations and inferring
@@ -458,8 +460,13 @@ async fn download_sector<PG: PieceGetter>(
                         return Err(PlottingError::FailedToRetrievePiece { piece_index, error });
                     }
                 };
-                let recovered_piece =
-                    recover_missing_piece(piece_getter, kzg.clone(), piece_index).await;
+                let recovered_piece = recover_missing_piece(
+                    piece_getter,
+                    kzg.clone(),
+                    erasure_coding_rate,
+                    piece_index,
+                )
+                .await;
 
                 piece_result = recovered_piece.map(Some).map_err(Into::into);
             }
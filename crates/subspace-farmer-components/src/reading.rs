@@ -10,7 +10,7 @@ use subspace_core_primitives::crypto::{blake3_hash, Scalar};
 use subspace_core_primitives::{
     Piece, PieceOffset, Record, RecordCommitment, RecordWitness, SBucket, SectorId,
 };
-use subspace_erasure_coding::ErasureCoding;
+use subspace_erasure_coding::{ErasureCoding, ErasureCodingError};
 use subspace_proof_of_space::{Quality, Table, TableGenerator};
 use thiserror::Error;
 use tracing::debug;
@@ -56,7 +56,7 @@ pub enum ReadingError {
         /// Piece offset
         piece_offset: PieceOffset,
         /// Lower-level error
-        error: String,
+        error: ErasureCodingError,
     },
     /// Wrong record size after decoding
     #[error("Wrong record size after decoding: expected {expected}, actual {actual}")]
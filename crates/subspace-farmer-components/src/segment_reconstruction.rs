@@ -2,7 +2,9 @@ use crate::plotting::{PieceGetter, PieceGetterRetryPolicy};
 use futures::stream::FuturesOrdered;
 use futures::StreamExt;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use subspace_archiving::piece_reconstructor::{PiecesReconstructor, ReconstructorError};
+use subspace_archiving::piece_reconstructor::{
+    PiecesReconstructor, ReconstructorError, ReconstructorInstantiationError,
+};
 use subspace_core_primitives::crypto::kzg::Kzg;
 use subspace_core_primitives::{ArchivedHistorySegment, Piece, PieceIndex, RecordedHistorySegment};
 use thiserror::Error;
@@ -18,6 +20,10 @@ pub(crate) enum SegmentReconstructionError {
     #[error("Not enough pieces to reconstruct a segment")]
     NotEnoughPiecesAcquired,
 
+    /// Failed to create pieces reconstructor
+    #[error("Failed to create pieces reconstructor: {0}")]
+    ReconstructorInstantiation(#[from] ReconstructorInstantiationError),
+
     /// Internal piece retrieval process failed
     #[error("Pieces retrieval failed")]
     PieceRetrievalFailed(#[from] ReconstructorError),
@@ -26,6 +32,7 @@ pub(crate) enum SegmentReconstructionError {
 pub(crate) async fn recover_missing_piece<PG: PieceGetter>(
     piece_getter: &PG,
     kzg: Kzg,
+    erasure_coding_rate: (usize, usize),
     missing_piece_index: PieceIndex,
 ) -> Result<Piece, SegmentReconstructionError> {
     info!(%missing_piece_index, "Recovering missing piece...");
@@ -102,7 +109,7 @@ pub(crate) async fn recover_missing_piece<PG: PieceGetter>(
         return Err(SegmentReconstructionError::NotEnoughPiecesAcquired);
     }
 
-    let archiver = PiecesReconstructor::new(kzg).expect("Internal constructor call must succeed.");
+    let archiver = PiecesReconstructor::new(kzg, erasure_coding_rate)?;
 
     let result = archiver.reconstruct_piece(&segment_pieces, position as usize)?;
 
@@ -28,6 +28,7 @@ use subspace_farmer_components::plotting::PlottedSector;
 use subspace_metrics::{start_prometheus_metrics_server, RegistryAdapter};
 use subspace_networking::libp2p::identity::{ed25519, Keypair};
 use subspace_networking::utils::piece_provider::PieceProvider;
+use subspace_networking::utils::segment_header_announcements::SegmentHeaderAnnouncementValidator;
 use subspace_proof_of_space::Table;
 use tempfile::TempDir;
 use tracing::{debug, error, info, info_span, warn};
@@ -109,8 +110,20 @@ where
 
     let (piece_cache, piece_cache_worker) = PieceCache::new(node_client.clone(), peer_id);
 
+    // Segment header announcements are validated against segment headers known to the node
+    let segment_header_announcement_validator = SegmentHeaderAnnouncementValidator::new(
+        node_client
+            .last_segment_headers(1)
+            .await
+            .map_err(|error| anyhow::anyhow!(error))?
+            .into_iter()
+            .flatten(),
+    );
+
     let metrics_endpoints_are_specified = !metrics_endpoints.is_empty();
 
+    let kzg = Kzg::new(embedded_kzg_settings());
+
     let (node, mut node_runner, metrics_registry) = {
         if dsn.bootstrap_nodes.is_empty() {
             dsn.bootstrap_nodes = farmer_app_info.dsn_bootstrap_nodes.clone();
@@ -124,6 +137,8 @@ where
             Arc::downgrade(&readers_and_pieces),
             node_client.clone(),
             piece_cache.clone(),
+            segment_header_announcement_validator.clone(),
+            kzg.clone(),
             metrics_endpoints_are_specified,
         )?
     };
@@ -137,7 +152,6 @@ where
         let _prometheus_worker = tokio::spawn(prometheus_task);
     }
 
-    let kzg = Kzg::new(embedded_kzg_settings());
     let erasure_coding = ErasureCoding::new(
         NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
     )
@@ -164,7 +178,14 @@ where
     ));
 
     let _piece_cache_worker = run_future_in_dedicated_thread(
-        Box::pin(piece_cache_worker.run(piece_getter.clone())),
+        Box::pin(
+            piece_cache_worker
+                .with_segment_header_announcements(
+                    node.clone(),
+                    segment_header_announcement_validator,
+                )
+                .run(piece_getter.clone()),
+        ),
         "cache-worker".to_string(),
     );
 
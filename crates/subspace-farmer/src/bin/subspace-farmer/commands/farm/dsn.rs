@@ -1,9 +1,15 @@
 use crate::DsnArgs;
+use bytesize::ByteSize;
 use parking_lot::Mutex;
 use prometheus_client::registry::Registry;
 use std::collections::HashSet;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::path::PathBuf;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
+use subspace_archiving::archiver::create_record_chunks_proof;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{Piece, PieceIndex};
 use subspace_farmer::piece_cache::PieceCache;
 use subspace_farmer::utils::readers_and_pieces::ReadersAndPieces;
 use subspace_farmer::{NodeClient, NodeRpcClient};
@@ -12,19 +18,36 @@ use subspace_networking::libp2p::kad::RecordKey;
 use subspace_networking::libp2p::metrics::Metrics;
 use subspace_networking::libp2p::multiaddr::Protocol;
 use subspace_networking::utils::multihash::ToMultihash;
+use subspace_networking::utils::rate_limiter::RateLimiter;
+use subspace_networking::utils::segment_header_announcements::{
+    segment_header_announcements_topic_config, SegmentHeaderAnnouncementValidator,
+};
 use subspace_networking::utils::strip_peer_id;
 use subspace_networking::{
-    construct, Config, NetworkingParametersManager, Node, NodeRunner, PeerInfo, PeerInfoProvider,
-    PieceByIndexRequest, PieceByIndexRequestHandler, PieceByIndexResponse,
+    construct, BandwidthLimits, BandwidthMetrics, Config, MuxedTransportConfig,
+    NetworkingParametersManager, Node, NodeRunner, PeerInfo, PeerInfoProvider, PieceByIndexRequest,
+    PieceByIndexRequestHandler, PieceByIndexResponse, PieceChunksRequest,
+    PieceChunksRequestHandler, PieceChunksResponse, QuicTransportConfig,
     SegmentHeaderBySegmentIndexesRequestHandler, SegmentHeaderRequest, SegmentHeaderResponse,
+    TransportConfig,
 };
 use subspace_rpc_primitives::MAX_SEGMENT_HEADERS_PER_REQUEST;
+use tokio::sync::Semaphore;
 use tracing::{debug, error, info, Instrument};
 
 /// How many segment headers can be requested at a time.
 ///
 /// Must be the same as RPC limit since all requests go to the node anyway.
 const SEGMENT_HEADER_NUMBER_LIMIT: u64 = MAX_SEGMENT_HEADERS_PER_REQUEST as u64;
+/// How many piece chunks proofs can be created concurrently, the rest of requests is rejected.
+const PIECE_CHUNKS_PROOFS_CONCURRENCY: usize = 2;
+/// How many piece chunks requests a single peer can make within
+/// [`PIECE_CHUNKS_REQUESTS_INTERVAL`].
+const PIECE_CHUNKS_REQUESTS_PER_PEER: NonZeroU32 = NonZeroU32::new(10).expect("Not zero; qed");
+const PIECE_CHUNKS_REQUESTS_INTERVAL: Duration = Duration::from_secs(60);
+/// How many peers piece chunks requests rate is tracked for.
+const PIECE_CHUNKS_RATE_LIMITER_PEERS: NonZeroUsize =
+    NonZeroUsize::new(1000).expect("Not zero; qed");
 
 #[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub(super) fn configure_dsn(
@@ -42,10 +65,23 @@ pub(super) fn configure_dsn(
         pending_out_connections,
         target_connections,
         external_addresses,
+        upload_limit,
+        download_limit,
+        max_requests_per_peer,
+        transports,
+        tcp_timeout,
+        ws_timeout,
+        yamux_receive_window_size,
+        yamux_max_buffer_size,
+        quic_handshake_timeout,
+        quic_max_idle_timeout,
+        quic_keep_alive_interval,
     }: DsnArgs,
     weak_readers_and_pieces: Weak<Mutex<Option<ReadersAndPieces>>>,
     node_client: NodeRpcClient,
     piece_cache: PieceCache,
+    segment_header_announcement_validator: SegmentHeaderAnnouncementValidator,
+    kzg: Kzg,
     initialize_metrics: bool,
 ) -> Result<(Node, NodeRunner<PieceCache>, Registry), anyhow::Error> {
     let networking_parameters_registry = NetworkingParametersManager::new(
@@ -60,68 +96,162 @@ pub(super) fn configure_dsn(
     // Metrics
     let mut metrics_registry = Registry::default();
     let metrics = initialize_metrics.then(|| Metrics::new(&mut metrics_registry));
+    let bandwidth_metrics =
+        initialize_metrics.then(|| BandwidthMetrics::new(&mut metrics_registry));
 
     let default_config = Config::new(
         protocol_prefix,
         keypair,
         piece_cache.clone(),
-        Some(PeerInfoProvider::new_farmer()),
+        Some(PeerInfoProvider::new_farmer().with_piece_cache({
+            let piece_cache = piece_cache.clone();
+
+            Arc::new(move || piece_cache.capability())
+        })),
     );
     let config = Config {
         reserved_peers,
         listen_on,
+        transports: TransportConfig::with_transport_configs(
+            &transports,
+            MuxedTransportConfig {
+                timeout: Duration::from_secs(tcp_timeout),
+                yamux_receive_window_size: yamux_receive_window_size,
+                yamux_max_buffer_size: yamux_max_buffer_size,
+                ..MuxedTransportConfig::default()
+            },
+            QuicTransportConfig {
+                handshake_timeout: Duration::from_secs(quic_handshake_timeout),
+                max_idle_timeout: Duration::from_secs(quic_max_idle_timeout),
+                keep_alive_interval: Duration::from_secs(quic_keep_alive_interval),
+            },
+            MuxedTransportConfig {
+                timeout: Duration::from_secs(ws_timeout),
+                yamux_receive_window_size: yamux_receive_window_size,
+                yamux_max_buffer_size: yamux_max_buffer_size,
+                ..MuxedTransportConfig::default()
+            },
+        ),
         allow_non_global_addresses_in_dht: enable_private_ips,
         networking_parameters_registry: Some(networking_parameters_registry),
+        gossip_topics: vec![segment_header_announcements_topic_config(
+            segment_header_announcement_validator.into_gossip_message_validator(),
+        )],
         request_response_protocols: vec![
-            PieceByIndexRequestHandler::create(move |_, &PieceByIndexRequest { piece_index }| {
-                debug!(?piece_index, "Piece request received. Trying cache...");
-
+            PieceByIndexRequestHandler::create({
                 let weak_readers_and_pieces = weak_readers_and_pieces.clone();
                 let piece_cache = piece_cache.clone();
 
-                async move {
-                    let key = RecordKey::from(piece_index.to_multihash());
-                    let piece_from_store = piece_cache.get_piece(key).await;
-
-                    if let Some(piece) = piece_from_store {
-                        Some(PieceByIndexResponse { piece: Some(piece) })
-                    } else {
-                        debug!(
-                            ?piece_index,
-                            "No piece in the cache. Trying archival storage..."
-                        );
-
-                        let read_piece_fut = {
-                            let readers_and_pieces = match weak_readers_and_pieces.upgrade() {
-                                Some(readers_and_pieces) => readers_and_pieces,
-                                None => {
-                                    debug!("A readers and pieces are already dropped");
-                                    return None;
-                                }
-                            };
-                            let readers_and_pieces = readers_and_pieces.lock();
-                            let readers_and_pieces = match readers_and_pieces.as_ref() {
-                                Some(readers_and_pieces) => readers_and_pieces,
-                                None => {
-                                    debug!(
-                                        ?piece_index,
-                                        "Readers and pieces are not initialized yet"
-                                    );
-                                    return None;
-                                }
-                            };
+                move |_, &PieceByIndexRequest { piece_index }| {
+                    debug!(?piece_index, "Piece request received");
 
-                            readers_and_pieces
-                                .read_piece(&piece_index)?
-                                .in_current_span()
-                        };
+                    let weak_readers_and_pieces = weak_readers_and_pieces.clone();
+                    let piece_cache = piece_cache.clone();
 
-                        let piece = read_piece_fut.await;
+                    async move {
+                        let piece =
+                            get_piece(piece_index, &piece_cache, &weak_readers_and_pieces).await;
 
                         Some(PieceByIndexResponse { piece })
                     }
+                    .in_current_span()
+                }
+            }),
+            PieceChunksRequestHandler::create({
+                let rate_limiter = Arc::new(Mutex::new(RateLimiter::new(
+                    PIECE_CHUNKS_REQUESTS_PER_PEER,
+                    PIECE_CHUNKS_REQUESTS_INTERVAL,
+                    PIECE_CHUNKS_RATE_LIMITER_PEERS,
+                )));
+                let proofs_semaphore = Arc::new(Semaphore::new(PIECE_CHUNKS_PROOFS_CONCURRENCY));
+
+                move |peer_id, &request| {
+                    debug!(%peer_id, ?request, "Piece chunks request received");
+
+                    let rate_limiter = Arc::clone(&rate_limiter);
+                    let proofs_semaphore = Arc::clone(&proofs_semaphore);
+                    let weak_readers_and_pieces = weak_readers_and_pieces.clone();
+                    let piece_cache = piece_cache.clone();
+                    let kzg = kzg.clone();
+
+                    async move {
+                        let PieceChunksRequest {
+                            piece_index,
+                            first_chunk_offset,
+                            num_chunks,
+                        } = request;
+
+                        if num_chunks > PieceChunksRequest::MAX_CHUNKS {
+                            debug!(
+                                %peer_id,
+                                ?piece_index,
+                                %num_chunks,
+                                "Number of requested piece chunks exceeded the limit"
+                            );
+
+                            return Some(PieceChunksResponse { proof: None });
+                        }
+
+                        if !rate_limiter.lock().try_acquire(peer_id) {
+                            debug!(%peer_id, "Piece chunks requests rate limit exceeded");
+
+                            return None;
+                        }
+
+                        // Permit is moved into blocking task below, such that the number of proofs
+                        // created concurrently is bounded even if request is cancelled
+                        let Ok(permit) = proofs_semaphore.try_acquire_owned() else {
+                            debug!(
+                                %peer_id,
+                                ?piece_index,
+                                "Too many piece chunks requests are being processed already"
+                            );
+
+                            return Some(PieceChunksResponse { proof: None });
+                        };
+
+                        let Some(piece) =
+                            get_piece(piece_index, &piece_cache, &weak_readers_and_pieces).await
+                        else {
+                            return Some(PieceChunksResponse { proof: None });
+                        };
+
+                        let chunks =
+                            first_chunk_offset..first_chunk_offset.saturating_add(num_chunks);
+                        // Creating witnesses is CPU-intensive
+                        let proof_result = tokio::task::spawn_blocking(move || {
+                            let _permit = permit;
+
+                            create_record_chunks_proof(&kzg, &piece, chunks)
+                        })
+                        .await;
+
+                        let proof = match proof_result {
+                            Ok(Ok(proof)) => Some(proof),
+                            Ok(Err(error)) => {
+                                debug!(
+                                    ?piece_index,
+                                    %error,
+                                    "Failed to create piece chunks proof"
+                                );
+
+                                None
+                            }
+                            Err(error) => {
+                                error!(
+                                    ?piece_index,
+                                    %error,
+                                    "Piece chunks proof creation task failed"
+                                );
+
+                                None
+                            }
+                        };
+
+                        Some(PieceChunksResponse { proof })
+                    }
+                    .in_current_span()
                 }
-                .in_current_span()
             }),
             SegmentHeaderBySegmentIndexesRequestHandler::create(move |_, req| {
                 debug!(?req, "Segment headers request received.");
@@ -190,7 +320,14 @@ pub(super) fn configure_dsn(
         })),
         bootstrap_addresses: bootstrap_nodes,
         external_addresses,
+        request_response_bandwidth_limits: BandwidthLimits {
+            upload_bytes_per_second: upload_limit.and_then(bytes_per_second),
+            download_bytes_per_second: download_limit.and_then(bytes_per_second),
+            max_requests_per_peer,
+            requests_per_peer_interval: Duration::from_secs(60),
+        },
         metrics,
+        bandwidth_metrics,
         ..default_config
     };
 
@@ -213,3 +350,51 @@ pub(super) fn configure_dsn(
         })
         .map_err(Into::into)
 }
+
+/// Get piece from cache or archival storage of farms
+async fn get_piece(
+    piece_index: PieceIndex,
+    piece_cache: &PieceCache,
+    weak_readers_and_pieces: &Weak<Mutex<Option<ReadersAndPieces>>>,
+) -> Option<Piece> {
+    debug!(?piece_index, "Trying cache...");
+
+    let key = RecordKey::from(piece_index.to_multihash());
+    if let Some(piece) = piece_cache.get_piece(key).await {
+        return Some(piece);
+    }
+
+    debug!(
+        ?piece_index,
+        "No piece in the cache. Trying archival storage..."
+    );
+
+    let read_piece_fut = {
+        let readers_and_pieces = match weak_readers_and_pieces.upgrade() {
+            Some(readers_and_pieces) => readers_and_pieces,
+            None => {
+                debug!("A readers and pieces are already dropped");
+                return None;
+            }
+        };
+        let readers_and_pieces = readers_and_pieces.lock();
+        let readers_and_pieces = match readers_and_pieces.as_ref() {
+            Some(readers_and_pieces) => readers_and_pieces,
+            None => {
+                debug!(?piece_index, "Readers and pieces are not initialized yet");
+                return None;
+            }
+        };
+
+        readers_and_pieces
+            .read_piece(&piece_index)?
+            .in_current_span()
+    };
+
+    read_piece_fut.await
+}
+
+/// Converts bandwidth limit to bytes per second, `None` for zero (unlimited).
+fn bytes_per_second(limit: ByteSize) -> Option<NonZeroU32> {
+    NonZeroU32::new(u32::try_from(limit.as_u64()).unwrap_or(u32::MAX))
+}
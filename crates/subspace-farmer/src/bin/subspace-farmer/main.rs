@@ -9,12 +9,13 @@ use clap::{Parser, ValueHint};
 use ss58::parse_ss58_reward_address;
 use std::fs;
 use std::net::SocketAddr;
-use std::num::NonZeroU8;
+use std::num::{NonZeroU32, NonZeroU8};
 use std::path::PathBuf;
 use std::str::FromStr;
 use subspace_core_primitives::PublicKey;
 use subspace_farmer::single_disk_farm::SingleDiskFarm;
 use subspace_networking::libp2p::Multiaddr;
+use subspace_networking::TransportKind;
 use subspace_proof_of_space::chia::ChiaTable;
 use tracing::info;
 use tracing_subscriber::filter::LevelFilter;
@@ -123,6 +124,45 @@ struct DsnArgs {
     /// Known external addresses
     #[arg(long, alias = "external-address")]
     external_addresses: Vec<Multiaddr>,
+    /// Transports to enable (comma-separated): `tcp`, `quic` and `ws` (WebSocket), listen and
+    /// bootstrap addresses must be supported by enabled transports.
+    #[arg(long, value_delimiter = ',', default_value = "tcp,quic,ws")]
+    transports: Vec<TransportKind>,
+    /// Timeout of TCP connection setup and protocol upgrade in seconds.
+    #[arg(long, default_value_t = 10)]
+    tcp_timeout: u64,
+    /// Timeout of WebSocket connection setup and protocol upgrade in seconds.
+    #[arg(long, default_value_t = 10)]
+    ws_timeout: u64,
+    /// Yamux receive window size per stream in bytes for TCP and WebSocket connections, values
+    /// below 262144 (256 KiB) are raised to 262144.
+    #[arg(long, default_value_t = 256 * 1024)]
+    yamux_receive_window_size: u32,
+    /// Max size of yamux buffer per stream in bytes for TCP and WebSocket connections.
+    #[arg(long, default_value_t = 1024 * 1024)]
+    yamux_max_buffer_size: usize,
+    /// Timeout of QUIC connection handshake in seconds.
+    #[arg(long, default_value_t = 5)]
+    quic_handshake_timeout: u64,
+    /// QUIC connection is closed after being idle for this number of seconds.
+    #[arg(long, default_value_t = 10)]
+    quic_max_idle_timeout: u64,
+    /// Interval of QUIC keep-alive packets in seconds, must be smaller than max idle timeout to
+    /// keep connections alive.
+    #[arg(long, default_value_t = 5)]
+    quic_keep_alive_interval: u64,
+    /// Max upload bandwidth per second used for responding to DSN requests (like `10MiB`),
+    /// unlimited by default. Segment headers are prioritized over pieces.
+    #[arg(long)]
+    upload_limit: Option<ByteSize>,
+    /// Max download bandwidth per second used by responses to DSN requests (like `10MiB`),
+    /// unlimited by default.
+    #[arg(long)]
+    download_limit: Option<ByteSize>,
+    /// Max number of DSN requests per protocol accepted from a single peer within a minute,
+    /// unlimited by default.
+    #[arg(long)]
+    max_requests_per_peer: Option<NonZeroU32>,
 }
 
 #[derive(Debug, Clone)]
@@ -1,19 +1,24 @@
 use crate::node_client::NodeClient;
 use crate::single_disk_farm::piece_cache::{DiskPieceCache, Offset};
 use crate::utils::AsyncJoinOnDrop;
-use futures::{select, FutureExt, StreamExt};
-use parking_lot::RwLock;
+use futures::{select, stream, FutureExt, StreamExt};
+use parking_lot::{Mutex, RwLock};
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::mem;
 use std::num::NonZeroU16;
 use std::sync::Arc;
-use subspace_core_primitives::{Piece, PieceIndex, SegmentIndex};
+use subspace_core_primitives::{Piece, PieceIndex, SegmentHeader, SegmentIndex};
 use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
 use subspace_networking::libp2p::kad::{ProviderRecord, RecordKey};
 use subspace_networking::libp2p::PeerId;
 use subspace_networking::utils::multihash::ToMultihash;
-use subspace_networking::{KeyWrapper, LocalRecordProvider, UniqueRecordBinaryHeap};
+use subspace_networking::utils::segment_header_announcements::{
+    SegmentHeaderAnnouncementValidator, SegmentHeaderAnnouncements,
+};
+use subspace_networking::{
+    KeyWrapper, LocalRecordProvider, Node, PieceCacheCapability, UniqueRecordBinaryHeap,
+};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, trace, warn};
 
@@ -23,6 +28,9 @@ const WORKER_CHANNEL_CAPACITY: usize = 100;
 const INTERMEDIATE_CACHE_UPDATE_INTERVAL: usize = 100;
 /// Get piece retry attempts number.
 const PIECE_GETTER_RETRY_NUMBER: NonZeroU16 = NonZeroU16::new(3).expect("Not zero; qed");
+/// How many segments announced over DSN are kept around until they can be processed, segments that
+/// are further ahead will be received from the node anyway
+const PENDING_ANNOUNCED_SEGMENTS_LIMIT: usize = 10;
 
 #[derive(Debug, Clone)]
 struct DiskPieceCacheState {
@@ -41,6 +49,26 @@ enum WorkerCommand {
 struct CacheWorkerState {
     heap: UniqueRecordBinaryHeap<KeyWrapper<PieceIndex>>,
     last_segment_index: SegmentIndex,
+    /// Segments announced over DSN that were not processed yet, either because there is a gap
+    /// after `last_segment_index` or because previous attempt has failed
+    pending_announced_segments: BTreeMap<SegmentIndex, SegmentHeader>,
+}
+
+/// Result of processing segment announced over DSN
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum AnnouncedSegmentResult {
+    /// Segment header was confirmed by the node and all necessary pieces were cached
+    Cached,
+    /// Segment header is not confirmed by the node yet or some pieces were not cached
+    Retry,
+    /// Node has different segment header
+    Invalid,
+}
+
+/// Source of segment header announcements received over DSN gossip
+struct SegmentHeaderAnnouncementsSource {
+    node: Node,
+    validator: SegmentHeaderAnnouncementValidator,
 }
 
 /// Cache worker used to drive the cache
@@ -49,13 +77,31 @@ pub struct CacheWorker<NC> {
     peer_id: PeerId,
     node_client: NC,
     caches: Arc<RwLock<Vec<DiskPieceCacheState>>>,
+    capability: Arc<Mutex<PieceCacheCapability>>,
     worker_receiver: Option<mpsc::Receiver<WorkerCommand>>,
+    segment_header_announcements: Option<SegmentHeaderAnnouncementsSource>,
 }
 
 impl<NC> CacheWorker<NC>
 where
     NC: NodeClient,
 {
+    /// Process segment headers announced over DSN gossip in addition to node notifications, such
+    /// that pieces of newly archived segments start being cached right away, even if notification
+    /// from the node is late.
+    ///
+    /// Validator must be the same that was used for segment header announcements topic in DSN
+    /// configuration, it will be updated with segment headers received from the node.
+    pub fn with_segment_header_announcements(
+        mut self,
+        node: Node,
+        validator: SegmentHeaderAnnouncementValidator,
+    ) -> Self {
+        self.segment_header_announcements
+            .replace(SegmentHeaderAnnouncementsSource { node, validator });
+        self
+    }
+
     /// Run the cache worker with provided piece getter
     pub async fn run<PG>(mut self, piece_getter: PG)
     where
@@ -65,6 +111,7 @@ where
         let mut worker_state = CacheWorkerState {
             heap: UniqueRecordBinaryHeap::new(self.peer_id, 0),
             last_segment_index: SegmentIndex::ZERO,
+            pending_announced_segments: BTreeMap::new(),
         };
 
         let mut worker_receiver = self
@@ -129,6 +176,7 @@ where
                     match cache.backend.read_piece_index(offset) {
                         Some(piece_index) => {
                             worker_state.heap.remove(KeyWrapper(piece_index));
+                            self.update_capability(worker_state);
                         }
                         None => {
                             warn!(
@@ -231,6 +279,7 @@ where
                 worker_state.heap.insert(KeyWrapper(piece_index));
             }
         }
+        self.update_capability(worker_state);
 
         // This hashset is faster than `heap`
         // Clippy complains about `RecordKey`, but it is not changing here, so it is fine
@@ -327,14 +376,64 @@ where
         self.keep_up_after_initial_sync(piece_getter, worker_state)
             .await;
 
-        while let Some(segment_header) = segment_headers_notifications.next().await {
-            let segment_index = segment_header.segment_index();
-            debug!(%segment_index, "Starting to process newly archived segment");
+        let mut announced_segment_headers = match &self.segment_header_announcements {
+            Some(SegmentHeaderAnnouncementsSource { node, .. }) => {
+                match SegmentHeaderAnnouncements::subscribe(node).await {
+                    Ok(segment_header_announcements) => segment_header_announcements.boxed(),
+                    Err(error) => {
+                        warn!(%error, "Failed to subscribe to segment header announcements");
+                        stream::pending().boxed()
+                    }
+                }
+            }
+            None => stream::pending().boxed(),
+        }
+        .fuse();
 
-            if worker_state.last_segment_index >= segment_index {
-                continue;
+        loop {
+            select! {
+                maybe_segment_header = segment_headers_notifications.next().fuse() => {
+                    let Some(segment_header) = maybe_segment_header else {
+                        // Subscription ended
+                        return;
+                    };
+
+                    if let Some(segment_header_announcements) = &self.segment_header_announcements {
+                        segment_header_announcements
+                            .validator
+                            .add_segment_header(segment_header);
+                    }
+
+                    self.process_archived_segment_header(segment_header, worker_state)
+                        .await;
+                    // Retry announced segments that are following the one from the node
+                    self.process_pending_announced_segments(piece_getter, worker_state)
+                        .await;
+                }
+                segment_header = announced_segment_headers.select_next_some() => {
+                    self.process_announced_segment_header(
+                        segment_header,
+                        piece_getter,
+                        worker_state,
+                    )
+                    .await;
+                }
             }
+        }
+    }
 
+    /// Process segment header received from the node, pieces are retrieved from the node as well
+    async fn process_archived_segment_header(
+        &self,
+        segment_header: SegmentHeader,
+        worker_state: &mut CacheWorkerState,
+    ) {
+        let segment_index = segment_header.segment_index();
+        debug!(%segment_index, "Starting to process newly archived segment");
+
+        // Segment might have been processed already due to announcement over DSN, but it still
+        // needs to be acknowledged
+        if worker_state.last_segment_index < segment_index {
             // TODO: Can probably do concurrency here
             for piece_index in segment_index.segment_piece_indexes() {
                 if !worker_state
@@ -376,21 +475,211 @@ where
             }
 
             worker_state.last_segment_index = segment_index;
+        }
+
+        match self
+            .node_client
+            .acknowledge_archived_segment_header(segment_index)
+            .await
+        {
+            Ok(()) => {
+                debug!(%segment_index, "Acknowledged archived segment");
+            }
+            Err(error) => {
+                error!(%segment_index, ?error, "Failed to acknowledge archived segment");
+            }
+        };
+
+        debug!(%segment_index, "Finished processing newly archived segment");
+    }
+
+    /// Process segment header announced over DSN gossip, pieces are retrieved using piece getter.
+    ///
+    /// Announced segments are processed in order once confirmed by the node, segments that can't
+    /// be processed yet are kept around and retried on the next announcement or notification from
+    /// the node.
+    async fn process_announced_segment_header<PG>(
+        &self,
+        segment_header: SegmentHeader,
+        piece_getter: &PG,
+        worker_state: &mut CacheWorkerState,
+    ) where
+        PG: PieceGetter,
+    {
+        let segment_index = segment_header.segment_index();
+
+        if worker_state.last_segment_index >= segment_index {
+            trace!(%segment_index, "Announced segment was already processed");
+            return;
+        }
+
+        worker_state
+            .pending_announced_segments
+            .insert(segment_index, segment_header);
+        while worker_state.pending_announced_segments.len() > PENDING_ANNOUNCED_SEGMENTS_LIMIT {
+            worker_state.pending_announced_segments.pop_last();
+        }
+
+        self.process_pending_announced_segments(piece_getter, worker_state)
+            .await;
+    }
+
+    /// Process pending segments announced over DSN that directly follow the last processed
+    /// segment, stops at the first segment that can't be processed yet
+    async fn process_pending_announced_segments<PG>(
+        &self,
+        piece_getter: &PG,
+        worker_state: &mut CacheWorkerState,
+    ) where
+        PG: PieceGetter,
+    {
+        while let Some((&segment_index, &segment_header)) =
+            worker_state.pending_announced_segments.first_key_value()
+        {
+            if segment_index <= worker_state.last_segment_index {
+                // Already processed using notification from the node
+                worker_state
+                    .pending_announced_segments
+                    .remove(&segment_index);
+                continue;
+            }
+
+            if segment_index != worker_state.last_segment_index + SegmentIndex::ONE {
+                trace!(
+                    %segment_index,
+                    last_segment_index = %worker_state.last_segment_index,
+                    "Announced segment doesn't follow the last processed segment yet"
+                );
+                return;
+            }
 
             match self
-                .node_client
-                .acknowledge_archived_segment_header(segment_index)
+                .process_announced_segment(segment_header, piece_getter, worker_state)
                 .await
             {
-                Ok(()) => {
-                    debug!(%segment_index, "Acknowledged archived segment");
+                AnnouncedSegmentResult::Cached => {
+                    worker_state
+                        .pending_announced_segments
+                        .remove(&segment_index);
+                    worker_state.last_segment_index = segment_index;
+                }
+                AnnouncedSegmentResult::Retry => {
+                    return;
+                }
+                AnnouncedSegmentResult::Invalid => {
+                    worker_state
+                        .pending_announced_segments
+                        .remove(&segment_index);
+                }
+            }
+        }
+    }
+
+    async fn process_announced_segment<PG>(
+        &self,
+        segment_header: SegmentHeader,
+        piece_getter: &PG,
+        worker_state: &mut CacheWorkerState,
+    ) -> AnnouncedSegmentResult
+    where
+        PG: PieceGetter,
+    {
+        let segment_index = segment_header.segment_index();
+
+        // Announcement was only checked against segment headers known to the farmer, confirm it
+        // with the node before downloading pieces
+        match self.node_client.segment_headers(vec![segment_index]).await {
+            Ok(segment_headers) => match segment_headers.first().copied().flatten() {
+                Some(node_segment_header) if node_segment_header == segment_header => {
+                    // Confirmed by node, further announcements of this segment can be accepted
+                    if let Some(segment_header_announcements) = &self.segment_header_announcements {
+                        segment_header_announcements
+                            .validator
+                            .add_segment_header(segment_header);
+                    }
+                }
+                Some(_) => {
+                    warn!(
+                        %segment_index,
+                        "Announced segment header doesn't match segment header from node, ignoring"
+                    );
+                    return AnnouncedSegmentResult::Invalid;
+                }
+                None => {
+                    debug!(
+                        %segment_index,
+                        "Announced segment is not known to node yet, will retry later"
+                    );
+                    return AnnouncedSegmentResult::Retry;
+                }
+            },
+            Err(error) => {
+                debug!(
+                    %error,
+                    %segment_index,
+                    "Failed to confirm announced segment header with node, will retry later"
+                );
+                return AnnouncedSegmentResult::Retry;
+            }
+        }
+
+        debug!(%segment_index, "Starting to process segment announced over DSN");
+
+        let mut all_pieces_cached = true;
+        // TODO: Can probably do concurrency here
+        for piece_index in segment_index.segment_piece_indexes() {
+            if !worker_state
+                .heap
+                .should_include_key(KeyWrapper(piece_index))
+            {
+                trace!(%piece_index, "Piece doesn't need to be cached #2");
+
+                continue;
+            }
+
+            trace!(%piece_index, "Piece needs to be cached #2");
+
+            let result = piece_getter
+                .get_piece(
+                    piece_index,
+                    PieceGetterRetryPolicy::Limited(PIECE_GETTER_RETRY_NUMBER.get()),
+                )
+                .await;
+
+            let piece = match result {
+                Ok(Some(piece)) => piece,
+                Ok(None) => {
+                    debug!(%segment_index, %piece_index, "Couldn't find announced piece");
+                    all_pieces_cached = false;
+                    continue;
                 }
                 Err(error) => {
-                    error!(%segment_index, ?error, "Failed to acknowledge archived segment");
+                    debug!(
+                        %error,
+                        %segment_index,
+                        %piece_index,
+                        "Failed to get announced piece for piece cache"
+                    );
+                    all_pieces_cached = false;
+                    continue;
                 }
             };
 
-            debug!(%segment_index, "Finished processing newly archived segment");
+            self.persist_piece_in_cache(piece_index, piece, worker_state);
+        }
+
+        if all_pieces_cached {
+            debug!(%segment_index, "Finished processing segment announced over DSN");
+
+            AnnouncedSegmentResult::Cached
+        } else {
+            // Pieces that were cached already will be skipped on retry
+            debug!(
+                %segment_index,
+                "Not all pieces of segment announced over DSN were cached, will retry later"
+            );
+
+            AnnouncedSegmentResult::Retry
         }
     }
 
@@ -468,6 +757,14 @@ where
         worker_state.last_segment_index = last_segment_index;
     }
 
+    /// Update piece cache capability advertised to other peers according to the current contents
+    /// of the heap
+    fn update_capability(&self, worker_state: &CacheWorkerState) {
+        *self.capability.lock() = PieceCacheCapability {
+            max_distance_ilog2: worker_state.heap.max_distance_ilog2(),
+        };
+    }
+
     /// This assumes it was already checked that piece needs to be stored, no verification for this
     /// is done internally and invariants will break if this assumption doesn't hold true
     fn persist_piece_in_cache(
@@ -480,7 +777,9 @@ where
         let heap_key = KeyWrapper(piece_index);
 
         let mut caches = self.caches.write();
-        match worker_state.heap.insert(heap_key) {
+        let evicted = worker_state.heap.insert(heap_key);
+        self.update_capability(worker_state);
+        match evicted {
             // Entry is already occupied, we need to find and replace old piece with new one
             Some(KeyWrapper(old_piece_index)) => {
                 for (disk_farm_index, cache) in caches.iter_mut().enumerate() {
@@ -562,6 +861,8 @@ pub struct PieceCache {
     peer_id: PeerId,
     /// Individual disk caches where pieces are stored
     caches: Arc<RwLock<Vec<DiskPieceCacheState>>>,
+    /// Piece cache capability advertised to other peers
+    capability: Arc<Mutex<PieceCacheCapability>>,
     // We do not want to increase capacity unnecessarily on clone
     worker_sender: mpsc::Sender<WorkerCommand>,
 }
@@ -576,23 +877,32 @@ impl PieceCache {
         NC: NodeClient,
     {
         let caches = Arc::default();
+        let capability = Arc::default();
         let (worker_sender, worker_receiver) = mpsc::channel(WORKER_CHANNEL_CAPACITY);
 
         let instance = Self {
             peer_id,
             caches: Arc::clone(&caches),
+            capability: Arc::clone(&capability),
             worker_sender,
         };
         let worker = CacheWorker {
             peer_id,
             node_client,
             caches,
+            capability,
             worker_receiver: Some(worker_receiver),
+            segment_header_announcements: None,
         };
 
         (instance, worker)
     }
 
+    /// Piece cache capability to advertise to other peers
+    pub fn capability(&self) -> PieceCacheCapability {
+        *self.capability.lock()
+    }
+
     /// Get piece from cache
     pub async fn get_piece(&self, key: RecordKey) -> Option<Piece> {
         let caches = Arc::clone(&self.caches);
@@ -12,7 +12,7 @@ use subspace_networking::libp2p::kad::RecordKey;
 use subspace_networking::libp2p::PeerId;
 use subspace_networking::utils::multihash::ToMultihash;
 use subspace_networking::utils::piece_provider::{PieceProvider, PieceValidator, RetryPolicy};
-use subspace_networking::Node;
+use subspace_networking::{GenericRequest, Node, PieceByIndexRequest};
 use tracing::{debug, error, trace};
 
 pub struct FarmerPieceGetter<PV, NC> {
@@ -61,7 +61,7 @@ where
     ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
         let key = RecordKey::from(piece_index.to_multihash());
 
-        if let Some(piece) = self.piece_cache.get_piece(key).await {
+        if let Some(piece) = self.piece_cache.get_piece(key.clone()).await {
             return Ok(Some(piece));
         }
 
@@ -106,13 +106,27 @@ where
 
         // L1 piece acquisition
         // TODO: consider using retry policy for L1 lookups as well.
-        let connected_peers = HashSet::<PeerId>::from_iter(self.node.connected_peers().await?);
+        let connected_peers = HashSet::<PeerId>::from_iter(
+            self.node
+                .connected_peers_supporting(PieceByIndexRequest::PROTOCOL_NAME)
+                .await?,
+        );
         if connected_peers.is_empty() {
             debug!(%piece_index, "Cannot acquire piece from L1: no connected peers.");
 
             return Ok(None);
         }
 
+        // Peers whose piece cache is likely to contain the piece are tried first
+        let mut connected_peers = connected_peers.into_iter().collect::<Vec<_>>();
+        connected_peers.sort_by_key(|peer_id| {
+            !self
+                .node
+                .peer_info(peer_id)
+                .map(|peer_info| peer_info.may_have_cached_key(*peer_id, &key))
+                .unwrap_or(true)
+        });
+
         for peer_id in connected_peers.iter() {
             let maybe_piece = self
                 .piece_provider
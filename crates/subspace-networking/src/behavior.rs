@@ -9,6 +9,7 @@ use crate::protocols::connected_peers::{
 use crate::protocols::peer_info::{
     Behaviour as PeerInfoBehaviour, Config as PeerInfoConfig, Event as PeerInfoEvent,
 };
+use crate::protocols::request_response::bandwidth::BandwidthConfig;
 use crate::protocols::request_response::request_response_factory::{
     Event as RequestResponseEvent, RequestHandler, RequestResponseFactoryBehaviour,
 };
@@ -28,7 +29,7 @@ use libp2p::kad::{Kademlia, KademliaConfig, KademliaEvent};
 use libp2p::ping::{Behaviour as Ping, Event as PingEvent};
 use libp2p::swarm::behaviour::toggle::Toggle;
 use libp2p::swarm::NetworkBehaviour;
-use libp2p::PeerId;
+use libp2p::{identity, PeerId};
 use void::Void as VoidEvent;
 
 type BlockListBehaviour = AllowBlockListBehaviour<BlockedPeers>;
@@ -36,6 +37,8 @@ type BlockListBehaviour = AllowBlockListBehaviour<BlockedPeers>;
 pub(crate) struct BehaviorConfig<RecordStore> {
     /// Identity keypair of a node used for authenticated connections.
     pub(crate) peer_id: PeerId,
+    /// Identity keypair of a node used for signing gossip messages.
+    pub(crate) keypair: identity::Keypair,
     /// The configuration for the [`Identify`] behaviour.
     pub(crate) identify: IdentifyConfig,
     /// The configuration for the [`Kademlia`] behaviour.
@@ -46,6 +49,8 @@ pub(crate) struct BehaviorConfig<RecordStore> {
     pub(crate) record_store: RecordStore,
     /// The configuration for the [`RequestResponsesBehaviour`] protocol.
     pub(crate) request_response_protocols: Vec<Box<dyn RequestHandler>>,
+    /// Bandwidth accounting and limits for request-response protocols.
+    pub(crate) request_response_bandwidth: BandwidthConfig,
     /// Connection limits for the swarm.
     pub(crate) connection_limits: ConnectionLimits,
     /// The configuration for the [`ReservedPeersBehaviour`].
@@ -101,12 +106,8 @@ where
         let gossipsub = config
             .gossipsub
             .map(|gossip_config| {
-                Gossipsub::new(
-                    // TODO: Do we want message signing?
-                    MessageAuthenticity::Anonymous,
-                    gossip_config,
-                )
-                .expect("Correct configuration")
+                Gossipsub::new(MessageAuthenticity::Signed(config.keypair), gossip_config)
+                    .expect("Correct configuration")
             })
             .into();
 
@@ -121,6 +122,7 @@ where
             ping: Ping::default(),
             request_response: RequestResponseFactoryBehaviour::new(
                 config.request_response_protocols,
+                config.request_response_bandwidth,
             )
             //TODO: Convert to an error.
             .expect("RequestResponse protocols registration failed."),
@@ -13,9 +13,12 @@ use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use subspace_metrics::{start_prometheus_metrics_server, RegistryAdapter};
 use subspace_networking::libp2p::multiaddr::Protocol;
-use subspace_networking::{peer_id, Config};
+use subspace_networking::{
+    peer_id, Config, MuxedTransportConfig, QuicTransportConfig, TransportConfig, TransportKind,
+};
 use tracing::{debug, info, Level};
 use tracing_subscriber::fmt::Subscriber;
 use tracing_subscriber::util::SubscriberInitExt;
@@ -35,6 +38,33 @@ enum Command {
         /// Multiaddr to listen on for subspace networking, multiple are supported
         #[clap(long, default_value = "/ip4/0.0.0.0/tcp/0")]
         listen_on: Vec<Multiaddr>,
+        /// Transports to enable (comma-separated): `tcp`, `quic` and `ws` (WebSocket), listen and
+        /// bootstrap addresses must be supported by enabled transports.
+        #[arg(long, value_delimiter = ',', default_value = "tcp,quic,ws")]
+        transports: Vec<TransportKind>,
+        /// Timeout of TCP connection setup and protocol upgrade in seconds.
+        #[arg(long, default_value_t = 10)]
+        tcp_timeout: u64,
+        /// Timeout of WebSocket connection setup and protocol upgrade in seconds.
+        #[arg(long, default_value_t = 10)]
+        ws_timeout: u64,
+        /// Yamux receive window size per stream in bytes for TCP and WebSocket connections, values
+        /// below 262144 (256 KiB) are raised to 262144.
+        #[arg(long, default_value_t = 256 * 1024)]
+        yamux_receive_window_size: u32,
+        /// Max size of yamux buffer per stream in bytes for TCP and WebSocket connections.
+        #[arg(long, default_value_t = 1024 * 1024)]
+        yamux_max_buffer_size: usize,
+        /// Timeout of QUIC connection handshake in seconds.
+        #[arg(long, default_value_t = 5)]
+        quic_handshake_timeout: u64,
+        /// QUIC connection is closed after being idle for this number of seconds.
+        #[arg(long, default_value_t = 10)]
+        quic_max_idle_timeout: u64,
+        /// Interval of QUIC keep-alive packets in seconds, must be smaller than max idle timeout to
+        /// keep connections alive.
+        #[arg(long, default_value_t = 5)]
+        quic_keep_alive_interval: u64,
         /// Multiaddresses of reserved peers to maintain connections to, multiple are supported
         #[arg(long, alias = "reserved-peer")]
         reserved_peers: Vec<Multiaddr>,
@@ -118,6 +148,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
             bootstrap_nodes,
             keypair,
             listen_on,
+            transports,
+            tcp_timeout,
+            ws_timeout,
+            yamux_receive_window_size,
+            yamux_max_buffer_size,
+            quic_handshake_timeout,
+            quic_max_idle_timeout,
+            quic_keep_alive_interval,
             reserved_peers,
             in_peers,
             out_peers,
@@ -153,6 +191,26 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
             let config = Config {
                 listen_on,
+                transports: TransportConfig::with_transport_configs(
+                    &transports,
+                    MuxedTransportConfig {
+                        timeout: Duration::from_secs(tcp_timeout),
+                        yamux_receive_window_size: yamux_receive_window_size,
+                        yamux_max_buffer_size: yamux_max_buffer_size,
+                        ..MuxedTransportConfig::default()
+                    },
+                    QuicTransportConfig {
+                        handshake_timeout: Duration::from_secs(quic_handshake_timeout),
+                        max_idle_timeout: Duration::from_secs(quic_max_idle_timeout),
+                        keep_alive_interval: Duration::from_secs(quic_keep_alive_interval),
+                    },
+                    MuxedTransportConfig {
+                        timeout: Duration::from_secs(ws_timeout),
+                        yamux_receive_window_size: yamux_receive_window_size,
+                        yamux_max_buffer_size: yamux_max_buffer_size,
+                        ..MuxedTransportConfig::default()
+                    },
+                ),
                 allow_non_global_addresses_in_dht: enable_private_ips,
                 reserved_peers,
                 max_established_incoming_connections: in_peers,
@@ -162,6 +220,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 // we don't maintain permanent connections with any peer
                 general_connected_peers_handler: None,
                 special_connected_peers_handler: None,
+                // bootstrap node can't validate gossip messages, so it doesn't participate in it
+                gossipsub: None,
                 bootstrap_addresses: bootstrap_nodes,
                 external_addresses,
                 metrics,
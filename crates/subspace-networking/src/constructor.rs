@@ -1,6 +1,10 @@
 pub(crate) mod temporary_bans;
 mod transport;
 
+pub use crate::constructor::transport::{
+    MuxedTransportConfig, QuicTransportConfig, TransportConfig, TransportKind,
+};
+
 use crate::behavior::persistent_parameters::{
     NetworkingParametersRegistry, StubNetworkingParametersManager,
 };
@@ -10,7 +14,10 @@ use crate::constructor::transport::build_transport;
 use crate::node::Node;
 use crate::node_runner::{NodeRunner, NodeRunnerConfig};
 use crate::protocols::connected_peers::Config as ConnectedPeersConfig;
-use crate::protocols::peer_info::PeerInfoProvider;
+use crate::protocols::peer_info::{PeerInfoProvider, ProtocolCapability};
+use crate::protocols::request_response::bandwidth::{
+    BandwidthConfig, BandwidthLimits, BandwidthMetrics, PeerBandwidthUsage,
+};
 use crate::protocols::request_response::request_response_factory::RequestHandler;
 use crate::protocols::reserved_peers::Config as ReservedPeersConfig;
 use crate::shared::Shared;
@@ -22,7 +29,7 @@ use libp2p::autonat::Config as AutonatConfig;
 use libp2p::connection_limits::ConnectionLimits;
 use libp2p::gossipsub::{
     Config as GossipsubConfig, ConfigBuilder as GossipsubConfigBuilder,
-    Message as GossipsubMessage, MessageId, ValidationMode,
+    Message as GossipsubMessage, MessageAcceptance, MessageId, Sha256Topic, ValidationMode,
 };
 use libp2p::identify::Config as IdentifyConfig;
 use libp2p::kad::store::RecordStore;
@@ -32,29 +39,58 @@ use libp2p::kad::{
 use libp2p::metrics::Metrics;
 use libp2p::multiaddr::Protocol;
 use libp2p::swarm::SwarmBuilder;
-use libp2p::yamux::Config as YamuxConfig;
 use libp2p::{identity, Multiaddr, PeerId, StreamProtocol, TransportError};
 use libp2p_kad::{Mode, RecordKey};
 use parking_lot::Mutex;
 use std::borrow::Cow;
 use std::iter::Empty;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::string::ToString;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{fmt, io, iter};
 use subspace_core_primitives::{crypto, Piece};
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// Defines whether connection should be maintained permanently.
 pub type ConnectedPeersHandler = Arc<dyn Fn(&PeerInfo) -> bool + Send + Sync + 'static>;
 
+/// Validates gossip message received from a peer before it is delivered to subscribers and
+/// propagated further.
+pub type GossipMessageValidator =
+    Arc<dyn Fn(&PeerId, &[u8]) -> MessageAcceptance + Send + Sync + 'static>;
+
+/// Validation and rate limiting of incoming messages for a particular gossip topic.
+#[derive(Clone)]
+pub struct GossipTopicConfig {
+    /// Gossip topic this configuration applies to.
+    pub topic: Sha256Topic,
+    /// Validator for incoming messages.
+    pub validator: GossipMessageValidator,
+    /// Max number of messages accepted from a single peer within `rate_limit_interval`, excess
+    /// messages are ignored.
+    pub max_messages_per_peer: NonZeroU32,
+    /// Interval of rate limiting.
+    pub rate_limit_interval: Duration,
+}
+
+impl fmt::Debug for GossipTopicConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GossipTopicConfig")
+            .field("topic", &self.topic)
+            .field("max_messages_per_peer", &self.max_messages_per_peer)
+            .field("rate_limit_interval", &self.rate_limit_interval)
+            .finish_non_exhaustive()
+    }
+}
+
 const DEFAULT_NETWORK_PROTOCOL_VERSION: &str = "dev";
 const KADEMLIA_PROTOCOL: &str = "/subspace/kad/0.1.0";
 const GOSSIPSUB_PROTOCOL_PREFIX: &str = "subspace/gossipsub";
 const RESERVED_PEERS_PROTOCOL_NAME: &str = "/subspace/reserved-peers/1.0.0";
-const PEER_INFO_PROTOCOL_NAME: &str = "/subspace/peer-info/1.0.0";
+const PEER_INFO_PROTOCOL_NAME: &str = "/subspace/peer-info/2.0.0";
+const LEGACY_PEER_INFO_PROTOCOL_NAME: &str = "/subspace/peer-info/1.0.0";
 const GENERAL_CONNECTED_PEERS_PROTOCOL_LOG_TARGET: &str = "general-connected-peers";
 const SPECIAL_CONNECTED_PEERS_PROTOCOL_LOG_TARGET: &str = "special-connected-peers";
 
@@ -73,12 +109,8 @@ const SWARM_MAX_PENDING_OUTGOING_CONNECTIONS: u32 = 80;
 const SWARM_TARGET_CONNECTION_NUMBER: u32 = 30;
 // Defines a replication factor for Kademlia on get_record operation.
 // "Good citizen" supports the network health.
-const YAMUX_MAX_STREAMS: usize = 256;
 const KADEMLIA_QUERY_TIMEOUT: Duration = Duration::from_secs(40);
 const SWARM_MAX_ESTABLISHED_CONNECTIONS_PER_PEER: Option<u32> = Some(3);
-// TODO: Consider moving this constant to configuration or removing `Toggle` wrapper when we find a
-// use-case for gossipsub protocol.
-const ENABLE_GOSSIP_PROTOCOL: bool = false;
 
 /// Base limit for number of concurrent tasks initiated towards Kademlia.
 ///
@@ -190,19 +222,19 @@ pub struct Config<LocalRecordProvider> {
     pub listen_on: Vec<Multiaddr>,
     /// Fallback to random port if specified (or default) port is already occupied.
     pub listen_on_fallback_to_random_port: bool,
-    /// Adds a timeout to the setup and protocol upgrade process for all inbound and outbound
-    /// connections established through the transport.
-    pub timeout: Duration,
+    /// Transports to use for inbound and outbound connections.
+    pub transports: TransportConfig,
     /// The configuration for the Identify behaviour.
     pub identify: IdentifyConfig,
     /// The configuration for the Kademlia behaviour.
     pub kademlia: KademliaConfig,
     /// The configuration for the Gossip behaviour.
     pub gossipsub: Option<GossipsubConfig>,
+    /// Validation and rate limiting configuration for gossip topics. Messages on topics that are
+    /// not listed here are accepted without validation.
+    pub gossip_topics: Vec<GossipTopicConfig>,
     /// Externally provided implementation of the local records provider
     pub local_records_provider: LocalRecordProvider,
-    /// Yamux multiplexing configuration.
-    pub yamux_config: YamuxConfig,
     /// Should non-global addresses be added to the DHT?
     pub allow_non_global_addresses_in_dht: bool,
     /// How frequently should random queries be done using Kademlia DHT to populate routing table.
@@ -211,6 +243,8 @@ pub struct Config<LocalRecordProvider> {
     pub networking_parameters_registry: Option<Box<dyn NetworkingParametersRegistry>>,
     /// The configuration for the `RequestResponsesBehaviour` protocol.
     pub request_response_protocols: Vec<Box<dyn RequestHandler>>,
+    /// Bandwidth limits and per-peer request quotas for request-response protocols.
+    pub request_response_bandwidth_limits: BandwidthLimits,
     /// Defines set of peers with a permanent connection (and reconnection if necessary).
     pub reserved_peers: Vec<Multiaddr>,
     /// Established incoming swarm connection limit.
@@ -227,6 +261,9 @@ pub struct Config<LocalRecordProvider> {
     pub temporary_ban_backoff: ExponentialBackoff,
     /// Optional external prometheus metrics. None will disable metrics gathering.
     pub metrics: Option<Metrics>,
+    /// Optional external prometheus metrics for bandwidth usage of request-response protocols.
+    /// None will disable metrics gathering.
+    pub bandwidth_metrics: Option<BandwidthMetrics>,
     /// Defines protocol version for the network peers. Affects network partition.
     pub protocol_version: String,
     /// Specifies a source for peer information. None disables the protocol.
@@ -301,22 +338,19 @@ where
             .set_record_ttl(None)
             .set_replication_interval(None);
 
-        let mut yamux_config = YamuxConfig::default();
-        yamux_config.set_max_num_streams(YAMUX_MAX_STREAMS);
-
-        let gossipsub = ENABLE_GOSSIP_PROTOCOL.then(|| {
-            GossipsubConfigBuilder::default()
-                .protocol_id_prefix(GOSSIPSUB_PROTOCOL_PREFIX)
-                // TODO: Do we want message signing?
-                .validation_mode(ValidationMode::None)
-                // To content-address message, we can take the hash of message and use it as an ID.
-                .message_id_fn(|message: &GossipsubMessage| {
-                    MessageId::from(crypto::blake2b_256_hash(&message.data))
-                })
-                .max_transmit_size(2 * 1024 * 1024) // 2MB
-                .build()
-                .expect("Default config for gossipsub is always correct; qed")
-        });
+        let gossipsub = GossipsubConfigBuilder::default()
+            .protocol_id_prefix(GOSSIPSUB_PROTOCOL_PREFIX)
+            // Messages are signed by publisher
+            .validation_mode(ValidationMode::Strict)
+            // Messages are only forwarded after they were validated by node runner
+            .validate_messages()
+            // To content-address message, we can take the hash of message and use it as an ID.
+            .message_id_fn(|message: &GossipsubMessage| {
+                MessageId::from(crypto::blake2b_256_hash(&message.data))
+            })
+            .max_transmit_size(2 * 1024 * 1024) // 2MB
+            .build()
+            .expect("Default config for gossipsub is always correct; qed");
 
         let protocol_version = format!("/subspace/{}", protocol_version);
         let identify = IdentifyConfig::new(protocol_version.clone(), keypair.public());
@@ -336,16 +370,17 @@ where
             keypair,
             listen_on: vec![],
             listen_on_fallback_to_random_port: true,
-            timeout: Duration::from_secs(10),
+            transports: TransportConfig::default(),
             identify,
             kademlia,
-            gossipsub,
+            gossipsub: Some(gossipsub),
+            gossip_topics: Vec::new(),
             local_records_provider,
             allow_non_global_addresses_in_dht: false,
             initial_random_query_interval: Duration::from_secs(1),
             networking_parameters_registry: None,
             request_response_protocols: Vec::new(),
-            yamux_config,
+            request_response_bandwidth_limits: BandwidthLimits::default(),
             reserved_peers: Vec::new(),
             max_established_incoming_connections: SWARM_MAX_ESTABLISHED_INCOMING_CONNECTIONS,
             max_established_outgoing_connections: SWARM_MAX_ESTABLISHED_OUTGOING_CONNECTIONS,
@@ -354,6 +389,7 @@ where
             temporary_bans_cache_size: TEMPORARY_BANS_CACHE_SIZE,
             temporary_ban_backoff,
             metrics: None,
+            bandwidth_metrics: None,
             protocol_version,
             peer_info_provider,
             // we don't need to keep additional connections by default
@@ -381,6 +417,12 @@ pub enum CreationError {
     /// Transport error when attempting to listen on multiaddr.
     #[error("Transport error when attempting to listen on multiaddr: {0}")]
     TransportError(#[from] TransportError<io::Error>),
+    /// None of the transports is enabled.
+    #[error("At least one transport must be enabled.")]
+    NoTransportsEnabled,
+    /// Listen address is not supported by any of the enabled transports.
+    #[error("Listen address {0} is not supported by any of the enabled transports.")]
+    UnsupportedListenAddress(Multiaddr),
 }
 
 /// Converts public key from keypair to PeerId.
@@ -400,16 +442,17 @@ where
         keypair,
         listen_on,
         listen_on_fallback_to_random_port,
-        timeout,
+        transports,
         identify,
         kademlia,
         gossipsub,
+        gossip_topics,
         local_records_provider,
-        yamux_config,
         allow_non_global_addresses_in_dht,
         initial_random_query_interval,
         networking_parameters_registry,
         request_response_protocols,
+        request_response_bandwidth_limits,
         reserved_peers,
         max_established_incoming_connections,
         max_established_outgoing_connections,
@@ -418,6 +461,7 @@ where
         temporary_bans_cache_size,
         temporary_ban_backoff,
         metrics,
+        bandwidth_metrics,
         protocol_version,
         peer_info_provider,
         general_connected_peers_handler: general_connection_decision_handler,
@@ -439,8 +483,7 @@ where
         allow_non_global_addresses_in_dht,
         &keypair,
         Arc::clone(&temporary_bans),
-        timeout,
-        yamux_config,
+        &transports,
     )?;
 
     info!(
@@ -459,19 +502,45 @@ where
 
     debug!(?connection_limits, "DSN connection limits set.");
 
+    let peer_bandwidth_usage = PeerBandwidthUsage::default();
+
+    let peer_info_provider = peer_info_provider.map(|mut peer_info_provider| {
+        peer_info_provider.set_protocols(
+            request_response_protocols
+                .iter()
+                .map(|request_handler| {
+                    let protocol_config = request_handler.protocol_config();
+
+                    ProtocolCapability {
+                        name: protocol_config.name.to_string(),
+                        max_batch_size: protocol_config.max_batch_size,
+                    }
+                })
+                .collect(),
+        );
+        peer_info_provider
+    });
+
     let behaviour = Behavior::new(BehaviorConfig {
         peer_id: local_peer_id,
+        keypair,
         identify,
         kademlia,
         gossipsub,
         record_store: LocalOnlyRecordStore::new(local_records_provider),
         request_response_protocols,
+        request_response_bandwidth: BandwidthConfig {
+            limits: request_response_bandwidth_limits,
+            metrics: bandwidth_metrics,
+            peer_usage: peer_bandwidth_usage.clone(),
+        },
         connection_limits,
         reserved_peers: ReservedPeersConfig {
             reserved_peers: reserved_peers.clone(),
             protocol_name: RESERVED_PEERS_PROTOCOL_NAME,
         },
-        peer_info_config: PeerInfoConfig::new(PEER_INFO_PROTOCOL_NAME),
+        peer_info_config: PeerInfoConfig::new(PEER_INFO_PROTOCOL_NAME)
+            .with_legacy_protocol_name(LEGACY_PEER_INFO_PROTOCOL_NAME),
         peer_info_provider,
         general_connected_peers_config: general_connection_decision_handler.as_ref().map(|_| {
             ConnectedPeersConfig {
@@ -498,8 +567,23 @@ where
         .max_negotiating_inbound_streams(SWARM_MAX_NEGOTIATING_INBOUND_STREAMS)
         .build();
 
+    for address in bootstrap_addresses.iter().chain(&reserved_peers) {
+        if !transports.supports(address) {
+            warn!(
+                %address,
+                enabled_transports = ?transports.enabled_transports(),
+                "Address is not supported by any of the enabled transports, connection to it will \
+                not be possible"
+            );
+        }
+    }
+
     // Setup listen_on addresses
     for mut addr in listen_on {
+        if !transports.supports(&addr) {
+            return Err(CreationError::UnsupportedListenAddress(addr));
+        }
+
         if let Err(error) = swarm.listen_on(addr.clone()) {
             if !listen_on_fallback_to_random_port {
                 return Err(error.into());
@@ -532,6 +616,7 @@ where
         command_sender,
         kademlia_tasks_semaphore,
         regular_tasks_semaphore,
+        peer_bandwidth_usage,
     ));
     let shared_weak = Arc::downgrade(&shared);
 
@@ -549,10 +634,12 @@ where
             temporary_bans,
             metrics,
             protocol_version,
+            gossip_topics,
             general_connection_decision_handler,
             special_connection_decision_handler,
             bootstrap_addresses,
             kademlia_mode,
+            transports,
             external_addresses,
         });
 
@@ -1,6 +1,10 @@
+#[cfg(test)]
+mod tests;
+
 use crate::constructor::temporary_bans::TemporaryBans;
 use crate::CreationError;
 use futures::future::Either;
+use futures::{AsyncRead, AsyncWrite};
 use libp2p::core::multiaddr::{Multiaddr, Protocol};
 use libp2p::core::muxing::StreamMuxerBox;
 use libp2p::core::transport::{Boxed, ListenerId, TransportError, TransportEvent};
@@ -14,66 +18,295 @@ use libp2p::{core, identity, noise, PeerId};
 use libp2p_quic::tokio::Transport as QuicTransport;
 use libp2p_quic::Config as QuicConfig;
 use parking_lot::Mutex;
-use std::io;
 use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
+use std::{fmt, io};
 use tracing::debug;
 
-// Builds the transport stack that LibP2P will communicate over along with a relay client.
+/// Yamux doesn't allow receive window to be smaller than this.
+const YAMUX_MIN_RECEIVE_WINDOW_SIZE: u32 = 256 * 1024;
+
+/// Kind of the transport.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TransportKind {
+    /// TCP with noise encryption and yamux multiplexing.
+    Tcp,
+    /// QUIC (`/quic-v1`).
+    Quic,
+    /// WebSocket over TCP with noise encryption and yamux multiplexing, primarily for browsers.
+    Websocket,
+}
+
+impl fmt::Display for TransportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Tcp => "tcp",
+            Self::Quic => "quic",
+            Self::Websocket => "ws",
+        })
+    }
+}
+
+impl FromStr for TransportKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tcp" => Ok(Self::Tcp),
+            "quic" => Ok(Self::Quic),
+            "ws" | "websocket" => Ok(Self::Websocket),
+            s => Err(format!(
+                "Unknown transport \"{s}\", supported transports: tcp, quic, ws"
+            )),
+        }
+    }
+}
+
+impl TransportKind {
+    /// Transport that is necessary to listen on or dial provided address, `None` if address is not
+    /// supported by any transport.
+    pub fn from_address(address: &Multiaddr) -> Option<Self> {
+        let mut protocols = address.iter().skip_while(|protocol| {
+            matches!(
+                protocol,
+                Protocol::Ip4(_)
+                    | Protocol::Ip6(_)
+                    | Protocol::Dns(_)
+                    | Protocol::Dns4(_)
+                    | Protocol::Dns6(_)
+                    | Protocol::Dnsaddr(_)
+            )
+        });
+
+        match (protocols.next(), protocols.next()) {
+            (Some(Protocol::Tcp(_)), None | Some(Protocol::P2p(_))) => Some(Self::Tcp),
+            (Some(Protocol::Tcp(_)), Some(Protocol::Ws(_) | Protocol::Wss(_))) => {
+                Some(Self::Websocket)
+            }
+            (Some(Protocol::Udp(_)), Some(Protocol::QuicV1)) => Some(Self::Quic),
+            _ => None,
+        }
+    }
+}
+
+/// Configuration of TCP-based transport (TCP or WebSocket) that is upgraded with noise encryption
+/// and yamux multiplexing.
+#[derive(Debug, Clone)]
+pub struct MuxedTransportConfig {
+    /// Timeout for the setup and protocol upgrade process of inbound and outbound connections.
+    pub timeout: Duration,
+    /// Yamux receive window size (per stream) in bytes, values below 256 KiB are raised to 256 KiB.
+    pub yamux_receive_window_size: u32,
+    /// Max size of yamux buffer (per stream) in bytes.
+    pub yamux_max_buffer_size: usize,
+    /// Max number of yamux streams per connection.
+    pub yamux_max_num_streams: usize,
+}
+
+impl Default for MuxedTransportConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            yamux_receive_window_size: YAMUX_MIN_RECEIVE_WINDOW_SIZE,
+            yamux_max_buffer_size: 1024 * 1024,
+            yamux_max_num_streams: 256,
+        }
+    }
+}
+
+impl MuxedTransportConfig {
+    fn yamux_config(&self) -> YamuxConfig {
+        let mut yamux_config = YamuxConfig::default();
+        yamux_config
+            .set_receive_window_size(
+                self.yamux_receive_window_size
+                    .max(YAMUX_MIN_RECEIVE_WINDOW_SIZE),
+            )
+            .set_max_buffer_size(self.yamux_max_buffer_size)
+            .set_max_num_streams(self.yamux_max_num_streams);
+        yamux_config
+    }
+}
+
+/// Configuration of QUIC transport.
+#[derive(Debug, Clone)]
+pub struct QuicTransportConfig {
+    /// Timeout for the initial handshake of the connection.
+    pub handshake_timeout: Duration,
+    /// Connection is closed after being idle for this long.
+    pub max_idle_timeout: Duration,
+    /// Interval of keep-alive packets, must be smaller than `max_idle_timeout` to keep connection
+    /// alive.
+    pub keep_alive_interval: Duration,
+}
+
+impl Default for QuicTransportConfig {
+    fn default() -> Self {
+        Self {
+            handshake_timeout: Duration::from_secs(5),
+            max_idle_timeout: Duration::from_secs(10),
+            keep_alive_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Transports used by the node, each of them can be disabled by setting it to `None`, but at least
+/// one must be enabled.
+///
+/// Two nodes can only connect to each other if they have at least one transport in common.
+#[derive(Debug, Clone)]
+pub struct TransportConfig {
+    /// TCP transport.
+    pub tcp: Option<MuxedTransportConfig>,
+    /// QUIC transport.
+    pub quic: Option<QuicTransportConfig>,
+    /// WebSocket transport.
+    pub websocket: Option<MuxedTransportConfig>,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            tcp: Some(MuxedTransportConfig::default()),
+            quic: Some(QuicTransportConfig::default()),
+            websocket: Some(MuxedTransportConfig::default()),
+        }
+    }
+}
+
+impl TransportConfig {
+    /// Config with only specified transports enabled (with default settings).
+    pub fn with_transports(transports: &[TransportKind]) -> Self {
+        Self::with_transport_configs(
+            transports,
+            MuxedTransportConfig::default(),
+            QuicTransportConfig::default(),
+            MuxedTransportConfig::default(),
+        )
+    }
+
+    /// Config with only specified transports enabled, settings of transports that are not enabled
+    /// are ignored.
+    pub fn with_transport_configs(
+        transports: &[TransportKind],
+        tcp: MuxedTransportConfig,
+        quic: QuicTransportConfig,
+        websocket: MuxedTransportConfig,
+    ) -> Self {
+        Self {
+            tcp: transports.contains(&TransportKind::Tcp).then_some(tcp),
+            quic: transports.contains(&TransportKind::Quic).then_some(quic),
+            websocket: transports
+                .contains(&TransportKind::Websocket)
+                .then_some(websocket),
+        }
+    }
+
+    /// Enabled transports.
+    pub fn enabled_transports(&self) -> Vec<TransportKind> {
+        [
+            self.tcp.as_ref().map(|_| TransportKind::Tcp),
+            self.quic.as_ref().map(|_| TransportKind::Quic),
+            self.websocket.as_ref().map(|_| TransportKind::Websocket),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Whether provided address can be listened on or dialed with enabled transports.
+    pub fn supports(&self, address: &Multiaddr) -> bool {
+        TransportKind::from_address(address)
+            .map(|transport| self.enabled_transports().contains(&transport))
+            .unwrap_or_default()
+    }
+}
+
+// Builds the transport stack that LibP2P will communicate over.
 pub(super) fn build_transport(
     allow_non_global_addresses_in_dht: bool,
     keypair: &identity::Keypair,
     temporary_bans: Arc<Mutex<TemporaryBans>>,
-    timeout: Duration,
-    yamux_config: YamuxConfig,
+    transports: &TransportConfig,
 ) -> Result<Boxed<(PeerId, StreamMuxerBox)>, CreationError> {
-    let wrapped_tcp_ws = {
-        let tcp_config = GenTcpConfig::default().nodelay(true);
+    let tcp_config = GenTcpConfig::default().nodelay(true);
+
+    let tcp = transports.tcp.as_ref().map(|config| {
         let wrapped_tcp = CustomTransportWrapper::new(
             TokioTcpTransport::new(tcp_config.clone()),
             allow_non_global_addresses_in_dht,
             temporary_bans.clone(),
         );
 
+        upgrade_with_noise_and_yamux(wrapped_tcp, keypair, config)
+    });
+
+    let websocket = transports.websocket.as_ref().map(|config| {
         let wrapped_ws = WsConfig::new(CustomTransportWrapper::new(
-            TokioTcpTransport::new(tcp_config),
+            TokioTcpTransport::new(tcp_config.clone()),
             allow_non_global_addresses_in_dht,
             temporary_bans.clone(),
         ));
 
-        wrapped_tcp.or_transport(wrapped_ws)
-    };
+        upgrade_with_noise_and_yamux(wrapped_ws, keypair, config)
+    });
 
-    let tcp_ws_upgraded = {
-        let noise =
-            noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed.");
+    let quic = transports.quic.as_ref().map(|config| {
+        let mut quic_config = QuicConfig::new(keypair);
+        quic_config.handshake_timeout = config.handshake_timeout;
+        quic_config.max_idle_timeout =
+            u32::try_from(config.max_idle_timeout.as_millis()).unwrap_or(u32::MAX);
+        quic_config.keep_alive_interval = config.keep_alive_interval;
 
-        wrapped_tcp_ws
-            .upgrade(core::upgrade::Version::V1Lazy)
-            .authenticate(noise)
-            .multiplex(yamux_config)
-            .timeout(timeout)
-            .boxed()
-    };
+        let quic = QuicTransport::new(quic_config)
+            .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
 
-    let quic = QuicTransport::new(QuicConfig::new(keypair))
-        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+        CustomTransportWrapper::new(quic, allow_non_global_addresses_in_dht, temporary_bans).boxed()
+    });
 
-    let wrapped_quic =
-        CustomTransportWrapper::new(quic, allow_non_global_addresses_in_dht, temporary_bans);
+    let transport = [tcp, websocket, quic]
+        .into_iter()
+        .flatten()
+        .reduce(|transport_a, transport_b| {
+            transport_a
+                .or_transport(transport_b)
+                .map(|either, _| match either {
+                    Either::Left((peer_id, muxer)) => (peer_id, muxer),
+                    Either::Right((peer_id, muxer)) => (peer_id, muxer),
+                })
+                .boxed()
+        })
+        .ok_or(CreationError::NoTransportsEnabled)?;
 
-    let tcp_ws_quic = tcp_ws_upgraded
-        .or_transport(wrapped_quic)
-        .map(|either, _| match either {
-            Either::Left((peer_id, muxer)) => (peer_id, muxer),
-            Either::Right((peer_id, muxer)) => (peer_id, muxer),
-        });
+    let dns_wrapped_transport = TokioDnsConfig::system(transport)?;
+
+    Ok(dns_wrapped_transport.boxed())
+}
 
-    let dns_wrapped_upgraded_tcp_ws_quic = TokioDnsConfig::system(tcp_ws_quic)?;
+fn upgrade_with_noise_and_yamux<T, C>(
+    transport: T,
+    keypair: &identity::Keypair,
+    config: &MuxedTransportConfig,
+) -> Boxed<(PeerId, StreamMuxerBox)>
+where
+    T: Transport<Output = C> + Send + Unpin + 'static,
+    T::Error: Send + Sync + 'static,
+    T::Dial: Send + 'static,
+    T::ListenerUpgrade: Send + 'static,
+    C: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let noise =
+        noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed.");
 
-    Ok(dns_wrapped_upgraded_tcp_ws_quic.boxed())
+    transport
+        .upgrade(core::upgrade::Version::V1Lazy)
+        .authenticate(noise)
+        .multiplex(config.yamux_config())
+        .timeout(config.timeout)
+        .boxed()
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,271 @@
+use crate::constructor::transport::{
+    MuxedTransportConfig, QuicTransportConfig, TransportConfig, TransportKind,
+};
+use crate::{Config, CreationError, DialError, Node};
+use futures::channel::oneshot;
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn transport_kind_from_address() {
+    let cases = [
+        ("/ip4/127.0.0.1/tcp/30333", Some(TransportKind::Tcp)),
+        (
+            "/dns/bootstrap.subspace.network/tcp/30333/p2p/12D3KooWGAjyJAZNNsHu8sV6MP6mXHzNXFQbadjVBFUr5deTiom2",
+            Some(TransportKind::Tcp),
+        ),
+        ("/ip6/::1/tcp/30333/ws", Some(TransportKind::Websocket)),
+        ("/ip4/127.0.0.1/tcp/443/wss", Some(TransportKind::Websocket)),
+        ("/ip4/127.0.0.1/udp/30333/quic-v1", Some(TransportKind::Quic)),
+        ("/ip4/127.0.0.1/udp/30333", None),
+        ("/memory/1234", None),
+    ];
+
+    for (address, transport) in cases {
+        let address = address.parse::<Multiaddr>().unwrap();
+        assert_eq!(
+            TransportKind::from_address(&address),
+            transport,
+            "{address}"
+        );
+    }
+
+    let quic_only = TransportConfig::with_transports(&[TransportKind::Quic]);
+    assert_eq!(quic_only.enabled_transports(), vec![TransportKind::Quic]);
+    assert!(quic_only.supports(&"/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()));
+    assert!(!quic_only.supports(&"/ip4/127.0.0.1/tcp/0".parse().unwrap()));
+    assert!(!quic_only.supports(&"/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()));
+}
+
+#[test]
+fn transport_configs() {
+    let tcp = MuxedTransportConfig {
+        timeout: Duration::from_secs(30),
+        yamux_receive_window_size: 1024 * 1024,
+        ..MuxedTransportConfig::default()
+    };
+    let quic = QuicTransportConfig {
+        max_idle_timeout: Duration::from_secs(60),
+        ..QuicTransportConfig::default()
+    };
+    let websocket = MuxedTransportConfig {
+        timeout: Duration::from_secs(3),
+        ..MuxedTransportConfig::default()
+    };
+
+    let config = TransportConfig::with_transport_configs(
+        &[TransportKind::Tcp, TransportKind::Quic],
+        tcp,
+        quic,
+        websocket,
+    );
+    assert_eq!(
+        config.enabled_transports(),
+        vec![TransportKind::Tcp, TransportKind::Quic]
+    );
+    let tcp = config.tcp.unwrap();
+    assert_eq!(tcp.timeout, Duration::from_secs(30));
+    assert_eq!(tcp.yamux_receive_window_size, 1024 * 1024);
+    assert_eq!(
+        config.quic.unwrap().max_idle_timeout,
+        Duration::from_secs(60)
+    );
+    assert!(config.websocket.is_none());
+}
+
+#[test]
+fn transport_kind_from_str() {
+    assert_eq!("tcp".parse::<TransportKind>(), Ok(TransportKind::Tcp));
+    assert_eq!("quic".parse::<TransportKind>(), Ok(TransportKind::Quic));
+    assert_eq!("ws".parse::<TransportKind>(), Ok(TransportKind::Websocket));
+    assert!("udp".parse::<TransportKind>().is_err());
+
+    for transport in [
+        TransportKind::Tcp,
+        TransportKind::Quic,
+        TransportKind::Websocket,
+    ] {
+        assert_eq!(
+            transport.to_string().parse::<TransportKind>(),
+            Ok(transport)
+        );
+    }
+}
+
+#[tokio::test]
+async fn construct_fails_without_transports() {
+    let config = Config {
+        listen_on: Vec::new(),
+        transports: TransportConfig::with_transports(&[]),
+        ..Config::default()
+    };
+
+    assert!(matches!(
+        crate::construct(config),
+        Err(CreationError::NoTransportsEnabled)
+    ));
+}
+
+#[tokio::test]
+async fn construct_fails_with_unsupported_listen_address() {
+    let listen_address = "/ip4/127.0.0.1/tcp/0".parse::<Multiaddr>().unwrap();
+    let config = Config {
+        listen_on: vec![listen_address.clone()],
+        transports: TransportConfig::with_transports(&[TransportKind::Quic]),
+        ..Config::default()
+    };
+
+    match crate::construct(config) {
+        Err(CreationError::UnsupportedListenAddress(address)) => {
+            assert_eq!(address, listen_address);
+        }
+        _ => panic!("Expected unsupported listen address error"),
+    }
+}
+
+/// Starts a node with specified transports, returns it along with its first listen address.
+async fn start_node(transports: &[TransportKind], listen_on: &str) -> (Node, Multiaddr) {
+    let config = Config {
+        listen_on: vec![listen_on.parse().unwrap()],
+        allow_non_global_addresses_in_dht: true,
+        transports: TransportConfig::with_transports(transports),
+        ..Config::default()
+    };
+    let (node, mut node_runner) = crate::construct(config).unwrap();
+
+    let (address_sender, address_receiver) = oneshot::channel();
+    let on_new_listener_handler = node.on_new_listener(Arc::new({
+        let address_sender = Mutex::new(Some(address_sender));
+
+        move |address| {
+            if let Some(address_sender) = address_sender.lock().take() {
+                address_sender.send(address.clone()).unwrap();
+            }
+        }
+    }));
+
+    tokio::spawn(async move {
+        node_runner.run().await;
+    });
+
+    let address = address_receiver.await.unwrap();
+    drop(on_new_listener_handler);
+
+    (node, address)
+}
+
+/// Whether node with `dialer_transports` can connect to the node with `listener_transports`
+/// listening on `listen_on`.
+async fn nodes_connect(
+    listener_transports: &[TransportKind],
+    listen_on: &str,
+    dialer_transports: &[TransportKind],
+) -> bool {
+    let (node_1, node_1_address) = start_node(listener_transports, listen_on).await;
+    // Dialer listens on any address it supports, it is not relevant for the test
+    let dialer_listen_on = match dialer_transports[0] {
+        TransportKind::Tcp => "/ip4/127.0.0.1/tcp/0",
+        TransportKind::Quic => "/ip4/127.0.0.1/udp/0/quic-v1",
+        TransportKind::Websocket => "/ip4/127.0.0.1/tcp/0/ws",
+    };
+    let (node_2, _node_2_address) = start_node(dialer_transports, dialer_listen_on).await;
+
+    let (connected_sender, connected_receiver) = oneshot::channel();
+    let _on_connected_peer_handler = node_2.on_connected_peer(Arc::new({
+        let connected_sender = Mutex::new(Some(connected_sender));
+        let node_1_id = node_1.id();
+
+        move |peer_id| {
+            if *peer_id == node_1_id {
+                if let Some(connected_sender) = connected_sender.lock().take() {
+                    let _ = connected_sender.send(());
+                }
+            }
+        }
+    }));
+
+    node_2
+        .dial(node_1_address.with(Protocol::P2p(node_1.id())))
+        .await
+        .unwrap();
+
+    tokio::time::timeout(CONNECTION_TIMEOUT, connected_receiver)
+        .await
+        .is_ok()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tcp_only_nodes_connect() {
+    assert!(
+        nodes_connect(
+            &[TransportKind::Tcp],
+            "/ip4/127.0.0.1/tcp/0",
+            &[TransportKind::Tcp]
+        )
+        .await
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn quic_only_nodes_connect() {
+    assert!(
+        nodes_connect(
+            &[TransportKind::Quic],
+            "/ip4/127.0.0.1/udp/0/quic-v1",
+            &[TransportKind::Quic]
+        )
+        .await
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn websocket_only_nodes_connect() {
+    assert!(
+        nodes_connect(
+            &[TransportKind::Websocket],
+            "/ip4/127.0.0.1/tcp/0/ws",
+            &[TransportKind::Websocket]
+        )
+        .await
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn all_transports_node_connects_to_quic_only_node() {
+    assert!(
+        nodes_connect(
+            &[TransportKind::Quic],
+            "/ip4/127.0.0.1/udp/0/quic-v1",
+            &[
+                TransportKind::Tcp,
+                TransportKind::Quic,
+                TransportKind::Websocket
+            ]
+        )
+        .await
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn nodes_with_non_overlapping_transports_do_not_connect() {
+    let (node_1, node_1_address) =
+        start_node(&[TransportKind::Quic], "/ip4/127.0.0.1/udp/0/quic-v1").await;
+    let (node_2, _node_2_address) = start_node(
+        &[TransportKind::Tcp, TransportKind::Websocket],
+        "/ip4/127.0.0.1/tcp/0",
+    )
+    .await;
+
+    let node_1_address = node_1_address.with(Protocol::P2p(node_1.id()));
+    match node_2.dial(node_1_address.clone()).await {
+        Err(DialError::UnsupportedAddress(address)) => {
+            assert_eq!(address, node_1_address);
+        }
+        result => panic!("Expected unsupported address error, got {result:?}"),
+    }
+}
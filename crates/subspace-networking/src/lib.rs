@@ -31,20 +31,32 @@ pub use crate::behavior::persistent_parameters::{
     NetworkParametersPersistenceError, NetworkingParametersManager,
 };
 pub use crate::node::{
-    GetClosestPeersError, Node, SendRequestError, SubscribeError, TopicSubscription,
+    DialError, GetClosestPeersError, Node, PublishError, SendRequestError, SubscribeError,
+    TopicSubscription,
 };
 pub use crate::node_runner::NodeRunner;
 pub use crate::protocols::peer_info::{
-    Config as PeerInfoConfig, Notification, NotificationHandler, PeerInfo, PeerInfoProvider,
+    Config as PeerInfoConfig, Notification, NotificationHandler, PeerCapabilities, PeerInfo,
+    PeerInfoProvider, PeerRole, PieceCacheCapability, PieceCacheCapabilityProvider,
+    ProtocolCapability,
+};
+pub use constructor::{
+    construct, peer_id, Config, CreationError, GossipMessageValidator, GossipTopicConfig,
+    LocalRecordProvider, MuxedTransportConfig, QuicTransportConfig, TransportConfig, TransportKind,
 };
-pub use constructor::{construct, peer_id, Config, CreationError, LocalRecordProvider};
 pub use libp2p;
+pub use protocols::request_response::bandwidth::{
+    BandwidthLimits, BandwidthMetrics, BandwidthUsage, RequestPriority,
+};
 pub use protocols::request_response::handlers::generic_request_handler::{
     GenericRequest, GenericRequestHandler,
 };
 pub use protocols::request_response::handlers::piece_by_index::{
     PieceByIndexRequest, PieceByIndexRequestHandler, PieceByIndexResponse,
 };
+pub use protocols::request_response::handlers::piece_chunks::{
+    PieceChunksRequest, PieceChunksRequestHandler, PieceChunksResponse,
+};
 pub use protocols::request_response::handlers::segment_header::{
     SegmentHeaderBySegmentIndexesRequestHandler, SegmentHeaderRequest, SegmentHeaderResponse,
 };
@@ -1,3 +1,5 @@
+use crate::protocols::peer_info::PeerInfo;
+use crate::protocols::request_response::bandwidth::BandwidthUsage;
 use crate::protocols::request_response::handlers::generic_request_handler::GenericRequest;
 use crate::protocols::request_response::request_response_factory;
 pub use crate::shared::NewPeerInfo;
@@ -139,6 +141,7 @@ impl From<oneshot::Canceled> for SubscribeError {
     }
 }
 
+/// Defines errors for `publish` operation.
 #[derive(Debug, Error)]
 pub enum PublishError {
     /// Failed to send command to the node runner
@@ -223,6 +226,30 @@ impl From<oneshot::Canceled> for ConnectedPeersError {
     }
 }
 
+/// Defines errors for `dial` operation.
+#[derive(Debug, Error)]
+pub enum DialError {
+    /// Failed to send command to the node runner
+    #[error("Failed to send command to the node runner: {0}")]
+    SendCommand(#[from] SendError),
+    /// Node runner was dropped
+    #[error("Node runner was dropped")]
+    NodeRunnerDropped,
+    /// Address is not supported by any of the enabled transports.
+    #[error("Address {0} is not supported by any of the enabled transports.")]
+    UnsupportedAddress(Multiaddr),
+    /// Failed to start dialing the address.
+    #[error("Failed to dial address: {0}")]
+    Dial(#[from] libp2p::swarm::DialError),
+}
+
+impl From<oneshot::Canceled> for DialError {
+    #[inline]
+    fn from(oneshot::Canceled: oneshot::Canceled) -> Self {
+        Self::NodeRunnerDropped
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum BootstrapError {
     /// Failed to send command to the node runner
@@ -437,13 +464,23 @@ impl Node {
     /// Dial multiaddress.
     /// It could be used to test libp2p transports bypassing protocol checks for bootstrap
     /// or listen-on addresses.
+    ///
+    /// Returns an error if dialing can't be started, for instance when none of the enabled
+    /// transports supports the address, connection itself is established asynchronously.
     #[doc(hidden)]
-    pub async fn dial(&self, address: Multiaddr) -> Result<(), SendError> {
+    pub async fn dial(&self, address: Multiaddr) -> Result<(), DialError> {
+        let (result_sender, result_receiver) = oneshot::channel();
+
         self.shared
             .command_sender
             .clone()
-            .send(Command::Dial { address })
-            .await
+            .send(Command::Dial {
+                address,
+                result_sender,
+            })
+            .await?;
+
+        result_receiver.await?
     }
 
     /// Node's own addresses where it listens for incoming requests.
@@ -456,6 +493,12 @@ impl Node {
         self.shared.external_addresses.lock().clone()
     }
 
+    /// Bytes sent to and received from a peer over request-response protocols, `None` if peer is
+    /// not known (or was evicted from the limited set of tracked peers).
+    pub fn peer_bandwidth_usage(&self, peer_id: &PeerId) -> Option<BandwidthUsage> {
+        self.shared.peer_bandwidth_usage.get(peer_id)
+    }
+
     /// Callback is called when node starts listening on new address.
     pub fn on_new_listener(&self, callback: HandlerFn<Multiaddr>) -> HandlerId {
         self.shared.handlers.new_listener.add(callback)
@@ -489,6 +532,35 @@ impl Node {
             .map_err(|_| ConnectedPeersError::ConnectedPeers)
     }
 
+    /// Returns a collection of currently connected peers that support request-response protocol
+    /// with provided name.
+    ///
+    /// Peers that didn't advertise their capabilities (older versions) are assumed to support it.
+    pub async fn connected_peers_supporting(
+        &self,
+        protocol_name: &str,
+    ) -> Result<Vec<PeerId>, ConnectedPeersError> {
+        let mut connected_peers = self.connected_peers().await?;
+
+        {
+            let peer_infos = self.shared.peer_infos.lock();
+            connected_peers.retain(|peer_id| {
+                peer_infos
+                    .get(peer_id)
+                    .map(|peer_info| peer_info.supports_protocol(protocol_name))
+                    .unwrap_or(true)
+            });
+        }
+
+        Ok(connected_peers)
+    }
+
+    /// Peer info received from connected peer, `None` if peer is not connected or peer info was
+    /// not received yet.
+    pub fn peer_info(&self, peer_id: &PeerId) -> Option<PeerInfo> {
+        self.shared.peer_infos.lock().get(peer_id).cloned()
+    }
+
     /// Bootstraps Kademlia network
     pub async fn bootstrap(&self) -> Result<(), BootstrapError> {
         let (result_sender, mut result_receiver) = mpsc::unbounded();
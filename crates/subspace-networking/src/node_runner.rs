@@ -8,7 +8,8 @@ use crate::behavior::{
 use crate::constructor;
 use crate::constructor::temporary_bans::TemporaryBans;
 use crate::constructor::{
-    ConnectedPeersHandler, LocalOnlyRecordStore, KADEMLIA_CONCURRENT_TASKS_BOOST_PER_PEER,
+    ConnectedPeersHandler, GossipMessageValidator, GossipTopicConfig, LocalOnlyRecordStore,
+    TransportConfig, KADEMLIA_CONCURRENT_TASKS_BOOST_PER_PEER,
     REGULAR_CONCURRENT_TASKS_BOOST_PER_PEER,
 };
 use crate::protocols::connected_peers::Event as ConnectedPeersEvent;
@@ -17,6 +18,7 @@ use crate::protocols::request_response::request_response_factory::{
     Event as RequestResponseEvent, IfDisconnected,
 };
 use crate::shared::{Command, CreatedSubscription, NewPeerInfo, Shared};
+use crate::utils::rate_limiter::RateLimiter;
 use crate::utils::{
     is_global_address_or_dns, strip_peer_id, PeerAddress, ResizableSemaphorePermit,
 };
@@ -28,7 +30,9 @@ use futures::future::Fuse;
 use futures::{FutureExt, StreamExt};
 use libp2p::autonat::Event as AutonatEvent;
 use libp2p::core::{address_translation, ConnectedPoint};
-use libp2p::gossipsub::{Event as GossipsubEvent, TopicHash};
+use libp2p::gossipsub::{
+    Event as GossipsubEvent, Message as GossipsubMessage, MessageAcceptance, TopicHash,
+};
 use libp2p::identify::Event as IdentifyEvent;
 use libp2p::kad::{
     BootstrapOk, GetClosestPeersError, GetClosestPeersOk, GetProvidersError, GetProvidersOk,
@@ -55,6 +59,9 @@ use tracing::{debug, error, info, trace, warn};
 
 // Defines a batch size for peer addresses from Kademlia buckets.
 const KADEMLIA_PEERS_ADDRESSES_BATCH_SIZE: usize = 20;
+/// How many peers are tracked by rate limiter of each gossip topic.
+const GOSSIP_RATE_LIMITER_PEERS_CAPACITY: NonZeroUsize =
+    NonZeroUsize::new(1_000).expect("Not zero; qed");
 
 /// How many peers should node be connected to before boosting turns on.
 ///
@@ -88,6 +95,12 @@ enum QueryResultSender {
     },
 }
 
+/// Validation and rate limiting state of a gossip topic.
+struct GossipTopicState {
+    validator: GossipMessageValidator,
+    rate_limiter: RateLimiter<PeerId>,
+}
+
 #[derive(Debug, Default)]
 enum BootstrapCommandState {
     #[default]
@@ -116,6 +129,8 @@ where
     /// Topic subscription senders for logical subscriptions (multiple logical subscriptions can be
     /// present for the same physical subscription).
     topic_subscription_senders: HashMap<TopicHash, IntMap<usize, mpsc::UnboundedSender<Bytes>>>,
+    /// Validation and rate limiting state of gossip topics.
+    gossip_topics: HashMap<TopicHash, GossipTopicState>,
     random_query_timeout: Pin<Box<Fuse<Sleep>>>,
     /// Defines an interval between periodical tasks.
     periodical_tasks_interval: Pin<Box<Fuse<Sleep>>>,
@@ -143,6 +158,8 @@ where
     bootstrap_command_state: Arc<AsyncMutex<BootstrapCommandState>>,
     /// Kademlia mode. None means "automatic mode".
     kademlia_mode: Option<Mode>,
+    /// Transports enabled for the swarm.
+    transports: TransportConfig,
     /// Known external addresses to the local peer. The addresses are added on the swarm start
     /// and enable peer to notify others about its reachable address.
     external_addresses: Vec<Multiaddr>,
@@ -168,10 +185,12 @@ where
     pub(crate) temporary_bans: Arc<Mutex<TemporaryBans>>,
     pub(crate) metrics: Option<Metrics>,
     pub(crate) protocol_version: String,
+    pub(crate) gossip_topics: Vec<GossipTopicConfig>,
     pub(crate) general_connection_decision_handler: Option<ConnectedPeersHandler>,
     pub(crate) special_connection_decision_handler: Option<ConnectedPeersHandler>,
     pub(crate) bootstrap_addresses: Vec<Multiaddr>,
     pub(crate) kademlia_mode: Option<Mode>,
+    pub(crate) transports: TransportConfig,
     pub(crate) external_addresses: Vec<Multiaddr>,
 }
 
@@ -191,10 +210,12 @@ where
             temporary_bans,
             metrics,
             protocol_version,
+            gossip_topics,
             general_connection_decision_handler,
             special_connection_decision_handler,
             bootstrap_addresses,
             kademlia_mode,
+            transports,
             external_addresses,
         }: NodeRunnerConfig<LocalRecordProvider>,
     ) -> Self {
@@ -211,6 +232,29 @@ where
             address_removal_task_handler_id.replace(handler_id);
         }
 
+        let gossip_topics = gossip_topics
+            .into_iter()
+            .map(|gossip_topic_config| {
+                let GossipTopicConfig {
+                    topic,
+                    validator,
+                    max_messages_per_peer,
+                    rate_limit_interval,
+                } = gossip_topic_config;
+
+                let state = GossipTopicState {
+                    validator,
+                    rate_limiter: RateLimiter::new(
+                        max_messages_per_peer,
+                        rate_limit_interval,
+                        GOSSIP_RATE_LIMITER_PEERS_CAPACITY,
+                    ),
+                };
+
+                (topic.hash(), state)
+            })
+            .collect();
+
         Self {
             allow_non_global_addresses_in_dht,
             command_receiver,
@@ -220,6 +264,7 @@ where
             query_id_receivers: HashMap::default(),
             next_subscription_id: 0,
             topic_subscription_senders: HashMap::default(),
+            gossip_topics,
             // We'll make the first query right away and continue at the interval.
             random_query_timeout: Box::pin(tokio::time::sleep(Duration::from_secs(0)).fuse()),
             // We'll make the first dial right away and continue at the interval.
@@ -236,6 +281,7 @@ where
             bootstrap_addresses,
             bootstrap_command_state: Arc::new(AsyncMutex::new(BootstrapCommandState::default())),
             kademlia_mode,
+            transports,
             external_addresses,
             removed_addresses_rx,
             _address_removal_task_handler_id: address_removal_task_handler_id,
@@ -569,6 +615,7 @@ where
 
                 // No more connections
                 if num_established == 0 {
+                    shared.peer_infos.lock().remove(&peer_id);
                     shared.handlers.disconnected_peer.call_simple(&peer_id);
                 }
             }
@@ -602,6 +649,19 @@ where
 
                 match error {
                     DialError::Transport(ref addresses) => {
+                        if !addresses.is_empty()
+                            && addresses.iter().all(|(_, error)| {
+                                matches!(error, TransportError::MultiaddrNotSupported(_))
+                            })
+                        {
+                            warn!(
+                                ?peer_id,
+                                addresses = ?addresses.iter().map(|(address, _)| address).collect::<Vec<_>>(),
+                                "Can't connect to peer, none of its addresses is supported by \
+                                enabled transports"
+                            );
+                        }
+
                         for (addr, _) in addresses {
                             trace!(?error, ?peer_id, %addr, "SwarmEvent::OutgoingConnectionError (DialError::Transport) for peer.");
                             if let Some(peer_id) = peer_id {
@@ -1071,7 +1131,29 @@ where
     }
 
     async fn handle_gossipsub_event(&mut self, event: GossipsubEvent) {
-        if let GossipsubEvent::Message { message, .. } = event {
+        if let GossipsubEvent::Message {
+            propagation_source,
+            message_id,
+            message,
+        } = event
+        {
+            let acceptance = self.validate_gossip_message(&propagation_source, &message);
+            let accepted = matches!(acceptance, MessageAcceptance::Accept);
+
+            if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+                if let Err(error) = gossipsub.report_message_validation_result(
+                    &message_id,
+                    &propagation_source,
+                    acceptance,
+                ) {
+                    debug!(%error, %message_id, "Failed to report gossip message validation result");
+                }
+            }
+
+            if !accepted {
+                return;
+            }
+
             if let Some(senders) = self.topic_subscription_senders.get(&message.topic) {
                 let bytes = Bytes::from(message.data);
 
@@ -1083,6 +1165,38 @@ where
         }
     }
 
+    fn validate_gossip_message(
+        &mut self,
+        propagation_source: &PeerId,
+        message: &GossipsubMessage,
+    ) -> MessageAcceptance {
+        let Some(gossip_topic) = self.gossip_topics.get_mut(&message.topic) else {
+            // No validation configured for this topic
+            return MessageAcceptance::Accept;
+        };
+
+        if !gossip_topic.rate_limiter.try_acquire(*propagation_source) {
+            debug!(
+                %propagation_source,
+                topic = %message.topic,
+                "Gossip message rate limit exceeded, ignoring message"
+            );
+            return MessageAcceptance::Ignore;
+        }
+
+        let acceptance = (gossip_topic.validator)(propagation_source, &message.data);
+
+        if matches!(acceptance, MessageAcceptance::Reject) {
+            debug!(
+                %propagation_source,
+                topic = %message.topic,
+                "Invalid gossip message received"
+            );
+        }
+
+        acceptance
+    }
+
     async fn handle_request_response_event(&mut self, event: RequestResponseEvent) {
         // No actions on statistics events.
         trace!("Request response event: {:?}", event);
@@ -1093,6 +1207,11 @@ where
 
         if let Ok(PeerInfoSuccess::Received(peer_info)) = event.result {
             if let Some(shared) = self.shared_weak.upgrade() {
+                shared
+                    .peer_infos
+                    .lock()
+                    .insert(event.peer_id, peer_info.clone());
+
                 let connected_peers = self.swarm.connected_peers().cloned().collect::<Vec<_>>();
 
                 shared.handlers.new_peer_info.call_simple(&NewPeerInfo {
@@ -1377,8 +1496,23 @@ where
             Command::BanPeer { peer_id } => {
                 self.ban_peer(peer_id);
             }
-            Command::Dial { address } => {
-                let _ = self.swarm.dial(address);
+            Command::Dial {
+                address,
+                result_sender,
+            } => {
+                let result = if self.transports.supports(&address) {
+                    self.swarm.dial(address).map_err(|error| {
+                        debug!(%error, "Failed to dial address");
+
+                        error.into()
+                    })
+                } else {
+                    debug!(%address, "Address is not supported by any of the enabled transports");
+
+                    Err(crate::node::DialError::UnsupportedAddress(address))
+                };
+
+                let _ = result_sender.send(result);
             }
             Command::ConnectedPeers { result_sender } => {
                 let connected_peers = self.swarm.connected_peers().cloned().collect();
@@ -1,10 +1,14 @@
 mod handler;
 mod protocol;
+#[cfg(test)]
+mod tests;
 
 use crate::protocols::peer_info::handler::HandlerInEvent;
 use handler::Handler;
 pub use handler::{Config, PeerInfoError, PeerInfoSuccess};
 use libp2p::core::{Endpoint, Multiaddr};
+use libp2p::kad::record::Key;
+use libp2p::kad::KBucketKey;
 use libp2p::swarm::behaviour::{ConnectionEstablished, FromSwarm};
 use libp2p::swarm::{
     ConnectionClosed, ConnectionDenied, ConnectionId, NetworkBehaviour, NotifyHandler,
@@ -14,6 +18,7 @@ use libp2p::PeerId;
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
 use std::collections::{HashSet, VecDeque};
+use std::fmt;
 use std::fmt::Debug;
 use std::sync::Arc;
 use std::task::{Context, Poll, Waker};
@@ -38,8 +43,11 @@ pub struct PlaceHolder {
 }
 
 #[derive(Clone, Encode, Decode, Default, Debug)]
-/// Peer info data
-pub enum PeerInfo {
+/// Role of the peer in the network.
+///
+/// This is also the complete peer info data of the legacy version of the protocol, hence encoding
+/// must not be changed.
+pub enum PeerRole {
     /// DSN farmer.
     Farmer {
         /// Backward compatibility placeholder.
@@ -54,10 +62,97 @@ pub enum PeerInfo {
     Client,
 }
 
+/// Request-response protocol supported by the peer.
+#[derive(Clone, Encode, Decode, Debug, PartialEq, Eq)]
+pub struct ProtocolCapability {
+    /// Protocol name, includes protocol version.
+    pub name: String,
+    /// Max number of items that can be requested in a single request.
+    pub max_batch_size: u32,
+}
+
+/// Piece cache of the peer.
+#[derive(Clone, Copy, Encode, Decode, Default, Debug, PartialEq, Eq)]
+pub struct PieceCacheCapability {
+    /// Approximate DHT key range of the cached pieces, expressed as `ilog2` of the max Kademlia
+    /// distance between peer ID and keys of cached pieces. `None` means the cache is not full yet
+    /// and may contain pieces with any keys.
+    pub max_distance_ilog2: Option<u32>,
+}
+
+impl PieceCacheCapability {
+    /// Whether piece cache of the peer with provided ID may contain a record with provided key.
+    pub fn may_contain_key(&self, peer_id: PeerId, key: &Key) -> bool {
+        let Some(max_distance_ilog2) = self.max_distance_ilog2 else {
+            return true;
+        };
+
+        KBucketKey::from(peer_id)
+            .distance(&KBucketKey::new(key.clone()))
+            .ilog2()
+            .map(|distance_ilog2| distance_ilog2 <= max_distance_ilog2)
+            .unwrap_or(true)
+    }
+}
+
+/// What the peer can serve to other peers.
+#[derive(Clone, Encode, Decode, Default, Debug, PartialEq, Eq)]
+pub struct PeerCapabilities {
+    /// Supported request-response protocols.
+    pub protocols: Vec<ProtocolCapability>,
+    /// Piece cache, `None` if the peer doesn't run piece cache.
+    pub piece_cache: Option<PieceCacheCapability>,
+}
+
+#[derive(Clone, Encode, Decode, Default, Debug)]
+/// Peer info data
+pub struct PeerInfo {
+    /// Role of the peer.
+    pub role: PeerRole,
+    /// Capabilities of the peer, `None` if the peer only supports legacy version of the protocol
+    /// and capabilities are not known.
+    pub capabilities: Option<PeerCapabilities>,
+}
+
 impl PeerInfo {
     /// Returns whether [`PeerInfo`] is a Farmer.
     pub fn is_farmer(peer_info: &PeerInfo) -> bool {
-        matches!(peer_info, Self::Farmer { .. })
+        matches!(peer_info.role, PeerRole::Farmer { .. })
+    }
+
+    /// Whether the peer supports request-response protocol with provided name.
+    ///
+    /// Peers with unknown capabilities are assumed to support it.
+    pub fn supports_protocol(&self, protocol_name: &str) -> bool {
+        self.protocol(protocol_name).is_some() || self.capabilities.is_none()
+    }
+
+    /// Max batch size of the request-response protocol with provided name, `None` if protocol is
+    /// not supported or peer capabilities are not known.
+    pub fn max_batch_size(&self, protocol_name: &str) -> Option<u32> {
+        self.protocol(protocol_name)
+            .map(|protocol| protocol.max_batch_size)
+    }
+
+    /// Whether piece cache of the peer with provided ID may contain a record with provided key.
+    ///
+    /// Peers with unknown capabilities are assumed to be able to contain any key.
+    pub fn may_have_cached_key(&self, peer_id: PeerId, key: &Key) -> bool {
+        match &self.capabilities {
+            Some(capabilities) => capabilities
+                .piece_cache
+                .map(|piece_cache| piece_cache.may_contain_key(peer_id, key))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+
+    fn protocol(&self, protocol_name: &str) -> Option<&ProtocolCapability> {
+        self.capabilities
+            .as_ref()?
+            .protocols
+            .iter()
+            .find(|protocol| protocol.name == protocol_name)
     }
 }
 
@@ -85,46 +180,79 @@ struct Request {
     peer_info: Arc<PeerInfo>,
 }
 
-/// Handles constant peer info data.
-#[derive(Debug)]
-pub enum PeerInfoProvider {
-    /// Provides peer-info for Node peer type.
-    Node,
-    /// Provides peer-info for Boostrap Node peer type.
-    BootstrapNode,
-    /// Provides peer-info for Client peer type.
-    Client,
-    /// Provides peer-info for Farmer peer type.
-    Farmer,
+/// Provides up-to-date piece cache capability of the local peer.
+pub type PieceCacheCapabilityProvider =
+    Arc<dyn Fn() -> PieceCacheCapability + Send + Sync + 'static>;
+
+/// Provides peer info data of the local peer.
+#[derive(Clone)]
+pub struct PeerInfoProvider {
+    role: PeerRole,
+    protocols: Vec<ProtocolCapability>,
+    piece_cache: Option<PieceCacheCapabilityProvider>,
+}
+
+impl Debug for PeerInfoProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeerInfoProvider")
+            .field("role", &self.role)
+            .field("protocols", &self.protocols)
+            .field(
+                "piece_cache",
+                &self.piece_cache.as_ref().map(|provider| provider()),
+            )
+            .finish()
+    }
 }
 
 impl PeerInfoProvider {
     /// Creates a new Node peer-info provider.
     pub fn new_node() -> Self {
-        Self::Node
+        Self::new(PeerRole::Node)
     }
     /// Creates a new Bootstrap Node peer-info provider.
     pub fn new_bootstrap_node() -> Self {
-        Self::BootstrapNode
+        Self::new(PeerRole::BootstrapNode)
     }
     /// Creates a new Client peer-info provider.
     pub fn new_client() -> Self {
-        Self::Client
+        Self::new(PeerRole::Client)
     }
     /// Creates a new Farmer peer-info provider.
     pub fn new_farmer() -> Self {
-        Self::Farmer
+        Self::new(PeerRole::Farmer {
+            placeholder: Default::default(),
+        })
+    }
+
+    fn new(role: PeerRole) -> Self {
+        Self {
+            role,
+            protocols: Vec::new(),
+            piece_cache: None,
+        }
+    }
+
+    /// Advertise piece cache with provided capability.
+    pub fn with_piece_cache(mut self, piece_cache: PieceCacheCapabilityProvider) -> Self {
+        self.piece_cache.replace(piece_cache);
+        self
+    }
+
+    /// Set supported request-response protocols, done automatically during networking stack
+    /// construction.
+    pub(crate) fn set_protocols(&mut self, protocols: Vec<ProtocolCapability>) {
+        self.protocols = protocols;
     }
 
     /// Returns the peer info data.
     pub fn peer_info(&self) -> PeerInfo {
-        match self {
-            PeerInfoProvider::Node => PeerInfo::Node,
-            PeerInfoProvider::BootstrapNode => PeerInfo::BootstrapNode,
-            PeerInfoProvider::Client => PeerInfo::Client,
-            PeerInfoProvider::Farmer => PeerInfo::Farmer {
-                placeholder: Default::default(),
-            },
+        PeerInfo {
+            role: self.role.clone(),
+            capabilities: Some(PeerCapabilities {
+                protocols: self.protocols.clone(),
+                piece_cache: self.piece_cache.as_ref().map(|provider| provider()),
+            }),
         }
     }
 }
@@ -1,7 +1,7 @@
+use crate::protocols::peer_info::protocol::{ProtocolVersion, Upgrade};
 use crate::protocols::peer_info::{protocol, PeerInfo};
 use futures::future::BoxFuture;
 use futures::prelude::*;
-use libp2p::core::upgrade::ReadyUpgrade;
 use libp2p::swarm::handler::{
     ConnectionEvent, DialUpgradeError, FullyNegotiatedInbound, FullyNegotiatedOutbound,
     ListenUpgradeError, StreamUpgradeError as ConnectionHandlerUpgrErr,
@@ -10,7 +10,6 @@ use libp2p::swarm::{
     ConnectionHandler, ConnectionHandlerEvent, KeepAlive, Stream as NegotiatedSubstream,
     SubstreamProtocol,
 };
-use libp2p::StreamProtocol;
 use std::error::Error;
 use std::io;
 use std::sync::Arc;
@@ -26,6 +25,9 @@ pub struct Config {
 
     /// Protocol name.
     protocol_name: &'static str,
+
+    /// Protocol name of the legacy version of the protocol that doesn't support capabilities.
+    legacy_protocol_name: Option<&'static str>,
 }
 
 impl Config {
@@ -36,14 +38,25 @@ impl Config {
         Self {
             timeout: Duration::from_secs(20),
             protocol_name,
+            legacy_protocol_name: None,
         }
     }
 
+    /// Additionally support legacy version of the protocol for compatibility with older peers.
+    pub fn with_legacy_protocol_name(mut self, legacy_protocol_name: &'static str) -> Self {
+        self.legacy_protocol_name.replace(legacy_protocol_name);
+        self
+    }
+
     /// Sets the protocol timeout.
     pub fn with_timeout(mut self, d: Duration) -> Self {
         self.timeout = d;
         self
     }
+
+    fn upgrade(&self) -> Upgrade {
+        Upgrade::new(self.protocol_name, self.legacy_protocol_name)
+    }
 }
 
 /// The successful result of processing an inbound or outbound peer info requests.
@@ -115,22 +128,21 @@ impl ConnectionHandler for Handler {
     type FromBehaviour = HandlerInEvent;
     type ToBehaviour = Result<PeerInfoSuccess, PeerInfoError>;
     type Error = PeerInfoError;
-    type InboundProtocol = ReadyUpgrade<StreamProtocol>;
-    type OutboundProtocol = ReadyUpgrade<StreamProtocol>;
+    type InboundProtocol = Upgrade;
+    type OutboundProtocol = Upgrade;
     type OutboundOpenInfo = Arc<PeerInfo>;
     type InboundOpenInfo = ();
 
-    fn listen_protocol(&self) -> SubstreamProtocol<ReadyUpgrade<StreamProtocol>, ()> {
-        SubstreamProtocol::new(
-            ReadyUpgrade::new(StreamProtocol::new(self.config.protocol_name)),
-            (),
-        )
+    fn listen_protocol(&self) -> SubstreamProtocol<Upgrade, ()> {
+        SubstreamProtocol::new(self.config.upgrade(), ())
     }
 
     fn on_behaviour_event(&mut self, event: Self::FromBehaviour) {
-        if let Some(OutboundState::Idle(stream)) = self.outbound.take() {
+        if let Some(OutboundState::Idle(stream, version)) = self.outbound.take() {
             self.outbound = Some(OutboundState::SendingData(
-                protocol::send(stream, event.peer_info).boxed(),
+                protocol::send(stream, event.peer_info, version)
+                    .map_ok(move |stream| (stream, version))
+                    .boxed(),
             ));
         } else {
             self.outbound = Some(OutboundState::RequestNewStream(event.peer_info));
@@ -147,7 +159,7 @@ impl ConnectionHandler for Handler {
         cx: &mut Context<'_>,
     ) -> Poll<
         ConnectionHandlerEvent<
-            ReadyUpgrade<StreamProtocol>,
+            Upgrade,
             Self::OutboundOpenInfo,
             Result<PeerInfoSuccess, PeerInfoError>,
             Self::Error,
@@ -168,10 +180,10 @@ impl ConnectionHandler for Handler {
                         error: Box::new(err),
                     }));
                 }
-                Poll::Ready(Ok((stream, peer_info))) => {
-                    debug!(?peer_info, "Inbound peer info");
+                Poll::Ready(Ok((stream, version, peer_info))) => {
+                    debug!(?peer_info, ?version, "Inbound peer info");
 
-                    self.inbound = Some(protocol::recv(stream).boxed());
+                    self.inbound = Some(recv(stream, version));
                     return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(Ok(
                         PeerInfoSuccess::Received(peer_info),
                     )));
@@ -186,8 +198,8 @@ impl ConnectionHandler for Handler {
                     Poll::Pending => {
                         self.outbound = Some(OutboundState::SendingData(peer_info_fut));
                     }
-                    Poll::Ready(Ok(stream)) => {
-                        self.outbound = Some(OutboundState::Idle(stream));
+                    Poll::Ready(Ok((stream, version))) => {
+                        self.outbound = Some(OutboundState::Idle(stream, version));
 
                         return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(Ok(
                             PeerInfoSuccess::Sent,
@@ -202,20 +214,17 @@ impl ConnectionHandler for Handler {
                     }
                 }
             }
-            Some(OutboundState::Idle(stream)) => {
+            Some(OutboundState::Idle(stream, version)) => {
                 // Nothing to do but we have a negotiated stream.
-                self.outbound = Some(OutboundState::Idle(stream));
+                self.outbound = Some(OutboundState::Idle(stream, version));
             }
             Some(OutboundState::NegotiatingStream) => {
                 self.outbound = Some(OutboundState::NegotiatingStream);
             }
             Some(OutboundState::RequestNewStream(peer_info)) => {
                 self.outbound = Some(OutboundState::NegotiatingStream);
-                let protocol = SubstreamProtocol::new(
-                    ReadyUpgrade::new(StreamProtocol::new(self.config.protocol_name)),
-                    peer_info,
-                )
-                .with_timeout(self.config.timeout);
+                let protocol = SubstreamProtocol::new(self.config.upgrade(), peer_info)
+                    .with_timeout(self.config.timeout);
                 return Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest { protocol });
             }
             None => {
@@ -238,17 +247,19 @@ impl ConnectionHandler for Handler {
     ) {
         match event {
             ConnectionEvent::FullyNegotiatedInbound(FullyNegotiatedInbound {
-                protocol: stream,
+                protocol: (stream, version),
                 ..
             }) => {
-                self.inbound = Some(protocol::recv(stream).boxed());
+                self.inbound = Some(recv(stream, version));
             }
             ConnectionEvent::FullyNegotiatedOutbound(FullyNegotiatedOutbound {
-                protocol: stream,
+                protocol: (stream, version),
                 info,
             }) => {
                 self.outbound = Some(OutboundState::SendingData(
-                    protocol::send(stream, info).boxed(),
+                    protocol::send(stream, info, version)
+                        .map_ok(move |stream| (stream, version))
+                        .boxed(),
                 ));
             }
             ConnectionEvent::DialUpgradeError(DialUpgradeError { error, .. }) => {
@@ -275,8 +286,16 @@ impl ConnectionHandler for Handler {
     }
 }
 
-type InPeerInfoFuture = BoxFuture<'static, Result<(NegotiatedSubstream, PeerInfo), io::Error>>;
-type OutPeerInfoFuture = BoxFuture<'static, Result<NegotiatedSubstream, io::Error>>;
+type InPeerInfoFuture =
+    BoxFuture<'static, Result<(NegotiatedSubstream, ProtocolVersion, PeerInfo), io::Error>>;
+type OutPeerInfoFuture =
+    BoxFuture<'static, Result<(NegotiatedSubstream, ProtocolVersion), io::Error>>;
+
+fn recv(stream: NegotiatedSubstream, version: ProtocolVersion) -> InPeerInfoFuture {
+    protocol::recv(stream, version)
+        .map_ok(move |(stream, peer_info)| (stream, version, peer_info))
+        .boxed()
+}
 
 /// The current state w.r.t. outbound peer info requests.
 enum OutboundState {
@@ -286,5 +305,5 @@ enum OutboundState {
     /// A peer info request is being sent and the response awaited.
     SendingData(OutPeerInfoFuture),
     /// The substream is idle, waiting to send the next peer info request.
-    Idle(NegotiatedSubstream),
+    Idle(NegotiatedSubstream, ProtocolVersion),
 }
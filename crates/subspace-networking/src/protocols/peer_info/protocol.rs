@@ -1,18 +1,121 @@
 //! This module defines low-level functions for working with inbound and outbound streams.
 
 use crate::protocols::peer_info::PeerInfo;
+use futures::future;
 use futures::prelude::*;
+use libp2p::core::upgrade::{InboundUpgrade, OutboundUpgrade, UpgradeInfo};
+use libp2p::StreamProtocol;
 use parity_scale_codec::{Decode, Encode};
-use std::io;
 use std::io::ErrorKind;
 use std::sync::Arc;
+use std::{io, vec};
+use void::Void;
+
+/// Version of the peer info protocol, defines encoding of the peer info on the wire.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProtocolVersion {
+    /// Legacy version, only peer role is sent.
+    V1,
+    /// Peer role is sent along with peer capabilities.
+    V2,
+}
+
+/// Protocol name along with its version.
+#[derive(Debug, Clone)]
+pub struct VersionedProtocol {
+    name: StreamProtocol,
+    version: ProtocolVersion,
+}
+
+impl AsRef<str> for VersionedProtocol {
+    fn as_ref(&self) -> &str {
+        self.name.as_ref()
+    }
+}
+
+/// Upgrade that negotiates the latest version of the peer info protocol supported by both peers.
+#[derive(Debug, Clone)]
+pub struct Upgrade {
+    protocols: Vec<VersionedProtocol>,
+}
+
+impl Upgrade {
+    /// Creates new upgrade, protocols are listed in the order of preference.
+    pub fn new(protocol_name: &'static str, legacy_protocol_name: Option<&'static str>) -> Self {
+        let protocols = [
+            Some(VersionedProtocol {
+                name: StreamProtocol::new(protocol_name),
+                version: ProtocolVersion::V2,
+            }),
+            legacy_protocol_name.map(|legacy_protocol_name| VersionedProtocol {
+                name: StreamProtocol::new(legacy_protocol_name),
+                version: ProtocolVersion::V1,
+            }),
+        ];
+
+        Self {
+            protocols: protocols.into_iter().flatten().collect(),
+        }
+    }
+}
+
+impl UpgradeInfo for Upgrade {
+    type Info = VersionedProtocol;
+    type InfoIter = vec::IntoIter<Self::Info>;
+
+    fn protocol_info(&self) -> Self::InfoIter {
+        self.protocols.clone().into_iter()
+    }
+}
+
+impl<S> InboundUpgrade<S> for Upgrade {
+    type Output = (S, ProtocolVersion);
+    type Error = Void;
+    type Future = future::Ready<Result<Self::Output, Self::Error>>;
+
+    fn upgrade_inbound(self, stream: S, info: Self::Info) -> Self::Future {
+        future::ready(Ok((stream, info.version)))
+    }
+}
+
+impl<S> OutboundUpgrade<S> for Upgrade {
+    type Output = (S, ProtocolVersion);
+    type Error = Void;
+    type Future = future::Ready<Result<Self::Output, Self::Error>>;
+
+    fn upgrade_outbound(self, stream: S, info: Self::Info) -> Self::Future {
+        future::ready(Ok((stream, info.version)))
+    }
+}
+
+/// Encode peer-info data according to the protocol version.
+pub fn encode(pi: &PeerInfo, version: ProtocolVersion) -> Vec<u8> {
+    match version {
+        ProtocolVersion::V1 => pi.role.encode(),
+        ProtocolVersion::V2 => pi.encode(),
+    }
+}
+
+/// Decode peer-info data according to the protocol version.
+pub fn decode(
+    mut data: &[u8],
+    version: ProtocolVersion,
+) -> Result<PeerInfo, parity_scale_codec::Error> {
+    match version {
+        ProtocolVersion::V1 => Ok(PeerInfo {
+            role: Decode::decode(&mut data)?,
+            capabilities: None,
+        }),
+        ProtocolVersion::V2 => PeerInfo::decode(&mut data),
+    }
+}
 
 /// Send peer-info data to a remote peer.
-pub async fn send<S>(mut stream: S, pi: Arc<PeerInfo>) -> io::Result<S>
+pub async fn send<S>(mut stream: S, pi: Arc<PeerInfo>, version: ProtocolVersion) -> io::Result<S>
 where
     S: AsyncRead + AsyncWrite + Unpin,
 {
-    let send_data = pi.encode();
+    let send_data = encode(&pi, version);
     let send_len_bytes = (send_data.len() as u32).to_le_bytes();
 
     stream.write_all(&send_len_bytes).await?;
@@ -23,7 +126,7 @@ where
 }
 
 /// Receive peer-info data from a remote peer.
-pub async fn recv<S>(mut stream: S) -> io::Result<(S, PeerInfo)>
+pub async fn recv<S>(mut stream: S, version: ProtocolVersion) -> io::Result<(S, PeerInfo)>
 where
     S: AsyncRead + AsyncWrite + Unpin,
 {
@@ -34,7 +137,7 @@ where
 
     stream.read_exact(&mut rec_data).await?;
     let received_peer_info =
-        PeerInfo::decode(&mut &*rec_data).map_err(|err| io::Error::new(ErrorKind::Other, err))?;
+        decode(&rec_data, version).map_err(|err| io::Error::new(ErrorKind::Other, err))?;
 
     Ok((stream, received_peer_info))
 }
@@ -0,0 +1,230 @@
+use crate::protocols::peer_info::protocol::{decode, encode, ProtocolVersion};
+use crate::protocols::peer_info::{
+    Behaviour, Config, Event, PeerCapabilities, PeerInfo, PeerInfoProvider, PeerInfoSuccess,
+    PeerRole, PieceCacheCapability, ProtocolCapability,
+};
+use futures::{select, FutureExt};
+use libp2p::core::transport::MemoryTransport;
+use libp2p::core::upgrade::Version;
+use libp2p::core::Transport;
+use libp2p::identity::{Keypair, PeerId};
+use libp2p::kad::record::Key;
+use libp2p::kad::KBucketKey;
+use libp2p::plaintext::PlainText2Config;
+use libp2p::swarm::{SwarmBuilder, SwarmEvent};
+use libp2p::{yamux, Swarm};
+use libp2p_swarm_test::SwarmExt;
+use parity_scale_codec::Encode;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+const PROTOCOL_NAME: &str = "/peer-info/2.0.0";
+const LEGACY_PROTOCOL_NAME: &str = "/peer-info/1.0.0";
+
+fn farmer_peer_info() -> PeerInfo {
+    PeerInfoProvider::new_farmer()
+        .with_piece_cache(Arc::new(|| PieceCacheCapability {
+            max_distance_ilog2: Some(250),
+        }))
+        .peer_info()
+}
+
+#[test]
+fn legacy_encoding_compatibility() {
+    let legacy_encoding = PeerRole::Node.encode();
+
+    let peer_info = decode(&legacy_encoding, ProtocolVersion::V1).unwrap();
+    assert!(matches!(peer_info.role, PeerRole::Node));
+    assert!(peer_info.capabilities.is_none());
+
+    let peer_info = farmer_peer_info();
+    let decoded_peer_info = decode(
+        &encode(&peer_info, ProtocolVersion::V1),
+        ProtocolVersion::V1,
+    )
+    .unwrap();
+    assert!(PeerInfo::is_farmer(&decoded_peer_info));
+    assert!(decoded_peer_info.capabilities.is_none());
+
+    let decoded_peer_info = decode(
+        &encode(&peer_info, ProtocolVersion::V2),
+        ProtocolVersion::V2,
+    )
+    .unwrap();
+    assert!(PeerInfo::is_farmer(&decoded_peer_info));
+    assert_eq!(decoded_peer_info.capabilities, peer_info.capabilities);
+}
+
+#[test]
+fn protocol_capabilities() {
+    let peer_info = PeerInfo {
+        role: PeerRole::Node,
+        capabilities: Some(PeerCapabilities {
+            protocols: vec![ProtocolCapability {
+                name: "/test/1.0.0".to_string(),
+                max_batch_size: 10,
+            }],
+            piece_cache: None,
+        }),
+    };
+
+    assert!(peer_info.supports_protocol("/test/1.0.0"));
+    assert!(!peer_info.supports_protocol("/test/2.0.0"));
+    assert_eq!(peer_info.max_batch_size("/test/1.0.0"), Some(10));
+    assert_eq!(peer_info.max_batch_size("/test/2.0.0"), None);
+
+    // Capabilities of legacy peers are not known
+    let legacy_peer_info = PeerInfo::default();
+    assert!(legacy_peer_info.supports_protocol("/test/2.0.0"));
+    assert_eq!(legacy_peer_info.max_batch_size("/test/2.0.0"), None);
+}
+
+#[test]
+fn piece_cache_key_range() {
+    let peer_id = PeerId::random();
+    let keys = (0..100u32)
+        .map(|index| Key::new(&index.to_le_bytes()))
+        .collect::<Vec<_>>();
+    let distance_ilog2 = |key: &Key| {
+        KBucketKey::from(peer_id)
+            .distance(&KBucketKey::new(key.clone()))
+            .ilog2()
+            .unwrap()
+    };
+    let max_distance_ilog2 = keys.iter().map(distance_ilog2).min().unwrap();
+
+    let not_full_cache = PieceCacheCapability {
+        max_distance_ilog2: None,
+    };
+    let full_cache = PieceCacheCapability {
+        max_distance_ilog2: Some(max_distance_ilog2),
+    };
+
+    for key in &keys {
+        assert!(not_full_cache.may_contain_key(peer_id, key));
+        assert_eq!(
+            full_cache.may_contain_key(peer_id, key),
+            distance_ilog2(key) <= max_distance_ilog2
+        );
+    }
+
+    let peer_info = PeerInfo {
+        role: PeerRole::Node,
+        capabilities: Some(PeerCapabilities::default()),
+    };
+    assert!(!peer_info.may_have_cached_key(peer_id, &keys[0]));
+    assert!(PeerInfo::default().may_have_cached_key(peer_id, &keys[0]));
+    assert!(farmer_peer_info().may_have_cached_key(peer_id, &keys[0]));
+}
+
+#[tokio::test()]
+async fn legacy_peer_compatibility() {
+    let mut peer1 = new_ephemeral(Behaviour::new(
+        Config::new(PROTOCOL_NAME).with_legacy_protocol_name(LEGACY_PROTOCOL_NAME),
+        PeerInfoProvider::new_farmer(),
+    ));
+    let mut peer2 = new_ephemeral(Behaviour::new(
+        Config::new(LEGACY_PROTOCOL_NAME),
+        PeerInfoProvider::new_node(),
+    ));
+
+    peer1.listen().await;
+    peer2.listen().await;
+    peer1.connect(&mut peer2).await;
+
+    let mut peer1_received = None;
+    let mut peer2_received = None;
+
+    while peer1_received.is_none() || peer2_received.is_none() {
+        select! {
+            event = peer1.next_swarm_event().fuse() => {
+                if let SwarmEvent::Behaviour(Event {
+                    result: Ok(PeerInfoSuccess::Received(peer_info)),
+                    ..
+                }) = event {
+                    peer1_received.replace(peer_info);
+                }
+            },
+            event = peer2.next_swarm_event().fuse() => {
+                if let SwarmEvent::Behaviour(Event {
+                    result: Ok(PeerInfoSuccess::Received(peer_info)),
+                    ..
+                }) = event {
+                    peer2_received.replace(peer_info);
+                }
+            },
+            _ = sleep(Duration::from_secs(5)).fuse() => {
+                panic!("Peer info was not exchanged");
+            }
+        }
+    }
+
+    // Legacy version of the protocol was negotiated, hence no capabilities
+    let peer1_received = peer1_received.unwrap();
+    assert!(matches!(peer1_received.role, PeerRole::Node));
+    assert!(peer1_received.capabilities.is_none());
+
+    let peer2_received = peer2_received.unwrap();
+    assert!(PeerInfo::is_farmer(&peer2_received));
+    assert!(peer2_received.capabilities.is_none());
+}
+
+#[tokio::test()]
+async fn capabilities_exchange() {
+    let mut peer1 = new_ephemeral(Behaviour::new(
+        Config::new(PROTOCOL_NAME).with_legacy_protocol_name(LEGACY_PROTOCOL_NAME),
+        PeerInfoProvider::new_farmer().with_piece_cache(Arc::new(PieceCacheCapability::default)),
+    ));
+    let mut peer2 = new_ephemeral(Behaviour::new(
+        Config::new(PROTOCOL_NAME).with_legacy_protocol_name(LEGACY_PROTOCOL_NAME),
+        PeerInfoProvider::new_node(),
+    ));
+
+    peer1.listen().await;
+    peer2.listen().await;
+    peer1.connect(&mut peer2).await;
+
+    let peer_info = loop {
+        select! {
+            _ = peer1.next_swarm_event().fuse() => {},
+            event = peer2.next_swarm_event().fuse() => {
+                if let SwarmEvent::Behaviour(Event {
+                    result: Ok(PeerInfoSuccess::Received(peer_info)),
+                    ..
+                }) = event {
+                    break peer_info;
+                }
+            },
+            _ = sleep(Duration::from_secs(5)).fuse() => {
+                panic!("Peer info was not received");
+            }
+        }
+    };
+
+    assert!(PeerInfo::is_farmer(&peer_info));
+    assert_eq!(
+        peer_info.capabilities,
+        Some(PeerCapabilities {
+            protocols: Vec::new(),
+            piece_cache: Some(PieceCacheCapability::default()),
+        })
+    );
+}
+
+fn new_ephemeral(behaviour: Behaviour) -> Swarm<Behaviour> {
+    let identity = Keypair::generate_ed25519();
+    let peer_id = PeerId::from(identity.public());
+
+    let transport = MemoryTransport::default()
+        .or_transport(libp2p::tcp::tokio::Transport::default())
+        .upgrade(Version::V1)
+        .authenticate(PlainText2Config {
+            local_public_key: identity.public(),
+        })
+        .multiplex(yamux::Config::default())
+        .timeout(Duration::from_secs(5))
+        .boxed();
+
+    SwarmBuilder::without_executor(transport, behaviour, peer_id).build()
+}
@@ -1,2 +1,3 @@
+pub(crate) mod bandwidth;
 pub(crate) mod handlers;
 pub(crate) mod request_response_factory;
@@ -0,0 +1,362 @@
+//! Bandwidth accounting, limiting and fair queuing for request-response protocols.
+
+#[cfg(test)]
+mod tests;
+
+use libp2p::PeerId;
+use lru::LruCache;
+use parking_lot::Mutex;
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::registry::Registry;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How many peers per-peer bandwidth usage is tracked for.
+const PEER_BANDWIDTH_USAGE_CAPACITY: NonZeroUsize = NonZeroUsize::new(1000).expect("Not zero; qed");
+
+/// Priority of the traffic of request-response protocol.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum RequestPriority {
+    /// Regular traffic, subject to bandwidth limits.
+    #[default]
+    Normal,
+    /// Consensus-critical traffic (like segment headers) that is never queued behind normal
+    /// traffic, it still counts towards bandwidth limits though.
+    High,
+}
+
+/// Bandwidth limits and per-peer request quotas for request-response protocols.
+#[derive(Debug, Clone)]
+pub struct BandwidthLimits {
+    /// Max number of bytes per second sent in responses to other peers, `None` means unlimited.
+    pub upload_bytes_per_second: Option<NonZeroU32>,
+    /// Max number of bytes per second received in responses from other peers, `None` means
+    /// unlimited.
+    ///
+    /// Size of the response is not known in advance, so this limit is enforced by delaying new
+    /// outgoing requests until previously received responses are "paid off".
+    pub download_bytes_per_second: Option<NonZeroU32>,
+    /// Max number of incoming requests from a single peer per protocol within
+    /// [`Self::requests_per_peer_interval`], `None` means unlimited.
+    pub max_requests_per_peer: Option<NonZeroU32>,
+    /// Interval for [`Self::max_requests_per_peer`].
+    pub requests_per_peer_interval: Duration,
+}
+
+impl Default for BandwidthLimits {
+    fn default() -> Self {
+        Self {
+            upload_bytes_per_second: None,
+            download_bytes_per_second: None,
+            max_requests_per_peer: None,
+            requests_per_peer_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Bandwidth-related configuration of request-response protocols.
+#[derive(Debug, Default)]
+pub(crate) struct BandwidthConfig {
+    pub(crate) limits: BandwidthLimits,
+    pub(crate) metrics: Option<BandwidthMetrics>,
+    pub(crate) peer_usage: PeerBandwidthUsage,
+}
+
+/// Direction of the traffic.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, EncodeLabelValue)]
+pub(crate) enum Direction {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct BandwidthLabels {
+    protocol: String,
+    direction: Direction,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ProtocolLabels {
+    protocol: String,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct PeerLabels {
+    peer_id: String,
+    direction: Direction,
+}
+
+/// Prometheus metrics for request-response protocols.
+///
+/// Per-peer metrics are only exported for peers whose bandwidth usage is tracked, metrics of a
+/// peer are removed once it is no longer tracked.
+#[derive(Debug, Clone)]
+pub struct BandwidthMetrics {
+    bytes: Family<BandwidthLabels, Counter>,
+    peer_bytes: Family<PeerLabels, Counter>,
+    rejected_requests: Family<ProtocolLabels, Counter>,
+}
+
+impl BandwidthMetrics {
+    /// Create new instance and register metrics in provided registry.
+    pub fn new(registry: &mut Registry) -> Self {
+        let sub_registry = registry.sub_registry_with_prefix("request_response");
+
+        let bytes = Family::default();
+        sub_registry.register(
+            "bytes",
+            "Bytes sent and received in requests and responses",
+            bytes.clone(),
+        );
+
+        let peer_bytes = Family::default();
+        sub_registry.register(
+            "peer_bytes",
+            "Bytes sent to and received from individual peers in requests and responses",
+            peer_bytes.clone(),
+        );
+
+        let rejected_requests = Family::default();
+        sub_registry.register(
+            "rejected_requests",
+            "Incoming requests rejected due to exceeded per-peer quota",
+            rejected_requests.clone(),
+        );
+
+        Self {
+            bytes,
+            peer_bytes,
+            rejected_requests,
+        }
+    }
+}
+
+/// Bytes transferred over request-response protocols.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct BandwidthUsage {
+    /// Bytes received in requests and responses.
+    pub inbound_bytes: u64,
+    /// Bytes sent in requests and responses.
+    pub outbound_bytes: u64,
+}
+
+/// Per-peer bandwidth usage, shared between node runner and node.
+#[derive(Debug, Clone)]
+pub(crate) struct PeerBandwidthUsage {
+    inner: Arc<Mutex<LruCache<PeerId, BandwidthUsage>>>,
+}
+
+impl Default for PeerBandwidthUsage {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LruCache::new(PEER_BANDWIDTH_USAGE_CAPACITY))),
+        }
+    }
+}
+
+impl PeerBandwidthUsage {
+    pub(crate) fn get(&self, peer_id: &PeerId) -> Option<BandwidthUsage> {
+        self.inner.lock().peek(peer_id).copied()
+    }
+
+    /// Record bytes transferred with the peer, returns peer that is no longer tracked as the
+    /// result.
+    fn record(&self, peer_id: PeerId, direction: Direction, bytes: usize) -> Option<PeerId> {
+        let mut inner = self.inner.lock();
+        let evicted_peer_id = if inner.contains(&peer_id) {
+            None
+        } else {
+            inner
+                .push(peer_id, BandwidthUsage::default())
+                .map(|(evicted_peer_id, _usage)| evicted_peer_id)
+        };
+        let usage = inner
+            .get_mut(&peer_id)
+            .expect("Inserted above if missing and capacity is not zero; qed");
+        match direction {
+            Direction::Inbound => {
+                usage.inbound_bytes += bytes as u64;
+            }
+            Direction::Outbound => {
+                usage.outbound_bytes += bytes as u64;
+            }
+        }
+
+        evicted_peer_id
+    }
+}
+
+/// Records traffic of request-response protocols per protocol (in metrics) and per peer.
+#[derive(Debug, Default)]
+pub(crate) struct BandwidthAccounting {
+    pub(crate) metrics: Option<BandwidthMetrics>,
+    pub(crate) peer_usage: PeerBandwidthUsage,
+}
+
+impl BandwidthAccounting {
+    pub(crate) fn record(
+        &self,
+        peer_id: PeerId,
+        protocol: &str,
+        direction: Direction,
+        bytes: usize,
+    ) {
+        let evicted_peer_id = self.peer_usage.record(peer_id, direction, bytes);
+
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .bytes
+                .get_or_create(&BandwidthLabels {
+                    protocol: protocol.to_string(),
+                    direction,
+                })
+                .inc_by(bytes as u64);
+            metrics
+                .peer_bytes
+                .get_or_create(&PeerLabels {
+                    peer_id: peer_id.to_string(),
+                    direction,
+                })
+                .inc_by(bytes as u64);
+
+            if let Some(evicted_peer_id) = evicted_peer_id {
+                for direction in [Direction::Inbound, Direction::Outbound] {
+                    metrics.peer_bytes.remove(&PeerLabels {
+                        peer_id: evicted_peer_id.to_string(),
+                        direction,
+                    });
+                }
+            }
+        }
+    }
+
+    pub(crate) fn record_rejected_request(&self, protocol: &str) {
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .rejected_requests
+                .get_or_create(&ProtocolLabels {
+                    protocol: protocol.to_string(),
+                })
+                .inc();
+        }
+    }
+}
+
+/// Token bucket that allows bursts of up to one second worth of bytes.
+///
+/// Consuming more bytes than available is allowed and results in debt that needs to be paid off
+/// before capacity becomes available again, this way large messages are not starved by small
+/// ones.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    bytes_per_second: u64,
+    available: i64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(bytes_per_second: NonZeroU32) -> Self {
+        Self::new_at(bytes_per_second, Instant::now())
+    }
+
+    pub(crate) fn new_at(bytes_per_second: NonZeroU32, now: Instant) -> Self {
+        Self {
+            bytes_per_second: u64::from(bytes_per_second.get()),
+            available: i64::from(bytes_per_second.get()),
+            updated_at: now,
+        }
+    }
+
+    /// Whether there is capacity for at least one more byte.
+    pub(crate) fn has_capacity(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.available > 0
+    }
+
+    /// Consume bytes regardless of available capacity.
+    pub(crate) fn consume(&mut self, bytes: usize, now: Instant) {
+        self.refill(now);
+        self.available = self
+            .available
+            .saturating_sub(i64::try_from(bytes).unwrap_or(i64::MAX));
+    }
+
+    /// How long to wait until there is capacity for at least one more byte.
+    pub(crate) fn time_until_capacity(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        if self.available > 0 {
+            return Duration::ZERO;
+        }
+
+        let missing_bytes = (1 - self.available) as u64;
+        Duration::from_nanos(missing_bytes.saturating_mul(1_000_000_000) / self.bytes_per_second)
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        let refill = elapsed.as_nanos() * u128::from(self.bytes_per_second) / 1_000_000_000;
+        if refill == 0 {
+            return;
+        }
+
+        let capacity = self.bytes_per_second as i64;
+        self.available = self
+            .available
+            .saturating_add(i64::try_from(refill).unwrap_or(i64::MAX))
+            .min(capacity);
+        self.updated_at = now;
+    }
+}
+
+/// Queue that yields items of different keys (typically peers) in round-robin fashion, so that a
+/// single key can't monopolize the queue.
+#[derive(Debug)]
+pub(crate) struct FairQueue<Key, Item> {
+    queues: HashMap<Key, VecDeque<Item>>,
+    order: VecDeque<Key>,
+}
+
+impl<Key, Item> Default for FairQueue<Key, Item> {
+    fn default() -> Self {
+        Self {
+            queues: HashMap::default(),
+            order: VecDeque::default(),
+        }
+    }
+}
+
+impl<Key, Item> FairQueue<Key, Item>
+where
+    Key: Hash + Eq + Clone,
+{
+    pub(crate) fn push(&mut self, key: Key, item: Item) {
+        let queue = self.queues.entry(key.clone()).or_default();
+        if queue.is_empty() {
+            self.order.push_back(key);
+        }
+        queue.push_back(item);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Item> {
+        let key = self.order.pop_front()?;
+        let queue = self.queues.get_mut(&key)?;
+        let item = queue.pop_front();
+
+        if queue.is_empty() {
+            self.queues.remove(&key);
+        } else {
+            self.order.push_back(key);
+        }
+
+        item
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
@@ -0,0 +1,125 @@
+use crate::protocols::request_response::bandwidth::{
+    BandwidthAccounting, BandwidthMetrics, BandwidthUsage, Direction, FairQueue,
+    PeerBandwidthUsage, TokenBucket, PEER_BANDWIDTH_USAGE_CAPACITY,
+};
+use libp2p::PeerId;
+use prometheus_client::encoding::text::encode;
+use prometheus_client::registry::Registry;
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+
+#[test]
+fn token_bucket() {
+    let now = Instant::now();
+    let mut token_bucket = TokenBucket::new_at(NonZeroU32::new(1000).unwrap(), now);
+
+    // Full second worth of bytes is available initially
+    assert!(token_bucket.has_capacity(now));
+    assert_eq!(token_bucket.time_until_capacity(now), Duration::ZERO);
+
+    // Going into debt is allowed
+    token_bucket.consume(1500, now);
+    assert!(!token_bucket.has_capacity(now));
+    assert_eq!(
+        token_bucket.time_until_capacity(now),
+        Duration::from_millis(501)
+    );
+
+    // Debt is paid off over time
+    let now = now + Duration::from_millis(250);
+    assert!(!token_bucket.has_capacity(now));
+    let now = now + Duration::from_millis(251);
+    assert!(token_bucket.has_capacity(now));
+
+    // Capacity doesn't accumulate beyond one second worth of bytes
+    let now = now + Duration::from_secs(10);
+    token_bucket.consume(1000, now);
+    assert!(!token_bucket.has_capacity(now));
+}
+
+#[test]
+fn fair_queue() {
+    let mut fair_queue = FairQueue::default();
+    assert!(fair_queue.is_empty());
+
+    fair_queue.push("a", 1);
+    fair_queue.push("a", 2);
+    fair_queue.push("a", 3);
+    fair_queue.push("b", 4);
+    fair_queue.push("c", 5);
+    fair_queue.push("b", 6);
+
+    // Keys are served in round-robin fashion, items of the same key in insertion order
+    assert_eq!(fair_queue.pop(), Some(1));
+    assert_eq!(fair_queue.pop(), Some(4));
+    assert_eq!(fair_queue.pop(), Some(5));
+    assert_eq!(fair_queue.pop(), Some(2));
+    assert_eq!(fair_queue.pop(), Some(6));
+
+    fair_queue.push("c", 7);
+
+    assert_eq!(fair_queue.pop(), Some(3));
+    assert_eq!(fair_queue.pop(), Some(7));
+    assert_eq!(fair_queue.pop(), None);
+    assert!(fair_queue.is_empty());
+}
+
+#[test]
+fn peer_bandwidth_usage() {
+    let accounting = BandwidthAccounting::default();
+    let peer_id_1 = PeerId::random();
+    let peer_id_2 = PeerId::random();
+
+    accounting.record(peer_id_1, "/test/1", Direction::Inbound, 10);
+    accounting.record(peer_id_1, "/test/2", Direction::Inbound, 20);
+    accounting.record(peer_id_1, "/test/1", Direction::Outbound, 100);
+
+    assert_eq!(
+        accounting.peer_usage.get(&peer_id_1),
+        Some(BandwidthUsage {
+            inbound_bytes: 30,
+            outbound_bytes: 100,
+        })
+    );
+    assert_eq!(accounting.peer_usage.get(&peer_id_2), None);
+}
+
+#[test]
+fn peer_bandwidth_metrics() {
+    let mut registry = Registry::default();
+    let accounting = BandwidthAccounting {
+        metrics: Some(BandwidthMetrics::new(&mut registry)),
+        peer_usage: PeerBandwidthUsage::default(),
+    };
+    let encoded_metrics = |registry: &Registry| {
+        let mut encoded = String::new();
+        encode(&mut encoded, registry).unwrap();
+        encoded
+    };
+
+    let first_peer_id = PeerId::random();
+    accounting.record(first_peer_id, "/test/1", Direction::Inbound, 10);
+    accounting.record(first_peer_id, "/test/2", Direction::Inbound, 20);
+    accounting.record(first_peer_id, "/test/1", Direction::Outbound, 100);
+
+    let encoded = encoded_metrics(&registry);
+    let metric = format!("request_response_peer_bytes_total{{peer_id=\"{first_peer_id}\"");
+    assert!(encoded.contains(&format!("{metric},direction=\"Inbound\"}} 30")));
+    assert!(encoded.contains(&format!("{metric},direction=\"Outbound\"}} 100")));
+
+    // Metrics of peers that are no longer tracked are removed
+    for _ in 0..PEER_BANDWIDTH_USAGE_CAPACITY.get() {
+        accounting.record(PeerId::random(), "/test/1", Direction::Inbound, 1);
+    }
+
+    assert_eq!(accounting.peer_usage.get(&first_peer_id), None);
+    let encoded = encoded_metrics(&registry);
+    assert!(!encoded.contains(&first_peer_id.to_string()));
+    assert_eq!(
+        encoded
+            .lines()
+            .filter(|line| line.starts_with("request_response_peer_bytes_total{"))
+            .count(),
+        PEER_BANDWIDTH_USAGE_CAPACITY.get()
+    );
+}
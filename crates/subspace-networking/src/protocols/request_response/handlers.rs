@@ -1,3 +1,4 @@
 pub mod generic_request_handler;
 pub mod piece_by_index;
+pub mod piece_chunks;
 pub mod segment_header;
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::protocols::request_response::bandwidth::RequestPriority;
 use crate::protocols::request_response::request_response_factory::{
     IncomingRequest, OutgoingResponse, ProtocolConfig, RequestHandler,
 };
@@ -35,6 +36,10 @@ pub trait GenericRequest: Encode + Decode + Send + Sync + 'static {
     const PROTOCOL_NAME: &'static str;
     /// Specifies log-parameters for tracing.
     const LOG_TARGET: &'static str;
+    /// Priority of the protocol traffic when bandwidth is limited.
+    const PRIORITY: RequestPriority = RequestPriority::Normal;
+    /// Max number of items that can be requested in a single request.
+    const MAX_BATCH_SIZE: u32 = 1;
     /// Response type that corresponds to this request
     type Response: Encode + Decode + Send + Sync + 'static;
 }
@@ -68,6 +73,8 @@ impl<Request: GenericRequest> GenericRequestHandler<Request> {
 
         let mut protocol_config = ProtocolConfig::new(Request::PROTOCOL_NAME);
         protocol_config.inbound_queue = Some(request_sender);
+        protocol_config.priority = Request::PRIORITY;
+        protocol_config.max_batch_size = Request::MAX_BATCH_SIZE;
 
         Box::new(Self {
             request_receiver,
@@ -161,6 +168,8 @@ impl<Request: GenericRequest> RequestHandler for GenericRequestHandler<Request>
 
         let mut protocol_config = ProtocolConfig::new(Request::PROTOCOL_NAME);
         protocol_config.inbound_queue = Some(request_sender);
+        protocol_config.priority = Request::PRIORITY;
+        protocol_config.max_batch_size = Request::MAX_BATCH_SIZE;
 
         Box::new(Self {
             request_receiver,
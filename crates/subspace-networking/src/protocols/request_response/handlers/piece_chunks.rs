@@ -0,0 +1,47 @@
+//! Helper for incoming piece chunks requests.
+//!
+//! Handle (i.e. answer) incoming piece chunks requests from a remote peer received via
+//! `RequestResponsesBehaviour` with generic [`GenericRequestHandler`].
+
+use super::generic_request_handler::{GenericRequest, GenericRequestHandler};
+use parity_scale_codec::{Decode, Encode};
+use subspace_core_primitives::{PieceIndex, RecordChunksProof};
+
+/// Piece chunks protocol request.
+///
+/// Requests a range of record chunks of a piece along with proof of their inclusion in archived
+/// history, which is much smaller than the whole piece when only a small object is needed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Encode, Decode)]
+pub struct PieceChunksRequest {
+    /// Piece index
+    pub piece_index: PieceIndex,
+    /// Offset of the first requested record chunk
+    pub first_chunk_offset: u32,
+    /// Number of requested record chunks, must not exceed [`PieceChunksRequest::MAX_CHUNKS`]
+    pub num_chunks: u32,
+}
+
+impl PieceChunksRequest {
+    /// Max number of record chunks that can be requested at once.
+    ///
+    /// Each chunk requires creation of a witness by the responder, which is CPU-intensive, hence
+    /// the limit is small and only meant for small objects.
+    pub const MAX_CHUNKS: u32 = 8;
+}
+
+impl GenericRequest for PieceChunksRequest {
+    const PROTOCOL_NAME: &'static str = "/subspace/piece-chunks/0.1.0";
+    const LOG_TARGET: &'static str = "piece-chunks-request-response-handler";
+    const MAX_BATCH_SIZE: u32 = Self::MAX_CHUNKS;
+    type Response = PieceChunksResponse;
+}
+
+/// Piece chunks protocol response.
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct PieceChunksResponse {
+    /// Requested record chunks with proof, `None` if piece is not available or request is invalid
+    pub proof: Option<RecordChunksProof>,
+}
+
+/// Create a new piece chunks request handler.
+pub type PieceChunksRequestHandler = GenericRequestHandler<PieceChunksRequest>;
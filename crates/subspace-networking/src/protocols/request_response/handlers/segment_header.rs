@@ -4,6 +4,7 @@
 //! `RequestResponsesBehaviour` with generic [`GenericRequestHandler`].
 
 use super::generic_request_handler::{GenericRequest, GenericRequestHandler};
+use crate::protocols::request_response::bandwidth::RequestPriority;
 use parity_scale_codec::{Decode, Encode};
 use subspace_core_primitives::{SegmentHeader, SegmentIndex};
 
@@ -25,6 +26,10 @@ pub enum SegmentHeaderRequest {
 impl GenericRequest for SegmentHeaderRequest {
     const PROTOCOL_NAME: &'static str = "/subspace/segment-headers-by-indexes/0.1.0";
     const LOG_TARGET: &'static str = "segment-headers-by-indexes-request-response-handler";
+    // Segment headers are needed for verification of everything else
+    const PRIORITY: RequestPriority = RequestPriority::High;
+    // Both nodes and farmers limit number of segment headers returned in a single response
+    const MAX_BATCH_SIZE: u32 = 1000;
     type Response = SegmentHeaderResponse;
 }
 
@@ -31,15 +31,24 @@
 //!
 //! - If provided, a ["requests processing"](ProtocolConfig::inbound_queue) channel
 //! is used to handle incoming requests.
+//!
+//! - Traffic is accounted per protocol and per peer, optionally limited according to
+//! [`BandwidthLimits`](crate::BandwidthLimits). Traffic of [`RequestPriority::High`] protocols
+//! is never queued behind traffic of other protocols.
 
 //! Original file commit: <https://github.com/paritytech/substrate/commit/c2fc4b3ca0d7a15cc3f9cb1e5f441d99ec8d6e0b>
 
 #[cfg(test)]
 mod tests;
 
+use crate::protocols::request_response::bandwidth::{
+    BandwidthAccounting, BandwidthConfig, Direction, FairQueue, RequestPriority, TokenBucket,
+};
+use crate::utils::rate_limiter::RateLimiter;
 use async_trait::async_trait;
 use futures::channel::{mpsc, oneshot};
 use futures::prelude::*;
+use futures_timer::Delay;
 use libp2p::core::{Endpoint, Multiaddr};
 use libp2p::identity::PeerId;
 use libp2p::request_response::{
@@ -57,7 +66,8 @@ use libp2p::swarm::{
 use libp2p::StreamProtocol;
 use std::borrow::Cow;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
@@ -65,6 +75,8 @@ use std::{io, iter};
 use tracing::{debug, error, warn};
 
 const LOG_TARGET: &str = "request-response-protocols";
+/// How many peers per-peer request quotas are tracked for (for each protocol).
+const REQUEST_QUOTA_PEERS_CAPACITY: NonZeroUsize = NonZeroUsize::new(1000).expect("Not zero; qed");
 
 /// Defines a handler for the request-response protocol factory.
 #[async_trait]
@@ -132,6 +144,12 @@ pub struct ProtocolConfig {
     /// advertise support for this protocol, but any incoming request will lead to an error being
     /// sent back.
     pub inbound_queue: Option<mpsc::Sender<IncomingRequest>>,
+
+    /// Priority of the protocol traffic when bandwidth is limited.
+    pub priority: RequestPriority,
+
+    /// Max number of items that can be requested in a single request, advertised to other peers.
+    pub max_batch_size: u32,
 }
 
 impl ProtocolConfig {
@@ -143,6 +161,8 @@ impl ProtocolConfig {
             max_response_size: 16 * 1024 * 1024,
             request_timeout: Duration::from_secs(20),
             inbound_queue: None,
+            priority: RequestPriority::Normal,
+            max_batch_size: 1,
         }
     }
 }
@@ -295,6 +315,30 @@ pub struct RequestResponseFactoryBehaviour {
 
     /// Request handlers future collection.
     request_handlers: Vec<Pin<Box<dyn Future<Output = ()> + Send>>>,
+
+    /// Protocols with [`RequestPriority::High`], their traffic is never queued.
+    high_priority_protocols: HashSet<Cow<'static, str>>,
+
+    /// Per-peer quotas of incoming requests for each protocol (if configured).
+    request_quotas: HashMap<Cow<'static, str>, RateLimiter<PeerId>>,
+
+    /// Traffic accounting in metrics and per peer.
+    bandwidth_accounting: BandwidthAccounting,
+
+    /// Limiter of outgoing responses (if configured).
+    upload_limiter: Option<TokenBucket>,
+
+    /// Limiter of incoming responses (if configured), enforced by delaying outgoing requests.
+    download_limiter: Option<TokenBucket>,
+
+    /// Responses waiting for upload capacity, queued per peer.
+    queued_responses: FairQueue<PeerId, RequestProcessingOutcome>,
+
+    /// Outgoing requests waiting for download capacity, queued per peer.
+    queued_requests: FairQueue<PeerId, QueuedRequest>,
+
+    /// Wakes up the behaviour when bandwidth becomes available for queued traffic.
+    bandwidth_timer: Option<Delay>,
 }
 
 // This is a state of processing incoming request Message.
@@ -309,20 +353,38 @@ struct MessageRequest {
 
 /// Generated by the response builder and waiting to be processed.
 struct RequestProcessingOutcome {
+    peer: PeerId,
     request_id: RequestId,
     protocol: Cow<'static, str>,
     inner_channel: ResponseChannel<Result<Vec<u8>, ()>>,
     response: OutgoingResponse,
 }
 
+/// Outgoing request waiting for download capacity.
+struct QueuedRequest {
+    target: PeerId,
+    protocol_name: String,
+    request: Vec<u8>,
+    pending_response: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
+}
+
 impl RequestResponseFactoryBehaviour {
     /// Creates a new behaviour. Must be passed a list of supported protocols. Returns an error if
     /// the same protocol is passed twice.
-    pub fn new(
+    pub(crate) fn new(
         list: impl IntoIterator<Item = Box<dyn RequestHandler>>,
+        bandwidth_config: BandwidthConfig,
     ) -> Result<Self, RegisterError> {
+        let BandwidthConfig {
+            limits,
+            metrics,
+            peer_usage,
+        } = bandwidth_config;
+
         let mut protocols = HashMap::new();
         let mut request_handlers = Vec::new();
+        let mut high_priority_protocols = HashSet::new();
+        let mut request_quotas = HashMap::new();
         for mut handler in list {
             let config = handler.protocol_config();
 
@@ -352,6 +414,21 @@ impl RequestResponseFactoryBehaviour {
                 }
             };
 
+            if config.priority == RequestPriority::High {
+                high_priority_protocols.insert(Cow::Borrowed(config.name));
+            }
+
+            if let Some(max_requests_per_peer) = limits.max_requests_per_peer {
+                request_quotas.insert(
+                    Cow::Borrowed(config.name),
+                    RateLimiter::new(
+                        max_requests_per_peer,
+                        limits.requests_per_peer_interval,
+                        REQUEST_QUOTA_PEERS_CAPACITY,
+                    ),
+                );
+            }
+
             let request_handler_run: Pin<Box<dyn Future<Output = ()> + Send>> =
                 Box::pin(async move { handler.run().await }.fuse());
 
@@ -364,6 +441,17 @@ impl RequestResponseFactoryBehaviour {
             pending_responses: Default::default(),
             message_request: None,
             request_handlers,
+            high_priority_protocols,
+            request_quotas,
+            bandwidth_accounting: BandwidthAccounting {
+                metrics,
+                peer_usage,
+            },
+            upload_limiter: limits.upload_bytes_per_second.map(TokenBucket::new),
+            download_limiter: limits.download_bytes_per_second.map(TokenBucket::new),
+            queued_responses: FairQueue::default(),
+            queued_requests: FairQueue::default(),
+            bandwidth_timer: None,
         })
     }
 
@@ -373,6 +461,9 @@ impl RequestResponseFactoryBehaviour {
     /// choice of `connect`.
     ///
     /// An error is returned if the protocol doesn't match one that has been registered.
+    ///
+    /// If download bandwidth is limited and exhausted, request is queued until bandwidth becomes
+    /// available (unless protocol has [`RequestPriority::High`]).
     pub fn send_request(
         &mut self,
         target: &PeerId,
@@ -383,12 +474,33 @@ impl RequestResponseFactoryBehaviour {
     ) {
         if let Some((protocol, _)) = self.protocols.get_mut(protocol_name) {
             if protocol.is_connected(target) || connect.should_connect() {
-                let request_id = protocol.send_request(target, request);
-                let prev_req_id = self.pending_requests.insert(
-                    (protocol_name.to_string().into(), request_id).into(),
-                    (Instant::now(), pending_response),
-                );
-                debug_assert!(prev_req_id.is_none(), "Expect request id to be unique.");
+                let send_now = self.high_priority_protocols.contains(protocol_name)
+                    || self.queued_requests.is_empty()
+                        && self
+                            .download_limiter
+                            .as_mut()
+                            .map_or(true, |download_limiter| {
+                                download_limiter.has_capacity(Instant::now())
+                            });
+
+                let queued_request = QueuedRequest {
+                    target: *target,
+                    protocol_name: protocol_name.to_string(),
+                    request,
+                    pending_response,
+                };
+
+                if send_now {
+                    self.dispatch_request(queued_request);
+                } else {
+                    debug!(
+                        target: LOG_TARGET,
+                        %target,
+                        %protocol_name,
+                        "Download bandwidth exhausted, queueing request"
+                    );
+                    self.queued_requests.push(*target, queued_request);
+                }
             } else if pending_response
                 .send(Err(RequestFailure::NotConnected))
                 .is_err()
@@ -412,6 +524,127 @@ impl RequestResponseFactoryBehaviour {
             );
         }
     }
+
+    /// Actually sends request to the underlying protocol.
+    fn dispatch_request(&mut self, queued_request: QueuedRequest) {
+        let QueuedRequest {
+            target,
+            protocol_name,
+            request,
+            pending_response,
+        } = queued_request;
+
+        let Some((protocol, _)) = self.protocols.get_mut(protocol_name.as_str()) else {
+            // Protocol presence is checked before request is queued
+            return;
+        };
+
+        self.bandwidth_accounting.record(
+            target,
+            &protocol_name,
+            Direction::Outbound,
+            request.len(),
+        );
+
+        let request_id = protocol.send_request(&target, request);
+        let prev_req_id = self.pending_requests.insert(
+            (protocol_name.into(), request_id).into(),
+            (Instant::now(), pending_response),
+        );
+        debug_assert!(prev_req_id.is_none(), "Expect request id to be unique.");
+    }
+
+    /// Sends response to the underlying protocol.
+    fn send_response(&mut self, outcome: RequestProcessingOutcome) {
+        let RequestProcessingOutcome {
+            peer,
+            request_id,
+            protocol: protocol_name,
+            inner_channel,
+            response: OutgoingResponse { result, .. },
+        } = outcome;
+
+        if let Ok(payload) = result {
+            if let Some((protocol, _)) = self.protocols.get_mut(&*protocol_name) {
+                let payload_len = payload.len();
+
+                if protocol.send_response(inner_channel, Ok(payload)).is_err() {
+                    // Note: Failure is handled further below when receiving
+                    // `InboundFailure` event from `RequestResponse` behaviour.
+                    debug!(
+                        target: LOG_TARGET,
+                        %request_id,
+                        "Failed to send response for request on protocol {} due to a \
+                        timeout or due to the connection to the peer being closed. \
+                        Dropping response",
+                        protocol_name,
+                    );
+                } else {
+                    self.bandwidth_accounting.record(
+                        peer,
+                        &protocol_name,
+                        Direction::Outbound,
+                        payload_len,
+                    );
+                    if let Some(upload_limiter) = &mut self.upload_limiter {
+                        upload_limiter.consume(payload_len, Instant::now());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends queued responses and requests while there is bandwidth available for them.
+    fn process_queued_traffic(&mut self) {
+        while !self.queued_responses.is_empty()
+            && self.upload_limiter.as_mut().map_or(true, |upload_limiter| {
+                upload_limiter.has_capacity(Instant::now())
+            })
+        {
+            if let Some(outcome) = self.queued_responses.pop() {
+                self.send_response(outcome);
+            }
+        }
+
+        while !self.queued_requests.is_empty()
+            && self
+                .download_limiter
+                .as_mut()
+                .map_or(true, |download_limiter| {
+                    download_limiter.has_capacity(Instant::now())
+                })
+        {
+            if let Some(queued_request) = self.queued_requests.pop() {
+                // Requester is no longer interested in the response
+                if queued_request.pending_response.is_canceled() {
+                    continue;
+                }
+                self.dispatch_request(queued_request);
+            }
+        }
+    }
+
+    /// How long to wait until bandwidth is available for queued traffic, `None` if nothing is
+    /// queued.
+    fn time_until_bandwidth_available(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+
+        let upload_wait = self
+            .upload_limiter
+            .as_mut()
+            .filter(|_| !self.queued_responses.is_empty())
+            .map(|upload_limiter| upload_limiter.time_until_capacity(now));
+        let download_wait = self
+            .download_limiter
+            .as_mut()
+            .filter(|_| !self.queued_requests.is_empty())
+            .map(|download_limiter| download_limiter.time_until_capacity(now));
+
+        match (upload_wait, download_wait) {
+            (Some(upload_wait), Some(download_wait)) => Some(upload_wait.min(download_wait)),
+            (upload_wait, download_wait) => upload_wait.or(download_wait),
+        }
+    }
 }
 
 impl NetworkBehaviour for RequestResponseFactoryBehaviour {
@@ -631,6 +864,7 @@ impl NetworkBehaviour for RequestResponseFactoryBehaviour {
                     // `InboundFailure::Omission` event.
                     if let Ok(response) = rx.await {
                         Some(RequestProcessingOutcome {
+                            peer,
                             request_id,
                             protocol: Cow::from(protocol),
                             inner_channel: channel,
@@ -647,36 +881,26 @@ impl NetworkBehaviour for RequestResponseFactoryBehaviour {
             }
             // Poll to see if any response is ready to be sent back.
             while let Poll::Ready(Some(outcome)) = self.pending_responses.poll_next_unpin(cx) {
-                let RequestProcessingOutcome {
-                    request_id,
-                    protocol: protocol_name,
-                    inner_channel,
-                    response: OutgoingResponse { result, .. },
-                } = match outcome {
+                let outcome = match outcome {
                     Some(outcome) => outcome,
                     // The response builder was too busy or handling the request failed. This is
                     // later on reported as a `InboundFailure::Omission`.
                     None => continue,
                 };
 
-                if let Ok(payload) = result {
-                    if let Some((protocol, _)) = self.protocols.get_mut(&*protocol_name) {
-                        if protocol.send_response(inner_channel, Ok(payload)).is_err() {
-                            // Note: Failure is handled further below when receiving
-                            // `InboundFailure` event from `RequestResponse` behaviour.
-                            debug!(
-                                target: LOG_TARGET,
-                                %request_id,
-                                "Failed to send response for request on protocol {} due to a \
-                                timeout or due to the connection to the peer being closed. \
-                                Dropping response",
-                                protocol_name,
-                            );
-                        }
-                    }
+                // Responses of high priority protocols are never queued, the rest is queued if
+                // upload bandwidth is limited, queue is drained right below
+                if self.upload_limiter.is_none()
+                    || self.high_priority_protocols.contains(&outcome.protocol)
+                {
+                    self.send_response(outcome);
+                } else {
+                    self.queued_responses.push(outcome.peer, outcome);
                 }
             }
 
+            self.process_queued_traffic();
+
             for rq_rs_runner in &mut self.request_handlers {
                 // Future.Output == (), so we don't need a result here
                 let _ = rq_rs_runner.poll_unpin(cx);
@@ -748,6 +972,33 @@ impl NetworkBehaviour for RequestResponseFactoryBehaviour {
                                     channel,
                                 },
                         } => {
+                            self.bandwidth_accounting.record(
+                                peer,
+                                protocol,
+                                Direction::Inbound,
+                                request.len(),
+                            );
+                            if let Some(download_limiter) = &mut self.download_limiter {
+                                download_limiter.consume(request.len(), Instant::now());
+                            }
+
+                            if let Some(request_quota) = self.request_quotas.get_mut(protocol) {
+                                if !request_quota.try_acquire(peer) {
+                                    debug!(
+                                        target: LOG_TARGET,
+                                        %peer,
+                                        %protocol,
+                                        "Per-peer request quota exceeded, dropping request"
+                                    );
+                                    self.bandwidth_accounting.record_rejected_request(protocol);
+
+                                    // Dropping the channel is reported as
+                                    // `InboundFailure::ResponseOmission`
+                                    drop(channel);
+                                    continue;
+                                }
+                            }
+
                             self.message_request = Some(MessageRequest {
                                 peer,
                                 request_id,
@@ -771,6 +1022,17 @@ impl NetworkBehaviour for RequestResponseFactoryBehaviour {
                                     response,
                                 },
                         } => {
+                            let response_len = response.as_ref().map_or(0, Vec::len);
+                            self.bandwidth_accounting.record(
+                                peer,
+                                protocol,
+                                Direction::Inbound,
+                                response_len,
+                            );
+                            if let Some(download_limiter) = &mut self.download_limiter {
+                                download_limiter.consume(response_len, Instant::now());
+                            }
+
                             let (started, delivered) = match self
                                 .pending_requests
                                 .remove(&(protocol.clone(), request_id).into())
@@ -876,6 +1138,17 @@ impl NetworkBehaviour for RequestResponseFactoryBehaviour {
                 }
             }
 
+            if let Some(wait) = self.time_until_bandwidth_available() {
+                let mut bandwidth_timer = Delay::new(wait);
+                if bandwidth_timer.poll_unpin(cx).is_ready() {
+                    self.bandwidth_timer.take();
+                    continue 'poll_all;
+                }
+                self.bandwidth_timer.replace(bandwidth_timer);
+            } else {
+                self.bandwidth_timer.take();
+            }
+
             break Poll::Pending;
         }
     }
@@ -1,3 +1,6 @@
+use crate::protocols::request_response::bandwidth::{
+    BandwidthConfig, BandwidthLimits, RequestPriority,
+};
 use crate::protocols::request_response::request_response_factory::{
     Event, IfDisconnected, IncomingRequest, OutboundFailure, OutgoingResponse, ProtocolConfig,
     RequestFailure, RequestHandler, RequestResponseFactoryBehaviour,
@@ -13,6 +16,7 @@ use libp2p::identity::Keypair;
 use libp2p::swarm::{Swarm, SwarmBuilder, SwarmEvent};
 use libp2p::{noise, Multiaddr};
 use std::iter;
+use std::num::NonZeroU32;
 use std::time::Duration;
 
 #[derive(Clone)]
@@ -37,6 +41,13 @@ impl RequestHandler for MockRunner {
 
 fn build_swarm(
     list: impl Iterator<Item = ProtocolConfig>,
+) -> (Swarm<RequestResponseFactoryBehaviour>, Multiaddr) {
+    build_swarm_with_bandwidth_config(list, BandwidthConfig::default())
+}
+
+fn build_swarm_with_bandwidth_config(
+    list: impl Iterator<Item = ProtocolConfig>,
+    bandwidth_config: BandwidthConfig,
 ) -> (Swarm<RequestResponseFactoryBehaviour>, Multiaddr) {
     let keypair = Keypair::generate_ed25519();
 
@@ -50,7 +61,7 @@ fn build_swarm(
         .into_iter()
         .map(|config| Box::new(MockRunner(config)) as Box<dyn RequestHandler>)
         .collect::<Vec<_>>();
-    let behaviour = RequestResponseFactoryBehaviour::new(configs).unwrap();
+    let behaviour = RequestResponseFactoryBehaviour::new(configs, bandwidth_config).unwrap();
 
     let mut swarm =
         SwarmBuilder::with_tokio_executor(transport, behaviour, keypair.public().to_peer_id())
@@ -95,6 +106,8 @@ async fn basic_request_response_works() {
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
                 inbound_queue: Some(tx),
+                priority: RequestPriority::Normal,
+                max_batch_size: 1,
             };
 
             build_swarm(iter::once(protocol_config))
@@ -194,6 +207,8 @@ async fn max_response_size_exceeded() {
                 max_response_size: 8, // <-- important for the test
                 request_timeout: Duration::from_secs(30),
                 inbound_queue: Some(tx),
+                priority: RequestPriority::Normal,
+                max_batch_size: 1,
             };
 
             build_swarm(iter::once(protocol_config))
@@ -287,6 +302,8 @@ async fn request_id_collision() {
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
                 inbound_queue: None,
+                priority: RequestPriority::Normal,
+                max_batch_size: 1,
             },
             ProtocolConfig {
                 name: protocol_name_2,
@@ -294,6 +311,8 @@ async fn request_id_collision() {
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
                 inbound_queue: None,
+                priority: RequestPriority::Normal,
+                max_batch_size: 1,
             },
         ];
 
@@ -311,6 +330,8 @@ async fn request_id_collision() {
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
                 inbound_queue: Some(tx_1),
+                priority: RequestPriority::Normal,
+                max_batch_size: 1,
             },
             ProtocolConfig {
                 name: protocol_name_2,
@@ -318,6 +339,8 @@ async fn request_id_collision() {
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
                 inbound_queue: Some(tx_2),
+                priority: RequestPriority::Normal,
+                max_batch_size: 1,
             },
         ];
 
@@ -427,3 +450,131 @@ async fn request_id_collision() {
         );
     });
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn per_peer_request_quota() {
+    let protocol_name = "/test/req-resp/1";
+    let mut pool = LocalPool::new();
+
+    // Build swarms whose behaviour is `RequestResponsesBehaviour`, each accepts only one request
+    // per peer.
+    let mut swarms = (0..2)
+        .map(|_| {
+            let (tx, mut rx) = mpsc::channel::<IncomingRequest>(64);
+
+            pool.spawner()
+                .spawn_obj(
+                    async move {
+                        while let Some(rq) = rx.next().await {
+                            let _ = rq.pending_response.send(OutgoingResponse {
+                                result: Ok(b"this is a response".to_vec()),
+                                sent_feedback: None,
+                            });
+                        }
+                    }
+                    .boxed()
+                    .into(),
+                )
+                .unwrap();
+
+            let protocol_config = ProtocolConfig {
+                name: protocol_name,
+                max_request_size: 1024,
+                max_response_size: 1024 * 1024,
+                request_timeout: Duration::from_secs(30),
+                inbound_queue: Some(tx),
+                priority: RequestPriority::Normal,
+                max_batch_size: 1,
+            };
+
+            build_swarm_with_bandwidth_config(
+                iter::once(protocol_config),
+                BandwidthConfig {
+                    limits: BandwidthLimits {
+                        max_requests_per_peer: Some(NonZeroU32::new(1).unwrap()),
+                        ..BandwidthLimits::default()
+                    },
+                    ..BandwidthConfig::default()
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Ask `swarm[0]` to dial `swarm[1]`. There isn't any discovery mechanism in place in
+    // this test, so they wouldn't connect to each other.
+    {
+        let dial_addr = swarms[1].1.clone();
+        Swarm::dial(&mut swarms[0].0, dial_addr).unwrap();
+    }
+
+    let (mut swarm, _) = swarms.remove(0);
+
+    // Running `swarm[0]` in the background.
+    pool.spawner()
+        .spawn_obj({
+            async move {
+                loop {
+                    swarm.select_next_some().await;
+                }
+            }
+            .boxed()
+            .into()
+        })
+        .unwrap();
+
+    // Remove and run the remaining swarm.
+    let (mut swarm, _) = swarms.remove(0);
+
+    pool.run_until(async move {
+        let mut response_receivers = Vec::new();
+        let mut finished_requests = 0;
+
+        loop {
+            match swarm.select_next_some().await {
+                SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                    for _ in 0..2 {
+                        let (sender, receiver) = oneshot::channel();
+                        swarm.behaviour_mut().send_request(
+                            &peer_id,
+                            protocol_name,
+                            b"this is a request".to_vec(),
+                            sender,
+                            IfDisconnected::ImmediateError,
+                        );
+                        response_receivers.push(receiver);
+                    }
+                }
+                SwarmEvent::Behaviour(Event::RequestFinished { .. }) => {
+                    finished_requests += 1;
+                    if finished_requests == 2 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut results = Vec::new();
+        for response_receiver in response_receivers {
+            results.push(response_receiver.await.unwrap());
+        }
+
+        // Exactly one request was answered, the other one exceeded the quota
+        assert_eq!(
+            results
+                .iter()
+                .filter(
+                    |result| matches!(result, Ok(response) if response == b"this is a response")
+                )
+                .count(),
+            1
+        );
+        assert_eq!(
+            results
+                .iter()
+                .filter(|result| matches!(result, Err(RequestFailure::Refused)))
+                .count(),
+            1
+        );
+    });
+}
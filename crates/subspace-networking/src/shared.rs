@@ -1,7 +1,9 @@
 //! Data structures shared between node and node runner, facilitating exchange and creation of
 //! queries, subscriptions, various events and shared information.
 
+use crate::node::DialError;
 use crate::protocols::peer_info::PeerInfo;
+use crate::protocols::request_response::bandwidth::PeerBandwidthUsage;
 use crate::protocols::request_response::request_response_factory::RequestFailure;
 use crate::utils::multihash::Multihash;
 use crate::utils::{Handler, ResizableSemaphore, ResizableSemaphorePermit};
@@ -11,6 +13,7 @@ use libp2p::gossipsub::{PublishError, Sha256Topic, SubscriptionError};
 use libp2p::kad::PeerRecord;
 use libp2p::{Multiaddr, PeerId};
 use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
@@ -69,6 +72,7 @@ pub(crate) enum Command {
     },
     Dial {
         address: Multiaddr,
+        result_sender: oneshot::Sender<Result<(), DialError>>,
     },
     ConnectedPeers {
         result_sender: oneshot::Sender<Vec<PeerId>>,
@@ -110,6 +114,10 @@ pub(crate) struct Shared {
     pub(crate) command_sender: mpsc::Sender<Command>,
     pub(crate) kademlia_tasks_semaphore: ResizableSemaphore,
     pub(crate) regular_tasks_semaphore: ResizableSemaphore,
+    /// Bandwidth usage of request-response protocols per peer.
+    pub(crate) peer_bandwidth_usage: PeerBandwidthUsage,
+    /// Peer info received from currently connected peers.
+    pub(crate) peer_infos: Mutex<HashMap<PeerId, PeerInfo>>,
 }
 
 impl Shared {
@@ -118,6 +126,7 @@ impl Shared {
         command_sender: mpsc::Sender<Command>,
         kademlia_tasks_semaphore: ResizableSemaphore,
         regular_tasks_semaphore: ResizableSemaphore,
+        peer_bandwidth_usage: PeerBandwidthUsage,
     ) -> Self {
         Self {
             handlers: Handlers::default(),
@@ -128,6 +137,8 @@ impl Shared {
             command_sender,
             kademlia_tasks_semaphore,
             regular_tasks_semaphore,
+            peer_bandwidth_usage,
+            peer_infos: Mutex::default(),
         }
     }
 }
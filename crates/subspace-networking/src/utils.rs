@@ -2,6 +2,8 @@
 
 pub mod multihash;
 pub mod piece_provider;
+pub mod rate_limiter;
+pub mod segment_header_announcements;
 #[cfg(test)]
 mod tests;
 pub(crate) mod unique_record_binary_heap;
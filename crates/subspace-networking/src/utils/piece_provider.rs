@@ -1,16 +1,20 @@
 //! Provides methods to retrieve pieces from DSN.
 
+use crate::protocols::request_response::handlers::generic_request_handler::GenericRequest;
 use crate::utils::multihash::ToMultihash;
-use crate::{Node, PieceByIndexRequest, PieceByIndexResponse};
+use crate::{
+    Node, PieceByIndexRequest, PieceByIndexResponse, PieceChunksRequest, PieceChunksResponse,
+};
 use async_trait::async_trait;
 use backoff::future::retry;
 use backoff::ExponentialBackoff;
 use futures::StreamExt;
 use libp2p::PeerId;
 use std::error::Error;
+use std::ops::Range;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use subspace_core_primitives::{Piece, PieceIndex};
+use subspace_core_primitives::{Piece, PieceIndex, RecordChunksProof};
 use tracing::{debug, trace, warn};
 
 /// Defines initial duration between get_piece calls.
@@ -74,6 +78,15 @@ where
         }
     }
 
+    /// Whether peer is capable of serving pieces according to its peer info, peers with unknown
+    /// capabilities are assumed to be capable.
+    fn is_capable_peer(&self, peer_id: PeerId) -> bool {
+        self.node
+            .peer_info(&peer_id)
+            .map(|peer_info| peer_info.supports_protocol(PieceByIndexRequest::PROTOCOL_NAME))
+            .unwrap_or(true)
+    }
+
     // Get from piece cache (L2)
     async fn get_piece_from_cache(&self, piece_index: PieceIndex) -> Option<Piece> {
         let key = piece_index.to_multihash();
@@ -85,6 +98,11 @@ where
                 while let Some(provider_id) = get_providers_stream.next().await {
                     trace!(%piece_index, %provider_id, "get_providers returned an item");
 
+                    if !self.is_capable_peer(provider_id) {
+                        trace!(%piece_index, %provider_id, "Provider doesn't serve pieces, skipping");
+                        continue;
+                    }
+
                     let request_result = self
                         .node
                         .send_generic_request(provider_id, PieceByIndexRequest { piece_index })
@@ -180,6 +198,11 @@ where
         peer_id: PeerId,
         piece_index: PieceIndex,
     ) -> Option<Piece> {
+        if !self.is_capable_peer(peer_id) {
+            debug!(%peer_id, %piece_index, "Peer doesn't serve pieces, skipping request.");
+            return None;
+        }
+
         let request_result = self
             .node
             .send_generic_request(peer_id, PieceByIndexRequest { piece_index })
@@ -205,4 +228,96 @@ where
 
         None
     }
+
+    /// Get `chunks` range of the record of the piece along with proof of their inclusion from the
+    /// first provider that has them.
+    ///
+    /// NOTE: Proof is not validated, caller is expected to either validate it against segment
+    /// commitment or to verify retrieved data otherwise.
+    pub async fn get_piece_chunks(
+        &self,
+        piece_index: PieceIndex,
+        chunks: Range<u32>,
+    ) -> Option<RecordChunksProof> {
+        if chunks.is_empty() || chunks.end - chunks.start > PieceChunksRequest::MAX_CHUNKS {
+            debug!(%piece_index, ?chunks, "Invalid range of piece chunks, skipping request.");
+            return None;
+        }
+
+        let key = piece_index.to_multihash();
+        let mut get_providers_stream = match self.node.get_providers(key).await {
+            Ok(get_providers_stream) => get_providers_stream,
+            Err(err) => {
+                warn!(%piece_index, ?key, ?err, "get_providers returned an error");
+                return None;
+            }
+        };
+
+        while let Some(provider_id) = get_providers_stream.next().await {
+            let is_capable_peer = self
+                .node
+                .peer_info(&provider_id)
+                .map(|peer_info| peer_info.supports_protocol(PieceChunksRequest::PROTOCOL_NAME))
+                .unwrap_or(true);
+            if !is_capable_peer {
+                trace!(%piece_index, %provider_id, "Provider doesn't serve piece chunks, skipping");
+                continue;
+            }
+
+            let request_result = self
+                .node
+                .send_generic_request(
+                    provider_id,
+                    PieceChunksRequest {
+                        piece_index,
+                        first_chunk_offset: chunks.start,
+                        num_chunks: chunks.end - chunks.start,
+                    },
+                )
+                .await;
+
+            match request_result {
+                Ok(PieceChunksResponse { proof: Some(proof) }) => {
+                    if proof.first_chunk_offset == chunks.start
+                        && proof.chunks.len() == chunks.len()
+                    {
+                        trace!(
+                            %provider_id,
+                            %piece_index,
+                            ?chunks,
+                            "Piece chunks request succeeded."
+                        );
+
+                        return Some(proof);
+                    }
+
+                    debug!(
+                        %provider_id,
+                        %piece_index,
+                        ?chunks,
+                        "Piece chunks request returned different chunks."
+                    );
+                }
+                Ok(PieceChunksResponse { proof: None }) => {
+                    debug!(
+                        %provider_id,
+                        %piece_index,
+                        ?chunks,
+                        "Piece chunks request returned no chunks."
+                    );
+                }
+                Err(error) => {
+                    debug!(
+                        %provider_id,
+                        %piece_index,
+                        ?chunks,
+                        ?error,
+                        "Piece chunks request failed."
+                    );
+                }
+            }
+        }
+
+        None
+    }
 }
@@ -0,0 +1,65 @@
+//! Simple fixed-window rate limiter keyed by arbitrary values (typically peer IDs).
+
+use lru::LruCache;
+use std::hash::Hash;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::time::{Duration, Instant};
+
+/// Window of a single key: when it started and how many events were registered within it.
+#[derive(Debug, Copy, Clone)]
+struct Window {
+    started_at: Instant,
+    events: u32,
+}
+
+/// Allows at most `limit` events per key within `interval`.
+///
+/// Only a bounded number of keys is tracked, least recently seen keys are evicted first, which
+/// means evicted keys will get a fresh window next time they are seen.
+#[derive(Debug)]
+pub struct RateLimiter<Key>
+where
+    Key: Hash + Eq,
+{
+    limit: NonZeroU32,
+    interval: Duration,
+    windows: LruCache<Key, Window>,
+}
+
+impl<Key> RateLimiter<Key>
+where
+    Key: Hash + Eq,
+{
+    /// Create new rate limiter tracking at most `capacity` keys.
+    pub fn new(limit: NonZeroU32, interval: Duration, capacity: NonZeroUsize) -> Self {
+        Self {
+            limit,
+            interval,
+            windows: LruCache::new(capacity),
+        }
+    }
+
+    /// Register an event for `key`, returns `false` if limit for current window is exceeded.
+    pub fn try_acquire(&mut self, key: Key) -> bool {
+        self.try_acquire_at(key, Instant::now())
+    }
+
+    pub(crate) fn try_acquire_at(&mut self, key: Key, now: Instant) -> bool {
+        let window = self.windows.get_or_insert_mut(key, || Window {
+            started_at: now,
+            events: 0,
+        });
+
+        if now.saturating_duration_since(window.started_at) >= self.interval {
+            window.started_at = now;
+            window.events = 0;
+        }
+
+        if window.events >= self.limit.get() {
+            return false;
+        }
+
+        window.events += 1;
+        true
+    }
+}
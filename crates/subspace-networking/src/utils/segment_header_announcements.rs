@@ -0,0 +1,231 @@
+//! Announcements of newly archived segment headers over gossip.
+//!
+//! Nodes publish segment header of every newly archived segment on a dedicated gossip topic, which
+//! allows farmers (and anyone else interested) to start fetching pieces of the new segment right
+//! away instead of waiting for the notification from a node they might not even be connected to.
+
+use crate::{
+    GossipMessageValidator, GossipTopicConfig, Node, PublishError, SubscribeError,
+    TopicSubscription,
+};
+use futures::{Stream, StreamExt};
+use libp2p::gossipsub::{MessageAcceptance, Sha256Topic};
+use parity_scale_codec::{Decode, Encode};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use subspace_core_primitives::{SegmentHeader, SegmentIndex};
+use tracing::{debug, trace};
+
+/// Gossip topic used for segment header announcements.
+const SEGMENT_HEADER_ANNOUNCEMENTS_TOPIC: &str = "/subspace/segment-header-announcements/1";
+/// How many recent segment headers validator keeps around to validate repeated announcements.
+const KNOWN_SEGMENT_HEADERS_LIMIT: usize = 100;
+/// Max number of segment header announcements accepted from a single peer within
+/// [`SEGMENT_HEADER_ANNOUNCEMENTS_RATE_LIMIT_INTERVAL`].
+///
+/// Segments are archived rarely, but announcements might be repeated by multiple nodes and a node
+/// that was offline for a while might catch up quickly.
+const SEGMENT_HEADER_ANNOUNCEMENTS_PER_PEER: NonZeroU32 =
+    NonZeroU32::new(10).expect("Not zero; qed");
+const SEGMENT_HEADER_ANNOUNCEMENTS_RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Gossip topic used for segment header announcements.
+pub fn segment_header_announcements_topic() -> Sha256Topic {
+    Sha256Topic::new(SEGMENT_HEADER_ANNOUNCEMENTS_TOPIC)
+}
+
+/// Configuration of [`segment_header_announcements_topic()`] with provided validator, the same
+/// rate limits are used by nodes and farmers.
+pub fn segment_header_announcements_topic_config(
+    validator: GossipMessageValidator,
+) -> GossipTopicConfig {
+    GossipTopicConfig {
+        topic: segment_header_announcements_topic(),
+        validator,
+        max_messages_per_peer: SEGMENT_HEADER_ANNOUNCEMENTS_PER_PEER,
+        rate_limit_interval: SEGMENT_HEADER_ANNOUNCEMENTS_RATE_LIMIT_INTERVAL,
+    }
+}
+
+/// Announcement of newly archived segment.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SegmentHeaderAnnouncement {
+    /// Segment header of newly archived segment
+    pub segment_header: SegmentHeader,
+}
+
+/// Result of checking announced segment header against known segment headers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ValidationResult {
+    /// Announcement matches known segment header
+    Valid,
+    /// Announcement can't be checked against known segment headers (not confirmed by trusted
+    /// source yet or too old)
+    Unknown,
+    /// Announcement conflicts with known segment headers
+    Invalid,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    known_segment_headers: BTreeMap<SegmentIndex, SegmentHeader>,
+}
+
+impl Inner {
+    fn add_segment_header(&mut self, segment_header: SegmentHeader) {
+        self.known_segment_headers
+            .insert(segment_header.segment_index(), segment_header);
+
+        while self.known_segment_headers.len() > KNOWN_SEGMENT_HEADERS_LIMIT {
+            self.known_segment_headers.pop_first();
+        }
+    }
+
+    fn validate(&self, segment_header: &SegmentHeader) -> ValidationResult {
+        let segment_index = segment_header.segment_index();
+
+        if let Some(known_segment_header) = self.known_segment_headers.get(&segment_index) {
+            return if known_segment_header == segment_header {
+                ValidationResult::Valid
+            } else {
+                ValidationResult::Invalid
+            };
+        }
+
+        // Segment header that follows known one must reference it, but matching parent alone
+        // doesn't make announcement trustworthy since anyone can produce such segment header
+        if segment_index > SegmentIndex::ZERO {
+            if let Some(previous_segment_header) = self
+                .known_segment_headers
+                .get(&(segment_index - SegmentIndex::ONE))
+            {
+                if segment_header.prev_segment_header_hash() != previous_segment_header.hash() {
+                    return ValidationResult::Invalid;
+                }
+            }
+        }
+
+        ValidationResult::Unknown
+    }
+}
+
+/// Validates segment header announcements by checking them against segment headers known to be
+/// valid.
+///
+/// Validator only knows segment headers from trusted source (like local node or node RPC) that
+/// were added with [`SegmentHeaderAnnouncementValidator::add_segment_header`], announcements never
+/// extend this set. Announcements that can't be checked yet are ignored (neither propagated nor
+/// penalized), announcements that conflict with known segment headers are rejected.
+#[derive(Debug, Clone, Default)]
+pub struct SegmentHeaderAnnouncementValidator {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SegmentHeaderAnnouncementValidator {
+    /// Create new validator seeded with segment headers known to be valid.
+    pub fn new<I>(segment_headers: I) -> Self
+    where
+        I: IntoIterator<Item = SegmentHeader>,
+    {
+        let validator = Self::default();
+        for segment_header in segment_headers {
+            validator.add_segment_header(segment_header);
+        }
+        validator
+    }
+
+    /// Add segment header known to be valid (obtained from trusted source).
+    pub fn add_segment_header(&self, segment_header: SegmentHeader) {
+        self.inner.lock().add_segment_header(segment_header);
+    }
+
+    /// Validate encoded segment header announcement received from gossip.
+    pub fn validate(&self, message: &[u8]) -> MessageAcceptance {
+        let Ok(SegmentHeaderAnnouncement { segment_header }) =
+            SegmentHeaderAnnouncement::decode(&mut &*message)
+        else {
+            return MessageAcceptance::Reject;
+        };
+
+        match self.inner.lock().validate(&segment_header) {
+            ValidationResult::Valid => MessageAcceptance::Accept,
+            ValidationResult::Unknown => {
+                trace!(
+                    segment_index = %segment_header.segment_index(),
+                    "Can't validate segment header announcement, ignoring"
+                );
+                MessageAcceptance::Ignore
+            }
+            ValidationResult::Invalid => {
+                debug!(
+                    segment_index = %segment_header.segment_index(),
+                    "Invalid segment header announcement"
+                );
+                MessageAcceptance::Reject
+            }
+        }
+    }
+
+    /// Turn validator into gossip message validator for [`GossipTopicConfig`].
+    pub fn into_gossip_message_validator(self) -> GossipMessageValidator {
+        Arc::new(move |_peer_id, message| self.validate(message))
+    }
+}
+
+/// Stream of validated segment headers announced over gossip.
+#[derive(Debug)]
+pub struct SegmentHeaderAnnouncements {
+    subscription: TopicSubscription,
+}
+
+impl Stream for SegmentHeaderAnnouncements {
+    type Item = SegmentHeader;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let Some(message) = futures::ready!(self.subscription.poll_next_unpin(cx)) else {
+                return Poll::Ready(None);
+            };
+
+            // Messages were validated by node runner before being delivered here, but we still
+            // need to decode them
+            match SegmentHeaderAnnouncement::decode(&mut message.as_ref()) {
+                Ok(SegmentHeaderAnnouncement { segment_header }) => {
+                    return Poll::Ready(Some(segment_header));
+                }
+                Err(error) => {
+                    debug!(%error, "Failed to decode segment header announcement");
+                }
+            }
+        }
+    }
+}
+
+impl SegmentHeaderAnnouncements {
+    /// Subscribe to segment header announcements.
+    ///
+    /// Announcements are validated with validator from [`GossipTopicConfig`] of
+    /// [`segment_header_announcements_topic()`] before they are delivered to this stream.
+    pub async fn subscribe(node: &Node) -> Result<Self, SubscribeError> {
+        let subscription = node.subscribe(segment_header_announcements_topic()).await?;
+
+        Ok(Self { subscription })
+    }
+}
+
+/// Publish announcement about newly archived segment.
+pub async fn announce_segment_header(
+    node: &Node,
+    segment_header: SegmentHeader,
+) -> Result<(), PublishError> {
+    node.publish(
+        segment_header_announcements_topic(),
+        SegmentHeaderAnnouncement { segment_header }.encode(),
+    )
+    .await
+}
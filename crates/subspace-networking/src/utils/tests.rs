@@ -1,5 +1,16 @@
+use super::rate_limiter::RateLimiter;
+use super::segment_header_announcements::{
+    SegmentHeaderAnnouncement, SegmentHeaderAnnouncementValidator,
+};
 use super::{CollectionBatcher, ResizableSemaphore};
-use std::num::NonZeroUsize;
+use libp2p::gossipsub::MessageAcceptance;
+use parity_scale_codec::Encode;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::time::{Duration, Instant};
+use subspace_core_primitives::{
+    ArchivedBlockProgress, Blake2b256Hash, LastArchivedBlock, SegmentCommitment, SegmentHeader,
+    SegmentIndex,
+};
 
 #[test]
 fn test_empty_collection() {
@@ -119,3 +130,150 @@ fn test_resizable_semaphore_shrink() {
     drop(permit_1);
     assert!(sem.try_acquire().is_some());
 }
+
+#[test]
+fn test_rate_limiter() {
+    let mut rate_limiter = RateLimiter::new(
+        NonZeroU32::new(2).unwrap(),
+        Duration::from_secs(10),
+        NonZeroUsize::new(1).unwrap(),
+    );
+    let now = Instant::now();
+
+    assert!(rate_limiter.try_acquire_at(1, now));
+    assert!(rate_limiter.try_acquire_at(1, now));
+    // Limit is reached within the window
+    assert!(!rate_limiter.try_acquire_at(1, now + Duration::from_secs(5)));
+    // New window starts after interval
+    assert!(rate_limiter.try_acquire_at(1, now + Duration::from_secs(10)));
+
+    // Capacity is 1, so the other key evicts the first one and it gets a fresh window
+    assert!(rate_limiter.try_acquire_at(2, now + Duration::from_secs(10)));
+    assert!(rate_limiter.try_acquire_at(1, now + Duration::from_secs(10)));
+    assert!(rate_limiter.try_acquire_at(1, now + Duration::from_secs(10)));
+    assert!(!rate_limiter.try_acquire_at(1, now + Duration::from_secs(10)));
+}
+
+fn segment_header(segment_index: u64, prev_segment_header_hash: Blake2b256Hash) -> SegmentHeader {
+    SegmentHeader::V0 {
+        segment_index: SegmentIndex::from(segment_index),
+        segment_commitment: SegmentCommitment::default(),
+        prev_segment_header_hash,
+        last_archived_block: LastArchivedBlock {
+            number: segment_index as u32,
+            archived_progress: ArchivedBlockProgress::Complete,
+        },
+    }
+}
+
+fn announcement(segment_header: SegmentHeader) -> Vec<u8> {
+    SegmentHeaderAnnouncement { segment_header }.encode()
+}
+
+#[test]
+fn test_segment_header_announcement_validator() {
+    let segment_header_0 = segment_header(0, Blake2b256Hash::default());
+    let segment_header_1 = segment_header(1, segment_header_0.hash());
+    let segment_header_2 = segment_header(2, segment_header_1.hash());
+
+    // Nothing is known yet, can't validate anything
+    let validator = SegmentHeaderAnnouncementValidator::default();
+    assert!(matches!(
+        validator.validate(&announcement(segment_header_1)),
+        MessageAcceptance::Ignore
+    ));
+
+    let validator = SegmentHeaderAnnouncementValidator::new([segment_header_0]);
+
+    // Garbage is rejected
+    assert!(matches!(
+        validator.validate(&[1, 2, 3]),
+        MessageAcceptance::Reject
+    ));
+    // Known segment header is accepted
+    assert!(matches!(
+        validator.validate(&announcement(segment_header_0)),
+        MessageAcceptance::Accept
+    ));
+    // Gap can't be validated
+    assert!(matches!(
+        validator.validate(&announcement(segment_header_2)),
+        MessageAcceptance::Ignore
+    ));
+    // Wrong parent is rejected
+    assert!(matches!(
+        validator.validate(&announcement(segment_header(1, Blake2b256Hash::default()))),
+        MessageAcceptance::Reject
+    ));
+    // Correct continuation is not known to be valid until confirmed by trusted source
+    assert!(matches!(
+        validator.validate(&announcement(segment_header_1)),
+        MessageAcceptance::Ignore
+    ));
+    validator.add_segment_header(segment_header_1);
+    assert!(matches!(
+        validator.validate(&announcement(segment_header_1)),
+        MessageAcceptance::Accept
+    ));
+    // Conflicting announcement is rejected
+    assert!(matches!(
+        validator.validate(&announcement(segment_header(1, [1; 32]))),
+        MessageAcceptance::Reject
+    ));
+
+    // Trusted segment header allows to validate after a gap
+    let segment_header_5 = segment_header(5, [2; 32]);
+    let segment_header_6 = segment_header(6, segment_header_5.hash());
+    validator.add_segment_header(segment_header_5);
+    assert!(matches!(
+        validator.validate(&announcement(segment_header_5)),
+        MessageAcceptance::Accept
+    ));
+    assert!(matches!(
+        validator.validate(&announcement(segment_header_6)),
+        MessageAcceptance::Ignore
+    ));
+    assert!(matches!(
+        validator.validate(&announcement(segment_header(6, [3; 32]))),
+        MessageAcceptance::Reject
+    ));
+}
+
+#[test]
+fn test_segment_header_announcement_validator_forged_announcement() {
+    let segment_header_0 = segment_header(0, Blake2b256Hash::default());
+    let honest_segment_header_1 = segment_header(1, segment_header_0.hash());
+    // References correct parent, but commits to different content
+    let forged_segment_header_1 = SegmentHeader::V0 {
+        segment_index: SegmentIndex::ONE,
+        segment_commitment: SegmentCommitment::default(),
+        prev_segment_header_hash: segment_header_0.hash(),
+        last_archived_block: LastArchivedBlock {
+            number: 100,
+            archived_progress: ArchivedBlockProgress::Complete,
+        },
+    };
+
+    let validator = SegmentHeaderAnnouncementValidator::new([segment_header_0]);
+
+    // Forged announcement arrives first and can't be told apart from honest one
+    assert!(matches!(
+        validator.validate(&announcement(forged_segment_header_1)),
+        MessageAcceptance::Ignore
+    ));
+    // It must not prevent honest announcement from being accepted later
+    assert!(matches!(
+        validator.validate(&announcement(honest_segment_header_1)),
+        MessageAcceptance::Ignore
+    ));
+
+    validator.add_segment_header(honest_segment_header_1);
+    assert!(matches!(
+        validator.validate(&announcement(honest_segment_header_1)),
+        MessageAcceptance::Accept
+    ));
+    assert!(matches!(
+        validator.validate(&announcement(forged_segment_header_1)),
+        MessageAcceptance::Reject
+    ));
+}
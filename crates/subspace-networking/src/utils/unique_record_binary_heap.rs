@@ -181,6 +181,16 @@ where
         }
     }
 
+    /// `ilog2` of the Kademlia distance of the farthest key in the heap if limit is reached,
+    /// `None` otherwise since any key would be included.
+    pub fn max_distance_ilog2(&self) -> Option<u32> {
+        if !self.is_limit_reached() {
+            return None;
+        }
+
+        self.set.last()?.peer_distance().ilog2()
+    }
+
     /// Iterator over all keys in arbitrary order
     pub fn keys(&self) -> impl ExactSizeIterator<Item = &'_ K> + '_ {
         self.set.iter().map(|key| &key.key)
@@ -21,12 +21,18 @@ use domain_client_operator::Bootstrapper;
 use domain_runtime_primitives::opaque::Block as DomainBlock;
 use frame_benchmarking_cli::BenchmarkCmd;
 use futures::future::TryFutureExt;
+use log::info;
 #[cfg(feature = "pot")]
 use log::warn;
 use sc_cli::{ChainSpec, CliConfiguration, SubstrateCli};
+use sc_consensus::ImportQueue;
 use sc_consensus_slots::SlotProportion;
 use sc_executor::NativeExecutionDispatch;
 #[cfg(feature = "pot")]
+use sc_proof_of_time::source::external_timekeeper::{
+    ExternalTimekeeperConfig, ExternalTimekeeperSecret,
+};
+#[cfg(feature = "pot")]
 use sc_service::Configuration;
 use sc_service::PartialComponents;
 use sc_storage_monitor::StorageMonitorService;
@@ -39,14 +45,23 @@ use sp_io::SubstrateHostFunctions;
 use sp_messenger::messages::ChainId;
 use sp_wasm_interface::ExtendedHostFunctions;
 use std::sync::Arc;
+use std::time::Duration;
+use subspace_core_primitives::SegmentIndex;
+use subspace_networking::libp2p::Multiaddr;
+use subspace_networking::{
+    BandwidthLimits, MuxedTransportConfig, QuicTransportConfig, TransportConfig,
+};
 use subspace_node::domain::{
     DomainCli, DomainGenesisBlockBuilder, DomainInstanceStarter, DomainSubcommand,
     EVMDomainExecutorDispatch,
 };
-use subspace_node::{Cli, ExecutorDispatch, Subcommand};
+use subspace_node::{ArchiveSubcommand, Cli, ExecutorDispatch, Subcommand};
 use subspace_proof_of_space::chia::ChiaTable;
 use subspace_runtime::{Block, RuntimeApi};
-use subspace_service::{DsnConfig, SubspaceConfiguration, SubspaceNetworking};
+use subspace_service::archival_pieces::ArchivalPiecesConfig;
+use subspace_service::{
+    ConsensusRelayConfig, DsnConfig, SubspaceConfiguration, SubspaceNetworking,
+};
 
 type PosTable = ChiaTable;
 
@@ -97,6 +112,20 @@ fn set_default_ss58_version<C: AsRef<dyn ChainSpec>>(chain_spec: C) {
     }
 }
 
+fn chain_spec_dsn_bootstrap_nodes(
+    chain_spec: &dyn ChainSpec,
+) -> Result<Vec<Multiaddr>, sc_service::Error> {
+    Ok(chain_spec
+        .properties()
+        .get("dsnBootstrapNodes")
+        .map(|d| serde_json::from_value(d.clone()))
+        .transpose()
+        .map_err(|error| {
+            sc_service::Error::Other(format!("Failed to decode DSN bootstrap nodes: {error:?}"))
+        })?
+        .unwrap_or_default())
+}
+
 #[cfg(feature = "pot")]
 fn pot_external_entropy(
     consensus_chain_config: &Configuration,
@@ -133,6 +162,7 @@ fn main() -> Result<(), Error> {
 
     match &cli.subcommand {
         Some(Subcommand::Key(cmd)) => cmd.run(&cli)?,
+        Some(Subcommand::PotBench(cmd)) => cmd.run()?,
         Some(Subcommand::BuildSpec(cmd)) => {
             let runner = cli.create_runner(cmd)?;
             runner.sync_run(|config| cmd.run(config.chain_spec, config.network))?
@@ -221,6 +251,164 @@ fn main() -> Result<(), Error> {
                 ))
             })?;
         }
+        Some(Subcommand::Archive(ArchiveSubcommand::Export(cmd))) => {
+            let runner = cli.create_runner(cmd)?;
+            set_default_ss58_version(&runner.config().chain_spec);
+            runner.async_run(|config| {
+                let PartialComponents {
+                    client,
+                    task_manager,
+                    other,
+                    ..
+                } = subspace_service::new_partial::<PosTable, RuntimeApi, ExecutorDispatch>(
+                    &config,
+                    None,
+                    #[cfg(feature = "pot")]
+                    &pot_external_entropy(&config, &cli)?,
+                )?;
+                let kzg = other.subspace_link.kzg().clone();
+                let segment_headers_store = other.segment_headers_store;
+                let to = cmd.to.clone();
+                Ok((
+                    async move {
+                        let exported_segments = subspace_service::archive::export_archive(
+                            client.as_ref(),
+                            &segment_headers_store,
+                            kzg,
+                            &to,
+                        )?;
+                        info!("Exported {exported_segments} archived segments");
+
+                        Ok::<_, Error>(())
+                    },
+                    task_manager,
+                ))
+            })?;
+        }
+        Some(Subcommand::ImportArchive(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            set_default_ss58_version(&runner.config().chain_spec);
+            runner.async_run(|config| {
+                let PartialComponents {
+                    client,
+                    import_queue,
+                    task_manager,
+                    other,
+                    ..
+                } = subspace_service::new_partial::<PosTable, RuntimeApi, ExecutorDispatch>(
+                    &config,
+                    None,
+                    #[cfg(feature = "pot")]
+                    &pot_external_entropy(&config, &cli)?,
+                )?;
+                let kzg = other.subspace_link.kzg().clone();
+                let segment_headers_store = other.segment_headers_store;
+                let from = cmd.from.clone();
+                Ok((
+                    async move {
+                        let mut import_queue_service = import_queue.service();
+                        let imported_blocks = subspace_service::archive::import_archive(
+                            &segment_headers_store,
+                            client.as_ref(),
+                            &kzg,
+                            import_queue_service.as_mut(),
+                            &from,
+                        )
+                        .await?;
+                        info!("Imported {imported_blocks} blocks from archived segments");
+
+                        Ok::<_, Error>(())
+                    },
+                    task_manager,
+                ))
+            })?;
+        }
+        Some(Subcommand::CheckArchive(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            set_default_ss58_version(&runner.config().chain_spec);
+            runner.async_run(|config| {
+                let PartialComponents {
+                    client,
+                    task_manager,
+                    other,
+                    ..
+                } = subspace_service::new_partial::<PosTable, RuntimeApi, ExecutorDispatch>(
+                    &config,
+                    None,
+                    #[cfg(feature = "pot")]
+                    &pot_external_entropy(&config, &cli)?,
+                )?;
+                let kzg = other.subspace_link.kzg().clone();
+                let segment_headers_store = other.segment_headers_store;
+                let dsn_bootstrap_nodes = if cmd.dsn_bootstrap_nodes.is_empty() {
+                    chain_spec_dsn_bootstrap_nodes(&*config.chain_spec)?
+                } else {
+                    cmd.dsn_bootstrap_nodes.clone()
+                };
+                let check_dsn_pieces = cmd.check_dsn_pieces;
+                Ok((
+                    async move {
+                        let report = subspace_service::archive::check_archive(
+                            client.as_ref(),
+                            &segment_headers_store,
+                            kzg.clone(),
+                        )?;
+
+                        let known_segments = segment_headers_store
+                            .max_segment_index()
+                            .map_or(0, |segment_index| u64::from(segment_index) + 1);
+                        info!(
+                            "{} of {known_segments} archived segments match local blockchain",
+                            report.matching_segments
+                        );
+
+                        let mut pieces_report = None;
+                        if check_dsn_pieces {
+                            // Divergent segment is checked too to find out which segment header
+                            // pieces in DSN correspond to
+                            let segments_to_check =
+                                report.matching_segments + u64::from(report.divergence.is_some());
+                            let segment_headers = segment_headers_store
+                                .segment_headers(..SegmentIndex::from(segments_to_check));
+
+                            let checked_pieces = subspace_service::archive::check_archive_pieces(
+                                hex::encode(client.chain_info().genesis_hash),
+                                dsn_bootstrap_nodes,
+                                &kzg,
+                                &segment_headers,
+                            )
+                            .await?;
+                            info!(
+                                "Checked pieces from DSN: {} valid, {} invalid, {} missing",
+                                checked_pieces.valid_pieces,
+                                checked_pieces.invalid_pieces,
+                                checked_pieces.missing_pieces
+                            );
+                            pieces_report.replace(checked_pieces);
+                        }
+
+                        if let Some(divergence) = report.divergence {
+                            return Err(Error::Other(format!(
+                                "Archived segment {} doesn't match segment header known to the \
+                                node",
+                                divergence.known_segment_header.segment_index()
+                            )));
+                        }
+                        if let Some(pieces_report) = pieces_report {
+                            if !pieces_report.is_success() {
+                                return Err(Error::Other(format!(
+                                    "{} pieces retrieved from DSN are invalid and {} are missing",
+                                    pieces_report.invalid_pieces, pieces_report.missing_pieces
+                                )));
+                            }
+                        }
+
+                        Ok::<_, Error>(())
+                    },
+                    task_manager,
+                ))
+            })?;
+        }
         Some(Subcommand::PurgeChain(cmd)) => {
             // This is a compatibility layer to make sure we wipe old data from disks of our users
             if let Some(base_dir) = dirs::data_local_dir() {
@@ -472,18 +660,7 @@ fn main() -> Result<(), Error> {
                             })?;
 
                         let dsn_bootstrap_nodes = if cli.dsn_bootstrap_nodes.is_empty() {
-                            consensus_chain_config
-                                .chain_spec
-                                .properties()
-                                .get("dsnBootstrapNodes")
-                                .map(|d| serde_json::from_value(d.clone()))
-                                .transpose()
-                                .map_err(|error| {
-                                    sc_service::Error::Other(format!(
-                                        "Failed to decode DSN bootstrap nodes: {error:?}"
-                                    ))
-                                })?
-                                .unwrap_or_default()
+                            chain_spec_dsn_bootstrap_nodes(&*consensus_chain_config.chain_spec)?
                         } else {
                             cli.dsn_bootstrap_nodes
                         };
@@ -505,6 +682,32 @@ fn main() -> Result<(), Error> {
                                 base_path.config_dir(consensus_chain_config.chain_spec.id())
                             }),
                             listen_on: cli.dsn_listen_on,
+                            transports: TransportConfig::with_transport_configs(
+                                &cli.dsn_transports,
+                                MuxedTransportConfig {
+                                    timeout: Duration::from_secs(cli.dsn_tcp_timeout),
+                                    yamux_receive_window_size: cli.dsn_yamux_receive_window_size,
+                                    yamux_max_buffer_size: cli.dsn_yamux_max_buffer_size,
+                                    ..MuxedTransportConfig::default()
+                                },
+                                QuicTransportConfig {
+                                    handshake_timeout: Duration::from_secs(
+                                        cli.dsn_quic_handshake_timeout,
+                                    ),
+                                    max_idle_timeout: Duration::from_secs(
+                                        cli.dsn_quic_max_idle_timeout,
+                                    ),
+                                    keep_alive_interval: Duration::from_secs(
+                                        cli.dsn_quic_keep_alive_interval,
+                                    ),
+                                },
+                                MuxedTransportConfig {
+                                    timeout: Duration::from_secs(cli.dsn_ws_timeout),
+                                    yamux_receive_window_size: cli.dsn_yamux_receive_window_size,
+                                    yamux_max_buffer_size: cli.dsn_yamux_max_buffer_size,
+                                    ..MuxedTransportConfig::default()
+                                },
+                            ),
                             bootstrap_nodes: dsn_bootstrap_nodes,
                             reserved_peers: cli.dsn_reserved_peers,
                             // Override enabling private IPs with --dev
@@ -516,9 +719,53 @@ fn main() -> Result<(), Error> {
                             max_pending_out_connections: cli.dsn_pending_out_connections,
                             target_connections: cli.dsn_target_connections,
                             external_addresses: cli.dsn_external_addresses,
+                            request_response_bandwidth_limits: BandwidthLimits {
+                                upload_bytes_per_second: cli.dsn_upload_limit,
+                                download_bytes_per_second: cli.dsn_download_limit,
+                                max_requests_per_peer: cli.dsn_max_requests_per_peer,
+                                ..BandwidthLimits::default()
+                            },
                         }
                     };
 
+                    let block_relay = ConsensusRelayConfig {
+                        max_in_flight_requests: cli.block_relay_max_in_flight_requests,
+                        request_timeout: Duration::from_secs(cli.block_relay_request_timeout),
+                        max_consecutive_failures: cli.block_relay_max_consecutive_failures,
+                        cost_sample_size: cli.block_relay_cost_sample_size,
+                        fallback_downloads: cli.block_relay_fallback_downloads,
+                        ..ConsensusRelayConfig::default()
+                    };
+
+                    let archival_pieces = cli.archival_pieces.then(|| ArchivalPiecesConfig {
+                        directory: consensus_chain_config
+                            .base_path
+                            .config_dir(consensus_chain_config.chain_spec.id())
+                            .join("archival-pieces"),
+                        max_segments: cli.archival_pieces_max_segments,
+                        backfill: cli.archival_pieces_backfill,
+                    });
+
+                    #[cfg(feature = "pot")]
+                    let pot_external_timekeeper = match (
+                        cli.pot_external_timekeeper,
+                        &cli.pot_external_timekeeper_secret_file,
+                    ) {
+                        (Some(address), Some(secret_file)) => Some(ExternalTimekeeperConfig {
+                            address,
+                            secret: ExternalTimekeeperSecret::from_file(secret_file).map_err(
+                                |error| {
+                                    sc_service::Error::Other(format!(
+                                        "Failed to read external timekeeper secret from {}: \
+                                        {error}",
+                                        secret_file.display()
+                                    ))
+                                },
+                            )?,
+                        }),
+                        _ => None,
+                    };
+
                     let consensus_chain_config = SubspaceConfiguration {
                         base: consensus_chain_config,
                         // Domain node needs slots notifications for bundle production.
@@ -526,8 +773,14 @@ fn main() -> Result<(), Error> {
                         subspace_networking: SubspaceNetworking::Create { config: dsn_config },
                         sync_from_dsn: cli.sync_from_dsn,
                         enable_subspace_block_relay: cli.enable_subspace_block_relay,
+                        block_relay,
+                        index_object_mappings: cli.index_object_mappings,
+                        reindex_object_mappings: cli.reindex_object_mappings,
+                        archival_pieces,
                         #[cfg(feature = "pot")]
                         is_timekeeper: cli.timekeeper,
+                        #[cfg(feature = "pot")]
+                        pot_external_timekeeper,
                     };
 
                     let construct_domain_genesis_block_builder =
@@ -21,7 +21,10 @@ mod chain_spec_utils;
 pub mod domain;
 
 use clap::Parser;
-use sc_cli::{RunCmd, SubstrateCli};
+use sc_cli::{
+    CliConfiguration, DatabaseParams, ImportParams, PruningParams, RunCmd, SharedParams,
+    SubstrateCli,
+};
 use sc_executor::NativeExecutionDispatch;
 use sc_service::ChainSpec;
 use sc_storage_monitor::StorageMonitorParams;
@@ -29,8 +32,15 @@ use sc_subspace_chain_specs::ConsensusChainSpec;
 use sc_telemetry::serde_json;
 use serde_json::Value;
 use std::io::Write;
+#[cfg(feature = "pot")]
+use std::net::SocketAddr;
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use std::{fs, io};
+use subspace_core_primitives::{PotCheckpoints, PotSeed};
 use subspace_networking::libp2p::Multiaddr;
+use subspace_networking::TransportKind;
 
 /// Executor dispatch for subspace runtime
 pub struct ExecutorDispatch;
@@ -137,6 +147,229 @@ impl PurgeChainCmd {
     }
 }
 
+/// Export archived history of the consensus chain into a directory.
+#[derive(Debug, Clone, Parser)]
+pub struct ExportArchiveCmd {
+    /// Directory to write archived segments to, one file per segment.
+    #[arg(long)]
+    pub to: PathBuf,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub database_params: DatabaseParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub pruning_params: PruningParams,
+}
+
+impl CliConfiguration for ExportArchiveCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+
+    fn pruning_params(&self) -> Option<&PruningParams> {
+        Some(&self.pruning_params)
+    }
+}
+
+/// Check that archived history re-created from local blocks matches segment headers known to the
+/// node.
+#[derive(Debug, Clone, Parser)]
+pub struct CheckArchiveCmd {
+    /// Additionally retrieve pieces of checked segments from DSN and verify them against segment
+    /// headers, check fails if any piece is missing or invalid. This is slow since every piece of
+    /// every segment is downloaded.
+    #[arg(long)]
+    pub check_dsn_pieces: bool,
+
+    /// Bootstrap nodes for DSN, chain spec bootstrap nodes are used by default.
+    #[arg(long, requires = "check_dsn_pieces")]
+    pub dsn_bootstrap_nodes: Vec<Multiaddr>,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub database_params: DatabaseParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub pruning_params: PruningParams,
+}
+
+impl CliConfiguration for CheckArchiveCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+
+    fn pruning_params(&self) -> Option<&PruningParams> {
+        Some(&self.pruning_params)
+    }
+}
+
+/// Sub-commands for working with archived history.
+#[derive(Debug, clap::Subcommand)]
+pub enum ArchiveSubcommand {
+    /// Export archived segments (segment headers along with pieces) into a directory.
+    ///
+    /// Archived history is re-created from blocks in the database, so blocks must not be pruned.
+    Export(ExportArchiveCmd),
+}
+
+/// Import blocks from archived segments previously exported with `archive export`.
+#[derive(Debug, Clone, Parser)]
+pub struct ImportArchiveCmd {
+    /// Directory with archived segments.
+    #[arg(long)]
+    pub from: PathBuf,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub import_params: ImportParams,
+}
+
+impl CliConfiguration for ImportArchiveCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn import_params(&self) -> Option<&ImportParams> {
+        Some(&self.import_params)
+    }
+}
+
+/// Benchmark proof of time on this machine and derive number of iterations per slot from it.
+#[derive(Debug, Clone, Parser)]
+pub struct PotBenchCmd {
+    /// Target slot duration in milliseconds.
+    #[arg(long, default_value = "1000")]
+    pub slot_duration: NonZeroU64,
+
+    /// Safety margin in percent, recommended number of iterations is reduced by this much such
+    /// that slightly slower hardware can keep up.
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u8).range(0..100))]
+    pub safety_margin: u8,
+
+    /// Number of iterations to prove in each measurement round, rounded up to a multiple of the
+    /// number of checkpoints times two.
+    #[arg(long, default_value = "50000000")]
+    pub sample_iterations: NonZeroU32,
+
+    /// Number of measurement rounds, the fastest one is used for recommendation.
+    #[arg(long, default_value = "3")]
+    pub rounds: NonZeroUsize,
+
+    /// Print chain spec fragment (runtime genesis config) with recommended number of iterations.
+    #[arg(long)]
+    pub chain_spec_fragment: bool,
+}
+
+impl PotBenchCmd {
+    /// Run the PoT benchmark
+    pub fn run(&self) -> sc_cli::Result<()> {
+        let iterations_multiple = u32::from(PotCheckpoints::NUM_CHECKPOINTS.get()) * 2;
+        let sample_iterations = self
+            .sample_iterations
+            .get()
+            .checked_next_multiple_of(iterations_multiple)
+            .and_then(NonZeroU32::new)
+            .ok_or_else(|| sc_cli::Error::Input("Too many sample iterations".to_string()))?;
+        // Seed doesn't affect performance
+        let seed = PotSeed::default();
+
+        let mut best_prove_time = Duration::MAX;
+        let mut checkpoints = PotCheckpoints::default();
+        for round in 1..=self.rounds.get() {
+            let start = Instant::now();
+            checkpoints = subspace_proof_of_time::prove(seed, sample_iterations)
+                .map_err(|error| sc_cli::Error::Application(error.into()))?;
+            let prove_time = start.elapsed();
+            println!("Round {round}: proved {sample_iterations} iterations in {prove_time:?}");
+
+            best_prove_time = best_prove_time.min(prove_time);
+        }
+
+        let start = Instant::now();
+        let valid = subspace_proof_of_time::verify(seed, sample_iterations, &*checkpoints)
+            .map_err(|error| sc_cli::Error::Application(error.into()))?;
+        let verify_time = start.elapsed();
+
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .build()
+            .map_err(|error| sc_cli::Error::Application(error.into()))?;
+        let start = Instant::now();
+        let valid_parallel = subspace_proof_of_time::verify_parallel(
+            seed,
+            sample_iterations,
+            &*checkpoints,
+            &thread_pool,
+        )
+        .map_err(|error| sc_cli::Error::Application(error.into()))?;
+        let verify_parallel_time = start.elapsed();
+
+        if !(valid && valid_parallel) {
+            return Err(sc_cli::Error::Application(
+                "Verification of produced proof failed, this is a bug or hardware issue".into(),
+            ));
+        }
+
+        let iterations_per_second =
+            f64::from(sample_iterations.get()) / best_prove_time.as_secs_f64();
+        let slot_iterations = iterations_per_second * self.slot_duration.get() as f64 / 1000.0
+            * f64::from(100 - self.safety_margin)
+            / 100.0;
+        // Round down to a valid number of iterations
+        let slot_iterations = (slot_iterations as u32 / iterations_multiple * iterations_multiple)
+            .max(iterations_multiple);
+
+        println!("Proving speed: {iterations_per_second:.0} iterations/s");
+        println!(
+            "Verification speed relative to proving: {:.1}x sequential, {:.1}x with {} threads",
+            best_prove_time.as_secs_f64() / verify_time.as_secs_f64(),
+            best_prove_time.as_secs_f64() / verify_parallel_time.as_secs_f64(),
+            thread_pool.current_num_threads(),
+        );
+        println!(
+            "Recommended slot iterations for {}ms slots with {}% safety margin: {slot_iterations}",
+            self.slot_duration, self.safety_margin
+        );
+
+        if self.chain_spec_fragment {
+            let fragment = serde_json::json!({
+                "subspace": {
+                    "potSlotIterations": slot_iterations,
+                },
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&fragment)
+                    .map_err(|error| { sc_cli::Error::Application(error.into()) })?
+            );
+        }
+
+        Ok(())
+    }
+}
+
 /// Utilities for working with a node.
 #[derive(Debug, clap::Subcommand)]
 #[allow(clippy::large_enum_variant)]
@@ -160,6 +393,20 @@ pub enum Subcommand {
     /// Import blocks.
     ImportBlocks(sc_cli::ImportBlocksCmd),
 
+    /// Work with archived history.
+    #[clap(subcommand)]
+    Archive(ArchiveSubcommand),
+
+    /// Import blocks from archived segments.
+    ImportArchive(ImportArchiveCmd),
+
+    /// Check that archived history matches local blockchain.
+    CheckArchive(CheckArchiveCmd),
+
+    /// Benchmark proof of time to find out number of iterations per slot suitable for this
+    /// machine.
+    PotBench(PotBenchCmd),
+
     /// Remove the whole chain.
     PurgeChain(PurgeChainCmd),
 
@@ -246,6 +493,56 @@ pub struct Cli {
     #[arg(long, alias = "dsn-external-address")]
     pub dsn_external_addresses: Vec<Multiaddr>,
 
+    /// Transports to enable for DSN (comma-separated): `tcp`, `quic` and `ws` (WebSocket), listen
+    /// and bootstrap addresses must be supported by enabled transports.
+    #[arg(long, value_delimiter = ',', default_value = "tcp,quic,ws")]
+    pub dsn_transports: Vec<TransportKind>,
+
+    /// Timeout of TCP connection setup and protocol upgrade in seconds.
+    #[arg(long, default_value_t = 10)]
+    pub dsn_tcp_timeout: u64,
+
+    /// Timeout of WebSocket connection setup and protocol upgrade in seconds.
+    #[arg(long, default_value_t = 10)]
+    pub dsn_ws_timeout: u64,
+
+    /// Yamux receive window size per stream in bytes for TCP and WebSocket connections, values
+    /// below 262144 (256 KiB) are raised to 262144.
+    #[arg(long, default_value_t = 256 * 1024)]
+    pub dsn_yamux_receive_window_size: u32,
+
+    /// Max size of yamux buffer per stream in bytes for TCP and WebSocket connections.
+    #[arg(long, default_value_t = 1024 * 1024)]
+    pub dsn_yamux_max_buffer_size: usize,
+
+    /// Timeout of QUIC connection handshake in seconds.
+    #[arg(long, default_value_t = 5)]
+    pub dsn_quic_handshake_timeout: u64,
+
+    /// QUIC connection is closed after being idle for this number of seconds.
+    #[arg(long, default_value_t = 10)]
+    pub dsn_quic_max_idle_timeout: u64,
+
+    /// Interval of QUIC keep-alive packets in seconds, must be smaller than max idle timeout to
+    /// keep connections alive.
+    #[arg(long, default_value_t = 5)]
+    pub dsn_quic_keep_alive_interval: u64,
+
+    /// Max upload bandwidth in bytes per second used for responding to DSN requests, unlimited by
+    /// default. Segment headers are prioritized over pieces.
+    #[arg(long)]
+    pub dsn_upload_limit: Option<NonZeroU32>,
+
+    /// Max download bandwidth in bytes per second used by responses to DSN requests, unlimited by
+    /// default.
+    #[arg(long)]
+    pub dsn_download_limit: Option<NonZeroU32>,
+
+    /// Max number of DSN requests per protocol accepted from a single peer within a minute,
+    /// unlimited by default.
+    #[arg(long)]
+    pub dsn_max_requests_per_peer: Option<NonZeroU32>,
+
     /// Domain arguments
     ///
     /// The command-line arguments provided first will be passed to the embedded consensus node,
@@ -264,11 +561,76 @@ pub struct Cli {
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
     pub enable_subspace_block_relay: bool,
 
+    /// Maximum number of incoming block relay requests queued, requests beyond this are dropped.
+    #[arg(long, default_value = "100")]
+    pub block_relay_max_in_flight_requests: NonZeroUsize,
+
+    /// Timeout of individual block relay request in seconds.
+    #[arg(long, default_value_t = 20)]
+    pub block_relay_request_timeout: u64,
+
+    /// Number of compact block relay failures in a row, after which blocks are downloaded from the
+    /// peer in full.
+    #[arg(long, default_value = "3")]
+    pub block_relay_max_consecutive_failures: NonZeroU32,
+
+    /// Number of compact block downloads, after which the bytes received are compared with the
+    /// size of full blocks, blocks are downloaded from the peer in full if compact relay costs
+    /// noticeably more.
+    #[arg(long, default_value = "16")]
+    pub block_relay_cost_sample_size: NonZeroU32,
+
+    /// Number of blocks downloaded from the peer in full after falling back from compact block
+    /// relay, before compact block relay is tried again.
+    #[arg(long, default_value = "64")]
+    pub block_relay_fallback_downloads: NonZeroU32,
+
+    /// Index object mappings of archived history, such that applications can find objects by
+    /// their hashes using `subspace_objectMapping` and `subspace_segmentObjectMappings` RPC
+    /// methods.
+    #[arg(long)]
+    pub index_object_mappings: bool,
+
+    /// Build object mappings index from the whole archived history on startup, useful when
+    /// indexing is enabled on a node that was already synced. Requires blocks to not be pruned
+    /// (`--blocks-pruning archive`).
+    #[arg(long, requires = "index_object_mappings")]
+    pub reindex_object_mappings: bool,
+
+    /// Persist pieces of every archived segment locally, such that they can be retrieved using
+    /// `subspace_piece` RPC method and served to other nodes over DSN even when farmers don't have
+    /// them anymore. Node is discoverable as a provider of stored pieces in Kademlia DHT, which
+    /// requires it to run DHT in server mode.
+    #[arg(long)]
+    pub archival_pieces: bool,
+
+    /// Keep pieces of only this number of the most recent segments in archival pieces store,
+    /// pieces of all segments are kept by default.
+    #[arg(long, requires = "archival_pieces")]
+    pub archival_pieces_max_segments: Option<NonZeroU64>,
+
+    /// Store pieces of segments that were archived before archival pieces store was enabled, which
+    /// are otherwise never stored. Archived history is re-created from blocks in the database on
+    /// startup for this, so blocks must not be pruned (`--blocks-pruning archive`).
+    #[arg(long, requires = "archival_pieces")]
+    pub archival_pieces_backfill: bool,
+
     /// Assigned PoT role for this node.
     #[arg(long)]
     #[cfg(feature = "pot")]
     pub timekeeper: bool,
 
+    /// Address of external timekeeper (see `subspace-pot-timekeeper`) to receive proofs of time
+    /// from, proofs are still verified and gossip is used as a fallback.
+    #[arg(long, requires = "pot_external_timekeeper_secret_file")]
+    #[cfg(feature = "pot")]
+    pub pot_external_timekeeper: Option<SocketAddr>,
+
+    /// File with secret shared with external timekeeper in hex format.
+    #[arg(long, requires = "pot_external_timekeeper")]
+    #[cfg(feature = "pot")]
+    pub pot_external_timekeeper_secret_file: Option<PathBuf>,
+
     /// External entropy, used initially when PoT chain starts to derive the first seed
     #[arg(long, value_parser = parse_pot_external_entropy)]
     #[cfg(feature = "pot")]
@@ -0,0 +1,141 @@
+//! Simple utility for retrieving archived objects from DSN
+
+use clap::Parser;
+use std::error::Error;
+use std::path::PathBuf;
+use subspace_core_primitives::objects::GlobalObject;
+use subspace_core_primitives::{Blake2b256Hash, PieceIndex};
+use subspace_networking::libp2p::identity::Keypair;
+use subspace_networking::libp2p::Multiaddr;
+use subspace_networking::utils::piece_provider::{NoPieceValidator, PieceProvider};
+use subspace_networking::{
+    Config, PeerInfoProvider, PieceByIndexRequestHandler, PieceChunksRequestHandler,
+    TransportConfig, TransportKind,
+};
+use subspace_object_fetcher::ObjectFetcher;
+use tracing::{debug, info, Level};
+use tracing_subscriber::fmt::Subscriber;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Fetch archived object by its hash and mapping
+#[derive(Debug, Parser)]
+#[clap(about, version)]
+struct Command {
+    /// Multiaddresses of bootstrap nodes to connect to on startup, multiple are supported
+    #[arg(long, alias = "bootstrap-node", required = true)]
+    bootstrap_nodes: Vec<Multiaddr>,
+    /// Transports to enable (comma-separated): `tcp`, `quic` and `ws` (WebSocket), bootstrap
+    /// addresses must be supported by enabled transports.
+    #[arg(long, value_delimiter = ',', default_value = "tcp,quic,ws")]
+    transports: Vec<TransportKind>,
+    /// Determines whether we allow keeping non-global (private, shared, loopback..) addresses in Kademlia DHT.
+    #[arg(long, default_value_t = false)]
+    enable_private_ips: bool,
+    /// Protocol version for libp2p stack, should be set as genesis hash of the blockchain for
+    /// production use.
+    #[arg(long)]
+    protocol_version: String,
+    /// Index of the piece that contains the beginning of the object
+    #[arg(long)]
+    piece_index: u64,
+    /// Offset of the object within raw record of the piece
+    #[arg(long)]
+    offset: u32,
+    /// Blake2b-256 hash of the object in hex format
+    #[arg(long, value_parser = parse_hash)]
+    hash: Blake2b256Hash,
+    /// File to write object bytes to, object is printed in hex format if not specified
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn parse_hash(s: &str) -> Result<Blake2b256Hash, String> {
+    let mut hash = Blake2b256Hash::default();
+    hex::decode_to_slice(s, &mut hash).map_err(|error| error.to_string())?;
+
+    Ok(hash)
+}
+
+fn init_logging() {
+    // set default log to info if the RUST_LOG is not set.
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(Level::INFO.into())
+        .from_env_lossy();
+
+    let builder = Subscriber::builder().with_env_filter(env_filter).finish();
+
+    builder.init()
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    init_logging();
+
+    let Command {
+        bootstrap_nodes,
+        transports,
+        enable_private_ips,
+        protocol_version,
+        piece_index,
+        offset,
+        hash,
+        output,
+    } = Command::parse();
+
+    debug!(
+        "Libp2p protocol stack instantiated with version: {} ",
+        protocol_version
+    );
+
+    let config = Config {
+        // Object fetcher only makes outgoing requests
+        listen_on: Vec::new(),
+        transports: TransportConfig::with_transports(&transports),
+        allow_non_global_addresses_in_dht: enable_private_ips,
+        // Handlers are only registered to be able to make requests
+        request_response_protocols: vec![
+            PieceByIndexRequestHandler::create(|_, _| async { None }),
+            PieceChunksRequestHandler::create(|_, _| async { None }),
+        ],
+        // Object fetcher can't validate gossip messages, so it doesn't participate in it
+        gossipsub: None,
+        bootstrap_addresses: bootstrap_nodes,
+        ..Config::new(
+            protocol_version,
+            Keypair::generate_ed25519(),
+            (),
+            Some(PeerInfoProvider::new_client()),
+        )
+    };
+    let (node, mut node_runner) = subspace_networking::construct(config)?;
+
+    tokio::spawn(async move {
+        node_runner.run().await;
+    });
+
+    node.bootstrap().await?;
+
+    // Pieces are not validated individually, object hash is checked instead
+    let piece_provider = PieceProvider::new(node, None::<NoPieceValidator>);
+    let object_fetcher = ObjectFetcher::new(piece_provider);
+
+    let mapping = GlobalObject::V0 {
+        piece_index: PieceIndex::from(piece_index),
+        offset,
+    };
+    let object = object_fetcher.fetch_object(hash, mapping).await?;
+
+    info!(size = %object.len(), "Object retrieved successfully");
+
+    match output {
+        Some(output) => {
+            tokio::fs::write(output, object).await?;
+        }
+        None => {
+            println!("{}", hex::encode(object));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,468 @@
+//! Retrieval of objects archived in the history of Subspace Network.
+//!
+//! Objects are located using [`GlobalObject`] mappings: index of the source piece that contains
+//! the beginning of SCALE-encoded object bytes and offset within its raw record. Objects might
+//! span multiple pieces and even continue in the next segment, in which case segment items
+//! encoding at the beginning of that segment is skipped. Retrieved object is verified against its
+//! Blake2b hash, such that pieces don't need to be validated separately.
+//!
+//! Small objects are retrieved as a few record chunks instead of the whole piece when piece
+//! getter supports it, falling back to whole pieces otherwise.
+
+#![warn(missing_docs)]
+
+#[cfg(test)]
+mod tests;
+
+use async_trait::async_trait;
+use parity_scale_codec::{Compact, CompactLen, Decode};
+use std::error::Error;
+use std::ops::Range;
+use subspace_archiving::archiver::SegmentItem;
+use subspace_core_primitives::crypto::{blake2b_256_hash, Scalar};
+use subspace_core_primitives::objects::GlobalObject;
+use subspace_core_primitives::{
+    ArchivedHistorySegment, Blake2b256Hash, Piece, PieceIndex, RawRecord, Record,
+    RecordChunksProof, RecordedHistorySegment, SegmentIndex,
+};
+use subspace_networking::utils::piece_provider::{PieceProvider, PieceValidator, RetryPolicy};
+use subspace_networking::PieceChunksRequest;
+use tracing::{debug, trace};
+
+/// Max size of compact-encoded `u32` length prefix of the object.
+const MAX_LENGTH_PREFIX_SIZE: usize = 5;
+/// Max number of zero bytes at the end of the segment after the last segment item.
+///
+/// When the last block in a segment is split, length prefix of its remaining bytes might take
+/// fewer bytes than archiver assumed, leaving the very end of the segment unused.
+const MAX_SEGMENT_PADDING: usize = 3;
+/// Encoded variant of [`SegmentItem::BlockContinuation`].
+const BLOCK_CONTINUATION_VARIANT: u8 = 3;
+/// Number of retries when retrieving individual pieces using [`PieceProvider`].
+const PIECE_PROVIDER_RETRIES: u16 = 3;
+
+/// Object fetcher error
+#[derive(Debug, thiserror::Error)]
+pub enum ObjectFetcherError {
+    /// Objects are only stored in source pieces
+    #[error("Piece {piece_index} is a parity piece, objects are only stored in source pieces")]
+    NotSourcePiece {
+        /// Piece index
+        piece_index: PieceIndex,
+    },
+    /// Offset is outside of the raw record
+    #[error(
+        "Offset {offset} is outside of the raw record of {} bytes",
+        RawRecord::SIZE
+    )]
+    InvalidOffset {
+        /// Offset
+        offset: u32,
+    },
+    /// Piece was not found
+    #[error("Piece {piece_index} was not found")]
+    PieceNotFound {
+        /// Piece index
+        piece_index: PieceIndex,
+    },
+    /// Failed to get piece
+    #[error("Failed to get piece {piece_index}: {error}")]
+    PieceGetter {
+        /// Piece index
+        piece_index: PieceIndex,
+        /// Low-level error
+        error: Box<dyn Error + Send + Sync + 'static>,
+    },
+    /// Failed to decode object length
+    #[error("Failed to decode object length: {0}")]
+    ObjectLengthDecoding(parity_scale_codec::Error),
+    /// Object length is larger than a segment, which is not possible for valid object
+    #[error("Object length {length} is larger than a segment")]
+    InvalidObjectLength {
+        /// Decoded object length
+        length: u32,
+    },
+    /// Failed to decode segment items at the beginning of the segment
+    #[error("Failed to decode beginning of segment {segment_index}: {error}")]
+    SegmentDecoding {
+        /// Segment index
+        segment_index: SegmentIndex,
+        /// Low-level error
+        error: parity_scale_codec::Error,
+    },
+    /// Segment doesn't start with continuation of the block from the previous segment
+    #[error("Segment {segment_index} doesn't start with block continuation")]
+    NoBlockContinuation {
+        /// Segment index
+        segment_index: SegmentIndex,
+    },
+    /// Object was not found in the history
+    #[error("Object ends past the end of the block continuation")]
+    UnexpectedEndOfData,
+    /// Object doesn't match expected hash
+    #[error(
+        "Object hash mismatch, expected {}, actual {}",
+        hex::encode(expected),
+        hex::encode(actual)
+    )]
+    HashMismatch {
+        /// Expected object hash
+        expected: Blake2b256Hash,
+        /// Actual object hash
+        actual: Blake2b256Hash,
+    },
+}
+
+/// Source of pieces for object retrieval.
+#[async_trait]
+pub trait PieceGetter {
+    /// Get piece by its index, `Ok(None)` means piece was not found.
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>>;
+
+    /// Get `chunks` range of the record of the piece, `Ok(None)` means chunks were not found.
+    ///
+    /// Proof doesn't need to be validated since object hash is verified instead. Default
+    /// implementation doesn't support chunks, such that whole pieces are always retrieved.
+    async fn get_piece_chunks(
+        &self,
+        _piece_index: PieceIndex,
+        _chunks: Range<u32>,
+    ) -> Result<Option<RecordChunksProof>, Box<dyn Error + Send + Sync + 'static>> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl<PV> PieceGetter for PieceProvider<PV>
+where
+    PV: PieceValidator,
+{
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        PieceProvider::get_piece(
+            self,
+            piece_index,
+            RetryPolicy::Limited(PIECE_PROVIDER_RETRIES),
+        )
+        .await
+    }
+
+    async fn get_piece_chunks(
+        &self,
+        piece_index: PieceIndex,
+        chunks: Range<u32>,
+    ) -> Result<Option<RecordChunksProof>, Box<dyn Error + Send + Sync + 'static>> {
+        Ok(PieceProvider::get_piece_chunks(self, piece_index, chunks).await)
+    }
+}
+
+/// Block continuation at the beginning of the segment.
+struct BlockContinuation {
+    /// Piece the last collected bytes belong to
+    piece_index: PieceIndex,
+    /// Collected bytes of the block continuation
+    bytes: Vec<u8>,
+    /// Number of bytes of the block continuation that were not collected yet
+    remaining: usize,
+}
+
+/// Fetches objects from archived history using provided piece getter.
+#[derive(Debug)]
+pub struct ObjectFetcher<PG> {
+    piece_getter: PG,
+}
+
+impl<PG> ObjectFetcher<PG>
+where
+    PG: PieceGetter,
+{
+    /// Create new instance
+    pub fn new(piece_getter: PG) -> Self {
+        Self { piece_getter }
+    }
+
+    /// Fetch object with provided hash and mapping, returns object bytes without length prefix.
+    pub async fn fetch_object(
+        &self,
+        hash: Blake2b256Hash,
+        mapping: GlobalObject,
+    ) -> Result<Vec<u8>, ObjectFetcherError> {
+        let mut piece_index = mapping.piece_index();
+        let offset = mapping.offset();
+
+        if piece_index.position() % 2 != 0 {
+            return Err(ObjectFetcherError::NotSourcePiece { piece_index });
+        }
+        if offset as usize >= RawRecord::SIZE {
+            return Err(ObjectFetcherError::InvalidOffset { offset });
+        }
+
+        trace!(%piece_index, %offset, hash = %hex::encode(hash), "Fetching object");
+
+        if let Some(object) = self.fetch_small_object(hash, piece_index, offset).await {
+            return Ok(object);
+        }
+
+        // Object bytes from the offset until the end of the segment or until the end of the object
+        let mut data = self.read_raw_record(piece_index).await?;
+        data.drain(..offset as usize);
+
+        loop {
+            if let Some(object) = decode_object(&data, &[])? {
+                return verify_object(hash, object);
+            }
+
+            match next_source_piece_index(piece_index) {
+                Some(next_piece_index) => {
+                    piece_index = next_piece_index;
+                    data.extend(self.read_raw_record(piece_index).await?);
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        // Object continues in the next segment, possibly with a few bytes of padding at the end of
+        // this segment that need to be skipped
+        let segment_index = piece_index.segment_index() + SegmentIndex::ONE;
+        debug!(
+            %segment_index,
+            hash = %hex::encode(hash),
+            "Object continues in the next segment"
+        );
+        let mut continuation = self.read_block_continuation(segment_index).await?;
+
+        loop {
+            let mut needs_more_data = false;
+
+            for padding in 0..=MAX_SEGMENT_PADDING.min(data.len()) {
+                let head = &data[..data.len() - padding];
+
+                match decode_object(head, &continuation.bytes) {
+                    Ok(Some(object)) => {
+                        if blake2b_256_hash(&object) == hash {
+                            return Ok(object);
+                        }
+                    }
+                    Ok(None) => {
+                        needs_more_data = true;
+                    }
+                    Err(error) => {
+                        trace!(%error, %padding, "Failed to decode object");
+                    }
+                }
+            }
+
+            if !needs_more_data || continuation.remaining == 0 {
+                break;
+            }
+
+            let next_piece_index = next_source_piece_index(continuation.piece_index)
+                .ok_or(ObjectFetcherError::UnexpectedEndOfData)?;
+            let raw_record = self.read_raw_record(next_piece_index).await?;
+            let new_bytes = continuation.remaining.min(raw_record.len());
+            continuation
+                .bytes
+                .extend_from_slice(&raw_record[..new_bytes]);
+            continuation.remaining -= new_bytes;
+            continuation.piece_index = next_piece_index;
+        }
+
+        // Report hash of the object as if there was no padding for troubleshooting purposes
+        match decode_object(&data, &continuation.bytes)? {
+            Some(object) => verify_object(hash, object),
+            None => Err(ObjectFetcherError::UnexpectedEndOfData),
+        }
+    }
+
+    /// Try to fetch small object using only a few record chunks of the piece, returns `None` if
+    /// object doesn't fit into them or chunks can't be retrieved.
+    async fn fetch_small_object(
+        &self,
+        hash: Blake2b256Hash,
+        piece_index: PieceIndex,
+        offset: u32,
+    ) -> Option<Vec<u8>> {
+        let safe_bytes = Scalar::SAFE_BYTES as u32;
+        let first_chunk_offset = offset / safe_bytes;
+        let chunks = first_chunk_offset
+            ..(first_chunk_offset + PieceChunksRequest::MAX_CHUNKS).min(Record::NUM_CHUNKS as u32);
+
+        let proof = match self
+            .piece_getter
+            .get_piece_chunks(piece_index, chunks.clone())
+            .await
+        {
+            Ok(Some(proof)) => proof,
+            Ok(None) => {
+                return None;
+            }
+            Err(error) => {
+                debug!(%piece_index, ?chunks, %error, "Failed to get piece chunks");
+                return None;
+            }
+        };
+
+        if proof.first_chunk_offset != chunks.start || proof.chunks.len() != chunks.len() {
+            debug!(%piece_index, ?chunks, "Piece getter returned different chunks");
+            return None;
+        }
+
+        let data = proof
+            .raw_bytes()
+            .skip((offset % safe_bytes) as usize)
+            .collect::<Vec<u8>>();
+        let object = match decode_object(&data, &[]) {
+            Ok(Some(object)) => object,
+            Ok(None) => {
+                trace!(%piece_index, %offset, "Object doesn't fit into piece chunks");
+                return None;
+            }
+            Err(error) => {
+                trace!(%piece_index, %offset, %error, "Failed to decode object from piece chunks");
+                return None;
+            }
+        };
+
+        // Chunks might have been returned by a malicious peer, in which case the whole piece is
+        // retrieved instead
+        if blake2b_256_hash(&object) != hash {
+            debug!(%piece_index, %offset, "Object from piece chunks doesn't match its hash");
+            return None;
+        }
+
+        Some(object)
+    }
+
+    /// Read block continuation at the beginning of the segment, skipping encoding of the segment
+    /// and parent segment header.
+    async fn read_block_continuation(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<BlockContinuation, ObjectFetcherError> {
+        let piece_index = segment_index.first_piece_index();
+        let raw_record = self.read_raw_record(piece_index).await?;
+        let mut input = raw_record.as_slice();
+
+        let segment_decoding_error = |error| ObjectFetcherError::SegmentDecoding {
+            segment_index,
+            error,
+        };
+
+        // Segment enum variant
+        u8::decode(&mut input).map_err(segment_decoding_error)?;
+        if !matches!(
+            SegmentItem::decode(&mut input).map_err(segment_decoding_error)?,
+            SegmentItem::ParentSegmentHeader(_)
+        ) {
+            return Err(ObjectFetcherError::NoBlockContinuation { segment_index });
+        }
+        if u8::decode(&mut input).map_err(segment_decoding_error)? != BLOCK_CONTINUATION_VARIANT {
+            return Err(ObjectFetcherError::NoBlockContinuation { segment_index });
+        }
+        let Compact(length) = Compact::<u32>::decode(&mut input).map_err(segment_decoding_error)?;
+        let length = length as usize;
+
+        let bytes = input[..length.min(input.len())].to_vec();
+        let remaining = length - bytes.len();
+
+        Ok(BlockContinuation {
+            piece_index,
+            bytes,
+            remaining,
+        })
+    }
+
+    /// Read raw record of the source piece.
+    async fn read_raw_record(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Vec<u8>, ObjectFetcherError> {
+        let piece = self
+            .piece_getter
+            .get_piece(piece_index)
+            .await
+            .map_err(|error| ObjectFetcherError::PieceGetter { piece_index, error })?
+            .ok_or(ObjectFetcherError::PieceNotFound { piece_index })?;
+
+        let mut raw_record = Vec::with_capacity(RawRecord::SIZE);
+        // Source record chunks only contain payload within first `Scalar::SAFE_BYTES` bytes
+        for chunk in piece.record().iter() {
+            raw_record.extend_from_slice(&chunk[..Scalar::SAFE_BYTES]);
+        }
+
+        Ok(raw_record)
+    }
+}
+
+/// Index of the next source piece within the same segment, `None` if this is the last source
+/// piece of the segment.
+fn next_source_piece_index(piece_index: PieceIndex) -> Option<PieceIndex> {
+    // Source and parity pieces are interleaved
+    let position = piece_index.position() as usize + 2;
+
+    (position < ArchivedHistorySegment::NUM_PIECES)
+        .then(|| PieceIndex::from(u64::from(piece_index) + 2))
+}
+
+/// Decode SCALE-encoded object bytes from concatenation of `head` and `tail`, returns `None` if
+/// there is not enough data.
+fn decode_object(head: &[u8], tail: &[u8]) -> Result<Option<Vec<u8>>, ObjectFetcherError> {
+    let length_prefix = head
+        .iter()
+        .chain(tail)
+        .take(MAX_LENGTH_PREFIX_SIZE)
+        .copied()
+        .collect::<Vec<u8>>();
+
+    let length = match Compact::<u32>::decode(&mut length_prefix.as_slice()) {
+        Ok(Compact(length)) => length,
+        Err(error) => {
+            if length_prefix.len() < MAX_LENGTH_PREFIX_SIZE {
+                return Ok(None);
+            }
+
+            return Err(ObjectFetcherError::ObjectLengthDecoding(error));
+        }
+    };
+
+    if length as usize >= RecordedHistorySegment::SIZE {
+        return Err(ObjectFetcherError::InvalidObjectLength { length });
+    }
+
+    let start = Compact::<u32>::compact_len(&length);
+    let end = start + length as usize;
+
+    if head.len() + tail.len() < end {
+        return Ok(None);
+    }
+
+    let mut object = Vec::with_capacity(length as usize);
+    if start < head.len() {
+        object.extend_from_slice(&head[start..end.min(head.len())]);
+    }
+    if end > head.len() {
+        object.extend_from_slice(&tail[start.saturating_sub(head.len())..end - head.len()]);
+    }
+
+    Ok(Some(object))
+}
+
+fn verify_object(hash: Blake2b256Hash, object: Vec<u8>) -> Result<Vec<u8>, ObjectFetcherError> {
+    let actual = blake2b_256_hash(&object);
+
+    if actual != hash {
+        return Err(ObjectFetcherError::HashMismatch {
+            expected: hash,
+            actual,
+        });
+    }
+
+    Ok(object)
+}
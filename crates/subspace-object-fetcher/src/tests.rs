@@ -0,0 +1,339 @@
+use crate::{ObjectFetcher, ObjectFetcherError, PieceGetter};
+use async_trait::async_trait;
+use parity_scale_codec::{Compact, Encode};
+use rand::{thread_rng, Rng};
+use std::collections::HashMap;
+use std::error::Error;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use subspace_archiving::archiver::{create_record_chunks_proof, Archiver, NewArchivedSegment};
+use subspace_core_primitives::crypto::blake2b_256_hash;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::objects::{BlockObject, BlockObjectMapping, GlobalObject};
+use subspace_core_primitives::{
+    Blake2b256Hash, Piece, PieceIndex, RawRecord, RecordChunksProof, RecordedHistorySegment,
+    SegmentIndex,
+};
+
+#[derive(Default)]
+struct TestPieceGetter {
+    pieces: HashMap<PieceIndex, Piece>,
+}
+
+#[async_trait]
+impl PieceGetter for TestPieceGetter {
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        Ok(self.pieces.get(&piece_index).cloned())
+    }
+}
+
+impl TestPieceGetter {
+    fn add_segments(&mut self, archived_segments: &[NewArchivedSegment]) {
+        for archived_segment in archived_segments {
+            let first_piece_index = archived_segment
+                .segment_header
+                .segment_index()
+                .first_piece_index();
+            for (position, piece) in archived_segment.pieces.iter().enumerate() {
+                self.pieces.insert(
+                    first_piece_index + PieceIndex::from(position as u64),
+                    Piece::from(piece),
+                );
+            }
+        }
+    }
+}
+
+/// Piece getter that also serves piece chunks and counts requests of whole pieces.
+struct TestPieceChunksGetter {
+    piece_getter: TestPieceGetter,
+    kzg: Kzg,
+    /// Whether to return chunks of a wrong piece, like malicious peer would
+    wrong_chunks: bool,
+    piece_requests: AtomicUsize,
+}
+
+#[async_trait]
+impl PieceGetter for TestPieceChunksGetter {
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        self.piece_requests.fetch_add(1, Ordering::Relaxed);
+
+        self.piece_getter.get_piece(piece_index).await
+    }
+
+    async fn get_piece_chunks(
+        &self,
+        mut piece_index: PieceIndex,
+        chunks: Range<u32>,
+    ) -> Result<Option<RecordChunksProof>, Box<dyn Error + Send + Sync + 'static>> {
+        if self.wrong_chunks {
+            piece_index = piece_index + PieceIndex::from(2);
+        }
+        let Some(piece) = self.piece_getter.pieces.get(&piece_index) else {
+            return Ok(None);
+        };
+
+        Ok(Some(create_record_chunks_proof(&self.kzg, piece, chunks)?))
+    }
+}
+
+/// Write SCALE-encoded object of specified size at specified offset of the block, returns object
+/// with its block mapping.
+fn write_object(block: &mut [u8], offset: usize, size: usize) -> (Vec<u8>, BlockObject) {
+    let mut object = vec![0u8; size];
+    thread_rng().fill(object.as_mut_slice());
+
+    let encoded_object = object.encode();
+    block[offset..][..encoded_object.len()].copy_from_slice(&encoded_object);
+
+    let block_object = BlockObject::V0 {
+        hash: blake2b_256_hash(&object),
+        offset: offset as u32,
+    };
+
+    (object, block_object)
+}
+
+/// Find global mapping of the object with specified hash in archived segments.
+fn find_mapping(archived_segments: &[NewArchivedSegment], hash: Blake2b256Hash) -> GlobalObject {
+    archived_segments
+        .iter()
+        .flat_map(|archived_segment| {
+            let first_piece_index = archived_segment
+                .segment_header
+                .segment_index()
+                .first_piece_index();
+
+            archived_segment.object_mapping.iter().enumerate().flat_map(
+                move |(source_position, piece_object_mapping)| {
+                    // Source pieces are at even positions
+                    let piece_index =
+                        first_piece_index + PieceIndex::from(source_position as u64 * 2);
+
+                    piece_object_mapping
+                        .objects
+                        .iter()
+                        .map(move |piece_object| (piece_index, piece_object))
+                },
+            )
+        })
+        .find_map(|(piece_index, piece_object)| {
+            (piece_object.hash() == hash).then_some(GlobalObject::V0 {
+                piece_index,
+                offset: piece_object.offset(),
+            })
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn fetch_objects() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver = Archiver::new(kzg, RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+
+    let mut block_0 = vec![0u8; RecordedHistorySegment::SIZE / 2];
+    thread_rng().fill(block_0.as_mut_slice());
+    // Fits into a single piece
+    let (small_object, small_block_object) = write_object(&mut block_0, 1000, 100);
+    // Spans multiple pieces
+    let (large_object, large_block_object) =
+        write_object(&mut block_0, RawRecord::SIZE * 2 - 10, RawRecord::SIZE * 3);
+
+    let mut block_1 = vec![0u8; RecordedHistorySegment::SIZE];
+    thread_rng().fill(block_1.as_mut_slice());
+    // Spans segment boundary, first half of the segment is occupied by the previous block
+    let (boundary_object, boundary_block_object) = write_object(
+        &mut block_1,
+        RecordedHistorySegment::SIZE / 2 - RawRecord::SIZE,
+        RawRecord::SIZE * 2,
+    );
+
+    let objects = [
+        (small_object, small_block_object),
+        (large_object, large_block_object),
+        (boundary_object, boundary_block_object),
+    ];
+
+    let mut archived_segments = archiver.add_block(
+        block_0,
+        BlockObjectMapping {
+            objects: vec![small_block_object, large_block_object],
+        },
+        true,
+    );
+    archived_segments.extend(archiver.add_block(
+        block_1,
+        BlockObjectMapping {
+            objects: vec![boundary_block_object],
+        },
+        true,
+    ));
+    // Make sure continuation of the last block is archived too
+    archived_segments.extend(archiver.add_block(
+        vec![0u8; RecordedHistorySegment::SIZE],
+        BlockObjectMapping::default(),
+        true,
+    ));
+    assert!(archived_segments.len() >= 2);
+
+    let mut piece_getter = TestPieceGetter::default();
+    piece_getter.add_segments(&archived_segments);
+    let object_fetcher = ObjectFetcher::new(piece_getter);
+
+    for (object, block_object) in objects {
+        let hash = block_object.hash();
+        let mapping = find_mapping(&archived_segments, hash);
+
+        assert_eq!(
+            object_fetcher.fetch_object(hash, mapping).await.unwrap(),
+            object
+        );
+    }
+
+    // Boundary object must start in the first segment and end in the next one
+    let boundary_mapping = find_mapping(&archived_segments, boundary_block_object.hash());
+    assert_eq!(
+        boundary_mapping.piece_index().segment_index(),
+        SegmentIndex::ZERO
+    );
+
+    let small_mapping = find_mapping(&archived_segments, small_block_object.hash());
+
+    assert!(matches!(
+        object_fetcher
+            .fetch_object(Blake2b256Hash::default(), small_mapping)
+            .await,
+        Err(ObjectFetcherError::HashMismatch { .. })
+    ));
+
+    assert!(matches!(
+        object_fetcher
+            .fetch_object(
+                small_block_object.hash(),
+                GlobalObject::V0 {
+                    piece_index: small_mapping.piece_index() + PieceIndex::ONE,
+                    offset: small_mapping.offset(),
+                },
+            )
+            .await,
+        Err(ObjectFetcherError::NotSourcePiece { .. })
+    ));
+
+    assert!(matches!(
+        object_fetcher
+            .fetch_object(
+                small_block_object.hash(),
+                GlobalObject::V0 {
+                    piece_index: small_mapping.piece_index(),
+                    offset: RawRecord::SIZE as u32,
+                },
+            )
+            .await,
+        Err(ObjectFetcherError::InvalidOffset { .. })
+    ));
+}
+
+#[tokio::test]
+async fn fetch_objects_from_piece_chunks() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+
+    let mut block = vec![0u8; RecordedHistorySegment::SIZE];
+    thread_rng().fill(block.as_mut_slice());
+    let (small_object, small_block_object) = write_object(&mut block, 1000, 100);
+    let (large_object, large_block_object) = write_object(&mut block, RawRecord::SIZE * 2, 1000);
+
+    let archived_segments = archiver.add_block(
+        block,
+        BlockObjectMapping {
+            objects: vec![small_block_object, large_block_object],
+        },
+        true,
+    );
+
+    for wrong_chunks in [false, true] {
+        let mut piece_getter = TestPieceGetter::default();
+        piece_getter.add_segments(&archived_segments);
+        let object_fetcher = ObjectFetcher::new(TestPieceChunksGetter {
+            piece_getter,
+            kzg: kzg.clone(),
+            wrong_chunks,
+            piece_requests: AtomicUsize::new(0),
+        });
+
+        let hash = small_block_object.hash();
+        let mapping = find_mapping(&archived_segments, hash);
+        assert_eq!(
+            object_fetcher.fetch_object(hash, mapping).await.unwrap(),
+            small_object
+        );
+        // Small object is retrieved from piece chunks unless they are wrong, in which case the
+        // whole piece is retrieved instead
+        assert_eq!(
+            object_fetcher
+                .piece_getter
+                .piece_requests
+                .load(Ordering::Relaxed),
+            usize::from(wrong_chunks)
+        );
+
+        // Large object doesn't fit into piece chunks
+        let hash = large_block_object.hash();
+        let mapping = find_mapping(&archived_segments, hash);
+        assert_eq!(
+            object_fetcher.fetch_object(hash, mapping).await.unwrap(),
+            large_object
+        );
+    }
+}
+
+#[tokio::test]
+async fn missing_piece() {
+    let object_fetcher = ObjectFetcher::new(TestPieceGetter::default());
+
+    assert!(matches!(
+        object_fetcher
+            .fetch_object(
+                Blake2b256Hash::default(),
+                GlobalObject::V0 {
+                    piece_index: PieceIndex::ZERO,
+                    offset: 0,
+                },
+            )
+            .await,
+        Err(ObjectFetcherError::PieceNotFound { .. })
+    ));
+}
+
+#[test]
+fn object_decoding() {
+    let object = vec![1u8; 100];
+    let encoded_object = object.encode();
+
+    // Not enough data
+    assert!(crate::decode_object(&encoded_object[..50], &[])
+        .unwrap()
+        .is_none());
+    assert!(crate::decode_object(&[], &[]).unwrap().is_none());
+
+    // Object split between head and tail at any point
+    for split_point in 0..=encoded_object.len() {
+        let (head, tail) = encoded_object.split_at(split_point);
+        assert_eq!(
+            crate::decode_object(head, tail).unwrap(),
+            Some(object.clone())
+        );
+    }
+
+    assert!(matches!(
+        crate::decode_object(&Compact(RecordedHistorySegment::SIZE as u32).encode(), &[]),
+        Err(ObjectFetcherError::InvalidObjectLength { .. })
+    ));
+}
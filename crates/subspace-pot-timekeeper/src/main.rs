@@ -0,0 +1,79 @@
+//! Standalone proof of time timekeeper.
+//!
+//! Proves proof of time on a dedicated CPU core and streams proofs to nodes that are configured to
+//! use it as an external timekeeper.
+
+use clap::{Parser, Subcommand};
+use sc_proof_of_time::source::external_timekeeper::{
+    run_external_timekeeper, ExternalTimekeeperSecret,
+};
+use std::error::Error;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tracing::{info, Level};
+use tracing_subscriber::fmt::Subscriber;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Standalone proof of time timekeeper
+#[derive(Debug, Parser)]
+#[clap(about, version)]
+enum Command {
+    /// Run timekeeper
+    Run {
+        /// Address to listen on for connections from nodes
+        #[arg(long, default_value = "127.0.0.1:30777")]
+        listen_on: SocketAddr,
+        /// File with secret shared with nodes in hex format
+        #[arg(long)]
+        secret_file: PathBuf,
+    },
+    /// Generate new secret to be shared between timekeeper and nodes
+    GenerateSecret {
+        /// File to write secret to, secret is printed if not specified
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+fn init_logging() {
+    // set default log to info if the RUST_LOG is not set.
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(Level::INFO.into())
+        .from_env_lossy();
+
+    let builder = Subscriber::builder().with_env_filter(env_filter).finish();
+
+    builder.init()
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    init_logging();
+
+    match Command::parse() {
+        Command::Run {
+            listen_on,
+            secret_file,
+        } => {
+            let secret = ExternalTimekeeperSecret::from_file(&secret_file)?;
+
+            run_external_timekeeper(listen_on, secret)?;
+        }
+        Command::GenerateSecret { output } => {
+            let secret = ExternalTimekeeperSecret::generate().to_hex();
+
+            match output {
+                Some(output) => {
+                    fs::write(&output, secret)?;
+                    info!(output = %output.display(), "Secret written to file");
+                }
+                None => {
+                    println!("{secret}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
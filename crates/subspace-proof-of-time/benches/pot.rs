@@ -1,8 +1,9 @@
 use core::num::NonZeroU32;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rand::{thread_rng, Rng};
+use rayon::ThreadPoolBuilder;
 use subspace_core_primitives::PotSeed;
-use subspace_proof_of_time::{prove, verify};
+use subspace_proof_of_time::{prove, verify, verify_parallel};
 
 fn criterion_benchmark(c: &mut Criterion) {
     let mut seed = PotSeed::default();
@@ -28,6 +29,20 @@ fn criterion_benchmark(c: &mut Criterion) {
             .unwrap();
         })
     });
+
+    let thread_pool = ThreadPoolBuilder::new().build().unwrap();
+
+    c.bench_function("verify-parallel", |b| {
+        b.iter(|| {
+            black_box(verify_parallel(
+                black_box(seed),
+                black_box(pot_iterations),
+                black_box(&*checkpoints),
+                &thread_pool,
+            ))
+            .unwrap();
+        })
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
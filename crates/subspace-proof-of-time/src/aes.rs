@@ -1,4 +1,10 @@
 //! AES related functionality.
+//!
+//! Optimized AES-NI implementation is used for proving when CPU supports it, otherwise `aes` crate
+//! is used, which detects hardware acceleration at runtime and falls back to constant-time
+//! fixsliced software implementation, such that results are identical on all targets (including
+//! wasm). Software implementation can be forced with `--cfg aes_force_soft` in `RUSTFLAGS` to
+//! test and benchmark it on CPUs with hardware acceleration.
 
 // TODO: Similarly optimized version for aarch64
 #[cfg(target_arch = "x86_64")]
@@ -11,18 +17,21 @@ use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
 use aes::Aes128;
 use subspace_core_primitives::{PotCheckpoints, PotKey, PotOutput, PotSeed};
 
+#[cfg(target_arch = "x86_64")]
+cpufeatures::new!(has_aes, "aes");
+
 /// Creates the AES based proof.
 #[inline(always)]
 pub(crate) fn create(seed: PotSeed, key: PotKey, checkpoint_iterations: u32) -> PotCheckpoints {
     #[cfg(target_arch = "x86_64")]
-    {
-        unsafe { x86_64::create(seed.as_ref(), key.as_ref(), checkpoint_iterations) }
+    if has_aes::get() {
+        // SAFETY: Checked above that CPU supports AES-NI
+        return unsafe { x86_64::create(seed.as_ref(), key.as_ref(), checkpoint_iterations) };
     }
-    #[cfg(not(target_arch = "x86_64"))]
+
     create_generic(seed, key, checkpoint_iterations)
 }
 
-#[cfg(any(not(target_arch = "x86_64"), test))]
 #[inline(always)]
 fn create_generic(seed: PotSeed, key: PotKey, checkpoint_iterations: u32) -> PotCheckpoints {
     let key = GenericArray::from(*key);
@@ -77,6 +86,7 @@ pub(crate) fn verify_sequential(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::prelude::*;
     use subspace_core_primitives::{PotKey, PotOutput, PotSeed};
 
     const SEED: [u8; 16] = [
@@ -158,4 +168,56 @@ mod tests {
             checkpoint_iterations
         ));
     }
+
+    /// `aes` crate uses its constant-time software backend only when compiled with
+    /// `--cfg aes_force_soft` (which CI does for this crate), otherwise it detects AES-NI at
+    /// runtime as well and comparison is against `aes` crate's AES-NI backend.
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn aes_ni_matches_generic() {
+        if !has_aes::get() {
+            // Nothing to compare against
+            return;
+        }
+
+        let mut rng = StdRng::seed_from_u64(42);
+
+        for checkpoint_iterations in [2, 10, 100, 1000] {
+            let seed = PotSeed::from(rng.gen::<[u8; 16]>());
+            let key = PotKey::from(rng.gen::<[u8; 16]>());
+
+            // SAFETY: Checked above that CPU supports AES-NI
+            let aes_ni_checkpoints =
+                unsafe { x86_64::create(seed.as_ref(), key.as_ref(), checkpoint_iterations) };
+            let generic_checkpoints = create_generic(seed, key, checkpoint_iterations);
+            assert_eq!(aes_ni_checkpoints, generic_checkpoints);
+            assert_eq!(create(seed, key, checkpoint_iterations), aes_ni_checkpoints);
+
+            // Any number of checkpoints
+            for num_checkpoints in 1..=aes_ni_checkpoints.len() {
+                assert!(verify_sequential(
+                    seed,
+                    key,
+                    &aes_ni_checkpoints[..num_checkpoints],
+                    checkpoint_iterations
+                ));
+                assert!(verify_sequential(
+                    seed,
+                    key,
+                    &generic_checkpoints[..num_checkpoints],
+                    checkpoint_iterations
+                ));
+            }
+
+            // Both reject the same invalid proof
+            let mut invalid_checkpoints = aes_ni_checkpoints;
+            invalid_checkpoints[invalid_checkpoints.len() - 1] = PotOutput::from(BAD_CIPHER);
+            assert!(!verify_sequential(
+                seed,
+                key,
+                &*invalid_checkpoints,
+                checkpoint_iterations
+            ));
+        }
+    }
 }
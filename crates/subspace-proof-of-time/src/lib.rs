@@ -2,8 +2,14 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 mod aes;
+#[cfg(all(test, feature = "parallel"))]
+mod tests;
 
 use core::num::NonZeroU32;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::ThreadPool;
 use subspace_core_primitives::{PotCheckpoints, PotOutput, PotSeed};
 
 /// Proof of time error
@@ -52,6 +58,64 @@ pub fn verify(
     iterations: NonZeroU32,
     checkpoints: &[PotOutput],
 ) -> Result<bool, PotError> {
+    let checkpoint_iterations = checkpoint_iterations(iterations, checkpoints)?;
+
+    Ok(aes::verify_sequential(
+        seed,
+        seed.key(),
+        checkpoints,
+        checkpoint_iterations,
+    ))
+}
+
+/// Verify checkpoint using provided thread pool, number of iterations is set across uniformly
+/// distributed checkpoints.
+///
+/// Checkpoints are known upfront, so intervals between them are verified independently by
+/// different threads. Verification stops as soon as any of the intervals turns out to be invalid,
+/// result is always the same as of [`verify()`].
+///
+/// Returns error if `iterations` is not a multiple of checkpoints times two.
+#[cfg(feature = "parallel")]
+pub fn verify_parallel(
+    seed: PotSeed,
+    iterations: NonZeroU32,
+    checkpoints: &[PotOutput],
+    thread_pool: &ThreadPool,
+) -> Result<bool, PotError> {
+    let checkpoint_iterations = checkpoint_iterations(iterations, checkpoints)?;
+    let key = seed.key();
+    // Sequential verification processes multiple checkpoints at once efficiently, so only split
+    // checkpoints as much as necessary to occupy all threads
+    let chunk_size = checkpoints
+        .len()
+        .div_ceil(thread_pool.current_num_threads())
+        .max(1);
+
+    Ok(thread_pool.install(|| {
+        checkpoints
+            .par_chunks(chunk_size)
+            .enumerate()
+            .all(|(chunk_index, chunk)| {
+                // Each interval starts where the previous one ends
+                let chunk_seed = if chunk_index == 0 {
+                    seed
+                } else {
+                    checkpoints[chunk_index * chunk_size - 1].seed()
+                };
+
+                aes::verify_sequential(chunk_seed, key, chunk, checkpoint_iterations)
+            })
+    }))
+}
+
+/// Number of iterations between checkpoints.
+///
+/// Returns error if `iterations` is not a multiple of checkpoints times two.
+fn checkpoint_iterations(
+    iterations: NonZeroU32,
+    checkpoints: &[PotOutput],
+) -> Result<u32, PotError> {
     let num_checkpoints = checkpoints.len() as u32;
     if iterations.get() % (num_checkpoints * 2) != 0 {
         return Err(PotError::NotMultipleOfCheckpoints {
@@ -60,10 +124,5 @@ pub fn verify(
         });
     }
 
-    Ok(aes::verify_sequential(
-        seed,
-        seed.key(),
-        checkpoints,
-        iterations.get() / num_checkpoints,
-    ))
+    Ok(iterations.get() / num_checkpoints)
 }
@@ -0,0 +1,58 @@
+use crate::{prove, verify, verify_parallel};
+use core::num::NonZeroU32;
+use rand::prelude::*;
+use rayon::ThreadPoolBuilder;
+use subspace_core_primitives::{PotCheckpoints, PotSeed};
+
+#[test]
+fn parallel_verification_matches_sequential() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let iterations =
+        NonZeroU32::new(u32::from(PotCheckpoints::NUM_CHECKPOINTS.get()) * 2 * 50).unwrap();
+
+    for num_threads in [1, 3, 8, 16] {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+
+        for _ in 0..10 {
+            let mut seed = PotSeed::default();
+            rng.fill(seed.as_mut());
+            let checkpoints = prove(seed, iterations).unwrap();
+
+            assert!(verify(seed, iterations, &*checkpoints).unwrap());
+            assert!(verify_parallel(seed, iterations, &*checkpoints, &thread_pool).unwrap());
+
+            // Corrupt random checkpoint
+            let mut corrupted_checkpoints = checkpoints;
+            let checkpoint_index = rng.gen_range(0..corrupted_checkpoints.len());
+            corrupted_checkpoints[checkpoint_index].as_mut()[0] ^= 1;
+            assert!(!verify(seed, iterations, &*corrupted_checkpoints).unwrap());
+            assert!(
+                !verify_parallel(seed, iterations, &*corrupted_checkpoints, &thread_pool).unwrap()
+            );
+
+            // Wrong seed
+            let mut wrong_seed = seed;
+            wrong_seed.as_mut()[0] ^= 1;
+            assert!(!verify(wrong_seed, iterations, &*checkpoints).unwrap());
+            assert!(!verify_parallel(wrong_seed, iterations, &*checkpoints, &thread_pool).unwrap());
+
+            // Wrong number of iterations
+            let wrong_iterations = NonZeroU32::new(
+                iterations.get() + u32::from(PotCheckpoints::NUM_CHECKPOINTS.get()) * 2,
+            )
+            .unwrap();
+            assert!(!verify(seed, wrong_iterations, &*checkpoints).unwrap());
+            assert!(!verify_parallel(seed, wrong_iterations, &*checkpoints, &thread_pool).unwrap());
+
+            // Invalid number of iterations
+            let invalid_iterations = NonZeroU32::new(iterations.get() + 1).unwrap();
+            assert!(verify(seed, invalid_iterations, &*checkpoints).is_err());
+            assert!(
+                verify_parallel(seed, invalid_iterations, &*checkpoints, &thread_pool).is_err()
+            );
+        }
+    }
+}
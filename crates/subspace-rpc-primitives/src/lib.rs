@@ -16,14 +16,19 @@
 //! Primitives for Subspace RPC.
 
 use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+use subspace_core_primitives::objects::GlobalObject;
 use subspace_core_primitives::{
-    Blake2b256Hash, PublicKey, RewardSignature, SlotNumber, Solution, SolutionRange,
+    Blake2b256Hash, Blake3Hash, PotOutput, PotSeed, PublicKey, RewardSignature, SegmentIndex,
+    SlotNumber, Solution, SolutionRange,
 };
 use subspace_farmer_components::FarmerProtocolInfo;
 use subspace_networking::libp2p::Multiaddr;
 
 /// Defines a limit for number of segments that can be requested over RPC
 pub const MAX_SEGMENT_HEADERS_PER_REQUEST: usize = 1000;
+/// Defines a limit for number of segments whose object mappings can be requested over RPC at once
+pub const MAX_OBJECT_MAPPINGS_SEGMENTS_PER_REQUEST: u64 = 100;
 
 /// Information necessary for farmer application
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,3 +109,65 @@ impl NodeSyncStatus {
         matches!(self, Self::Synced)
     }
 }
+
+/// Hash of the object, serialized as hex string
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ObjectHash(#[serde(with = "hex::serde")] pub Blake2b256Hash);
+
+/// Mapping of the object to its location in archived history
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectMapping {
+    /// Object hash
+    pub hash: ObjectHash,
+    /// Location of the object in archived history
+    pub global_object: GlobalObject,
+}
+
+/// Object mappings of objects stored in archived segment
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentObjectMappings {
+    /// Segment index
+    pub segment_index: SegmentIndex,
+    /// Objects stored in the segment
+    pub objects: Vec<ObjectMapping>,
+}
+
+/// Information about proof of time slot produced or received by the node
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PotSlotInfo {
+    /// Slot number
+    pub slot_number: SlotNumber,
+    /// Proof of time seed
+    pub seed: PotSeed,
+    /// Iterations per slot
+    pub slot_iterations: NonZeroU32,
+    /// Proof of time checkpoints, the last one is the output of the slot
+    pub checkpoints: Vec<PotOutput>,
+}
+
+/// Scheduled change of proof of time parameters
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PotParametersChangeInfo {
+    /// At which slot change of parameters takes effect
+    pub slot_number: SlotNumber,
+    /// New number of slot iterations
+    pub slot_iterations: NonZeroU32,
+    /// Entropy that is injected into proof of time chain at the slot
+    #[serde(with = "hex::serde")]
+    pub entropy: Blake3Hash,
+}
+
+/// Proof of time parameters at the best block of the node
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PotParametersInfo {
+    /// Number of iterations per slot, corresponds to slot that directly follows best block's slot
+    pub slot_iterations: NonZeroU32,
+    /// Next scheduled change of parameters (entropy injection) if any
+    pub next_change: Option<PotParametersChangeInfo>,
+}
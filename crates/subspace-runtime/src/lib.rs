@@ -218,6 +218,8 @@ const RECENT_HISTORY_FRACTION: (HistorySize, HistorySize) = (
 /// Minimum lifetime of a plotted sector, measured in archived segment.
 const MIN_SECTOR_LIFETIME: HistorySize =
     HistorySize::new(NonZeroU64::new(4).expect("Not zero; qed"));
+/// Erasure coding rate `(source, total)` of records during archiving process.
+const ERASURE_CODING_RATE: (u32, u32) = (1, 2);
 
 /// The block weight for 2 seconds of compute
 const BLOCK_WEIGHT_FOR_2_SEC: Weight =
@@ -306,6 +308,7 @@ parameter_types! {
     pub const RecentSegments: HistorySize = RECENT_SEGMENTS;
     pub const RecentHistoryFraction: (HistorySize, HistorySize) = RECENT_HISTORY_FRACTION;
     pub const MinSectorLifetime: HistorySize = MIN_SECTOR_LIFETIME;
+    pub const ErasureCodingRate: (u32, u32) = ERASURE_CODING_RATE;
     // Disable solution range adjustment at the start of chain.
     // Root origin must enable later
     pub const ShouldAdjustSolutionRange: bool = false;
@@ -334,6 +337,7 @@ impl pallet_subspace::Config for Runtime {
     type RecentSegments = RecentSegments;
     type RecentHistoryFraction = RecentHistoryFraction;
     type MinSectorLifetime = MinSectorLifetime;
+    type ErasureCodingRate = ErasureCodingRate;
     type ExpectedVotesPerBlock = ExpectedVotesPerBlock;
     type MaxPiecesInSector = ConstU16<{ MAX_PIECES_IN_SECTOR }>;
     type ShouldAdjustSolutionRange = ShouldAdjustSolutionRange;
@@ -0,0 +1,369 @@
+// Copyright (C) 2023 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Local store of archived history pieces.
+//!
+//! Normally node only keeps recently archived segments around and older pieces have to be
+//! retrieved from farmers. Archival node persists pieces of every archived segment (or a number of
+//! the most recent segments) such that they can be served over RPC and DSN, acting as a last-resort
+//! provider of archived history for the network.
+//!
+//! Only segments archived while the store is enabled are stored as they are produced, segments
+//! archived before that can be backfilled from blocks in the database (see
+//! [`backfill_archival_pieces`]), which requires blocks to not be pruned.
+
+#[cfg(test)]
+mod tests;
+
+use futures::{Stream, StreamExt};
+use sc_client_api::{AuxStore, BlockBackend, HeaderBackend};
+use sc_consensus_subspace::archiver::recreate_archived_segments;
+use sc_consensus_subspace::ArchivedSegmentNotification;
+use sc_consensus_subspace_rpc::ArchivalPiecesProvider;
+use sp_api::ProvideRuntimeApi;
+use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
+use sp_objects::ObjectsApi;
+use sp_runtime::traits::Block as BlockT;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use subspace_archiving::archiver::NewArchivedSegment;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{Piece, PieceIndex, SegmentIndex};
+use subspace_networking::libp2p::kad::{ProviderRecord, RecordKey};
+use subspace_networking::libp2p::PeerId;
+use subspace_networking::utils::multihash::MultihashCode;
+use subspace_networking::{LocalRecordProvider, Multihash};
+use tracing::{debug, error, info};
+
+const SEGMENT_FILE_EXTENSION: &str = "pieces";
+const TEMPORARY_FILE_EXTENSION: &str = "tmp";
+
+/// Archival pieces store configuration.
+#[derive(Debug, Clone)]
+pub struct ArchivalPiecesConfig {
+    /// Directory where pieces are stored
+    pub directory: PathBuf,
+    /// Number of the most recent segments to keep, all segments are kept if `None`
+    pub max_segments: Option<NonZeroU64>,
+    /// Store pieces of segments archived before the store was enabled on startup, requires blocks
+    /// not being pruned
+    pub backfill: bool,
+}
+
+#[derive(Debug)]
+struct Inner {
+    directory: PathBuf,
+    max_segments: Option<NonZeroU64>,
+}
+
+/// Persistent store of archived history pieces.
+///
+/// Each segment is stored in a separate file containing all of its pieces one after another, so
+/// pruning of old segments is just removal of corresponding files.
+#[derive(Debug, Clone)]
+pub struct ArchivalPiecesStore {
+    inner: Arc<Inner>,
+}
+
+impl ArchivalPiecesStore {
+    /// Open store in provided directory, creating it if necessary
+    pub fn open(config: ArchivalPiecesConfig) -> io::Result<Self> {
+        let ArchivalPiecesConfig {
+            directory,
+            max_segments,
+            backfill: _,
+        } = config;
+
+        fs::create_dir_all(&directory)?;
+
+        // Segments that were not fully written due to interrupted process
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str())
+                == Some(TEMPORARY_FILE_EXTENSION)
+            {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                directory,
+                max_segments,
+            }),
+        })
+    }
+
+    /// Store pieces of archived segment, storing the same segment again overrides it.
+    ///
+    /// When the number of segments to keep is limited, segments that are too old relatively to
+    /// the stored one are pruned.
+    pub fn store_segment(&self, archived_segment: &NewArchivedSegment) -> io::Result<()> {
+        let segment_index = archived_segment.segment_header.segment_index();
+
+        self.write_segment(archived_segment)?;
+
+        if let Some(max_segments) = self.inner.max_segments {
+            self.prune(segment_index, max_segments)?;
+        }
+
+        Ok(())
+    }
+
+    /// Store pieces of archived segment unless they are stored already or the segment is too old
+    /// to be kept relatively to the most recent stored segment, returns `true` if pieces were
+    /// stored.
+    ///
+    /// Unlike [`Self::store_segment()`] this never overrides or prunes stored segments, which makes
+    /// it suitable for filling the store with older segments.
+    pub fn store_missing_segment(&self, archived_segment: &NewArchivedSegment) -> io::Result<bool> {
+        let segment_index = archived_segment.segment_header.segment_index();
+
+        if self.contains_segment(segment_index) {
+            return Ok(false);
+        }
+
+        if let Some(max_segments) = self.inner.max_segments {
+            if let Some(last_segment_index) = self.last_segment_index()? {
+                if u64::from(segment_index) + max_segments.get() <= last_segment_index {
+                    return Ok(false);
+                }
+            }
+        }
+
+        self.write_segment(archived_segment)?;
+
+        Ok(true)
+    }
+
+    /// Whether pieces of the segment are stored
+    pub fn contains_segment(&self, segment_index: SegmentIndex) -> bool {
+        self.segment_path(segment_index).exists()
+    }
+
+    /// Get piece from the store, `None` is returned if segment of the piece is not stored
+    pub fn get_piece(&self, piece_index: PieceIndex) -> io::Result<Option<Piece>> {
+        let mut file = match File::open(self.segment_path(piece_index.segment_index())) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(error) => {
+                return Err(error);
+            }
+        };
+
+        let mut piece = Piece::default();
+        file.seek(SeekFrom::Start(
+            u64::from(piece_index.position()) * Piece::SIZE as u64,
+        ))?;
+        file.read_exact(piece.as_mut())?;
+
+        Ok(Some(piece))
+    }
+
+    fn write_segment(&self, archived_segment: &NewArchivedSegment) -> io::Result<()> {
+        let segment_index = archived_segment.segment_header.segment_index();
+        let segment_path = self.segment_path(segment_index);
+        let temporary_path = segment_path.with_extension(TEMPORARY_FILE_EXTENSION);
+
+        {
+            let pieces: &[u8] = archived_segment.pieces.as_ref();
+            let mut file = File::create(&temporary_path)?;
+            file.write_all(pieces)?;
+            file.sync_data()?;
+        }
+        // Rename is atomic, such that readers never observe partially written segment
+        fs::rename(&temporary_path, &segment_path)?;
+
+        debug!(%segment_index, "Stored pieces of archived segment");
+
+        Ok(())
+    }
+
+    /// Index of the most recent stored segment
+    fn last_segment_index(&self) -> io::Result<Option<u64>> {
+        let mut last_segment_index = None;
+
+        for entry in fs::read_dir(&self.inner.directory)? {
+            let segment_index = Self::segment_index_from_path(&entry?.path());
+            last_segment_index = last_segment_index.max(segment_index);
+        }
+
+        Ok(last_segment_index)
+    }
+
+    fn prune(
+        &self,
+        latest_segment_index: SegmentIndex,
+        max_segments: NonZeroU64,
+    ) -> io::Result<()> {
+        let Some(first_segment_index_to_keep) =
+            (u64::from(latest_segment_index) + 1).checked_sub(max_segments.get())
+        else {
+            return Ok(());
+        };
+
+        for entry in fs::read_dir(&self.inner.directory)? {
+            let path = entry?.path();
+            let Some(segment_index) = Self::segment_index_from_path(&path) else {
+                continue;
+            };
+
+            if segment_index < first_segment_index_to_keep {
+                fs::remove_file(&path)?;
+
+                debug!(%segment_index, "Pruned pieces of archived segment");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn segment_path(&self, segment_index: SegmentIndex) -> PathBuf {
+        // Zero-padded such that files are sorted by segment index
+        self.inner.directory.join(format!(
+            "{:020}.{SEGMENT_FILE_EXTENSION}",
+            u64::from(segment_index)
+        ))
+    }
+
+    fn segment_index_from_path(path: &Path) -> Option<u64> {
+        if path.extension()?.to_str()? != SEGMENT_FILE_EXTENSION {
+            return None;
+        }
+
+        path.file_stem()?.to_str()?.parse().ok()
+    }
+}
+
+impl ArchivalPiecesProvider for ArchivalPiecesStore {
+    fn piece(&self, piece_index: PieceIndex) -> Result<Option<Piece>, sp_blockchain::Error> {
+        self.get_piece(piece_index).map_err(|error| {
+            sp_blockchain::Error::Backend(format!(
+                "Failed to read piece {piece_index} from archival pieces store: {error}"
+            ))
+        })
+    }
+}
+
+/// Provider records for pieces in archival pieces store, such that peers looking for pieces in DSN
+/// with `get_providers` can discover archival node.
+#[derive(Debug, Clone)]
+pub(crate) struct ArchivalPiecesRecordProvider {
+    peer_id: PeerId,
+    archival_pieces_store: Option<ArchivalPiecesStore>,
+}
+
+impl ArchivalPiecesRecordProvider {
+    pub(crate) fn new(peer_id: PeerId, archival_pieces_store: Option<ArchivalPiecesStore>) -> Self {
+        Self {
+            peer_id,
+            archival_pieces_store,
+        }
+    }
+}
+
+impl LocalRecordProvider for ArchivalPiecesRecordProvider {
+    fn record(&self, key: &RecordKey) -> Option<ProviderRecord> {
+        let archival_pieces_store = self.archival_pieces_store.as_ref()?;
+
+        let multihash = Multihash::from_bytes(key.as_ref()).ok()?;
+        if multihash.code() != u64::from(MultihashCode::PieceIndex) {
+            return None;
+        }
+        let piece_index = PieceIndex::from_bytes(multihash.digest().try_into().ok()?);
+
+        archival_pieces_store
+            .contains_segment(piece_index.segment_index())
+            .then(|| ProviderRecord {
+                key: key.clone(),
+                provider: self.peer_id,
+                expires: None,
+                // Addresses of local node are added by Kademlia when record is returned
+                addresses: Vec::new(),
+            })
+    }
+}
+
+/// Store pieces of newly archived segments as they arrive.
+pub(crate) async fn store_archived_segments<S>(
+    archival_pieces_store: ArchivalPiecesStore,
+    mut archived_segments: S,
+) where
+    S: Stream<Item = ArchivedSegmentNotification> + Unpin,
+{
+    info!(
+        directory = %archival_pieces_store.inner.directory.display(),
+        max_segments = ?archival_pieces_store.inner.max_segments,
+        "Storing pieces of archived history"
+    );
+
+    while let Some(ArchivedSegmentNotification {
+        archived_segment,
+        acknowledgement_sender,
+    }) = archived_segments.next().await
+    {
+        if let Err(error) = archival_pieces_store.store_segment(&archived_segment) {
+            error!(
+                %error,
+                segment_index = %archived_segment.segment_header.segment_index(),
+                "Failed to store pieces of archived segment"
+            );
+        }
+
+        // Archiver waits for all acknowledgement senders to be dropped, pieces are stored by now
+        drop(acknowledgement_sender);
+    }
+}
+
+/// Store pieces of archived history that is re-created from blocks in the database, which must not
+/// be pruned, for segments that are not stored yet. This allows to fill the store with segments
+/// that were archived before archival pieces store was enabled.
+///
+/// Returns number of stored segments.
+pub(crate) fn backfill_archival_pieces<Block, Client>(
+    client: &Client,
+    kzg: Kzg,
+    archival_pieces_store: &ArchivalPiecesStore,
+) -> Result<u64, Box<dyn std::error::Error>>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block> + AuxStore,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+{
+    info!("Backfilling archival pieces store from archived history");
+
+    let mut stored_segments = 0;
+
+    recreate_archived_segments(client, kzg, |archived_segment| {
+        if archival_pieces_store.store_missing_segment(&archived_segment)? {
+            stored_segments += 1;
+
+            info!(
+                segment_index = %archived_segment.segment_header.segment_index(),
+                "Backfilled pieces of archived segment"
+            );
+        }
+
+        Ok(())
+    })?;
+
+    Ok(stored_segments)
+}
@@ -0,0 +1,187 @@
+use crate::archival_pieces::{
+    ArchivalPiecesConfig, ArchivalPiecesStore, SEGMENT_FILE_EXTENSION, TEMPORARY_FILE_EXTENSION,
+};
+use std::fs;
+use std::num::NonZeroU64;
+use std::path::Path;
+use subspace_archiving::archiver::NewArchivedSegment;
+use subspace_core_primitives::{
+    ArchivedBlockProgress, ArchivedHistorySegment, Blake2b256Hash, LastArchivedBlock, Piece,
+    SegmentCommitment, SegmentHeader, SegmentIndex,
+};
+
+fn open_store(directory: &Path, max_segments: Option<u64>) -> ArchivalPiecesStore {
+    ArchivalPiecesStore::open(ArchivalPiecesConfig {
+        directory: directory.to_path_buf(),
+        max_segments: max_segments.map(|max_segments| NonZeroU64::new(max_segments).unwrap()),
+        backfill: false,
+    })
+    .unwrap()
+}
+
+/// Archived segment with every piece filled with bytes derived from segment index and position
+fn archived_segment(segment_index: u64) -> NewArchivedSegment {
+    let mut pieces = ArchivedHistorySegment::default();
+    for (position, piece) in pieces.iter_mut().enumerate() {
+        AsMut::<[u8]>::as_mut(piece).fill((segment_index as u8) ^ (position as u8));
+    }
+
+    NewArchivedSegment {
+        segment_header: SegmentHeader::V0 {
+            segment_index: SegmentIndex::from(segment_index),
+            segment_commitment: SegmentCommitment::default(),
+            prev_segment_header_hash: Blake2b256Hash::default(),
+            last_archived_block: LastArchivedBlock {
+                number: 0,
+                archived_progress: ArchivedBlockProgress::Complete,
+            },
+        },
+        pieces,
+        object_mapping: Vec::new(),
+    }
+}
+
+fn stored_segment_indexes(store: &ArchivalPiecesStore) -> Vec<u64> {
+    (0..10)
+        .filter(|&segment_index| {
+            store
+                .get_piece(SegmentIndex::from(segment_index).first_piece_index())
+                .unwrap()
+                .is_some()
+        })
+        .collect()
+}
+
+#[test]
+fn store_and_get_pieces() {
+    let directory = tempfile::tempdir().unwrap();
+    let store = open_store(directory.path(), None);
+
+    let segment_index = SegmentIndex::ONE;
+    assert_eq!(
+        store.get_piece(segment_index.first_piece_index()).unwrap(),
+        None
+    );
+
+    let archived_segment = archived_segment(1);
+    store.store_segment(&archived_segment).unwrap();
+
+    for (piece_index, expected_piece) in segment_index
+        .segment_piece_indexes()
+        .into_iter()
+        .zip(archived_segment.pieces.iter())
+    {
+        assert_eq!(
+            store.get_piece(piece_index).unwrap(),
+            Some(Piece::from(expected_piece))
+        );
+    }
+    // Other segments are not stored
+    assert_eq!(
+        store
+            .get_piece(SegmentIndex::ZERO.last_piece_index())
+            .unwrap(),
+        None
+    );
+
+    // Storing the same segment again overrides it
+    let mut archived_segment = archived_segment;
+    AsMut::<[u8]>::as_mut(&mut archived_segment.pieces[0]).fill(0xff);
+    store.store_segment(&archived_segment).unwrap();
+    assert_eq!(
+        store.get_piece(segment_index.first_piece_index()).unwrap(),
+        Some(Piece::from(&archived_segment.pieces[0]))
+    );
+
+    // Pieces survive re-opening of the store
+    let store = open_store(directory.path(), None);
+    assert_eq!(
+        store.get_piece(segment_index.last_piece_index()).unwrap(),
+        Some(Piece::from(archived_segment.pieces.iter().last().unwrap()))
+    );
+}
+
+#[test]
+fn interrupted_writes_are_removed() {
+    let directory = tempfile::tempdir().unwrap();
+    let temporary_path = directory
+        .path()
+        .join(format!("{:020}.{SEGMENT_FILE_EXTENSION}", 0))
+        .with_extension(TEMPORARY_FILE_EXTENSION);
+    fs::write(&temporary_path, [1, 2, 3]).unwrap();
+
+    let store = open_store(directory.path(), None);
+
+    assert!(!temporary_path.exists());
+    assert_eq!(
+        store
+            .get_piece(SegmentIndex::ZERO.first_piece_index())
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn pruning() {
+    let directory = tempfile::tempdir().unwrap();
+    let store = open_store(directory.path(), Some(2));
+
+    for segment_index in 0..4 {
+        store
+            .store_segment(&archived_segment(segment_index))
+            .unwrap();
+    }
+
+    // Only the most recent segments are kept
+    assert_eq!(stored_segment_indexes(&store), vec![2, 3]);
+}
+
+#[test]
+fn backfill() {
+    // Unlimited store
+    {
+        let directory = tempfile::tempdir().unwrap();
+        let store = open_store(directory.path(), None);
+
+        let mut stored_segment = archived_segment(3);
+        AsMut::<[u8]>::as_mut(&mut stored_segment.pieces[0]).fill(0xff);
+        store.store_segment(&stored_segment).unwrap();
+
+        for segment_index in 0..4 {
+            assert_eq!(
+                store
+                    .store_missing_segment(&archived_segment(segment_index))
+                    .unwrap(),
+                segment_index != 3
+            );
+        }
+
+        assert_eq!(stored_segment_indexes(&store), vec![0, 1, 2, 3]);
+        // Already stored segment is not overridden
+        assert_eq!(
+            store
+                .get_piece(SegmentIndex::from(3).first_piece_index())
+                .unwrap(),
+            Some(Piece::from(&stored_segment.pieces[0]))
+        );
+    }
+
+    // Segments that are too old to be kept are not stored and newer segments are not pruned
+    {
+        let directory = tempfile::tempdir().unwrap();
+        let store = open_store(directory.path(), Some(2));
+
+        store.store_segment(&archived_segment(5)).unwrap();
+
+        for segment_index in 0..5 {
+            assert_eq!(
+                store
+                    .store_missing_segment(&archived_segment(segment_index))
+                    .unwrap(),
+                segment_index == 4
+            );
+        }
+
+        assert_eq!(stored_segment_indexes(&store), vec![4, 5]);
+    }
+}
@@ -0,0 +1,787 @@
+// Copyright (C) 2023 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline archive of the blockchain history.
+//!
+//! Archived segments (segment header along with all of the pieces) are exported into a directory,
+//! one file per segment, such that blocks can be later imported from them without DSN, which is
+//! useful for reproducible bootstrapping of test networks and air-gapped environments.
+//!
+//! Segment headers read from the archive are not trusted, they are only persisted once the same
+//! segment headers are re-created from imported blocks.
+//!
+//! Archived history can also be checked against local blockchain and segment headers known to the
+//! node, optionally along with pieces retrieved from DSN.
+
+#[cfg(test)]
+mod tests;
+
+use crate::sync_from_dsn::import_blocks::SegmentPiecesSource;
+use async_trait::async_trait;
+use futures::future::Either;
+use futures::stream::FuturesUnordered;
+use futures::{future, StreamExt};
+use parity_scale_codec::{Decode, Encode, IoReader};
+use sc_client_api::{AuxStore, BlockBackend, HeaderBackend};
+use sc_consensus::import_queue::ImportQueueService;
+use sc_consensus::IncomingBlock;
+use sc_consensus_subspace::archiver::{
+    recreate_archived_segments, recreate_genesis_segment, ArchivedSegmentsRecreator,
+    SegmentHeadersStore,
+};
+use sc_consensus_subspace::get_chain_constants;
+use sp_api::ProvideRuntimeApi;
+use sp_consensus::BlockOrigin;
+use sp_consensus_subspace::digests::CompatibleDigestItem;
+use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
+use sp_objects::ObjectsApi;
+use sp_runtime::traits::{Block as BlockT, Header, NumberFor};
+use std::error::Error;
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::time::{Duration, Instant};
+use std::{fmt, fs, io, mem};
+use subspace_archiving::archiver::{is_piece_valid, NewArchivedSegment};
+use subspace_archiving::reconstructor::Reconstructor;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{BlockNumber, Piece, PieceIndex, SegmentHeader, SegmentIndex};
+use subspace_networking::libp2p::{identity, Multiaddr};
+use subspace_networking::utils::piece_provider::{NoPieceValidator, PieceProvider, RetryPolicy};
+use subspace_networking::{Config, PeerInfoProvider};
+use tracing::{debug, error, info, warn};
+
+/// Extension of files with archived segments
+const SEGMENT_FILE_EXTENSION: &str = "segment";
+/// Interval for checking whether queued blocks were imported
+const WAIT_FOR_BLOCKS_TO_IMPORT: Duration = Duration::from_secs(1);
+/// How long to wait for the best block to change before considering import to be stuck
+const BLOCK_IMPORT_STALL_TIMEOUT: Duration = Duration::from_secs(60);
+/// Number of retries when retrieving pieces from DSN during archive check
+const CHECK_PIECE_RETRY_NUMBER: u16 = 3;
+/// How many blocks are sent to import queue at once, also limits the number of queued blocks
+const IMPORT_BLOCKS_BATCH_SIZE: usize = 500;
+
+fn segment_file_path(dir: &Path, segment_index: SegmentIndex) -> PathBuf {
+    // Zero-padded to keep files sorted by segment index
+    dir.join(format!(
+        "{:020}.{SEGMENT_FILE_EXTENSION}",
+        u64::from(segment_index)
+    ))
+}
+
+fn write_archived_segment(dir: &Path, archived_segment: &NewArchivedSegment) -> io::Result<()> {
+    fs::write(
+        segment_file_path(dir, archived_segment.segment_header.segment_index()),
+        archived_segment.encode(),
+    )
+}
+
+/// Read just the segment header from the beginning of the segment file.
+fn read_segment_header(path: &Path) -> Result<SegmentHeader, sc_service::Error> {
+    let file = File::open(path)?;
+
+    SegmentHeader::decode(&mut IoReader(BufReader::new(file))).map_err(|error| {
+        sc_service::Error::Other(format!(
+            "Failed to decode segment header from {}: {error}",
+            path.display()
+        ))
+    })
+}
+
+/// Export archived history into `to` directory, one file per archived segment.
+///
+/// Archived history is re-created from blocks in the database (which must not be pruned) and
+/// checked against segment headers known to the node.
+///
+/// Returns number of exported segments.
+pub fn export_archive<Block, AS, Client>(
+    client: &Client,
+    segment_headers_store: &SegmentHeadersStore<AS>,
+    kzg: Kzg,
+    to: &Path,
+) -> Result<u64, sc_service::Error>
+where
+    Block: BlockT,
+    AS: AuxStore,
+    Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block> + AuxStore,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+{
+    fs::create_dir_all(to)?;
+
+    recreate_archived_segments(client, kzg, |archived_segment| {
+        let segment_header = archived_segment.segment_header;
+        let segment_index = segment_header.segment_index();
+
+        if let Some(known_segment_header) = segment_headers_store.get_segment_header(segment_index)
+        {
+            if known_segment_header != segment_header {
+                return Err(format!(
+                    "Re-created segment header {segment_index} doesn't match segment header known \
+                    to the node: {segment_header:?} != {known_segment_header:?}"
+                )
+                .into());
+            }
+        }
+
+        write_archived_segment(to, &archived_segment)?;
+
+        info!(%segment_index, "Exported archived segment");
+
+        Ok(())
+    })
+    .map_err(|error| sc_service::Error::Other(error.to_string()))
+}
+
+/// Source of pieces backed by segment files in a directory, pieces are verified against segment
+/// headers read from the same directory earlier.
+struct ArchiveSegmentPieces<'a> {
+    dir: &'a Path,
+    kzg: &'a Kzg,
+    segment_headers: &'a [SegmentHeader],
+}
+
+#[async_trait]
+impl<'a> SegmentPiecesSource for ArchiveSegmentPieces<'a> {
+    async fn get_segment_pieces(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<Vec<Option<Piece>>, sc_service::Error> {
+        let path = segment_file_path(self.dir, segment_index);
+        debug!(%segment_index, path = %path.display(), "Reading archived segment");
+
+        let archived_segment = NewArchivedSegment::decode(&mut fs::read(&path)?.as_slice())
+            .map_err(|error| {
+                sc_service::Error::Other(format!(
+                    "Failed to decode archived segment from {}: {error}",
+                    path.display()
+                ))
+            })?;
+
+        let segment_header = *self
+            .segment_headers
+            .get(u64::from(segment_index) as usize)
+            .ok_or_else(|| {
+                sc_service::Error::Other(format!("Segment header {segment_index} is not known"))
+            })?;
+        if archived_segment.segment_header != segment_header {
+            return Err(sc_service::Error::Other(format!(
+                "Segment header in {} doesn't match segment header {segment_index} read earlier",
+                path.display()
+            )));
+        }
+
+        let segment_commitment = segment_header.segment_commitment();
+        archived_segment
+            .pieces
+            .iter()
+            .enumerate()
+            .map(|(position, piece)| {
+                if !is_piece_valid(self.kzg, piece, &segment_commitment, position as u32) {
+                    return Err(sc_service::Error::Other(format!(
+                        "Piece at position {position} of segment {segment_index} is invalid"
+                    )));
+                }
+
+                Ok(Some(Piece::from(piece)))
+            })
+            .collect()
+    }
+}
+
+/// Read segment headers of archived segments in `from` directory.
+///
+/// Segment headers are verified to form a chain and to match segment headers known to the node,
+/// but are not persisted.
+fn read_segment_headers<AS>(
+    segment_headers_store: &SegmentHeadersStore<AS>,
+    from: &Path,
+) -> Result<Vec<SegmentHeader>, sc_service::Error>
+where
+    AS: AuxStore,
+{
+    let mut segment_headers = Vec::<SegmentHeader>::new();
+    loop {
+        let segment_index = SegmentIndex::from(segment_headers.len() as u64);
+        let path = segment_file_path(from, segment_index);
+        if !path.exists() {
+            break;
+        }
+
+        let segment_header = read_segment_header(&path)?;
+
+        if segment_header.segment_index() != segment_index {
+            return Err(sc_service::Error::Other(format!(
+                "File {} contains segment {} instead of {segment_index}",
+                path.display(),
+                segment_header.segment_index(),
+            )));
+        }
+        let expected_prev_segment_header_hash = segment_headers
+            .last()
+            .map(SegmentHeader::hash)
+            .unwrap_or_default();
+        if segment_header.prev_segment_header_hash() != expected_prev_segment_header_hash {
+            return Err(sc_service::Error::Other(format!(
+                "Segment header {segment_index} doesn't follow the previous segment header"
+            )));
+        }
+        if let Some(known_segment_header) = segment_headers_store.get_segment_header(segment_index)
+        {
+            if known_segment_header != segment_header {
+                return Err(sc_service::Error::Other(format!(
+                    "Segment header {segment_index} doesn't match segment header known to the \
+                    node"
+                )));
+            }
+        }
+
+        segment_headers.push(segment_header);
+    }
+
+    if segment_headers.is_empty() {
+        return Err(sc_service::Error::Other(format!(
+            "No archived segments found in {}",
+            from.display()
+        )));
+    }
+
+    Ok(segment_headers)
+}
+
+/// Persist re-created segment header if it matches segment header read from the archive.
+///
+/// Segment headers re-created past the end of the archive are ignored, node's archiver will
+/// produce them on its own.
+fn confirm_segment_header<AS>(
+    segment_headers_store: &SegmentHeadersStore<AS>,
+    segment_headers: &[SegmentHeader],
+    recreated_segment_header: SegmentHeader,
+) -> Result<(), Box<dyn Error>>
+where
+    AS: AuxStore,
+{
+    let segment_index = recreated_segment_header.segment_index();
+    let Some(segment_header) = segment_headers.get(u64::from(segment_index) as usize) else {
+        return Ok(());
+    };
+
+    if *segment_header != recreated_segment_header {
+        return Err(format!(
+            "Segment header {segment_index} in archive doesn't match segment header re-created \
+            from imported blocks: {segment_header:?} != {recreated_segment_header:?}"
+        )
+        .into());
+    }
+
+    segment_headers_store.add_segment_headers(&[recreated_segment_header])?;
+
+    Ok(())
+}
+
+/// Import blocks from archived segments previously exported with [`export_archive()`] into `from`
+/// directory.
+///
+/// Segment headers are verified to form a chain and to match segment headers known to the node,
+/// pieces are verified against segment commitments before blocks are reconstructed and imported.
+///
+/// Segment headers from the archive are only persisted once archived history re-created from
+/// imported blocks confirms them, which happens right before a block that includes them is
+/// imported (block import needs them). This requires blocks to not be pruned during import.
+///
+/// Returns number of imported blocks.
+pub async fn import_archive<Block, AS, Client, IQS>(
+    segment_headers_store: &SegmentHeadersStore<AS>,
+    client: &Client,
+    kzg: &Kzg,
+    import_queue_service: &mut IQS,
+    from: &Path,
+) -> Result<u64, sc_service::Error>
+where
+    Block: BlockT,
+    AS: AuxStore + Send + Sync + 'static,
+    Client: ProvideRuntimeApi<Block>
+        + HeaderBackend<Block>
+        + BlockBackend<Block>
+        + AuxStore
+        + Send
+        + Sync
+        + 'static,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+    IQS: ImportQueueService<Block> + ?Sized,
+{
+    let segment_headers = read_segment_headers(segment_headers_store, from)?;
+    let erasure_coding_rate = get_chain_constants(client)
+        .map_err(|error| sc_service::Error::Other(error.to_string()))?
+        .erasure_coding_rate();
+
+    info!(
+        "Found {} archived segments in {}",
+        segment_headers.len(),
+        from.display()
+    );
+
+    let segment_pieces_source = ArchiveSegmentPieces {
+        dir: from,
+        kzg,
+        segment_headers: &segment_headers,
+    };
+    let mut recreator = ArchivedSegmentsRecreator::new(kzg.clone(), erasure_coding_rate)
+        .map_err(|error| sc_service::Error::Other(error.to_string()))?;
+    let mut confirm_segment_headers = || {
+        let best_block_number = TryInto::<BlockNumber>::try_into(client.info().best_number)
+            .map_err(|_| {
+                sc_service::Error::Other(
+                    "Best block number can't be converted into BlockNumber".to_string(),
+                )
+            })?;
+
+        recreator
+            .archive_blocks(client, best_block_number, |archived_segment| {
+                confirm_segment_header(
+                    segment_headers_store,
+                    &segment_headers,
+                    archived_segment.segment_header,
+                )
+            })
+            .map_err(|error| {
+                sc_service::Error::Other(format!("Failed to confirm segment headers: {error}"))
+            })
+    };
+
+    let mut reconstructor =
+        Reconstructor::new(erasure_coding_rate).map_err(|error| error.to_string())?;
+    let mut blocks_to_import = Vec::with_capacity(IMPORT_BLOCKS_BATCH_SIZE);
+    let mut imported_blocks = 0;
+    let mut last_block_number = client.info().best_number;
+    let mut last_queued_block_number = last_block_number;
+
+    for segment_header in &segment_headers {
+        let segment_index = segment_header.segment_index();
+        let segment_pieces = segment_pieces_source
+            .get_segment_pieces(segment_index)
+            .await?;
+        let blocks = reconstructor
+            .add_segment(&segment_pieces)
+            .map_err(|error| error.to_string())?
+            .blocks;
+
+        for (block_number, block_bytes) in blocks {
+            let block =
+                Block::decode(&mut block_bytes.as_slice()).map_err(|error| error.to_string())?;
+
+            if block_number == 0 && block.hash() != client.info().genesis_hash {
+                return Err(sc_service::Error::Other(
+                    "Wrong genesis block in archive".to_string(),
+                ));
+            }
+
+            // No need to import blocks that are already present
+            if client.expect_header(block.hash()).is_ok() {
+                continue;
+            }
+
+            let (header, extrinsics) = block.deconstruct();
+            let block_number = NumberFor::<Block>::from(block_number);
+
+            // Segment headers included in the block must be known before it is imported
+            let max_included_segment_index = header
+                .digest()
+                .logs()
+                .iter()
+                .filter_map(|log| log.as_segment_commitment())
+                .map(|(segment_index, _segment_commitment)| segment_index)
+                .max();
+            if max_included_segment_index.is_some()
+                && segment_headers_store.max_segment_index() < max_included_segment_index
+            {
+                if !blocks_to_import.is_empty() {
+                    import_queue_service.import_blocks(
+                        BlockOrigin::NetworkInitialSync,
+                        mem::take(&mut blocks_to_import),
+                    );
+                    last_queued_block_number = last_block_number;
+                }
+                wait_for_blocks_to_import(client, last_queued_block_number).await?;
+                confirm_segment_headers()?;
+
+                if segment_headers_store.max_segment_index() < max_included_segment_index {
+                    return Err(sc_service::Error::Other(format!(
+                        "Block {block_number} includes segment headers that can't be confirmed \
+                        by previously imported blocks"
+                    )));
+                }
+            }
+
+            blocks_to_import.push(IncomingBlock {
+                hash: header.hash(),
+                header: Some(header),
+                body: Some(extrinsics),
+                indexed_body: None,
+                justifications: None,
+                origin: None,
+                allow_missing_state: false,
+                import_existing: false,
+                state: None,
+                skip_execution: false,
+            });
+            imported_blocks += 1;
+            last_block_number = block_number;
+
+            if blocks_to_import.len() == IMPORT_BLOCKS_BATCH_SIZE {
+                // Previous batch must be imported before queueing the next one to limit memory
+                // usage
+                wait_for_blocks_to_import(client, last_queued_block_number).await?;
+                import_queue_service.import_blocks(
+                    BlockOrigin::NetworkInitialSync,
+                    mem::take(&mut blocks_to_import),
+                );
+                last_queued_block_number = last_block_number;
+            }
+        }
+
+        debug!(%segment_index, "Archived segment processed");
+    }
+
+    if !blocks_to_import.is_empty() {
+        import_queue_service.import_blocks(BlockOrigin::NetworkInitialSync, blocks_to_import);
+    }
+    wait_for_blocks_to_import(client, last_block_number).await?;
+    // Confirm segment headers that were not included in any of the imported blocks yet
+    confirm_segment_headers()?;
+
+    Ok(imported_blocks)
+}
+
+/// Wait for queued blocks up to `block_number` to be imported.
+async fn wait_for_blocks_to_import<Block, Client>(
+    client: &Client,
+    block_number: NumberFor<Block>,
+) -> Result<(), sc_service::Error>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block>,
+{
+    let mut best_block_number = client.info().best_number;
+    let mut best_block_updated_at = Instant::now();
+
+    while best_block_number < block_number {
+        if best_block_updated_at.elapsed() > BLOCK_IMPORT_STALL_TIMEOUT {
+            return Err(sc_service::Error::Other(format!(
+                "Block import is stuck at block {best_block_number}, expected to reach \
+                {block_number}"
+            )));
+        }
+
+        tokio::time::sleep(WAIT_FOR_BLOCKS_TO_IMPORT).await;
+
+        let new_best_block_number = client.info().best_number;
+        if new_best_block_number != best_block_number {
+            best_block_number = new_best_block_number;
+            best_block_updated_at = Instant::now();
+        }
+    }
+
+    Ok(())
+}
+
+/// Segment of archived history re-created from local blocks that doesn't match segment header
+/// known to the node.
+#[derive(Debug, Clone)]
+pub struct ArchiveDivergence<Hash> {
+    /// Segment header known to the node
+    pub known_segment_header: SegmentHeader,
+    /// Segment header re-created from local blocks
+    pub recreated_segment_header: SegmentHeader,
+    /// Local hash of the last archived block according to known segment header, `None` if there
+    /// is no such block locally
+    pub known_last_archived_block_hash: Option<Hash>,
+    /// Local hash of the last archived block according to re-created segment header
+    pub recreated_last_archived_block_hash: Option<Hash>,
+}
+
+/// Result of [`check_archive()`].
+#[derive(Debug, Clone)]
+pub struct ArchiveCheckReport<Hash> {
+    /// Number of segments (starting with genesis segment) that match segment headers known to the
+    /// node
+    pub matching_segments: u64,
+    /// The first segment that doesn't match segment header known to the node
+    pub divergence: Option<ArchiveDivergence<Hash>>,
+}
+
+/// Used to stop re-creation of archived history early
+#[derive(Debug)]
+struct ArchiveCheckFinished;
+
+impl fmt::Display for ArchiveCheckFinished {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Archive check finished")
+    }
+}
+
+impl Error for ArchiveCheckFinished {}
+
+/// Check that archived history re-created from local blocks matches segment headers known to the
+/// node.
+///
+/// Segment commitments and last archived blocks are compared segment by segment until the first
+/// divergence. Genesis segment is re-created from genesis block alone, later segments require
+/// blocks to not be pruned.
+pub fn check_archive<Block, AS, Client>(
+    client: &Client,
+    segment_headers_store: &SegmentHeadersStore<AS>,
+    kzg: Kzg,
+) -> Result<ArchiveCheckReport<Block::Hash>, sc_service::Error>
+where
+    Block: BlockT,
+    AS: AuxStore,
+    Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block> + AuxStore,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+{
+    let mut report = ArchiveCheckReport {
+        matching_segments: 0,
+        divergence: None,
+    };
+
+    let Some(max_segment_index) = segment_headers_store.max_segment_index() else {
+        return Ok(report);
+    };
+
+    let genesis_segment = recreate_genesis_segment(client, kzg.clone())
+        .map_err(|error| {
+            sc_service::Error::Other(format!("Failed to re-create genesis segment: {error}"))
+        })?
+        .ok_or_else(|| sc_service::Error::Other("Genesis block is not available".to_string()))?;
+
+    report.divergence = check_segment_header(
+        client,
+        segment_headers_store,
+        genesis_segment.segment_header,
+    )?;
+    if report.divergence.is_some() {
+        return Ok(report);
+    }
+    report.matching_segments += 1;
+    info!(segment_index = %SegmentIndex::ZERO, "Archived segment matches");
+
+    if max_segment_index == SegmentIndex::ZERO {
+        return Ok(report);
+    }
+
+    let result = recreate_archived_segments(client, kzg, |archived_segment| {
+        let segment_index = archived_segment.segment_header.segment_index();
+
+        if segment_index == SegmentIndex::ZERO {
+            // Already checked
+            return Ok(());
+        }
+
+        report.divergence = check_segment_header(
+            client,
+            segment_headers_store,
+            archived_segment.segment_header,
+        )?;
+        if report.divergence.is_some() {
+            return Err(ArchiveCheckFinished.into());
+        }
+        report.matching_segments += 1;
+        info!(%segment_index, "Archived segment matches");
+
+        if segment_index == max_segment_index {
+            return Err(ArchiveCheckFinished.into());
+        }
+
+        Ok(())
+    });
+
+    if let Err(error) = result {
+        if !error.is::<ArchiveCheckFinished>() {
+            return Err(sc_service::Error::Other(format!(
+                "Failed to re-create archived history after {} matching segments: {error}",
+                report.matching_segments
+            )));
+        }
+    }
+
+    Ok(report)
+}
+
+/// Compare re-created segment header with the one known to the node, returns divergence if they
+/// don't match.
+fn check_segment_header<Block, AS, Client>(
+    client: &Client,
+    segment_headers_store: &SegmentHeadersStore<AS>,
+    recreated_segment_header: SegmentHeader,
+) -> Result<Option<ArchiveDivergence<Block::Hash>>, sc_service::Error>
+where
+    Block: BlockT,
+    AS: AuxStore,
+    Client: HeaderBackend<Block>,
+{
+    let segment_index = recreated_segment_header.segment_index();
+    let known_segment_header = segment_headers_store
+        .get_segment_header(segment_index)
+        .ok_or_else(|| {
+            sc_service::Error::Other(format!("Segment header {segment_index} is not known"))
+        })?;
+
+    let segment_commitment_matches =
+        known_segment_header.segment_commitment() == recreated_segment_header.segment_commitment();
+    let last_archived_block_matches = known_segment_header.last_archived_block()
+        == recreated_segment_header.last_archived_block();
+    if segment_commitment_matches && last_archived_block_matches {
+        return Ok(None);
+    }
+
+    let known_last_archived_block_hash =
+        client.hash(known_segment_header.last_archived_block().number.into())?;
+    let recreated_last_archived_block_hash =
+        client.hash(recreated_segment_header.last_archived_block().number.into())?;
+
+    error!(
+        %segment_index,
+        %segment_commitment_matches,
+        %last_archived_block_matches,
+        known_last_archived_block = ?known_segment_header.last_archived_block(),
+        ?known_last_archived_block_hash,
+        recreated_last_archived_block = ?recreated_segment_header.last_archived_block(),
+        ?recreated_last_archived_block_hash,
+        "Archived segment doesn't match segment header known to the node"
+    );
+
+    Ok(Some(ArchiveDivergence {
+        known_segment_header,
+        recreated_segment_header,
+        known_last_archived_block_hash,
+        recreated_last_archived_block_hash,
+    }))
+}
+
+/// Result of [`check_archive_pieces()`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ArchivePiecesCheckReport {
+    /// Number of pieces that are valid according to segment headers
+    pub valid_pieces: u64,
+    /// Number of pieces that are invalid according to segment headers
+    pub invalid_pieces: u64,
+    /// Number of pieces that could not be retrieved from DSN
+    pub missing_pieces: u64,
+}
+
+impl ArchivePiecesCheckReport {
+    /// Whether all pieces were retrieved and are valid
+    pub fn is_success(&self) -> bool {
+        self.invalid_pieces == 0 && self.missing_pieces == 0
+    }
+}
+
+/// Retrieve pieces of provided segments from DSN and check them against segment commitments.
+///
+/// Temporary DSN node is created for this purpose and connected to `bootstrap_nodes`.
+pub async fn check_archive_pieces(
+    dsn_protocol_version: String,
+    bootstrap_nodes: Vec<Multiaddr>,
+    kzg: &Kzg,
+    segment_headers: &[SegmentHeader],
+) -> Result<ArchivePiecesCheckReport, sc_service::Error> {
+    let (node, mut node_runner) = subspace_networking::construct(Config {
+        bootstrap_addresses: bootstrap_nodes,
+        ..Config::new(
+            dsn_protocol_version,
+            identity::Keypair::generate_ed25519(),
+            (),
+            Some(PeerInfoProvider::new_client()),
+        )
+    })
+    .map_err(|error| sc_service::Error::Other(format!("Failed to create DSN node: {error}")))?;
+
+    let check = async {
+        if let Err(error) = node.bootstrap().await {
+            warn!(%error, "Failed to bootstrap DSN node");
+        }
+
+        let piece_provider = &PieceProvider::<NoPieceValidator>::new(node.clone(), None);
+        let mut report = ArchivePiecesCheckReport::default();
+
+        for segment_header in segment_headers {
+            check_segment_pieces(kzg, segment_header, &mut report, move |piece_index| {
+                piece_provider
+                    .get_piece(piece_index, RetryPolicy::Limited(CHECK_PIECE_RETRY_NUMBER))
+            })
+            .await;
+        }
+
+        report
+    };
+
+    match future::select(pin!(check), pin!(node_runner.run())).await {
+        Either::Left((report, _)) => Ok(report),
+        Either::Right(((), _)) => Err(sc_service::Error::Other(
+            "DSN node exited unexpectedly".to_string(),
+        )),
+    }
+}
+
+/// Retrieve pieces of provided segment with `get_piece` and check them against segment commitment,
+/// results are added to `report`.
+async fn check_segment_pieces<GetPiece, GetPieceFut>(
+    kzg: &Kzg,
+    segment_header: &SegmentHeader,
+    report: &mut ArchivePiecesCheckReport,
+    get_piece: GetPiece,
+) where
+    GetPiece: Fn(PieceIndex) -> GetPieceFut,
+    GetPieceFut: Future<Output = Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>>>,
+{
+    let segment_index = segment_header.segment_index();
+    let segment_commitment = segment_header.segment_commitment();
+
+    let mut pieces = segment_index
+        .segment_piece_indexes()
+        .into_iter()
+        .map(|piece_index| {
+            let piece_fut = get_piece(piece_index);
+
+            async move { (piece_index, piece_fut.await) }
+        })
+        .collect::<FuturesUnordered<_>>();
+
+    while let Some((piece_index, result)) = pieces.next().await {
+        match result {
+            Ok(Some(piece)) => {
+                if is_piece_valid(kzg, &piece, &segment_commitment, piece_index.position()) {
+                    report.valid_pieces += 1;
+                } else {
+                    error!(%piece_index, "Piece retrieved from DSN is invalid");
+                    report.invalid_pieces += 1;
+                }
+            }
+            Ok(None) => {
+                warn!(%piece_index, "Piece not found in DSN");
+                report.missing_pieces += 1;
+            }
+            Err(error) => {
+                warn!(%error, %piece_index, "Failed to retrieve piece from DSN");
+                report.missing_pieces += 1;
+            }
+        }
+    }
+
+    info!(%segment_index, "Checked pieces of archived segment retrieved from DSN");
+}
@@ -0,0 +1,291 @@
+use crate::archive::{
+    check_segment_pieces, confirm_segment_header, read_segment_headers, segment_file_path,
+    write_archived_segment, ArchivePiecesCheckReport, ArchiveSegmentPieces,
+};
+use crate::sync_from_dsn::import_blocks::SegmentPiecesSource;
+use crate::test_utils::TestAuxStore;
+use futures::executor::block_on;
+use futures::future;
+use parity_scale_codec::{Decode, Encode};
+use sc_consensus_subspace::archiver::SegmentHeadersStore;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use subspace_archiving::archiver::{Archiver, NewArchivedSegment};
+use subspace_archiving::reconstructor::Reconstructor;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::objects::BlockObjectMapping;
+use subspace_core_primitives::{
+    ArchivedHistorySegment, Blake2b256Hash, LastArchivedBlock, Piece, RecordedHistorySegment,
+    SegmentHeader, SegmentIndex,
+};
+
+fn new_segment_headers_store() -> SegmentHeadersStore<TestAuxStore> {
+    SegmentHeadersStore::new(Arc::new(TestAuxStore::default())).unwrap()
+}
+
+/// Blocks with deterministic contents that result in two archived segments
+fn create_blocks() -> Vec<Vec<u8>> {
+    [
+        RecordedHistorySegment::SIZE / 2,
+        RecordedHistorySegment::SIZE,
+        RecordedHistorySegment::SIZE,
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(block_number, size)| {
+        (0..size)
+            .map(|offset| (offset as u8).wrapping_mul(block_number as u8 + 1))
+            .collect()
+    })
+    .collect()
+}
+
+fn archive_blocks(kzg: &Kzg, blocks: &[Vec<u8>]) -> Vec<NewArchivedSegment> {
+    let mut archiver =
+        Archiver::new(kzg.clone(), RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+
+    blocks
+        .iter()
+        .flat_map(|block| archiver.add_block(block.clone(), BlockObjectMapping::default(), true))
+        .collect()
+}
+
+fn export(dir: &Path, archived_segments: &[NewArchivedSegment]) {
+    for archived_segment in archived_segments {
+        write_archived_segment(dir, archived_segment).unwrap();
+    }
+}
+
+#[test]
+fn archive_round_trip() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let blocks = create_blocks();
+    let archived_segments = archive_blocks(&kzg, &blocks);
+    assert_eq!(archived_segments.len(), 2);
+
+    let dir = tempfile::tempdir().unwrap();
+    export(dir.path(), &archived_segments);
+
+    let segment_headers_store = new_segment_headers_store();
+    let segment_headers = read_segment_headers(&segment_headers_store, dir.path()).unwrap();
+    assert_eq!(
+        segment_headers,
+        archived_segments
+            .iter()
+            .map(|archived_segment| archived_segment.segment_header)
+            .collect::<Vec<_>>()
+    );
+    // Nothing is persisted until confirmed
+    assert_eq!(segment_headers_store.max_segment_index(), None);
+
+    let segment_pieces_source = ArchiveSegmentPieces {
+        dir: dir.path(),
+        kzg: &kzg,
+        segment_headers: &segment_headers,
+    };
+    let mut reconstructor =
+        Reconstructor::new(RecordedHistorySegment::ERASURE_CODING_RATE).unwrap();
+    let mut reconstructed_blocks = Vec::new();
+    for segment_header in &segment_headers {
+        let segment_pieces =
+            block_on(segment_pieces_source.get_segment_pieces(segment_header.segment_index()))
+                .unwrap();
+        reconstructed_blocks.extend(reconstructor.add_segment(&segment_pieces).unwrap().blocks);
+    }
+
+    // Last block is only partially archived
+    assert_eq!(
+        reconstructed_blocks,
+        vec![(0, blocks[0].clone()), (1, blocks[1].clone())]
+    );
+
+    // Segment headers re-created from the same blocks are persisted
+    for archived_segment in &archived_segments {
+        confirm_segment_header(
+            &segment_headers_store,
+            &segment_headers,
+            archived_segment.segment_header,
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        segment_headers_store.max_segment_index(),
+        Some(SegmentIndex::ONE)
+    );
+}
+
+#[test]
+fn tampered_archive() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let blocks = create_blocks();
+    let archived_segments = archive_blocks(&kzg, &blocks);
+
+    // Tampered piece
+    {
+        let dir = tempfile::tempdir().unwrap();
+        export(dir.path(), &archived_segments);
+
+        let path = segment_file_path(dir.path(), SegmentIndex::ONE);
+        let mut archived_segment =
+            NewArchivedSegment::decode(&mut fs::read(&path).unwrap().as_slice()).unwrap();
+        AsMut::<[u8]>::as_mut(&mut archived_segment.pieces[0])[0] ^= 1;
+        fs::write(&path, archived_segment.encode()).unwrap();
+
+        let segment_headers_store = new_segment_headers_store();
+        let segment_headers = read_segment_headers(&segment_headers_store, dir.path()).unwrap();
+        let segment_pieces_source = ArchiveSegmentPieces {
+            dir: dir.path(),
+            kzg: &kzg,
+            segment_headers: &segment_headers,
+        };
+        assert!(block_on(segment_pieces_source.get_segment_pieces(SegmentIndex::ZERO)).is_ok());
+        assert!(block_on(segment_pieces_source.get_segment_pieces(SegmentIndex::ONE)).is_err());
+    }
+
+    // Tampered segment header breaks the chain of segment headers
+    {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tampered_segments = archived_segments.clone();
+        tampered_segments[0].segment_header = tamper_segment_header(
+            tampered_segments[0].segment_header,
+            tampered_segments[0]
+                .segment_header
+                .prev_segment_header_hash(),
+        );
+        export(dir.path(), &tampered_segments);
+
+        let segment_headers_store = new_segment_headers_store();
+        assert!(read_segment_headers(&segment_headers_store, dir.path()).is_err());
+    }
+
+    // Consistently tampered segment headers are not persisted
+    {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tampered_segments = archived_segments.clone();
+        let tampered_segment_header = tamper_segment_header(
+            tampered_segments[0].segment_header,
+            tampered_segments[0]
+                .segment_header
+                .prev_segment_header_hash(),
+        );
+        tampered_segments[0].segment_header = tampered_segment_header;
+        tampered_segments[1].segment_header = tamper_segment_header(
+            tampered_segments[1].segment_header,
+            tampered_segment_header.hash(),
+        );
+        export(dir.path(), &tampered_segments);
+
+        let segment_headers_store = new_segment_headers_store();
+        let segment_headers = read_segment_headers(&segment_headers_store, dir.path()).unwrap();
+
+        // Segment header re-created from blocks doesn't match
+        assert!(confirm_segment_header(
+            &segment_headers_store,
+            &segment_headers,
+            archived_segments[0].segment_header,
+        )
+        .is_err());
+        assert_eq!(segment_headers_store.max_segment_index(), None);
+    }
+
+    // Segment headers that don't match segment headers known to the node are rejected
+    {
+        let dir = tempfile::tempdir().unwrap();
+        export(dir.path(), &archived_segments);
+
+        let segment_headers_store = new_segment_headers_store();
+        segment_headers_store
+            .add_segment_headers(&[tamper_segment_header(
+                archived_segments[0].segment_header,
+                archived_segments[0]
+                    .segment_header
+                    .prev_segment_header_hash(),
+            )])
+            .unwrap();
+        assert!(read_segment_headers(&segment_headers_store, dir.path()).is_err());
+    }
+}
+
+#[test]
+fn check_pieces() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let blocks = create_blocks();
+    let archived_segments = archive_blocks(&kzg, &blocks);
+    let archived_segment = &archived_segments[1];
+
+    // All pieces are valid
+    {
+        let mut report = ArchivePiecesCheckReport::default();
+        block_on(check_segment_pieces(
+            &kzg,
+            &archived_segment.segment_header,
+            &mut report,
+            |piece_index| {
+                let piece = Piece::from(&archived_segment.pieces[piece_index.position() as usize]);
+                future::ready(Ok::<_, Box<dyn Error + Send + Sync>>(Some(piece)))
+            },
+        ));
+
+        assert_eq!(
+            report.valid_pieces,
+            ArchivedHistorySegment::NUM_PIECES as u64
+        );
+        assert_eq!(report.invalid_pieces, 0);
+        assert_eq!(report.missing_pieces, 0);
+        assert!(report.is_success());
+    }
+
+    // Invalid, not found and failed to retrieve pieces
+    {
+        let mut report = ArchivePiecesCheckReport::default();
+        block_on(check_segment_pieces(
+            &kzg,
+            &archived_segment.segment_header,
+            &mut report,
+            |piece_index| {
+                let position = piece_index.position() as usize;
+                let mut piece = Piece::from(&archived_segment.pieces[position]);
+                let result: Result<_, Box<dyn Error + Send + Sync>> = match position {
+                    0 => {
+                        piece.as_mut()[0] ^= 1;
+                        Ok(Some(piece))
+                    }
+                    1 => Ok(None),
+                    2 => Err("Failed to retrieve piece".into()),
+                    // Piece of a different segment
+                    3 => Ok(Some(Piece::from(&archived_segments[0].pieces[position]))),
+                    _ => Ok(Some(piece)),
+                };
+                future::ready(result)
+            },
+        ));
+
+        assert_eq!(
+            report.valid_pieces,
+            ArchivedHistorySegment::NUM_PIECES as u64 - 4
+        );
+        assert_eq!(report.invalid_pieces, 2);
+        assert_eq!(report.missing_pieces, 2);
+        assert!(!report.is_success());
+    }
+}
+
+/// Change last archived block of the segment header and set previous segment header hash
+fn tamper_segment_header(
+    segment_header: SegmentHeader,
+    prev_segment_header_hash: Blake2b256Hash,
+) -> SegmentHeader {
+    let last_archived_block = segment_header.last_archived_block();
+
+    SegmentHeader::V0 {
+        segment_index: segment_header.segment_index(),
+        segment_commitment: segment_header.segment_commitment(),
+        prev_segment_header_hash,
+        last_archived_block: LastArchivedBlock {
+            number: last_archived_block.number + 1,
+            archived_progress: last_archived_block.archived_progress,
+        },
+    }
+}
@@ -1,19 +1,32 @@
+#[cfg(test)]
+mod tests;
+
+use crate::archival_pieces::{ArchivalPiecesRecordProvider, ArchivalPiecesStore};
+use futures::StreamExt;
+use parity_scale_codec::Decode;
 use prometheus_client::registry::Registry;
 use sc_client_api::AuxStore;
 use sc_consensus_subspace::archiver::SegmentHeadersStore;
+use sc_consensus_subspace::notification::SubspaceNotificationStream;
+use sc_consensus_subspace::ArchivedSegmentNotification;
 use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 use subspace_core_primitives::{SegmentHeader, SegmentIndex};
+use subspace_networking::libp2p::gossipsub::MessageAcceptance;
 use subspace_networking::libp2p::kad::Mode as KademliaMode;
 use subspace_networking::libp2p::metrics::Metrics;
 use subspace_networking::libp2p::{identity, Multiaddr};
+use subspace_networking::utils::segment_header_announcements::{
+    announce_segment_header, segment_header_announcements_topic_config, SegmentHeaderAnnouncement,
+};
 use subspace_networking::utils::strip_peer_id;
 use subspace_networking::{
-    CreationError, NetworkParametersPersistenceError, NetworkingParametersManager, Node,
-    NodeRunner, PeerInfoProvider, PieceByIndexRequestHandler,
-    SegmentHeaderBySegmentIndexesRequestHandler, SegmentHeaderRequest, SegmentHeaderResponse,
+    BandwidthLimits, BandwidthMetrics, CreationError, NetworkParametersPersistenceError,
+    NetworkingParametersManager, Node, NodeRunner, PeerInfoProvider, PieceByIndexRequest,
+    PieceByIndexRequestHandler, PieceByIndexResponse, SegmentHeaderBySegmentIndexesRequestHandler,
+    SegmentHeaderRequest, SegmentHeaderResponse, TransportConfig,
 };
 use thiserror::Error;
 use tracing::{debug, error, trace};
@@ -37,6 +50,9 @@ pub struct DsnConfig {
     /// Where local DSN node will listen for incoming connections.
     pub listen_on: Vec<Multiaddr>,
 
+    /// Transports used by DSN.
+    pub transports: TransportConfig,
+
     /// Bootstrap nodes for DSN.
     pub bootstrap_nodes: Vec<Multiaddr>,
 
@@ -69,14 +85,25 @@ pub struct DsnConfig {
 
     /// Known external addresses
     pub external_addresses: Vec<Multiaddr>,
+
+    /// Bandwidth limits and per-peer request quotas for request-response protocols.
+    pub request_response_bandwidth_limits: BandwidthLimits,
 }
 
 pub(crate) fn create_dsn_instance<AS>(
     dsn_protocol_version: String,
     dsn_config: DsnConfig,
     segment_headers_store: SegmentHeadersStore<AS>,
+    archival_pieces_store: Option<ArchivalPiecesStore>,
     enable_metrics: bool,
-) -> Result<(Node, NodeRunner<()>, Option<Registry>), DsnConfigurationError>
+) -> Result<
+    (
+        Node,
+        NodeRunner<ArchivalPiecesRecordProvider>,
+        Option<Registry>,
+    ),
+    DsnConfigurationError,
+>
 where
     AS: AuxStore + Sync + Send + 'static,
 {
@@ -84,6 +111,7 @@ where
 
     let mut metric_registry = Registry::default();
     let metrics = enable_metrics.then(|| Metrics::new(&mut metric_registry));
+    let bandwidth_metrics = enable_metrics.then(|| BandwidthMetrics::new(&mut metric_registry));
 
     let networking_parameters_registry = dsn_config
         .base_path
@@ -105,27 +133,106 @@ where
         })
         .transpose()?;
 
+    let segment_header_announcements = segment_header_announcements_topic_config(Arc::new({
+        let segment_headers_store = segment_headers_store.clone();
+
+        move |_peer_id, message| {
+            let Ok(SegmentHeaderAnnouncement { segment_header }) =
+                SegmentHeaderAnnouncement::decode(&mut &*message)
+            else {
+                return MessageAcceptance::Reject;
+            };
+
+            // Announcements are checked against segment headers archived locally
+            match segment_headers_store.get_segment_header(segment_header.segment_index()) {
+                Some(local_segment_header) => {
+                    if local_segment_header == segment_header {
+                        MessageAcceptance::Accept
+                    } else {
+                        debug!(
+                            segment_index = %segment_header.segment_index(),
+                            "Announced segment header doesn't match local segment header"
+                        );
+                        MessageAcceptance::Reject
+                    }
+                }
+                // Not archived locally yet, can't check
+                None => MessageAcceptance::Ignore,
+            }
+        }
+    }));
+
+    // Archival node is a provider of pieces, hence it needs to serve Kademlia requests such that
+    // other peers can discover it
+    let kademlia_mode = if archival_pieces_store.is_some() {
+        KademliaMode::Server
+    } else {
+        KademliaMode::Client
+    };
+
     let keypair = dsn_config.keypair.clone();
     let default_networking_config = subspace_networking::Config::new(
         dsn_protocol_version,
         keypair,
-        (),
+        ArchivalPiecesRecordProvider::new(
+            dsn_config.keypair.public().to_peer_id(),
+            archival_pieces_store.clone(),
+        ),
         Some(PeerInfoProvider::new_node()),
     );
 
     let networking_config = subspace_networking::Config {
         keypair: dsn_config.keypair.clone(),
         listen_on: dsn_config.listen_on,
+        transports: dsn_config.transports,
         allow_non_global_addresses_in_dht: dsn_config.allow_non_global_addresses_in_dht,
         networking_parameters_registry,
+        gossip_topics: vec![segment_header_announcements],
         request_response_protocols: vec![
             // We need to enable protocol to request pieces
-            PieceByIndexRequestHandler::create(|_, _| async { None }),
+            PieceByIndexRequestHandler::create(move |_, &PieceByIndexRequest { piece_index }| {
+                let archival_pieces_store = archival_pieces_store.clone();
+
+                async move {
+                    // Without archival pieces store node doesn't have pieces to serve
+                    let archival_pieces_store = archival_pieces_store?;
+
+                    // Reading from disk must not block networking
+                    let piece = match tokio::task::spawn_blocking(move || {
+                        archival_pieces_store.get_piece(piece_index)
+                    })
+                    .await
+                    {
+                        Ok(Ok(maybe_piece)) => maybe_piece,
+                        Ok(Err(error)) => {
+                            error!(
+                                %error,
+                                %piece_index,
+                                "Failed to read piece from archival pieces store"
+                            );
+                            None
+                        }
+                        Err(error) => {
+                            error!(
+                                %error,
+                                %piece_index,
+                                "Archival pieces store read task failed"
+                            );
+                            None
+                        }
+                    };
+
+                    Some(PieceByIndexResponse { piece })
+                }
+            }),
             SegmentHeaderBySegmentIndexesRequestHandler::create(move |_, req| {
-                let segment_indexes = match req {
-                    SegmentHeaderRequest::SegmentIndexes { segment_indexes } => {
-                        segment_indexes.clone()
-                    }
+                let maybe_segment_headers = match req {
+                    SegmentHeaderRequest::SegmentIndexes { segment_indexes } => segment_indexes
+                        .iter()
+                        .map(|segment_index| {
+                            segment_headers_store.get_segment_header(*segment_index)
+                        })
+                        .collect::<Option<Vec<SegmentHeader>>>(),
                     SegmentHeaderRequest::LastSegmentHeaders {
                         segment_header_number,
                     } => {
@@ -141,25 +248,25 @@ where
 
                         match segment_headers_store.max_segment_index() {
                             Some(max_segment_index) => {
-                                // Several last segment indexes
-                                (SegmentIndex::ZERO..=max_segment_index)
-                                    .rev()
-                                    .take(segment_headers_limit as usize)
-                                    .collect::<Vec<_>>()
+                                // Several last segment headers, the most recent first
+                                let first_segment_index = SegmentIndex::from(
+                                    (u64::from(max_segment_index) + 1)
+                                        .saturating_sub(segment_headers_limit),
+                                );
+                                let mut segment_headers = segment_headers_store
+                                    .segment_headers(first_segment_index..=max_segment_index);
+                                segment_headers.reverse();
+
+                                Some(segment_headers)
                             }
                             None => {
                                 // Nothing yet
-                                Vec::new()
+                                Some(Vec::new())
                             }
                         }
                     }
                 };
 
-                let maybe_segment_headers = segment_indexes
-                    .iter()
-                    .map(|segment_index| segment_headers_store.get_segment_header(*segment_index))
-                    .collect::<Option<Vec<SegmentHeader>>>();
-
                 let result = match maybe_segment_headers {
                     Some(segment_headers) => Some(SegmentHeaderResponse { segment_headers }),
                     None => {
@@ -183,8 +290,10 @@ where
         general_connected_peers_handler: Some(Arc::new(|_| true)),
         bootstrap_addresses: dsn_config.bootstrap_nodes,
         external_addresses: dsn_config.external_addresses,
-        kademlia_mode: Some(KademliaMode::Client),
+        request_response_bandwidth_limits: dsn_config.request_response_bandwidth_limits,
+        kademlia_mode: Some(kademlia_mode),
         metrics,
+        bandwidth_metrics,
 
         ..default_networking_config
     };
@@ -193,3 +302,26 @@ where
         .map(|(node, node_runner)| (node, node_runner, enable_metrics.then_some(metric_registry)))
         .map_err(Into::into)
 }
+
+/// Announce segment headers of newly archived segments over DSN gossip.
+pub(crate) async fn announce_archived_segment_headers(
+    node: Node,
+    archived_segment_notification_stream: SubspaceNotificationStream<ArchivedSegmentNotification>,
+) {
+    let mut archived_segment_notifications = archived_segment_notification_stream.subscribe();
+
+    while let Some(archived_segment_notification) = archived_segment_notifications.next().await {
+        let segment_header = archived_segment_notification
+            .archived_segment
+            .segment_header;
+        // Announcement doesn't need to wait for anything, acknowledge right away
+        drop(archived_segment_notification);
+
+        let segment_index = segment_header.segment_index();
+        if let Err(error) = announce_segment_header(&node, segment_header).await {
+            debug!(%error, %segment_index, "Failed to announce segment header");
+        } else {
+            trace!(%segment_index, "Segment header announced");
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::archival_pieces::{ArchivalPiecesConfig, ArchivalPiecesStore};
+use crate::dsn::{create_dsn_instance, DsnConfig};
+use crate::test_utils::TestAuxStore;
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+use sc_consensus_subspace::archiver::SegmentHeadersStore;
+use std::sync::Arc;
+use std::time::Duration;
+use subspace_archiving::archiver::NewArchivedSegment;
+use subspace_core_primitives::{
+    ArchivedBlockProgress, ArchivedHistorySegment, Blake2b256Hash, LastArchivedBlock, Piece,
+    SegmentCommitment, SegmentHeader, SegmentIndex,
+};
+use subspace_networking::libp2p::multiaddr::Protocol;
+use subspace_networking::libp2p::{identity, Multiaddr};
+use subspace_networking::utils::piece_provider::{NoPieceValidator, PieceProvider, RetryPolicy};
+use subspace_networking::{BandwidthLimits, Config, Node, PeerInfoProvider, TransportConfig};
+
+const DSN_PROTOCOL_VERSION: &str = "archival-pieces-test";
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn archived_segment(segment_index: u64) -> NewArchivedSegment {
+    let mut pieces = ArchivedHistorySegment::default();
+    for (position, piece) in pieces.iter_mut().enumerate() {
+        AsMut::<[u8]>::as_mut(piece).fill((segment_index as u8) ^ (position as u8));
+    }
+
+    NewArchivedSegment {
+        segment_header: SegmentHeader::V0 {
+            segment_index: SegmentIndex::from(segment_index),
+            segment_commitment: SegmentCommitment::default(),
+            prev_segment_header_hash: Blake2b256Hash::default(),
+            last_archived_block: LastArchivedBlock {
+                number: 0,
+                archived_progress: ArchivedBlockProgress::Complete,
+            },
+        },
+        pieces,
+        object_mapping: Vec::new(),
+    }
+}
+
+/// Starts a node that only retrieves pieces, returns it along with its first listen address.
+async fn start_client_node() -> (Node, Multiaddr) {
+    let config = Config {
+        listen_on: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        allow_non_global_addresses_in_dht: true,
+        ..Config::new(
+            DSN_PROTOCOL_VERSION.to_string(),
+            identity::Keypair::generate_ed25519(),
+            (),
+            Some(PeerInfoProvider::new_client()),
+        )
+    };
+    let (node, mut node_runner) = subspace_networking::construct(config).unwrap();
+
+    let (address_sender, address_receiver) = oneshot::channel();
+    let on_new_listener_handler = node.on_new_listener(Arc::new({
+        let address_sender = Mutex::new(Some(address_sender));
+
+        move |address| {
+            if let Some(address_sender) = address_sender.lock().take() {
+                address_sender.send(address.clone()).unwrap();
+            }
+        }
+    }));
+
+    tokio::spawn(async move {
+        node_runner.run().await;
+    });
+
+    let address = address_receiver.await.unwrap();
+    drop(on_new_listener_handler);
+
+    (node, address)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_gets_piece_from_archival_node() {
+    let directory = tempfile::tempdir().unwrap();
+    let archival_pieces_store = ArchivalPiecesStore::open(ArchivalPiecesConfig {
+        directory: directory.path().to_path_buf(),
+        max_segments: None,
+        backfill: false,
+    })
+    .unwrap();
+    let old_archived_segment = archived_segment(0);
+    archival_pieces_store
+        .store_segment(&old_archived_segment)
+        .unwrap();
+    archival_pieces_store
+        .store_segment(&archived_segment(1))
+        .unwrap();
+
+    // Client has no bootstrap nodes, archival node connects to the client instead and client must
+    // discover that archival node provides pieces through Kademlia
+    let (client_node, client_address) = start_client_node().await;
+
+    let (connected_sender, connected_receiver) = oneshot::channel();
+    let _on_connected_peer_handler = client_node.on_connected_peer(Arc::new({
+        let connected_sender = Mutex::new(Some(connected_sender));
+
+        move |_peer_id| {
+            if let Some(connected_sender) = connected_sender.lock().take() {
+                let _ = connected_sender.send(());
+            }
+        }
+    }));
+
+    let dsn_config = DsnConfig {
+        listen_on: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        transports: TransportConfig::default(),
+        bootstrap_nodes: vec![client_address.with(Protocol::P2p(client_node.id()))],
+        reserved_peers: Vec::new(),
+        keypair: identity::Keypair::generate_ed25519(),
+        allow_non_global_addresses_in_dht: true,
+        base_path: None,
+        max_in_connections: 10,
+        max_out_connections: 10,
+        max_pending_in_connections: 10,
+        max_pending_out_connections: 10,
+        target_connections: 10,
+        external_addresses: Vec::new(),
+        request_response_bandwidth_limits: BandwidthLimits::default(),
+    };
+    let (_archival_node, mut archival_node_runner, _metrics_registry) = create_dsn_instance(
+        DSN_PROTOCOL_VERSION.to_string(),
+        dsn_config,
+        SegmentHeadersStore::new(Arc::new(TestAuxStore::default())).unwrap(),
+        Some(archival_pieces_store),
+        false,
+    )
+    .unwrap();
+    tokio::spawn(async move {
+        archival_node_runner.run().await;
+    });
+
+    tokio::time::timeout(TIMEOUT, connected_receiver)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let piece_provider = PieceProvider::<NoPieceValidator>::new(client_node, None);
+    let piece_index = SegmentIndex::ZERO.segment_piece_indexes()[3];
+
+    // Archival node is only added to Kademlia routing table of the client once it is identified
+    let piece = tokio::time::timeout(TIMEOUT, async {
+        loop {
+            if let Some(piece) = piece_provider
+                .get_piece(piece_index, RetryPolicy::Limited(0))
+                .await
+                .unwrap()
+            {
+                break piece;
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(piece, Piece::from(&old_archived_segment.pieces[3]));
+}
@@ -24,14 +24,26 @@
     type_changing_struct_update
 )]
 
+pub mod archival_pieces;
+pub mod archive;
 pub mod dsn;
 mod metrics;
+pub mod object_mappings;
 pub mod rpc;
 mod sync_from_dsn;
+mod sync_transaction_pool;
+#[cfg(test)]
+mod test_utils;
 pub mod tx_pre_validator;
 
+use crate::archival_pieces::{
+    backfill_archival_pieces, store_archived_segments, ArchivalPiecesConfig, ArchivalPiecesStore,
+};
 use crate::dsn::{create_dsn_instance, DsnConfigurationError};
 use crate::metrics::NodeMetrics;
+use crate::object_mappings::{
+    index_archived_segments, reindex_archived_history, ObjectMappingsIndex,
+};
 use crate::tx_pre_validator::ConsensusChainTxPreValidator;
 use cross_domain_message_gossip::cdm_gossip_peers_set_config;
 use domain_runtime_primitives::{BlockNumber as DomainNumber, Hash as DomainHash};
@@ -51,14 +63,19 @@ use sc_client_api::{Backend, BlockBackend, BlockchainEvents, ExecutorProvider, H
 use sc_consensus::{BlockImport, DefaultImportQueue, ImportQueue};
 use sc_consensus_slots::SlotProportion;
 use sc_consensus_subspace::archiver::{create_subspace_archiver, SegmentHeadersStore};
+use sc_consensus_subspace::farmer_equivocation::FarmerEquivocationTracker;
 use sc_consensus_subspace::notification::SubspaceNotificationStream;
 use sc_consensus_subspace::{
-    ArchivedSegmentNotification, BlockImportingNotification, NewSlotNotification,
-    RewardSigningNotification, SubspaceLink, SubspaceParams, SubspaceSyncOracle,
+    get_chain_constants, ArchivedSegmentNotification, BlockImportingNotification,
+    NewSlotNotification, RewardSigningNotification, SubspaceLink, SubspaceParams,
+    SubspaceSyncOracle,
 };
+use sc_consensus_subspace_rpc::{ArchivalPiecesProvider, ObjectMappingsProvider};
 use sc_executor::{NativeElseWasmExecutor, NativeExecutionDispatch};
 use sc_network::NetworkService;
 #[cfg(feature = "pot")]
+use sc_proof_of_time::source::external_timekeeper::ExternalTimekeeperConfig;
+#[cfg(feature = "pot")]
 use sc_proof_of_time::source::gossip::pot_gossip_peers_set_config;
 #[cfg(feature = "pot")]
 use sc_proof_of_time::source::PotSourceWorker;
@@ -66,8 +83,10 @@ use sc_proof_of_time::source::PotSourceWorker;
 use sc_proof_of_time::verifier::PotVerifier;
 use sc_service::error::Error as ServiceError;
 use sc_service::{Configuration, NetworkStarter, SpawnTasksParams, TaskManager};
+pub use sc_subspace_block_relay::ConsensusRelayConfig;
 use sc_subspace_block_relay::{
-    build_consensus_relay, BlockRelayConfigurationError, NetworkWrapper,
+    build_consensus_relay, build_transaction_pool_relay, BlockRelayConfigurationError,
+    NetworkWrapper, TransactionPoolRelayParams,
 };
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
@@ -226,9 +245,23 @@ pub struct SubspaceConfiguration {
     /// Use the block request handler implementation from subspace
     /// instead of the default substrate handler.
     pub enable_subspace_block_relay: bool,
+    /// Consensus block relay configuration, used if `enable_subspace_block_relay` is set.
+    pub block_relay: ConsensusRelayConfig,
+    /// Index object mappings of archived history, such that objects can be found by their hashes
+    /// over RPC.
+    pub index_object_mappings: bool,
+    /// Build object mappings index from the whole archived history on startup, requires
+    /// `index_object_mappings` and blocks not being pruned.
+    pub reindex_object_mappings: bool,
+    /// Persist pieces of archived history locally, such that they can be served over RPC and DSN
+    /// even after they are no longer cached, `None` if disabled.
+    pub archival_pieces: Option<ArchivalPiecesConfig>,
     /// Is this node a Timekeeper
     #[cfg(feature = "pot")]
     pub is_timekeeper: bool,
+    /// External timekeeper to receive proofs of time from, `None` if not used
+    #[cfg(feature = "pot")]
+    pub pot_external_timekeeper: Option<ExternalTimekeeperConfig>,
 }
 
 struct SubspaceExtensionsFactory<PosTable, Client> {
@@ -728,6 +761,12 @@ where
         mut telemetry,
     } = other;
 
+    let archival_pieces_store = config
+        .archival_pieces
+        .clone()
+        .map(ArchivalPiecesStore::open)
+        .transpose()?;
+
     let (node, bootstrap_nodes, dsn_metrics_registry) = match config.subspace_networking {
         SubspaceNetworking::Reuse {
             node,
@@ -747,6 +786,7 @@ where
                 dsn_protocol_version,
                 dsn_config.clone(),
                 segment_headers_store.clone(),
+                archival_pieces_store.clone(),
                 config.base.prometheus_config.is_some(),
             )?;
 
@@ -832,6 +872,7 @@ where
                 network_wrapper.clone(),
                 client.clone(),
                 transaction_pool.clone(),
+                config.block_relay.clone(),
                 config.base.prometheus_registry(),
             )
             .map_err(Error::BlockRelay)?,
@@ -841,6 +882,23 @@ where
     };
     let mut net_config = sc_network::config::FullNetworkConfiguration::new(&config.base.network);
     net_config.add_notification_protocol(cdm_gossip_peers_set_config());
+    let transaction_pool_relay = if config.enable_subspace_block_relay {
+        let TransactionPoolRelayParams {
+            relay,
+            server,
+            request_response_config,
+        } = build_transaction_pool_relay(
+            network_wrapper.clone(),
+            client.clone(),
+            transaction_pool.clone(),
+            config.base.prometheus_registry(),
+        )
+        .map_err(Error::BlockRelay)?;
+        net_config.add_request_response_protocol(request_response_config);
+        Some((relay, server))
+    } else {
+        None
+    };
     #[cfg(feature = "pot")]
     net_config.add_notification_protocol(pot_gossip_peers_set_config());
     let sync_mode = Arc::clone(&net_config.network_config.sync_mode);
@@ -859,22 +917,129 @@ where
 
     let sync_oracle = SubspaceSyncOracle::new(config.base.force_authoring, sync_service.clone());
 
+    let object_mappings_index = config
+        .index_object_mappings
+        .then(|| ObjectMappingsIndex::new(client.clone()));
+    if let Some(object_mappings_index) = &object_mappings_index {
+        // Subscribe before archiver is started to not miss any archived segments
+        task_manager.spawn_handle().spawn(
+            "object-mappings-indexer",
+            Some("subspace-object-mappings"),
+            index_archived_segments(
+                object_mappings_index.clone(),
+                subspace_link
+                    .archived_segment_notification_stream()
+                    .subscribe(),
+            ),
+        );
+
+        if config.reindex_object_mappings {
+            let client = client.clone();
+            let kzg = subspace_link.kzg().clone();
+            let object_mappings_index = object_mappings_index.clone();
+
+            task_manager.spawn_handle().spawn_blocking(
+                "object-mappings-reindexer",
+                Some("subspace-object-mappings"),
+                async move {
+                    match reindex_archived_history(&*client, kzg, &object_mappings_index) {
+                        Ok(indexed_segments) => {
+                            info!(%indexed_segments, "Object mappings re-indexing finished");
+                        }
+                        Err(error) => {
+                            error!(%error, "Object mappings re-indexing failed");
+                        }
+                    }
+                },
+            );
+        }
+    }
+
+    if let Some(archival_pieces_store) = &archival_pieces_store {
+        // Subscribe before archiver is started to not miss any archived segments
+        task_manager.spawn_handle().spawn_blocking(
+            "archival-pieces-store",
+            Some("subspace-archival-pieces"),
+            store_archived_segments(
+                archival_pieces_store.clone(),
+                subspace_link
+                    .archived_segment_notification_stream()
+                    .subscribe(),
+            ),
+        );
+
+        if config
+            .archival_pieces
+            .as_ref()
+            .is_some_and(|archival_pieces| archival_pieces.backfill)
+        {
+            let client = client.clone();
+            let kzg = subspace_link.kzg().clone();
+            let archival_pieces_store = archival_pieces_store.clone();
+
+            task_manager.spawn_handle().spawn_blocking(
+                "archival-pieces-backfill",
+                Some("subspace-archival-pieces"),
+                async move {
+                    match backfill_archival_pieces(&*client, kzg, &archival_pieces_store) {
+                        Ok(stored_segments) => {
+                            info!(%stored_segments, "Archival pieces backfill finished");
+                        }
+                        Err(error) => {
+                            error!(%error, "Archival pieces backfill failed");
+                        }
+                    }
+                },
+            );
+        }
+    }
+
     let subspace_archiver = create_subspace_archiver(
         segment_headers_store.clone(),
         &subspace_link,
         client.clone(),
         sync_oracle.clone(),
         telemetry.as_ref().map(|telemetry| telemetry.handle()),
-    );
+    )
+    .map_err(sc_service::Error::from)?;
 
     task_manager
         .spawn_essential_handle()
         .spawn_essential_blocking("subspace-archiver", None, Box::pin(subspace_archiver));
 
+    task_manager.spawn_handle().spawn(
+        "segment-header-announcer",
+        Some("subspace-networking"),
+        dsn::announce_archived_segment_headers(
+            node.clone(),
+            subspace_link.archived_segment_notification_stream(),
+        ),
+    );
+
     if config.enable_subspace_block_relay {
         network_wrapper.set(network_service.clone());
     }
+    if let Some((relay, server)) = transaction_pool_relay {
+        task_manager
+            .spawn_handle()
+            .spawn("transaction-pool-relay-server", None, server.run());
+        let network_service = network_service.clone();
+        let sync_oracle = sync_oracle.clone();
+        task_manager
+            .spawn_handle()
+            .spawn("transaction-pool-sync", None, async move {
+                sync_transaction_pool::sync_transaction_pool(
+                    relay,
+                    network_service.as_ref(),
+                    sync_oracle,
+                )
+                .await
+            });
+    }
     if config.sync_from_dsn {
+        let erasure_coding_rate = get_chain_constants::<Block, _>(client.as_ref())
+            .map_err(|error| Error::Other(error.into()))?
+            .erasure_coding_rate();
         let (observer, worker) = sync_from_dsn::create_observer_and_worker(
             segment_headers_store.clone(),
             Arc::clone(&network_service),
@@ -883,6 +1048,7 @@ where
             import_queue_service,
             sync_mode,
             subspace_link.kzg().clone(),
+            erasure_coding_rate,
         );
         task_manager
             .spawn_handle()
@@ -950,9 +1116,10 @@ where
     let archived_segment_notification_stream = subspace_link.archived_segment_notification_stream();
 
     #[cfg(feature = "pot")]
-    let pot_slot_info_stream = {
+    let (pot_slot_info_stream, pot_slot_info_notification_stream) = {
         let (pot_source_worker, pot_gossip_worker, pot_slot_info_stream) = PotSourceWorker::new(
             config.is_timekeeper,
+            config.pot_external_timekeeper.clone(),
             client.clone(),
             pot_verifier.clone(),
             network_service.clone(),
@@ -960,14 +1127,18 @@ where
             sync_oracle.clone(),
         )
         .map_err(|error| Error::Other(error.into()))?;
+        let pot_slot_info_notification_stream = pot_source_worker.slot_info_notification_stream();
         let spawn_essential_handle = task_manager.spawn_essential_handle();
 
         spawn_essential_handle.spawn("pot-source", Some("pot"), pot_source_worker.run());
         spawn_essential_handle.spawn("pot-gossip", Some("pot"), pot_gossip_worker.run());
 
-        pot_slot_info_stream
+        (pot_slot_info_stream, pot_slot_info_notification_stream)
     };
 
+    let farmer_equivocation_tracker =
+        FarmerEquivocationTracker::new(config.base.prometheus_registry())?;
+
     if config.base.role.is_authority() || config.force_new_slot_notifications {
         let proposer_factory = ProposerFactory::new(
             task_manager.spawn_handle(),
@@ -1020,6 +1191,7 @@ where
             max_block_proposal_slot_portion: None,
             telemetry: telemetry.as_ref().map(|x| x.handle()),
             offchain_tx_pool_factory,
+            farmer_equivocation_tracker: farmer_equivocation_tracker.clone(),
             #[cfg(feature = "pot")]
             pot_verifier,
             #[cfg(feature = "pot")]
@@ -1073,6 +1245,16 @@ where
             let archived_segment_notification_stream = archived_segment_notification_stream.clone();
             let transaction_pool = transaction_pool.clone();
             let chain_spec = config.base.chain_spec.cloned_box();
+            let object_mappings_provider = object_mappings_index.map(
+                |object_mappings_index| -> Arc<dyn ObjectMappingsProvider> {
+                    Arc::new(object_mappings_index)
+                },
+            );
+            let archival_pieces_provider = archival_pieces_store.map(
+                |archival_pieces_store| -> Arc<dyn ArchivalPiecesProvider> {
+                    Arc::new(archival_pieces_store)
+                },
+            );
 
             Box::new(move |deny_unsafe, subscription_executor| {
                 let deps = rpc::FullDeps {
@@ -1088,7 +1270,12 @@ where
                     dsn_bootstrap_nodes: dsn_bootstrap_nodes.clone(),
                     segment_headers_store: segment_headers_store.clone(),
                     sync_oracle: sync_oracle.clone(),
+                    farmer_equivocation_tracker: farmer_equivocation_tracker.clone(),
                     kzg: subspace_link.kzg().clone(),
+                    object_mappings_provider: object_mappings_provider.clone(),
+                    archival_pieces_provider: archival_pieces_provider.clone(),
+                    #[cfg(feature = "pot")]
+                    pot_slot_info_notification_stream: pot_slot_info_notification_stream.clone(),
                 };
 
                 rpc::create_full(deps).map_err(Into::into)
@@ -0,0 +1,226 @@
+// Copyright (C) 2023 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Index of object mappings of archived history.
+//!
+//! Archiver produces object mappings for every archived segment, index persists them such that
+//! applications can find objects in archived history by their hashes over RPC.
+
+#[cfg(test)]
+mod tests;
+
+use futures::{Stream, StreamExt};
+use parity_scale_codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sc_client_api::{AuxStore, BlockBackend, HeaderBackend};
+use sc_consensus_subspace::archiver::recreate_archived_segments;
+use sc_consensus_subspace::ArchivedSegmentNotification;
+use sc_consensus_subspace_rpc::ObjectMappingsProvider;
+use sp_api::ProvideRuntimeApi;
+use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
+use sp_objects::ObjectsApi;
+use sp_runtime::traits::Block as BlockT;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+use subspace_archiving::archiver::NewArchivedSegment;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::objects::GlobalObject;
+use subspace_core_primitives::{Blake2b256Hash, SegmentIndex};
+use tracing::{debug, error, info};
+
+/// Persistent index of object mappings of archived history.
+///
+/// Maps object hashes to location of objects in archived history and additionally stores object
+/// mappings of every indexed segment. In case the same object is stored in archived history more
+/// than once, its earliest location is indexed.
+#[derive(Debug)]
+pub struct ObjectMappingsIndex<AS> {
+    aux_store: Arc<AS>,
+    /// Indexing reads existing entries before writing new ones, segments indexed concurrently (like
+    /// during re-indexing) must not overwrite each other's earlier object locations
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl<AS> Clone for ObjectMappingsIndex<AS> {
+    fn clone(&self) -> Self {
+        Self {
+            aux_store: Arc::clone(&self.aux_store),
+            write_lock: Arc::clone(&self.write_lock),
+        }
+    }
+}
+
+impl<AS> ObjectMappingsIndex<AS>
+where
+    AS: AuxStore,
+{
+    const OBJECT_KEY_PREFIX: &[u8] = b"object-mapping";
+    const SEGMENT_KEY_PREFIX: &[u8] = b"segment-object-mappings";
+
+    /// Create new instance
+    pub fn new(aux_store: Arc<AS>) -> Self {
+        Self {
+            aux_store,
+            write_lock: Arc::default(),
+        }
+    }
+
+    /// Index object mappings of archived segment, indexing the same segment again is a no-op
+    pub fn index_segment(
+        &self,
+        archived_segment: &NewArchivedSegment,
+    ) -> Result<(), sp_blockchain::Error> {
+        let segment_index = archived_segment.segment_header.segment_index();
+        let objects = archived_segment.global_object_mapping().collect::<Vec<_>>();
+
+        // Objects are iterated in the order of their location, so the first occurrence within the
+        // segment is the earliest one
+        let mut new_objects = HashMap::<Blake2b256Hash, GlobalObject>::new();
+        for (hash, global_object) in &objects {
+            if let Entry::Vacant(entry) = new_objects.entry(*hash) {
+                entry.insert(*global_object);
+            }
+        }
+
+        let _write_guard = self.write_lock.lock();
+
+        let mut values = Vec::with_capacity(new_objects.len() + 1);
+        for (hash, global_object) in new_objects {
+            let maybe_existing_global_object =
+                self.load::<GlobalObject>(&Self::object_key(&hash))?;
+            if let Some(existing_global_object) = maybe_existing_global_object {
+                if existing_global_object.piece_index() <= global_object.piece_index() {
+                    continue;
+                }
+            }
+
+            values.push((Self::object_key(&hash), global_object.encode()));
+        }
+        values.push((Self::segment_key(segment_index), objects.encode()));
+
+        let insert_data = values
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+            .collect::<Vec<_>>();
+        self.aux_store.insert_aux(&insert_data, &[])?;
+
+        debug!(
+            %segment_index,
+            objects = %objects.len(),
+            "Indexed object mappings of archived segment"
+        );
+
+        Ok(())
+    }
+
+    fn object_key(hash: &Blake2b256Hash) -> Vec<u8> {
+        (Self::OBJECT_KEY_PREFIX, hash).encode()
+    }
+
+    fn segment_key(segment_index: SegmentIndex) -> Vec<u8> {
+        (Self::SEGMENT_KEY_PREFIX, segment_index).encode()
+    }
+
+    fn load<T>(&self, key: &[u8]) -> Result<Option<T>, sp_blockchain::Error>
+    where
+        T: Decode,
+    {
+        self.aux_store
+            .get_aux(key)?
+            .map(|value| {
+                T::decode(&mut value.as_slice()).map_err(|error| {
+                    sp_blockchain::Error::Backend(format!(
+                        "Failed to decode object mappings index entry: {error}"
+                    ))
+                })
+            })
+            .transpose()
+    }
+}
+
+impl<AS> ObjectMappingsProvider for ObjectMappingsIndex<AS>
+where
+    AS: AuxStore + Send + Sync,
+{
+    fn object_mapping(
+        &self,
+        hash: &Blake2b256Hash,
+    ) -> Result<Option<GlobalObject>, sp_blockchain::Error> {
+        self.load(&Self::object_key(hash))
+    }
+
+    fn segment_object_mappings(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<Option<Vec<(Blake2b256Hash, GlobalObject)>>, sp_blockchain::Error> {
+        self.load(&Self::segment_key(segment_index))
+    }
+}
+
+/// Index object mappings of newly archived segments as they arrive.
+pub(crate) async fn index_archived_segments<AS, S>(
+    object_mappings_index: ObjectMappingsIndex<AS>,
+    mut archived_segments: S,
+) where
+    AS: AuxStore,
+    S: Stream<Item = ArchivedSegmentNotification> + Unpin,
+{
+    while let Some(ArchivedSegmentNotification {
+        archived_segment,
+        acknowledgement_sender,
+    }) = archived_segments.next().await
+    {
+        if let Err(error) = object_mappings_index.index_segment(&archived_segment) {
+            error!(
+                %error,
+                segment_index = %archived_segment.segment_header.segment_index(),
+                "Failed to index object mappings of archived segment"
+            );
+        }
+
+        // Archiver waits for all acknowledgement senders to be dropped, indexing is done by now
+        drop(acknowledgement_sender);
+    }
+}
+
+/// Build object mappings index from archived history that is re-created from blocks in the
+/// database, which must not be pruned.
+///
+/// Returns number of indexed segments.
+pub(crate) fn reindex_archived_history<Block, Client>(
+    client: &Client,
+    kzg: Kzg,
+    object_mappings_index: &ObjectMappingsIndex<Client>,
+) -> Result<u64, Box<dyn std::error::Error>>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block> + AuxStore,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+{
+    info!("Re-indexing object mappings of archived history");
+
+    recreate_archived_segments(client, kzg, |archived_segment| {
+        object_mappings_index.index_segment(&archived_segment)?;
+
+        info!(
+            segment_index = %archived_segment.segment_header.segment_index(),
+            "Re-indexed object mappings of archived segment"
+        );
+
+        Ok(())
+    })
+}
@@ -0,0 +1,149 @@
+use crate::object_mappings::ObjectMappingsIndex;
+use crate::test_utils::TestAuxStore;
+use sc_consensus_subspace_rpc::ObjectMappingsProvider;
+use std::sync::Arc;
+use std::thread;
+use subspace_archiving::archiver::NewArchivedSegment;
+use subspace_core_primitives::objects::{GlobalObject, PieceObject, PieceObjectMapping};
+use subspace_core_primitives::{
+    ArchivedBlockProgress, ArchivedHistorySegment, Blake2b256Hash, LastArchivedBlock, PieceIndex,
+    SegmentCommitment, SegmentHeader, SegmentIndex,
+};
+
+/// Archived segment with objects in the first source piece, pieces themselves are not used by the
+/// index
+fn archived_segment(segment_index: u64, objects: &[(Blake2b256Hash, u32)]) -> NewArchivedSegment {
+    NewArchivedSegment {
+        segment_header: SegmentHeader::V0 {
+            segment_index: SegmentIndex::from(segment_index),
+            segment_commitment: SegmentCommitment::default(),
+            prev_segment_header_hash: Blake2b256Hash::default(),
+            last_archived_block: LastArchivedBlock {
+                number: 0,
+                archived_progress: ArchivedBlockProgress::Complete,
+            },
+        },
+        pieces: ArchivedHistorySegment::default(),
+        object_mapping: vec![PieceObjectMapping {
+            objects: objects
+                .iter()
+                .map(|&(hash, offset)| PieceObject::V0 { hash, offset })
+                .collect(),
+        }],
+    }
+}
+
+fn global_object(segment_index: u64, offset: u32) -> GlobalObject {
+    GlobalObject::V0 {
+        piece_index: SegmentIndex::from(segment_index).first_piece_index(),
+        offset,
+    }
+}
+
+#[test]
+fn index_segments() {
+    let index = ObjectMappingsIndex::new(Arc::new(TestAuxStore::default()));
+    let hash_1 = [1; 32];
+    let hash_2 = [2; 32];
+    let hash_3 = [3; 32];
+
+    assert_eq!(index.object_mapping(&hash_1).unwrap(), None);
+    assert_eq!(
+        index.segment_object_mappings(SegmentIndex::ONE).unwrap(),
+        None
+    );
+
+    // The same object is stored twice within the segment
+    index
+        .index_segment(&archived_segment(
+            1,
+            &[(hash_1, 10), (hash_2, 20), (hash_1, 30)],
+        ))
+        .unwrap();
+
+    // The earliest location is indexed
+    assert_eq!(
+        index.object_mapping(&hash_1).unwrap(),
+        Some(global_object(1, 10))
+    );
+    assert_eq!(
+        index.object_mapping(&hash_2).unwrap(),
+        Some(global_object(1, 20))
+    );
+    // All objects of the segment are stored
+    assert_eq!(
+        index.segment_object_mappings(SegmentIndex::ONE).unwrap(),
+        Some(vec![
+            (hash_1, global_object(1, 10)),
+            (hash_2, global_object(1, 20)),
+            (hash_1, global_object(1, 30)),
+        ])
+    );
+
+    // Later segment doesn't override earlier locations, but older one does
+    index
+        .index_segment(&archived_segment(2, &[(hash_1, 1), (hash_3, 2)]))
+        .unwrap();
+    index
+        .index_segment(&archived_segment(0, &[(hash_2, 5)]))
+        .unwrap();
+
+    assert_eq!(
+        index.object_mapping(&hash_1).unwrap(),
+        Some(global_object(1, 10))
+    );
+    assert_eq!(
+        index.object_mapping(&hash_2).unwrap(),
+        Some(global_object(0, 5))
+    );
+    assert_eq!(
+        index.object_mapping(&hash_3).unwrap(),
+        Some(global_object(2, 2))
+    );
+    assert_eq!(
+        index
+            .segment_object_mappings(SegmentIndex::from(2))
+            .unwrap()
+            .map(|objects| objects.len()),
+        Some(2)
+    );
+
+    // Indexing the same segment again is a no-op
+    index
+        .index_segment(&archived_segment(
+            1,
+            &[(hash_1, 10), (hash_2, 20), (hash_1, 30)],
+        ))
+        .unwrap();
+    assert_eq!(
+        index.object_mapping(&hash_2).unwrap(),
+        Some(global_object(0, 5))
+    );
+}
+
+#[test]
+fn concurrent_indexing() {
+    let index = ObjectMappingsIndex::new(Arc::new(TestAuxStore::default()));
+    let hash = [1; 32];
+
+    // The same object in every segment, like when segments are indexed as they are archived and
+    // re-indexed at the same time
+    thread::scope(|scope| {
+        for segment_index in (0..8).rev() {
+            let index = &index;
+            scope.spawn(move || {
+                index
+                    .index_segment(&archived_segment(segment_index, &[(hash, 7)]))
+                    .unwrap();
+            });
+        }
+    });
+
+    assert_eq!(
+        index.object_mapping(&hash).unwrap(),
+        Some(GlobalObject::V0 {
+            piece_index: PieceIndex::ZERO,
+            offset: 7,
+        })
+    );
+}
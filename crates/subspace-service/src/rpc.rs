@@ -25,11 +25,17 @@ use jsonrpsee::RpcModule;
 use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 use sc_client_api::{AuxStore, BlockBackend};
 use sc_consensus_subspace::archiver::SegmentHeadersStore;
+use sc_consensus_subspace::farmer_equivocation::FarmerEquivocationTracker;
 use sc_consensus_subspace::notification::SubspaceNotificationStream;
 use sc_consensus_subspace::{
     ArchivedSegmentNotification, NewSlotNotification, RewardSigningNotification, SubspaceSyncOracle,
 };
-use sc_consensus_subspace_rpc::{SubspaceRpc, SubspaceRpcApiServer, SubspaceRpcConfig};
+use sc_consensus_subspace_rpc::{
+    ArchivalPiecesProvider, ObjectMappingsProvider, SubspaceRpc, SubspaceRpcApiServer,
+    SubspaceRpcConfig,
+};
+#[cfg(feature = "pot")]
+use sc_proof_of_time::source::PotSlotInfoNotificationStream;
 use sc_rpc::SubscriptionTaskExecutor;
 use sc_rpc_api::DenyUnsafe;
 use sc_rpc_spec_v2::chain_spec::{ChainSpec, ChainSpecApiServer};
@@ -76,8 +82,17 @@ where
     pub segment_headers_store: SegmentHeadersStore<AS>,
     /// Subspace sync oracle.
     pub sync_oracle: SubspaceSyncOracle<SO>,
+    /// Tracker of equivocation by the connected farmers.
+    pub farmer_equivocation_tracker: FarmerEquivocationTracker,
     /// Kzg instance.
     pub kzg: Kzg,
+    /// Object mappings provider, `None` if object mappings index is disabled.
+    pub object_mappings_provider: Option<Arc<dyn ObjectMappingsProvider>>,
+    /// Archival pieces provider, `None` if archival pieces store is disabled.
+    pub archival_pieces_provider: Option<Arc<dyn ArchivalPiecesProvider>>,
+    /// A stream with notifications about proof of time slots.
+    #[cfg(feature = "pot")]
+    pub pot_slot_info_notification_stream: PotSlotInfoNotificationStream,
 }
 
 /// Instantiate all full RPC extensions.
@@ -114,7 +129,12 @@ where
         dsn_bootstrap_nodes,
         segment_headers_store,
         sync_oracle,
+        farmer_equivocation_tracker,
         kzg,
+        object_mappings_provider,
+        archival_pieces_provider,
+        #[cfg(feature = "pot")]
+        pot_slot_info_notification_stream,
     } = deps;
 
     let chain_name = chain_spec.name().to_string();
@@ -135,7 +155,12 @@ where
             dsn_bootstrap_nodes,
             segment_headers_store,
             sync_oracle,
+            farmer_equivocation_tracker,
             kzg,
+            object_mappings_provider,
+            archival_pieces_provider,
+            #[cfg(feature = "pot")]
+            pot_slot_info_notification_stream,
             deny_unsafe,
         })?
         .into_rpc(),
@@ -1,4 +1,4 @@
-mod import_blocks;
+pub(crate) mod import_blocks;
 mod piece_validator;
 mod segment_header_downloader;
 
@@ -49,6 +49,7 @@ pub(super) fn create_observer_and_worker<Block, AS, Client>(
     mut import_queue_service: Box<dyn ImportQueueService<Block>>,
     sync_mode: Arc<Atomic<SyncMode>>,
     kzg: Kzg,
+    erasure_coding_rate: (usize, usize),
 ) -> (
     impl Future<Output = ()> + Send + 'static,
     impl Future<Output = Result<(), sc_service::Error>> + Send + 'static,
@@ -79,6 +80,7 @@ where
             sync_mode,
             rx,
             &kzg,
+            erasure_coding_rate,
         )
         .await
     };
@@ -208,6 +210,7 @@ async fn create_worker<Block, AS, IQS, Client>(
     sync_mode: Arc<Atomic<SyncMode>>,
     mut notifications: mpsc::Receiver<NotificationReason>,
     kzg: &Kzg,
+    erasure_coding_rate: (usize, usize),
 ) -> Result<(), sc_service::Error>
 where
     Block: BlockT,
@@ -248,6 +251,7 @@ where
             import_queue_service,
             &mut last_processed_segment_index,
             &mut last_processed_block_number,
+            erasure_coding_rate,
         )
         .await
         {
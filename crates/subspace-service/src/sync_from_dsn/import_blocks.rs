@@ -15,6 +15,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::sync_from_dsn::segment_header_downloader::SegmentHeaderDownloader;
+use async_trait::async_trait;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use sc_client_api::{AuxStore, BlockBackend, HeaderBackend};
@@ -44,6 +45,31 @@ const QUEUED_BLOCKS_LIMIT: BlockNumber = 500;
 /// Time to wait for blocks to import if import is too slow
 const WAIT_FOR_BLOCKS_TO_IMPORT: Duration = Duration::from_secs(1);
 
+/// Source of pieces of archived segments that blocks are reconstructed from.
+#[async_trait]
+pub(crate) trait SegmentPiecesSource {
+    /// Get pieces of the segment at corresponding positions, at least
+    /// [`RecordedHistorySegment::NUM_RAW_RECORDS`] of them must be present for reconstruction to
+    /// succeed.
+    async fn get_segment_pieces(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<Vec<Option<Piece>>, sc_service::Error>;
+}
+
+#[async_trait]
+impl<PV> SegmentPiecesSource for PieceProvider<PV>
+where
+    PV: PieceValidator,
+{
+    async fn get_segment_pieces(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<Vec<Option<Piece>>, sc_service::Error> {
+        Ok(download_segment_pieces(segment_index, self).await)
+    }
+}
+
 /// Starts the process of importing blocks.
 ///
 /// Returns number of downloaded blocks.
@@ -55,6 +81,7 @@ pub async fn import_blocks_from_dsn<Block, AS, Client, PV, IQS>(
     import_queue_service: &mut IQS,
     last_processed_segment_index: &mut SegmentIndex,
     last_processed_block_number: &mut <Block::Header as Header>::Number,
+    erasure_coding_rate: (usize, usize),
 ) -> Result<u64, sc_service::Error>
 where
     Block: BlockT,
@@ -83,13 +110,50 @@ where
         }
     }
 
+    import_blocks_from_segments(
+        segment_headers_store,
+        client,
+        piece_provider,
+        import_queue_service,
+        last_processed_segment_index,
+        last_processed_block_number,
+        erasure_coding_rate,
+    )
+    .await
+}
+
+/// Reconstructs blocks from segments following `last_processed_segment_index` up to the last
+/// segment known to segment headers store and imports them.
+///
+/// Returns number of imported blocks.
+pub(crate) async fn import_blocks_from_segments<Block, AS, Client, SPS, IQS>(
+    segment_headers_store: &SegmentHeadersStore<AS>,
+    client: &Client,
+    segment_pieces_source: &SPS,
+    import_queue_service: &mut IQS,
+    last_processed_segment_index: &mut SegmentIndex,
+    last_processed_block_number: &mut <Block::Header as Header>::Number,
+    erasure_coding_rate: (usize, usize),
+) -> Result<u64, sc_service::Error>
+where
+    Block: BlockT,
+    AS: AuxStore + Send + Sync + 'static,
+    Client: HeaderBackend<Block> + BlockBackend<Block> + Send + Sync + 'static,
+    SPS: SegmentPiecesSource,
+    IQS: ImportQueueService<Block> + ?Sized,
+{
+    let Some(max_segment_index) = segment_headers_store.max_segment_index() else {
+        return Err(sc_service::Error::Other(
+            "Segment headers need to be known before importing blocks from segments".to_string(),
+        ));
+    };
+
     let mut downloaded_blocks = 0;
-    let mut reconstructor = Reconstructor::new().map_err(|error| error.to_string())?;
+    let mut reconstructor =
+        Reconstructor::new(erasure_coding_rate).map_err(|error| error.to_string())?;
     // Start from the first unprocessed segment and process all segments known so far
-    let segment_indices_iter = (*last_processed_segment_index + SegmentIndex::ONE)
-        ..=segment_headers_store
-            .max_segment_index()
-            .expect("Exists, we have inserted segment headers above; qed");
+    let segment_indices_iter =
+        (*last_processed_segment_index + SegmentIndex::ONE)..=max_segment_index;
     let mut segment_indices_iter = segment_indices_iter.peekable();
 
     while let Some(segment_index) = segment_indices_iter.next() {
@@ -120,7 +184,8 @@ where
             *last_processed_segment_index = segment_index;
             *last_processed_block_number = last_archived_block;
             // Reset reconstructor instance
-            reconstructor = Reconstructor::new().map_err(|error| error.to_string())?;
+            reconstructor =
+                Reconstructor::new(erasure_coding_rate).map_err(|error| error.to_string())?;
             continue;
         }
         // Just one partial unprocessed block and this was the last segment available, so nothing to
@@ -130,13 +195,21 @@ where
             && segment_indices_iter.peek().is_none()
         {
             // Reset reconstructor instance
-            reconstructor = Reconstructor::new().map_err(|error| error.to_string())?;
+            reconstructor =
+                Reconstructor::new(erasure_coding_rate).map_err(|error| error.to_string())?;
             continue;
         }
 
-        let blocks =
-            download_and_reconstruct_blocks(segment_index, piece_provider, &mut reconstructor)
-                .await?;
+        let segment_pieces = segment_pieces_source
+            .get_segment_pieces(segment_index)
+            .await?;
+        let blocks = reconstructor
+            .add_segment(segment_pieces.as_ref())
+            .map_err(|error| error.to_string())?
+            .blocks;
+        drop(segment_pieces);
+
+        trace!(%segment_index, "Segment reconstructed successfully");
 
         let mut blocks_to_import = Vec::with_capacity(QUEUED_BLOCKS_LIMIT as usize);
 
@@ -239,11 +312,10 @@ where
     Ok(downloaded_blocks)
 }
 
-async fn download_and_reconstruct_blocks<PV>(
+async fn download_segment_pieces<PV>(
     segment_index: SegmentIndex,
     piece_provider: &PieceProvider<PV>,
-    reconstructor: &mut Reconstructor,
-) -> Result<Vec<(BlockNumber, Vec<u8>)>, sc_service::Error>
+) -> Vec<Option<Piece>>
 where
     PV: PieceValidator,
 {
@@ -330,12 +402,5 @@ where
         }
     }
 
-    let reconstructed_contents = reconstructor
-        .add_segment(segment_pieces.as_ref())
-        .map_err(|error| error.to_string())?;
-    drop(segment_pieces);
-
-    trace!(%segment_index, "Segment reconstructed successfully");
-
-    Ok(reconstructed_contents.blocks)
+    segment_pieces
 }
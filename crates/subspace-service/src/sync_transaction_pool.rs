@@ -0,0 +1,79 @@
+//! Sync of the transaction pool from the peers on node start.
+
+use futures::StreamExt;
+use sc_network::{Event, NetworkEventStream, PeerId};
+use sc_subspace_block_relay::TransactionPoolRelay;
+use sp_consensus::SyncOracle;
+use sp_runtime::traits::Block as BlockT;
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Number of peers to sync the transaction pool from.
+const SYNC_PEERS: usize = 3;
+/// Frequency with which to check whether the node finished the major sync.
+const CHECK_SYNC_STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Syncs the transaction pool from the connected peers once the node finishes the major sync,
+/// so that the pending transactions are known without waiting for them to be gossiped again
+/// (e.g. after restart).
+pub(super) async fn sync_transaction_pool<Block, Network, SO>(
+    relay: TransactionPoolRelay<Block>,
+    network: &Network,
+    sync_oracle: SO,
+) where
+    Block: BlockT,
+    Network: NetworkEventStream,
+    SO: SyncOracle,
+{
+    let mut events = network.event_stream("transaction-pool-sync");
+    let mut connected_peers = Vec::<PeerId>::new();
+    let mut tried_peers = HashSet::<PeerId>::new();
+    let mut synced_peers = 0;
+
+    loop {
+        match tokio::time::timeout(CHECK_SYNC_STATUS_INTERVAL, events.next()).await {
+            Ok(Some(Event::NotificationStreamOpened { remote, .. })) => {
+                if !connected_peers.contains(&remote) {
+                    connected_peers.push(remote);
+                }
+            }
+            Ok(Some(_event)) => {
+                // Not interested
+            }
+            Ok(None) => {
+                // Network has terminated
+                return;
+            }
+            Err(_timeout) => {
+                // Check the sync status
+            }
+        }
+
+        // The transactions can't be validated before the node catches up with the chain
+        if sync_oracle.is_major_syncing() {
+            continue;
+        }
+
+        for peer in connected_peers.drain(..) {
+            if !tried_peers.insert(peer) {
+                continue;
+            }
+
+            match relay.sync_from_peer(peer).await {
+                Ok(imported) => {
+                    debug!(%peer, %imported, "Synced transaction pool from peer");
+                    synced_peers += 1;
+                }
+                Err(error) => {
+                    debug!(%peer, %error, "Failed to sync transaction pool from peer");
+                }
+            }
+
+            if synced_peers >= SYNC_PEERS {
+                info!(%synced_peers, "Transaction pool sync finished");
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Utilities shared by tests of different modules.
+
+use parking_lot::Mutex;
+use sc_client_api::AuxStore;
+use std::collections::HashMap;
+
+/// In-memory aux store
+#[derive(Default)]
+pub(crate) struct TestAuxStore(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+impl AuxStore for TestAuxStore {
+    fn insert_aux<
+        'a,
+        'b: 'a,
+        'c: 'a,
+        I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+        D: IntoIterator<Item = &'a &'b [u8]>,
+    >(
+        &self,
+        insert: I,
+        delete: D,
+    ) -> sp_blockchain::Result<()> {
+        let mut storage = self.0.lock();
+        for (key, value) in insert {
+            storage.insert(key.to_vec(), value.to_vec());
+        }
+        for key in delete {
+            storage.remove(*key);
+        }
+        Ok(())
+    }
+
+    fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+        Ok(self.0.lock().get(key).cloned())
+    }
+}
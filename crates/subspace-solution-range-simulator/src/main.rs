@@ -0,0 +1,143 @@
+//! Offline simulator of solution range adjustment.
+//!
+//! Runs `pallet-subspace` era transitions against a synthetic model of pledged space over time and
+//! outputs block time, vote rate and solution range trajectories as CSV, such that consensus
+//! parameters can be tested before they are put into a chain spec.
+//!
+//! For example, pledged space growing from 1 TiB to 100 TiB over the first week with solution
+//! range adjustment enabled at block 1000:
+//! ```bash
+//! subspace-solution-range-simulator --pledged-space 0:1024 --pledged-space 604800:102400 \
+//!     --enable-solution-range-adjustment-at 1000 --output trajectory.csv
+//! ```
+
+#![feature(const_option)]
+
+mod runtime;
+mod simulation;
+
+use crate::runtime::RuntimeParams;
+use crate::simulation::{
+    initial_solution_range, simulate, BlockRecord, PledgedSpace, PledgedSpacePoint,
+    SimulationParams,
+};
+use clap::Parser;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::num::NonZeroU64;
+use std::path::PathBuf;
+use subspace_core_primitives::SolutionRange;
+
+/// Offline simulator of solution range adjustment
+#[derive(Debug, Parser)]
+#[clap(about, version)]
+struct Cli {
+    /// Pledged space at specific slot in `<SLOT>:<GIB>` format, can be specified multiple times,
+    /// pledged space is linearly interpolated between slots
+    #[arg(long, required = true, value_name = "SLOT:GIB")]
+    pledged_space: Vec<PledgedSpacePoint>,
+    /// Number of slots to simulate
+    #[arg(long, default_value_t = 100_000)]
+    slots: u64,
+    /// Slot duration in milliseconds
+    #[arg(long, default_value = "1000")]
+    slot_duration_ms: NonZeroU64,
+    /// Slot probability in `<NUMERATOR>/<DENOMINATOR>` format
+    #[arg(long, default_value = "1/6", value_parser = parse_slot_probability)]
+    slot_probability: (u64, u64),
+    /// Era duration in blocks
+    #[arg(long, default_value_t = 2016)]
+    era_duration: u64,
+    /// Initial solution range, derived from pledged space at the first slot by default
+    #[arg(long)]
+    initial_solution_range: Option<SolutionRange>,
+    /// Number of votes expected per block
+    #[arg(long, default_value_t = 9)]
+    expected_votes_per_block: u32,
+    /// Whether solution range adjustment is enabled at genesis
+    #[arg(long)]
+    should_adjust_solution_range: bool,
+    /// Block at which solution range adjustment is enabled, like with
+    /// `enable_solution_range_adjustment` sudo call
+    #[arg(long)]
+    enable_solution_range_adjustment_at: Option<u64>,
+    /// Solution range override for `enable_solution_range_adjustment` call
+    #[arg(long, requires = "enable_solution_range_adjustment_at")]
+    solution_range_override: Option<SolutionRange>,
+    /// Voting solution range override for `enable_solution_range_adjustment` call
+    #[arg(long, requires = "solution_range_override")]
+    voting_solution_range_override: Option<SolutionRange>,
+    /// Seed for random number generator
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// File to write CSV to, printed to stdout if not specified
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn parse_slot_probability(s: &str) -> Result<(u64, u64), String> {
+    let (numerator, denominator) = s
+        .split_once('/')
+        .ok_or_else(|| format!("Expected <NUMERATOR>/<DENOMINATOR>, got \"{s}\""))?;
+    let numerator = numerator
+        .parse::<u64>()
+        .map_err(|error| format!("Invalid numerator \"{numerator}\": {error}"))?;
+    let denominator = denominator
+        .parse::<u64>()
+        .map_err(|error| format!("Invalid denominator \"{denominator}\": {error}"))?;
+    if numerator == 0 || numerator > denominator {
+        return Err(format!(
+            "Slot probability must be within (0, 1], got \"{s}\""
+        ));
+    }
+
+    Ok((numerator, denominator))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    if cli.era_duration == 0 {
+        return Err("Era duration must not be zero".into());
+    }
+    let pledged_space = PledgedSpace::new(cli.pledged_space)
+        .ok_or("Pledged space must be specified at least for one slot")?;
+    let initial_solution_range = cli.initial_solution_range.unwrap_or_else(|| {
+        initial_solution_range(pledged_space.space_gib(1), cli.slot_probability)
+    });
+
+    let params = SimulationParams {
+        runtime: RuntimeParams {
+            era_duration: cli.era_duration,
+            initial_solution_range,
+            slot_probability: cli.slot_probability,
+            expected_votes_per_block: cli.expected_votes_per_block,
+            should_adjust_solution_range: cli.should_adjust_solution_range,
+        },
+        pledged_space,
+        slots: cli.slots,
+        slot_duration_ms: cli.slot_duration_ms,
+        enable_solution_range_adjustment_at: cli.enable_solution_range_adjustment_at,
+        solution_range_override: cli.solution_range_override,
+        voting_solution_range_override: cli.voting_solution_range_override,
+        seed: cli.seed,
+    };
+
+    let mut output: Box<dyn Write> = match &cli.output {
+        Some(output) => Box::new(BufWriter::new(File::create(output)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    writeln!(output, "{}", BlockRecord::CSV_HEADER)?;
+    let mut write_result = Ok(());
+    simulate(&params, |block| {
+        if write_result.is_ok() {
+            write_result = writeln!(output, "{}", block.to_csv());
+        }
+    })?;
+    write_result?;
+    output.flush()?;
+
+    Ok(())
+}
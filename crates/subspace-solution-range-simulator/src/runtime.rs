@@ -0,0 +1,236 @@
+//! Minimal runtime with `pallet-subspace`, such that solution range adjustment is done by the
+//! exact same code as on chain.
+
+use frame_support::parameter_types;
+use frame_support::storage::{with_transaction, TransactionOutcome};
+use frame_support::traits::{ConstU16, ConstU32, ConstU64, ConstU8, OnFinalize, OnInitialize};
+use pallet_subspace::{AllowAuthoringBy, NormalEraChange, NormalGlobalRandomnessInterval};
+use sp_consensus_slots::Slot;
+#[cfg(feature = "pot")]
+use sp_consensus_subspace::digests::PreDigestPotInfo;
+use sp_consensus_subspace::digests::{CompatibleDigestItem, PreDigest};
+use sp_consensus_subspace::{FarmerPublicKey, SolutionRanges};
+use sp_core::crypto::UncheckedFrom;
+use sp_core::H256;
+use sp_io::TestExternalities;
+use sp_runtime::testing::{Digest, DigestItem, TestXt};
+use sp_runtime::traits::{Header as _, IdentityLookup};
+use sp_runtime::{BuildStorage, DispatchError, DispatchResult};
+use std::marker::PhantomData;
+use std::num::{NonZeroU32, NonZeroU64};
+use subspace_core_primitives::{
+    HistorySize, PieceOffset, SegmentIndex, SlotNumber, Solution, SolutionRange,
+};
+
+type Block = frame_system::mocking::MockBlock<Runtime>;
+
+frame_support::construct_runtime!(
+    pub struct Runtime {
+        System: frame_system,
+        Timestamp: pallet_timestamp,
+        Subspace: pallet_subspace,
+    }
+);
+
+impl frame_system::Config for Runtime {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type Nonce = u64;
+    type RuntimeCall = RuntimeCall;
+    type Hash = H256;
+    type Version = ();
+    type Hashing = sp_runtime::traits::BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Block = Block;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type PalletInfo = PalletInfo;
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+    RuntimeCall: From<C>,
+{
+    type OverarchingCall = RuntimeCall;
+    type Extrinsic = TestXt<RuntimeCall, ()>;
+}
+
+impl pallet_timestamp::Config for Runtime {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<1>;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub static EraDuration: u64 = 2016;
+    pub static InitialSolutionRange: SolutionRange = SolutionRange::MAX;
+    pub static SlotProbability: (u64, u64) = (1, 6);
+    pub static ExpectedVotesPerBlock: u32 = 9;
+    pub static ShouldAdjustSolutionRange: bool = false;
+    pub const RecentSegments: HistorySize =
+        HistorySize::new(NonZeroU64::new(5).expect("Not zero; qed"));
+    pub const RecentHistoryFraction: (HistorySize, HistorySize) = (
+        HistorySize::new(NonZeroU64::new(1).expect("Not zero; qed")),
+        HistorySize::new(NonZeroU64::new(10).expect("Not zero; qed")),
+    );
+    pub const MinSectorLifetime: HistorySize =
+        HistorySize::new(NonZeroU64::new(4).expect("Not zero; qed"));
+    pub const ErasureCodingRate: (u32, u32) = (1, 2);
+    pub const BlockAuthoringDelay: SlotNumber = 4;
+    pub const PotEntropyInjectionDelay: SlotNumber = 15;
+}
+
+impl pallet_subspace::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type GlobalRandomnessUpdateInterval = ConstU64<256>;
+    type BlockAuthoringDelay = BlockAuthoringDelay;
+    type PotEntropyInjectionInterval = ConstU64<50>;
+    type PotEntropyInjectionLookbackDepth = ConstU8<2>;
+    type PotEntropyInjectionDelay = PotEntropyInjectionDelay;
+    type EraDuration = EraDuration;
+    type InitialSolutionRange = InitialSolutionRange;
+    type SlotProbability = SlotProbability;
+    type ExpectedBlockTime = ConstU64<6000>;
+    type ConfirmationDepthK = ConstU64<100>;
+    type RecentSegments = RecentSegments;
+    type RecentHistoryFraction = RecentHistoryFraction;
+    type MinSectorLifetime = MinSectorLifetime;
+    type ErasureCodingRate = ErasureCodingRate;
+    type ExpectedVotesPerBlock = ExpectedVotesPerBlock;
+    type MaxPiecesInSector = ConstU16<1000>;
+    type ShouldAdjustSolutionRange = ShouldAdjustSolutionRange;
+    type GlobalRandomnessIntervalTrigger = NormalGlobalRandomnessInterval;
+    type EraChangeTrigger = NormalEraChange;
+    type HandleEquivocation = ();
+    type WeightInfo = ();
+}
+
+/// Consensus parameters of the runtime that impact solution range.
+#[derive(Debug, Clone)]
+pub(crate) struct RuntimeParams {
+    /// Era duration in blocks
+    pub(crate) era_duration: u64,
+    /// Solution range used during the very first era
+    pub(crate) initial_solution_range: SolutionRange,
+    /// How often (on average) slots will have a block
+    pub(crate) slot_probability: (u64, u64),
+    /// Number of votes expected per block
+    pub(crate) expected_votes_per_block: u32,
+    /// Whether solution range adjustment is enabled at genesis
+    pub(crate) should_adjust_solution_range: bool,
+}
+
+/// Creates externalities with the genesis state of the runtime, parameters are thread-local and
+/// only apply to the current thread.
+pub(crate) fn new_externalities(params: &RuntimeParams) -> TestExternalities {
+    EraDuration::set(&params.era_duration);
+    InitialSolutionRange::set(&params.initial_solution_range);
+    SlotProbability::set(&params.slot_probability);
+    ExpectedVotesPerBlock::set(&params.expected_votes_per_block);
+    ShouldAdjustSolutionRange::set(&params.should_adjust_solution_range);
+
+    let mut storage = frame_system::GenesisConfig::<Runtime>::default()
+        .build_storage()
+        .expect("Genesis config of the simulation runtime is valid; qed");
+
+    pallet_subspace::GenesisConfig::<Runtime> {
+        enable_rewards: false,
+        enable_storage_access: true,
+        allow_authoring_by: AllowAuthoringBy::Anyone,
+        pot_slot_iterations: NonZeroU32::new(100_000).expect("Not zero; qed"),
+        phantom: PhantomData,
+    }
+    .assimilate_storage(&mut storage)
+    .expect("Genesis config of the simulation runtime is valid; qed");
+
+    TestExternalities::from(storage)
+}
+
+/// Finalizes the current block and imports the next one authored at the slot, must be called
+/// within externalities.
+pub(crate) fn import_block(block_number: u64, slot: SlotNumber) {
+    let parent_number = System::block_number();
+    if parent_number > 0 {
+        Subspace::on_finalize(parent_number);
+    }
+    let parent_hash = if parent_number > 1 {
+        System::finalize().hash()
+    } else {
+        System::parent_hash()
+    };
+
+    System::reset_events();
+    System::initialize(&block_number, &parent_hash, &pre_digest(Slot::from(slot)));
+    Subspace::on_initialize(block_number);
+}
+
+/// Returns solution ranges the pallet applies to the block with the number, without importing it,
+/// must be called within externalities.
+///
+/// Solution ranges of the block don't depend on its slot (era change at the block only impacts
+/// solution ranges of the next block), so the block is imported at arbitrary slot after the
+/// parent and rolled back afterwards.
+pub(crate) fn block_solution_ranges(block_number: u64, parent_slot: SlotNumber) -> SolutionRanges {
+    with_transaction(|| {
+        import_block(block_number, parent_slot + 1);
+        TransactionOutcome::Rollback(Ok::<_, DispatchError>(solution_ranges()))
+    })
+    .expect("Only returns `Ok`; qed")
+}
+
+/// Enables solution range adjustment the same way as sudo call would, must be called within
+/// externalities.
+pub(crate) fn enable_solution_range_adjustment(
+    solution_range_override: Option<SolutionRange>,
+    voting_solution_range_override: Option<SolutionRange>,
+) -> DispatchResult {
+    Subspace::enable_solution_range_adjustment(
+        RuntimeOrigin::root(),
+        solution_range_override,
+        voting_solution_range_override,
+    )
+}
+
+/// Returns current solution ranges, must be called within externalities.
+pub(crate) fn solution_ranges() -> SolutionRanges {
+    Subspace::solution_ranges()
+}
+
+/// Pre-digest of a block, only slot matters for solution range adjustment.
+fn pre_digest(slot: Slot) -> Digest {
+    let solution = Solution {
+        public_key: FarmerPublicKey::unchecked_from([0; 32]),
+        reward_address: 0,
+        sector_index: 0,
+        history_size: HistorySize::from(SegmentIndex::ZERO),
+        piece_offset: PieceOffset::default(),
+        record_commitment: Default::default(),
+        record_witness: Default::default(),
+        chunk: Default::default(),
+        chunk_witness: Default::default(),
+        audit_chunk_offset: 0,
+        proof_of_space: Default::default(),
+    };
+    let log = DigestItem::subspace_pre_digest(&PreDigest::V0 {
+        slot,
+        solution,
+        #[cfg(feature = "pot")]
+        pot_info: PreDigestPotInfo::V0 {
+            proof_of_time: Default::default(),
+            future_proof_of_time: Default::default(),
+        },
+    });
+    Digest { logs: vec![log] }
+}
@@ -0,0 +1,381 @@
+//! Slot-by-slot simulation of block production and voting for a synthetic model of pledged space.
+
+use crate::runtime::{self, RuntimeParams};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::mem;
+use std::num::NonZeroU64;
+use std::str::FromStr;
+use subspace_core_primitives::crypto::Scalar;
+use subspace_core_primitives::{Piece, Record, SlotNumber, SolutionRange};
+
+/// Number of bytes in one GiB.
+const GIB: f64 = (1024 * 1024 * 1024) as f64;
+/// Poisson distribution is sampled in chunks of this mean to avoid underflow of `exp(-mean)`.
+const MAX_POISSON_CHUNK: f64 = 32.0;
+
+/// Errors happening during simulation.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum SimulationError {
+    /// Failed to enable solution range adjustment
+    #[error("Failed to enable solution range adjustment at block {block_number}: {error}")]
+    EnableSolutionRangeAdjustment {
+        /// Block number
+        block_number: u64,
+        /// Error message
+        error: String,
+    },
+}
+
+/// Point of the pledged space model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PledgedSpacePoint {
+    /// Slot at which space is pledged
+    pub(crate) slot: SlotNumber,
+    /// Pledged space in GiB
+    pub(crate) space_gib: f64,
+}
+
+impl FromStr for PledgedSpacePoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (slot, space_gib) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Expected <SLOT>:<GIB>, got \"{s}\""))?;
+        let slot = slot
+            .parse()
+            .map_err(|error| format!("Invalid slot \"{slot}\": {error}"))?;
+        let space_gib = space_gib
+            .parse::<f64>()
+            .map_err(|error| format!("Invalid space \"{space_gib}\": {error}"))?;
+        if !space_gib.is_finite() || space_gib < 0.0 {
+            return Err(format!("Space must be non-negative, got \"{space_gib}\""));
+        }
+
+        Ok(Self { slot, space_gib })
+    }
+}
+
+/// Synthetic model of pledged space over time, linearly interpolated between points and constant
+/// before the first and after the last point.
+#[derive(Debug, Clone)]
+pub(crate) struct PledgedSpace {
+    points: Vec<PledgedSpacePoint>,
+}
+
+impl PledgedSpace {
+    /// Create new instance from points in any order, returns `None` if there are no points.
+    pub(crate) fn new(mut points: Vec<PledgedSpacePoint>) -> Option<Self> {
+        if points.is_empty() {
+            return None;
+        }
+        points.sort_by_key(|point| point.slot);
+
+        Some(Self { points })
+    }
+
+    /// Pledged space at the slot in GiB.
+    pub(crate) fn space_gib(&self, slot: SlotNumber) -> f64 {
+        let next_index = self.points.partition_point(|point| point.slot <= slot);
+        let Some(next) = self.points.get(next_index) else {
+            return self.points[next_index - 1].space_gib;
+        };
+        let Some(previous) = next_index.checked_sub(1).map(|index| self.points[index]) else {
+            return next.space_gib;
+        };
+
+        let progress = (slot - previous.slot) as f64 / (next.slot - previous.slot) as f64;
+        previous.space_gib + (next.space_gib - previous.space_gib) * progress
+    }
+}
+
+/// Expected number of solutions per slot for the pledged space and solution range.
+///
+/// This is the inverse of the way initial solution range is derived in the runtime: every chunk
+/// of every plotted piece is audited and each audit chunk is a potential solution.
+pub(crate) fn expected_solutions(space_gib: f64, solution_range: SolutionRange) -> f64 {
+    let pieces = space_gib * GIB / Piece::SIZE as f64;
+    pieces * Record::NUM_S_BUCKETS as f64 / Record::NUM_CHUNKS as f64
+        * (mem::size_of::<SolutionRange>() as f64 / Scalar::FULL_BYTES as f64)
+        * (solution_range as f64 / SolutionRange::MAX as f64)
+}
+
+/// Solution range for the pledged space, such that expected number of solutions per slot matches
+/// slot probability (the runtime does the same for its initial pledged space of one sector).
+pub(crate) fn initial_solution_range(
+    space_gib: f64,
+    slot_probability: (u64, u64),
+) -> SolutionRange {
+    let slot_probability = slot_probability.0 as f64 / slot_probability.1 as f64;
+
+    (slot_probability / expected_solutions(space_gib, 1)) as SolutionRange
+}
+
+/// Simulation parameters.
+#[derive(Debug, Clone)]
+pub(crate) struct SimulationParams {
+    /// Runtime parameters
+    pub(crate) runtime: RuntimeParams,
+    /// Pledged space model
+    pub(crate) pledged_space: PledgedSpace,
+    /// Number of slots to simulate
+    pub(crate) slots: u64,
+    /// Slot duration in milliseconds
+    pub(crate) slot_duration_ms: NonZeroU64,
+    /// Block at which solution range adjustment is enabled (like with sudo call)
+    pub(crate) enable_solution_range_adjustment_at: Option<u64>,
+    /// Solution range override when solution range adjustment is enabled
+    pub(crate) solution_range_override: Option<SolutionRange>,
+    /// Voting solution range override when solution range adjustment is enabled
+    pub(crate) voting_solution_range_override: Option<SolutionRange>,
+    /// Seed for random number generator
+    pub(crate) seed: u64,
+}
+
+/// State of the chain after the block was imported.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BlockRecord {
+    /// Block number
+    pub(crate) block_number: u64,
+    /// Slot of the block
+    pub(crate) slot: SlotNumber,
+    /// Time since the previous block in milliseconds
+    pub(crate) block_time_ms: u64,
+    /// Pledged space in GiB at the slot of the block
+    pub(crate) space_gib: f64,
+    /// Number of votes since the previous block
+    pub(crate) votes: u64,
+    /// Solution range used for the block
+    pub(crate) solution_range: SolutionRange,
+    /// Voting solution range used for the block
+    pub(crate) voting_solution_range: SolutionRange,
+}
+
+impl BlockRecord {
+    /// CSV header matching [`BlockRecord::to_csv`].
+    pub(crate) const CSV_HEADER: &'static str =
+        "block_number,slot,block_time_ms,pledged_space_gib,votes,solution_range,voting_solution_range";
+
+    /// Record as CSV line.
+    pub(crate) fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.block_number,
+            self.slot,
+            self.block_time_ms,
+            self.space_gib,
+            self.votes,
+            self.solution_range,
+            self.voting_solution_range,
+        )
+    }
+}
+
+/// Runs the simulation, calling `on_block` for every imported block.
+pub(crate) fn simulate<F>(params: &SimulationParams, mut on_block: F) -> Result<(), SimulationError>
+where
+    F: FnMut(BlockRecord),
+{
+    let mut rng = StdRng::seed_from_u64(params.seed);
+
+    runtime::new_externalities(&params.runtime).execute_with(|| {
+        let mut block_number = 0;
+        let mut parent_slot = 0;
+        let mut votes = 0;
+        let mut solution_ranges = runtime::block_solution_ranges(block_number + 1, parent_slot);
+
+        for slot in 1..=params.slots {
+            let space_gib = params.pledged_space.space_gib(slot);
+            let block_solutions = expected_solutions(space_gib, solution_ranges.current);
+            let voting_solutions = expected_solutions(space_gib, solution_ranges.voting_current);
+
+            // Solutions within voting solution range, but outside of block solution range are
+            // votes
+            votes += sample_poisson(&mut rng, (voting_solutions - block_solutions).max(0.0));
+            if !rng.gen_bool(1.0 - (-block_solutions).exp()) {
+                continue;
+            }
+
+            block_number += 1;
+            runtime::import_block(block_number, slot);
+            // Record what the pallet actually applied to the block
+            let block_solution_ranges = runtime::solution_ranges();
+            debug_assert_eq!(block_solution_ranges.current, solution_ranges.current);
+            if params.enable_solution_range_adjustment_at == Some(block_number) {
+                runtime::enable_solution_range_adjustment(
+                    params.solution_range_override,
+                    params.voting_solution_range_override,
+                )
+                .map_err(|error| {
+                    SimulationError::EnableSolutionRangeAdjustment {
+                        block_number,
+                        error: format!("{error:?}"),
+                    }
+                })?;
+            }
+
+            on_block(BlockRecord {
+                block_number,
+                slot,
+                block_time_ms: (slot - parent_slot) * params.slot_duration_ms.get(),
+                space_gib,
+                votes: mem::take(&mut votes),
+                solution_range: block_solution_ranges.current,
+                voting_solution_range: block_solution_ranges.voting_current,
+            });
+
+            parent_slot = slot;
+            solution_ranges = runtime::block_solution_ranges(block_number + 1, parent_slot);
+        }
+
+        Ok(())
+    })
+}
+
+/// Samples Poisson distribution with the mean.
+fn sample_poisson<R: Rng>(rng: &mut R, mut mean: f64) -> u64 {
+    let mut value = 0;
+    while mean > 0.0 {
+        let chunk = mean.min(MAX_POISSON_CHUNK);
+        mean -= chunk;
+
+        // Knuth's algorithm
+        let limit = (-chunk).exp();
+        let mut product = rng.gen::<f64>();
+        while product > limit {
+            value += 1;
+            product *= rng.gen::<f64>();
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        initial_solution_range, simulate, BlockRecord, PledgedSpace, PledgedSpacePoint,
+        SimulationParams,
+    };
+    use crate::runtime::RuntimeParams;
+    use std::num::NonZeroU64;
+
+    const SLOT_PROBABILITY: (u64, u64) = (1, 6);
+    const ERA_DURATION: u64 = 100;
+    const EXPECTED_BLOCK_TIME_MS: f64 = 6_000.0;
+
+    fn point(slot: u64, space_gib: f64) -> PledgedSpacePoint {
+        PledgedSpacePoint { slot, space_gib }
+    }
+
+    fn params(pledged_space: Vec<PledgedSpacePoint>) -> SimulationParams {
+        SimulationParams {
+            runtime: RuntimeParams {
+                era_duration: ERA_DURATION,
+                initial_solution_range: initial_solution_range(1.0, SLOT_PROBABILITY),
+                slot_probability: SLOT_PROBABILITY,
+                expected_votes_per_block: 9,
+                should_adjust_solution_range: true,
+            },
+            pledged_space: PledgedSpace::new(pledged_space).unwrap(),
+            slots: 40_000,
+            slot_duration_ms: NonZeroU64::new(1000).unwrap(),
+            enable_solution_range_adjustment_at: None,
+            solution_range_override: None,
+            voting_solution_range_override: None,
+            seed: 0,
+        }
+    }
+
+    fn blocks(params: &SimulationParams) -> Vec<BlockRecord> {
+        let mut blocks = Vec::new();
+        simulate(params, |block| blocks.push(block)).unwrap();
+        blocks
+    }
+
+    /// Average block time and votes per block over the last blocks.
+    fn last_blocks_stats(blocks: &[BlockRecord], count: usize) -> (f64, f64) {
+        let last_blocks = &blocks[blocks.len() - count..];
+        let block_time_ms = last_blocks
+            .iter()
+            .map(|block| block.block_time_ms)
+            .sum::<u64>();
+        let votes = last_blocks.iter().map(|block| block.votes).sum::<u64>();
+        (
+            block_time_ms as f64 / count as f64,
+            votes as f64 / count as f64,
+        )
+    }
+
+    #[test]
+    fn pledged_space_model() {
+        assert_eq!("10:1.5".parse::<PledgedSpacePoint>(), Ok(point(10, 1.5)));
+        assert!("10".parse::<PledgedSpacePoint>().is_err());
+        assert!("10:-1".parse::<PledgedSpacePoint>().is_err());
+        assert!(PledgedSpace::new(Vec::new()).is_none());
+
+        let pledged_space =
+            PledgedSpace::new(vec![point(300, 10.0), point(100, 2.0), point(200, 4.0)]).unwrap();
+        assert_eq!(pledged_space.space_gib(0), 2.0);
+        assert_eq!(pledged_space.space_gib(100), 2.0);
+        assert_eq!(pledged_space.space_gib(150), 3.0);
+        assert_eq!(pledged_space.space_gib(250), 7.0);
+        assert_eq!(pledged_space.space_gib(300), 10.0);
+        assert_eq!(pledged_space.space_gib(1_000), 10.0);
+    }
+
+    #[test]
+    fn solution_range_follows_pledged_space() {
+        // Space grows 100x early on, block production speeds up until solution range is adjusted
+        let params = params(vec![point(0, 1.0), point(1_000, 100.0)]);
+        let blocks = blocks(&params);
+
+        let (block_time_ms, votes) = last_blocks_stats(&blocks, 3_000);
+        assert!(
+            (block_time_ms / EXPECTED_BLOCK_TIME_MS - 1.0).abs() < 0.1,
+            "Block time {block_time_ms}ms"
+        );
+        // Votes are a bit above expected value due to collisions of block solutions within a slot
+        assert!((8.5..11.0).contains(&votes), "Votes per block {votes}");
+
+        let last_block = blocks.last().unwrap();
+        let solution_range_ratio =
+            params.runtime.initial_solution_range as f64 / last_block.solution_range as f64;
+        assert!(
+            (80.0..120.0).contains(&solution_range_ratio),
+            "Solution range decreased {solution_range_ratio}x"
+        );
+        assert_eq!(
+            last_block.voting_solution_range,
+            last_block.solution_range * 10
+        );
+
+        // Same seed results in the same trajectory
+        assert_eq!(self::blocks(&params), blocks);
+    }
+
+    #[test]
+    fn solution_range_adjustment_disabled() {
+        let mut params = params(vec![point(0, 1.0), point(1_000, 100.0)]);
+        params.runtime.should_adjust_solution_range = false;
+        params.slots = 12_000;
+        let blocks = blocks(&params);
+
+        // Solution range doesn't change, so blocks are produced in almost every slot
+        assert!(blocks
+            .iter()
+            .all(|block| block.solution_range == params.runtime.initial_solution_range));
+        let (block_time_ms, votes) = last_blocks_stats(&blocks, 1_000);
+        assert!(block_time_ms < 1_100.0, "Block time {block_time_ms}ms");
+        assert_eq!(votes, 0.0);
+
+        // Enabling adjustment later brings block time back to expected value
+        params.enable_solution_range_adjustment_at = Some(3_000);
+        let blocks = self::blocks(&params);
+        let (block_time_ms, _votes) = last_blocks_stats(&blocks, 500);
+        assert!(
+            (block_time_ms / EXPECTED_BLOCK_TIME_MS - 1.0).abs() < 0.2,
+            "Block time {block_time_ms}ms"
+        );
+    }
+}
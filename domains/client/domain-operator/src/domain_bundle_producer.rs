@@ -260,10 +260,10 @@ where
                 extrinsics,
             };
 
-            // TODO: Re-enable the bundle gossip over X-Net when the compact bundle is supported.
-            // if let Err(e) = self.bundle_sender.unbounded_send(signed_bundle.clone()) {
-            // tracing::error!(error = ?e, "Failed to send transaction bundle");
-            // }
+            // The bundle is gossiped over the domain subnet in the compact form.
+            if let Err(e) = self.bundle_sender.unbounded_send(bundle.clone()) {
+                tracing::error!(error = ?e, "Failed to send transaction bundle");
+            }
 
             Ok(Some(bundle.into_opaque_bundle()))
         } else {
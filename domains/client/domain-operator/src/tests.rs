@@ -2140,3 +2140,70 @@ async fn test_multiple_consensus_blocks_derive_similar_domain_block() {
     // Simply produce more block
     produce_blocks!(ferdie, alice, 3).await.unwrap();
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_transaction_pool_sync_from_peer() {
+    let directory = TempDir::new().expect("Must be able to create temporary directory");
+
+    let mut builder = sc_cli::LoggerBuilder::new("");
+    builder.with_colors(false);
+    let _ = builder.init();
+
+    let tokio_handle = tokio::runtime::Handle::current();
+
+    // Start Ferdie
+    let mut ferdie = MockConsensusNode::run(
+        tokio_handle.clone(),
+        Ferdie,
+        BasePath::new(directory.path().join("ferdie")),
+    );
+    // Produce 1 consensus block to initialize genesis domain
+    ferdie.produce_block_with_slot(1.into()).await.unwrap();
+
+    // Run Alice (a evm domain authority node)
+    let mut alice = domain_test_service::DomainNodeBuilder::new(
+        tokio_handle.clone(),
+        Alice,
+        BasePath::new(directory.path().join("alice")),
+    )
+    .build_evm_node(Role::Authority, GENESIS_DOMAIN_ID, &mut ferdie)
+    .await;
+
+    // Bundle produced by Alice is in the transaction pool of Ferdie
+    alice.send_system_remark().await;
+    let (slot, bundle) = ferdie.produce_slot_and_wait_for_bundle_submission().await;
+    assert!(bundle.is_some());
+
+    // Start Charlie (a consensus node) and connect it to Ferdie
+    let mut charlie = MockConsensusNode::run(
+        tokio_handle,
+        Sr25519Keyring::Charlie,
+        BasePath::new(directory.path().join("charlie")),
+    );
+    ferdie.start_network();
+    charlie.start_network();
+    charlie.connect_to_consensus_node(ferdie.addr.clone());
+
+    // The bundle can only be validated once Charlie catches up with Ferdie
+    while charlie.client.info().best_hash != ferdie.client.info().best_hash {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    // The bundle goes through the same validation as the gossiped transactions, including the
+    // pre-validation
+    let imported = charlie
+        .transaction_pool_relay
+        .sync_from_peer(ferdie.addr.peer_id)
+        .await
+        .unwrap();
+    assert_eq!(imported, 1);
+    assert_eq!(charlie.get_bundle_from_tx_pool(slot.into()), bundle);
+
+    // Nothing to import when syncing again
+    let imported = charlie
+        .transaction_pool_relay
+        .sync_from_peer(ferdie.addr.peer_id)
+        .await
+        .unwrap();
+    assert_eq!(imported, 0);
+}
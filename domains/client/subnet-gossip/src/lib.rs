@@ -1,7 +1,10 @@
-//! This crate is intended to provide the feature of gossiping bundles over the domain subnet.
-//! However, it's unused at present as it's not yet fully implemented.
+//! This crate provides the feature of gossiping bundles over the domain subnet.
 //!
-//! We may enable this feature in the future:
+//! The bundles are gossiped in the compact form (bundle header + extrinsic hashes), the receivers
+//! resolve the extrinsics from their transaction pool and fetch the missing ones from the sender
+//! using the execution relay of `sc-subspace-block-relay`.
+//!
+//! To enable this feature:
 //! 1. Implement the [`GossipMessageHandler`] somewhere.
 //! 2. Run the gossip worker using `start_gossip_worker` when building the service.
 
@@ -17,9 +20,10 @@ use sc_network_gossip::{
     GossipEngine, MessageIntent, Network as GossipNetwork, Syncing as GossipSyncing,
     ValidationResult, Validator, ValidatorContext,
 };
+use sc_subspace_block_relay::{CompactBundle, ExecutionRelay};
 use sc_utils::mpsc::TracingUnboundedReceiver;
 use sp_core::hashing::twox_64;
-use sp_domains::Bundle;
+use sp_domains::{Bundle, SealedBundleHeader};
 use sp_runtime::traits::{Block as BlockT, Hash as HashT, Header as HeaderT, NumberFor};
 use std::collections::HashSet;
 use std::fmt::Debug;
@@ -32,7 +36,7 @@ const LOG_TARGET: &str = "gossip::operator";
 
 const DOMAIN_SUBNET_PROTOCOL_NAME: &str = "/subspace/operator/1";
 
-type BundleFor<Block, CBlock> = Bundle<
+pub type BundleFor<Block, CBlock> = Bundle<
     <Block as BlockT>::Extrinsic,
     NumberFor<CBlock>,
     <CBlock as BlockT>::Hash,
@@ -41,6 +45,16 @@ type BundleFor<Block, CBlock> = Bundle<
     Balance,
 >;
 
+pub type SealedBundleHeaderFor<Block, CBlock> = SealedBundleHeader<
+    NumberFor<CBlock>,
+    <CBlock as BlockT>::Hash,
+    NumberFor<Block>,
+    <Block as BlockT>::Hash,
+    Balance,
+>;
+
+type CompactBundleFor<Block, CBlock> = CompactBundle<Block, SealedBundleHeaderFor<Block, CBlock>>;
+
 // TODO: proper timeout
 /// Timeout for rebroadcasting messages.
 /// The default value used in network-gossip is 1100ms.
@@ -66,15 +80,15 @@ fn topic<Block: BlockT>() -> Block::Hash {
 /// This is the root type that gets encoded and sent on the network.
 #[derive(Debug, Encode, Decode)]
 pub enum GossipMessage<CBlock: BlockT, Block: BlockT> {
-    Bundle(BundleFor<Block, CBlock>),
+    CompactBundle(CompactBundleFor<Block, CBlock>),
 }
 
-impl<CBlock: BlockT, Block: BlockT> From<BundleFor<Block, CBlock>>
+impl<CBlock: BlockT, Block: BlockT> From<CompactBundleFor<Block, CBlock>>
     for GossipMessage<CBlock, Block>
 {
     #[inline]
-    fn from(bundle: BundleFor<Block, CBlock>) -> Self {
-        Self::Bundle(bundle)
+    fn from(compact_bundle: CompactBundleFor<Block, CBlock>) -> Self {
+        Self::CompactBundle(compact_bundle)
     }
 }
 
@@ -104,7 +118,17 @@ where
     /// Error type.
     type Error: Debug;
 
+    /// Validates the header of the received compact bundle, called before the bundle extrinsics
+    /// are resolved so that the bundles that can't be valid are dropped without fetching anything
+    /// from the sender.
+    fn on_bundle_header(
+        &self,
+        sealed_header: &SealedBundleHeaderFor<Block, CBlock>,
+    ) -> Result<(), Self::Error>;
+
     /// Validates and applies when a transaction bundle was received.
+    ///
+    /// The bundle header has already been validated with [`Self::on_bundle_header`].
     fn on_bundle(&self, bundle: &BundleFor<Block, CBlock>) -> Result<Action, Self::Error>;
 }
 
@@ -145,25 +169,38 @@ where
 
     fn validate_message(&self, msg: GossipMessage<CBlock, Block>) -> ValidationResult<Block::Hash> {
         match msg {
-            GossipMessage::Bundle(bundle) => {
-                let outcome = self.executor.on_bundle(&bundle);
-                match outcome {
-                    Ok(action) if action.rebroadcast_bundle() => {
-                        ValidationResult::ProcessAndKeep(self.topic)
-                    }
+            // The rest of the compact bundle can only be validated once the extrinsics are
+            // resolved, this is done by the worker which also rebroadcasts the valid bundles.
+            GossipMessage::CompactBundle(compact_bundle) => {
+                match self.executor.on_bundle_header(compact_bundle.header()) {
+                    Ok(()) => ValidationResult::ProcessAndDiscard(self.topic),
                     Err(err) => {
                         tracing::debug!(
                             target: LOG_TARGET,
                             ?err,
-                            "Invalid GossipMessage::Bundle discarded"
+                            "Invalid GossipMessage::CompactBundle header discarded"
                         );
                         ValidationResult::Discard
                     }
-                    _ => ValidationResult::ProcessAndDiscard(self.topic),
                 }
             }
         }
     }
+
+    /// Validates the resolved bundle, returns `true` if it should be rebroadcasted.
+    pub(crate) fn validate_bundle(&self, bundle: &BundleFor<Block, CBlock>) -> bool {
+        match self.executor.on_bundle(bundle) {
+            Ok(action) => action.rebroadcast_bundle(),
+            Err(err) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?err,
+                    "Invalid GossipMessage::CompactBundle discarded"
+                );
+                false
+            }
+        }
+    }
 }
 
 impl<CBlock, Block, Operator> Validator<Block> for GossipValidator<CBlock, Block, Operator>
@@ -268,6 +305,8 @@ pub struct ExecutorGossipParams<CBlock: BlockT, Block: BlockT, Network, GossipSy
     pub operator: Operator,
     /// Stream of transaction bundle produced locally.
     pub bundle_receiver: BundleReceiver<Block, CBlock>,
+    /// Relay used to compact the bundles and to resolve the received compact bundles.
+    pub execution_relay: ExecutionRelay<Block, SealedBundleHeaderFor<Block, CBlock>>,
 }
 
 /// Starts the executor gossip worker.
@@ -285,6 +324,7 @@ pub async fn start_gossip_worker<CBlock, Block, Network, GossipSync, Operator>(
         sync,
         operator,
         bundle_receiver,
+        execution_relay,
     } = gossip_params;
 
     let gossip_validator = Arc::new(GossipValidator::new(operator));
@@ -300,6 +340,7 @@ pub async fn start_gossip_worker<CBlock, Block, Network, GossipSync, Operator>(
         gossip_validator,
         Arc::new(Mutex::new(gossip_engine)),
         bundle_receiver,
+        execution_relay,
     );
 
     gossip_worker.run().await
@@ -1,14 +1,26 @@
 use crate::{
-    topic, BundleFor, BundleReceiver, GossipMessage, GossipMessageHandler, GossipValidator,
-    LOG_TARGET,
+    topic, BundleFor, BundleReceiver, CompactBundleFor, GossipMessage, GossipMessageHandler,
+    GossipValidator, SealedBundleHeaderFor, LOG_TARGET,
 };
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
 use futures::{future, FutureExt, StreamExt};
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
+use sc_network::PeerId;
 use sc_network_gossip::GossipEngine;
+use sc_subspace_block_relay::ExecutionRelay;
+use sp_domains::Bundle;
 use sp_runtime::traits::Block as BlockT;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Maximum number of compact bundles being resolved at the same time.
+const MAX_PENDING_BUNDLES: usize = 256;
+/// Maximum number of compact bundles from the same peer being resolved at the same time, a single
+/// peer can't occupy the whole [`MAX_PENDING_BUNDLES`] this way.
+const MAX_PENDING_BUNDLES_PER_PEER: usize = 16;
+
 /// A worker plays the executor gossip protocol.
 pub struct GossipWorker<CBlock, Block, Executor>
 where
@@ -19,6 +31,7 @@ where
     gossip_validator: Arc<GossipValidator<CBlock, Block, Executor>>,
     gossip_engine: Arc<Mutex<GossipEngine<Block>>>,
     bundle_receiver: BundleReceiver<Block, CBlock>,
+    execution_relay: ExecutionRelay<Block, SealedBundleHeaderFor<Block, CBlock>>,
 }
 
 impl<CBlock, Block, Executor> GossipWorker<CBlock, Block, Executor>
@@ -31,16 +44,25 @@ where
         gossip_validator: Arc<GossipValidator<CBlock, Block, Executor>>,
         gossip_engine: Arc<Mutex<GossipEngine<Block>>>,
         bundle_receiver: BundleReceiver<Block, CBlock>,
+        execution_relay: ExecutionRelay<Block, SealedBundleHeaderFor<Block, CBlock>>,
     ) -> Self {
         Self {
             gossip_validator,
             gossip_engine,
             bundle_receiver,
+            execution_relay,
         }
     }
 
     fn gossip_bundle(&self, bundle: BundleFor<Block, CBlock>) {
-        let outgoing_message: GossipMessage<CBlock, Block> = bundle.into();
+        let Bundle {
+            sealed_header,
+            extrinsics,
+        } = bundle;
+        let outgoing_message: GossipMessage<CBlock, Block> = self
+            .execution_relay
+            .compact_bundle(sealed_header, extrinsics)
+            .into();
         let encoded_message = outgoing_message.encode();
         self.gossip_validator.note_rebroadcasted(&encoded_message);
         self.gossip_engine
@@ -48,15 +70,49 @@ where
             .gossip_message(topic::<Block>(), encoded_message, false);
     }
 
+    /// Resolves the extrinsics of the compact bundle received from the peer.
+    fn resolve_bundle(
+        &self,
+        sender: PeerId,
+        compact_bundle: CompactBundleFor<Block, CBlock>,
+    ) -> BoxFuture<'static, (PeerId, Option<BundleFor<Block, CBlock>>)> {
+        let execution_relay = self.execution_relay.clone();
+        async move {
+            let bundle = match execution_relay.resolve_bundle(sender, compact_bundle).await {
+                Ok((sealed_header, extrinsics)) => Some(Bundle {
+                    sealed_header,
+                    extrinsics,
+                }),
+                Err(err) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?sender,
+                        ?err,
+                        "Failed to resolve the compact bundle"
+                    );
+                    None
+                }
+            };
+
+            (sender, bundle)
+        }
+        .boxed()
+    }
+
     pub(super) async fn run(mut self) {
         let mut incoming = Box::pin(
             self.gossip_engine
                 .lock()
                 .messages_for(topic::<Block>())
                 .filter_map(|notification| async move {
-                    GossipMessage::<CBlock, Block>::decode(&mut &notification.message[..]).ok()
+                    let sender = notification.sender?;
+                    GossipMessage::<CBlock, Block>::decode(&mut &notification.message[..])
+                        .ok()
+                        .map(|message| (sender, message))
                 }),
         );
+        let mut pending_bundles = FuturesUnordered::new();
+        let mut pending_bundles_per_peer = HashMap::<PeerId, usize>::new();
 
         loop {
             let engine = self.gossip_engine.clone();
@@ -64,15 +120,49 @@ where
 
             futures::select! {
                 gossip_message = incoming.next().fuse() => {
-                    if let Some(message) = gossip_message {
-                        tracing::debug!(target: LOG_TARGET, ?message, "Rebroadcasting an executor gossip message");
+                    if let Some((sender, message)) = gossip_message {
+                        tracing::debug!(target: LOG_TARGET, ?message, "Resolving an executor gossip message");
                         match message {
-                            GossipMessage::Bundle(bundle) => self.gossip_bundle(bundle),
+                            GossipMessage::CompactBundle(compact_bundle) => {
+                                let peer_pending_bundles =
+                                    pending_bundles_per_peer.entry(sender).or_default();
+                                if pending_bundles.len() >= MAX_PENDING_BUNDLES
+                                    || *peer_pending_bundles >= MAX_PENDING_BUNDLES_PER_PEER
+                                {
+                                    tracing::debug!(
+                                        target: LOG_TARGET,
+                                        ?sender,
+                                        "Too many pending bundles, compact bundle dropped"
+                                    );
+                                    if *peer_pending_bundles == 0 {
+                                        pending_bundles_per_peer.remove(&sender);
+                                    }
+                                    continue;
+                                }
+
+                                *peer_pending_bundles += 1;
+                                pending_bundles.push(self.resolve_bundle(sender, compact_bundle));
+                            }
                         }
                     } else {
                         return
                     }
                 }
+                (sender, resolved_bundle) = pending_bundles.select_next_some() => {
+                    if let Some(peer_pending_bundles) = pending_bundles_per_peer.get_mut(&sender) {
+                        *peer_pending_bundles -= 1;
+                        if *peer_pending_bundles == 0 {
+                            pending_bundles_per_peer.remove(&sender);
+                        }
+                    }
+
+                    if let Some(bundle) = resolved_bundle {
+                        if self.gossip_validator.validate_bundle(&bundle) {
+                            tracing::debug!(target: LOG_TARGET, bundle_hash = ?bundle.hash(), "Rebroadcasting a bundle");
+                            self.gossip_bundle(bundle);
+                        }
+                    }
+                }
                 bundle = self.bundle_receiver.next().fuse() => {
                     if let Some(bundle) = bundle {
                         self.gossip_bundle(bundle);
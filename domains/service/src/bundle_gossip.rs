@@ -0,0 +1,95 @@
+//! Validation of the bundles gossiped over the domain subnet.
+
+use domain_client_subnet_gossip::{Action, BundleFor, GossipMessageHandler, SealedBundleHeaderFor};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StateVersion;
+use sp_core::Encode;
+use sp_domains::{BundleProducerElectionApi, DomainId, OperatorId};
+use sp_runtime::traits::{BlakeTwo256, Block as BlockT, Hash as HashT};
+use sp_runtime::RuntimeAppPublic;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use subspace_runtime_primitives::Balance;
+
+/// Error of the gossiped bundle validation.
+#[derive(Debug)]
+pub(crate) enum BundleGossipError {
+    /// Bundle of another domain.
+    UnexpectedDomain(DomainId),
+    /// Bundle extrinsics don't match the extrinsics root in the header.
+    InvalidExtrinsicsRoot,
+    /// Bundle producer is not a known operator.
+    UnknownOperator(OperatorId),
+    /// Invalid bundle signature.
+    BadSignature,
+    /// Error calling the consensus runtime api.
+    RuntimeApi(sp_api::ApiError),
+}
+
+/// Validates the bundles received over the domain subnet before they are rebroadcasted.
+pub(crate) struct BundleGossipHandler<CBlock, CClient> {
+    domain_id: DomainId,
+    consensus_client: Arc<CClient>,
+    _phantom_data: PhantomData<CBlock>,
+}
+
+impl<CBlock, CClient> BundleGossipHandler<CBlock, CClient> {
+    pub(crate) fn new(domain_id: DomainId, consensus_client: Arc<CClient>) -> Self {
+        Self {
+            domain_id,
+            consensus_client,
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<Block, CBlock, CClient> GossipMessageHandler<CBlock, Block>
+    for BundleGossipHandler<CBlock, CClient>
+where
+    Block: BlockT,
+    CBlock: BlockT,
+    CClient: HeaderBackend<CBlock> + ProvideRuntimeApi<CBlock>,
+    CClient::Api: BundleProducerElectionApi<CBlock, Balance>,
+{
+    type Error = BundleGossipError;
+
+    fn on_bundle_header(
+        &self,
+        sealed_header: &SealedBundleHeaderFor<Block, CBlock>,
+    ) -> Result<(), Self::Error> {
+        let proof_of_election = &sealed_header.header.proof_of_election;
+        if proof_of_election.domain_id != self.domain_id {
+            return Err(BundleGossipError::UnexpectedDomain(
+                proof_of_election.domain_id,
+            ));
+        }
+
+        let operator_id = proof_of_election.operator_id;
+        let (operator_signing_key, _stake) = self
+            .consensus_client
+            .runtime_api()
+            .operator(self.consensus_client.info().best_hash, operator_id)
+            .map_err(BundleGossipError::RuntimeApi)?
+            .ok_or(BundleGossipError::UnknownOperator(operator_id))?;
+        if !operator_signing_key.verify(&sealed_header.pre_hash(), &sealed_header.signature) {
+            return Err(BundleGossipError::BadSignature);
+        }
+
+        Ok(())
+    }
+
+    fn on_bundle(&self, bundle: &BundleFor<Block, CBlock>) -> Result<Action, Self::Error> {
+        // The extrinsics are resolved from the compact bundle, make sure they are the ones
+        // committed to by the bundle producer.
+        let extrinsics_root = BlakeTwo256::ordered_trie_root(
+            bundle.extrinsics.iter().map(|xt| xt.encode()).collect(),
+            StateVersion::V1,
+        );
+        if extrinsics_root != bundle.extrinsics_root() {
+            return Err(BundleGossipError::InvalidExtrinsicsRoot);
+        }
+
+        Ok(Action::RebroadcastBundle)
+    }
+}
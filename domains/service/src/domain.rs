@@ -1,3 +1,4 @@
+use crate::bundle_gossip::BundleGossipHandler;
 use crate::providers::{BlockImportProvider, RpcProvider};
 use crate::{FullBackend, FullClient};
 use domain_client_block_preprocessor::inherents::CreateInherentDataProvider;
@@ -5,6 +6,7 @@ use domain_client_block_preprocessor::runtime_api_full::RuntimeApiFull;
 use domain_client_consensus_relay_chain::DomainBlockImport;
 use domain_client_message_relayer::GossipMessageSink;
 use domain_client_operator::{Operator, OperatorParams, OperatorStreams};
+use domain_client_subnet_gossip::{start_gossip_worker, ExecutorGossipParams};
 use domain_runtime_primitives::opaque::Block;
 use domain_runtime_primitives::{Balance, BlockNumber, DomainCoreApi, Hash, InherentExtrinsicApi};
 use futures::channel::mpsc;
@@ -17,6 +19,7 @@ use sc_service::{
     BuildNetworkParams, Configuration as ServiceConfiguration, NetworkStarter, PartialComponents,
     SpawnTasksParams, TFullBackend, TaskManager,
 };
+use sc_subspace_block_relay::{build_execution_relay, ExecutionRelayParams, NetworkWrapper};
 use sc_telemetry::{Telemetry, TelemetryWorker, TelemetryWorkerHandle};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver};
@@ -380,6 +383,21 @@ where
         domain_client_subnet_gossip::domain_subnet_gossip_peers_set_config(),
     );
 
+    let execution_relay_network = Arc::new(NetworkWrapper::default());
+    let ExecutionRelayParams {
+        relay: execution_relay,
+        server: execution_relay_server,
+        request_response_config,
+    } = build_execution_relay(
+        execution_relay_network.clone(),
+        transaction_pool.clone(),
+        domain_config.prometheus_registry(),
+    )
+    .map_err(|error| {
+        sc_service::Error::Other(format!("Failed to build execution relay: {error}"))
+    })?;
+    net_config.add_request_response_protocol(request_response_config);
+
     let (network_service, system_rpc_tx, tx_handler_controller, network_starter, sync_service) =
         crate::build_network(BuildNetworkParams {
             config: &domain_config,
@@ -394,6 +412,11 @@ where
             block_relay: None,
         })?;
 
+    execution_relay_network.set(network_service.clone());
+    task_manager
+        .spawn_handle()
+        .spawn("domain-execution-relay", None, execution_relay_server.run());
+
     let is_authority = domain_config.role.is_authority();
     domain_config.rpc_id_provider = provider.rpc_id();
     let rpc_builder = {
@@ -447,7 +470,7 @@ where
     let code_executor = Arc::new(code_executor);
 
     let spawn_essential = task_manager.spawn_essential_handle();
-    let (bundle_sender, _bundle_receiver) = tracing_unbounded("domain_bundle_stream", 100);
+    let (bundle_sender, bundle_receiver) = tracing_unbounded("domain_bundle_stream", 100);
 
     // let domain_confirmation_depth = consensus_client
     // .runtime_api()
@@ -480,6 +503,20 @@ where
     )
     .await?;
 
+    let bundle_gossip_worker = start_gossip_worker(ExecutorGossipParams {
+        network: network_service.clone(),
+        sync: sync_service.clone(),
+        operator: BundleGossipHandler::new(domain_id, consensus_client.clone()),
+        bundle_receiver,
+        execution_relay,
+    });
+
+    spawn_essential.spawn_essential_blocking(
+        "domain-bundle-gossip",
+        None,
+        Box::pin(bundle_gossip_worker),
+    );
+
     if is_authority {
         let relayer_worker = domain_client_message_relayer::worker::relay_domain_messages(
             consensus_client.clone(),
@@ -1,5 +1,6 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+mod bundle_gossip;
 mod domain;
 mod domain_tx_pre_validator;
 pub mod providers;
@@ -33,7 +33,9 @@ use std::num::{NonZeroU64, NonZeroUsize};
 use std::sync::Arc;
 use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
 use subspace_core_primitives::objects::BlockObjectMapping;
-use subspace_core_primitives::{HistorySize, PublicKey, Record, SegmentIndex, Solution};
+use subspace_core_primitives::{
+    HistorySize, PublicKey, Record, RecordedHistorySegment, SegmentIndex, Solution,
+};
 use subspace_erasure_coding::ErasureCoding;
 use subspace_farmer_components::auditing::audit_sector;
 use subspace_farmer_components::plotting::{plot_sector, PieceGetterRetryPolicy, PlottedSector};
@@ -223,8 +225,11 @@ where
     Client: BlockBackend<Block> + HeaderBackend<Block>,
 {
     let kzg = Kzg::new(embedded_kzg_settings());
-    let mut archiver = subspace_archiving::archiver::Archiver::new(kzg.clone())
-        .expect("Incorrect parameters for archiver");
+    let mut archiver = subspace_archiving::archiver::Archiver::new(
+        kzg.clone(),
+        RecordedHistorySegment::ERASURE_CODING_RATE,
+    )
+    .expect("Incorrect parameters for archiver");
 
     let genesis_block = client
         .block(client.info().genesis_hash)
@@ -250,6 +255,7 @@ where
             HistorySize::from(NonZeroU64::new(10).unwrap()),
         ),
         min_sector_lifetime: HistorySize::from(NonZeroU64::new(4).unwrap()),
+        erasure_coding_rate: RecordedHistorySegment::ERASURE_CODING_RATE,
     };
 
     let plotted_sector = plot_sector::<_, PosTable>(
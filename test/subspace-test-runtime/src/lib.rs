@@ -285,6 +285,7 @@ parameter_types! {
         HistorySize::new(NonZeroU64::new(10).unwrap()),
     );
     pub const MinSectorLifetime: HistorySize = HistorySize::new(NonZeroU64::new(4).unwrap());
+    pub const ErasureCodingRate: (u32, u32) = (1, 2);
 }
 
 impl pallet_subspace::Config for Runtime {
@@ -302,6 +303,7 @@ impl pallet_subspace::Config for Runtime {
     type RecentSegments = RecentSegments;
     type RecentHistoryFraction = RecentHistoryFraction;
     type MinSectorLifetime = MinSectorLifetime;
+    type ErasureCodingRate = ErasureCodingRate;
     type ExpectedVotesPerBlock = ExpectedVotesPerBlock;
     type MaxPiecesInSector = ConstU16<{ MAX_PIECES_IN_SECTOR }>;
     type ShouldAdjustSolutionRange = ShouldAdjustSolutionRange;
@@ -35,7 +35,7 @@ use sc_consensus::{BasicQueue, BlockImport, StateAction, Verifier as VerifierT};
 use sc_consensus_fraud_proof::FraudProofBlockImport;
 use sc_executor::NativeElseWasmExecutor;
 use sc_network::config::{NetworkConfiguration, TransportConfig};
-use sc_network::multiaddr;
+use sc_network::{multiaddr, NetworkPeers, NetworkStateInfo};
 use sc_service::config::{
     DatabaseSource, KeystoreConfig, MultiaddrWithPeerId, WasmExecutionMethod,
     WasmtimeInstantiationStrategy,
@@ -44,6 +44,9 @@ use sc_service::{
     BasePath, BlocksPruning, Configuration, InPoolTransaction, NetworkStarter, Role,
     SpawnTasksParams, TaskManager, TransactionPool,
 };
+use sc_subspace_block_relay::{
+    build_transaction_pool_relay, NetworkWrapper, TransactionPoolRelay, TransactionPoolRelayParams,
+};
 use sc_transaction_pool::error::Error as PoolError;
 use sc_transaction_pool_api::TransactionSource;
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
@@ -214,6 +217,11 @@ pub struct MockConsensusNode {
     pub rpc_handlers: sc_service::RpcHandlers,
     /// Network starter
     pub network_starter: Option<NetworkStarter>,
+    /// The `MultiaddrWithPeerId` to this node. This is useful if you want to connect other nodes
+    /// to this node.
+    pub addr: MultiaddrWithPeerId,
+    /// Transaction pool relay, used to sync the transaction pool from the peers.
+    pub transaction_pool_relay: TransactionPoolRelay<Block>,
     /// The next slot number
     next_slot: u64,
     /// The slot notification subscribers
@@ -306,7 +314,21 @@ impl MockConsensusNode {
 
         let block_import = MockBlockImport::<_, _, _>::new(fraud_proof_block_import);
 
-        let net_config = sc_network::config::FullNetworkConfiguration::new(&config.network);
+        let multiaddr = config.network.listen_addresses[0].clone();
+        let network_wrapper = Arc::new(NetworkWrapper::default());
+        let mut net_config = sc_network::config::FullNetworkConfiguration::new(&config.network);
+        let TransactionPoolRelayParams {
+            relay: transaction_pool_relay,
+            server: transaction_pool_relay_server,
+            request_response_config,
+        } = build_transaction_pool_relay(
+            network_wrapper.clone(),
+            client.clone(),
+            transaction_pool.clone(),
+            None,
+        )
+        .expect("Should be able to build transaction pool relay");
+        net_config.add_request_response_protocol(request_response_config);
 
         let (network_service, system_rpc_tx, tx_handler_controller, network_starter, sync_service) =
             sc_service::build_network(sc_service::BuildNetworkParams {
@@ -325,6 +347,17 @@ impl MockConsensusNode {
             })
             .expect("Should be able to build network");
 
+        network_wrapper.set(network_service.clone());
+        task_manager.spawn_handle().spawn(
+            "transaction-pool-relay-server",
+            None,
+            transaction_pool_relay_server.run(),
+        );
+        let addr = MultiaddrWithPeerId {
+            multiaddr,
+            peer_id: network_service.local_peer_id(),
+        };
+
         let rpc_handlers = sc_service::spawn_tasks(SpawnTasksParams {
             network: network_service.clone(),
             client: client.clone(),
@@ -357,6 +390,8 @@ impl MockConsensusNode {
             sync_service,
             rpc_handlers,
             network_starter: Some(network_starter),
+            addr,
+            transaction_pool_relay,
             next_slot: 1,
             new_slot_notification_subscribers: Vec::new(),
             block_import,
@@ -374,6 +409,13 @@ impl MockConsensusNode {
             .start_network();
     }
 
+    /// Connect to the consensus node with the given address, the network must be started.
+    pub fn connect_to_consensus_node(&self, addr: MultiaddrWithPeerId) {
+        self.network_service
+            .add_reserved_peer(addr)
+            .expect("Address of the consensus node must be valid");
+    }
+
     /// Get the cross domain gossip message worker builder
     pub fn xdm_gossip_worker_builder(&mut self) -> &mut GossipWorkerBuilder {
         self.xdm_gossip_worker_builder